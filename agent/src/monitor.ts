@@ -231,6 +231,16 @@ export class FoldMonitor {
       `[${timestamp()}]   Encrypted data → submitting to Arcium MPC network...`
     );
 
+    // Position 1, registered by this wallet
+    const [positionAcc] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("position"),
+        this.wallet.publicKey.toBuffer(),
+        new anchor.BN(1).toArrayLike(Buffer, "le", 4),
+      ],
+      this.programId
+    );
+
     // Submit on-chain health check
     try {
       const checkOffset = new anchor.BN(randomBytes(8), "hex");
@@ -261,6 +271,8 @@ export class FoldMonitor {
             Buffer.from(getCompDefAccOffset("check_position_health")).readUInt32LE()
           ),
           owner: this.wallet.publicKey,
+          positionAcc,
+          riskHistory: null,
         })
         .rpc({ skipPreflight: true, commitment: "confirmed" });
 
@@ -288,6 +300,8 @@ export class FoldMonitor {
             this.programId,
             Buffer.from(getCompDefAccOffset("reveal_risk")).readUInt32LE()
           ),
+          positionAcc,
+          autoAction: null,
         })
         .rpc({ skipPreflight: true, commitment: "confirmed" });
 
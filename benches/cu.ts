@@ -0,0 +1,211 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Sentinel } from "../target/types/sentinel";
+import { randomBytes } from "crypto";
+import {
+  awaitComputationFinalization,
+  getArciumEnv,
+  getCompDefAccOffset,
+  RescueCipher,
+  deserializeLE,
+  getMXEAccAddress,
+  getMempoolAccAddress,
+  getCompDefAccAddress,
+  getExecutingPoolAccAddress,
+  x25519,
+  getComputationAccAddress,
+  getClusterAccAddress,
+} from "@arcium-hq/client";
+import * as fs from "fs";
+import * as os from "os";
+import { expect } from "chai";
+import {
+  ENCRYPTION_KEY_MESSAGE,
+  deriveEncryptionKey,
+  getMXEPublicKeyWithRetry,
+  initAllCompDefs,
+  readKpJson,
+} from "../tests/helpers";
+
+// Compute-unit benchmark. Runs register_position, check_health and
+// reveal_risk, each with its callback, on the Arcium localnet started by
+// `arcium test`, then compares the CUs each transaction consumed against
+// benches/cu-baseline.json. The test script only picks it up with
+// CU_BENCH=1, so a checkout without a recorded baseline still passes
+// `arcium test`.
+//
+//   CU_REGRESSION_PCT   allowed growth over the baseline (default 5)
+//   CU_BASELINE_UPDATE  set to 1 to overwrite the baseline with this run
+//
+// Without CU_BASELINE_UPDATE the baseline is only read, and an instruction
+// missing from it fails like a regression: record it on purpose.
+
+const BASELINE_PATH = `${__dirname}/cu-baseline.json`;
+const REGRESSION_PCT = Number(process.env.CU_REGRESSION_PCT ?? "5");
+const UPDATE_BASELINE = process.env.CU_BASELINE_UPDATE === "1";
+const POSITION_ID = 1000;
+
+describe("Compute unit benchmark", () => {
+  anchor.setProvider(anchor.AnchorProvider.env());
+  const program = anchor.workspace.Sentinel as Program<Sentinel>;
+  const provider = anchor.getProvider() as anchor.AnchorProvider;
+
+  const arciumEnv = getArciumEnv();
+  const clusterAccount = getClusterAccAddress(arciumEnv.arciumClusterOffset);
+  const measured: Record<string, number> = {};
+
+  const queueAccounts = (circuit: string, offset: anchor.BN) => ({
+    computationAccount: getComputationAccAddress(
+      arciumEnv.arciumClusterOffset,
+      offset
+    ),
+    clusterAccount,
+    mxeAccount: getMXEAccAddress(program.programId),
+    mempoolAccount: getMempoolAccAddress(arciumEnv.arciumClusterOffset),
+    executingPool: getExecutingPoolAccAddress(arciumEnv.arciumClusterOffset),
+    compDefAccount: getCompDefAccAddress(
+      program.programId,
+      Buffer.from(getCompDefAccOffset(circuit)).readUInt32LE()
+    ),
+  });
+
+  const record = async (name: string, signature: string) => {
+    const tx = await provider.connection.getTransaction(signature, {
+      commitment: "confirmed",
+      maxSupportedTransactionVersion: 0,
+    });
+    measured[name] = tx.meta.computeUnitsConsumed;
+  };
+
+  // Records the queue transaction and the callback transaction that
+  // finalizes the computation.
+  const recordComputation = async (
+    name: string,
+    queueSig: string,
+    offset: anchor.BN
+  ) => {
+    const callbackSig = await awaitComputationFinalization(
+      provider,
+      offset,
+      program.programId,
+      "confirmed"
+    );
+    await record(name, queueSig);
+    await record(`${name}_callback`, callbackSig);
+  };
+
+  it("measures instruction compute units", async () => {
+    const owner = readKpJson(`${os.homedir()}/.config/solana/id.json`);
+    const mxePublicKey = await getMXEPublicKeyWithRetry(
+      provider,
+      program.programId
+    );
+    await initAllCompDefs(provider, program, owner);
+
+    const { privateKey, publicKey } = deriveEncryptionKey(
+      owner,
+      ENCRYPTION_KEY_MESSAGE
+    );
+    const cipher = new RescueCipher(
+      x25519.getSharedSecret(privateKey, mxePublicKey)
+    );
+
+    const registerOffset = new anchor.BN(randomBytes(8), "hex");
+    const registerSig = await program.methods
+      .registerPosition(
+        registerOffset,
+        POSITION_ID,
+        new anchor.BN(deserializeLE(randomBytes(16)).toString()),
+        null,
+        {
+          protocol: { unspecified: {} },
+          market: anchor.web3.PublicKey.default,
+          collateralMint: anchor.web3.PublicKey.default,
+          debtMint: anchor.web3.PublicKey.default,
+        },
+        Array(32).fill(0),
+        0
+      )
+      .accountsPartial({
+        ...queueAccounts("init_risk_state", registerOffset),
+        referralStats: null,
+        previousOwnerIndex: null,
+      })
+      .rpc({ skipPreflight: true, commitment: "confirmed" });
+    await recordComputation("register_position", registerSig, registerOffset);
+
+    const checkNonce = randomBytes(16);
+    const ciphertext = cipher.encrypt(
+      [BigInt(500000), BigInt(11500), BigInt(11000)],
+      checkNonce
+    );
+    const checkOffset = new anchor.BN(randomBytes(8), "hex");
+    const checkSig = await program.methods
+      .checkHealth(
+        checkOffset,
+        POSITION_ID,
+        [
+          Array.from(ciphertext[0]),
+          Array.from(ciphertext[1]),
+          Array.from(ciphertext[2]),
+        ],
+        Array.from(publicKey),
+        new anchor.BN(deserializeLE(checkNonce).toString())
+      )
+      .accountsPartial({
+        ...queueAccounts("check_position_health", checkOffset),
+        owner: owner.publicKey,
+      })
+      .rpc({ skipPreflight: true, commitment: "confirmed" });
+    await recordComputation("check_health", checkSig, checkOffset);
+
+    const revealOffset = new anchor.BN(randomBytes(8), "hex");
+    const revealSig = await program.methods
+      .revealRisk(revealOffset, POSITION_ID)
+      .accountsPartial(queueAccounts("reveal_risk", revealOffset))
+      .rpc({ skipPreflight: true, commitment: "confirmed" });
+    await recordComputation("reveal_risk", revealSig, revealOffset);
+  });
+
+  after(() => {
+    const baseline: Record<string, number> = JSON.parse(
+      fs.readFileSync(BASELINE_PATH, "utf8")
+    );
+    const regressions: string[] = [];
+    const missing: string[] = [];
+
+    console.table(
+      Object.entries(measured).map(([name, units]) => ({
+        instruction: name,
+        baseline: baseline[name] ?? "-",
+        measured: units,
+      }))
+    );
+
+    if (UPDATE_BASELINE) {
+      fs.writeFileSync(
+        BASELINE_PATH,
+        JSON.stringify({ ...baseline, ...measured }, null, 2) + "\n"
+      );
+      return;
+    }
+
+    for (const [name, units] of Object.entries(measured)) {
+      const base = baseline[name];
+      if (base === undefined) {
+        missing.push(name);
+      } else if (units > base * (1 + REGRESSION_PCT / 100)) {
+        regressions.push(`${name}: ${base} -> ${units} CU`);
+      }
+    }
+
+    expect(
+      missing,
+      "not in the CU baseline, record with CU_BASELINE_UPDATE=1"
+    ).to.be.empty;
+    expect(
+      regressions,
+      `CU usage regressed by more than ${REGRESSION_PCT}%`
+    ).to.be.empty;
+  });
+});
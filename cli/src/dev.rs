@@ -0,0 +1,204 @@
+//! `dev bootstrap`: takes a fresh wallet on localnet or devnet from nothing
+//! to a decrypted risk state, printing each step, as a walkthrough of what
+//! an integration does.
+
+use crate::{check, print_risk, register, reveal_risk, session::Session};
+use anchor_client::solana_sdk::{native_token::LAMPORTS_PER_SOL, pubkey::Pubkey};
+use anyhow::{bail, ensure, Context, Result};
+use arcium_client::pda;
+use sentinel::GlobalConfig;
+use sentinel_client::{instructions, PositionData};
+use std::process::Command;
+
+/// Circuits one check/reveal cycle runs.
+const CIRCUITS: &[&str] = &[
+    "init_risk_state",
+    "check_position_health",
+    "reveal_risk_to_owner",
+];
+
+/// Most position ids tried for a free one.
+const MAX_POSITION_ID: u32 = 64;
+
+/// A position inside the built-in risk model's warning buffer, so the demo
+/// check has something to report.
+pub const DEMO_POSITION: PositionData = PositionData {
+    position_value: 250_000,
+    collateral_ratio: 11_800,
+    liquidation_threshold: 11_000,
+};
+
+#[derive(clap::Args)]
+pub struct BootstrapArgs {
+    /// SOL to airdrop when the wallet holds less
+    #[arg(long, default_value_t = 2)]
+    pub airdrop_sol: u64,
+    /// Fail instead of running `cargo xtask deploy` for missing computation
+    /// definitions
+    #[arg(long)]
+    pub no_deploy: bool,
+}
+
+pub async fn bootstrap(session: &Session, cluster: &str, args: &BootstrapArgs) -> Result<()> {
+    ensure!(
+        matches!(cluster, "localnet" | "devnet")
+            || cluster.contains("localhost")
+            || cluster.contains("127.0.0.1"),
+        "bootstrap only runs against localnet or devnet"
+    );
+
+    step(1, "Funding the wallet");
+    fund(session, args.airdrop_sol * LAMPORTS_PER_SOL).await?;
+
+    step(2, "Checking the MXE, computation definitions and config");
+    let mut missing = missing_comp_defs(session).await?;
+    let config_missing = !session.exists(&instructions::global_config_pda()).await?;
+    if !session.exists(&instructions::admin_audit_log_pda()).await? {
+        missing.push("admin audit log");
+    }
+    if config_missing {
+        missing.push("global config");
+    }
+    if !missing.is_empty() {
+        ensure!(!args.no_deploy, "not initialized: {missing:?}");
+        println!("  missing {missing:?}, running `cargo xtask deploy`");
+        deploy(session, cluster)?;
+    }
+    verify_config(session, config_missing).await?;
+    println!("  ready");
+
+    step(3, "Registering a demo position");
+    let me = session.me();
+    let position_id = free_position_id(session, &me).await?;
+    register(session, position_id, Some("demo"), None).await?;
+
+    step(4, "Checking it with synthetic data");
+    println!(
+        "  value ${:.2}, collateral ratio {} bps, liquidation threshold {} bps",
+        DEMO_POSITION.position_value as f64 / 100.0,
+        DEMO_POSITION.collateral_ratio,
+        DEMO_POSITION.liquidation_threshold
+    );
+    println!("  (encrypted to the MXE; the program only ever sees ciphertexts)");
+    check(session, &me, position_id, &DEMO_POSITION, false).await?;
+
+    step(5, "Revealing the risk state to the wallet");
+    let risk = reveal_risk(session, &me, position_id)
+        .await?
+        .context("the reveal could not be decrypted with this wallet's key")?;
+    print_risk(&risk);
+
+    println!("\nDone. Try `sentinel-cli status {position_id}`, then `close {position_id}`.");
+    Ok(())
+}
+
+fn step(n: u32, title: &str) {
+    println!("\n[{n}/5] {title}");
+}
+
+/// Airdrops `lamports` unless the wallet already holds that much.
+async fn fund(session: &Session, lamports: u64) -> Result<()> {
+    let rpc = session.program.internal_rpc();
+    let me = session.me();
+    let balance = rpc.get_balance(&me).await?;
+    if balance >= lamports {
+        println!(
+            "  {me} holds {} SOL",
+            balance as f64 / LAMPORTS_PER_SOL as f64
+        );
+        return Ok(());
+    }
+    let signature = rpc
+        .request_airdrop(&me, lamports - balance)
+        .await
+        .context("airdrop failed; the devnet faucet is rate limited, see faucet.solana.com")?;
+    rpc.poll_for_signature(&signature).await?;
+    let sol = (lamports - balance) as f64 / LAMPORTS_PER_SOL as f64;
+    println!("  airdropped {sol} SOL to {me}");
+    Ok(())
+}
+
+/// Circuits of the cycle whose computation definition isn't initialized.
+async fn missing_comp_defs(session: &Session) -> Result<Vec<&'static str>> {
+    if !session.exists(&pda::mxe_acc(&sentinel::ID)).await? {
+        bail!("no MXE for the program, deploy it with `cargo xtask deploy` first");
+    }
+    let mut missing = Vec::new();
+    for circuit in CIRCUITS {
+        if !session
+            .exists(&instructions::comp_def_address(circuit))
+            .await?
+        {
+            missing.push(*circuit);
+        }
+    }
+    Ok(missing)
+}
+
+/// Asserts that the `GlobalConfig` lets the demo queue computations. One
+/// `cargo xtask deploy` just `created` has this wallet as admin and the
+/// first circuit version.
+async fn verify_config(session: &Session, created: bool) -> Result<()> {
+    let config: GlobalConfig = session
+        .program
+        .account(instructions::global_config_pda())
+        .await?;
+    ensure!(
+        !config.paused,
+        "the program is paused by its admin {}",
+        config.admin
+    );
+    if created {
+        ensure!(
+            config.admin == session.me(),
+            "config admin is {}, expected this wallet",
+            config.admin
+        );
+        ensure!(
+            config.circuit_version == 0,
+            "config is at circuit version {}, expected 0",
+            config.circuit_version
+        );
+    }
+    println!(
+        "  config admin {}, circuit version {}",
+        config.admin, config.circuit_version
+    );
+    Ok(())
+}
+
+/// Initializes and uploads the computation definitions, and creates the
+/// audit log and config, with the program already deployed.
+fn deploy(session: &Session, cluster: &str) -> Result<()> {
+    let status = Command::new("cargo")
+        .args([
+            "xtask",
+            "deploy",
+            "--skip-program-deploy",
+            "--cluster",
+            cluster,
+        ])
+        .args(["--cluster-offset", &session.cluster_offset.to_string()])
+        .arg("--keypair")
+        .arg(&session.keypair_path)
+        .status()
+        .context("failed to run cargo xtask")?;
+    ensure!(status.success(), "cargo xtask deploy failed ({status})");
+    Ok(())
+}
+
+/// Lowest position id the wallet hasn't registered.
+async fn free_position_id(session: &Session, owner: &Pubkey) -> Result<u32> {
+    for position_id in 0..MAX_POSITION_ID {
+        if !session
+            .exists(&instructions::position_pda(owner, position_id))
+            .await?
+        {
+            return Ok(position_id);
+        }
+    }
+    bail!(
+        "positions 0 to {} are all registered, close one first",
+        MAX_POSITION_ID - 1
+    )
+}
@@ -0,0 +1,552 @@
+//! `sentinel-cli`: runs a position through its lifecycle from the command
+//! line, signing with a Solana keypair file.
+//!
+//! ```text
+//! sentinel-cli register 1 --label treasury
+//! sentinel-cli check 1 --value 1500000 --collateral-ratio 16000 --liquidation-threshold 11000
+//! sentinel-cli reveal 1
+//! sentinel-cli status 1
+//! sentinel-cli list
+//! sentinel-cli close 1
+//! ```
+//!
+//! `sentinel-cli dev bootstrap --cluster localnet` runs all of it once on a
+//! demo position, airdropping SOL and setting up the computation
+//! definitions first if needed; see `dev`.
+//!
+//! Position data is encrypted to the MXE with the wallet's encryption key,
+//! the same one the keeper and the TypeScript client derive, so a reveal
+//! made here can be read there and the other way round. Commands that queue
+//! a computation wait for its callback before returning.
+
+use anchor_client::solana_sdk::pubkey::Pubkey;
+use anyhow::{ensure, Result};
+use clap::{Parser, Subcommand};
+use sentinel::PositionMetadata;
+use sentinel_client::{
+    instructions::{self, Queue},
+    memo, new_nonce, PositionData, RiskState,
+};
+use session::Session;
+use std::path::PathBuf;
+
+mod dev;
+mod session;
+
+#[derive(Parser)]
+#[command(name = "sentinel-cli", version, about)]
+struct Cli {
+    #[command(flatten)]
+    args: Args,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// Target cluster (`localnet`, `devnet`, or an RPC URL)
+    #[arg(long, global = true, default_value = "devnet")]
+    pub cluster: String,
+    /// Arcium cluster computations are queued on
+    #[arg(long, global = true, default_value_t = 456)]
+    pub cluster_offset: u32,
+    /// Wallet that signs and owns the positions
+    #[arg(long, global = true, default_value = "~/.config/solana/id.json")]
+    pub keypair: String,
+    /// Seconds to wait for a computation's callback
+    #[arg(long, global = true, default_value_t = 120)]
+    pub timeout_secs: u64,
+}
+
+impl Args {
+    pub fn keypair_path(&self) -> PathBuf {
+        session::expand_home(&self.keypair)
+    }
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Registers a position and waits for its risk state to be initialized.
+    Register {
+        position_id: u32,
+        /// Label, stored only as a salted hash
+        #[arg(long)]
+        label: Option<String>,
+        /// Wallet that referred you
+        #[arg(long)]
+        referrer: Option<Pubkey>,
+    },
+    /// Encrypts the position's data and checks its health.
+    Check {
+        position_id: u32,
+        #[command(flatten)]
+        position: PositionArgs,
+        /// Append the result to the position's risk history
+        #[arg(long)]
+        record_history: bool,
+        #[command(flatten)]
+        target: Target,
+    },
+    /// Reveals the position's risk state to the wallet and decrypts it.
+    Reveal {
+        position_id: u32,
+        #[command(flatten)]
+        target: Target,
+    },
+    /// Prints the position's public state and its last reveal.
+    Status {
+        position_id: u32,
+        #[command(flatten)]
+        target: Target,
+    },
+    /// Lists the open positions registered by the wallet, or by another
+    /// registrant, from its on-chain index.
+    List {
+        #[command(flatten)]
+        target: Target,
+    },
+    /// Closes the position, refunding its rent, subscription credits and
+    /// check bounty escrow, and burns its monitoring receipt if it has one.
+    Close {
+        position_id: u32,
+        #[command(flatten)]
+        target: Target,
+    },
+    /// Closes a deactivated position and its risk history like `close`,
+    /// keeping an archive record of their final state.
+    Archive {
+        position_id: u32,
+        #[command(flatten)]
+        target: Target,
+    },
+    /// Development helpers.
+    Dev {
+        #[command(subcommand)]
+        command: DevCommand,
+    },
+}
+
+#[derive(Subcommand)]
+enum DevCommand {
+    /// Airdrops SOL, sets up the computation definitions, and runs a demo
+    /// position through one check and reveal, printing what happens.
+    Bootstrap(dev::BootstrapArgs),
+}
+
+#[derive(clap::Args)]
+struct PositionArgs {
+    /// Position value in USD cents
+    #[arg(long)]
+    value: u64,
+    /// Collateral ratio in basis points
+    #[arg(long)]
+    collateral_ratio: u64,
+    /// Liquidation threshold in basis points
+    #[arg(long)]
+    liquidation_threshold: u64,
+}
+
+impl PositionArgs {
+    fn data(&self) -> Result<PositionData> {
+        ensure!(
+            self.collateral_ratio > 0 && self.liquidation_threshold > 0,
+            "collateral ratio and liquidation threshold must be positive"
+        );
+        Ok(PositionData {
+            position_value: self.value,
+            collateral_ratio: self.collateral_ratio,
+            liquidation_threshold: self.liquidation_threshold,
+        })
+    }
+}
+
+#[derive(clap::Args)]
+struct Target {
+    /// Wallet that registered the position, if it was transferred to you
+    #[arg(long)]
+    registrant: Option<Pubkey>,
+}
+
+impl Target {
+    fn registrant(&self, session: &Session) -> Pubkey {
+        self.registrant.unwrap_or_else(|| session.me())
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let session = Session::connect(&cli.args)?;
+    match cli.command {
+        Command::Register {
+            position_id,
+            label,
+            referrer,
+        } => register(&session, position_id, label.as_deref(), referrer).await,
+        Command::Check {
+            position_id,
+            position,
+            record_history,
+            target,
+        } => {
+            let registrant = target.registrant(&session);
+            check(
+                &session,
+                &registrant,
+                position_id,
+                &position.data()?,
+                record_history,
+            )
+            .await
+        }
+        Command::Reveal {
+            position_id,
+            target,
+        } => reveal(&session, &target.registrant(&session), position_id).await,
+        Command::Status {
+            position_id,
+            target,
+        } => status(&session, &target.registrant(&session), position_id).await,
+        Command::List { target } => list(&session, &target.registrant(&session)).await,
+        Command::Close {
+            position_id,
+            target,
+        } => close(&session, &target.registrant(&session), position_id).await,
+        Command::Archive {
+            position_id,
+            target,
+        } => archive(&session, &target.registrant(&session), position_id).await,
+        Command::Dev {
+            command: DevCommand::Bootstrap(args),
+        } => dev::bootstrap(&session, &cli.args.cluster, &args).await,
+    }
+}
+
+async fn register(
+    session: &Session,
+    position_id: u32,
+    label: Option<&str>,
+    referrer: Option<Pubkey>,
+) -> Result<()> {
+    let me = session.me();
+    let queue = Queue::new(session.cluster_offset);
+    // The first index page with room, or a new one after the last
+    let pages = session.owner_index(&me).await;
+    let index_page = pages
+        .iter()
+        .position(|page| page.position_ids.len() < sentinel::MAX_INDEXED_POSITIONS)
+        .unwrap_or(pages.len()) as u32;
+    let ix = instructions::register_position(
+        &me,
+        position_id,
+        queue,
+        new_nonce(),
+        referrer,
+        PositionMetadata::UNSPECIFIED,
+        label,
+        index_page,
+    );
+    let signature = session.send(&[ix]).await?;
+    println!("registered position {position_id}: {signature}");
+    session
+        .settled(&me, position_id, queue.computation_offset)
+        .await?;
+    println!("risk state initialized");
+    Ok(())
+}
+
+async fn check(
+    session: &Session,
+    registrant: &Pubkey,
+    position_id: u32,
+    data: &PositionData,
+    record_history: bool,
+) -> Result<()> {
+    let position = session.position(registrant, position_id).await?;
+    let (key, cipher) = session.encryption().await?;
+    let queue = Queue::new(session.cluster_offset);
+    let position_acc = instructions::position_pda(registrant, position_id);
+    let check_bounty = session
+        .exists(&instructions::check_bounty_pda(&position_acc))
+        .await?;
+    let keeper_account = position.registered_keepers_only
+        && session.me() != position.owner
+        && session
+            .exists(&instructions::keeper_account_pda(&session.me()))
+            .await?;
+    let ix = instructions::check_health(
+        &session.me(),
+        &position.owner,
+        registrant,
+        position_id,
+        queue,
+        key.public_key(),
+        &data.encrypt(&cipher, new_nonce()),
+        record_history,
+        position.price_feed,
+        position.tvl_protocol,
+        check_bounty,
+        keeper_account,
+    );
+    let signature = session.send(&[ix]).await?;
+    println!("queued check {}: {signature}", queue.computation_offset);
+    let position = session
+        .settled(registrant, position_id, queue.computation_offset)
+        .await?;
+    println!(
+        "checked at {}; run `reveal` to read the result",
+        position.last_check
+    );
+    Ok(())
+}
+
+async fn reveal(session: &Session, registrant: &Pubkey, position_id: u32) -> Result<()> {
+    match reveal_risk(session, registrant, position_id).await? {
+        Some(risk) => print_risk(&risk),
+        None => println!("the reveal could not be decrypted with this wallet's key"),
+    }
+    Ok(())
+}
+
+/// Reveals the position's risk state to the wallet and decrypts it; `None`
+/// if the delivered envelope doesn't decrypt with the wallet's key. The
+/// position's action memo, if it has one, is attached and printed when the
+/// risk is critical.
+async fn reveal_risk(
+    session: &Session,
+    registrant: &Pubkey,
+    position_id: u32,
+) -> Result<Option<RiskState>> {
+    let (key, cipher) = session.encryption().await?;
+    let queue = Queue::new(session.cluster_offset);
+    let position_acc = instructions::position_pda(registrant, position_id);
+    let action_memo = session
+        .exists(&instructions::action_memo_pda(&position_acc))
+        .await?;
+    let ix = instructions::reveal_risk_to_owner(
+        &session.me(),
+        registrant,
+        position_id,
+        queue,
+        key.public_key(),
+        new_nonce(),
+        action_memo,
+    );
+    let signature = session.send(&[ix]).await?;
+    println!("queued reveal {}: {signature}", queue.computation_offset);
+    let inbox = session
+        .delivered(registrant, position_id, queue.computation_offset)
+        .await?;
+    if let Some(plan) = memo::critical_plan(&key, &cipher, &inbox) {
+        println!("your plan:     {plan}");
+    }
+    Ok(RiskState::from_inbox(&cipher, &inbox))
+}
+
+async fn status(session: &Session, registrant: &Pubkey, position_id: u32) -> Result<()> {
+    let position = session.position(registrant, position_id).await?;
+    println!(
+        "position:      {}",
+        instructions::position_pda(registrant, position_id)
+    );
+    println!("owner:         {}", position.owner);
+    println!("active:        {}", position.is_active);
+    println!("last check:    {}", position.last_check);
+    if position.monitoring_lapsed {
+        println!(
+            "monitoring:    lapsed, no check within {}s",
+            position.heartbeat_deadline_secs
+        );
+    }
+    if position.receipt_minted {
+        println!(
+            "receipt:       {}",
+            instructions::receipt_account(&position.owner)
+        );
+    }
+    match position.pending_computation {
+        Some(offset) => println!("pending:       {offset} since {}", position.pending_since),
+        None => println!("pending:       none"),
+    }
+    if let Some(failure) = position.last_error {
+        println!(
+            "last failure:  {:?} {} at {}",
+            failure.kind, failure.computation_offset, failure.failed_at
+        );
+    }
+    let Some(inbox) = session.inbox(registrant, position_id).await else {
+        return Ok(());
+    };
+    let (_, cipher) = session.encryption().await?;
+    if let Some(risk) = RiskState::from_inbox(&cipher, &inbox) {
+        println!("last reveal:   {}", inbox.delivered_at);
+        print_risk(&risk);
+    }
+    Ok(())
+}
+
+async fn list(session: &Session, registrant: &Pubkey) -> Result<()> {
+    let pages = session.owner_index(registrant).await;
+    if pages.is_empty() {
+        println!("no indexed positions for {registrant}");
+        return Ok(());
+    }
+    for position_id in pages.into_iter().flat_map(|page| page.position_ids) {
+        let position = session.position(registrant, position_id).await?;
+        let state = if position.is_active {
+            "active"
+        } else {
+            "inactive"
+        };
+        println!(
+            "{position_id:>10}  {state:<8}  last check {}",
+            position.last_check
+        );
+    }
+    Ok(())
+}
+
+async fn close(session: &Session, registrant: &Pubkey, position_id: u32) -> Result<()> {
+    let position = session.position(registrant, position_id).await?;
+    ensure!(
+        position.pending_computation.is_none(),
+        "a computation is still pending; wait for its callback or clear it first"
+    );
+    let position_acc = instructions::position_pda(registrant, position_id);
+    let subscription = session
+        .exists(&instructions::subscription_pda(&position_acc))
+        .await?;
+    let check_bounty = session
+        .exists(&instructions::check_bounty_pda(&position_acc))
+        .await?;
+    let mut ixs = Vec::new();
+    if position.receipt_minted {
+        ixs.push(instructions::burn_monitoring_receipt(
+            &session.me(),
+            registrant,
+            position_id,
+        ));
+    }
+    ixs.push(instructions::close_position(
+        &session.me(),
+        registrant,
+        position_id,
+        subscription,
+        check_bounty,
+        position.indexed.then_some(position.index_page),
+    ));
+    let signature = session.send(&ixs).await?;
+    println!("closed position {position_id}: {signature}");
+    Ok(())
+}
+
+async fn archive(session: &Session, registrant: &Pubkey, position_id: u32) -> Result<()> {
+    let position = session.position(registrant, position_id).await?;
+    ensure!(
+        !position.is_active,
+        "the position is still active; deactivate it first"
+    );
+    ensure!(
+        position.pending_computation.is_none(),
+        "a computation is still pending; wait for its callback or clear it first"
+    );
+    let position_acc = instructions::position_pda(registrant, position_id);
+    let risk_history = session
+        .exists(&instructions::risk_history_pda(&position_acc))
+        .await?;
+    let subscription = session
+        .exists(&instructions::subscription_pda(&position_acc))
+        .await?;
+    let check_bounty = session
+        .exists(&instructions::check_bounty_pda(&position_acc))
+        .await?;
+    let ix = instructions::archive_position(
+        &session.me(),
+        registrant,
+        position_id,
+        risk_history,
+        subscription,
+        check_bounty,
+        position.indexed.then_some(position.index_page),
+    );
+    let signature = session.send(&[ix]).await?;
+    let archive = instructions::archive_pda(registrant, position_id);
+    println!("archived position {position_id} to {archive}: {signature}");
+    Ok(())
+}
+
+fn print_risk(risk: &RiskState) {
+    const SEVERITIES: [&str; 4] = ["safe", "low", "medium", "critical"];
+    println!("at risk:       {}", risk.is_at_risk);
+    println!("severity:      {}", SEVERITIES[risk.severity as usize]);
+    if risk.last_price > 0 {
+        println!("last price:    {} micro-USD", risk.last_price);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn cli_is_well_formed() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn check_takes_position_data_and_global_options_after_the_subcommand() {
+        let cli = Cli::try_parse_from([
+            "sentinel-cli",
+            "check",
+            "7",
+            "--value",
+            "1500000",
+            "--collateral-ratio",
+            "16000",
+            "--liquidation-threshold",
+            "11000",
+            "--cluster",
+            "localnet",
+        ])
+        .unwrap();
+        assert_eq!(cli.args.cluster, "localnet");
+        let Command::Check {
+            position_id,
+            position,
+            ..
+        } = cli.command
+        else {
+            panic!("not a check");
+        };
+        assert_eq!(position_id, 7);
+        assert_eq!(position.data().unwrap().collateral_ratio, 16_000);
+
+        let missing = Cli::try_parse_from(["sentinel-cli", "check", "7", "--value", "1"]);
+        assert!(missing.is_err());
+    }
+
+    #[test]
+    fn dev_bootstrap_parses_its_options() {
+        let cli = Cli::try_parse_from([
+            "sentinel-cli",
+            "dev",
+            "bootstrap",
+            "--cluster",
+            "localnet",
+            "--airdrop-sol",
+            "5",
+            "--no-deploy",
+        ])
+        .unwrap();
+        assert_eq!(cli.args.cluster, "localnet");
+        let Command::Dev {
+            command: DevCommand::Bootstrap(args),
+        } = cli.command
+        else {
+            panic!("not a bootstrap");
+        };
+        assert_eq!(args.airdrop_sol, 5);
+        assert!(args.no_deploy);
+    }
+}
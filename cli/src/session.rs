@@ -0,0 +1,197 @@
+//! A connection to the cluster as the wallet the CLI signs with: sending
+//! transactions, deriving the wallet's encryption key, and waiting for the
+//! MPC callback of a queued computation.
+
+use crate::Args;
+use anchor_client::{
+    solana_sdk::{
+        commitment_config::CommitmentConfig,
+        instruction::Instruction,
+        pubkey::Pubkey,
+        signature::{read_keypair_file, Keypair, Signature},
+        signer::Signer,
+        transaction::Transaction,
+    },
+    Client, Cluster, Program,
+};
+use anyhow::{anyhow, bail, Context, Result};
+use arcium_client::{idl::arcium::accounts::MXEAccount, pda};
+use sentinel::{OwnerIndex, PositionAccount, RevealInbox};
+use sentinel_client::{instructions, Cipher, EncryptionKey, ENCRYPTION_KEY_MESSAGE};
+use solana_rpc_client::nonblocking::rpc_client::RpcClient;
+use std::{
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// Time between two reads of an account being waited on.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+pub struct Session {
+    pub payer: Arc<Keypair>,
+    pub program: Program<Arc<Keypair>>,
+    pub keypair_path: PathBuf,
+    pub cluster_offset: u32,
+    timeout: Duration,
+}
+
+impl Session {
+    pub fn connect(args: &Args) -> Result<Self> {
+        let cluster: Cluster = args
+            .cluster
+            .parse()
+            .map_err(|e| anyhow!("invalid cluster {:?}: {e}", args.cluster))?;
+        let keypair_path = args.keypair_path();
+        let payer = Arc::new(
+            read_keypair_file(&keypair_path)
+                .map_err(|e| anyhow!("failed to read {}: {e}", keypair_path.display()))?,
+        );
+        let rpc = RpcClient::new_with_commitment(cluster.url().to_string(), confirmed());
+        let client = Client::new_with_options(cluster, payer.clone(), confirmed());
+        Ok(Self {
+            program: client.program(sentinel::ID, rpc)?,
+            payer,
+            keypair_path,
+            cluster_offset: args.cluster_offset,
+            timeout: Duration::from_secs(args.timeout_secs),
+        })
+    }
+
+    pub fn me(&self) -> Pubkey {
+        self.payer.pubkey()
+    }
+
+    /// Signs `ixs` with the wallet and waits for confirmation.
+    pub async fn send(&self, ixs: &[Instruction]) -> Result<Signature> {
+        let rpc = self.program.internal_rpc();
+        let blockhash = rpc.get_latest_blockhash().await?;
+        let tx =
+            Transaction::new_signed_with_payer(ixs, Some(&self.me()), &[&*self.payer], blockhash);
+        Ok(rpc.send_and_confirm_transaction(&tx).await?)
+    }
+
+    /// The wallet's encryption key, derived from its signature of
+    /// `ENCRYPTION_KEY_MESSAGE` as the keeper and the TypeScript client do,
+    /// and its cipher with the MXE.
+    pub async fn encryption(&self) -> Result<(EncryptionKey, Cipher)> {
+        let mxe: MXEAccount = self
+            .program
+            .account(pda::mxe_acc(&sentinel::ID))
+            .await
+            .context("MXE account not found, is the program deployed?")?;
+        let mxe_pubkey = mxe
+            .x25519_pubkey()
+            .context("MXE keys are not finalized yet")?;
+        let signature = self.payer.sign_message(ENCRYPTION_KEY_MESSAGE);
+        let key = EncryptionKey::from_signature(signature.as_ref().try_into()?);
+        let cipher = key.cipher(&mxe_pubkey)?;
+        Ok((key, cipher))
+    }
+
+    pub async fn position(&self, registrant: &Pubkey, position_id: u32) -> Result<PositionAccount> {
+        let address = instructions::position_pda(registrant, position_id);
+        self.program
+            .account(address)
+            .await
+            .with_context(|| format!("position {position_id} of {registrant} not found"))
+    }
+
+    pub async fn exists(&self, address: &Pubkey) -> Result<bool> {
+        let rpc = self.program.internal_rpc();
+        Ok(rpc
+            .get_account_with_commitment(address, confirmed())
+            .await?
+            .value
+            .is_some())
+    }
+
+    /// The pages of the index of the open positions `registrant`
+    /// registered, in order; empty if it registered none since the index
+    /// was introduced.
+    pub async fn owner_index(&self, registrant: &Pubkey) -> Vec<OwnerIndex> {
+        let mut pages = Vec::new();
+        while let Ok(page) = self
+            .program
+            .account::<OwnerIndex>(instructions::owner_index_pda(
+                registrant,
+                pages.len() as u32,
+            ))
+            .await
+        {
+            pages.push(page);
+        }
+        pages
+    }
+
+    /// The position's reveal inbox, `None` before its first reveal.
+    pub async fn inbox(&self, registrant: &Pubkey, position_id: u32) -> Option<RevealInbox> {
+        let position = instructions::position_pda(registrant, position_id);
+        self.program
+            .account(instructions::reveal_inbox_pda(&position))
+            .await
+            .ok()
+    }
+
+    /// Waits for the callback of `computation_offset` to land in the
+    /// position, failing if it reported the computation as failed or
+    /// doesn't arrive within the timeout.
+    pub async fn settled(
+        &self,
+        registrant: &Pubkey,
+        position_id: u32,
+        computation_offset: u64,
+    ) -> Result<PositionAccount> {
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            // The position doesn't exist until a registration lands
+            if let Ok(position) = self.position(registrant, position_id).await {
+                if position.pending_computation != Some(computation_offset) {
+                    match position.last_error {
+                        Some(failure) if failure.computation_offset == computation_offset => {
+                            bail!(
+                                "computation {computation_offset} failed ({:?})",
+                                failure.kind
+                            )
+                        }
+                        _ => return Ok(position),
+                    }
+                }
+            }
+            if Instant::now() >= deadline {
+                bail!(
+                    "no callback for computation {computation_offset} after {}s",
+                    self.timeout.as_secs()
+                );
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    /// Waits for the reveal of `computation_offset` to be delivered to the
+    /// position's inbox.
+    pub async fn delivered(
+        &self,
+        registrant: &Pubkey,
+        position_id: u32,
+        computation_offset: u64,
+    ) -> Result<RevealInbox> {
+        self.settled(registrant, position_id, computation_offset)
+            .await?;
+        self.inbox(registrant, position_id)
+            .await
+            .filter(|inbox| inbox.computation_offset == computation_offset)
+            .context("the reveal was not delivered to the inbox")
+    }
+}
+
+fn confirmed() -> CommitmentConfig {
+    CommitmentConfig::confirmed()
+}
+
+pub fn expand_home(path: &str) -> PathBuf {
+    match path.strip_prefix("~/") {
+        Some(rest) => PathBuf::from(std::env::var("HOME").unwrap_or_default()).join(rest),
+        None => PathBuf::from(path),
+    }
+}
@@ -0,0 +1,282 @@
+//! [`AccountCache`], for dashboards polling many positions.
+//!
+//! Reads go out as `getMultipleAccounts` pages of at most
+//! [`MAX_ACCOUNTS_PER_REQUEST`]. Every entry remembers the slot it was read
+//! at and is served from the cache until the cluster is more than
+//! `max_age_slots` past it. Callers asking for an account another thread is
+//! already fetching wait for that fetch instead of sending their own. Paths
+//! that can't accept a stale read, like deciding whether to act on a
+//! position, use [`AccountCache::refresh`].
+
+use anchor_lang::{prelude::Pubkey, AccountDeserialize};
+use sentinel::PositionAccount;
+use solana_rpc_client::rpc_client::RpcClient;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Condvar, Mutex, MutexGuard};
+
+/// Most accounts one `getMultipleAccounts` request may ask for.
+pub const MAX_ACCOUNTS_PER_REQUEST: usize = 100;
+
+/// Slot of a fetch and the data of each account it asked for, `None` for
+/// missing accounts.
+pub type Page = (u64, Vec<Option<Vec<u8>>>);
+
+/// Where the cache reads accounts from.
+pub trait AccountSource {
+    type Error;
+
+    /// Reads `addresses`, never more than [`MAX_ACCOUNTS_PER_REQUEST`].
+    fn fetch(&self, addresses: &[Pubkey]) -> Result<Page, Self::Error>;
+}
+
+impl AccountSource for RpcClient {
+    type Error = solana_rpc_client_api::client_error::Error;
+
+    fn fetch(&self, addresses: &[Pubkey]) -> Result<Page, Self::Error> {
+        let response = self.get_multiple_accounts_with_commitment(addresses, self.commitment())?;
+        let data = response
+            .value
+            .into_iter()
+            .map(|account| account.map(|a| a.data))
+            .collect();
+        Ok((response.context.slot, data))
+    }
+}
+
+struct Entry {
+    slot: u64,
+    data: Option<Vec<u8>>,
+}
+
+#[derive(Default)]
+struct State {
+    /// Latest slot seen, from a fetch or `observe_slot`
+    slot: u64,
+    entries: HashMap<Pubkey, Entry>,
+    in_flight: HashSet<Pubkey>,
+}
+
+pub struct AccountCache<S> {
+    source: S,
+    max_age_slots: u64,
+    state: Mutex<State>,
+    fetched: Condvar,
+}
+
+impl<S: AccountSource> AccountCache<S> {
+    pub fn new(source: S, max_age_slots: u64) -> Self {
+        Self {
+            source,
+            max_age_slots,
+            state: Mutex::default(),
+            fetched: Condvar::new(),
+        }
+    }
+
+    pub fn source(&self) -> &S {
+        &self.source
+    }
+
+    /// Data of each of `addresses`, `None` for missing accounts. Only
+    /// accounts that aren't cached, have gone stale or are already being
+    /// fetched by another caller cost a request.
+    pub fn get_many(&self, addresses: &[Pubkey]) -> Result<Vec<Option<Vec<u8>>>, S::Error> {
+        let mut state = self.lock();
+        // Anything read during this call is fresh enough for it, even if
+        // another caller has seen a later slot meanwhile
+        let since = state.slot.saturating_sub(self.max_age_slots);
+        loop {
+            let mut claimed = Vec::new();
+            for address in addresses {
+                let cached = state
+                    .entries
+                    .get(address)
+                    .is_some_and(|entry| entry.slot >= since);
+                if !cached && !state.in_flight.contains(address) && !claimed.contains(address) {
+                    claimed.push(*address);
+                }
+            }
+            if !claimed.is_empty() {
+                state.in_flight.extend(&claimed);
+                drop(state);
+                let fetched = self.fetch_pages(&claimed);
+                state = self.lock();
+                for address in &claimed {
+                    state.in_flight.remove(address);
+                }
+                self.fetched.notify_all();
+                fetched?;
+                continue;
+            }
+            if addresses
+                .iter()
+                .any(|address| state.in_flight.contains(address))
+            {
+                state = self.fetched.wait(state).unwrap_or_else(|e| e.into_inner());
+                continue;
+            }
+            return Ok(addresses
+                .iter()
+                .map(|address| {
+                    state
+                        .entries
+                        .get(address)
+                        .and_then(|entry| entry.data.clone())
+                })
+                .collect());
+        }
+    }
+
+    /// Positions at `addresses`, `None` for accounts that are missing or
+    /// aren't positions.
+    pub fn positions(
+        &self,
+        addresses: &[Pubkey],
+    ) -> Result<Vec<Option<PositionAccount>>, S::Error> {
+        Ok(self
+            .get_many(addresses)?
+            .into_iter()
+            .map(|data| PositionAccount::try_deserialize(&mut data?.as_slice()).ok())
+            .collect())
+    }
+
+    /// Reads `address` from the source whatever the cache holds, and caches
+    /// the result.
+    pub fn refresh(&self, address: &Pubkey) -> Result<Option<Vec<u8>>, S::Error> {
+        let (slot, mut data) = self.source.fetch(&[*address])?;
+        let data = data.pop().flatten();
+        let mut state = self.lock();
+        state.slot = state.slot.max(slot);
+        state.entries.insert(
+            *address,
+            Entry {
+                slot,
+                data: data.clone(),
+            },
+        );
+        Ok(data)
+    }
+
+    /// Drops `address` from the cache, e.g. after sending a transaction
+    /// that writes it.
+    pub fn invalidate(&self, address: &Pubkey) {
+        self.lock().entries.remove(address);
+    }
+
+    /// Tells the cache the cluster reached `slot`, e.g. from a slot
+    /// subscription, so entries age without a fetch.
+    pub fn observe_slot(&self, slot: u64) {
+        let mut state = self.lock();
+        state.slot = state.slot.max(slot);
+    }
+
+    fn fetch_pages(&self, addresses: &[Pubkey]) -> Result<(), S::Error> {
+        for page in addresses.chunks(MAX_ACCOUNTS_PER_REQUEST) {
+            let (slot, data) = self.source.fetch(page)?;
+            let mut state = self.lock();
+            state.slot = state.slot.max(slot);
+            for (address, data) in page.iter().zip(data) {
+                state.entries.insert(*address, Entry { slot, data });
+            }
+        }
+        Ok(())
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::thread;
+    use std::time::Duration;
+
+    /// Returns each address's bytes at the current slot and counts requests.
+    #[derive(Default)]
+    struct FakeRpc {
+        slot: AtomicU64,
+        requests: AtomicU64,
+        delay: Option<Duration>,
+    }
+
+    impl AccountSource for FakeRpc {
+        type Error = ();
+
+        fn fetch(&self, addresses: &[Pubkey]) -> Result<Page, ()> {
+            assert!(addresses.len() <= MAX_ACCOUNTS_PER_REQUEST);
+            self.requests.fetch_add(1, Ordering::SeqCst);
+            if let Some(delay) = self.delay {
+                thread::sleep(delay);
+            }
+            let slot = self.slot.load(Ordering::SeqCst);
+            Ok((
+                slot,
+                addresses
+                    .iter()
+                    .map(|a| Some(a.to_bytes().to_vec()))
+                    .collect(),
+            ))
+        }
+    }
+
+    fn requests(cache: &AccountCache<FakeRpc>) -> u64 {
+        cache.source().requests.load(Ordering::SeqCst)
+    }
+
+    #[test]
+    fn get_many_pages_deduplicates_and_serves_from_cache() {
+        let cache = AccountCache::new(FakeRpc::default(), 10);
+        let mut addresses: Vec<_> = (0..150).map(|_| Pubkey::new_unique()).collect();
+        addresses.push(addresses[0]);
+
+        let data = cache.get_many(&addresses).unwrap();
+        assert_eq!(data.len(), 151);
+        assert_eq!(data[150], Some(addresses[0].to_bytes().to_vec()));
+        assert_eq!(requests(&cache), 2);
+
+        cache.get_many(&addresses[..10]).unwrap();
+        assert_eq!(requests(&cache), 2);
+    }
+
+    #[test]
+    fn entries_go_stale_with_the_slot_and_refresh_bypasses_the_cache() {
+        let cache = AccountCache::new(FakeRpc::default(), 10);
+        let address = Pubkey::new_unique();
+        cache.get_many(&[address]).unwrap();
+
+        cache.observe_slot(10);
+        cache.get_many(&[address]).unwrap();
+        assert_eq!(requests(&cache), 1);
+
+        cache.observe_slot(11);
+        cache.source().slot.store(11, Ordering::SeqCst);
+        cache.get_many(&[address]).unwrap();
+        assert_eq!(requests(&cache), 2);
+
+        cache.refresh(&address).unwrap();
+        assert_eq!(requests(&cache), 3);
+        cache.invalidate(&address);
+        cache.get_many(&[address]).unwrap();
+        assert_eq!(requests(&cache), 4);
+    }
+
+    #[test]
+    fn concurrent_reads_of_one_account_share_a_request() {
+        let rpc = FakeRpc {
+            delay: Some(Duration::from_millis(50)),
+            ..FakeRpc::default()
+        };
+        let cache = AccountCache::new(rpc, 10);
+        let address = Pubkey::new_unique();
+
+        thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| assert!(cache.get_many(&[address]).unwrap()[0].is_some()));
+            }
+        });
+        assert_eq!(requests(&cache), 1);
+    }
+}
@@ -0,0 +1,607 @@
+//! x25519 keys, nonces and the Rescue cipher shared with the MXE.
+//!
+//! Encryption matches `@arcium-hq/client`: the client's x25519 key and the
+//! MXE's public key give a shared secret, the Rescue cipher is keyed from it,
+//! and every `u64` encrypts to one 32-byte little-endian field element under a
+//! 128-bit nonce.
+
+use crate::envelope;
+use arcis_compiler::{
+    traits::FromLeBytes,
+    utils::{
+        crypto::{
+            key::{X25519PrivateKey, X25519PublicKey},
+            rescue_cipher::RescueCipher,
+        },
+        curve_point::CurvePoint,
+        field::{BaseField, ScalarField},
+    },
+};
+use sentinel::POSITION_FIELD_COUNT;
+use sha2::{Digest, Sha256};
+use std::fmt;
+
+/// Message the wallet signs to derive its encryption key. Same as the agent
+/// and the TypeScript tests, so keys derived here decrypt their results.
+pub const ENCRYPTION_KEY_MESSAGE: &[u8] = b"fold-defi-encryption-key-v1";
+
+/// A random nonce. Never reuse one with the same key.
+pub fn new_nonce() -> u128 {
+    rand::random()
+}
+
+/// An x25519 key pair for talking to the MXE.
+#[derive(Clone, Copy)]
+pub struct EncryptionKey {
+    secret: [u8; 32],
+}
+
+impl EncryptionKey {
+    pub fn from_secret(secret: [u8; 32]) -> Self {
+        Self { secret }
+    }
+
+    pub fn random() -> Self {
+        Self::from_secret(rand::random())
+    }
+
+    /// Derives the key from the wallet's ed25519 signature over
+    /// [`ENCRYPTION_KEY_MESSAGE`], as `deriveEncryptionKey` does in TypeScript.
+    pub fn from_signature(signature: &[u8; 64]) -> Self {
+        Self::from_secret(Sha256::digest(signature).into())
+    }
+
+    pub fn secret(&self) -> [u8; 32] {
+        self.secret
+    }
+
+    /// The public key passed as `encryption_pubkey`.
+    pub fn public_key(&self) -> [u8; 32] {
+        X25519PublicKey::<CurvePoint>::new_from_private_key(self.private_key()).to_le_bytes()
+    }
+
+    /// The cipher shared with the holder of `peer_pubkey`, usually the MXE.
+    pub fn cipher(&self, peer_pubkey: &[u8; 32]) -> Result<Cipher, InvalidPublicKey> {
+        let peer =
+            X25519PublicKey::<CurvePoint>::from_le_bytes(*peer_pubkey).ok_or(InvalidPublicKey)?;
+        Ok(Cipher(RescueCipher::new_with_client_from_keys(
+            self.private_key(),
+            peer,
+        )))
+    }
+
+    fn private_key(&self) -> X25519PrivateKey<ScalarField> {
+        X25519PrivateKey::from_le_bytes(self.secret)
+    }
+}
+
+/// The peer's x25519 public key is not a valid curve point.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidPublicKey;
+
+impl fmt::Display for InvalidPublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid x25519 public key")
+    }
+}
+
+impl std::error::Error for InvalidPublicKey {}
+
+/// Rescue cipher in counter mode over the curve25519 base field.
+pub struct Cipher(RescueCipher<BaseField, BaseField>);
+
+impl Cipher {
+    pub fn encrypt(&self, values: &[u64], nonce: u128) -> Vec<[u8; 32]> {
+        let plaintext = values.iter().map(|&value| BaseField::from(value)).collect();
+        self.0
+            .encrypt(plaintext, nonce_element(nonce))
+            .iter()
+            .map(BaseField::to_le_bytes)
+            .collect()
+    }
+
+    /// Decrypts `u64` fields. `None` if a field doesn't fit, which means the
+    /// ciphertexts, nonce or key are wrong.
+    pub fn decrypt(&self, ciphertexts: &[[u8; 32]], nonce: u128) -> Option<Vec<u64>> {
+        let ciphertext = ciphertexts
+            .iter()
+            .map(|bytes| BaseField::from_le_bytes_checked(*bytes))
+            .collect::<Option<Vec<_>>>()?;
+        self.0
+            .decrypt(ciphertext, nonce_element(nonce))
+            .iter()
+            .map(|value| {
+                let bytes = value.to_le_bytes();
+                let (low, high) = bytes.split_at(8);
+                high.iter()
+                    .all(|&b| b == 0)
+                    .then(|| u64::from_le_bytes(low.try_into().unwrap()))
+            })
+            .collect()
+    }
+}
+
+fn nonce_element(nonce: u128) -> BaseField {
+    let mut bytes = [0; 32];
+    bytes[..16].copy_from_slice(&nonce.to_le_bytes());
+    BaseField::from_le_bytes(bytes)
+}
+
+/// `PositionData` in `encrypted-ixs`, field for field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PositionData {
+    /// USD cents
+    pub position_value: u64,
+    /// Basis points
+    pub collateral_ratio: u64,
+    /// Basis points
+    pub liquidation_threshold: u64,
+}
+
+impl PositionData {
+    pub fn encrypt(&self, cipher: &Cipher, nonce: u128) -> EncryptedPosition {
+        // Fails to compile when a field is added here but not to the
+        // program's `POSITION_FIELD_COUNT`, which the circuit is checked against.
+        let fields: [u64; POSITION_FIELD_COUNT] = [
+            self.position_value,
+            self.collateral_ratio,
+            self.liquidation_threshold,
+        ];
+        let ciphertexts = cipher.encrypt(&fields, nonce);
+        EncryptedPosition {
+            ciphertexts: ciphertexts.try_into().unwrap(),
+            nonce,
+        }
+    }
+}
+
+/// The `encrypted_position` and `encryption_nonce` arguments of the health
+/// check instructions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EncryptedPosition {
+    pub ciphertexts: [[u8; 32]; POSITION_FIELD_COUNT],
+    pub nonce: u128,
+}
+
+/// `PositionValues` in `encrypted-ixs`, the input of
+/// `compute_health_factor` and cross-chain checks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PositionValues {
+    /// USD cents
+    pub collateral_value: u64,
+    /// USD cents
+    pub debt_value: u64,
+    /// Basis points
+    pub liquidation_threshold: u64,
+}
+
+impl PositionValues {
+    pub fn encrypt(&self, cipher: &Cipher, nonce: u128) -> EncryptedPosition {
+        let ciphertexts = cipher.encrypt(
+            &[
+                self.collateral_value,
+                self.debt_value,
+                self.liquidation_threshold,
+            ],
+            nonce,
+        );
+        EncryptedPosition {
+            ciphertexts: ciphertexts.try_into().unwrap(),
+            nonce,
+        }
+    }
+}
+
+/// `StopLossConfig` in `encrypted-ixs`, the order `set_stop_loss` stores.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StopLossConfig {
+    /// Health factor in basis points at or below which the order fires
+    pub trigger_health_factor_bps: u64,
+    /// Code the executor interprets
+    pub action: u64,
+    /// Basis points
+    pub max_slippage_bps: u64,
+}
+
+impl StopLossConfig {
+    pub fn encrypt(&self, cipher: &Cipher, nonce: u128) -> EncryptedPosition {
+        let ciphertexts = cipher.encrypt(
+            &[
+                self.trigger_health_factor_bps,
+                self.action,
+                self.max_slippage_bps,
+            ],
+            nonce,
+        );
+        EncryptedPosition {
+            ciphertexts: ciphertexts.try_into().unwrap(),
+            nonce,
+        }
+    }
+}
+
+/// `PerpPosition` in `encrypted-ixs`, a perpetual futures position for
+/// `check_perp_health`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PerpPosition {
+    pub is_long: bool,
+    /// Micro-USD per token
+    pub entry_price: u64,
+    /// Millionths of a token
+    pub size: u64,
+    /// Basis points, 50_000 = 5x
+    pub leverage_bps: u64,
+    /// Basis points of the notional at the mark price
+    pub maintenance_margin_bps: u64,
+    /// Funding paid net of funding received, in USD cents, 0 if negative
+    pub funding_owed_cents: u64,
+}
+
+impl PerpPosition {
+    pub fn encrypt(&self, cipher: &Cipher, nonce: u128) -> EncryptedPerpPosition {
+        let ciphertexts = cipher.encrypt(
+            &[
+                self.is_long as u64,
+                self.entry_price,
+                self.size,
+                self.leverage_bps,
+                self.maintenance_margin_bps,
+                self.funding_owed_cents,
+            ],
+            nonce,
+        );
+        EncryptedPerpPosition {
+            ciphertexts: ciphertexts.try_into().unwrap(),
+            nonce,
+        }
+    }
+}
+
+/// The `encrypted_position` and `encryption_nonce` arguments of
+/// `check_perp_health`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EncryptedPerpPosition {
+    pub ciphertexts: [[u8; 32]; 6],
+    pub nonce: u128,
+}
+
+/// `ScoredPosition` in `encrypted-ixs`, the inputs of
+/// `compute_risk_score`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScoredPosition {
+    /// Basis points, 15_000 = 150%
+    pub collateral_ratio: u64,
+    /// Basis points
+    pub liquidation_threshold: u64,
+    /// The position's share of the owner's holdings, in basis points
+    pub concentration_bps: u64,
+}
+
+impl ScoredPosition {
+    pub fn encrypt(&self, cipher: &Cipher, nonce: u128) -> EncryptedScoredPosition {
+        let ciphertexts = cipher.encrypt(
+            &[
+                self.collateral_ratio,
+                self.liquidation_threshold,
+                self.concentration_bps,
+            ],
+            nonce,
+        );
+        EncryptedScoredPosition {
+            ciphertexts: ciphertexts.try_into().unwrap(),
+            nonce,
+        }
+    }
+}
+
+/// The `encrypted_position` and `encryption_nonce` arguments of
+/// `compute_risk_score`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EncryptedScoredPosition {
+    pub ciphertexts: [[u8; 32]; 3],
+    pub nonce: u128,
+}
+
+/// `HealthFactor` in `encrypted-ixs`, as revealed to the owner by
+/// `reveal_health_factor_to_owner`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HealthFactor {
+    /// Collateral ratio over the liquidation threshold in basis points:
+    /// 10_000 is the threshold, `u64::MAX` means no debt
+    pub health_factor_bps: u64,
+}
+
+impl HealthFactor {
+    /// Decrypts a `reveal_health_factor_to_owner` envelope. `None` for
+    /// envelopes of other circuits or versions this client doesn't know.
+    pub fn from_envelope(cipher: &Cipher, envelope: &sentinel::EncryptedEnvelope) -> Option<Self> {
+        match *envelope::open(cipher, envelope, "reveal_health_factor_to_owner")? {
+            [health_factor_bps] => Some(Self { health_factor_bps }),
+            _ => None,
+        }
+    }
+
+    /// Decrypts the reveal delivered to `inbox`. `None` if nothing has been
+    /// delivered yet, or as for [`HealthFactor::from_envelope`].
+    pub fn from_inbox(cipher: &Cipher, inbox: &sentinel::RevealInbox) -> Option<Self> {
+        if inbox.delivered_at == 0 {
+            return None;
+        }
+        Self::from_envelope(cipher, &inbox.envelope)
+    }
+}
+
+/// `StressResult` in `encrypted-ixs`, the answer to a
+/// `stress_test_position` what-if.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StressResult {
+    /// Whether the shocked position would be liquidatable
+    pub would_liquidate: bool,
+    /// Health factor after the shock, as in [`HealthFactor`]
+    pub health_factor_bps: u64,
+}
+
+impl StressResult {
+    /// Decrypts a `stress_test_position` envelope. `None` for envelopes of
+    /// other circuits or versions this client doesn't know.
+    pub fn from_envelope(cipher: &Cipher, envelope: &sentinel::EncryptedEnvelope) -> Option<Self> {
+        match *envelope::open(cipher, envelope, "stress_test_position")? {
+            [would_liquidate @ 0..=1, health_factor_bps] => Some(Self {
+                would_liquidate: would_liquidate == 1,
+                health_factor_bps,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Decrypts the result delivered to `inbox`. `None` if nothing has been
+    /// delivered yet, or as for [`StressResult::from_envelope`].
+    pub fn from_inbox(cipher: &Cipher, inbox: &sentinel::RevealInbox) -> Option<Self> {
+        if inbox.delivered_at == 0 {
+            return None;
+        }
+        Self::from_envelope(cipher, &inbox.envelope)
+    }
+}
+
+/// `RiskState` in `encrypted-ixs`, as revealed to the owner by
+/// `reveal_risk_to_owner` in the `RiskRevealedToOwner` event and the
+/// position's `RevealInbox`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RiskState {
+    pub is_at_risk: bool,
+    /// 0 = safe, 1 = low, 2 = medium, 3 = critical
+    pub severity: u8,
+    /// Oracle price at the last priced check in micro-USD per token, 0 if
+    /// there has been none
+    pub last_price: u64,
+}
+
+impl RiskState {
+    pub fn decrypt(cipher: &Cipher, ciphertexts: &[[u8; 32]; 3], nonce: u128) -> Option<Self> {
+        Self::from_fields(&cipher.decrypt(ciphertexts, nonce)?)
+    }
+
+    /// Decrypts a `reveal_risk_to_owner` envelope. `None` for envelopes of
+    /// other circuits or versions this client doesn't know.
+    pub fn from_envelope(cipher: &Cipher, envelope: &sentinel::EncryptedEnvelope) -> Option<Self> {
+        Self::from_fields(&envelope::open(cipher, envelope, "reveal_risk_to_owner")?)
+    }
+
+    /// Decrypts the reveal delivered to `inbox`. `None` if nothing has been
+    /// delivered yet, or as for [`RiskState::from_envelope`].
+    pub fn from_inbox(cipher: &Cipher, inbox: &sentinel::RevealInbox) -> Option<Self> {
+        if inbox.delivered_at == 0 {
+            return None;
+        }
+        Self::from_envelope(cipher, &inbox.envelope)
+    }
+
+    /// Decrypts the latest reveal delivered to a viewer's `grant`, with the
+    /// viewer's cipher. `None` if nothing has been delivered yet, or as for
+    /// [`RiskState::from_envelope`].
+    pub fn from_viewer_grant(cipher: &Cipher, grant: &sentinel::ViewerGrant) -> Option<Self> {
+        if grant.delivered_at == 0 {
+            return None;
+        }
+        Self::from_fields(&envelope::open(
+            cipher,
+            &grant.envelope,
+            "reveal_risk_to_viewer",
+        )?)
+    }
+
+    fn from_fields(fields: &[u64]) -> Option<Self> {
+        match *fields {
+            [is_at_risk @ 0..=1, severity @ 0..=3, last_price] => Some(Self {
+                is_at_risk: is_at_risk == 1,
+                severity: severity as u8,
+                last_price,
+            }),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::prelude::Pubkey;
+
+    #[test]
+    fn mxe_decrypts_position() {
+        let client = EncryptionKey::random();
+        let mxe = EncryptionKey::random();
+        let position = PositionData {
+            position_value: 100_000,
+            collateral_ratio: 15_000,
+            liquidation_threshold: 11_000,
+        };
+
+        let encrypted = position.encrypt(&client.cipher(&mxe.public_key()).unwrap(), new_nonce());
+        let mxe_cipher = mxe.cipher(&client.public_key()).unwrap();
+        assert_eq!(
+            mxe_cipher.decrypt(&encrypted.ciphertexts, encrypted.nonce),
+            Some(vec![100_000, 15_000, 11_000])
+        );
+        assert_ne!(
+            mxe_cipher.decrypt(&encrypted.ciphertexts, encrypted.nonce + 1),
+            Some(vec![100_000, 15_000, 11_000])
+        );
+    }
+
+    #[test]
+    fn counts_the_first_encrypted_input_of_a_circuit() {
+        use sentinel::layout::first_encrypted_input_len;
+        let ciphertext = r#"{"size_in_bits":255,"type":"ciphertext"}"#;
+        let input = |n: usize| {
+            let shared = r#"{"content":[{"type":"arcis_x25519_pubkey"},{"type":"u128"}]}"#;
+            let data = vec![ciphertext; n].join(",");
+            let data = format!(r#"{{"content":[{{"content":[{data}],"type":"array"}}]}}"#);
+            format!(r#"{{"content":[{shared},{data}]}}"#)
+        };
+        let idarc = format!(r#"{{"name":"c","inputs":[{},{}]}}"#, input(4), input(2));
+
+        assert_eq!(first_encrypted_input_len(&idarc), 4);
+        assert_eq!(first_encrypted_input_len(r#"{"name":"c","inputs":[]}"#), 0);
+    }
+
+    #[test]
+    fn perp_position_encrypts_in_circuit_order() {
+        let client = EncryptionKey::random();
+        let mxe = EncryptionKey::random();
+        let position = PerpPosition {
+            is_long: false,
+            entry_price: 150_000_000,
+            size: 2_000_000,
+            leverage_bps: 50_000,
+            maintenance_margin_bps: 500,
+            funding_owed_cents: 1_200,
+        };
+
+        let encrypted = position.encrypt(&client.cipher(&mxe.public_key()).unwrap(), 9);
+        assert_eq!(
+            mxe.cipher(&client.public_key())
+                .unwrap()
+                .decrypt(&encrypted.ciphertexts, 9),
+            Some(vec![0, 150_000_000, 2_000_000, 50_000, 500, 1_200])
+        );
+    }
+
+    #[test]
+    fn health_factor_opens_only_its_own_envelopes() {
+        let owner = EncryptionKey::random();
+        let mxe = EncryptionKey::random();
+        let nonce = new_nonce();
+        let ciphertexts = mxe
+            .cipher(&owner.public_key())
+            .unwrap()
+            .encrypt(&[13_500], nonce);
+        let cipher = owner.cipher(&mxe.public_key()).unwrap();
+
+        let envelope = sentinel::EncryptedEnvelope::new(
+            envelope::circuit_id("reveal_health_factor_to_owner"),
+            nonce,
+            &ciphertexts,
+        );
+        assert_eq!(
+            HealthFactor::from_envelope(&cipher, &envelope),
+            Some(HealthFactor {
+                health_factor_bps: 13_500
+            })
+        );
+        let risk = sentinel::EncryptedEnvelope {
+            circuit_id: envelope::circuit_id("reveal_risk_to_owner"),
+            ..envelope
+        };
+        assert_eq!(HealthFactor::from_envelope(&cipher, &risk), None);
+    }
+
+    #[test]
+    fn decrypts_stress_result() {
+        let owner = EncryptionKey::random();
+        let mxe = EncryptionKey::random();
+        let nonce = new_nonce();
+        let ciphertexts = mxe
+            .cipher(&owner.public_key())
+            .unwrap()
+            .encrypt(&[1, 9_600], nonce);
+        let cipher = owner.cipher(&mxe.public_key()).unwrap();
+
+        let envelope = sentinel::EncryptedEnvelope::new(
+            envelope::circuit_id("stress_test_position"),
+            nonce,
+            &ciphertexts,
+        );
+        assert_eq!(
+            StressResult::from_envelope(&cipher, &envelope),
+            Some(StressResult {
+                would_liquidate: true,
+                health_factor_bps: 9_600
+            })
+        );
+    }
+
+    #[test]
+    fn decrypts_revealed_risk() {
+        let owner = EncryptionKey::from_signature(&[7; 64]);
+        let mxe = EncryptionKey::random();
+        let nonce = new_nonce();
+        let ciphertexts = mxe
+            .cipher(&owner.public_key())
+            .unwrap()
+            .encrypt(&[1, 3, 152_000_000], nonce);
+
+        let risk = RiskState::decrypt(
+            &owner.cipher(&mxe.public_key()).unwrap(),
+            &ciphertexts.try_into().unwrap(),
+            nonce,
+        );
+        assert_eq!(
+            risk,
+            Some(RiskState {
+                is_at_risk: true,
+                severity: 3,
+                last_price: 152_000_000,
+            })
+        );
+    }
+
+    #[test]
+    fn viewer_reads_only_delivered_grants() {
+        let auditor = EncryptionKey::random();
+        let mxe = EncryptionKey::random();
+        let nonce = new_nonce();
+        let ciphertexts = mxe
+            .cipher(&auditor.public_key())
+            .unwrap()
+            .encrypt(&[0, 1, 99], nonce);
+        let cipher = auditor.cipher(&mxe.public_key()).unwrap();
+        let mut grant = sentinel::ViewerGrant {
+            bump: 255,
+            position: Pubkey::new_unique(),
+            owner: Pubkey::new_unique(),
+            viewer: Pubkey::new_unique(),
+            encryption_key: auditor.public_key(),
+            granted_at: 1,
+            computation_offset: 0,
+            delivered_at: 0,
+            envelope: sentinel::EncryptedEnvelope::new(
+                envelope::circuit_id("reveal_risk_to_viewer"),
+                nonce,
+                &ciphertexts,
+            ),
+        };
+        assert_eq!(RiskState::from_viewer_grant(&cipher, &grant), None);
+
+        grant.delivered_at = 2;
+        assert_eq!(
+            RiskState::from_viewer_grant(&cipher, &grant),
+            Some(RiskState {
+                is_at_risk: false,
+                severity: 1,
+                last_price: 99
+            })
+        );
+    }
+}
@@ -0,0 +1,68 @@
+//! [`EncryptedEnvelope`], the form owner-encrypted outputs are stored in
+//! (`RevealInbox`) and emitted in (`RiskRevealedToOwner`).
+//!
+//! An envelope names the circuit that produced it, so a client only decrypts
+//! layouts it knows and reports anything else as unreadable rather than
+//! misreading it after a circuit change.
+
+use crate::encryption::Cipher;
+use anchor_lang::{AnchorDeserialize, AnchorSerialize};
+use arcium_anchor::comp_def_offset;
+
+pub use sentinel::{EncryptedEnvelope, ENVELOPE_VERSION};
+
+/// Borsh encoding, as the program writes it.
+pub fn encode(envelope: &EncryptedEnvelope) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    envelope
+        .serialize(&mut bytes)
+        .expect("writing to a Vec cannot fail");
+    bytes
+}
+
+pub fn decode(bytes: &[u8]) -> std::io::Result<EncryptedEnvelope> {
+    EncryptedEnvelope::try_from_slice(bytes)
+}
+
+/// The circuit id of envelopes written from `circuit`'s output.
+pub fn circuit_id(circuit: &str) -> u32 {
+    comp_def_offset(circuit)
+}
+
+/// Decrypts `envelope` if it is `circuit`'s output in an envelope version this
+/// client reads. `None` otherwise, or if decryption fails.
+pub fn open(cipher: &Cipher, envelope: &EncryptedEnvelope, circuit: &str) -> Option<Vec<u64>> {
+    if envelope.version != ENVELOPE_VERSION || envelope.circuit_id != circuit_id(circuit) {
+        return None;
+    }
+    cipher.decrypt(&envelope.ciphertexts, envelope.nonce)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{new_nonce, EncryptionKey};
+
+    #[test]
+    fn envelope_round_trips_and_opens_only_for_its_circuit() {
+        let owner = EncryptionKey::random();
+        let mxe = EncryptionKey::random();
+        let nonce = new_nonce();
+        let ciphertexts = mxe
+            .cipher(&owner.public_key())
+            .unwrap()
+            .encrypt(&[1, 2], nonce);
+        let envelope =
+            EncryptedEnvelope::new(circuit_id("reveal_risk_to_owner"), nonce, &ciphertexts);
+
+        let decoded = decode(&encode(&envelope)).unwrap();
+        assert_eq!(decoded, envelope);
+
+        let cipher = owner.cipher(&mxe.public_key()).unwrap();
+        assert_eq!(
+            open(&cipher, &decoded, "reveal_risk_to_owner"),
+            Some(vec![1, 2])
+        );
+        assert_eq!(open(&cipher, &decoded, "reveal_risk"), None);
+    }
+}
@@ -0,0 +1,494 @@
+//! Golden tests of instruction encoding: every builder in `instructions`,
+//! called with fixed keys and encrypted with a fixed key pair, must produce
+//! exactly the accounts and data recorded in `golden/instructions.txt`. An
+//! argument reordered in a builder or in the `encrypt` of a circuit input
+//! shows up here before it reaches a circuit. After an intentional change,
+//! re-record with `GOLDEN_UPDATE=1 cargo test -p sentinel-client golden`.
+
+use crate::{
+    instructions::{self, Queue},
+    Cipher, EncryptionKey, PerpPosition, PositionData, PositionValues, ScoredPosition,
+    StopLossConfig,
+};
+use anchor_lang::{prelude::Pubkey, solana_program::instruction::Instruction};
+use sentinel::{
+    protocols::Protocol, AutoActionAccount, PositionMetadata, ProtectionTrigger, SizeBucket,
+};
+use std::{fmt::Write, path::Path};
+
+fn key(n: u8) -> Pubkey {
+    Pubkey::new_from_array([n; 32])
+}
+
+fn cipher() -> (EncryptionKey, Cipher) {
+    let client = EncryptionKey::from_signature(&[7; 64]);
+    let mxe = EncryptionKey::from_signature(&[9; 64]);
+    let cipher = client.cipher(&mxe.public_key()).unwrap();
+    (client, cipher)
+}
+
+fn instructions() -> Vec<(&'static str, Instruction)> {
+    let (payer, owner, registrant) = (key(1), key(2), key(3));
+    let queue = Queue {
+        cluster_offset: 456,
+        computation_offset: 42,
+    };
+    let (client, cipher) = cipher();
+    let pubkey = client.public_key();
+    let position = PositionData {
+        position_value: 100_000,
+        collateral_ratio: 15_000,
+        liquidation_threshold: 11_000,
+    }
+    .encrypt(&cipher, 11);
+    let values = PositionValues {
+        collateral_value: 150_000,
+        debt_value: 100_000,
+        liquidation_threshold: 8_000,
+    }
+    .encrypt(&cipher, 12);
+    let stop_loss = StopLossConfig {
+        trigger_health_factor_bps: 10_500,
+        action: 1,
+        max_slippage_bps: 50,
+    }
+    .encrypt(&cipher, 13);
+    let perp = PerpPosition {
+        is_long: true,
+        entry_price: 150_000_000,
+        size: 2_000_000,
+        leverage_bps: 50_000,
+        maintenance_margin_bps: 500,
+        funding_owed_cents: 120,
+    }
+    .encrypt(&cipher, 14);
+    let scored = ScoredPosition {
+        collateral_ratio: 15_000,
+        liquidation_threshold: 11_000,
+        concentration_bps: 4_000,
+    }
+    .encrypt(&cipher, 15);
+    let metadata = PositionMetadata {
+        protocol: Protocol::Kamino,
+        market: key(20),
+        collateral_mint: key(21),
+        debt_mint: key(22),
+    };
+    let executor_accounts = vec![AutoActionAccount {
+        pubkey: key(23),
+        is_signer: false,
+        is_writable: true,
+    }];
+    let memo = sentinel::EncryptedMemo {
+        encryption_key: pubkey,
+        nonce: 15,
+        ciphertexts: cipher.encrypt(&[4, 0x6c6c_6573], 15),
+    };
+
+    vec![
+        (
+            "register_position",
+            instructions::register_position(
+                &payer,
+                7,
+                queue,
+                10,
+                Some(key(4)),
+                metadata,
+                Some("treasury"),
+                1,
+            ),
+        ),
+        (
+            "check_health",
+            instructions::check_health(
+                &payer,
+                &owner,
+                &registrant,
+                7,
+                queue,
+                pubkey,
+                &position,
+                true,
+                Some(key(5)),
+                Some(key(6)),
+                true,
+                true,
+            ),
+        ),
+        (
+            "check_perp_health",
+            instructions::check_perp_health(
+                &payer,
+                &owner,
+                &registrant,
+                7,
+                queue,
+                pubkey,
+                &perp,
+                key(5),
+            ),
+        ),
+        (
+            "compute_risk_score",
+            instructions::compute_risk_score(
+                &payer,
+                &owner,
+                &registrant,
+                7,
+                queue,
+                pubkey,
+                &scored,
+                16,
+                Some(key(5)),
+                Some(key(6)),
+            ),
+        ),
+        (
+            "check_health_from_protocol",
+            instructions::check_health_from_protocol(
+                &payer,
+                &owner,
+                &registrant,
+                7,
+                queue,
+                key(7),
+                &key(8),
+            ),
+        ),
+        (
+            "check_health_batch",
+            instructions::check_health_batch(
+                &owner,
+                queue,
+                pubkey,
+                &[(key(9), position), (key(10), position)],
+            ),
+        ),
+        (
+            "reveal_risk",
+            instructions::reveal_risk(&payer, &registrant, 7, queue, true),
+        ),
+        (
+            "reveal_risk_to_owner",
+            instructions::reveal_risk_to_owner(&payer, &registrant, 7, queue, pubkey, 16, true),
+        ),
+        (
+            "grant_viewer",
+            instructions::grant_viewer(&owner, &registrant, 7, key(11), [12; 32]),
+        ),
+        (
+            "revoke_viewer",
+            instructions::revoke_viewer(&owner, &registrant, 7, key(11)),
+        ),
+        (
+            "reveal_risk_to_viewer",
+            instructions::reveal_risk_to_viewer(&payer, &registrant, 7, &key(11), queue, 17),
+        ),
+        (
+            "compute_health_factor",
+            instructions::compute_health_factor(&payer, &registrant, 7, queue, pubkey, &values, 18),
+        ),
+        (
+            "reveal_health_factor_to_owner",
+            instructions::reveal_health_factor_to_owner(&payer, &registrant, 7, queue, pubkey, 19),
+        ),
+        (
+            "stress_test_position",
+            instructions::stress_test_position(
+                &payer,
+                &registrant,
+                7,
+                queue,
+                pubkey,
+                &values,
+                2_500,
+            ),
+        ),
+        (
+            "attest_health",
+            instructions::attest_health(&payer, &registrant, 7, queue, 12_000),
+        ),
+        (
+            "set_label",
+            instructions::set_label(&owner, &registrant, 7, Some("vault")),
+        ),
+        (
+            "rotate_encryption",
+            instructions::rotate_encryption(&payer, &registrant, 7, queue, 20),
+        ),
+        (
+            "configure_alerts",
+            instructions::configure_alerts(&owner, &registrant, 7, 2),
+        ),
+        (
+            "set_min_check_interval",
+            instructions::set_min_check_interval(&owner, &registrant, 7, 300),
+        ),
+        (
+            "set_heartbeat_deadline",
+            instructions::set_heartbeat_deadline(&owner, &registrant, 7, 3_600),
+        ),
+        ("heartbeat", instructions::heartbeat(&payer, &registrant, 7)),
+        (
+            "flag_stale_position",
+            instructions::flag_stale_position(&payer, &registrant, 7, true),
+        ),
+        (
+            "is_monitored",
+            instructions::is_monitored(&registrant, 7, owner, 3_600),
+        ),
+        (
+            "set_max_staleness",
+            instructions::set_max_staleness(&owner, &registrant, 7, Some(60)),
+        ),
+        (
+            "set_tvl_protocol",
+            instructions::set_tvl_protocol(&owner, &registrant, 7, Some(key(8)), 1_500),
+        ),
+        (
+            "accept_config_version",
+            instructions::accept_config_version(&owner, &registrant, 7, 3),
+        ),
+        (
+            "update_tvl_snapshot",
+            instructions::update_tvl_snapshot(&payer, key(8), 5_000_000),
+        ),
+        (
+            "flag_price_feed",
+            instructions::flag_price_feed(&payer, key(5)),
+        ),
+        (
+            "repoint_price_feeds",
+            instructions::repoint_price_feeds(&key(5), &[key(13), key(14)]),
+        ),
+        (
+            "fund_subscription",
+            instructions::fund_subscription(&payer, key(13), 1_000_000),
+        ),
+        (
+            "withdraw_subscription",
+            instructions::withdraw_subscription(&owner, &registrant, 7, 500_000),
+        ),
+        (
+            "fund_check_bounty",
+            instructions::fund_check_bounty(&owner, &registrant, 7, 1_000, 50_000),
+        ),
+        (
+            "close_check_bounty",
+            instructions::close_check_bounty(&owner, &registrant, 7),
+        ),
+        (
+            "close_position",
+            instructions::close_position(&owner, &registrant, 7, true, true, Some(1)),
+        ),
+        (
+            "cancel_computation",
+            instructions::cancel_computation(&payer, &registrant, 7, queue, true),
+        ),
+        (
+            "mint_monitoring_receipt",
+            instructions::mint_monitoring_receipt(&owner, &registrant, 7),
+        ),
+        (
+            "burn_monitoring_receipt",
+            instructions::burn_monitoring_receipt(&owner, &registrant, 7),
+        ),
+        (
+            "archive_position",
+            instructions::archive_position(&owner, &registrant, 7, true, true, true, Some(1)),
+        ),
+        (
+            "migrate_position",
+            instructions::migrate_position(&payer, &registrant, 7),
+        ),
+        (
+            "renew_keeper_lease",
+            instructions::renew_keeper_lease(&payer, 3, 1, 120),
+        ),
+        (
+            "register_keeper",
+            instructions::register_keeper(&payer, 2_000_000_000),
+        ),
+        ("unbond_keeper", instructions::unbond_keeper(&payer)),
+        (
+            "withdraw_keeper_bond",
+            instructions::withdraw_keeper_bond(&payer),
+        ),
+        (
+            "set_registered_keepers_only",
+            instructions::set_registered_keepers_only(&owner, &registrant, 7, true),
+        ),
+        (
+            "propose_position_transfer",
+            instructions::propose_position_transfer(&owner, &registrant, 7, Some(key(15))),
+        ),
+        (
+            "accept_position_transfer",
+            instructions::accept_position_transfer(&key(15), &registrant, 7, true, true),
+        ),
+        (
+            "set_auto_action",
+            instructions::set_auto_action(
+                &owner,
+                &registrant,
+                7,
+                key(16),
+                executor_accounts.clone(),
+                vec![1, 2, 3],
+            ),
+        ),
+        (
+            "clear_auto_action",
+            instructions::clear_auto_action(&owner, &registrant, 7),
+        ),
+        (
+            "execute_auto_action",
+            instructions::execute_auto_action(
+                &registrant,
+                7,
+                &sentinel::AutoActionConfig {
+                    bump: 255,
+                    position: instructions::position_pda(&registrant, 7),
+                    owner,
+                    program_id: key(16),
+                    accounts: executor_accounts.clone(),
+                    data: vec![1, 2, 3],
+                    due_severity: 4,
+                },
+            ),
+        ),
+        (
+            "set_stop_loss",
+            instructions::set_stop_loss(
+                &owner,
+                &registrant,
+                7,
+                pubkey,
+                &stop_loss,
+                Some(key(16)),
+                executor_accounts.clone(),
+            ),
+        ),
+        (
+            "clear_stop_loss",
+            instructions::clear_stop_loss(&owner, &registrant, 7),
+        ),
+        (
+            "execute_stop_loss",
+            instructions::execute_stop_loss(
+                &registrant,
+                7,
+                &sentinel::StopLossAccount {
+                    bump: 255,
+                    config: [[1; 32]; 3],
+                    encryption_pubkey: [2; 32],
+                    nonce: 3,
+                    position: instructions::position_pda(&registrant, 7),
+                    owner,
+                    executor: Some(key(17)),
+                    executor_accounts,
+                    last_evaluated_at: 4,
+                    triggered_at: 5,
+                    action: 1,
+                    max_slippage_bps: 50,
+                    executed_at: 0,
+                },
+            ),
+        ),
+        (
+            "set_action_memo",
+            instructions::set_action_memo(&owner, &registrant, 7, memo),
+        ),
+        (
+            "clear_action_memo",
+            instructions::clear_action_memo(&owner, &registrant, 7),
+        ),
+        (
+            "evaluate_stop_loss",
+            instructions::evaluate_stop_loss(&payer, &registrant, 7, queue),
+        ),
+        (
+            "set_size_disclosure",
+            instructions::set_size_disclosure(&owner, &registrant, 7, true),
+        ),
+        (
+            "reveal_size_bucket",
+            instructions::reveal_size_bucket(&payer, &registrant, 7, queue, pubkey, &position),
+        ),
+        (
+            "record_value_protected",
+            instructions::record_value_protected(
+                &payer,
+                &registrant,
+                7,
+                ProtectionTrigger::AutoAction,
+                SizeBucket::From10kTo100k,
+                500,
+            ),
+        ),
+        (
+            "set_sponsor_budget",
+            instructions::set_sponsor_budget(&payer, owner, 10),
+        ),
+        (
+            "sponsored_reveal_risk_to_owner",
+            instructions::sponsored_reveal_risk_to_owner(
+                &payer,
+                &owner,
+                &registrant,
+                7,
+                queue,
+                pubkey,
+                21,
+                true,
+            ),
+        ),
+    ]
+}
+
+/// One `## name` section of the fixture: program, one line per account,
+/// then the data in hex.
+fn render(name: &str, ix: &Instruction) -> String {
+    let mut out = format!("## {name}\nprogram {}\n", ix.program_id);
+    for meta in &ix.accounts {
+        let signer = if meta.is_signer { " signer" } else { "" };
+        let writable = if meta.is_writable { " writable" } else { "" };
+        writeln!(out, "account {}{signer}{writable}", meta.pubkey).unwrap();
+    }
+    out.push_str("data ");
+    for byte in &ix.data {
+        write!(out, "{byte:02x}").unwrap();
+    }
+    out.push('\n');
+    out
+}
+
+#[test]
+fn instructions_match_golden_fixtures() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("golden/instructions.txt");
+    let rendered: Vec<String> = instructions()
+        .iter()
+        .map(|(name, ix)| render(name, ix))
+        .collect();
+    if std::env::var("GOLDEN_UPDATE").as_deref() == Ok("1") {
+        std::fs::write(&path, rendered.join("\n")).unwrap();
+        return;
+    }
+
+    let golden = std::fs::read_to_string(&path).unwrap_or_default();
+    let golden: Vec<&str> = golden.split("\n## ").collect();
+    assert_eq!(
+        golden.len(),
+        rendered.len(),
+        "instruction count changed, see golden.rs"
+    );
+    for (golden, rendered) in golden.iter().zip(&rendered) {
+        let name = rendered[3..].lines().next().unwrap();
+        assert_eq!(
+            golden.trim_start_matches("## ").trim_end(),
+            rendered[3..].trim_end(),
+            "{name} encodes differently than golden/instructions.txt"
+        );
+    }
+}
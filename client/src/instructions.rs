@@ -0,0 +1,1728 @@
+//! Instruction builders. Account lists come from the program's own
+//! `sentinel::accounts` structs, so they stay in step with it.
+
+use crate::encryption::{EncryptedPerpPosition, EncryptedPosition, EncryptedScoredPosition};
+use anchor_lang::{
+    prelude::*, solana_program::instruction::Instruction, InstructionData, ToAccountMetas,
+};
+use anchor_spl::{
+    associated_token::{get_associated_token_address_with_program_id, AssociatedToken},
+    token_2022::Token2022,
+};
+use arcium_anchor::prelude::*;
+use arcium_client::pda;
+use sentinel::{PositionMetadata, ProtectionTrigger, SizeBucket};
+use solana_message::Message;
+
+/// Where a computation is queued: the MXE's cluster and an offset that
+/// identifies the computation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Queue {
+    pub cluster_offset: u32,
+    pub computation_offset: u64,
+}
+
+impl Queue {
+    /// A queue on `cluster_offset` with a random computation offset.
+    pub fn new(cluster_offset: u32) -> Self {
+        Self {
+            cluster_offset,
+            computation_offset: rand::random(),
+        }
+    }
+}
+
+/// A position's address, derived from the wallet that registered it. It
+/// stays the same when the position is transferred, so pass
+/// `PositionAccount::registrant` rather than the current owner.
+pub fn position_pda(registrant: &Pubkey, position_id: u32) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"position", registrant.as_ref(), &position_id.to_le_bytes()],
+        &sentinel::ID,
+    )
+    .0
+}
+
+pub fn risk_history_pda(position: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"risk_history", position.as_ref()], &sentinel::ID).0
+}
+
+pub fn referral_stats_pda(referrer: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"referral", referrer.as_ref()], &sentinel::ID).0
+}
+
+pub fn reveal_inbox_pda(position: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"reveal_inbox", position.as_ref()], &sentinel::ID).0
+}
+
+pub fn attestation_pda(position: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"attestation", position.as_ref()], &sentinel::ID).0
+}
+
+pub fn auto_action_pda(position: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"auto_action", position.as_ref()], &sentinel::ID).0
+}
+
+pub fn stop_loss_pda(position: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"stop_loss", position.as_ref()], &sentinel::ID).0
+}
+
+pub fn action_memo_pda(position: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"action_memo", position.as_ref()], &sentinel::ID).0
+}
+
+pub fn check_bounty_pda(position: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"check_bounty", position.as_ref()], &sentinel::ID).0
+}
+
+pub fn subscription_pda(position: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"subscription", position.as_ref()], &sentinel::ID).0
+}
+
+pub fn receipt_registry_pda() -> Pubkey {
+    Pubkey::find_program_address(&[b"receipt_registry"], &sentinel::ID).0
+}
+
+/// The non-transferable mint of monitoring receipts.
+pub fn receipt_mint_pda() -> Pubkey {
+    Pubkey::find_program_address(&[b"receipt_mint"], &sentinel::ID).0
+}
+
+/// `owner`'s token account for monitoring receipts; its balance is the
+/// number of `owner`'s positions holding one.
+pub fn receipt_account(owner: &Pubkey) -> Pubkey {
+    get_associated_token_address_with_program_id(owner, &receipt_mint_pda(), &Token2022::id())
+}
+
+/// Page `page` of the index of the open positions `registrant` registered.
+pub fn owner_index_pda(registrant: &Pubkey, page: u32) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            b"owner_index",
+            registrant.as_ref(),
+            page.to_le_bytes().as_ref(),
+        ],
+        &sentinel::ID,
+    )
+    .0
+}
+
+/// Address of the `ArchiveRecord` a position leaves when archived, derived
+/// like the position's own.
+pub fn archive_pda(registrant: &Pubkey, position_id: u32) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"archive", registrant.as_ref(), &position_id.to_le_bytes()],
+        &sentinel::ID,
+    )
+    .0
+}
+
+pub fn protocol_adapter_pda(protocol_program: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"protocol_adapter", protocol_program.as_ref()],
+        &sentinel::ID,
+    )
+    .0
+}
+
+pub fn tvl_snapshot_pda(protocol_program: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"tvl_snapshot", protocol_program.as_ref()], &sentinel::ID).0
+}
+
+pub fn viewer_grant_pda(position: &Pubkey, viewer: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"viewer_grant", position.as_ref(), viewer.as_ref()],
+        &sentinel::ID,
+    )
+    .0
+}
+
+pub fn feed_migration_pda(price_feed: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"feed_migration", price_feed.as_ref()], &sentinel::ID).0
+}
+
+pub fn monitoring_plan_pda() -> Pubkey {
+    Pubkey::find_program_address(&[b"monitoring_plan"], &sentinel::ID).0
+}
+
+pub fn risk_model_config_pda() -> Pubkey {
+    Pubkey::find_program_address(&[b"risk_model_config"], &sentinel::ID).0
+}
+
+/// Where `compute_risk_score` keeps the position's encrypted score.
+pub fn risk_score_pda(position: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"risk_score", position.as_ref()], &sentinel::ID).0
+}
+
+pub fn admin_audit_log_pda() -> Pubkey {
+    Pubkey::find_program_address(&[b"admin_audit_log"], &sentinel::ID).0
+}
+
+pub fn global_config_pda() -> Pubkey {
+    Pubkey::find_program_address(&[b"global_config"], &sentinel::ID).0
+}
+
+pub fn sponsor_budget_pda(sponsor: &Pubkey, owner: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"sponsor_budget", sponsor.as_ref(), owner.as_ref()],
+        &sentinel::ID,
+    )
+    .0
+}
+
+pub fn keeper_lease_pda(keeper: &Pubkey, shard: u16) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"keeper_lease", keeper.as_ref(), &shard.to_le_bytes()],
+        &sentinel::ID,
+    )
+    .0
+}
+
+pub fn keeper_account_pda(keeper: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"keeper", keeper.as_ref()], &sentinel::ID).0
+}
+
+pub fn sign_pda() -> Pubkey {
+    Pubkey::find_program_address(&[SIGN_PDA_SEED], &sentinel::ID).0
+}
+
+pub fn comp_def_address(circuit: &str) -> Pubkey {
+    pda::computation_definition_acc(&sentinel::ID, comp_def_offset(circuit))
+}
+
+/// Registers `payer`'s position `position_id`. `nonce` seeds the encryption of
+/// its initial risk state. `label` is hashed as in [`crate::labels`]. The
+/// position is listed in page `index_page` of `payer`'s index; the page
+/// before it goes along, which has to exist if the page doesn't yet.
+#[allow(clippy::too_many_arguments)]
+pub fn register_position(
+    payer: &Pubkey,
+    position_id: u32,
+    queue: Queue,
+    nonce: u128,
+    referrer: Option<Pubkey>,
+    metadata: PositionMetadata,
+    label: Option<&str>,
+    index_page: u32,
+) -> Instruction {
+    let accounts = sentinel::accounts::RegisterPosition {
+        payer: *payer,
+        sign_pda_account: sign_pda(),
+        mxe_account: pda::mxe_acc(&sentinel::ID),
+        mempool_account: pda::mempool_acc(queue.cluster_offset),
+        executing_pool: pda::execpool_acc(queue.cluster_offset),
+        computation_account: pda::computation_acc(queue.cluster_offset, queue.computation_offset),
+        comp_def_account: comp_def_address("init_risk_state"),
+        cluster_account: pda::cluster_acc(queue.cluster_offset),
+        pool_account: ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+        clock_account: ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+        system_program: System::id(),
+        arcium_program: ARCIUM_PROG_ID,
+        position_acc: position_pda(payer, position_id),
+        global_config: global_config_pda(),
+        referral_stats: referrer.as_ref().map(referral_stats_pda),
+        owner_index: owner_index_pda(payer, index_page),
+        previous_owner_index: index_page
+            .checked_sub(1)
+            .map(|page| owner_index_pda(payer, page)),
+    };
+    let data = sentinel::instruction::RegisterPosition {
+        computation_offset: queue.computation_offset,
+        position_id,
+        nonce,
+        referrer,
+        metadata,
+        label_hash: label.map_or([0; 32], |label| crate::labels::label_hash(payer, label)),
+        index_page,
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Checks `owner`'s position against `position`, encrypted under
+/// `encryption_pubkey`. The position is addressed by its `registrant`, which
+/// is `owner` unless it was transferred. `payer` may be the owner or its
+/// keeper; a keeper is paid from the position's subscription. With
+/// `record_history`, the result is also appended to the position's risk
+/// history, which must exist. `price_feed` is the position's Pyth feed and
+/// `tvl_protocol` the protocol whose TVL it follows, if it has them, so the
+/// circuit can detect price drops and TVL exoduses. With `check_bounty`, a
+/// keeper is also paid the position's check bounty, which must exist, once
+/// the check lands. With `keeper_account`, `payer`'s registry account goes
+/// along, which a keeper needs on positions restricted to registered
+/// keepers.
+#[allow(clippy::too_many_arguments)]
+pub fn check_health(
+    payer: &Pubkey,
+    owner: &Pubkey,
+    registrant: &Pubkey,
+    position_id: u32,
+    queue: Queue,
+    encryption_pubkey: [u8; 32],
+    position: &EncryptedPosition,
+    record_history: bool,
+    price_feed: Option<Pubkey>,
+    tvl_protocol: Option<Pubkey>,
+    check_bounty: bool,
+    keeper_account: bool,
+) -> Instruction {
+    let position_acc = position_pda(registrant, position_id);
+    let keeper_check = payer != owner;
+    let accounts = sentinel::accounts::CheckHealth {
+        payer: *payer,
+        sign_pda_account: sign_pda(),
+        mxe_account: pda::mxe_acc(&sentinel::ID),
+        mempool_account: pda::mempool_acc(queue.cluster_offset),
+        executing_pool: pda::execpool_acc(queue.cluster_offset),
+        computation_account: pda::computation_acc(queue.cluster_offset, queue.computation_offset),
+        comp_def_account: comp_def_address("check_position_health"),
+        cluster_account: pda::cluster_acc(queue.cluster_offset),
+        pool_account: ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+        clock_account: ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+        system_program: System::id(),
+        arcium_program: ARCIUM_PROG_ID,
+        owner: *owner,
+        position_acc,
+        global_config: global_config_pda(),
+        risk_history: record_history.then(|| risk_history_pda(&position_acc)),
+        monitoring_plan: keeper_check.then(monitoring_plan_pda),
+        subscription: keeper_check.then(|| subscription_pda(&position_acc)),
+        price_update: price_feed,
+        tvl_snapshot: tvl_protocol.as_ref().map(tvl_snapshot_pda),
+        check_bounty: check_bounty.then(|| check_bounty_pda(&position_acc)),
+        keeper_account: keeper_account.then(|| keeper_account_pda(payer)),
+    };
+    let data = sentinel::instruction::CheckHealth {
+        computation_offset: queue.computation_offset,
+        _position_id: position_id,
+        encrypted_position: position.ciphertexts,
+        encryption_pubkey,
+        encryption_nonce: position.nonce,
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Checks `owner`'s perpetual futures position ([`crate::PerpPosition`]
+/// encrypted under `encryption_pubkey`) at the mark price of its Pyth feed,
+/// `price_feed`. `payer` is the owner or its keeper; `registrant` is as for
+/// [`check_health`].
+#[allow(clippy::too_many_arguments)]
+pub fn check_perp_health(
+    payer: &Pubkey,
+    owner: &Pubkey,
+    registrant: &Pubkey,
+    position_id: u32,
+    queue: Queue,
+    encryption_pubkey: [u8; 32],
+    position: &EncryptedPerpPosition,
+    price_feed: Pubkey,
+) -> Instruction {
+    let accounts = sentinel::accounts::CheckPerpHealth {
+        payer: *payer,
+        sign_pda_account: sign_pda(),
+        mxe_account: pda::mxe_acc(&sentinel::ID),
+        mempool_account: pda::mempool_acc(queue.cluster_offset),
+        executing_pool: pda::execpool_acc(queue.cluster_offset),
+        computation_account: pda::computation_acc(queue.cluster_offset, queue.computation_offset),
+        comp_def_account: comp_def_address("check_perp_health"),
+        cluster_account: pda::cluster_acc(queue.cluster_offset),
+        pool_account: ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+        clock_account: ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+        system_program: System::id(),
+        arcium_program: ARCIUM_PROG_ID,
+        owner: *owner,
+        position_acc: position_pda(registrant, position_id),
+        global_config: global_config_pda(),
+        price_update: price_feed,
+    };
+    let data = sentinel::instruction::CheckPerpHealth {
+        computation_offset: queue.computation_offset,
+        _position_id: position_id,
+        encrypted_position: position.ciphertexts,
+        encryption_pubkey,
+        encryption_nonce: position.nonce,
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Scores `owner`'s position ([`crate::ScoredPosition`] encrypted under
+/// `encryption_pubkey`) from 0 to 100 and grades its risk state by the
+/// score. The score is stored encrypted to the MXE under `nonce`.
+/// `price_feed` and `tvl_protocol` feed the volatility and TVL trend
+/// signals, which score 0 without them; `payer` is the owner or its keeper,
+/// which is paid from the position's subscription as for [`check_health`],
+/// and `registrant` is as for [`check_health`].
+#[allow(clippy::too_many_arguments)]
+pub fn compute_risk_score(
+    payer: &Pubkey,
+    owner: &Pubkey,
+    registrant: &Pubkey,
+    position_id: u32,
+    queue: Queue,
+    encryption_pubkey: [u8; 32],
+    position: &EncryptedScoredPosition,
+    nonce: u128,
+    price_feed: Option<Pubkey>,
+    tvl_protocol: Option<Pubkey>,
+) -> Instruction {
+    let position_acc = position_pda(registrant, position_id);
+    let keeper_check = payer != owner;
+    let accounts = sentinel::accounts::ComputeRiskScore {
+        payer: *payer,
+        sign_pda_account: sign_pda(),
+        mxe_account: pda::mxe_acc(&sentinel::ID),
+        mempool_account: pda::mempool_acc(queue.cluster_offset),
+        executing_pool: pda::execpool_acc(queue.cluster_offset),
+        computation_account: pda::computation_acc(queue.cluster_offset, queue.computation_offset),
+        comp_def_account: comp_def_address("compute_risk_score"),
+        cluster_account: pda::cluster_acc(queue.cluster_offset),
+        pool_account: ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+        clock_account: ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+        system_program: System::id(),
+        arcium_program: ARCIUM_PROG_ID,
+        owner: *owner,
+        position_acc,
+        global_config: global_config_pda(),
+        risk_model_config: risk_model_config_pda(),
+        price_update: price_feed,
+        tvl_snapshot: tvl_protocol.as_ref().map(tvl_snapshot_pda),
+        risk_score: risk_score_pda(&position_acc),
+        monitoring_plan: keeper_check.then(monitoring_plan_pda),
+        subscription: keeper_check.then(|| subscription_pda(&position_acc)),
+    };
+    let data = sentinel::instruction::ComputeRiskScore {
+        computation_offset: queue.computation_offset,
+        _position_id: position_id,
+        encrypted_position: position.ciphertexts,
+        encryption_pubkey,
+        encryption_nonce: position.nonce,
+        nonce,
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Checks `owner`'s position against `obligation`, an account of
+/// `protocol_program` that has a registered adapter. Refresh the obligation
+/// earlier in the same transaction. `registrant` is as for [`check_health`].
+pub fn check_health_from_protocol(
+    payer: &Pubkey,
+    owner: &Pubkey,
+    registrant: &Pubkey,
+    position_id: u32,
+    queue: Queue,
+    obligation: Pubkey,
+    protocol_program: &Pubkey,
+) -> Instruction {
+    let accounts = sentinel::accounts::CheckHealthFromProtocol {
+        payer: *payer,
+        sign_pda_account: sign_pda(),
+        mxe_account: pda::mxe_acc(&sentinel::ID),
+        mempool_account: pda::mempool_acc(queue.cluster_offset),
+        executing_pool: pda::execpool_acc(queue.cluster_offset),
+        computation_account: pda::computation_acc(queue.cluster_offset, queue.computation_offset),
+        comp_def_account: comp_def_address("check_position_health_from_protocol"),
+        cluster_account: pda::cluster_acc(queue.cluster_offset),
+        pool_account: ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+        clock_account: ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+        system_program: System::id(),
+        arcium_program: ARCIUM_PROG_ID,
+        owner: *owner,
+        position_acc: position_pda(registrant, position_id),
+        global_config: global_config_pda(),
+        obligation,
+        protocol_adapter: protocol_adapter_pda(protocol_program),
+    };
+    let data = sentinel::instruction::CheckHealthFromProtocol {
+        computation_offset: queue.computation_offset,
+        _position_id: position_id,
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Checks up to `sentinel::MAX_BATCH_POSITIONS` of `owner`'s positions in one
+/// computation. Each entry is a position address ([`position_pda`]) and its
+/// data encrypted under `encryption_pubkey`, every one with its own nonce.
+pub fn check_health_batch(
+    owner: &Pubkey,
+    queue: Queue,
+    encryption_pubkey: [u8; 32],
+    positions: &[(Pubkey, EncryptedPosition)],
+) -> Instruction {
+    let mut accounts = sentinel::accounts::CheckHealthBatch {
+        payer: *owner,
+        sign_pda_account: sign_pda(),
+        mxe_account: pda::mxe_acc(&sentinel::ID),
+        mempool_account: pda::mempool_acc(queue.cluster_offset),
+        executing_pool: pda::execpool_acc(queue.cluster_offset),
+        computation_account: pda::computation_acc(queue.cluster_offset, queue.computation_offset),
+        comp_def_account: comp_def_address("check_position_health_batch"),
+        cluster_account: pda::cluster_acc(queue.cluster_offset),
+        pool_account: ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+        clock_account: ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+        system_program: System::id(),
+        arcium_program: ARCIUM_PROG_ID,
+        global_config: global_config_pda(),
+    }
+    .to_account_metas(None);
+    accounts.extend(
+        positions
+            .iter()
+            .map(|(address, _)| AccountMeta::new(*address, false)),
+    );
+    let data = sentinel::instruction::CheckHealthBatch {
+        computation_offset: queue.computation_offset,
+        encryption_pubkey,
+        positions: positions
+            .iter()
+            .map(|(_, position)| sentinel::EncryptedPositionInput {
+                ciphertexts: position.ciphertexts,
+                nonce: position.nonce,
+            })
+            .collect(),
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts)
+}
+
+/// Publicly reveals whether `payer`'s position is at risk. `registrant` is
+/// as for [`check_health`]. Pass `auto_action` if the position has an auto
+/// action ([`auto_action_pda`]) so a critical result runs it.
+pub fn reveal_risk(
+    payer: &Pubkey,
+    registrant: &Pubkey,
+    position_id: u32,
+    queue: Queue,
+    auto_action: bool,
+) -> Instruction {
+    let position_acc = position_pda(registrant, position_id);
+    let accounts = sentinel::accounts::RevealRisk {
+        payer: *payer,
+        sign_pda_account: sign_pda(),
+        mxe_account: pda::mxe_acc(&sentinel::ID),
+        mempool_account: pda::mempool_acc(queue.cluster_offset),
+        executing_pool: pda::execpool_acc(queue.cluster_offset),
+        computation_account: pda::computation_acc(queue.cluster_offset, queue.computation_offset),
+        comp_def_account: comp_def_address("reveal_risk"),
+        cluster_account: pda::cluster_acc(queue.cluster_offset),
+        pool_account: ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+        clock_account: ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+        system_program: System::id(),
+        arcium_program: ARCIUM_PROG_ID,
+        position_acc,
+        auto_action: auto_action.then(|| auto_action_pda(&position_acc)),
+        global_config: global_config_pda(),
+    };
+    let data = sentinel::instruction::RevealRisk {
+        computation_offset: queue.computation_offset,
+        position_id,
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Re-encrypts `payer`'s risk state to `encryption_pubkey`. Once delivered,
+/// read it from the position's inbox ([`reveal_inbox_pda`]) with
+/// `RiskState::from_inbox`. `registrant` is as for [`check_health`]. With
+/// `action_memo`, the position's memo is attached to the inbox; see
+/// [`crate::memo::critical_plan`].
+pub fn reveal_risk_to_owner(
+    payer: &Pubkey,
+    registrant: &Pubkey,
+    position_id: u32,
+    queue: Queue,
+    encryption_pubkey: [u8; 32],
+    encryption_nonce: u128,
+    action_memo: bool,
+) -> Instruction {
+    let position_acc = position_pda(registrant, position_id);
+    let accounts = sentinel::accounts::RevealRiskToOwner {
+        payer: *payer,
+        sign_pda_account: sign_pda(),
+        mxe_account: pda::mxe_acc(&sentinel::ID),
+        mempool_account: pda::mempool_acc(queue.cluster_offset),
+        executing_pool: pda::execpool_acc(queue.cluster_offset),
+        computation_account: pda::computation_acc(queue.cluster_offset, queue.computation_offset),
+        comp_def_account: comp_def_address("reveal_risk_to_owner"),
+        cluster_account: pda::cluster_acc(queue.cluster_offset),
+        pool_account: ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+        clock_account: ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+        system_program: System::id(),
+        arcium_program: ARCIUM_PROG_ID,
+        position_acc,
+        global_config: global_config_pda(),
+        reveal_inbox: reveal_inbox_pda(&position_acc),
+        action_memo: action_memo.then(|| action_memo_pda(&position_acc)),
+    };
+    let data = sentinel::instruction::RevealRiskToOwner {
+        computation_offset: queue.computation_offset,
+        position_id,
+        encryption_pubkey,
+        encryption_nonce,
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Computes the health factor of the position registered by `registrant`
+/// from `values` ([`crate::PositionValues`] encrypted under
+/// `encryption_pubkey`). `payer` is the owner or its keeper; `nonce` is the
+/// MXE nonce the stored factor is encrypted under.
+/// Lets `viewer` have the position's risk state revealed to
+/// `encryption_key`, e.g. an auditor's x25519 key.
+pub fn grant_viewer(
+    owner: &Pubkey,
+    registrant: &Pubkey,
+    position_id: u32,
+    viewer: Pubkey,
+    encryption_key: [u8; 32],
+) -> Instruction {
+    let position_acc = position_pda(registrant, position_id);
+    let accounts = sentinel::accounts::GrantViewer {
+        owner: *owner,
+        position_acc,
+        viewer_grant: viewer_grant_pda(&position_acc, &viewer),
+        system_program: System::id(),
+    };
+    let data = sentinel::instruction::GrantViewer {
+        position_id,
+        viewer,
+        encryption_key,
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+pub fn revoke_viewer(
+    owner: &Pubkey,
+    registrant: &Pubkey,
+    position_id: u32,
+    viewer: Pubkey,
+) -> Instruction {
+    let position_acc = position_pda(registrant, position_id);
+    let accounts = sentinel::accounts::RevokeViewer {
+        owner: *owner,
+        position_acc,
+        viewer_grant: viewer_grant_pda(&position_acc, &viewer),
+    };
+    let data = sentinel::instruction::RevokeViewer {
+        position_id,
+        viewer,
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Re-encrypts the risk state to the key granted to `viewer`; `payer` is the
+/// viewer or the owner. Once delivered, the viewer reads it from the grant
+/// with `RiskState::from_viewer_grant`.
+pub fn reveal_risk_to_viewer(
+    payer: &Pubkey,
+    registrant: &Pubkey,
+    position_id: u32,
+    viewer: &Pubkey,
+    queue: Queue,
+    encryption_nonce: u128,
+) -> Instruction {
+    let position_acc = position_pda(registrant, position_id);
+    let accounts = sentinel::accounts::RevealRiskToViewer {
+        payer: *payer,
+        sign_pda_account: sign_pda(),
+        mxe_account: pda::mxe_acc(&sentinel::ID),
+        mempool_account: pda::mempool_acc(queue.cluster_offset),
+        executing_pool: pda::execpool_acc(queue.cluster_offset),
+        computation_account: pda::computation_acc(queue.cluster_offset, queue.computation_offset),
+        comp_def_account: comp_def_address("reveal_risk_to_viewer"),
+        cluster_account: pda::cluster_acc(queue.cluster_offset),
+        pool_account: ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+        clock_account: ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+        system_program: System::id(),
+        arcium_program: ARCIUM_PROG_ID,
+        position_acc,
+        global_config: global_config_pda(),
+        viewer_grant: viewer_grant_pda(&position_acc, viewer),
+    };
+    let data = sentinel::instruction::RevealRiskToViewer {
+        computation_offset: queue.computation_offset,
+        position_id,
+        encryption_nonce,
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+pub fn compute_health_factor(
+    payer: &Pubkey,
+    registrant: &Pubkey,
+    position_id: u32,
+    queue: Queue,
+    encryption_pubkey: [u8; 32],
+    values: &EncryptedPosition,
+    nonce: u128,
+) -> Instruction {
+    let accounts = sentinel::accounts::ComputeHealthFactor {
+        payer: *payer,
+        sign_pda_account: sign_pda(),
+        mxe_account: pda::mxe_acc(&sentinel::ID),
+        mempool_account: pda::mempool_acc(queue.cluster_offset),
+        executing_pool: pda::execpool_acc(queue.cluster_offset),
+        computation_account: pda::computation_acc(queue.cluster_offset, queue.computation_offset),
+        comp_def_account: comp_def_address("compute_health_factor"),
+        cluster_account: pda::cluster_acc(queue.cluster_offset),
+        pool_account: ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+        clock_account: ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+        system_program: System::id(),
+        arcium_program: ARCIUM_PROG_ID,
+        position_acc: position_pda(registrant, position_id),
+        global_config: global_config_pda(),
+    };
+    let data = sentinel::instruction::ComputeHealthFactor {
+        computation_offset: queue.computation_offset,
+        position_id,
+        encrypted_values: values.ciphertexts,
+        encryption_pubkey,
+        encryption_nonce: values.nonce,
+        nonce,
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Re-encrypts `payer`'s stored health factor to `encryption_pubkey`. Once
+/// delivered, read it from the position's inbox with
+/// `HealthFactor::from_inbox`.
+pub fn reveal_health_factor_to_owner(
+    payer: &Pubkey,
+    registrant: &Pubkey,
+    position_id: u32,
+    queue: Queue,
+    encryption_pubkey: [u8; 32],
+    encryption_nonce: u128,
+) -> Instruction {
+    let position_acc = position_pda(registrant, position_id);
+    let accounts = sentinel::accounts::RevealHealthFactorToOwner {
+        payer: *payer,
+        sign_pda_account: sign_pda(),
+        mxe_account: pda::mxe_acc(&sentinel::ID),
+        mempool_account: pda::mempool_acc(queue.cluster_offset),
+        executing_pool: pda::execpool_acc(queue.cluster_offset),
+        computation_account: pda::computation_acc(queue.cluster_offset, queue.computation_offset),
+        comp_def_account: comp_def_address("reveal_health_factor_to_owner"),
+        cluster_account: pda::cluster_acc(queue.cluster_offset),
+        pool_account: ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+        clock_account: ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+        system_program: System::id(),
+        arcium_program: ARCIUM_PROG_ID,
+        position_acc,
+        global_config: global_config_pda(),
+        reveal_inbox: reveal_inbox_pda(&position_acc),
+    };
+    let data = sentinel::instruction::RevealHealthFactorToOwner {
+        computation_offset: queue.computation_offset,
+        position_id,
+        encryption_pubkey,
+        encryption_nonce,
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Asks whether the position would be liquidatable if its collateral lost
+/// `shock_bps` of its value. `values` must be encrypted with the key behind
+/// `encryption_pubkey`; once delivered, read the answer from the position's
+/// inbox with `StressResult::from_inbox`.
+pub fn stress_test_position(
+    payer: &Pubkey,
+    registrant: &Pubkey,
+    position_id: u32,
+    queue: Queue,
+    encryption_pubkey: [u8; 32],
+    values: &EncryptedPosition,
+    shock_bps: u16,
+) -> Instruction {
+    let position_acc = position_pda(registrant, position_id);
+    let accounts = sentinel::accounts::StressTestPosition {
+        payer: *payer,
+        sign_pda_account: sign_pda(),
+        mxe_account: pda::mxe_acc(&sentinel::ID),
+        mempool_account: pda::mempool_acc(queue.cluster_offset),
+        executing_pool: pda::execpool_acc(queue.cluster_offset),
+        computation_account: pda::computation_acc(queue.cluster_offset, queue.computation_offset),
+        comp_def_account: comp_def_address("stress_test_position"),
+        cluster_account: pda::cluster_acc(queue.cluster_offset),
+        pool_account: ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+        clock_account: ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+        system_program: System::id(),
+        arcium_program: ARCIUM_PROG_ID,
+        position_acc,
+        global_config: global_config_pda(),
+        reveal_inbox: reveal_inbox_pda(&position_acc),
+    };
+    let data = sentinel::instruction::StressTestPosition {
+        computation_offset: queue.computation_offset,
+        position_id,
+        encrypted_values: values.ciphertexts,
+        encryption_pubkey,
+        encryption_nonce: values.nonce,
+        shock_bps,
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Attests that the position's stored health factor is at least
+/// `min_health_factor_bps`, e.g. 12_000 for 1.2. Counterparties read the
+/// result from [`attestation_pda`] with `AttestationAccount::attests`.
+pub fn attest_health(
+    payer: &Pubkey,
+    registrant: &Pubkey,
+    position_id: u32,
+    queue: Queue,
+    min_health_factor_bps: u32,
+) -> Instruction {
+    let position_acc = position_pda(registrant, position_id);
+    let accounts = sentinel::accounts::AttestHealth {
+        payer: *payer,
+        sign_pda_account: sign_pda(),
+        mxe_account: pda::mxe_acc(&sentinel::ID),
+        mempool_account: pda::mempool_acc(queue.cluster_offset),
+        executing_pool: pda::execpool_acc(queue.cluster_offset),
+        computation_account: pda::computation_acc(queue.cluster_offset, queue.computation_offset),
+        comp_def_account: comp_def_address("attest_health"),
+        cluster_account: pda::cluster_acc(queue.cluster_offset),
+        pool_account: ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+        clock_account: ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+        system_program: System::id(),
+        arcium_program: ARCIUM_PROG_ID,
+        position_acc,
+        global_config: global_config_pda(),
+        attestation: attestation_pda(&position_acc),
+    };
+    let data = sentinel::instruction::AttestHealth {
+        computation_offset: queue.computation_offset,
+        position_id,
+        min_health_factor_bps,
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Tags the position with `label`, hashed by [`crate::labels::label_hash`]
+/// for `owner`; `None` clears it.
+pub fn set_label(
+    owner: &Pubkey,
+    registrant: &Pubkey,
+    position_id: u32,
+    label: Option<&str>,
+) -> Instruction {
+    let accounts = sentinel::accounts::SetLabel {
+        owner: *owner,
+        position_acc: position_pda(registrant, position_id),
+    };
+    let data = sentinel::instruction::SetLabel {
+        position_id,
+        label_hash: label.map_or([0; 32], |label| crate::labels::label_hash(owner, label)),
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Re-encrypts `payer`'s stored risk state under `nonce`, which has to be
+/// newer than the position's, e.g. after the owner's x25519 key leaked.
+pub fn rotate_encryption(
+    payer: &Pubkey,
+    registrant: &Pubkey,
+    position_id: u32,
+    queue: Queue,
+    nonce: u128,
+) -> Instruction {
+    let accounts = sentinel::accounts::RotateEncryption {
+        payer: *payer,
+        sign_pda_account: sign_pda(),
+        mxe_account: pda::mxe_acc(&sentinel::ID),
+        mempool_account: pda::mempool_acc(queue.cluster_offset),
+        executing_pool: pda::execpool_acc(queue.cluster_offset),
+        computation_account: pda::computation_acc(queue.cluster_offset, queue.computation_offset),
+        comp_def_account: comp_def_address("rotate_encryption"),
+        cluster_account: pda::cluster_acc(queue.cluster_offset),
+        pool_account: ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+        clock_account: ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+        system_program: System::id(),
+        arcium_program: ARCIUM_PROG_ID,
+        position_acc: position_pda(registrant, position_id),
+        global_config: global_config_pda(),
+    };
+    let data = sentinel::instruction::RotateEncryption {
+        computation_offset: queue.computation_offset,
+        position_id,
+        nonce,
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Only reveals of `min_action_severity` (1 = low .. 3 = critical) or above
+/// emit `ActionRequired` for the position.
+pub fn configure_alerts(
+    owner: &Pubkey,
+    registrant: &Pubkey,
+    position_id: u32,
+    min_action_severity: u8,
+) -> Instruction {
+    let accounts = sentinel::accounts::ConfigureAlerts {
+        owner: *owner,
+        position_acc: position_pda(registrant, position_id),
+    };
+    let data = sentinel::instruction::ConfigureAlerts {
+        position_id,
+        min_action_severity,
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Sets the least seconds between two health checks of the position; 0
+/// removes the limit.
+pub fn set_min_check_interval(
+    owner: &Pubkey,
+    registrant: &Pubkey,
+    position_id: u32,
+    min_check_interval_secs: u32,
+) -> Instruction {
+    let accounts = sentinel::accounts::SetMinCheckInterval {
+        owner: *owner,
+        position_acc: position_pda(registrant, position_id),
+    };
+    let data = sentinel::instruction::SetMinCheckInterval {
+        position_id,
+        min_check_interval_secs,
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Sets how many seconds the position may go without a check before anyone
+/// may flag its monitoring as lapsed; 0 removes the deadline.
+pub fn set_heartbeat_deadline(
+    owner: &Pubkey,
+    registrant: &Pubkey,
+    position_id: u32,
+    heartbeat_deadline_secs: u32,
+) -> Instruction {
+    let accounts = sentinel::accounts::SetHeartbeatDeadline {
+        owner: *owner,
+        position_acc: position_pda(registrant, position_id),
+    };
+    let data = sentinel::instruction::SetHeartbeatDeadline {
+        position_id,
+        heartbeat_deadline_secs,
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Records that `signer`, the owner or its keeper, is monitoring the
+/// position though it skipped checking it.
+pub fn heartbeat(signer: &Pubkey, registrant: &Pubkey, position_id: u32) -> Instruction {
+    let accounts = sentinel::accounts::Heartbeat {
+        signer: *signer,
+        position_acc: position_pda(registrant, position_id),
+    };
+    let data = sentinel::instruction::Heartbeat { position_id };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Flags the position's monitoring as lapsed after its heartbeat deadline
+/// passed without a check or heartbeat. With `check_bounty`, `caller` is paid a bounty
+/// from the position's escrow, which must exist.
+pub fn flag_stale_position(
+    caller: &Pubkey,
+    registrant: &Pubkey,
+    position_id: u32,
+    check_bounty: bool,
+) -> Instruction {
+    let position_acc = position_pda(registrant, position_id);
+    let accounts = sentinel::accounts::FlagStalePosition {
+        caller: *caller,
+        position_acc,
+        check_bounty: check_bounty.then(|| check_bounty_pda(&position_acc)),
+    };
+    let data = sentinel::instruction::FlagStalePosition { position_id };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Asks whether the position is `owner`'s, active and checked within
+/// `max_check_age_secs`; the answer comes back as the transaction's return
+/// data, so simulating it is enough.
+pub fn is_monitored(
+    registrant: &Pubkey,
+    position_id: u32,
+    owner: Pubkey,
+    max_check_age_secs: u32,
+) -> Instruction {
+    let accounts = sentinel::accounts::IsMonitored {
+        position_acc: position_pda(registrant, position_id),
+    };
+    let data = sentinel::instruction::IsMonitored {
+        _position_id: position_id,
+        owner,
+        max_check_age_secs,
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Limits how old, in seconds since its last check, the position's risk
+/// state may be when revealed; `None` follows the program default.
+pub fn set_max_staleness(
+    owner: &Pubkey,
+    registrant: &Pubkey,
+    position_id: u32,
+    max_staleness_secs: Option<i64>,
+) -> Instruction {
+    let accounts = sentinel::accounts::SetMaxStaleness {
+        owner: *owner,
+        position_acc: position_pda(registrant, position_id),
+    };
+    let data = sentinel::instruction::SetMaxStaleness {
+        position_id,
+        max_staleness_secs,
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Has the position's checks follow `protocol_program`'s TVL snapshots,
+/// flagging a drop of more than `tvl_drop_bps`; `None` stops following.
+pub fn set_tvl_protocol(
+    owner: &Pubkey,
+    registrant: &Pubkey,
+    position_id: u32,
+    protocol_program: Option<Pubkey>,
+    tvl_drop_bps: u16,
+) -> Instruction {
+    let accounts = sentinel::accounts::SetTvlProtocol {
+        owner: *owner,
+        position_acc: position_pda(registrant, position_id),
+    };
+    let data = sentinel::instruction::SetTvlProtocol {
+        position_id,
+        protocol_program,
+        tvl_drop_bps,
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Pins the position to risk model `config_version`, the latest the
+/// operator published.
+pub fn accept_config_version(
+    owner: &Pubkey,
+    registrant: &Pubkey,
+    position_id: u32,
+    config_version: u32,
+) -> Instruction {
+    let accounts = sentinel::accounts::AcceptConfigVersion {
+        owner: *owner,
+        position_acc: position_pda(registrant, position_id),
+        global_config: global_config_pda(),
+    };
+    let data = sentinel::instruction::AcceptConfigVersion {
+        position_id,
+        config_version,
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Posts `tvl`, in USD cents, as `protocol_program`'s latest snapshot.
+/// `updater` is the signer the upgrade authority named for the protocol.
+pub fn update_tvl_snapshot(updater: &Pubkey, protocol_program: Pubkey, tvl: u64) -> Instruction {
+    let accounts = sentinel::accounts::UpdateTvlSnapshot {
+        updater: *updater,
+        tvl_snapshot: tvl_snapshot_pda(&protocol_program),
+    };
+    let data = sentinel::instruction::UpdateTvlSnapshot {
+        protocol_program,
+        tvl,
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Flags `price_feed` as no longer publishing, for the upgrade authority to
+/// name a replacement.
+pub fn flag_price_feed(flagger: &Pubkey, price_feed: Pubkey) -> Instruction {
+    let accounts = sentinel::accounts::FlagPriceFeed {
+        flagger: *flagger,
+        price_feed,
+        feed_migration: feed_migration_pda(&price_feed),
+        system_program: System::id(),
+    };
+    let data = sentinel::instruction::FlagPriceFeed {};
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Moves `positions`, all on the migrated `old_feed`, to its replacement.
+pub fn repoint_price_feeds(old_feed: &Pubkey, positions: &[Pubkey]) -> Instruction {
+    let mut accounts = sentinel::accounts::RepointPriceFeeds {
+        feed_migration: feed_migration_pda(old_feed),
+    }
+    .to_account_metas(None);
+    accounts.extend(
+        positions
+            .iter()
+            .map(|position| AccountMeta::new(*position, false)),
+    );
+    let data = sentinel::instruction::RepointPriceFeeds {};
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts)
+}
+
+/// Escrows `lamports` from `funder` for keeper fees on `position`.
+pub fn fund_subscription(funder: &Pubkey, position: Pubkey, lamports: u64) -> Instruction {
+    let accounts = sentinel::accounts::FundSubscription {
+        funder: *funder,
+        position_acc: position,
+        subscription: subscription_pda(&position),
+        system_program: System::id(),
+    };
+    let data = sentinel::instruction::FundSubscription { lamports };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Returns `lamports` of the position's subscription to `owner`.
+pub fn withdraw_subscription(
+    owner: &Pubkey,
+    registrant: &Pubkey,
+    position_id: u32,
+    lamports: u64,
+) -> Instruction {
+    let position_acc = position_pda(registrant, position_id);
+    let accounts = sentinel::accounts::WithdrawSubscription {
+        owner: *owner,
+        position_acc,
+        subscription: subscription_pda(&position_acc),
+    };
+    let data = sentinel::instruction::WithdrawSubscription {
+        position_id,
+        lamports,
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Sets the bounty a keeper earns per landed check of the position and
+/// escrows `lamports` more for it.
+pub fn fund_check_bounty(
+    owner: &Pubkey,
+    registrant: &Pubkey,
+    position_id: u32,
+    check_bounty_lamports: u64,
+    lamports: u64,
+) -> Instruction {
+    let position_acc = position_pda(registrant, position_id);
+    let accounts = sentinel::accounts::FundCheckBounty {
+        owner: *owner,
+        position_acc,
+        check_bounty: check_bounty_pda(&position_acc),
+        system_program: System::id(),
+    };
+    let data = sentinel::instruction::FundCheckBounty {
+        position_id,
+        check_bounty_lamports,
+        lamports,
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Closes the position's bounty escrow, refunding its balance to `owner`.
+pub fn close_check_bounty(owner: &Pubkey, registrant: &Pubkey, position_id: u32) -> Instruction {
+    let position_acc = position_pda(registrant, position_id);
+    let accounts = sentinel::accounts::CloseCheckBounty {
+        owner: *owner,
+        position_acc,
+        check_bounty: check_bounty_pda(&position_acc),
+    };
+    let data = sentinel::instruction::CloseCheckBounty { position_id };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Closes the position and refunds its rent to `owner`. With
+/// `subscription` and `check_bounty`, the position's subscription and bounty
+/// escrow, which must exist, are emptied into `owner` as well. With
+/// `owner_index`, the position's `PositionAccount::index_page` and required
+/// if `PositionAccount::indexed`, the position is removed from its
+/// registrant's index.
+pub fn close_position(
+    owner: &Pubkey,
+    registrant: &Pubkey,
+    position_id: u32,
+    subscription: bool,
+    check_bounty: bool,
+    owner_index: Option<u32>,
+) -> Instruction {
+    let position_acc = position_pda(registrant, position_id);
+    let accounts = sentinel::accounts::ClosePosition {
+        owner: *owner,
+        position_acc,
+        subscription: subscription.then(|| subscription_pda(&position_acc)),
+        check_bounty: check_bounty.then(|| check_bounty_pda(&position_acc)),
+        owner_index: owner_index.map(|page| owner_index_pda(registrant, page)),
+    };
+    let data = sentinel::instruction::ClosePosition { position_id };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Cancels the position's pending computation, queued on `queue`, once the
+/// cluster let it expire. `signer` is the owner or its keeper. With
+/// `subscription`, the position's subscription, which must exist, gives back
+/// the fee held for a keeper check.
+pub fn cancel_computation(
+    signer: &Pubkey,
+    registrant: &Pubkey,
+    position_id: u32,
+    queue: Queue,
+    subscription: bool,
+) -> Instruction {
+    let position_acc = position_pda(registrant, position_id);
+    let accounts = sentinel::accounts::CancelComputation {
+        signer: *signer,
+        position_acc,
+        mxe_account: pda::mxe_acc(&sentinel::ID),
+        computation_account: pda::computation_acc(queue.cluster_offset, queue.computation_offset),
+        subscription: subscription.then(|| subscription_pda(&position_acc)),
+    };
+    let data = sentinel::instruction::CancelComputation { position_id };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Mints `owner` a monitoring receipt for the active position.
+pub fn mint_monitoring_receipt(
+    owner: &Pubkey,
+    registrant: &Pubkey,
+    position_id: u32,
+) -> Instruction {
+    let accounts = sentinel::accounts::MintMonitoringReceipt {
+        owner: *owner,
+        position_acc: position_pda(registrant, position_id),
+        receipt_registry: receipt_registry_pda(),
+        receipt_mint: receipt_mint_pda(),
+        receipt_account: receipt_account(owner),
+        token_program: Token2022::id(),
+        associated_token_program: AssociatedToken::id(),
+        system_program: System::id(),
+    };
+    let data = sentinel::instruction::MintMonitoringReceipt { position_id };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Burns the position's monitoring receipt, which has to happen before
+/// the position is deactivated, closed or handed over.
+pub fn burn_monitoring_receipt(
+    owner: &Pubkey,
+    registrant: &Pubkey,
+    position_id: u32,
+) -> Instruction {
+    let accounts = sentinel::accounts::BurnMonitoringReceipt {
+        owner: *owner,
+        position_acc: position_pda(registrant, position_id),
+        receipt_registry: receipt_registry_pda(),
+        receipt_mint: receipt_mint_pda(),
+        receipt_account: receipt_account(owner),
+        token_program: Token2022::id(),
+    };
+    let data = sentinel::instruction::BurnMonitoringReceipt { position_id };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Archives a deactivated position, closing it along with the accounts
+/// flagged and keeping an `ArchiveRecord` of them. `owner_index` is as for
+/// [`close_position`].
+pub fn archive_position(
+    owner: &Pubkey,
+    registrant: &Pubkey,
+    position_id: u32,
+    risk_history: bool,
+    subscription: bool,
+    check_bounty: bool,
+    owner_index: Option<u32>,
+) -> Instruction {
+    let position_acc = position_pda(registrant, position_id);
+    let accounts = sentinel::accounts::ArchivePosition {
+        owner: *owner,
+        position_acc,
+        risk_history: risk_history.then(|| risk_history_pda(&position_acc)),
+        subscription: subscription.then(|| subscription_pda(&position_acc)),
+        check_bounty: check_bounty.then(|| check_bounty_pda(&position_acc)),
+        archive: archive_pda(registrant, position_id),
+        owner_index: owner_index.map(|page| owner_index_pda(registrant, page)),
+        system_program: System::id(),
+    };
+    let data = sentinel::instruction::ArchivePosition { position_id };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Upgrades a position written under an older `PositionAccount` layout;
+/// `payer` covers the rent of the bytes it grows by.
+pub fn migrate_position(payer: &Pubkey, registrant: &Pubkey, position_id: u32) -> Instruction {
+    let accounts = sentinel::accounts::MigratePosition {
+        payer: *payer,
+        position_acc: position_pda(registrant, position_id),
+        system_program: System::id(),
+    };
+    let data = sentinel::instruction::MigratePosition {};
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Takes or renews `keeper`'s lease on `shard` for `region` (lower regions
+/// have priority) for `duration_secs`.
+pub fn renew_keeper_lease(
+    keeper: &Pubkey,
+    shard: u16,
+    region: u8,
+    duration_secs: i64,
+) -> Instruction {
+    let accounts = sentinel::accounts::RenewKeeperLease {
+        keeper: *keeper,
+        lease: keeper_lease_pda(keeper, shard),
+        system_program: System::id(),
+    };
+    let data = sentinel::instruction::RenewKeeperLease {
+        shard,
+        region,
+        duration_secs,
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Bonds `lamports` more into `keeper`'s registry account, registering it
+/// if it isn't yet.
+pub fn register_keeper(keeper: &Pubkey, lamports: u64) -> Instruction {
+    let accounts = sentinel::accounts::RegisterKeeper {
+        keeper: *keeper,
+        keeper_account: keeper_account_pda(keeper),
+        system_program: System::id(),
+    };
+    let data = sentinel::instruction::RegisterKeeper { lamports };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Starts unbonding `keeper`; see [`withdraw_keeper_bond`].
+pub fn unbond_keeper(keeper: &Pubkey) -> Instruction {
+    let accounts = sentinel::accounts::UnbondKeeper {
+        keeper: *keeper,
+        keeper_account: keeper_account_pda(keeper),
+    };
+    let data = sentinel::instruction::UnbondKeeper {};
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Withdraws `keeper`'s bond once `sentinel::KEEPER_UNBONDING_SECS` have
+/// passed since [`unbond_keeper`].
+pub fn withdraw_keeper_bond(keeper: &Pubkey) -> Instruction {
+    let accounts = sentinel::accounts::WithdrawKeeperBond {
+        keeper: *keeper,
+        keeper_account: keeper_account_pda(keeper),
+    };
+    let data = sentinel::instruction::WithdrawKeeperBond {};
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Restricts the position's delegated keeper to keepers bonded in the
+/// registry, or lifts the restriction.
+pub fn set_registered_keepers_only(
+    owner: &Pubkey,
+    registrant: &Pubkey,
+    position_id: u32,
+    enabled: bool,
+) -> Instruction {
+    let accounts = sentinel::accounts::SetRegisteredKeepersOnly {
+        owner: *owner,
+        position_acc: position_pda(registrant, position_id),
+    };
+    let data = sentinel::instruction::SetRegisteredKeepersOnly {
+        position_id,
+        enabled,
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Offers the position registered by `registrant` to `new_owner`; `None`
+/// withdraws the offer.
+pub fn propose_position_transfer(
+    owner: &Pubkey,
+    registrant: &Pubkey,
+    position_id: u32,
+    new_owner: Option<Pubkey>,
+) -> Instruction {
+    let accounts = sentinel::accounts::ProposePositionTransfer {
+        owner: *owner,
+        position_acc: position_pda(registrant, position_id),
+    };
+    let data = sentinel::instruction::ProposePositionTransfer {
+        position_id,
+        new_owner,
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Accepts a transfer offered to `new_owner`. With `subscription` and
+/// `check_bounty`, the position's subscription and check bounty, which must
+/// exist, are handed over too.
+pub fn accept_position_transfer(
+    new_owner: &Pubkey,
+    registrant: &Pubkey,
+    position_id: u32,
+    subscription: bool,
+    check_bounty: bool,
+) -> Instruction {
+    let position_acc = position_pda(registrant, position_id);
+    let accounts = sentinel::accounts::AcceptPositionTransfer {
+        new_owner: *new_owner,
+        position_acc,
+        subscription: subscription.then(|| subscription_pda(&position_acc)),
+        check_bounty: check_bounty.then(|| check_bounty_pda(&position_acc)),
+    };
+    let data = sentinel::instruction::AcceptPositionTransfer { position_id };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Sets the instruction a critical reveal runs for the position. It is
+/// signed by [`auto_action_pda`], so that address must be the position's
+/// delegate in the target protocol.
+pub fn set_auto_action(
+    owner: &Pubkey,
+    registrant: &Pubkey,
+    position_id: u32,
+    program_id: Pubkey,
+    accounts: Vec<sentinel::AutoActionAccount>,
+    data: Vec<u8>,
+) -> Instruction {
+    let position_acc = position_pda(registrant, position_id);
+    let metas = sentinel::accounts::SetAutoAction {
+        owner: *owner,
+        position_acc,
+        auto_action: auto_action_pda(&position_acc),
+        system_program: System::id(),
+    }
+    .to_account_metas(None);
+    let data = sentinel::instruction::SetAutoAction {
+        position_id,
+        program_id,
+        accounts,
+        data,
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), metas)
+}
+
+/// Removes the position's auto action and refunds its rent to `owner`.
+pub fn clear_auto_action(owner: &Pubkey, registrant: &Pubkey, position_id: u32) -> Instruction {
+    let position_acc = position_pda(registrant, position_id);
+    let accounts = sentinel::accounts::ClearAutoAction {
+        owner: *owner,
+        position_acc,
+        auto_action: auto_action_pda(&position_acc),
+    };
+    let data = sentinel::instruction::ClearAutoAction { position_id };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Runs `action`, the position's auto action, which a critical reveal made
+/// due. Any payer may send it.
+pub fn execute_auto_action(
+    registrant: &Pubkey,
+    position_id: u32,
+    action: &sentinel::AutoActionConfig,
+) -> Instruction {
+    let position_acc = position_pda(registrant, position_id);
+    let mut accounts = sentinel::accounts::ExecuteAutoAction {
+        position_acc,
+        auto_action: auto_action_pda(&position_acc),
+    }
+    .to_account_metas(None);
+    accounts.extend(template_metas(&action.program_id, &action.accounts));
+    let data = sentinel::instruction::ExecuteAutoAction { position_id };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts)
+}
+
+/// `program_id` and `accounts` as the remaining accounts of an instruction
+/// that runs a template. Only the program signs for the template's signer.
+fn template_metas(
+    program_id: &Pubkey,
+    accounts: &[sentinel::AutoActionAccount],
+) -> Vec<AccountMeta> {
+    let metas = accounts.iter().map(|account| match account.is_writable {
+        true => AccountMeta::new(account.pubkey, false),
+        false => AccountMeta::new_readonly(account.pubkey, false),
+    });
+    std::iter::once(AccountMeta::new_readonly(*program_id, false))
+        .chain(metas)
+        .collect()
+}
+
+/// Sends `stop_loss`, the position's fired stop-loss order, to its
+/// executor. Any payer may send it.
+pub fn execute_stop_loss(
+    registrant: &Pubkey,
+    position_id: u32,
+    stop_loss: &sentinel::StopLossAccount,
+) -> Instruction {
+    let position_acc = position_pda(registrant, position_id);
+    let mut accounts = sentinel::accounts::ExecuteStopLoss {
+        position_acc,
+        stop_loss: stop_loss_pda(&position_acc),
+    }
+    .to_account_metas(None);
+    let executor = stop_loss.executor.unwrap_or_default();
+    accounts.extend(template_metas(&executor, &stop_loss.executor_accounts));
+    let data = sentinel::instruction::ExecuteStopLoss { position_id };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts)
+}
+
+/// Sets the position's stop-loss order, encrypted to the MXE, and re-arms
+/// it. When it fires, the order is sent to `executor` over
+/// `executor_accounts`, signed by [`stop_loss_pda`].
+#[allow(clippy::too_many_arguments)]
+pub fn set_stop_loss(
+    owner: &Pubkey,
+    registrant: &Pubkey,
+    position_id: u32,
+    encryption_pubkey: [u8; 32],
+    config: &EncryptedPosition,
+    executor: Option<Pubkey>,
+    executor_accounts: Vec<sentinel::AutoActionAccount>,
+) -> Instruction {
+    let position_acc = position_pda(registrant, position_id);
+    let accounts = sentinel::accounts::SetStopLoss {
+        owner: *owner,
+        position_acc,
+        stop_loss: stop_loss_pda(&position_acc),
+        system_program: System::id(),
+    };
+    let data = sentinel::instruction::SetStopLoss {
+        position_id,
+        encrypted_config: config.ciphertexts,
+        encryption_pubkey,
+        encryption_nonce: config.nonce,
+        executor,
+        executor_accounts,
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Removes the position's stop-loss order and refunds its rent to `owner`.
+pub fn clear_stop_loss(owner: &Pubkey, registrant: &Pubkey, position_id: u32) -> Instruction {
+    let position_acc = position_pda(registrant, position_id);
+    let accounts = sentinel::accounts::ClearStopLoss {
+        owner: *owner,
+        position_acc,
+        stop_loss: stop_loss_pda(&position_acc),
+    };
+    let data = sentinel::instruction::ClearStopLoss { position_id };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Stores the owner's action memo ([`crate::memo::encrypt_memo`]), attached
+/// to reveals built with `action_memo`.
+pub fn set_action_memo(
+    owner: &Pubkey,
+    registrant: &Pubkey,
+    position_id: u32,
+    memo: sentinel::EncryptedMemo,
+) -> Instruction {
+    let position_acc = position_pda(registrant, position_id);
+    let accounts = sentinel::accounts::SetActionMemo {
+        owner: *owner,
+        position_acc,
+        action_memo: action_memo_pda(&position_acc),
+        system_program: System::id(),
+    };
+    let data = sentinel::instruction::SetActionMemo { position_id, memo };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Removes the position's action memo and refunds its rent to `owner`.
+pub fn clear_action_memo(owner: &Pubkey, registrant: &Pubkey, position_id: u32) -> Instruction {
+    let position_acc = position_pda(registrant, position_id);
+    let accounts = sentinel::accounts::ClearActionMemo {
+        owner: *owner,
+        position_acc,
+        action_memo: action_memo_pda(&position_acc),
+    };
+    let data = sentinel::instruction::ClearActionMemo { position_id };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Evaluates the position's stop-loss against its stored health factor, as
+/// the owner or its keeper. Run [`compute_health_factor`] first.
+pub fn evaluate_stop_loss(
+    payer: &Pubkey,
+    registrant: &Pubkey,
+    position_id: u32,
+    queue: Queue,
+) -> Instruction {
+    let position_acc = position_pda(registrant, position_id);
+    let accounts = sentinel::accounts::EvaluateStopLoss {
+        payer: *payer,
+        sign_pda_account: sign_pda(),
+        mxe_account: pda::mxe_acc(&sentinel::ID),
+        mempool_account: pda::mempool_acc(queue.cluster_offset),
+        executing_pool: pda::execpool_acc(queue.cluster_offset),
+        computation_account: pda::computation_acc(queue.cluster_offset, queue.computation_offset),
+        comp_def_account: comp_def_address("evaluate_stop_loss"),
+        cluster_account: pda::cluster_acc(queue.cluster_offset),
+        pool_account: ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+        clock_account: ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+        system_program: System::id(),
+        arcium_program: ARCIUM_PROG_ID,
+        position_acc,
+        global_config: global_config_pda(),
+        stop_loss: stop_loss_pda(&position_acc),
+    };
+    let data = sentinel::instruction::EvaluateStopLoss {
+        computation_offset: queue.computation_offset,
+        position_id,
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Allows or forbids revealing the position's size bucket.
+pub fn set_size_disclosure(
+    owner: &Pubkey,
+    registrant: &Pubkey,
+    position_id: u32,
+    allowed: bool,
+) -> Instruction {
+    let accounts = sentinel::accounts::SetSizeDisclosure {
+        owner: *owner,
+        position_acc: position_pda(registrant, position_id),
+    };
+    let data = sentinel::instruction::SetSizeDisclosure {
+        position_id,
+        allowed,
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Reveals the position's `SizeBucket` from `data`, encrypted with the key
+/// behind `encryption_pubkey`, into `PositionAccount::size_bucket`. The
+/// owner has to allow it with [`set_size_disclosure`] first.
+pub fn reveal_size_bucket(
+    payer: &Pubkey,
+    registrant: &Pubkey,
+    position_id: u32,
+    queue: Queue,
+    encryption_pubkey: [u8; 32],
+    data: &EncryptedPosition,
+) -> Instruction {
+    let accounts = sentinel::accounts::RevealSizeBucket {
+        payer: *payer,
+        sign_pda_account: sign_pda(),
+        mxe_account: pda::mxe_acc(&sentinel::ID),
+        mempool_account: pda::mempool_acc(queue.cluster_offset),
+        executing_pool: pda::execpool_acc(queue.cluster_offset),
+        computation_account: pda::computation_acc(queue.cluster_offset, queue.computation_offset),
+        comp_def_account: comp_def_address("reveal_size_bucket"),
+        cluster_account: pda::cluster_acc(queue.cluster_offset),
+        pool_account: ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+        clock_account: ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+        system_program: System::id(),
+        arcium_program: ARCIUM_PROG_ID,
+        position_acc: position_pda(registrant, position_id),
+        global_config: global_config_pda(),
+    };
+    let data = sentinel::instruction::RevealSizeBucket {
+        computation_offset: queue.computation_offset,
+        position_id,
+        encrypted_position: data.ciphertexts,
+        encryption_pubkey,
+        encryption_nonce: data.nonce,
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Records that a protective step saved roughly `penalty_bps` of a
+/// position in `size_bucket`, emitted as `ValueProtected`. `signer` is the
+/// owner or its keeper.
+pub fn record_value_protected(
+    signer: &Pubkey,
+    registrant: &Pubkey,
+    position_id: u32,
+    trigger: ProtectionTrigger,
+    size_bucket: SizeBucket,
+    penalty_bps: u16,
+) -> Instruction {
+    let accounts = sentinel::accounts::RecordValueProtected {
+        signer: *signer,
+        position_acc: position_pda(registrant, position_id),
+    };
+    let data = sentinel::instruction::RecordValueProtected {
+        position_id,
+        trigger,
+        size_bucket,
+        penalty_bps,
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Lets `sponsor` pay for up to `reveals` of `owner`'s reveals.
+pub fn set_sponsor_budget(sponsor: &Pubkey, owner: Pubkey, reveals: u32) -> Instruction {
+    let accounts = sentinel::accounts::SetSponsorBudget {
+        sponsor: *sponsor,
+        sponsor_budget: sponsor_budget_pda(sponsor, &owner),
+        system_program: System::id(),
+    };
+    let data = sentinel::instruction::SetSponsorBudget { owner, reveals };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// `reveal_risk_to_owner` paid for by `sponsor`. Both sign; build the
+/// transaction with [`sponsored_message`] so the sponsor is also fee payer.
+#[allow(clippy::too_many_arguments)]
+pub fn sponsored_reveal_risk_to_owner(
+    sponsor: &Pubkey,
+    owner: &Pubkey,
+    registrant: &Pubkey,
+    position_id: u32,
+    queue: Queue,
+    encryption_pubkey: [u8; 32],
+    encryption_nonce: u128,
+    action_memo: bool,
+) -> Instruction {
+    let position_acc = position_pda(registrant, position_id);
+    let accounts = sentinel::accounts::SponsoredRevealRiskToOwner {
+        payer: *sponsor,
+        owner: *owner,
+        sign_pda_account: sign_pda(),
+        mxe_account: pda::mxe_acc(&sentinel::ID),
+        mempool_account: pda::mempool_acc(queue.cluster_offset),
+        executing_pool: pda::execpool_acc(queue.cluster_offset),
+        computation_account: pda::computation_acc(queue.cluster_offset, queue.computation_offset),
+        comp_def_account: comp_def_address("reveal_risk_to_owner"),
+        cluster_account: pda::cluster_acc(queue.cluster_offset),
+        pool_account: ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+        clock_account: ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+        system_program: System::id(),
+        arcium_program: ARCIUM_PROG_ID,
+        position_acc,
+        global_config: global_config_pda(),
+        reveal_inbox: reveal_inbox_pda(&position_acc),
+        sponsor_budget: sponsor_budget_pda(sponsor, owner),
+        action_memo: action_memo.then(|| action_memo_pda(&position_acc)),
+    };
+    let data = sentinel::instruction::SponsoredRevealRiskToOwner {
+        computation_offset: queue.computation_offset,
+        position_id,
+        encryption_pubkey,
+        encryption_nonce,
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// A message with `sponsor` as fee payer. Set `recent_blockhash`, have the
+/// owner sign the serialized message and hand the signature to the sponsor,
+/// who adds its own and submits; the owner never needs SOL.
+pub fn sponsored_message(sponsor: &Pubkey, instructions: &[Instruction]) -> Message {
+    Message::new(instructions, Some(sponsor))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sponsored_message_is_co_signed() {
+        let (sponsor, owner) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let ix = sponsored_reveal_risk_to_owner(
+            &sponsor,
+            &owner,
+            &owner,
+            1,
+            Queue::new(1),
+            [9; 32],
+            3,
+            false,
+        );
+
+        let message = sponsored_message(&sponsor, &[ix]);
+        assert_eq!(message.header.num_required_signatures, 2);
+        assert_eq!(&message.account_keys[..2], &[sponsor, owner]);
+    }
+}
@@ -0,0 +1,112 @@
+//! Position labels. The program only stores a hash of the label, salted
+//! with the owner's key so the same label hashes differently per owner and
+//! can't be looked up in a table built for everyone. Searching by label means
+//! hashing it here and filtering positions on `PositionAccount::label_hash`.
+
+use anchor_lang::prelude::Pubkey;
+use sentinel::PositionAccount;
+use sha2::{Digest, Sha256};
+
+/// Domain separator of label hashes.
+pub const LABEL_DOMAIN: &[u8] = b"sentinel-label-v1";
+
+/// Hash `set_label` stores for `owner`'s `label`. Labels are trimmed and
+/// compared case-insensitively.
+pub fn label_hash(owner: &Pubkey, label: &str) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(LABEL_DOMAIN);
+    hasher.update(owner.as_ref());
+    hasher.update(label.trim().to_lowercase().as_bytes());
+    hasher.finalize().into()
+}
+
+/// Offset and bytes of a memcmp filter matching `owner`'s positions labeled
+/// `label`, for `getProgramAccounts`.
+pub fn label_filter(owner: &Pubkey, label: &str) -> (usize, [u8; 32]) {
+    (PositionAccount::LABEL_HASH_OFFSET, label_hash(owner, label))
+}
+
+/// Whether `position` carries `label` of its owner.
+pub fn has_label(position: &PositionAccount, label: &str) -> bool {
+    position.label_hash == label_hash(&position.owner, label)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::AccountSerialize;
+
+    #[test]
+    fn label_hash_is_salted_by_owner() {
+        let alice = Pubkey::new_unique();
+        let bob = Pubkey::new_unique();
+
+        assert_eq!(
+            label_hash(&alice, " Treasury "),
+            label_hash(&alice, "treasury")
+        );
+        assert_ne!(label_hash(&alice, "treasury"), label_hash(&bob, "treasury"));
+        assert_ne!(label_hash(&alice, "treasury"), label_hash(&alice, "vault"));
+    }
+
+    #[test]
+    fn label_filter_matches_serialized_position() {
+        let owner = Pubkey::new_unique();
+        let position = PositionAccount {
+            bump: 255,
+            risk_state: [[1; 32]; 3],
+            alert_config: [[2; 32]; 3],
+            health_factor: [3; 32],
+            label_hash: label_hash(&owner, "treasury"),
+            position_id: 1,
+            owner,
+            nonce: 0,
+            last_check: 0,
+            is_active: true,
+            referrer: Some(Pubkey::new_unique()),
+            pending_computation: None,
+            price_feed: None,
+            keeper: None,
+            keeper_expiry_slot: None,
+            cross_chain_emitter: None,
+            pending_since: 0,
+            alert_nonce: 0,
+            alert_config_set: false,
+            registrant: owner,
+            pending_owner: None,
+            min_action_severity: 1,
+            max_staleness_secs: None,
+            health_factor_nonce: 0,
+            health_factor_at: 0,
+            tvl_protocol: None,
+            tvl_drop_bps: sentinel::DEFAULT_TVL_DROP_BPS,
+            config_version: 0,
+            risk_model: sentinel::RiskModel::BUILT_IN,
+            risk_state_version: 0,
+            pending_circuit_version: 0,
+            last_error: None,
+            metadata: sentinel::PositionMetadata::UNSPECIFIED,
+            deferred_until: 0,
+            heatmap_epoch: 0,
+            min_check_interval_secs: 0,
+            size_disclosure_allowed: false,
+            size_bucket: None,
+            version: sentinel::POSITION_VERSION,
+            registered_keepers_only: false,
+            heartbeat_deadline_secs: 0,
+            monitoring_lapsed: false,
+            last_seen: 0,
+            receipt_minted: false,
+            indexed: false,
+            index_page: 0,
+            _reserved: [0; sentinel::POSITION_RESERVED_BYTES],
+        };
+        let mut data = Vec::new();
+        position.try_serialize(&mut data).unwrap();
+
+        let (offset, bytes) = label_filter(&owner, "treasury");
+        assert_eq!(&data[offset..offset + 32], &bytes);
+        assert!(has_label(&position, "Treasury"));
+        assert!(!has_label(&position, "vault"));
+    }
+}
@@ -0,0 +1,54 @@
+//! Client for the sentinel program.
+//!
+//! The program never sees position data in the clear: the client encrypts it
+//! to the MXE with a Rescue cipher keyed by an x25519 exchange, and the
+//! instructions carry the ciphertexts, the client's x25519 public key and the
+//! nonce. This crate wraps those steps so integrators don't have to mirror the
+//! `ArgBuilder` layout in the program:
+//!
+//! ```no_run
+//! # fn example(wallet_signature: [u8; 64], mxe_pubkey: [u8; 32], owner: anchor_lang::prelude::Pubkey) {
+//! use sentinel_client::{instructions, EncryptionKey, PositionData};
+//!
+//! // The wallet signs `ENCRYPTION_KEY_MESSAGE`, so the key can be re-derived later
+//! let key = EncryptionKey::from_signature(&wallet_signature);
+//! let cipher = key.cipher(&mxe_pubkey).unwrap();
+//! let position = PositionData {
+//!     position_value: 100_000,
+//!     collateral_ratio: 15_000,
+//!     liquidation_threshold: 11_000,
+//! };
+//! let encrypted = position.encrypt(&cipher, sentinel_client::new_nonce());
+//! let ix = instructions::check_health(
+//!     &owner,
+//!     &owner,
+//!     &owner,
+//!     0,
+//!     instructions::Queue::new(456),
+//!     key.public_key(),
+//!     &encrypted,
+//!     false,
+//!     None,
+//!     None,
+//!     false,
+//!     false,
+//! );
+//! # }
+//! ```
+
+pub mod cache;
+pub mod encryption;
+pub mod envelope;
+#[cfg(test)]
+mod golden;
+pub mod instructions;
+pub mod labels;
+pub mod memo;
+pub mod schema;
+
+pub use encryption::{
+    new_nonce, Cipher, EncryptedPerpPosition, EncryptedPosition, EncryptedScoredPosition,
+    EncryptionKey, HealthFactor, InvalidPublicKey, PerpPosition, PositionData, PositionValues,
+    RiskState, ScoredPosition, StopLossConfig, StressResult, ENCRYPTION_KEY_MESSAGE,
+};
+pub use envelope::EncryptedEnvelope;
@@ -0,0 +1,148 @@
+//! Action memos: a plan the owner writes ahead of time, stored encrypted to
+//! the owner's own key and attached to risk reveals, so the wallet can show
+//! "what I said I'd do" next to a critical alert.
+//!
+//! The plaintext is the memo's length in bytes followed by its UTF-8 bytes,
+//! eight to a `u64`, so a memo holds up to [`MAX_MEMO_BYTES`].
+
+use crate::{EncryptionKey, RiskState};
+use sentinel::{EncryptedMemo, RevealInbox, MAX_MEMO_CIPHERTEXTS};
+use std::fmt;
+
+/// Longest memo [`encrypt_memo`] takes, in bytes.
+pub const MAX_MEMO_BYTES: usize = (MAX_MEMO_CIPHERTEXTS - 1) * 8;
+
+/// The memo is longer than [`MAX_MEMO_BYTES`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoTooLong;
+
+impl fmt::Display for MemoTooLong {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "action memo is longer than {MAX_MEMO_BYTES} bytes")
+    }
+}
+
+impl std::error::Error for MemoTooLong {}
+
+/// Encrypts `text` to `key` itself, for `set_action_memo`.
+pub fn encrypt_memo(
+    key: &EncryptionKey,
+    text: &str,
+    nonce: u128,
+) -> Result<EncryptedMemo, MemoTooLong> {
+    let bytes = text.as_bytes();
+    if bytes.len() > MAX_MEMO_BYTES {
+        return Err(MemoTooLong);
+    }
+    let mut values = vec![bytes.len() as u64];
+    values.extend(bytes.chunks(8).map(|chunk| {
+        let mut word = [0; 8];
+        word[..chunk.len()].copy_from_slice(chunk);
+        u64::from_le_bytes(word)
+    }));
+    Ok(EncryptedMemo {
+        encryption_key: key.public_key(),
+        nonce,
+        ciphertexts: self_cipher(key).encrypt(&values, nonce),
+    })
+}
+
+/// Decrypts a memo `key` encrypted. `None` if it was encrypted with another
+/// key or doesn't decode.
+pub fn decrypt_memo(key: &EncryptionKey, memo: &EncryptedMemo) -> Option<String> {
+    if memo.encryption_key != key.public_key() {
+        return None;
+    }
+    let values = self_cipher(key).decrypt(&memo.ciphertexts, memo.nonce)?;
+    let (&len, words) = values.split_first()?;
+    let mut bytes: Vec<u8> = words.iter().flat_map(|word| word.to_le_bytes()).collect();
+    if len as usize > bytes.len() {
+        return None;
+    }
+    bytes.truncate(len as usize);
+    String::from_utf8(bytes).ok()
+}
+
+/// The owner's plan to show next to the risk reveal in `inbox`: the
+/// attached memo, decrypted, if the revealed severity is critical. `cipher`
+/// decrypts the reveal and `key` the memo, as they may differ.
+pub fn critical_plan(
+    key: &EncryptionKey,
+    cipher: &crate::Cipher,
+    inbox: &RevealInbox,
+) -> Option<String> {
+    let risk = RiskState::from_inbox(cipher, inbox)?;
+    if risk.severity != 3 {
+        return None;
+    }
+    decrypt_memo(key, inbox.memo.as_ref()?)
+}
+
+fn self_cipher(key: &EncryptionKey) -> crate::Cipher {
+    key.cipher(&key.public_key())
+        .expect("a derived public key is a curve point")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::envelope::circuit_id;
+    use anchor_lang::prelude::Pubkey;
+    use sentinel::EncryptedEnvelope;
+
+    #[test]
+    fn memo_round_trips_only_with_its_key() {
+        let key = EncryptionKey::random();
+        let text = "Repay 40% from the USDC vault, then unwind the SOL loop.";
+        let memo = encrypt_memo(&key, text, 7).unwrap();
+
+        assert_eq!(memo.ciphertexts.len(), 1 + text.len().div_ceil(8));
+        assert_eq!(decrypt_memo(&key, &memo).as_deref(), Some(text));
+        assert_eq!(decrypt_memo(&EncryptionKey::random(), &memo), None);
+        assert_eq!(
+            decrypt_memo(&key, &encrypt_memo(&key, "", 8).unwrap()).as_deref(),
+            Some("")
+        );
+    }
+
+    #[test]
+    fn memo_fits_the_account() {
+        let key = EncryptionKey::random();
+        let longest = "x".repeat(MAX_MEMO_BYTES);
+        let memo = encrypt_memo(&key, &longest, 1).unwrap();
+
+        assert_eq!(memo.ciphertexts.len(), MAX_MEMO_CIPHERTEXTS);
+        assert_eq!(
+            encrypt_memo(&key, &format!("{longest}x"), 1),
+            Err(MemoTooLong)
+        );
+    }
+
+    #[test]
+    fn critical_plan_needs_a_critical_reveal_and_a_memo() {
+        let owner = EncryptionKey::random();
+        let mxe = EncryptionKey::random();
+        let to_owner = mxe.cipher(&owner.public_key()).unwrap();
+        let cipher = owner.cipher(&mxe.public_key()).unwrap();
+        let inbox = |severity: u64, memo: Option<EncryptedMemo>| RevealInbox {
+            bump: 255,
+            position: Pubkey::new_unique(),
+            owner: Pubkey::new_unique(),
+            encryption_key: owner.public_key(),
+            computation_offset: 1,
+            delivered_at: 1,
+            envelope: EncryptedEnvelope::new(
+                circuit_id("reveal_risk_to_owner"),
+                2,
+                &to_owner.encrypt(&[1, severity, 0], 2),
+            ),
+            memo,
+        };
+        let memo = encrypt_memo(&owner, "sell half", 3).unwrap();
+
+        let plan = critical_plan(&owner, &cipher, &inbox(3, Some(memo.clone())));
+        assert_eq!(plan.as_deref(), Some("sell half"));
+        assert_eq!(critical_plan(&owner, &cipher, &inbox(2, Some(memo))), None);
+        assert_eq!(critical_plan(&owner, &cipher, &inbox(3, None)), None);
+    }
+}
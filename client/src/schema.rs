@@ -0,0 +1,315 @@
+//! JSON shapes of what the keeper and integrators exchange off-chain: the
+//! webhook payloads the keeper posts for program events, and reveal inboxes
+//! rendered for APIs that serve them. Their JSON Schemas are generated from
+//! these types and published under `schemas/v<SCHEMA_VERSION>/` with
+//! `cargo xtask schema`; a test fails when the published files fall behind.
+//!
+//! Pubkeys are base58, byte arrays and ciphertexts hex, and `u128` nonces
+//! decimal strings, since JSON numbers can't hold them. A change an
+//! integrator's validator would reject bumps [`SCHEMA_VERSION`].
+
+use anchor_lang::prelude::Pubkey;
+use schemars::{JsonSchema, Schema};
+use sentinel::{
+    ActionRequired, ComputationFailed, ConfigUpdateProposed, EncryptedEnvelope, EncryptedMemo,
+    RevealInbox, RiskRevealed,
+};
+use serde::{Deserialize, Serialize};
+
+/// Version of the published schemas, the directory they live in.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// What the keeper posts to each webhook, one per program event. `event`
+/// names the event; `signature` and `slot` are of the transaction that
+/// emitted it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "event")]
+pub enum WebhookPayload {
+    RiskRevealed {
+        signature: String,
+        slot: u64,
+        is_at_risk: bool,
+        timestamp: i64,
+    },
+    ActionRequired {
+        signature: String,
+        slot: u64,
+        owner: String,
+        position_id: u32,
+        /// `ActionType` variant, e.g. `Deleverage`
+        action_type: String,
+        /// 1=low .. 3=critical
+        severity: u8,
+        timestamp: i64,
+    },
+    ConfigUpdateProposed {
+        signature: String,
+        slot: u64,
+        max_staleness_secs: i64,
+        update_delay_secs: i64,
+        apply_after: i64,
+        timestamp: i64,
+    },
+    ComputationFailed {
+        signature: String,
+        slot: u64,
+        /// The position's or portfolio's owner, or the `RiskHeatmap` address
+        owner: String,
+        /// `null` for the owner's portfolio and the heatmap
+        position_id: Option<u32>,
+        computation_offset: u64,
+        /// `ComputationKind` variant, e.g. `CheckHealth`
+        kind: String,
+        timestamp: i64,
+    },
+}
+
+impl WebhookPayload {
+    pub fn risk_revealed(signature: String, slot: u64, event: &RiskRevealed) -> Self {
+        Self::RiskRevealed {
+            signature,
+            slot,
+            is_at_risk: event.is_at_risk,
+            timestamp: event.timestamp,
+        }
+    }
+
+    pub fn action_required(signature: String, slot: u64, event: &ActionRequired) -> Self {
+        Self::ActionRequired {
+            signature,
+            slot,
+            owner: event.owner.to_string(),
+            position_id: event.position_id,
+            action_type: format!("{:?}", event.action_type),
+            severity: event.severity,
+            timestamp: event.timestamp,
+        }
+    }
+
+    pub fn config_update_proposed(
+        signature: String,
+        slot: u64,
+        event: &ConfigUpdateProposed,
+    ) -> Self {
+        Self::ConfigUpdateProposed {
+            signature,
+            slot,
+            max_staleness_secs: event.max_staleness_secs,
+            update_delay_secs: event.update_delay_secs,
+            apply_after: event.apply_after,
+            timestamp: event.timestamp,
+        }
+    }
+
+    pub fn computation_failed(signature: String, slot: u64, event: &ComputationFailed) -> Self {
+        Self::ComputationFailed {
+            signature,
+            slot,
+            owner: event.owner.to_string(),
+            position_id: event.position_id,
+            computation_offset: event.computation_offset,
+            kind: format!("{:?}", event.kind),
+            timestamp: event.timestamp,
+        }
+    }
+}
+
+/// A `RevealInbox` account as JSON.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct InboxRecord {
+    /// The inbox account
+    pub address: String,
+    pub position: String,
+    pub owner: String,
+    /// x25519 public key the envelope is encrypted to, all zeros until the
+    /// first reveal is delivered
+    pub encryption_key: String,
+    pub computation_offset: u64,
+    /// Unix timestamp of delivery, 0 before the first
+    pub delivered_at: i64,
+    pub envelope: EnvelopeRecord,
+    pub memo: Option<MemoRecord>,
+}
+
+/// An `EncryptedEnvelope` as JSON.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct EnvelopeRecord {
+    pub version: u8,
+    pub circuit_id: u32,
+    /// Decimal
+    pub nonce: String,
+    pub ciphertexts: Vec<String>,
+}
+
+/// An `EncryptedMemo` as JSON.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct MemoRecord {
+    pub encryption_key: String,
+    /// Decimal
+    pub nonce: String,
+    pub ciphertexts: Vec<String>,
+}
+
+impl InboxRecord {
+    pub fn new(address: &Pubkey, inbox: &RevealInbox) -> Self {
+        Self {
+            address: address.to_string(),
+            position: inbox.position.to_string(),
+            owner: inbox.owner.to_string(),
+            encryption_key: hex(&inbox.encryption_key),
+            computation_offset: inbox.computation_offset,
+            delivered_at: inbox.delivered_at,
+            envelope: EnvelopeRecord::from(&inbox.envelope),
+            memo: inbox.memo.as_ref().map(MemoRecord::from),
+        }
+    }
+}
+
+impl From<&EncryptedEnvelope> for EnvelopeRecord {
+    fn from(envelope: &EncryptedEnvelope) -> Self {
+        Self {
+            version: envelope.version,
+            circuit_id: envelope.circuit_id,
+            nonce: envelope.nonce.to_string(),
+            ciphertexts: envelope.ciphertexts.iter().map(|c| hex(c)).collect(),
+        }
+    }
+}
+
+impl From<&EncryptedMemo> for MemoRecord {
+    fn from(memo: &EncryptedMemo) -> Self {
+        Self {
+            encryption_key: hex(&memo.encryption_key),
+            nonce: memo.nonce.to_string(),
+            ciphertexts: memo.ciphertexts.iter().map(|c| hex(c)).collect(),
+        }
+    }
+}
+
+/// Every published schema with its file name.
+pub fn schemas() -> Vec<(&'static str, Schema)> {
+    vec![
+        (
+            "webhook_payload.json",
+            schemars::schema_for!(WebhookPayload),
+        ),
+        ("inbox_record.json", schemars::schema_for!(InboxRecord)),
+    ]
+}
+
+/// `schema` as written to its file.
+pub fn render(schema: &Schema) -> String {
+    let mut json = serde_json::to_string_pretty(schema).expect("schemas serialize");
+    json.push('\n');
+    json
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::envelope::circuit_id;
+    use serde_json::{json, Value};
+    use std::path::Path;
+
+    /// Whether every key of `value` is a property of the object `schema`
+    /// and every required property is present.
+    fn fits(schema: &Value, value: &Value) -> bool {
+        let properties = schema["properties"].as_object().unwrap();
+        let object = value.as_object().unwrap();
+        let required = schema["required"].as_array().unwrap();
+        object.keys().all(|key| properties.contains_key(key))
+            && required
+                .iter()
+                .all(|key| object.contains_key(key.as_str().unwrap()))
+    }
+
+    #[test]
+    fn published_schemas_are_current() {
+        let dir =
+            Path::new(env!("CARGO_MANIFEST_DIR")).join(format!("../schemas/v{SCHEMA_VERSION}"));
+        for (name, schema) in schemas() {
+            let published = std::fs::read_to_string(dir.join(name)).unwrap_or_default();
+            assert!(
+                published == render(&schema),
+                "schemas/v{SCHEMA_VERSION}/{name} is out of date, run `cargo xtask schema`"
+            );
+        }
+    }
+
+    #[test]
+    fn webhook_payloads_fit_their_schema() {
+        let schema = serde_json::to_value(schemars::schema_for!(WebhookPayload)).unwrap();
+        let owner = Pubkey::new_unique();
+        let payload = WebhookPayload::action_required(
+            "sig".into(),
+            7,
+            &ActionRequired {
+                owner,
+                position_id: 2,
+                action_type: sentinel::ActionType::Deleverage,
+                severity: 2,
+                timestamp: 9,
+            },
+        );
+        let value = serde_json::to_value(&payload).unwrap();
+
+        assert_eq!(
+            value,
+            json!({
+                "event": "ActionRequired",
+                "signature": "sig",
+                "slot": 7,
+                "owner": owner.to_string(),
+                "position_id": 2,
+                "action_type": "Deleverage",
+                "severity": 2,
+                "timestamp": 9,
+            })
+        );
+        let variant = schema["oneOf"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|variant| variant["properties"]["event"]["const"] == "ActionRequired")
+            .unwrap();
+        assert!(fits(variant, &value));
+        assert_eq!(
+            serde_json::from_value::<WebhookPayload>(value).unwrap(),
+            payload
+        );
+    }
+
+    #[test]
+    fn inbox_records_fit_their_schema() {
+        let schema = serde_json::to_value(schemars::schema_for!(InboxRecord)).unwrap();
+        let inbox = RevealInbox {
+            bump: 255,
+            position: Pubkey::new_unique(),
+            owner: Pubkey::new_unique(),
+            encryption_key: [0xab; 32],
+            computation_offset: 3,
+            delivered_at: 4,
+            envelope: EncryptedEnvelope::new(
+                circuit_id("reveal_risk_to_owner"),
+                u128::MAX,
+                &[[1; 32]],
+            ),
+            memo: None,
+        };
+        let record = InboxRecord::new(&Pubkey::new_unique(), &inbox);
+        let value = serde_json::to_value(&record).unwrap();
+
+        assert!(fits(&schema, &value));
+        assert_eq!(value["encryption_key"], "ab".repeat(32));
+        assert_eq!(value["envelope"]["nonce"], u128::MAX.to_string());
+        assert_eq!(value["memo"], Value::Null);
+        assert_eq!(
+            serde_json::from_value::<InboxRecord>(value).unwrap(),
+            record
+        );
+    }
+}
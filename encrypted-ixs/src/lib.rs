@@ -15,12 +15,124 @@ mod circuits {
         liquidation_threshold: u64,
     }
 
+    /// Position data for oracle-priced health checks. The collateral is
+    /// valued inside the circuit at the on-chain price.
+    pub struct CollateralPosition {
+        /// Collateral held, in millionths of a token (e.g., 2500000 = 2.5 tokens)
+        collateral_amount: u64,
+        /// Outstanding debt in USD cents
+        debt_value: u64,
+        /// Liquidation threshold in basis points (e.g., 11000 = 110%)
+        liquidation_threshold: u64,
+    }
+
+    /// Solana-side values for a cross-chain health check, in USD cents.
+    pub struct PositionValues {
+        /// Collateral value in USD cents
+        collateral_value: u64,
+        /// Outstanding debt in USD cents
+        debt_value: u64,
+        /// Liquidation threshold in basis points (e.g., 11000 = 110%)
+        liquidation_threshold: u64,
+    }
+
     /// Encrypted risk assessment result stored on-chain.
     pub struct RiskState {
         /// Whether the position is at risk (1 = at risk, 0 = safe)
         is_at_risk: u64,
         /// Risk severity: 0=safe, 1=low, 2=medium, 3=critical
         severity: u64,
+        /// Oracle price at the last priced check in micro-USD per token, 0 if
+        /// there has been none
+        last_price: u64,
+    }
+
+    /// Per-position alert thresholds set by the owner. The buffers are how
+    /// far above the liquidation threshold, in basis points, the collateral
+    /// ratio may fall before a critical or warning alert.
+    pub struct AlertConfig {
+        /// Buffer for severity 3 (default 500 = 5%)
+        critical_buffer_bps: u64,
+        /// Buffer for severity 2 (default 1000 = 10%)
+        warning_buffer_bps: u64,
+        /// Position value in USD cents below which severity 1 fires (default 100)
+        min_value_cents: u64,
+    }
+
+    /// Encrypted health factor: the collateral ratio relative to the
+    /// liquidation threshold, in basis points. 10_000 means exactly at the
+    /// threshold, anything below is liquidatable.
+    pub struct HealthFactor {
+        /// `u64::MAX` when there is no debt
+        health_factor_bps: u64,
+    }
+
+    /// Outcome of a what-if price shock, for the owner's eyes only.
+    pub struct StressResult {
+        /// Whether the shocked position breaches its liquidation threshold
+        /// (1 = liquidatable, 0 = safe)
+        would_liquidate: u64,
+        /// Health factor after the shock, as in `HealthFactor`
+        health_factor_bps: u64,
+    }
+
+    /// Encrypted aggregate over every position in a portfolio.
+    pub struct PortfolioRisk {
+        /// Number of positions currently at risk
+        at_risk_positions: u64,
+        /// Highest severity across the portfolio: 0=safe .. 3=critical
+        max_severity: u64,
+    }
+
+    /// Owner's stop-loss order. `action` is an opaque code the executor
+    /// interprets, e.g. 1 = close, 2 = repay half.
+    pub struct StopLossConfig {
+        /// Health factor at or below which the order fires, as in `HealthFactor`
+        trigger_health_factor_bps: u64,
+        action: u64,
+        /// Slippage the executor may accept, in basis points
+        max_slippage_bps: u64,
+    }
+
+    /// A perpetual futures position. Prices are in micro-USD per token, as
+    /// oracle prices are.
+    pub struct PerpPosition {
+        /// 1 = long, 0 = short
+        is_long: u64,
+        /// Average entry price in micro-USD per token
+        entry_price: u64,
+        /// Position size in millionths of a token
+        size: u64,
+        /// Leverage at entry in basis points (e.g., 50000 = 5x)
+        leverage_bps: u64,
+        /// Maintenance margin in basis points of the notional at the mark price
+        maintenance_margin_bps: u64,
+        /// Funding paid since entry net of funding received, in USD cents;
+        /// 0 if the position has received more than it paid
+        funding_owed_cents: u64,
+    }
+
+    /// Encrypted running at-risk counts per asset class, for the operator's
+    /// risk heatmap. The last class collects every other asset.
+    pub struct HeatmapCounts {
+        at_risk: [u64; 5],
+    }
+
+    /// Position data for the composite risk score.
+    pub struct ScoredPosition {
+        /// Collateral ratio in basis points (e.g., 15000 = 150%)
+        collateral_ratio: u64,
+        /// Liquidation threshold in basis points (e.g., 11000 = 110%)
+        liquidation_threshold: u64,
+        /// Share of the owner's holdings in this position, in basis points
+        concentration_bps: u64,
+    }
+
+    /// Encrypted composite risk score, kept beside the risk state it was
+    /// graded into.
+    pub struct RiskScore {
+        /// 0 (no risk) to 100
+        score: u64,
     }
 
     /// Initializes a new risk state account with safe defaults.
@@ -30,31 +142,78 @@ mod circuits {
         let state = RiskState {
             is_at_risk: 0,
             severity: 0,
+            last_price: 0,
         };
         mxe.from_arcis(state)
     }
 
+    /// Stores the owner's alert thresholds re-encrypted under the MXE key so
+    /// `check_position_health` can read them without the owner present.
+    /// A warning buffer below the critical one is raised to match it.
+    #[instruction]
+    pub fn set_alert_config(mxe: Mxe, config: Enc<Shared, AlertConfig>) -> Enc<Mxe, AlertConfig> {
+        let mut config = config.to_arcis();
+        if config.warning_buffer_bps < config.critical_buffer_bps {
+            config.warning_buffer_bps = config.critical_buffer_bps;
+        }
+        mxe.from_arcis(config)
+    }
+
     /// Performs a privacy-preserving health check on an encrypted position.
     ///
     /// The agent submits the user's encrypted position data along with public
     /// oracle data. MPC nodes compute the risk assessment without ever seeing
     /// the actual position values.
     ///
+    /// The buffers come from the position's `AlertConfig` when
+    /// `has_alert_config` is 1; otherwise `alert_config` is ignored and
+    /// `model`, the operator's risk model the position is pinned to, applies:
+    /// `[critical_buffer_bps, warning_buffer_bps, min_value_cents]`.
+    ///
+    /// `price` is the oracle price of the collateral in micro-USD per token,
+    /// or 0 if the position has no price feed. It is compared against the
+    /// price of the previous priced check, kept encrypted in the risk state.
+    ///
+    /// `tvl` and `previous_tvl` are the last two snapshots of the protocol's
+    /// public TVL, both 0 if the position follows none. A drop of more than
+    /// `tvl_drop_bps` between them counts as an exodus.
+    ///
     /// Risk levels:
     /// - 3 (critical): Position near liquidation (within 5% of threshold)
-    /// - 2 (medium): Significant price drop detected (>10%)
-    /// - 1 (low): TVL exodus from protocol (>20% drop)
+    /// - 2 (medium): Near the warning buffer, or a price drop of more than 10%
+    /// - 1 (low): Position value below the alert config's minimum, or TVL exodus
+    ///   from the protocol (default >20% drop)
     /// - 0 (safe): No threats detected
+    #[allow(clippy::too_many_arguments)]
     #[instruction]
     pub fn check_position_health(
         position: Enc<Shared, PositionData>,
         risk_state: Enc<Mxe, RiskState>,
+        alert_config: Enc<Mxe, AlertConfig>,
+        has_alert_config: u8,
+        model: [u64; 3],
+        price: u64,
+        tvl: u64,
+        previous_tvl: u64,
+        tvl_drop_bps: u64,
     ) -> Enc<Mxe, RiskState> {
         let pos = position.to_arcis();
-        let _prev = risk_state.to_arcis();
+        let prev = risk_state.to_arcis();
+        let custom = alert_config.to_arcis();
 
-        // Check if position is near liquidation (within 5% buffer = 500 basis points)
-        let near_liquidation = pos.collateral_ratio < pos.liquidation_threshold + 500;
+        let config = if has_alert_config == 1 {
+            custom
+        } else {
+            AlertConfig {
+                critical_buffer_bps: model[0],
+                warning_buffer_bps: model[1],
+                min_value_cents: model[2],
+            }
+        };
+
+        // Check if position is near liquidation (within the critical buffer)
+        let near_liquidation =
+            pos.collateral_ratio < pos.liquidation_threshold + config.critical_buffer_bps;
 
         // Determine severity based on how close to liquidation
         let mut severity: u64 = 0;
@@ -65,32 +224,874 @@ mod circuits {
             at_risk = 1;
         }
 
-        // Check if collateral ratio is in warning zone (within 10% = 1000 basis points)
-        if severity == 0 && pos.collateral_ratio < pos.liquidation_threshold + 1000 {
+        // Check if collateral ratio is in the warning zone
+        if severity == 0
+            && pos.collateral_ratio < pos.liquidation_threshold + config.warning_buffer_bps
+        {
+            severity = 2; // medium
+            at_risk = 1;
+        }
+
+        // Check if the price fell more than 10% since the last priced check
+        if severity == 0 && price_dropped(prev.last_price, price) {
             severity = 2; // medium
             at_risk = 1;
         }
 
         // Check if position value is suspiciously low (possible exploit drain)
-        if severity == 0 && pos.position_value < 100 {
+        if severity == 0 && pos.position_value < config.min_value_cents {
             severity = 1; // low - possible dust/drained position
             at_risk = 1;
         }
 
+        // Check if liquidity is leaving the protocol
+        if severity == 0 && tvl_dropped(previous_tvl, tvl, tvl_drop_bps) {
+            severity = 1; // low - TVL exodus
+            at_risk = 1;
+        }
+
         let new_state = RiskState {
             is_at_risk: at_risk,
             severity,
+            last_price: if price > 0 { price } else { prev.last_price },
         };
 
         risk_state.owner.from_arcis(new_state)
     }
 
+    /// Health checks of 4 positions in one computation, each graded like
+    /// `check_position_health` against its own alert config or risk model.
+    ///
+    /// Arcis circuits have a fixed arity, so callers always pass 4 positions;
+    /// a batch of fewer repeats one of them and ignores the extra results.
+    #[allow(clippy::too_many_arguments, clippy::type_complexity)]
+    #[instruction]
+    pub fn check_position_health_batch(
+        p0: Enc<Shared, PositionData>,
+        s0: Enc<Mxe, RiskState>,
+        c0: Enc<Mxe, AlertConfig>,
+        has_alert_config_0: u8,
+        m0: [u64; 3],
+        p1: Enc<Shared, PositionData>,
+        s1: Enc<Mxe, RiskState>,
+        c1: Enc<Mxe, AlertConfig>,
+        has_alert_config_1: u8,
+        m1: [u64; 3],
+        p2: Enc<Shared, PositionData>,
+        s2: Enc<Mxe, RiskState>,
+        c2: Enc<Mxe, AlertConfig>,
+        has_alert_config_2: u8,
+        m2: [u64; 3],
+        p3: Enc<Shared, PositionData>,
+        s3: Enc<Mxe, RiskState>,
+        c3: Enc<Mxe, AlertConfig>,
+        has_alert_config_3: u8,
+        m3: [u64; 3],
+    ) -> (
+        Enc<Mxe, RiskState>,
+        Enc<Mxe, RiskState>,
+        Enc<Mxe, RiskState>,
+        Enc<Mxe, RiskState>,
+    ) {
+        let r0 = grade(
+            p0.to_arcis(),
+            s0.to_arcis(),
+            c0.to_arcis(),
+            has_alert_config_0,
+            m0,
+        );
+        let r1 = grade(
+            p1.to_arcis(),
+            s1.to_arcis(),
+            c1.to_arcis(),
+            has_alert_config_1,
+            m1,
+        );
+        let r2 = grade(
+            p2.to_arcis(),
+            s2.to_arcis(),
+            c2.to_arcis(),
+            has_alert_config_2,
+            m2,
+        );
+        let r3 = grade(
+            p3.to_arcis(),
+            s3.to_arcis(),
+            c3.to_arcis(),
+            has_alert_config_3,
+            m3,
+        );
+        (
+            s0.owner.from_arcis(r0),
+            s1.owner.from_arcis(r1),
+            s2.owner.from_arcis(r2),
+            s3.owner.from_arcis(r3),
+        )
+    }
+
+    /// The grading of `check_position_health`, for one position of a batch.
+    /// Batches carry no price, so the last price is kept as it was.
+    fn grade(
+        pos: PositionData,
+        prev: RiskState,
+        custom: AlertConfig,
+        has_alert_config: u8,
+        model: [u64; 3],
+    ) -> RiskState {
+        let config = if has_alert_config == 1 {
+            custom
+        } else {
+            AlertConfig {
+                critical_buffer_bps: model[0],
+                warning_buffer_bps: model[1],
+                min_value_cents: model[2],
+            }
+        };
+
+        let mut severity: u64 = 0;
+        if pos.collateral_ratio < pos.liquidation_threshold + config.critical_buffer_bps {
+            severity = 3;
+        } else if pos.collateral_ratio < pos.liquidation_threshold + config.warning_buffer_bps {
+            severity = 2;
+        } else if pos.position_value < config.min_value_cents {
+            severity = 1;
+        }
+
+        RiskState {
+            is_at_risk: if severity > 0 { 1 } else { 0 },
+            severity,
+            last_price: prev.last_price,
+        }
+    }
+
+    /// Whether `price` is more than 10% below `last_price`. Either being 0
+    /// means there is nothing to compare.
+    fn price_dropped(last_price: u64, price: u64) -> bool {
+        price > 0 && (price as u128) * 10 < (last_price as u128) * 9
+    }
+
+    /// Whether `tvl` is more than `drop_bps` (at most 10_000) below
+    /// `previous_tvl`. Nothing to compare while `previous_tvl` is 0.
+    fn tvl_dropped(previous_tvl: u64, tvl: u64, drop_bps: u64) -> bool {
+        (tvl as u128) * 10_000 < (previous_tvl as u128) * (10_000 - drop_bps as u128)
+    }
+
+    /// Health check against an oracle price instead of a client-supplied ratio.
+    ///
+    /// `price` and `confidence` are plaintext Pyth values in micro-USD per
+    /// token. Collateral is valued at the bottom of the confidence interval so
+    /// an uncertain price can only make the assessment more conservative.
+    /// Severity levels match `check_position_health`, price drop included.
+    // Arcis has no `saturating_sub`
+    #[allow(clippy::implicit_saturating_sub)]
+    #[instruction]
+    pub fn check_position_health_with_price(
+        position: Enc<Shared, CollateralPosition>,
+        risk_state: Enc<Mxe, RiskState>,
+        price: u64,
+        confidence: u64,
+    ) -> Enc<Mxe, RiskState> {
+        let pos = position.to_arcis();
+        let prev = risk_state.to_arcis();
+
+        let low_price = if price > confidence {
+            price - confidence
+        } else {
+            0
+        };
+
+        // collateral_amount * low_price is in 10^-12 USD; the debt is in
+        // 10^-2 USD, so scale the debt side by 10^10 / 10^4 (basis points)
+        // instead of dividing.
+        let collateral_value = (pos.collateral_amount as u128) * (low_price as u128);
+        let debt_scaled = (pos.debt_value as u128) * 1_000_000;
+        let threshold = pos.liquidation_threshold as u128;
+
+        let mut severity: u64 = 0;
+        let mut at_risk: u64 = 0;
+
+        // Within 5% (500 basis points) of the liquidation threshold
+        if collateral_value < (threshold + 500) * debt_scaled {
+            severity = 3;
+            at_risk = 1;
+        }
+
+        // Within 10% (1000 basis points)
+        if severity == 0 && collateral_value < (threshold + 1000) * debt_scaled {
+            severity = 2;
+            at_risk = 1;
+        }
+
+        // More than 10% below the price of the last priced check
+        if severity == 0 && price_dropped(prev.last_price, price) {
+            severity = 2;
+            at_risk = 1;
+        }
+
+        // Collateral worth less than $1: dust or drained
+        if severity == 0 && collateral_value < 1_000_000_000_000 {
+            severity = 1;
+            at_risk = 1;
+        }
+
+        risk_state.owner.from_arcis(RiskState {
+            is_at_risk: at_risk,
+            severity,
+            last_price: price,
+        })
+    }
+
+    /// Health check over collateral spread across chains.
+    ///
+    /// `remote_collateral` and `remote_debt` are plaintext USD-cent totals
+    /// attested from another chain via Wormhole; they are public there
+    /// already. They are added to the encrypted Solana-side values and the
+    /// combined ratio is graded like `check_position_health`.
+    #[instruction]
+    pub fn check_position_health_cross_chain(
+        position: Enc<Shared, PositionValues>,
+        risk_state: Enc<Mxe, RiskState>,
+        remote_collateral: u64,
+        remote_debt: u64,
+    ) -> Enc<Mxe, RiskState> {
+        let pos = position.to_arcis();
+        let prev = risk_state.to_arcis();
+
+        let collateral = (pos.collateral_value as u128 + remote_collateral as u128) * 10_000;
+        let debt = pos.debt_value as u128 + remote_debt as u128;
+        let threshold = pos.liquidation_threshold as u128;
+
+        let mut severity: u64 = 0;
+        let mut at_risk: u64 = 0;
+
+        // Within 5% (500 basis points) of the liquidation threshold
+        if collateral < (threshold + 500) * debt {
+            severity = 3;
+            at_risk = 1;
+        }
+
+        // Within 10% (1000 basis points)
+        if severity == 0 && collateral < (threshold + 1000) * debt {
+            severity = 2;
+            at_risk = 1;
+        }
+
+        // Less than $1 of collateral across all chains
+        if severity == 0 && collateral < 100 * 10_000 {
+            severity = 1;
+            at_risk = 1;
+        }
+
+        risk_state.owner.from_arcis(RiskState {
+            is_at_risk: at_risk,
+            severity,
+            last_price: prev.last_price,
+        })
+    }
+
+    /// Health check over values read from a lending protocol's obligation.
+    ///
+    /// The program derives `position_value`, `collateral_ratio` and
+    /// `liquidation_threshold` from the on-chain obligation, which is public
+    /// already, so they arrive as plaintext; only the resulting risk state is
+    /// encrypted. Graded like `check_position_health`, alert config and risk
+    /// model included.
+    #[allow(clippy::too_many_arguments)]
+    #[instruction]
+    pub fn check_position_health_from_protocol(
+        risk_state: Enc<Mxe, RiskState>,
+        alert_config: Enc<Mxe, AlertConfig>,
+        has_alert_config: u8,
+        model: [u64; 3],
+        position_value: u64,
+        collateral_ratio: u64,
+        liquidation_threshold: u64,
+    ) -> Enc<Mxe, RiskState> {
+        let prev = risk_state.to_arcis();
+        let custom = alert_config.to_arcis();
+
+        let config = if has_alert_config == 1 {
+            custom
+        } else {
+            AlertConfig {
+                critical_buffer_bps: model[0],
+                warning_buffer_bps: model[1],
+                min_value_cents: model[2],
+            }
+        };
+
+        let mut severity: u64 = 0;
+        let mut at_risk: u64 = 0;
+
+        if collateral_ratio < liquidation_threshold + config.critical_buffer_bps {
+            severity = 3;
+            at_risk = 1;
+        }
+
+        if severity == 0 && collateral_ratio < liquidation_threshold + config.warning_buffer_bps {
+            severity = 2;
+            at_risk = 1;
+        }
+
+        if severity == 0 && position_value < config.min_value_cents {
+            severity = 1;
+            at_risk = 1;
+        }
+
+        risk_state.owner.from_arcis(RiskState {
+            is_at_risk: at_risk,
+            severity,
+            last_price: prev.last_price,
+        })
+    }
+
+    /// Health check scored by a rule set instead of fixed thresholds.
+    ///
+    /// `rules` is the plaintext parameter vector laid out in
+    /// `sentinel::rules`: 4 slots of `[enabled, field, op, relative, value,
+    /// weight]` followed by the low/medium/critical score boundaries. Each
+    /// enabled rule compares one position field against `value` (plus the
+    /// liquidation threshold if `relative` is set) and adds its weight to the
+    /// score when it matches. The layout is public; only the position is secret.
+    #[instruction]
+    pub fn check_position_health_with_rules(
+        position: Enc<Shared, PositionData>,
+        risk_state: Enc<Mxe, RiskState>,
+        rules: [u64; 27],
+    ) -> Enc<Mxe, RiskState> {
+        let pos = position.to_arcis();
+        let prev = risk_state.to_arcis();
+
+        let mut score: u64 = 0;
+        for i in 0..4 {
+            let slot = i * 6;
+            if rules[slot] == 1 {
+                let lhs = if rules[slot + 1] == 0 {
+                    pos.position_value
+                } else if rules[slot + 1] == 1 {
+                    pos.collateral_ratio
+                } else {
+                    pos.liquidation_threshold
+                };
+                let rhs = if rules[slot + 3] == 1 {
+                    pos.liquidation_threshold + rules[slot + 4]
+                } else {
+                    rules[slot + 4]
+                };
+                let matches = if rules[slot + 2] == 0 {
+                    lhs < rhs
+                } else {
+                    lhs >= rhs
+                };
+                if matches {
+                    score += rules[slot + 5];
+                }
+            }
+        }
+
+        let mut severity: u64 = 0;
+        for tier in 0..3 {
+            if score >= rules[24 + tier] {
+                severity = tier as u64 + 1;
+            }
+        }
+
+        risk_state.owner.from_arcis(RiskState {
+            is_at_risk: if severity > 0 { 1 } else { 0 },
+            severity,
+            last_price: prev.last_price,
+        })
+    }
+
+    /// Computes the position's health factor from encrypted collateral, debt
+    /// and threshold. Only the factor is kept, encrypted under the MXE key;
+    /// the owner reads it through `reveal_health_factor_to_owner`.
+    #[instruction]
+    pub fn compute_health_factor(
+        mxe: Mxe,
+        position: Enc<Shared, PositionValues>,
+    ) -> Enc<Mxe, HealthFactor> {
+        let pos = position.to_arcis();
+
+        // (collateral / debt) / (threshold / 10_000), in basis points
+        let numerator = pos.collateral_value as u128 * 100_000_000;
+        let denominator = pos.debt_value as u128 * pos.liquidation_threshold as u128;
+        let mut health_factor_bps = u64::MAX;
+        if denominator > 0 {
+            let quotient = numerator / denominator;
+            if quotient < u64::MAX as u128 {
+                health_factor_bps = quotient as u64;
+            }
+        }
+
+        mxe.from_arcis(HealthFactor { health_factor_bps })
+    }
+
+    /// What-if check: would the position be liquidatable if its collateral
+    /// lost `shock_bps` basis points of value? `shock_bps` is plaintext and
+    /// at most 10_000; the position and the answer stay encrypted to the
+    /// owner, nothing is stored or revealed on-chain.
+    #[instruction]
+    pub fn stress_test_position(
+        position: Enc<Shared, PositionValues>,
+        shock_bps: u64,
+    ) -> Enc<Shared, StressResult> {
+        let pos = position.to_arcis();
+
+        // Collateral after the shock, in USD cents times basis points
+        let shocked = pos.collateral_value as u128 * (10_000 - shock_bps) as u128;
+        let denominator = pos.debt_value as u128 * pos.liquidation_threshold as u128;
+        let mut health_factor_bps = u64::MAX;
+        let mut would_liquidate: u64 = 0;
+        if denominator > 0 {
+            let quotient = shocked * 10_000 / denominator;
+            if quotient < u64::MAX as u128 {
+                health_factor_bps = quotient as u64;
+            }
+            if shocked < denominator {
+                would_liquidate = 1;
+            }
+        }
+
+        position.owner.from_arcis(StressResult {
+            would_liquidate,
+            health_factor_bps,
+        })
+    }
+
+    /// Reveals only the position's size bucket, for value-protected
+    /// reporting: 0 under $1k, 1 under $10k, 2 under $100k, 3 above. The
+    /// value itself stays encrypted.
+    #[instruction]
+    pub fn reveal_size_bucket(position: Enc<Shared, PositionData>) -> u8 {
+        let value = position.to_arcis().position_value;
+        let mut bucket: u8 = 0;
+        if value >= 100_000 {
+            bucket = 1;
+        }
+        if value >= 1_000_000 {
+            bucket = 2;
+        }
+        if value >= 10_000_000 {
+            bucket = 3;
+        }
+        bucket.reveal()
+    }
+
     /// Reveals the risk assessment result.
     /// Only the position owner can trigger this to see if action is needed.
-    /// Returns true if the position is at risk.
+    /// Returns the severity (0=safe .. 3=critical); nonzero means at risk.
+    #[instruction]
+    pub fn reveal_risk(risk_state: Enc<Mxe, RiskState>) -> u8 {
+        let state = risk_state.to_arcis();
+        (state.severity as u8).reveal()
+    }
+
+    /// Folds the risk state of up to 8 positions into one portfolio score.
+    ///
+    /// Arcis circuits have a fixed arity, so callers always pass 8 states and
+    /// `count` says how many of them belong to the portfolio; the rest are
+    /// ignored.
+    #[allow(clippy::too_many_arguments)]
+    #[instruction]
+    pub fn aggregate_portfolio_risk(
+        mxe: Mxe,
+        count: u8,
+        p0: Enc<Mxe, RiskState>,
+        p1: Enc<Mxe, RiskState>,
+        p2: Enc<Mxe, RiskState>,
+        p3: Enc<Mxe, RiskState>,
+        p4: Enc<Mxe, RiskState>,
+        p5: Enc<Mxe, RiskState>,
+        p6: Enc<Mxe, RiskState>,
+        p7: Enc<Mxe, RiskState>,
+    ) -> Enc<Mxe, PortfolioRisk> {
+        let states = [
+            p0.to_arcis(),
+            p1.to_arcis(),
+            p2.to_arcis(),
+            p3.to_arcis(),
+            p4.to_arcis(),
+            p5.to_arcis(),
+            p6.to_arcis(),
+            p7.to_arcis(),
+        ];
+
+        let mut at_risk_positions: u64 = 0;
+        let mut max_severity: u64 = 0;
+        for (i, state) in states.iter().enumerate() {
+            if (i as u8) < count {
+                if state.is_at_risk > 0 {
+                    at_risk_positions += 1;
+                }
+                if state.severity > max_severity {
+                    max_severity = state.severity;
+                }
+            }
+        }
+
+        mxe.from_arcis(PortfolioRisk {
+            at_risk_positions,
+            max_severity,
+        })
+    }
+
+    /// Adds up to 8 positions to the heatmap's encrypted at-risk counts.
+    /// `c0..c7` are the positions' asset classes, which are public; only
+    /// their at-risk flags are secret. With `fresh` the counts start from
+    /// zero and `heatmap` is ignored.
+    #[allow(clippy::too_many_arguments)]
+    #[instruction]
+    pub fn accumulate_risk_heatmap(
+        mxe: Mxe,
+        heatmap: Enc<Mxe, HeatmapCounts>,
+        fresh: u8,
+        count: u8,
+        c0: u8,
+        c1: u8,
+        c2: u8,
+        c3: u8,
+        c4: u8,
+        c5: u8,
+        c6: u8,
+        c7: u8,
+        p0: Enc<Mxe, RiskState>,
+        p1: Enc<Mxe, RiskState>,
+        p2: Enc<Mxe, RiskState>,
+        p3: Enc<Mxe, RiskState>,
+        p4: Enc<Mxe, RiskState>,
+        p5: Enc<Mxe, RiskState>,
+        p6: Enc<Mxe, RiskState>,
+        p7: Enc<Mxe, RiskState>,
+    ) -> Enc<Mxe, HeatmapCounts> {
+        let mut counts = heatmap.to_arcis();
+        if fresh > 0 {
+            counts.at_risk = [0; 5];
+        }
+        let states = [
+            p0.to_arcis(),
+            p1.to_arcis(),
+            p2.to_arcis(),
+            p3.to_arcis(),
+            p4.to_arcis(),
+            p5.to_arcis(),
+            p6.to_arcis(),
+            p7.to_arcis(),
+        ];
+
+        let classes = [c0, c1, c2, c3, c4, c5, c6, c7];
+
+        for (i, state) in states.iter().enumerate() {
+            for (c, at_risk) in counts.at_risk.iter_mut().enumerate() {
+                if (i as u8) < count && classes[i] == c as u8 && state.is_at_risk > 0 {
+                    *at_risk += 1;
+                }
+            }
+        }
+
+        mxe.from_arcis(counts)
+    }
+
+    /// Reveals the heatmap's at-risk counts. Classes flagged in `s0..s4`
+    /// hold too few positions to stay anonymous and come back as 0.
+    #[instruction]
+    pub fn reveal_risk_heatmap(
+        heatmap: Enc<Mxe, HeatmapCounts>,
+        s0: u8,
+        s1: u8,
+        s2: u8,
+        s3: u8,
+        s4: u8,
+    ) -> [u64; 5] {
+        let counts = heatmap.to_arcis();
+        let suppressed = [s0, s1, s2, s3, s4];
+        let mut revealed = [0; 5];
+        for (c, at_risk) in counts.at_risk.iter().enumerate() {
+            if suppressed[c] == 0 {
+                revealed[c] = *at_risk;
+            }
+        }
+        revealed.reveal()
+    }
+
+    /// Counts how many of a position's last 8 health checks came back at risk,
+    /// revealing only the count. `count` says how many of the history
+    /// entries are filled; the rest are ignored.
+    #[allow(clippy::too_many_arguments)]
+    #[instruction]
+    pub fn reveal_history_summary(
+        count: u8,
+        h0: Enc<Mxe, RiskState>,
+        h1: Enc<Mxe, RiskState>,
+        h2: Enc<Mxe, RiskState>,
+        h3: Enc<Mxe, RiskState>,
+        h4: Enc<Mxe, RiskState>,
+        h5: Enc<Mxe, RiskState>,
+        h6: Enc<Mxe, RiskState>,
+        h7: Enc<Mxe, RiskState>,
+    ) -> u64 {
+        let states = [
+            h0.to_arcis(),
+            h1.to_arcis(),
+            h2.to_arcis(),
+            h3.to_arcis(),
+            h4.to_arcis(),
+            h5.to_arcis(),
+            h6.to_arcis(),
+            h7.to_arcis(),
+        ];
+
+        let mut at_risk_checks: u64 = 0;
+        for (i, state) in states.iter().enumerate() {
+            if (i as u8) < count && state.is_at_risk > 0 {
+                at_risk_checks += 1;
+            }
+        }
+
+        at_risk_checks.reveal()
+    }
+
+    /// Re-encrypts the risk assessment to the owner's x25519 key.
+    /// Unlike `reveal_risk`, nothing is revealed on-chain: only the holder of
+    /// the matching private key can decrypt the result.
     #[instruction]
-    pub fn reveal_risk(risk_state: Enc<Mxe, RiskState>) -> bool {
+    pub fn reveal_risk_to_owner(
+        risk_state: Enc<Mxe, RiskState>,
+        owner: Shared,
+    ) -> Enc<Shared, RiskState> {
         let state = risk_state.to_arcis();
-        (state.is_at_risk > 0).reveal()
+        owner.from_arcis(state)
+    }
+
+    /// Re-encrypts the risk assessment to a viewer the owner granted read
+    /// access, like `reveal_risk_to_owner`. A circuit of its own so viewer
+    /// envelopes can't be mistaken for the owner's.
+    #[instruction]
+    pub fn reveal_risk_to_viewer(
+        risk_state: Enc<Mxe, RiskState>,
+        viewer: Shared,
+    ) -> Enc<Shared, RiskState> {
+        viewer.from_arcis(risk_state.to_arcis())
+    }
+
+    /// Re-encrypts the health factor to the owner's x25519 key, like
+    /// `reveal_risk_to_owner`.
+    #[instruction]
+    pub fn reveal_health_factor_to_owner(
+        health_factor: Enc<Mxe, HealthFactor>,
+        owner: Shared,
+    ) -> Enc<Shared, HealthFactor> {
+        owner.from_arcis(health_factor.to_arcis())
+    }
+
+    /// Reveals only whether the health factor is at least
+    /// `min_health_factor_bps`, for solvency attestations: the answer is
+    /// public, the factor itself stays encrypted.
+    #[instruction]
+    pub fn attest_health(
+        health_factor: Enc<Mxe, HealthFactor>,
+        min_health_factor_bps: u64,
+    ) -> bool {
+        (health_factor.to_arcis().health_factor_bps >= min_health_factor_bps).reveal()
+    }
+
+    /// Compares the health factor to a stop-loss trigger, revealing
+    /// `[fired, action, max_slippage_bps]`. Until the order fires all three
+    /// are 0, so neither the trigger nor the order leaks.
+    #[instruction]
+    pub fn evaluate_stop_loss(
+        config: Enc<Shared, StopLossConfig>,
+        health_factor: Enc<Mxe, HealthFactor>,
+    ) -> [u64; 3] {
+        let config = config.to_arcis();
+        let fired = health_factor.to_arcis().health_factor_bps <= config.trigger_health_factor_bps;
+        let order = if fired {
+            [1, config.action, config.max_slippage_bps]
+        } else {
+            [0, 0, 0]
+        };
+        order.reveal()
+    }
+
+    /// Health check of a perpetual futures position at the plaintext oracle
+    /// `mark_price` ± `confidence`, in micro-USD per token. The mark is taken
+    /// at the edge of the confidence interval that hurts the position.
+    ///
+    /// Equity is the initial margin (entry notional over leverage) plus the
+    /// unrealized PnL at the mark, less funding owed; the maintenance
+    /// requirement is `maintenance_margin_bps` of the notional at the mark.
+    /// Below the requirement the position is liquidatable.
+    /// - 3 (critical): equity under 1.25x the maintenance requirement
+    /// - 2 (medium): under 1.5x
+    /// - 1 (low): under 2x
+    /// - 0 (safe): otherwise
+    // Arcis has no `saturating_sub`
+    #[allow(clippy::implicit_saturating_sub)]
+    #[instruction]
+    pub fn check_perp_health(
+        position: Enc<Shared, PerpPosition>,
+        risk_state: Enc<Mxe, RiskState>,
+        mark_price: u64,
+        confidence: u64,
+    ) -> Enc<Mxe, RiskState> {
+        let pos = position.to_arcis();
+        let is_long = pos.is_long == 1;
+
+        let low_mark = if mark_price > confidence {
+            mark_price - confidence
+        } else {
+            0
+        };
+        let mark = (if is_long {
+            low_mark
+        } else {
+            mark_price + confidence
+        }) as u128;
+        let entry = pos.entry_price as u128;
+        let size = pos.size as u128;
+        let leverage = pos.leverage_bps as u128;
+
+        // Amounts are in 10^-12 USD (millionths of a token times micro-USD)
+        // and multiplied by the leverage, so the initial margin, notional *
+        // 10_000 / leverage, needs no division.
+        let rise = if mark > entry { mark - entry } else { 0 };
+        let fall = if entry > mark { entry - mark } else { 0 };
+        let (gain, loss) = if is_long { (rise, fall) } else { (fall, rise) };
+        let assets = size * entry * 10_000 + size * gain * leverage;
+        let liabilities =
+            size * loss * leverage + (pos.funding_owed_cents as u128) * 10_000_000_000 * leverage;
+        let maintenance = (pos.maintenance_margin_bps as u128) * size * mark * leverage;
+
+        let severity: u64 = if perp_equity_below(assets, liabilities, maintenance, 5, 4) {
+            3
+        } else if perp_equity_below(assets, liabilities, maintenance, 3, 2) {
+            2
+        } else if perp_equity_below(assets, liabilities, maintenance, 2, 1) {
+            1
+        } else {
+            0
+        };
+
+        risk_state.owner.from_arcis(RiskState {
+            is_at_risk: if severity > 0 { 1 } else { 0 },
+            severity,
+            last_price: mark_price,
+        })
+    }
+
+    /// Whether `assets - liabilities` is under `num / den` times the
+    /// maintenance requirement, with `maintenance` scaled by 10_000 (it is
+    /// in basis points) on top of the scale of the other two.
+    fn perp_equity_below(
+        assets: u128,
+        liabilities: u128,
+        maintenance: u128,
+        num: u128,
+        den: u128,
+    ) -> bool {
+        assets * den * 10_000 < liabilities * den * 10_000 + maintenance * num
+    }
+
+    /// Grades a position by a weighted 0-100 score instead of the first
+    /// threshold it crosses. Each signal scores 0 to 100:
+    /// - liquidation proximity: 100 at the liquidation threshold, 0 once the
+    ///   collateral ratio is 5_000 bps above it
+    /// - volatility: the price move since the last priced check, 100 at 20%;
+    ///   0 while `price` or the stored price is 0
+    /// - concentration: the owner's `concentration_bps`, 100 at 10_000
+    /// - TVL trend: the protocol's drop from `previous_tvl` to `tvl`, 100 at
+    ///   50%; 0 while `previous_tvl` is 0
+    ///
+    /// `weights` are those of `RiskModelConfig`, in the order above, and sum
+    /// to 100. The score is the weighted mean; `bands` are the lowest scores
+    /// of severity 1, 2 and 3. Returns the graded risk state and the score,
+    /// the latter under the fresh nonce of `mxe`.
+    // Arcis has no `saturating_sub` or `abs_diff`
+    #[allow(
+        clippy::too_many_arguments,
+        clippy::implicit_saturating_sub,
+        clippy::manual_abs_diff
+    )]
+    #[instruction]
+    pub fn compute_risk_score(
+        mxe: Mxe,
+        position: Enc<Shared, ScoredPosition>,
+        risk_state: Enc<Mxe, RiskState>,
+        weights: [u64; 4],
+        bands: [u64; 3],
+        price: u64,
+        tvl: u64,
+        previous_tvl: u64,
+    ) -> (Enc<Mxe, RiskState>, Enc<Mxe, RiskScore>) {
+        let pos = position.to_arcis();
+        let prev = risk_state.to_arcis();
+
+        let buffer = if pos.collateral_ratio > pos.liquidation_threshold {
+            pos.collateral_ratio - pos.liquidation_threshold
+        } else {
+            0
+        };
+        let proximity = 100 - subscore(buffer as u128, 5_000);
+
+        let mut volatility: u64 = 0;
+        if price > 0 && prev.last_price > 0 {
+            let delta = if price > prev.last_price {
+                price - prev.last_price
+            } else {
+                prev.last_price - price
+            };
+            volatility = subscore(delta as u128 * 10_000 / prev.last_price as u128, 2_000);
+        }
+
+        let concentration = subscore(pos.concentration_bps as u128, 10_000);
+
+        let mut tvl_trend: u64 = 0;
+        if previous_tvl > 0 && tvl < previous_tvl {
+            let drop_bps = (previous_tvl - tvl) as u128 * 10_000 / previous_tvl as u128;
+            tvl_trend = subscore(drop_bps, 5_000);
+        }
+
+        let score = (weights[0] * proximity
+            + weights[1] * volatility
+            + weights[2] * concentration
+            + weights[3] * tvl_trend)
+            / 100;
+        let severity: u64 = if score >= bands[2] {
+            3
+        } else if score >= bands[1] {
+            2
+        } else if score >= bands[0] {
+            1
+        } else {
+            0
+        };
+
+        let state = RiskState {
+            is_at_risk: if severity > 0 { 1 } else { 0 },
+            severity,
+            last_price: if price > 0 { price } else { prev.last_price },
+        };
+        (
+            risk_state.owner.from_arcis(state),
+            mxe.from_arcis(RiskScore { score }),
+        )
+    }
+
+    /// `value` as a 0-100 score that reaches 100 at `full_at`.
+    fn subscore(value: u128, full_at: u128) -> u64 {
+        let score = value * 100 / full_at;
+        if score > 100 {
+            100
+        } else {
+            score as u64
+        }
+    }
+
+    /// Re-encrypts the risk state under the fresh nonce of `mxe`, so
+    /// ciphertexts and nonces captured before the rotation no longer
+    /// correspond to the stored state.
+    #[instruction]
+    pub fn rotate_encryption(mxe: Mxe, risk_state: Enc<Mxe, RiskState>) -> Enc<Mxe, RiskState> {
+        mxe.from_arcis(risk_state.to_arcis())
     }
 }
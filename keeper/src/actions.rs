@@ -0,0 +1,116 @@
+//! Running auto actions that critical reveals made due, and stop-loss
+//! orders that fired.
+//!
+//! Neither `reveal_risk_callback` nor `evaluate_stop_loss_callback` calls
+//! the target program itself: a target that fails would fail the callback,
+//! losing its result and leaving the position pending. They mark the
+//! action or order due instead (`AutoActionDue`, `StopLossTriggered` with
+//! an executor), and the keeper sends it with `execute_auto_action` or
+//! `execute_stop_loss` in a transaction of its own. A send that fails
+//! leaves it due, for anyone to send again.
+
+use anchor_client::{
+    solana_sdk::{
+        pubkey::Pubkey,
+        signature::{Keypair, Signature},
+        signer::Signer,
+        transaction::Transaction,
+    },
+    EventUnsubscriber, Program,
+};
+use anyhow::Result;
+use sentinel::{
+    AutoActionConfig, AutoActionDue, PositionAccount, StopLossAccount, StopLossTriggered,
+};
+use sentinel_client::instructions;
+use std::{ops::Deref, sync::Arc};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+
+/// What became due on a position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    AutoAction,
+    StopLoss,
+}
+
+impl Kind {
+    /// The instruction that sends it.
+    pub fn instruction(self) -> &'static str {
+        match self {
+            Kind::AutoAction => "execute_auto_action",
+            Kind::StopLoss => "execute_stop_loss",
+        }
+    }
+}
+
+/// An auto action or stop-loss order due on `owner`'s position
+/// `position_id`, at `position`.
+#[derive(Debug, Clone, Copy)]
+pub struct Due {
+    pub kind: Kind,
+    pub owner: Pubkey,
+    pub position_id: u32,
+    pub position: Pubkey,
+}
+
+/// Subscribes to `AutoActionDue` and to `StopLossTriggered` for orders with
+/// an executor. Events stop once the unsubscribers are dropped.
+pub async fn subscribe<C>(
+    program: &Program<C>,
+) -> Result<(UnboundedReceiver<Due>, [EventUnsubscriber<'_>; 2])>
+where
+    C: Deref<Target = Keypair> + Clone,
+{
+    let (tx, rx) = unbounded_channel();
+    let actions = tx.clone();
+    let auto_actions = program
+        .on(move |_, event: AutoActionDue| {
+            let _ = actions.send(Due {
+                kind: Kind::AutoAction,
+                owner: event.owner,
+                position_id: event.position_id,
+                position: event.position,
+            });
+        })
+        .await?;
+    let stop_losses = program
+        .on(move |_, event: StopLossTriggered| {
+            if event.executor.is_some() {
+                let _ = tx.send(Due {
+                    kind: Kind::StopLoss,
+                    owner: event.owner,
+                    position_id: event.position_id,
+                    position: event.position,
+                });
+            }
+        })
+        .await?;
+    Ok((rx, [auto_actions, stop_losses]))
+}
+
+/// Sends what `due` names, as its account reads now.
+pub async fn execute(
+    program: &Program<Arc<Keypair>>,
+    payer: &Keypair,
+    due: &Due,
+) -> Result<Signature> {
+    let position: PositionAccount = program.account(due.position).await?;
+    let ix = match due.kind {
+        Kind::AutoAction => {
+            let action: AutoActionConfig = program
+                .account(instructions::auto_action_pda(&due.position))
+                .await?;
+            instructions::execute_auto_action(&position.registrant, due.position_id, &action)
+        }
+        Kind::StopLoss => {
+            let stop_loss: StopLossAccount = program
+                .account(instructions::stop_loss_pda(&due.position))
+                .await?;
+            instructions::execute_stop_loss(&position.registrant, due.position_id, &stop_loss)
+        }
+    };
+    let rpc = program.internal_rpc();
+    let blockhash = rpc.get_latest_blockhash().await?;
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&payer.pubkey()), &[payer], blockhash);
+    Ok(rpc.send_and_confirm_transaction(&tx).await?)
+}
@@ -0,0 +1,61 @@
+//! Sources of fresh position data. The keeper re-reads a position through
+//! its adapter every cycle and encrypts the result; the plaintext never
+//! leaves the keeper.
+
+use anyhow::{Context, Result};
+use sentinel_client::PositionData;
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "adapter", rename_all = "snake_case")]
+pub enum Adapter {
+    /// Fixed values, for testing a deployment end to end
+    Static {
+        position_value: u64,
+        collateral_ratio: u64,
+        liquidation_threshold: u64,
+    },
+    /// `GET url` returning `{ "position_value", "collateral_ratio",
+    /// "liquidation_threshold" }` in the units of `PositionData`. Protocol
+    /// integrations (Kamino, MarginFi, ...) sit behind such an endpoint.
+    Http { url: String },
+}
+
+#[derive(Deserialize)]
+struct HttpPosition {
+    position_value: u64,
+    collateral_ratio: u64,
+    liquidation_threshold: u64,
+}
+
+impl Adapter {
+    pub async fn fetch(&self, http: &reqwest::Client) -> Result<PositionData> {
+        match self {
+            Adapter::Static {
+                position_value,
+                collateral_ratio,
+                liquidation_threshold,
+            } => Ok(PositionData {
+                position_value: *position_value,
+                collateral_ratio: *collateral_ratio,
+                liquidation_threshold: *liquidation_threshold,
+            }),
+            Adapter::Http { url } => {
+                let position: HttpPosition = http
+                    .get(url)
+                    .send()
+                    .await
+                    .and_then(reqwest::Response::error_for_status)
+                    .with_context(|| format!("failed to fetch {url}"))?
+                    .json()
+                    .await
+                    .with_context(|| format!("unexpected response from {url}"))?;
+                Ok(PositionData {
+                    position_value: position.position_value,
+                    collateral_ratio: position.collateral_ratio,
+                    liquidation_threshold: position.liquidation_threshold,
+                })
+            }
+        }
+    }
+}
@@ -0,0 +1,270 @@
+use crate::adapters::Adapter;
+use anchor_lang::prelude::Pubkey;
+use anyhow::{ensure, Context, Result};
+use serde::{Deserialize, Deserializer};
+use std::path::{Path, PathBuf};
+
+/// `keeper.toml`; see `keeper.example.toml`.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// `localnet`, `devnet`, or an RPC URL
+    pub cluster: String,
+    /// Arcium cluster the MXE runs on
+    pub cluster_offset: u32,
+    /// Keeper keypair: a position's owner or its delegated keeper
+    #[serde(default = "default_keypair")]
+    pub keypair: String,
+    /// Seconds between monitoring cycles
+    #[serde(default = "default_interval")]
+    pub interval_secs: u64,
+    /// URLs that receive a JSON POST for every `RiskRevealed` and
+    /// `ActionRequired` event
+    #[serde(default)]
+    pub webhooks: Vec<String>,
+    /// File recording checks sent but not yet seen to land; see `ledger`
+    #[serde(default = "default_ledger")]
+    pub ledger: PathBuf,
+    /// Submit through a Jito block engine, falling back to RPC
+    #[serde(default)]
+    pub jito: Option<JitoConfig>,
+    /// Watch positions close to liquidation between cycles
+    #[serde(default)]
+    pub hot: Option<HotConfig>,
+    /// Share the positions with keepers in other regions; see `leases`
+    #[serde(default)]
+    pub region: Option<RegionConfig>,
+    /// Accept owner-signed requests for urgent checks; see `inbound`
+    #[serde(default)]
+    pub inbound: Option<InboundConfig>,
+    /// Plugins to load, by registered name; see `plugins`
+    #[serde(default)]
+    pub plugins: Vec<PluginConfig>,
+    #[serde(default)]
+    pub positions: Vec<PositionConfig>,
+}
+
+/// `[hot]`: the latency-optimized path for positions near liquidation.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HotConfig {
+    /// A position is hot while its collateral ratio is less than this many
+    /// basis points above its liquidation threshold
+    #[serde(default = "default_hot_buffer")]
+    pub buffer_bps: u64,
+    /// Milliseconds between adapter reads of hot positions
+    #[serde(default = "default_hot_poll")]
+    pub poll_ms: u64,
+}
+
+/// `[region]`: failover between keepers running in several regions under
+/// one key.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RegionConfig {
+    /// This keeper's region; lower regions have priority, so 0 is the primary
+    pub priority: u8,
+    /// Shards the positions are split into; every region must agree
+    #[serde(default = "default_shards")]
+    pub shards: u16,
+    /// Seconds a lease lasts without a renewal, longer than a cycle
+    #[serde(default = "default_lease")]
+    pub lease_secs: i64,
+}
+
+/// `[inbound]`: the HTTP endpoint for urgent checks.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct InboundConfig {
+    /// Address to listen on, e.g. `127.0.0.1:8787`
+    pub listen: String,
+    /// Seconds a signed request stays valid
+    #[serde(default = "default_inbound_max_age")]
+    pub max_age_secs: i64,
+    /// Least seconds between two urgent checks of one position
+    #[serde(default = "default_inbound_interval")]
+    pub min_interval_secs: u64,
+}
+
+/// `[jito]`: bundle submission for congested blocks.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JitoConfig {
+    /// e.g. `https://mainnet.block-engine.jito.wtf`
+    pub block_engine_url: String,
+    /// Lamports tipped per bundle
+    pub tip_lamports: u64,
+    /// Tip account to pay; by default one of the block engine's
+    #[serde(default, deserialize_with = "optional_pubkey")]
+    pub tip_account: Option<Pubkey>,
+    /// Seconds to wait for a bundle before resending over RPC
+    #[serde(default = "default_confirm_timeout")]
+    pub confirm_timeout_secs: u64,
+}
+
+/// `[[plugins]]`: one plugin and its options, which only the plugin reads.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PluginConfig {
+    pub name: String,
+    #[serde(default)]
+    pub options: toml::Table,
+}
+
+/// A position to check and where its data comes from.
+#[derive(Debug, Clone, Deserialize)]
+pub struct PositionConfig {
+    #[serde(deserialize_with = "pubkey")]
+    pub owner: Pubkey,
+    pub position_id: u32,
+    /// Liquidation penalty of the position's protocol, for `savings`
+    #[serde(default)]
+    pub liquidation_penalty_bps: Option<u16>,
+    #[serde(flatten)]
+    pub adapter: Adapter,
+}
+
+impl Config {
+    pub fn load(path: &Path) -> Result<Self> {
+        let source = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let config: Self = toml::from_str(&source)
+            .with_context(|| format!("invalid config {}", path.display()))?;
+        if let Some(region) = &config.region {
+            ensure!(region.shards > 0, "region.shards must be positive");
+            ensure!(
+                region.lease_secs > config.interval_secs as i64
+                    && region.lease_secs <= sentinel::MAX_LEASE_SECS,
+                "region.lease_secs must exceed interval_secs and be at most {}",
+                sentinel::MAX_LEASE_SECS
+            );
+        }
+        Ok(config)
+    }
+
+    pub fn keypair_path(&self) -> PathBuf {
+        match self.keypair.strip_prefix("~/") {
+            Some(rest) => PathBuf::from(std::env::var("HOME").unwrap_or_default()).join(rest),
+            None => PathBuf::from(&self.keypair),
+        }
+    }
+
+    pub fn position(&self, owner: &Pubkey, position_id: u32) -> Option<&PositionConfig> {
+        self.positions
+            .iter()
+            .find(|p| p.owner == *owner && p.position_id == position_id)
+    }
+}
+
+fn default_keypair() -> String {
+    "~/.config/solana/id.json".into()
+}
+
+fn default_interval() -> u64 {
+    30
+}
+
+fn default_hot_buffer() -> u64 {
+    1_500
+}
+
+fn default_hot_poll() -> u64 {
+    400
+}
+
+fn default_shards() -> u16 {
+    16
+}
+
+fn default_lease() -> i64 {
+    90
+}
+
+fn default_inbound_max_age() -> i64 {
+    60
+}
+
+fn default_inbound_interval() -> u64 {
+    30
+}
+
+fn default_ledger() -> PathBuf {
+    "keeper-ledger.json".into()
+}
+
+fn default_confirm_timeout() -> u64 {
+    10
+}
+
+fn pubkey<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Pubkey, D::Error> {
+    String::deserialize(deserializer)?
+        .parse()
+        .map_err(serde::de::Error::custom)
+}
+
+fn optional_pubkey<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Pubkey>, D::Error> {
+    pubkey(deserializer).map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example_config_parses() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("keeper.example.toml");
+        let config = Config::load(&path).unwrap();
+        assert_eq!(config.cluster_offset, 456);
+        assert_eq!(config.positions.len(), 2);
+        assert!(matches!(
+            config.positions[0].adapter,
+            Adapter::Static { .. }
+        ));
+        assert!(matches!(config.positions[1].adapter, Adapter::Http { .. }));
+        assert!(config.jito.is_none());
+        assert!(config.plugins.is_empty());
+    }
+
+    #[test]
+    fn plugins_keep_their_options() {
+        let config: Config = toml::from_str(
+            r#"
+            cluster = "devnet"
+            cluster_offset = 1
+
+            [[plugins]]
+            name = "jsonl"
+            options = { path = "events.jsonl" }
+
+            [[plugins]]
+            name = "failed_first"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.plugins.len(), 2);
+        assert_eq!(
+            config.plugins[0].options["path"].as_str(),
+            Some("events.jsonl")
+        );
+        assert!(config.plugins[1].options.is_empty());
+    }
+
+    #[test]
+    fn jito_section_parses() {
+        let config: Config = toml::from_str(
+            r#"
+            cluster = "mainnet"
+            cluster_offset = 1
+
+            [jito]
+            block_engine_url = "https://mainnet.block-engine.jito.wtf"
+            tip_lamports = 10000
+            "#,
+        )
+        .unwrap();
+        let jito = config.jito.unwrap();
+        assert_eq!(jito.tip_lamports, 10_000);
+        assert_eq!(jito.tip_account, None);
+        assert_eq!(jito.confirm_timeout_secs, 10);
+    }
+}
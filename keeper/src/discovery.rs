@@ -0,0 +1,122 @@
+//! Finding the positions the keeper checks without scanning every position
+//! of the program.
+//!
+//! The keeper only checks positions it has an adapter for, configured in
+//! `keeper.toml` or returned by a discovery plugin, so it only needs the
+//! positions of their owners. Those are read through the owners'
+//! `OwnerIndex` pages: each page lists position ids, and a position's
+//! address follows from its registrant and id. Positions the index can't
+//! list, registered before it existed or transferred to their owner, are
+//! found with a scan filtered to the owner, only for owners with such a
+//! position.
+
+use crate::config::PositionConfig;
+use anchor_client::{
+    solana_sdk::{pubkey::Pubkey, signature::Keypair},
+    Program,
+};
+use anchor_lang::AccountDeserialize;
+use anyhow::Result;
+use sentinel::{OwnerIndex, PositionAccount};
+use sentinel_client::{cache::MAX_ACCOUNTS_PER_REQUEST, instructions};
+use solana_rpc_client_api::filter::{Memcmp, RpcFilterType};
+use std::{collections::BTreeSet, sync::Arc};
+
+/// The positions of the owners of `wanted`, by address.
+pub async fn positions(
+    program: &Program<Arc<Keypair>>,
+    wanted: &[PositionConfig],
+) -> Result<Vec<(Pubkey, PositionAccount)>> {
+    let owners: BTreeSet<Pubkey> = wanted.iter().map(|config| config.owner).collect();
+    let mut addresses = Vec::new();
+    for owner in &owners {
+        for page in 0.. {
+            let Ok(index) = program
+                .account::<OwnerIndex>(instructions::owner_index_pda(owner, page))
+                .await
+            else {
+                break;
+            };
+            addresses.extend(
+                index
+                    .position_ids
+                    .iter()
+                    .map(|id| instructions::position_pda(owner, *id)),
+            );
+        }
+    }
+
+    let rpc = program.internal_rpc();
+    let mut positions = Vec::with_capacity(addresses.len());
+    for chunk in addresses.chunks(MAX_ACCOUNTS_PER_REQUEST) {
+        for (address, account) in chunk.iter().zip(rpc.get_multiple_accounts(chunk).await?) {
+            if let Some(position) =
+                account.and_then(|a| PositionAccount::try_deserialize(&mut &a.data[..]).ok())
+            {
+                positions.push((*address, position));
+            }
+        }
+    }
+
+    for owner in unlisted(wanted, &positions) {
+        let filters = vec![RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+            PositionAccount::OWNER_OFFSET,
+            owner.as_ref(),
+        ))];
+        for (address, position) in program.accounts::<PositionAccount>(filters).await? {
+            if !positions.iter().any(|(listed, _)| *listed == address) {
+                positions.push((address, position));
+            }
+        }
+    }
+    Ok(positions)
+}
+
+/// Owners with a wanted position missing from `found`.
+fn unlisted(wanted: &[PositionConfig], found: &[(Pubkey, PositionAccount)]) -> BTreeSet<Pubkey> {
+    let found: BTreeSet<(Pubkey, u32)> = found
+        .iter()
+        .map(|(_, position)| (position.owner, position.position_id))
+        .collect();
+    wanted
+        .iter()
+        .filter(|config| !found.contains(&(config.owner, config.position_id)))
+        .map(|config| config.owner)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::Adapter;
+    use anchor_lang::{Discriminator, Space};
+
+    fn config(owner: Pubkey, position_id: u32) -> PositionConfig {
+        PositionConfig {
+            owner,
+            position_id,
+            liquidation_penalty_bps: None,
+            adapter: Adapter::Http {
+                url: "http://localhost/".into(),
+            },
+        }
+    }
+
+    fn position(owner: Pubkey, position_id: u32) -> PositionAccount {
+        let mut data = PositionAccount::DISCRIMINATOR.to_vec();
+        data.resize(8 + PositionAccount::INIT_SPACE, 0);
+        let mut position = PositionAccount::try_deserialize(&mut data.as_slice()).unwrap();
+        position.owner = owner;
+        position.position_id = position_id;
+        position
+    }
+
+    #[test]
+    fn scans_only_owners_with_unlisted_positions() {
+        let (listed, transferred) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let found = [(Pubkey::new_unique(), position(listed, 1))];
+        let wanted = [config(listed, 1), config(transferred, 2)];
+        assert_eq!(unlisted(&wanted, &found), BTreeSet::from([transferred]));
+        assert!(unlisted(&wanted[..1], &found).is_empty());
+    }
+}
@@ -0,0 +1,150 @@
+//! Pyth feed migrations.
+//!
+//! Pyth sometimes moves an asset to a new price account and lets the old
+//! one go quiet. Every cycle the keeper looks at the feeds its positions are
+//! priced with. A feed that stopped publishing is flagged on-chain with
+//! `flag_price_feed`, and until the upgrade authority names its replacement
+//! the positions on it are checked without a price rather than failing on a
+//! stale one. Once a replacement is named, the keeper re-points every
+//! position still on the old feed with `repoint_price_feeds`.
+
+use anchor_client::solana_sdk::{
+    pubkey::Pubkey, signature::Keypair, signer::Signer, transaction::Transaction,
+};
+use anchor_lang::AccountDeserialize;
+use anyhow::Result;
+use sentinel::{oracle, FeedMigration, PositionAccount};
+use sentinel_client::instructions;
+use solana_rpc_client::nonblocking::rpc_client::RpcClient;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    time::SystemTime,
+};
+
+/// Positions re-pointed per transaction.
+const REPOINTS_PER_TRANSACTION: usize = 16;
+
+/// What a feed needs this cycle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Action {
+    /// Publishing, nothing to do
+    Live,
+    /// Stopped publishing and not flagged yet
+    Flag,
+    /// Flagged, waiting for a replacement
+    AwaitReplacement,
+    /// Replaced; positions still on it move over
+    Repoint,
+}
+
+impl Action {
+    /// Whether checks should leave the feed out.
+    fn is_dead(self) -> bool {
+        self != Self::Live
+    }
+}
+
+fn action(feed_data: Option<&[u8]>, migration: Option<&FeedMigration>, now: i64) -> Action {
+    match migration {
+        Some(migration) if migration.replacement.is_some() => Action::Repoint,
+        Some(_) => Action::AwaitReplacement,
+        None if oracle::is_deprecated(feed_data.unwrap_or_default(), now) => Action::Flag,
+        None => Action::Live,
+    }
+}
+
+/// Flags dead feeds and re-points positions off replaced ones, returning
+/// the feeds this cycle's checks should leave out. Failed transactions are
+/// logged and tried again next cycle.
+pub async fn maintain(
+    rpc: &RpcClient,
+    payer: &Keypair,
+    positions: &[(Pubkey, PositionAccount)],
+) -> Result<BTreeSet<Pubkey>> {
+    let mut by_feed: BTreeMap<Pubkey, Vec<Pubkey>> = BTreeMap::new();
+    for (address, position) in positions {
+        if let Some(feed) = position.price_feed {
+            by_feed.entry(feed).or_default().push(*address);
+        }
+    }
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)?
+        .as_secs() as i64;
+
+    let mut dead = BTreeSet::new();
+    let feeds: Vec<Pubkey> = by_feed.keys().copied().collect();
+    for chunk in feeds.chunks(50) {
+        let addresses: Vec<Pubkey> = chunk
+            .iter()
+            .flat_map(|feed| [*feed, instructions::feed_migration_pda(feed)])
+            .collect();
+        let accounts = rpc.get_multiple_accounts(&addresses).await?;
+        for (feed, pair) in chunk.iter().zip(accounts.chunks(2)) {
+            let migration = pair[1]
+                .as_ref()
+                .and_then(|a| FeedMigration::try_deserialize(&mut &a.data[..]).ok());
+            let action = action(
+                pair[0].as_ref().map(|a| &a.data[..]),
+                migration.as_ref(),
+                now,
+            );
+            if action.is_dead() {
+                dead.insert(*feed);
+            }
+            let ixs: Vec<_> = match action {
+                Action::Live | Action::AwaitReplacement => continue,
+                Action::Flag => vec![instructions::flag_price_feed(&payer.pubkey(), *feed)],
+                Action::Repoint => by_feed[feed]
+                    .chunks(REPOINTS_PER_TRANSACTION)
+                    .map(|positions| instructions::repoint_price_feeds(feed, positions))
+                    .collect(),
+            };
+            for ix in ixs {
+                let blockhash = rpc.get_latest_blockhash().await?;
+                let tx = Transaction::new_signed_with_payer(
+                    &[ix],
+                    Some(&payer.pubkey()),
+                    &[payer],
+                    blockhash,
+                );
+                match rpc.send_and_confirm_transaction(&tx).await {
+                    Ok(sig) => println!("feed {feed}: {action:?} {sig}"),
+                    Err(err) => eprintln!("feed {feed}: {action:?} failed: {err}"),
+                }
+            }
+        }
+    }
+    Ok(dead)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn migration(replacement: Option<Pubkey>) -> FeedMigration {
+        FeedMigration {
+            bump: 0,
+            old_feed: Pubkey::new_unique(),
+            flagged_by: Pubkey::new_unique(),
+            flagged_at: 0,
+            replacement,
+            migrated_at: 0,
+            repointed: 0,
+        }
+    }
+
+    #[test]
+    fn flags_only_unflagged_dead_feeds_and_repoints_replaced_ones() {
+        let now = 10 * oracle::FEED_DEPRECATED_AFTER_SECS;
+        assert_eq!(action(None, None, now), Action::Flag);
+        assert_eq!(action(Some(&[]), None, now), Action::Flag);
+        assert_eq!(
+            action(None, Some(&migration(None)), now),
+            Action::AwaitReplacement
+        );
+        let replaced = migration(Some(Pubkey::new_unique()));
+        assert_eq!(action(None, Some(&replaced), now), Action::Repoint);
+        assert!(!Action::Live.is_dead());
+        assert!(Action::AwaitReplacement.is_dead());
+    }
+}
@@ -0,0 +1,61 @@
+//! Heartbeats for positions the keeper skips. A position checked too
+//! recently has nothing to check, but the owner's heartbeat deadline keeps
+//! running; `heartbeat` records on-chain that the keeper is still watching,
+//! so `flag_stale_position` isn't pulled on a keeper that is alive. A
+//! heartbeat goes out once half the deadline has passed since the keeper
+//! was last seen, so a quiet position costs at most two per deadline.
+
+use anchor_client::solana_sdk::{
+    signature::{Keypair, Signature},
+    signer::Signer,
+    transaction::Transaction,
+};
+use anyhow::Result;
+use sentinel::PositionAccount;
+use sentinel_client::instructions;
+use solana_rpc_client::nonblocking::rpc_client::RpcClient;
+
+/// Whether `position`, skipped at `now`, is due a heartbeat.
+pub fn due(position: &PositionAccount, now: i64) -> bool {
+    let deadline = position.heartbeat_deadline_secs as i64;
+    let seen = position.last_seen.max(position.last_check);
+    deadline > 0 && now - seen >= deadline / 2
+}
+
+/// Sends a heartbeat for `position`, signed by `payer` as its owner or
+/// keeper.
+pub async fn send(
+    rpc: &RpcClient,
+    payer: &Keypair,
+    position: &PositionAccount,
+) -> Result<Signature> {
+    let ix = instructions::heartbeat(&payer.pubkey(), &position.registrant, position.position_id);
+    let blockhash = rpc.get_latest_blockhash().await?;
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&payer.pubkey()), &[payer], blockhash);
+    Ok(rpc.send_and_confirm_transaction(&tx).await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::{AccountDeserialize, Discriminator, Space};
+
+    fn position(heartbeat_deadline_secs: u32, last_check: i64, last_seen: i64) -> PositionAccount {
+        let mut data = PositionAccount::DISCRIMINATOR.to_vec();
+        data.resize(8 + PositionAccount::INIT_SPACE, 0);
+        let mut position = PositionAccount::try_deserialize(&mut data.as_slice()).unwrap();
+        position.heartbeat_deadline_secs = heartbeat_deadline_secs;
+        position.last_check = last_check;
+        position.last_seen = last_seen;
+        position
+    }
+
+    #[test]
+    fn due_after_half_the_deadline_since_last_seen() {
+        assert!(!due(&position(0, 0, 0), 1_000));
+        assert!(!due(&position(600, 900, 900), 1_000));
+        assert!(due(&position(600, 700, 700), 1_000));
+        assert!(!due(&position(600, 100, 900), 1_000));
+        assert!(due(&position(600, 700, 0), 1_000));
+    }
+}
@@ -0,0 +1,176 @@
+//! The hot path: positions close to liquidation are watched between cycles
+//! and checked as soon as their data moves.
+//!
+//! A position is hot when the data the keeper last read puts its collateral
+//! ratio within `buffer_bps` of the liquidation threshold. Everything its
+//! check needs besides the new data is cached when the cycle checks it: the
+//! accounts, whether history is recorded, and a blockhash refreshed in the
+//! background. An urgent check then costs one adapter read, one encryption
+//! and the send, with no RPC round trip before it.
+//!
+//! A hot position is checked at most once per computation. It is marked in
+//! flight when a check is sent and settled by the `HealthCheckCompleted`
+//! event, or by the next cycle finding it idle.
+
+use crate::{config::HotConfig, Target};
+use anchor_client::{
+    solana_sdk::{commitment_config::CommitmentConfig, hash::Hash, pubkey::Pubkey},
+    EventUnsubscriber, Program,
+};
+use anyhow::Result;
+use sentinel::HealthCheckCompleted;
+use sentinel_client::PositionData;
+use solana_rpc_client::nonblocking::rpc_client::RpcClient;
+use std::{collections::HashMap, ops::Deref, sync::Mutex, time::Duration};
+use tokio::sync::{
+    mpsc::{unbounded_channel, UnboundedReceiver},
+    watch,
+};
+
+/// How often the cached blockhash is replaced. Blockhashes stay valid for
+/// about a minute, so this leaves plenty of margin.
+const BLOCKHASH_REFRESH: Duration = Duration::from_secs(2);
+
+/// Everything a hot position's check needs besides fresh data.
+#[derive(Debug, Clone)]
+pub struct HotPosition {
+    pub target: Target,
+    /// Data the last check was sent with
+    pub last_data: PositionData,
+    in_flight: bool,
+}
+
+impl HotConfig {
+    /// Whether `data` puts the position close enough to liquidation to
+    /// watch it.
+    pub fn is_hot(&self, data: &PositionData) -> bool {
+        data.collateral_ratio < data.liquidation_threshold.saturating_add(self.buffer_bps)
+    }
+}
+
+pub struct HotSet {
+    config: HotConfig,
+    positions: Mutex<HashMap<Pubkey, HotPosition>>,
+    completed: Mutex<UnboundedReceiver<(Pubkey, u32)>>,
+    blockhash: watch::Receiver<Hash>,
+}
+
+impl HotSet {
+    /// Starts refreshing the blockhash and listening for completed checks.
+    /// Events stop once the unsubscriber is dropped.
+    pub async fn start<C>(
+        config: HotConfig,
+        program: &Program<C>,
+        rpc_url: String,
+    ) -> Result<(Self, EventUnsubscriber<'_>)>
+    where
+        C: Deref<Target = anchor_client::solana_sdk::signature::Keypair> + Clone,
+    {
+        let rpc = RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed());
+        let (tx, blockhash) = watch::channel(rpc.get_latest_blockhash().await?);
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(BLOCKHASH_REFRESH).await;
+                match rpc.get_latest_blockhash().await {
+                    Ok(hash) => {
+                        let _ = tx.send(hash);
+                    }
+                    Err(err) => eprintln!("blockhash refresh failed: {err}"),
+                }
+            }
+        });
+
+        let (events, completed) = unbounded_channel();
+        let unsubscriber = program
+            .on(move |_, event: HealthCheckCompleted| {
+                let _ = events.send((event.owner, event.position_id));
+            })
+            .await?;
+
+        Ok((
+            Self {
+                config,
+                positions: Mutex::new(HashMap::new()),
+                completed: Mutex::new(completed),
+                blockhash,
+            },
+            unsubscriber,
+        ))
+    }
+
+    pub fn poll_interval(&self) -> Duration {
+        Duration::from_millis(self.config.poll_ms)
+    }
+
+    pub fn blockhash(&self) -> Hash {
+        *self.blockhash.borrow()
+    }
+
+    /// Records that a check with `data` was just sent to `target`, caching
+    /// it while it's hot and dropping it otherwise.
+    pub fn sent(&self, target: Target, data: PositionData) {
+        let mut positions = self.positions.lock().unwrap();
+        if !self.config.is_hot(&data) {
+            positions.remove(&target.address);
+            return;
+        }
+        positions.insert(
+            target.address,
+            HotPosition {
+                target,
+                last_data: data,
+                in_flight: true,
+            },
+        );
+    }
+
+    /// Settles a position the cycle found idle on chain.
+    pub fn idle(&self, address: &Pubkey) {
+        if let Some(hot) = self.positions.lock().unwrap().get_mut(address) {
+            hot.in_flight = false;
+        }
+    }
+
+    /// Drops a position that can no longer be checked.
+    pub fn remove(&self, address: &Pubkey) {
+        self.positions.lock().unwrap().remove(address);
+    }
+
+    /// Hot positions with no check in flight.
+    pub fn ready(&self) -> Vec<HotPosition> {
+        let mut positions = self.positions.lock().unwrap();
+        let mut completed = self.completed.lock().unwrap();
+        while let Ok((owner, position_id)) = completed.try_recv() {
+            for hot in positions.values_mut() {
+                if hot.target.owner == owner && hot.target.position_id == position_id {
+                    hot.in_flight = false;
+                }
+            }
+        }
+        positions
+            .values()
+            .filter(|hot| !hot.in_flight)
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hot_within_buffer_of_threshold() {
+        let config = HotConfig {
+            buffer_bps: 1_500,
+            poll_ms: 400,
+        };
+        let data = |collateral_ratio| PositionData {
+            position_value: 100_000,
+            collateral_ratio,
+            liquidation_threshold: 11_000,
+        };
+        assert!(config.is_hot(&data(12_499)));
+        assert!(!config.is_hot(&data(12_500)));
+    }
+}
@@ -0,0 +1,341 @@
+//! `[inbound]`: an HTTP endpoint where an owner, or a risk system holding
+//! their key, asks for a position to be checked now rather than next cycle.
+//!
+//! `POST /check` takes a JSON body
+//!
+//! ```json
+//! { "owner": "<pubkey>", "position_id": 1, "timestamp": 1700000000, "signature": "<base58>" }
+//! ```
+//!
+//! where `signature` is the owner's ed25519 signature of [`message`]. A
+//! request is accepted if it's signed, its timestamp is within
+//! `max_age_secs` of the keeper's clock and newer than the last one accepted
+//! for the position, and the position wasn't fast-tracked in the last
+//! `min_interval_secs`. Accepted requests are answered `202` and queued for
+//! the keeper; whether it may check the position is decided there.
+
+use crate::config::InboundConfig;
+use anchor_client::solana_sdk::{pubkey::Pubkey, signature::Signature};
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    sync::mpsc::{channel, Receiver, Sender},
+};
+
+/// Largest request read, headers included.
+const MAX_REQUEST_BYTES: usize = 4096;
+/// Time a client has to send its request.
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+/// Accepted requests waiting for the keeper; beyond this, `503`.
+const QUEUE_LEN: usize = 64;
+
+/// A position to check now.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UrgentCheck {
+    pub owner: Pubkey,
+    pub position_id: u32,
+}
+
+/// What the owner signs to request an urgent check.
+pub fn message(owner: &Pubkey, position_id: u32, timestamp: i64) -> Vec<u8> {
+    format!("sentinel-urgent-check:{owner}:{position_id}:{timestamp}").into_bytes()
+}
+
+#[derive(Debug, Deserialize)]
+struct Request {
+    owner: String,
+    position_id: u32,
+    timestamp: i64,
+    signature: String,
+}
+
+/// Why a request was turned down, as an HTTP status.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Rejection {
+    BadRequest,
+    NotFound,
+    Unauthorized,
+    PayloadTooLarge,
+    TooManyRequests,
+    Unavailable,
+}
+
+impl Rejection {
+    fn status(self) -> &'static str {
+        match self {
+            Self::BadRequest => "400 Bad Request",
+            Self::NotFound => "404 Not Found",
+            Self::Unauthorized => "401 Unauthorized",
+            Self::PayloadTooLarge => "413 Payload Too Large",
+            Self::TooManyRequests => "429 Too Many Requests",
+            Self::Unavailable => "503 Service Unavailable",
+        }
+    }
+}
+
+/// Last accepted request per position.
+struct Limiter {
+    config: InboundConfig,
+    accepted: Mutex<HashMap<(Pubkey, u32), (Instant, i64)>>,
+}
+
+impl Limiter {
+    /// Checks `body`'s signature, freshness and rate at Unix time `now` and
+    /// records it as accepted.
+    fn admit(&self, body: &[u8], now: i64, at: Instant) -> Result<UrgentCheck, Rejection> {
+        let request: Request = serde_json::from_slice(body).map_err(|_| Rejection::BadRequest)?;
+        let owner: Pubkey = request.owner.parse().map_err(|_| Rejection::BadRequest)?;
+        let signature: Signature = request
+            .signature
+            .parse()
+            .map_err(|_| Rejection::BadRequest)?;
+        if (now - request.timestamp).abs() > self.config.max_age_secs
+            || !signature.verify(
+                owner.as_ref(),
+                &message(&owner, request.position_id, request.timestamp),
+            )
+        {
+            return Err(Rejection::Unauthorized);
+        }
+
+        let mut accepted = self.accepted.lock().unwrap();
+        let key = (owner, request.position_id);
+        if let Some((last_at, last_timestamp)) = accepted.get(&key) {
+            // A replayed request is as stale as an old one
+            if request.timestamp <= *last_timestamp {
+                return Err(Rejection::Unauthorized);
+            }
+            if at.duration_since(*last_at) < Duration::from_secs(self.config.min_interval_secs) {
+                return Err(Rejection::TooManyRequests);
+            }
+        }
+        accepted.insert(key, (at, request.timestamp));
+        Ok(UrgentCheck {
+            owner,
+            position_id: request.position_id,
+        })
+    }
+}
+
+/// Starts listening on `config.listen`, returning the accepted checks.
+pub async fn serve(config: InboundConfig) -> Result<Receiver<UrgentCheck>> {
+    let listener = TcpListener::bind(&config.listen)
+        .await
+        .with_context(|| format!("failed to listen on {}", config.listen))?;
+    println!("inbound checks on {}", config.listen);
+    let limiter = Arc::new(Limiter {
+        config,
+        accepted: Mutex::new(HashMap::new()),
+    });
+    let (tx, rx) = channel(QUEUE_LEN);
+    tokio::spawn(async move {
+        loop {
+            let stream = match listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(err) => {
+                    eprintln!("inbound accept failed: {err}");
+                    continue;
+                }
+            };
+            let (limiter, tx) = (limiter.clone(), tx.clone());
+            tokio::spawn(async move {
+                if let Err(err) = handle(stream, &limiter, &tx).await {
+                    eprintln!("inbound request failed: {err}");
+                }
+            });
+        }
+    });
+    Ok(rx)
+}
+
+async fn handle(
+    mut stream: TcpStream,
+    limiter: &Limiter,
+    checks: &Sender<UrgentCheck>,
+) -> Result<()> {
+    let admitted = match tokio::time::timeout(READ_TIMEOUT, read_body(&mut stream)).await {
+        Ok(Ok(body)) => {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+            limiter.admit(&body, now, Instant::now()).and_then(|check| {
+                checks.try_send(check).map_err(|_| Rejection::Unavailable)?;
+                Ok(check)
+            })
+        }
+        Ok(Err(rejection)) => Err(rejection),
+        Err(_) => Err(Rejection::BadRequest),
+    };
+    let status = match admitted {
+        Ok(check) => {
+            println!(
+                "inbound: urgent check of {} #{}",
+                check.owner, check.position_id
+            );
+            "202 Accepted"
+        }
+        Err(rejection) => rejection.status(),
+    };
+    let response = format!("HTTP/1.1 {status}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n");
+    stream.write_all(response.as_bytes()).await?;
+    Ok(())
+}
+
+/// Reads a `POST /check` request and returns its body.
+async fn read_body(stream: &mut TcpStream) -> Result<Vec<u8>, Rejection> {
+    let mut buf = Vec::with_capacity(1024);
+    let mut chunk = [0; 1024];
+    loop {
+        if let Some((head, body_start)) = split_head(&buf) {
+            let content_length = parse_head(head)?;
+            if body_start + content_length > MAX_REQUEST_BYTES {
+                return Err(Rejection::PayloadTooLarge);
+            }
+            while buf.len() < body_start + content_length {
+                let read = stream
+                    .read(&mut chunk)
+                    .await
+                    .map_err(|_| Rejection::BadRequest)?;
+                if read == 0 {
+                    return Err(Rejection::BadRequest);
+                }
+                buf.extend_from_slice(&chunk[..read]);
+            }
+            return Ok(buf[body_start..body_start + content_length].to_vec());
+        }
+        if buf.len() >= MAX_REQUEST_BYTES {
+            return Err(Rejection::PayloadTooLarge);
+        }
+        let read = stream
+            .read(&mut chunk)
+            .await
+            .map_err(|_| Rejection::BadRequest)?;
+        if read == 0 {
+            return Err(Rejection::BadRequest);
+        }
+        buf.extend_from_slice(&chunk[..read]);
+    }
+}
+
+/// The request head and where the body starts, once the head is complete.
+fn split_head(buf: &[u8]) -> Option<(&str, usize)> {
+    let end = buf.windows(4).position(|w| w == b"\r\n\r\n")?;
+    Some((std::str::from_utf8(&buf[..end]).unwrap_or(""), end + 4))
+}
+
+/// Checks the request line and returns the body's length.
+fn parse_head(head: &str) -> Result<usize, Rejection> {
+    let mut lines = head.split("\r\n");
+    let mut request_line = lines.next().unwrap_or("").split(' ');
+    if request_line.next() != Some("POST") || request_line.next() != Some("/check") {
+        return Err(Rejection::NotFound);
+    }
+    lines
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.trim().parse().ok())
+        .ok_or(Rejection::BadRequest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_client::solana_sdk::{signature::Keypair, signer::Signer};
+    use serde_json::json;
+
+    fn limiter() -> Limiter {
+        Limiter {
+            config: InboundConfig {
+                listen: "127.0.0.1:0".into(),
+                max_age_secs: 60,
+                min_interval_secs: 30,
+            },
+            accepted: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn signed(owner: &Keypair, position_id: u32, timestamp: i64) -> Vec<u8> {
+        let signature = owner.sign_message(&message(&owner.pubkey(), position_id, timestamp));
+        json!({
+            "owner": owner.pubkey().to_string(),
+            "position_id": position_id,
+            "timestamp": timestamp,
+            "signature": signature.to_string(),
+        })
+        .to_string()
+        .into_bytes()
+    }
+
+    #[test]
+    fn admits_only_fresh_requests_signed_by_the_owner() {
+        let (limiter, owner, now) = (limiter(), Keypair::new(), 1_000_000);
+        let at = Instant::now();
+
+        let mut forged: serde_json::Value =
+            serde_json::from_slice(&signed(&owner, 1, now)).unwrap();
+        forged["owner"] = json!(Pubkey::new_unique().to_string());
+        let forged = forged.to_string().into_bytes();
+        assert_eq!(
+            limiter.admit(&forged, now, at),
+            Err(Rejection::Unauthorized)
+        );
+        let expired = signed(&owner, 1, now - 61);
+        assert_eq!(
+            limiter.admit(&expired, now, at),
+            Err(Rejection::Unauthorized)
+        );
+        assert_eq!(limiter.admit(b"{}", now, at), Err(Rejection::BadRequest));
+
+        let check = limiter.admit(&signed(&owner, 1, now), now, at).unwrap();
+        assert_eq!(
+            check,
+            UrgentCheck {
+                owner: owner.pubkey(),
+                position_id: 1
+            }
+        );
+    }
+
+    #[test]
+    fn rate_limits_and_rejects_replays_per_position() {
+        let (limiter, owner, now) = (limiter(), Keypair::new(), 1_000_000);
+        let at = Instant::now();
+        let request = signed(&owner, 1, now);
+        limiter.admit(&request, now, at).unwrap();
+
+        let later = at + Duration::from_secs(31);
+        assert_eq!(
+            limiter.admit(&request, now, later),
+            Err(Rejection::Unauthorized)
+        );
+        assert_eq!(
+            limiter.admit(
+                &signed(&owner, 1, now + 1),
+                now,
+                at + Duration::from_secs(5)
+            ),
+            Err(Rejection::TooManyRequests)
+        );
+        assert!(limiter.admit(&signed(&owner, 2, now + 1), now, at).is_ok());
+        assert!(limiter
+            .admit(&signed(&owner, 1, now + 2), now, later)
+            .is_ok());
+    }
+
+    #[test]
+    fn parses_only_post_check_with_a_length() {
+        let head = "POST /check HTTP/1.1\r\nHost: keeper\r\ncontent-length: 12";
+        assert_eq!(parse_head(head), Ok(12));
+        let get = "GET /check HTTP/1.1\r\nContent-Length: 1";
+        assert_eq!(parse_head(get), Err(Rejection::NotFound));
+        let no_length = "POST /check HTTP/1.1\r\nHost: keeper";
+        assert_eq!(parse_head(no_length), Err(Rejection::BadRequest));
+        let request = b"POST /check HTTP/1.1\r\n\r\n{}";
+        assert_eq!(split_head(request), Some(("POST /check HTTP/1.1", 24)));
+    }
+}
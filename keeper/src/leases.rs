@@ -0,0 +1,119 @@
+//! Failover between keepers running in several regions under one key.
+//!
+//! Positions are split into `shards` by address, and each shard has an
+//! on-chain lease naming the region that checks it. Every cycle a keeper
+//! renews the leases it holds, which is its heartbeat, and takes those it
+//! may: ones that ran out, and ones held by a region with lower priority.
+//! It then checks only positions in the shards it holds. A secondary thus
+//! picks up a shard once its primary missed a renewal, and the primary
+//! reclaims it when it comes back. The lease lasts longer than a cycle, so
+//! a shard goes unchecked for at most `lease_secs`. A check the previous
+//! holder still has in flight when the shard changes hands can't be doubled
+//! either: the position is waiting on its computation and refuses another.
+
+use crate::config::RegionConfig;
+use anchor_client::solana_sdk::{
+    pubkey::Pubkey, signature::Keypair, signer::Signer, transaction::Transaction,
+};
+use anchor_lang::AccountDeserialize;
+use anyhow::Result;
+use sentinel::KeeperLease;
+use sentinel_client::instructions;
+use solana_rpc_client::nonblocking::rpc_client::RpcClient;
+use std::{collections::BTreeSet, time::SystemTime};
+
+/// Renewals sent per transaction.
+const RENEWALS_PER_TRANSACTION: usize = 8;
+
+/// The shard `position` falls in.
+pub fn shard_of(position: &Pubkey, shards: u16) -> u16 {
+    let bytes = position.to_bytes();
+    u16::from_le_bytes([bytes[0], bytes[1]]) % shards
+}
+
+/// Shards `region` may take or renew at `now`, given each shard's lease
+/// (`None` where there is none yet).
+fn wanted(leases: &[Option<KeeperLease>], region: u8, now: i64) -> Vec<u16> {
+    (0..leases.len() as u16)
+        .filter(|&shard| {
+            leases[shard as usize]
+                .as_ref()
+                .is_none_or(|lease| lease.may_take(region, now))
+        })
+        .collect()
+}
+
+/// Renews or takes every lease this keeper may and returns the shards it
+/// holds for the cycle. Shards whose renewal failed, for example because
+/// another region got there first, are left out.
+pub async fn renew(
+    rpc: &RpcClient,
+    payer: &Keypair,
+    config: &RegionConfig,
+) -> Result<BTreeSet<u16>> {
+    let keeper = payer.pubkey();
+    let addresses: Vec<Pubkey> = (0..config.shards)
+        .map(|shard| instructions::keeper_lease_pda(&keeper, shard))
+        .collect();
+    let mut leases = Vec::with_capacity(addresses.len());
+    for chunk in addresses.chunks(100) {
+        for account in rpc.get_multiple_accounts(chunk).await? {
+            leases.push(account.and_then(|a| KeeperLease::try_deserialize(&mut &a.data[..]).ok()));
+        }
+    }
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)?
+        .as_secs() as i64;
+
+    let mut held = BTreeSet::new();
+    for shards in wanted(&leases, config.priority, now).chunks(RENEWALS_PER_TRANSACTION) {
+        let ixs: Vec<_> = shards
+            .iter()
+            .map(|&shard| {
+                instructions::renew_keeper_lease(&keeper, shard, config.priority, config.lease_secs)
+            })
+            .collect();
+        let blockhash = rpc.get_latest_blockhash().await?;
+        let tx = Transaction::new_signed_with_payer(&ixs, Some(&keeper), &[payer], blockhash);
+        match rpc.send_and_confirm_transaction(&tx).await {
+            Ok(_) => held.extend(shards),
+            Err(err) => eprintln!("lease renewal for shards {shards:?} failed: {err}"),
+        }
+    }
+    Ok(held)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shards_cover_every_position() {
+        for _ in 0..64 {
+            assert!(shard_of(&Pubkey::new_unique(), 16) < 16);
+        }
+        assert_eq!(shard_of(&Pubkey::new_from_array([0; 32]), 16), 0);
+    }
+
+    #[test]
+    fn takes_missing_expired_and_lower_priority_leases() {
+        let lease = |region, expires_at| {
+            Some(KeeperLease {
+                bump: 0,
+                keeper: Pubkey::default(),
+                shard: 0,
+                region,
+                expires_at,
+            })
+        };
+        let leases = [
+            None,
+            lease(0, 200),
+            lease(0, 50),
+            lease(1, 200),
+            lease(2, 200),
+        ];
+        assert_eq!(wanted(&leases, 1, 100), [0, 2, 3, 4]);
+        assert_eq!(wanted(&leases, 2, 100), [0, 2, 4]);
+    }
+}
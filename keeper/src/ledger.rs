@@ -0,0 +1,154 @@
+//! The keeper's record of checks it sent but hasn't seen land, kept in a
+//! JSON file so it survives restarts.
+//!
+//! Every check is queued under a computation offset the keeper picks, which
+//! doubles as its idempotency key. The key and the signatures of the signed
+//! transactions are recorded before anything is sent. If the send fails
+//! ambiguously (a timeout, a dropped connection, a crash), the next cycle
+//! looks the signatures up: a check that landed counts as done, and one
+//! that may not have is retried under the same key. The retry can't queue a
+//! second computation: once either transaction lands, the computation
+//! account at that offset exists and the position is waiting on it, so the
+//! other one fails. The entry is dropped once a transaction lands or every
+//! one of them failed outright.
+
+use anchor_client::solana_sdk::{pubkey::Pubkey, signature::Signature};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use solana_rpc_client::nonblocking::rpc_client::RpcClient;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+/// A check that was sent but not seen to land.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    /// Computation offset the check is queued under
+    pub computation_offset: u64,
+    /// Every transaction sent for the check, retries included
+    pub signatures: Vec<String>,
+}
+
+/// What became of a recorded check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// A transaction landed; the check is done
+    Landed,
+    /// Every transaction failed; a new check needs a new key
+    Failed,
+    /// Nothing has landed yet; retry under the same key
+    Unknown,
+}
+
+pub struct Ledger {
+    path: PathBuf,
+    /// By position address
+    entries: BTreeMap<String, Entry>,
+}
+
+impl Ledger {
+    /// Loads the ledger at `path`, empty if the file doesn't exist yet.
+    pub fn open(path: &Path) -> Result<Self> {
+        let entries = match std::fs::read_to_string(path) {
+            Ok(source) => serde_json::from_str(&source)
+                .with_context(|| format!("invalid ledger {}", path.display()))?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(err) => {
+                return Err(err).with_context(|| format!("failed to read {}", path.display()))
+            }
+        };
+        Ok(Self {
+            path: path.to_path_buf(),
+            entries,
+        })
+    }
+
+    pub fn get(&self, position: &Pubkey) -> Option<&Entry> {
+        self.entries.get(&position.to_string())
+    }
+
+    /// Records `signatures` as sent for the check of `position` queued under
+    /// `computation_offset`, keeping those of earlier tries under that key.
+    pub fn record(
+        &mut self,
+        position: &Pubkey,
+        computation_offset: u64,
+        signatures: &[Signature],
+    ) -> Result<()> {
+        let entry = self.entries.entry(position.to_string()).or_insert(Entry {
+            computation_offset,
+            signatures: vec![],
+        });
+        if entry.computation_offset != computation_offset {
+            entry.computation_offset = computation_offset;
+            entry.signatures.clear();
+        }
+        entry
+            .signatures
+            .extend(signatures.iter().map(Signature::to_string));
+        self.save()
+    }
+
+    pub fn clear(&mut self, position: &Pubkey) -> Result<()> {
+        if self.entries.remove(&position.to_string()).is_some() {
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    /// Written to a temporary file first, so a crash never leaves half a
+    /// ledger behind.
+    fn save(&self) -> Result<()> {
+        let tmp = self.path.with_extension("tmp");
+        std::fs::write(&tmp, serde_json::to_vec_pretty(&self.entries)?)
+            .with_context(|| format!("failed to write {}", tmp.display()))?;
+        std::fs::rename(&tmp, &self.path)
+            .with_context(|| format!("failed to write {}", self.path.display()))
+    }
+}
+
+/// Looks up what became of `entry`'s transactions.
+pub async fn outcome(rpc: &RpcClient, entry: &Entry) -> Result<Outcome> {
+    let signatures = entry
+        .signatures
+        .iter()
+        .map(|s| s.parse())
+        .collect::<Result<Vec<Signature>, _>>()?;
+    let statuses = rpc
+        .get_signature_statuses_with_history(&signatures)
+        .await?
+        .value;
+    if statuses.iter().flatten().any(|status| status.err.is_none()) {
+        Ok(Outcome::Landed)
+    } else if !statuses.is_empty() && statuses.iter().all(Option::is_some) {
+        Ok(Outcome::Failed)
+    } else {
+        Ok(Outcome::Unknown)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entries_survive_reopening() {
+        let path = std::env::temp_dir().join(format!("keeper-ledger-{}.json", std::process::id()));
+        let position = Pubkey::new_unique();
+        let (first, retry) = (Signature::from([1; 64]), Signature::from([2; 64]));
+
+        let mut ledger = Ledger::open(&path).unwrap();
+        ledger.record(&position, 7, &[first]).unwrap();
+        ledger.record(&position, 7, &[retry]).unwrap();
+
+        let mut reopened = Ledger::open(&path).unwrap();
+        let entry = reopened.get(&position).unwrap();
+        assert_eq!(entry.computation_offset, 7);
+        assert_eq!(entry.signatures, [first.to_string(), retry.to_string()]);
+
+        reopened.clear(&position).unwrap();
+        assert!(Ledger::open(&path).unwrap().get(&position).is_none());
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,783 @@
+//! Keeper daemon: every `interval_secs` it re-reads each configured position
+//! through its adapter, encrypts it to the MXE and submits `check_health`,
+//! or `compute_risk_score` once the admin set a `RiskModelConfig`, and it
+//! forwards `RiskRevealed` / `ActionRequired` events to webhooks, as
+//! well as `ConfigUpdateProposed` so operators see config changes coming and
+//! `ComputationFailed`. A failed computation no longer blocks the position,
+//! so it is simply checked again next cycle.
+//!
+//! The keeper signs as the owner or as the delegate set with
+//! `delegate_keeper`; positions it may not check are skipped. As a delegate
+//! it is paid per check from the position's subscription, and it stops
+//! checking positions whose subscription can't cover the fee.
+//!
+//! With `[jito]` configured, transactions go out as Jito bundles and fall
+//! back to RPC; see `submit`. Checks that may or may not have landed are
+//! retried under the same idempotency key; see `ledger`. With `[hot]`,
+//! positions close to liquidation are also checked between cycles as soon
+//! as their data moves; see `hot`. With `[region]`, keepers in several
+//! regions share the positions by shard lease and fail over for each other;
+//! see `leases`. With `[inbound]`, owners can ask for a position to be
+//! checked right away over HTTP; see `inbound`. Price feeds that stopped
+//! publishing are flagged and their positions re-pointed once a
+//! replacement is named; see `feeds`. Auto actions that critical reveals
+//! made due and stop-loss orders that fired are sent; see `actions`. When
+//! a position's auto action runs, the liquidation penalty it likely saved
+//! is recorded; see `savings`.
+//! Positions skipped as checked too recently get a heartbeat when their
+//! owner set a heartbeat deadline; see `heartbeats`. A computation still
+//! pending after `PENDING_TIMEOUT_SECS` is cancelled if the cluster let it
+//! expire.
+//! Positions are read through their owners' `OwnerIndex` pages rather than
+//! a scan of the whole program; see `discovery`. Third parties extend
+//! discovery, check order and notifications with plugins; see `plugins`.
+
+use adapters::Adapter;
+use anchor_client::{
+    solana_sdk::{
+        commitment_config::CommitmentConfig,
+        hash::Hash,
+        pubkey::Pubkey,
+        signature::{read_keypair_file, Keypair, Signature},
+        signer::Signer,
+    },
+    Client, Cluster, Program,
+};
+use anyhow::{anyhow, ensure, Context, Result};
+use arcium_client::{idl::arcium::accounts::MXEAccount, pda};
+use clap::Parser;
+use config::{Config, PositionConfig};
+use hot::HotSet;
+use inbound::UrgentCheck;
+use ledger::{Ledger, Outcome};
+use plugins::{PluginContext, Plugins, Registry};
+use sentinel::{MonitoringPlan, PositionAccount};
+use sentinel_client::{
+    instructions::{self, Queue},
+    new_nonce, Cipher, EncryptionKey, PositionData, ScoredPosition, ENCRYPTION_KEY_MESSAGE,
+};
+use solana_rpc_client::nonblocking::rpc_client::RpcClient;
+use solana_rpc_client_api::filter::{Memcmp, RpcFilterType};
+use std::{
+    collections::{BTreeSet, HashMap},
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use submit::Submitter;
+
+mod actions;
+mod adapters;
+mod config;
+mod discovery;
+mod feeds;
+mod heartbeats;
+mod hot;
+mod inbound;
+mod leases;
+mod ledger;
+pub mod plugins;
+mod savings;
+mod submit;
+mod webhooks;
+
+/// Submits encrypted health checks for sentinel positions on an interval.
+#[derive(Parser)]
+#[command(name = "sentinel-keeper")]
+struct Cli {
+    /// Keeper configuration
+    #[arg(long, default_value = "keeper.toml")]
+    config: PathBuf,
+}
+
+struct Keeper {
+    config: Config,
+    payer: Arc<Keypair>,
+    program: Program<Arc<Keypair>>,
+    key: EncryptionKey,
+    cipher: Cipher,
+    http: reqwest::Client,
+    submitter: Submitter,
+    ledger: Mutex<Ledger>,
+    hot: Option<HotSet>,
+    /// Shards held as of the last cycle, with `[region]`
+    held: Mutex<BTreeSet<u16>>,
+    /// Feeds that stopped publishing as of the last cycle, left out of checks
+    dead_feeds: Mutex<BTreeSet<Pubkey>>,
+    /// Registrant and value of each position at its last check, by owner and
+    /// id, for `savings`
+    sizes: Mutex<HashMap<(Pubkey, u32), (Pubkey, u64)>>,
+    plugins: Plugins,
+    /// Positions the discovery plugins returned in the last cycle
+    discovered: Mutex<Vec<PositionConfig>>,
+}
+
+/// Where a position's check goes.
+#[derive(Debug, Clone)]
+pub struct Target {
+    pub address: Pubkey,
+    pub owner: Pubkey,
+    pub registrant: Pubkey,
+    pub position_id: u32,
+    /// Whether the position has a risk history to append to
+    pub record_history: bool,
+    /// Whether the position has a check bounty escrow, which pays the keeper
+    /// once the check lands
+    pub check_bounty: bool,
+    /// Whether the position only takes checks from registered keepers and
+    /// this keeper is registered, so its registry account goes along
+    pub keeper_account: bool,
+    /// Pyth feed the check is priced with, for price-drop detection
+    pub price_feed: Option<Pubkey>,
+    /// Protocol whose TVL snapshots the check compares
+    pub tvl_protocol: Option<Pubkey>,
+    /// Whether the check is graded by `compute_risk_score`: the admin set a
+    /// `RiskModelConfig` and the check needs nothing only `check_health`
+    /// takes (risk history, check bounty, keeper registry account)
+    pub risk_score: bool,
+}
+
+impl Target {
+    /// The instruction the check is sent as.
+    pub fn instruction(&self) -> &'static str {
+        match self.risk_score {
+            true => "compute_risk_score",
+            false => "check_health",
+        }
+    }
+}
+
+/// Runs the keeper with the configuration named on the command line, until
+/// interrupted. Plugins the configuration names are built from `registry`;
+/// a binary that registers its own plugins calls this from its `main`.
+pub async fn run(registry: Registry) -> Result<()> {
+    let config = Config::load(&Cli::parse().config)?;
+    let plugins = registry.load(&config.plugins)?;
+
+    let cluster: Cluster = config
+        .cluster
+        .parse()
+        .map_err(|e| anyhow!("invalid cluster {:?}: {e}", config.cluster))?;
+    let keypair_path = config.keypair_path();
+    let payer = Arc::new(
+        read_keypair_file(&keypair_path)
+            .map_err(|e| anyhow!("failed to read {}: {e}", keypair_path.display()))?,
+    );
+    let rpc_url = cluster.url().to_string();
+    let rpc = RpcClient::new_with_commitment(rpc_url.clone(), CommitmentConfig::confirmed());
+    let client = Client::new_with_options(cluster, payer.clone(), CommitmentConfig::confirmed());
+    let program = client.program(sentinel::ID, rpc)?;
+
+    let mxe: MXEAccount = program
+        .account(pda::mxe_acc(&sentinel::ID))
+        .await
+        .context("MXE account not found, is the program deployed?")?;
+    let mxe_pubkey = mxe
+        .x25519_pubkey()
+        .context("MXE keys are not finalized yet")?;
+    let signature = payer.sign_message(ENCRYPTION_KEY_MESSAGE);
+    let key = EncryptionKey::from_signature(signature.as_ref().try_into()?);
+    let cipher = key.cipher(&mxe_pubkey)?;
+
+    let context = PluginContext {
+        keeper: payer.pubkey(),
+        cluster: config.cluster.clone(),
+    };
+    plugins.start(&context).await?;
+
+    let (events, _subscriptions) = webhooks::subscribe(&program).await?;
+    tokio::spawn(webhooks::run(
+        events,
+        config.webhooks.clone().into(),
+        plugins.notifiers(),
+    ));
+    let (executed, _executed_subscription) = savings::subscribe(&program).await?;
+    let (due, _due_subscription) = actions::subscribe(&program).await?;
+
+    let submitter = Submitter::new(config.jito.clone()).await?;
+    let ledger = Mutex::new(Ledger::open(&config.ledger)?);
+    let (hot, _completions) = match config.hot.clone() {
+        Some(hot) => {
+            let (hot, completions) = HotSet::start(hot, &program, rpc_url).await?;
+            (Some(hot), Some(completions))
+        }
+        None => (None, None),
+    };
+    let urgent = match config.inbound.clone() {
+        Some(inbound) => Some(inbound::serve(inbound).await?),
+        None => None,
+    };
+
+    println!(
+        "keeper {} watching {} position(s)",
+        payer.pubkey(),
+        config.positions.len()
+    );
+    let keeper = Keeper {
+        config,
+        payer,
+        program,
+        key,
+        cipher,
+        http: reqwest::Client::new(),
+        submitter,
+        ledger,
+        hot,
+        held: Mutex::default(),
+        dead_feeds: Mutex::default(),
+        sizes: Mutex::default(),
+        plugins,
+        discovered: Mutex::default(),
+    };
+    let cycles = async {
+        let mut interval = tokio::time::interval(Duration::from_secs(keeper.config.interval_secs));
+        loop {
+            interval.tick().await;
+            if let Err(err) = keeper.cycle().await {
+                eprintln!("cycle failed: {err:#}");
+            }
+        }
+    };
+    tokio::select! {
+        _ = cycles => {}
+        _ = keeper.watch_hot() => {}
+        _ = keeper.serve_urgent(urgent) => {}
+        _ = keeper.record_savings(executed) => {}
+        _ = keeper.run_actions(due) => {}
+        _ = tokio::signal::ctrl_c() => {}
+    }
+    keeper.plugins.stop().await;
+    Ok(())
+}
+
+impl Keeper {
+    /// Checks every active, idle position the keeper may check and has an
+    /// adapter for. The positions of the configured and discovered owners
+    /// are re-read each cycle, so new registrations and delegations are
+    /// picked up without a restart.
+    async fn cycle(&self) -> Result<()> {
+        let me = self.payer.pubkey();
+        let rpc = self.program.internal_rpc();
+        let slot = rpc.get_slot().await?;
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+        *self.discovered.lock().unwrap() = self.plugins.discover().await;
+        let wanted: Vec<PositionConfig> = self
+            .config
+            .positions
+            .iter()
+            .cloned()
+            .chain(self.discovered.lock().unwrap().iter().cloned())
+            .collect();
+        let mut positions = discovery::positions(&self.program, &wanted).await?;
+        self.plugins.order(&mut positions);
+        let plan: Option<MonitoringPlan> = self
+            .program
+            .account(instructions::monitoring_plan_pda())
+            .await
+            .ok();
+        let held = match &self.config.region {
+            Some(region) => Some((
+                region.shards,
+                leases::renew(rpc, &self.payer, region).await?,
+            )),
+            None => None,
+        };
+        if let Some((_, held)) = &held {
+            self.held.lock().unwrap().clone_from(held);
+        }
+        match feeds::maintain(rpc, &self.payer, &positions).await {
+            Ok(dead) => *self.dead_feeds.lock().unwrap() = dead,
+            Err(err) => eprintln!("feed maintenance failed: {err:#}"),
+        }
+
+        for (address, position) in positions {
+            if !position.is_active || !position.may_check(&me, slot) {
+                self.forget_hot(&address);
+                continue;
+            }
+            if let Some((shards, held)) = &held {
+                if !held.contains(&leases::shard_of(&address, *shards)) {
+                    self.forget_hot(&address);
+                    continue;
+                }
+            }
+            let Some(adapter) = self.adapter(&position.owner, position.position_id) else {
+                continue;
+            };
+            if let Some(computation_offset) = position.pending_computation {
+                println!("{address}: computation pending, skipped");
+                if now - position.pending_since >= sentinel::PENDING_TIMEOUT_SECS {
+                    match self.cancel_computation(&position, computation_offset).await {
+                        Ok(sig) => println!("{address}: cancelled expired computation {sig}"),
+                        Err(err) => eprintln!("{address}: cancel failed: {err:#}"),
+                    }
+                }
+                continue;
+            }
+            if position.require_check_interval(now).is_err() {
+                println!("{address}: checked too recently, skipped");
+                if heartbeats::due(&position, now) {
+                    match heartbeats::send(rpc, &self.payer, &position).await {
+                        Ok(sig) => println!("{address}: heartbeat {sig}"),
+                        Err(err) => eprintln!("{address}: heartbeat failed: {err:#}"),
+                    }
+                }
+                continue;
+            }
+            if let Some(hot) = &self.hot {
+                hot.idle(&address);
+            }
+            if position.owner != me {
+                let Some(plan) = &plan else {
+                    eprintln!("{address}: no monitoring plan, skipped");
+                    continue;
+                };
+                match self.subscription_balance(&address).await {
+                    Ok(Some(balance)) if balance >= plan.fee_per_check => {}
+                    Ok(_) => {
+                        println!("{address}: subscription depleted, skipped");
+                        self.forget_hot(&address);
+                        continue;
+                    }
+                    Err(err) => {
+                        eprintln!("{address}: {err:#}");
+                        continue;
+                    }
+                }
+            }
+
+            // A check sent earlier that never confirmed is resolved first
+            let entry = self.ledger.lock().unwrap().get(&address).cloned();
+            let computation_offset = match entry {
+                None => Queue::new(self.config.cluster_offset).computation_offset,
+                Some(entry) => match ledger::outcome(rpc, &entry).await {
+                    Ok(Outcome::Landed) => {
+                        println!("{address}: check {} landed", entry.computation_offset);
+                        self.ledger.lock().unwrap().clear(&address)?;
+                        continue;
+                    }
+                    Ok(Outcome::Failed) => {
+                        self.ledger.lock().unwrap().clear(&address)?;
+                        Queue::new(self.config.cluster_offset).computation_offset
+                    }
+                    Ok(Outcome::Unknown) => entry.computation_offset,
+                    Err(err) => {
+                        eprintln!("{address}: {err:#}");
+                        continue;
+                    }
+                },
+            };
+
+            let checked = async {
+                let data = adapter.fetch(&self.http).await?;
+                let target = self.target(address, &position).await?;
+                let instruction = target.instruction();
+                let blockhash = rpc.get_latest_blockhash().await?;
+                let signature = self
+                    .send_check(&target, &data, computation_offset, blockhash)
+                    .await?;
+                if let Some(hot) = &self.hot {
+                    hot.sent(target, data);
+                }
+                anyhow::Ok((instruction, signature))
+            };
+            match checked.await {
+                Ok((instruction, sig)) => println!("{address}: {instruction} {sig}"),
+                Err(err) => eprintln!("{address}: {err:#}"),
+            }
+        }
+        Ok(())
+    }
+
+    /// Polls the adapters of hot positions and checks each one whose data
+    /// moved, built from the cache. Runs until the keeper stops.
+    async fn watch_hot(&self) {
+        let Some(hot) = &self.hot else {
+            return std::future::pending().await;
+        };
+        let mut interval = tokio::time::interval(hot.poll_interval());
+        loop {
+            interval.tick().await;
+            for position in hot.ready() {
+                let target = &position.target;
+                let Some(adapter) = self.adapter(&target.owner, target.position_id) else {
+                    continue;
+                };
+                let checked = async {
+                    let data = adapter.fetch(&self.http).await?;
+                    if data == position.last_data {
+                        return Ok(None);
+                    }
+                    let started = Instant::now();
+                    let computation_offset =
+                        Queue::new(self.config.cluster_offset).computation_offset;
+                    let signature = self
+                        .send_check(target, &data, computation_offset, hot.blockhash())
+                        .await?;
+                    hot.sent(target.clone(), data);
+                    anyhow::Ok(Some((signature, started.elapsed())))
+                };
+                match checked.await {
+                    Ok(Some((sig, elapsed))) => println!(
+                        "{}: hot {} {sig} in {}ms",
+                        target.address,
+                        target.instruction(),
+                        elapsed.as_millis()
+                    ),
+                    Ok(None) => {}
+                    Err(err) => eprintln!("{}: {err:#}", target.address),
+                }
+            }
+        }
+    }
+
+    /// Checks positions owners asked for over `[inbound]` as they come in,
+    /// outside the cycle. Runs until the keeper stops.
+    async fn serve_urgent(&self, requests: Option<tokio::sync::mpsc::Receiver<UrgentCheck>>) {
+        let Some(mut requests) = requests else {
+            return std::future::pending().await;
+        };
+        while let Some(request) = requests.recv().await {
+            match self.urgent_check(request).await {
+                Ok((instruction, sig)) => println!(
+                    "{} #{}: urgent {instruction} {sig}",
+                    request.owner, request.position_id
+                ),
+                Err(err) => eprintln!("{} #{}: {err:#}", request.owner, request.position_id),
+            }
+        }
+    }
+
+    /// Checks one position now, if the cycle would check it. Returns the
+    /// instruction the check was sent as and its signature.
+    async fn urgent_check(&self, request: UrgentCheck) -> Result<(&'static str, Signature)> {
+        let adapter = self
+            .adapter(&request.owner, request.position_id)
+            .context("no adapter configured")?;
+        let filters = vec![
+            RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+                PositionAccount::POSITION_ID_OFFSET,
+                &request.position_id.to_le_bytes(),
+            )),
+            RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+                PositionAccount::OWNER_OFFSET,
+                request.owner.as_ref(),
+            )),
+        ];
+        let (address, position) = self
+            .program
+            .accounts::<PositionAccount>(filters)
+            .await?
+            .into_iter()
+            .next()
+            .context("position not found")?;
+        let rpc = self.program.internal_rpc();
+        let me = self.payer.pubkey();
+        ensure!(
+            position.is_active && position.may_check(&me, rpc.get_slot().await?),
+            "keeper may not check this position"
+        );
+        if let Some(region) = &self.config.region {
+            let shard = leases::shard_of(&address, region.shards);
+            ensure!(
+                self.held.lock().unwrap().contains(&shard),
+                "shard held by another region"
+            );
+        }
+        ensure!(
+            position.pending_computation.is_none(),
+            "computation pending"
+        );
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+        ensure!(
+            position.require_check_interval(now).is_ok(),
+            "checked too recently"
+        );
+        ensure!(
+            self.ledger.lock().unwrap().get(&address).is_none(),
+            "an earlier check is unresolved"
+        );
+        if position.owner != me {
+            let plan: MonitoringPlan = self
+                .program
+                .account(instructions::monitoring_plan_pda())
+                .await?;
+            let balance = self
+                .subscription_balance(&address)
+                .await?
+                .unwrap_or_default();
+            ensure!(balance >= plan.fee_per_check, "subscription depleted");
+        }
+
+        let data = adapter.fetch(&self.http).await?;
+        let target = self.target(address, &position).await?;
+        let blockhash = rpc.get_latest_blockhash().await?;
+        let computation_offset = Queue::new(self.config.cluster_offset).computation_offset;
+        let instruction = target.instruction();
+        let signature = self
+            .send_check(&target, &data, computation_offset, blockhash)
+            .await?;
+        if let Some(hot) = &self.hot {
+            hot.sent(target, data);
+        }
+        Ok((instruction, signature))
+    }
+
+    /// Sends each auto action and stop-loss order that became due on a
+    /// position the keeper checks. Runs until the keeper stops.
+    async fn run_actions(&self, mut due: tokio::sync::mpsc::UnboundedReceiver<actions::Due>) {
+        while let Some(due) = due.recv().await {
+            if self.adapter(&due.owner, due.position_id).is_none() {
+                continue;
+            }
+            let instruction = due.kind.instruction();
+            match actions::execute(&self.program, &self.payer, &due).await {
+                Ok(sig) => println!("{}: {instruction} {sig}", due.position),
+                Err(err) => eprintln!("{}: {instruction} failed: {err:#}", due.position),
+            }
+        }
+    }
+
+    /// Records a `ValueProtected` estimate for each position the keeper
+    /// checked whose auto action ran. Runs until the keeper stops.
+    async fn record_savings(
+        &self,
+        mut executed: tokio::sync::mpsc::UnboundedReceiver<(Pubkey, u32)>,
+    ) {
+        while let Some((owner, position_id)) = executed.recv().await {
+            let Some((registrant, value)) = self
+                .sizes
+                .lock()
+                .unwrap()
+                .get(&(owner, position_id))
+                .copied()
+            else {
+                continue;
+            };
+            let recorded = async {
+                let address = instructions::position_pda(&registrant, position_id);
+                let position: PositionAccount = self.program.account(address).await?;
+                let penalty_bps = self
+                    .position_config(&owner, position_id)
+                    .and_then(|config| config.liquidation_penalty_bps);
+                let estimate = savings::estimate(position.size_bucket, value, penalty_bps);
+                let rpc = self.program.internal_rpc();
+                savings::record(rpc, &self.payer, &registrant, position_id, estimate).await
+            };
+            if let Err(err) = recorded.await {
+                eprintln!("{owner} #{position_id}: value protected failed: {err:#}");
+            }
+        }
+    }
+
+    /// Where `position`'s check goes.
+    async fn target(&self, address: Pubkey, position: &PositionAccount) -> Result<Target> {
+        let record_history = self
+            .exists(&instructions::risk_history_pda(&address))
+            .await?;
+        let check_bounty = self
+            .exists(&instructions::check_bounty_pda(&address))
+            .await?;
+        let keeper_account = position.registered_keepers_only
+            && self
+                .exists(&instructions::keeper_account_pda(&self.payer.pubkey()))
+                .await?;
+        let risk_score = !(record_history || check_bounty || keeper_account)
+            && self.exists(&instructions::risk_model_config_pda()).await?;
+        Ok(Target {
+            address,
+            owner: position.owner,
+            registrant: position.registrant,
+            position_id: position.position_id,
+            record_history,
+            check_bounty,
+            keeper_account,
+            price_feed: position
+                .price_feed
+                .filter(|feed| !self.dead_feeds.lock().unwrap().contains(feed)),
+            tvl_protocol: position.tvl_protocol,
+            risk_score,
+        })
+    }
+
+    /// Encrypts `data`, signs the check under `computation_offset` and sends
+    /// it. The idempotency key stays in the ledger until the check confirms.
+    async fn send_check(
+        &self,
+        target: &Target,
+        data: &PositionData,
+        computation_offset: u64,
+        blockhash: Hash,
+    ) -> Result<Signature> {
+        let queue = Queue {
+            cluster_offset: self.config.cluster_offset,
+            computation_offset,
+        };
+        let ix = match target.risk_score {
+            true => {
+                let scored = ScoredPosition {
+                    collateral_ratio: data.collateral_ratio,
+                    liquidation_threshold: data.liquidation_threshold,
+                    concentration_bps: self.concentration_bps(target, data.position_value),
+                };
+                instructions::compute_risk_score(
+                    &self.payer.pubkey(),
+                    &target.owner,
+                    &target.registrant,
+                    target.position_id,
+                    queue,
+                    self.key.public_key(),
+                    &scored.encrypt(&self.cipher, new_nonce()),
+                    new_nonce(),
+                    target.price_feed,
+                    target.tvl_protocol,
+                )
+            }
+            false => instructions::check_health(
+                &self.payer.pubkey(),
+                &target.owner,
+                &target.registrant,
+                target.position_id,
+                queue,
+                self.key.public_key(),
+                &data.encrypt(&self.cipher, new_nonce()),
+                target.record_history,
+                target.price_feed,
+                target.tvl_protocol,
+                target.check_bounty,
+                target.keeper_account,
+            ),
+        };
+        let signed = self
+            .submitter
+            .sign(&self.payer, vec![vec![ix]], blockhash)?;
+        let signature = signed[0].signatures[0];
+        self.ledger
+            .lock()
+            .unwrap()
+            .record(&target.address, computation_offset, &[signature])?;
+        self.submitter
+            .send(self.program.internal_rpc(), &signed)
+            .await?;
+        self.ledger.lock().unwrap().clear(&target.address)?;
+        self.sizes.lock().unwrap().insert(
+            (target.owner, target.position_id),
+            (target.registrant, data.position_value),
+        );
+        Ok(signature)
+    }
+
+    /// `target`'s share, at `value`, of its owner's positions the keeper
+    /// last checked, in basis points.
+    fn concentration_bps(&self, target: &Target, value: u64) -> u64 {
+        let sizes = self.sizes.lock().unwrap();
+        let others = sizes
+            .iter()
+            .filter(|((owner, id), _)| *owner == target.owner && *id != target.position_id)
+            .map(|(_, (_, value))| *value);
+        concentration_bps(value, others)
+    }
+
+    /// Cancels `position`'s pending computation, which the program only
+    /// allows once the cluster let it expire; the position is checked again
+    /// next cycle. The fee held for a delegated check is given back.
+    async fn cancel_computation(
+        &self,
+        position: &PositionAccount,
+        computation_offset: u64,
+    ) -> Result<Signature> {
+        let ix = instructions::cancel_computation(
+            &self.payer.pubkey(),
+            &position.registrant,
+            position.position_id,
+            Queue {
+                cluster_offset: self.config.cluster_offset,
+                computation_offset,
+            },
+            position.owner != self.payer.pubkey(),
+        );
+        let rpc = self.program.internal_rpc();
+        let blockhash = rpc.get_latest_blockhash().await?;
+        let signed = self
+            .submitter
+            .sign(&self.payer, vec![vec![ix]], blockhash)?;
+        self.submitter.send(rpc, &signed).await?;
+        Ok(signed[0].signatures[0])
+    }
+
+    /// The configured or discovered position `position_id` of `owner`;
+    /// `keeper.toml` wins over discovery.
+    fn position_config(&self, owner: &Pubkey, position_id: u32) -> Option<PositionConfig> {
+        if let Some(config) = self.config.position(owner, position_id) {
+            return Some(config.clone());
+        }
+        self.discovered
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|config| config.owner == *owner && config.position_id == position_id)
+            .cloned()
+    }
+
+    fn adapter(&self, owner: &Pubkey, position_id: u32) -> Option<Adapter> {
+        self.position_config(owner, position_id)
+            .map(|config| config.adapter)
+    }
+
+    fn forget_hot(&self, address: &Pubkey) {
+        if let Some(hot) = &self.hot {
+            hot.remove(address);
+        }
+    }
+
+    async fn exists(&self, address: &Pubkey) -> Result<bool> {
+        let rpc = self.program.internal_rpc();
+        let account = rpc
+            .get_account_with_commitment(address, CommitmentConfig::confirmed())
+            .await?;
+        Ok(account.value.is_some())
+    }
+
+    /// Lamports the position's subscription holds above rent, `None` if it
+    /// was never funded.
+    async fn subscription_balance(&self, position: &Pubkey) -> Result<Option<u64>> {
+        let rpc = self.program.internal_rpc();
+        let Some(account) = rpc
+            .get_account_with_commitment(
+                &instructions::subscription_pda(position),
+                CommitmentConfig::confirmed(),
+            )
+            .await?
+            .value
+        else {
+            return Ok(None);
+        };
+        let rent = rpc
+            .get_minimum_balance_for_rent_exemption(account.data.len())
+            .await?;
+        Ok(Some(account.lamports.saturating_sub(rent)))
+    }
+}
+
+/// `value`'s share of itself plus `others`, in basis points; all of it when
+/// there is nothing to compare against.
+fn concentration_bps(value: u64, others: impl Iterator<Item = u64>) -> u64 {
+    let total = others.fold(value as u128, |total, other| total + other as u128);
+    match total {
+        0 => 10_000,
+        total => (value as u128 * 10_000 / total) as u64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn concentration_is_share_of_owner_holdings() {
+        assert_eq!(concentration_bps(25, [75].into_iter()), 2_500);
+        assert_eq!(concentration_bps(100, std::iter::empty()), 10_000);
+        assert_eq!(concentration_bps(0, std::iter::empty()), 10_000);
+        assert_eq!(concentration_bps(u64::MAX, [u64::MAX].into_iter()), 5_000);
+    }
+}
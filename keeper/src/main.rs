@@ -0,0 +1,6 @@
+use sentinel_keeper::plugins::Registry;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    sentinel_keeper::run(Registry::builtin()).await
+}
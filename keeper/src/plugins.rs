@@ -0,0 +1,412 @@
+//! Plugins: how third parties extend the keeper without forking it.
+//!
+//! - A [`DiscoveryPlugin`] finds positions to check beyond `keeper.toml`,
+//!   e.g. every position of a protocol's users, each with its adapter.
+//! - A [`ScoringHintPlugin`] scores positions so the riskiest are checked
+//!   first in a cycle.
+//! - A [`NotifierPlugin`] receives every event the keeper posts to its
+//!   webhooks, e.g. to page someone or write to a queue.
+//!
+//! Plugins are registered by name in a [`Registry`] and loaded from the
+//! `[[plugins]]` sections of `keeper.toml`, each with an `options` table
+//! only the plugin reads. The stock binary registers the built-ins below; a
+//! binary that adds its own depends on this crate and passes its registry
+//! to [`crate::run`]:
+//!
+//! ```ignore
+//! #[tokio::main]
+//! async fn main() -> anyhow::Result<()> {
+//!     let registry = Registry::builtin()
+//!         .notifier("pager", |options| Ok(Arc::new(Pager::new(options)?)));
+//!     sentinel_keeper::run(registry).await
+//! }
+//! ```
+//!
+//! Every plugin is started once before the first cycle and stopped when the
+//! keeper shuts down. Errors from a plugin's hooks are logged and never stop
+//! a cycle, except at start, where they stop the keeper.
+
+pub use crate::{adapters::Adapter, config::PluginConfig, config::PositionConfig};
+use anchor_client::solana_sdk::pubkey::Pubkey;
+use anyhow::{bail, Context, Result};
+pub use async_trait::async_trait;
+use sentinel::PositionAccount;
+use serde_json::Value;
+use std::{collections::BTreeMap, io::Write, path::PathBuf, sync::Arc, sync::Mutex};
+
+/// What a plugin learns about the keeper it runs in.
+#[derive(Debug, Clone)]
+pub struct PluginContext {
+    /// Key the keeper signs with
+    pub keeper: Pubkey,
+    /// `cluster` from `keeper.toml`
+    pub cluster: String,
+}
+
+/// Lifecycle hooks every plugin has.
+#[async_trait]
+pub trait Plugin: Send + Sync {
+    /// Name for logs
+    fn name(&self) -> &str;
+
+    /// Runs once before the first cycle; an error stops the keeper.
+    async fn start(&self, _context: &PluginContext) -> Result<()> {
+        Ok(())
+    }
+
+    /// Runs once when the keeper shuts down.
+    async fn stop(&self) {}
+}
+
+#[async_trait]
+pub trait DiscoveryPlugin: Plugin {
+    /// Positions to check this cycle besides the configured ones. A
+    /// position in `keeper.toml` keeps its configured adapter.
+    async fn discover(&self) -> Result<Vec<PositionConfig>>;
+}
+
+pub trait ScoringHintPlugin: Plugin {
+    /// How urgently `position` should be checked. The scores of all scoring
+    /// plugins are added up and higher totals go first; 0 is no opinion.
+    fn score(&self, address: &Pubkey, position: &PositionAccount) -> i64;
+}
+
+#[async_trait]
+pub trait NotifierPlugin: Plugin {
+    /// Receives an event payload, the same JSON the webhooks get.
+    async fn notify(&self, event: &Value) -> Result<()>;
+}
+
+type Factory<T> = Box<dyn Fn(&toml::Table) -> Result<T>>;
+
+enum Constructor {
+    Discovery(Factory<Box<dyn DiscoveryPlugin>>),
+    ScoringHint(Factory<Box<dyn ScoringHintPlugin>>),
+    Notifier(Factory<Arc<dyn NotifierPlugin>>),
+}
+
+/// Plugins the keeper can load, by name.
+#[derive(Default)]
+pub struct Registry {
+    constructors: BTreeMap<String, Constructor>,
+}
+
+impl Registry {
+    /// A registry with no plugins.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A registry with the built-in plugins: `jsonl`, a notifier appending
+    /// each event to the file `options.path`, and `failed_first`, a scoring
+    /// hint that checks positions whose last computation failed first.
+    pub fn builtin() -> Self {
+        Self::new()
+            .notifier("jsonl", |options| {
+                Ok(Arc::new(JsonlNotifier::new(options)?))
+            })
+            .scoring_hint("failed_first", |_| Ok(Box::new(FailedFirst)))
+    }
+
+    pub fn discovery(
+        mut self,
+        name: &str,
+        factory: impl Fn(&toml::Table) -> Result<Box<dyn DiscoveryPlugin>> + 'static,
+    ) -> Self {
+        let constructor = Constructor::Discovery(Box::new(factory));
+        self.constructors.insert(name.to_string(), constructor);
+        self
+    }
+
+    pub fn scoring_hint(
+        mut self,
+        name: &str,
+        factory: impl Fn(&toml::Table) -> Result<Box<dyn ScoringHintPlugin>> + 'static,
+    ) -> Self {
+        let constructor = Constructor::ScoringHint(Box::new(factory));
+        self.constructors.insert(name.to_string(), constructor);
+        self
+    }
+
+    pub fn notifier(
+        mut self,
+        name: &str,
+        factory: impl Fn(&toml::Table) -> Result<Arc<dyn NotifierPlugin>> + 'static,
+    ) -> Self {
+        let constructor = Constructor::Notifier(Box::new(factory));
+        self.constructors.insert(name.to_string(), constructor);
+        self
+    }
+
+    /// Builds the plugins `configs` name, in order.
+    pub fn load(&self, configs: &[PluginConfig]) -> Result<Plugins> {
+        let mut plugins = Plugins::default();
+        for config in configs {
+            let built = || format!("failed to load plugin {:?}", config.name);
+            match self.constructors.get(&config.name) {
+                Some(Constructor::Discovery(factory)) => plugins
+                    .discovery
+                    .push(factory(&config.options).with_context(built)?),
+                Some(Constructor::ScoringHint(factory)) => plugins
+                    .scoring
+                    .push(factory(&config.options).with_context(built)?),
+                Some(Constructor::Notifier(factory)) => plugins
+                    .notifiers
+                    .push(factory(&config.options).with_context(built)?),
+                None => bail!("unknown plugin {:?}", config.name),
+            }
+        }
+        Ok(plugins)
+    }
+}
+
+/// The loaded plugins, by kind.
+#[derive(Default)]
+pub struct Plugins {
+    discovery: Vec<Box<dyn DiscoveryPlugin>>,
+    scoring: Vec<Box<dyn ScoringHintPlugin>>,
+    notifiers: Vec<Arc<dyn NotifierPlugin>>,
+}
+
+impl Plugins {
+    fn all(&self) -> impl Iterator<Item = &dyn Plugin> {
+        let discovery = self.discovery.iter().map(|p| p.as_ref() as &dyn Plugin);
+        let scoring = self.scoring.iter().map(|p| p.as_ref() as &dyn Plugin);
+        let notifiers = self.notifiers.iter().map(|p| p.as_ref() as &dyn Plugin);
+        discovery.chain(scoring).chain(notifiers)
+    }
+
+    pub async fn start(&self, context: &PluginContext) -> Result<()> {
+        for plugin in self.all() {
+            let name = plugin.name().to_string();
+            plugin
+                .start(context)
+                .await
+                .with_context(|| format!("plugin {name} failed to start"))?;
+            println!("plugin {name} started");
+        }
+        Ok(())
+    }
+
+    pub async fn stop(&self) {
+        for plugin in self.all() {
+            plugin.stop().await;
+        }
+    }
+
+    /// Positions every discovery plugin returned; failing plugins are
+    /// logged and contribute none.
+    pub async fn discover(&self) -> Vec<PositionConfig> {
+        let mut positions = Vec::new();
+        for plugin in &self.discovery {
+            match plugin.discover().await {
+                Ok(found) => positions.extend(found),
+                Err(err) => eprintln!("plugin {}: discovery failed: {err:#}", plugin.name()),
+            }
+        }
+        positions
+    }
+
+    /// Sorts `positions` by their total score, highest first. Positions of
+    /// equal score keep their order.
+    pub fn order(&self, positions: &mut [(Pubkey, PositionAccount)]) {
+        if self.scoring.is_empty() {
+            return;
+        }
+        positions.sort_by_cached_key(|(address, position)| {
+            let total: i64 = self
+                .scoring
+                .iter()
+                .map(|plugin| plugin.score(address, position))
+                .fold(0, i64::saturating_add);
+            std::cmp::Reverse(total)
+        });
+    }
+
+    pub fn notifiers(&self) -> Arc<[Arc<dyn NotifierPlugin>]> {
+        self.notifiers.clone().into()
+    }
+}
+
+/// `jsonl`: appends each event as a line of JSON to `options.path`.
+struct JsonlNotifier {
+    path: PathBuf,
+    file: Mutex<Option<std::fs::File>>,
+}
+
+impl JsonlNotifier {
+    fn new(options: &toml::Table) -> Result<Self> {
+        let path = options
+            .get("path")
+            .and_then(toml::Value::as_str)
+            .context("options.path is required")?;
+        Ok(Self {
+            path: path.into(),
+            file: Mutex::default(),
+        })
+    }
+}
+
+#[async_trait]
+impl Plugin for JsonlNotifier {
+    fn name(&self) -> &str {
+        "jsonl"
+    }
+
+    async fn start(&self, _context: &PluginContext) -> Result<()> {
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .with_context(|| format!("failed to open {}", self.path.display()))?;
+        *self.file.lock().unwrap() = Some(file);
+        Ok(())
+    }
+
+    async fn stop(&self) {
+        self.file.lock().unwrap().take();
+    }
+}
+
+#[async_trait]
+impl NotifierPlugin for JsonlNotifier {
+    async fn notify(&self, event: &Value) -> Result<()> {
+        let mut file = self.file.lock().unwrap();
+        let file = file.as_mut().context("not started")?;
+        writeln!(file, "{event}")?;
+        Ok(())
+    }
+}
+
+/// `failed_first`: positions whose last computation failed go first, so a
+/// failure is retried before the cycle's other checks.
+struct FailedFirst;
+
+impl Plugin for FailedFirst {
+    fn name(&self) -> &str {
+        "failed_first"
+    }
+}
+
+impl ScoringHintPlugin for FailedFirst {
+    fn score(&self, _address: &Pubkey, position: &PositionAccount) -> i64 {
+        position.last_error.is_some() as i64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sentinel::{ComputationFailure, ComputationKind};
+
+    struct Fixed(Vec<PositionConfig>);
+
+    #[async_trait]
+    impl Plugin for Fixed {
+        fn name(&self) -> &str {
+            "fixed"
+        }
+    }
+
+    #[async_trait]
+    impl DiscoveryPlugin for Fixed {
+        async fn discover(&self) -> Result<Vec<PositionConfig>> {
+            Ok(self.0.clone())
+        }
+    }
+
+    fn plugin(name: &str, options: toml::Table) -> PluginConfig {
+        PluginConfig {
+            name: name.to_string(),
+            options,
+        }
+    }
+
+    fn discovered(position_id: u32) -> PositionConfig {
+        PositionConfig {
+            owner: Pubkey::new_unique(),
+            position_id,
+            liquidation_penalty_bps: None,
+            adapter: Adapter::Http {
+                url: "http://localhost/".into(),
+            },
+        }
+    }
+
+    fn position(failed: bool) -> (Pubkey, PositionAccount) {
+        let mut position: PositionAccount = zeroed_position();
+        position.last_error = failed.then_some(ComputationFailure {
+            kind: ComputationKind::CheckHealth,
+            computation_offset: 1,
+            failed_at: 0,
+        });
+        (Pubkey::new_unique(), position)
+    }
+
+    /// A zeroed position, as `PositionAccount` has no `Default`.
+    fn zeroed_position() -> PositionAccount {
+        use anchor_lang::{AccountDeserialize, Discriminator, Space};
+        let mut data = PositionAccount::DISCRIMINATOR.to_vec();
+        data.resize(8 + PositionAccount::INIT_SPACE, 0);
+        PositionAccount::try_deserialize(&mut data.as_slice()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn registry_loads_configured_plugins() {
+        let registry =
+            Registry::builtin().discovery("fixed", |_| Ok(Box::new(Fixed(vec![discovered(3)]))));
+        let path = std::env::temp_dir().join(format!("plugins-{}.jsonl", std::process::id()));
+        let mut options = toml::Table::new();
+        options.insert("path".into(), path.display().to_string().into());
+
+        let plugins = registry
+            .load(&[
+                plugin("fixed", toml::Table::new()),
+                plugin("jsonl", options),
+            ])
+            .unwrap();
+        let context = PluginContext {
+            keeper: Pubkey::new_unique(),
+            cluster: "localnet".into(),
+        };
+        plugins.start(&context).await.unwrap();
+
+        let found = plugins.discover().await;
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].position_id, 3);
+        for notifier in plugins.notifiers().iter() {
+            notifier
+                .notify(&serde_json::json!({ "event": "RiskRevealed" }))
+                .await
+                .unwrap();
+        }
+        plugins.stop().await;
+        let written = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(written, "{\"event\":\"RiskRevealed\"}\n");
+    }
+
+    #[test]
+    fn registry_rejects_unknown_and_misconfigured_plugins() {
+        let registry = Registry::builtin();
+        assert!(registry
+            .load(&[plugin("pager", toml::Table::new())])
+            .is_err());
+        assert!(registry
+            .load(&[plugin("jsonl", toml::Table::new())])
+            .is_err());
+    }
+
+    #[test]
+    fn scoring_orders_failed_positions_first() {
+        let plugins = Registry::builtin().load(&[plugin("failed_first", toml::Table::new())]);
+        let mut positions = vec![position(false), position(true), position(false)];
+        let failed = positions[1].0;
+        let first_ok = positions[0].0;
+
+        plugins.unwrap().order(&mut positions);
+
+        assert_eq!(positions[0].0, failed);
+        assert_eq!(positions[1].0, first_ok);
+    }
+}
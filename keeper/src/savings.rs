@@ -0,0 +1,97 @@
+//! `ValueProtected` estimates for ROI reporting.
+//!
+//! When a position's auto action runs, the keeper records roughly what
+//! liquidation would have cost the owner: the position's liquidation penalty
+//! applied to its size bucket. That is the bucket revealed on-chain with the
+//! owner's consent (`reveal_size_bucket`) if there is one, and otherwise the
+//! bucket of the value the keeper last checked. The exact size never leaves
+//! the keeper; only the bucket and the penalty go on-chain, through
+//! `record_value_protected`.
+
+use anchor_client::{
+    solana_sdk::{pubkey::Pubkey, signature::Keypair, signer::Signer, transaction::Transaction},
+    EventUnsubscriber, Program,
+};
+use anyhow::Result;
+use sentinel::{AutoActionExecuted, ProtectionTrigger, SizeBucket};
+use sentinel_client::instructions;
+use solana_rpc_client::nonblocking::rpc_client::RpcClient;
+use std::ops::Deref;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+
+/// Penalty assumed for positions without `liquidation_penalty_bps`: the
+/// usual liquidation bonus on Solend, Kamino and MarginFi.
+pub const DEFAULT_PENALTY_BPS: u16 = 500;
+
+/// Bucket and penalty recorded for a position last seen worth
+/// `position_value` USD cents, `revealed` being its on-chain bucket.
+pub fn estimate(
+    revealed: Option<SizeBucket>,
+    position_value: u64,
+    penalty_bps: Option<u16>,
+) -> (SizeBucket, u16) {
+    let bucket = revealed.unwrap_or_else(|| SizeBucket::of(position_value));
+    (bucket, penalty_bps.unwrap_or(DEFAULT_PENALTY_BPS))
+}
+
+/// Subscribes to `AutoActionExecuted`, returning the owner and id of each
+/// position whose auto action ran. Events stop once the unsubscriber is
+/// dropped.
+pub async fn subscribe<C>(
+    program: &Program<C>,
+) -> Result<(UnboundedReceiver<(Pubkey, u32)>, EventUnsubscriber<'_>)>
+where
+    C: Deref<Target = Keypair> + Clone,
+{
+    let (tx, rx) = unbounded_channel();
+    let executed = program
+        .on(move |_, event: AutoActionExecuted| {
+            let _ = tx.send((event.owner, event.position_id));
+        })
+        .await?;
+    Ok((rx, executed))
+}
+
+/// Records the estimate for `registrant`'s position `position_id`.
+pub async fn record(
+    rpc: &RpcClient,
+    payer: &Keypair,
+    registrant: &Pubkey,
+    position_id: u32,
+    (size_bucket, penalty_bps): (SizeBucket, u16),
+) -> Result<()> {
+    let ix = instructions::record_value_protected(
+        &payer.pubkey(),
+        registrant,
+        position_id,
+        ProtectionTrigger::AutoAction,
+        size_bucket,
+        penalty_bps,
+    );
+    let blockhash = rpc.get_latest_blockhash().await?;
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&payer.pubkey()), &[payer], blockhash);
+    let sig = rpc.send_and_confirm_transaction(&tx).await?;
+    println!("{registrant} #{position_id}: value protected ({size_bucket:?}) {sig}");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn estimates_from_the_bucket_and_configured_penalty() {
+        let expected = (SizeBucket::From1kTo10k, DEFAULT_PENALTY_BPS);
+        assert_eq!(estimate(None, 250_000, None), expected);
+        assert_eq!(
+            estimate(None, 99_999, Some(800)),
+            (SizeBucket::Under1k, 800)
+        );
+        assert_eq!(estimate(None, 10_000_000, None).0, SizeBucket::Over100k);
+        let revealed = Some(SizeBucket::From10kTo100k);
+        assert_eq!(
+            estimate(revealed, 250_000, None).0,
+            SizeBucket::From10kTo100k
+        );
+    }
+}
@@ -0,0 +1,185 @@
+//! Transaction submission: through a Jito block engine as a bundle when one is
+//! configured, so checks still land when blocks are congested, and through
+//! plain RPC otherwise.
+//!
+//! A bundle is the keeper's transactions in order with the tip added to the
+//! last one, all or nothing. If the block engine rejects it or it hasn't
+//! landed within `confirm_timeout_secs`, the same signed transactions are
+//! sent over RPC. They keep their signatures, so a bundle that lands late
+//! can't execute them twice.
+
+use crate::config::JitoConfig;
+use anchor_client::solana_sdk::{
+    commitment_config::CommitmentConfig,
+    hash::Hash,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature},
+    signer::Signer,
+    transaction::Transaction,
+};
+use anyhow::{anyhow, bail, Context, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde_json::{json, Value};
+use solana_rpc_client::nonblocking::rpc_client::RpcClient;
+use std::time::{Duration, Instant};
+
+/// Most transactions the block engine accepts in one bundle.
+pub const MAX_BUNDLE_TRANSACTIONS: usize = 5;
+
+pub struct Submitter {
+    jito: Option<Jito>,
+}
+
+struct Jito {
+    config: JitoConfig,
+    tip_accounts: Vec<Pubkey>,
+    http: reqwest::Client,
+}
+
+impl Submitter {
+    /// Without `jito`, everything goes through RPC. With it, the tip accounts
+    /// are fetched from the block engine unless one is configured.
+    pub async fn new(jito: Option<JitoConfig>) -> Result<Self> {
+        let Some(config) = jito else {
+            return Ok(Self { jito: None });
+        };
+        let http = reqwest::Client::new();
+        let tip_accounts = match config.tip_account {
+            Some(account) => vec![account],
+            None => {
+                let accounts = block_engine(&http, &config, "getTipAccounts", json!([]))
+                    .await
+                    .context("failed to fetch Jito tip accounts")?;
+                serde_json::from_value::<Vec<String>>(accounts)?
+                    .iter()
+                    .map(|account| account.parse())
+                    .collect::<Result<_, _>>()?
+            }
+        };
+        if tip_accounts.is_empty() {
+            bail!("block engine returned no tip accounts");
+        }
+        Ok(Self {
+            jito: Some(Jito {
+                config,
+                tip_accounts,
+                http,
+            }),
+        })
+    }
+
+    /// Signs one transaction per instruction list with `payer`, adding the
+    /// tip to the last one when bundling.
+    pub fn sign(
+        &self,
+        payer: &Keypair,
+        mut transactions: Vec<Vec<Instruction>>,
+        blockhash: Hash,
+    ) -> Result<Vec<Transaction>> {
+        if transactions.is_empty() || transactions.len() > MAX_BUNDLE_TRANSACTIONS {
+            bail!("expected 1 to {MAX_BUNDLE_TRANSACTIONS} transactions");
+        }
+        if let Some(jito) = &self.jito {
+            let tip = jito.tip_instruction(&payer.pubkey(), &blockhash);
+            transactions.last_mut().unwrap().push(tip);
+        }
+        Ok(transactions
+            .iter()
+            .map(|ixs| {
+                Transaction::new_signed_with_payer(ixs, Some(&payer.pubkey()), &[payer], blockhash)
+            })
+            .collect())
+    }
+
+    /// Submits transactions from [`Submitter::sign`] and waits until all are
+    /// confirmed.
+    pub async fn send(&self, rpc: &RpcClient, signed: &[Transaction]) -> Result<()> {
+        let signatures: Vec<Signature> = signed.iter().map(|tx| tx.signatures[0]).collect();
+        if let Some(jito) = &self.jito {
+            match jito.send_bundle(rpc, signed, &signatures).await {
+                Ok(()) => return Ok(()),
+                Err(err) => eprintln!("bundle failed, falling back to RPC: {err:#}"),
+            }
+        }
+        for tx in signed {
+            rpc.send_and_confirm_transaction(tx).await?;
+        }
+        Ok(())
+    }
+}
+
+impl Jito {
+    /// Tips one of the tip accounts, picked by blockhash so bundles spread
+    /// across them.
+    fn tip_instruction(&self, payer: &Pubkey, blockhash: &Hash) -> Instruction {
+        let account = self.tip_accounts[blockhash.as_ref()[0] as usize % self.tip_accounts.len()];
+        solana_system_interface::instruction::transfer(payer, &account, self.config.tip_lamports)
+    }
+
+    /// Sends the bundle and waits for every transaction to confirm.
+    async fn send_bundle(
+        &self,
+        rpc: &RpcClient,
+        transactions: &[Transaction],
+        signatures: &[Signature],
+    ) -> Result<()> {
+        let encoded = transactions
+            .iter()
+            .map(|tx| Ok(STANDARD.encode(bincode::serialize(tx)?)))
+            .collect::<Result<Vec<_>>>()?;
+        let bundle_id = block_engine(
+            &self.http,
+            &self.config,
+            "sendBundle",
+            json!([encoded, { "encoding": "base64" }]),
+        )
+        .await?;
+        println!("bundle {bundle_id} sent");
+
+        // The block engine drops bundles with a failing transaction, so these
+        // only confirm if all of them succeeded.
+        let deadline = Instant::now() + Duration::from_secs(self.config.confirm_timeout_secs);
+        while Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(500)).await;
+            let statuses = rpc.get_signature_statuses(signatures).await?.value;
+            let confirmed = statuses.iter().all(|status| {
+                status
+                    .as_ref()
+                    .is_some_and(|s| s.satisfies_commitment(CommitmentConfig::confirmed()))
+            });
+            if confirmed {
+                return Ok(());
+            }
+        }
+        Err(anyhow!("bundle {bundle_id} not confirmed in time"))
+    }
+}
+
+/// Calls a block engine JSON-RPC method and returns its `result`.
+async fn block_engine(
+    http: &reqwest::Client,
+    config: &JitoConfig,
+    method: &str,
+    params: Value,
+) -> Result<Value> {
+    let url = format!(
+        "{}/api/v1/bundles",
+        config.block_engine_url.trim_end_matches('/')
+    );
+    let response: Value = http
+        .post(url)
+        .json(&json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params }))
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    if let Some(err) = response.get("error") {
+        bail!("{method}: {err}");
+    }
+    response
+        .get("result")
+        .cloned()
+        .ok_or_else(|| anyhow!("{method}: no result"))
+}
@@ -0,0 +1,75 @@
+//! Forwards program events to the configured webhooks and notifier plugins.
+
+use crate::plugins::NotifierPlugin;
+use anchor_client::{EventContext, EventUnsubscriber, Program};
+use anyhow::Result;
+use sentinel_client::schema::WebhookPayload;
+use serde_json::Value;
+use std::{ops::Deref, sync::Arc};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+/// Subscribes to `RiskRevealed`, `ActionRequired`, `ConfigUpdateProposed` and
+/// `ComputationFailed`, returning the payloads to post, shaped as
+/// `schemas/v1/webhook_payload.json`. Events stop once the unsubscribers are
+/// dropped.
+pub async fn subscribe<C>(
+    program: &Program<C>,
+) -> Result<(UnboundedReceiver<Value>, [EventUnsubscriber<'_>; 4])>
+where
+    C: Deref<Target = anchor_client::solana_sdk::signature::Keypair> + Clone,
+{
+    let (tx, rx) = unbounded_channel();
+    let revealed = program
+        .on(forward(&tx, WebhookPayload::risk_revealed))
+        .await?;
+    let action = program
+        .on(forward(&tx, WebhookPayload::action_required))
+        .await?;
+    let proposed = program
+        .on(forward(&tx, WebhookPayload::config_update_proposed))
+        .await?;
+    let failed = program
+        .on(forward(&tx, WebhookPayload::computation_failed))
+        .await?;
+    Ok((rx, [revealed, action, proposed, failed]))
+}
+
+/// Posts each payload to every webhook and hands it to every notifier.
+/// Failures are logged, not retried.
+pub async fn run(
+    mut payloads: UnboundedReceiver<Value>,
+    urls: Arc<[String]>,
+    notifiers: Arc<[Arc<dyn NotifierPlugin>]>,
+) {
+    let http = reqwest::Client::new();
+    while let Some(payload) = payloads.recv().await {
+        println!("event: {payload}");
+        for url in urls.iter() {
+            let sent = http
+                .post(url)
+                .json(&payload)
+                .send()
+                .await
+                .and_then(reqwest::Response::error_for_status);
+            if let Err(err) = sent {
+                eprintln!("webhook {url} failed: {err}");
+            }
+        }
+        for notifier in notifiers.iter() {
+            if let Err(err) = notifier.notify(&payload).await {
+                eprintln!("plugin {} failed: {err:#}", notifier.name());
+            }
+        }
+    }
+}
+
+fn forward<T: 'static>(
+    tx: &UnboundedSender<Value>,
+    to_payload: fn(String, u64, &T) -> WebhookPayload,
+) -> impl Fn(&EventContext, T) + Send + 'static {
+    let tx = tx.clone();
+    move |ctx, event| {
+        let payload = to_payload(ctx.signature.to_string(), ctx.slot, &event);
+        let _ = tx.send(serde_json::to_value(payload).expect("payloads serialize"));
+    }
+}
@@ -0,0 +1,109 @@
+//! Price feeds whose price anyone holding the feed's authority can set, for
+//! localnet tests and demos where there is no Pyth.
+//!
+//! A feed is a Pyth `PriceUpdateV2` byte for byte, discriminator included,
+//! so `sentinel::oracle` reads it like a real update once built with the
+//! `localnet` feature, which accepts accounts this program owns.
+
+use anchor_lang::prelude::*;
+
+declare_id!("CJTZNnnoLgCWAUyGhLGUwvMUBvCy8mP5VULbHT58xMaQ");
+
+#[program]
+pub mod mock_oracle {
+    use super::*;
+
+    /// Creates the feed `feed_id` of `authority`, fully verified and with
+    /// prices in units of 10^`exponent`. It has no price until `set_price`.
+    pub fn create_feed(ctx: Context<CreateFeed>, feed_id: [u8; 32], exponent: i32) -> Result<()> {
+        let feed = &mut ctx.accounts.feed;
+        feed.write_authority = ctx.accounts.authority.key();
+        feed.verification_level = VerificationLevel::Full;
+        feed.price_message = PriceFeedMessage {
+            feed_id,
+            exponent,
+            ..PriceFeedMessage::default()
+        };
+        Ok(())
+    }
+
+    /// Publishes `price` ± `conf` at `publish_time`, or at the current time
+    /// if `None`, e.g. an old time to make the feed stale. The EMA follows
+    /// the price exactly and `posted_slot` is the current slot.
+    pub fn set_price(
+        ctx: Context<SetPrice>,
+        price: i64,
+        conf: u64,
+        publish_time: Option<i64>,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let feed = &mut ctx.accounts.feed;
+        let message = &mut feed.price_message;
+        message.prev_publish_time = message.publish_time;
+        message.publish_time = publish_time.unwrap_or(clock.unix_timestamp);
+        message.price = price;
+        message.conf = conf;
+        message.ema_price = price;
+        message.ema_conf = conf;
+        feed.posted_slot = clock.slot;
+        Ok(())
+    }
+
+    /// Marks the feed fully verified, or partially verified by
+    /// `num_signatures` guardians, which readers must reject.
+    pub fn set_verification_level(ctx: Context<SetPrice>, level: VerificationLevel) -> Result<()> {
+        ctx.accounts.feed.verification_level = level;
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(feed_id: [u8; 32])]
+pub struct CreateFeed<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + PriceUpdateV2::INIT_SPACE,
+        seeds = [b"feed", authority.key().as_ref(), feed_id.as_ref()],
+        bump,
+    )]
+    pub feed: Account<'info, PriceUpdateV2>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetPrice<'info> {
+    pub write_authority: Signer<'info>,
+    #[account(mut, has_one = write_authority)]
+    pub feed: Account<'info, PriceUpdateV2>,
+}
+
+/// Pyth receiver's `PriceUpdateV2`, field for field.
+#[account]
+#[derive(InitSpace)]
+pub struct PriceUpdateV2 {
+    pub write_authority: Pubkey,
+    pub verification_level: VerificationLevel,
+    pub price_message: PriceFeedMessage,
+    pub posted_slot: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub enum VerificationLevel {
+    Partial { num_signatures: u8 },
+    Full,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, InitSpace)]
+pub struct PriceFeedMessage {
+    pub feed_id: [u8; 32],
+    pub price: i64,
+    pub conf: u64,
+    pub exponent: i32,
+    pub publish_time: i64,
+    pub prev_publish_time: i64,
+    pub ema_price: i64,
+    pub ema_conf: u64,
+}
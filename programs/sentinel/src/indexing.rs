@@ -0,0 +1,57 @@
+//! Events indexers can't lose to log truncation.
+//!
+//! `emit!` writes an event to the program log, which the runtime truncates
+//! once a transaction logs too much. With the `event-cpi` feature,
+//! `emit_indexed!` also emits it with `emit_cpi!`: as the data of a
+//! self-invocation, which lands in the transaction's inner instructions
+//! instead. The self-CPI costs compute units on every callback, so it is
+//! opt-in.
+//!
+//! A callback emitting this way takes `#[cfg_attr(feature = "event-cpi",
+//! event_cpi)]` on its accounts, which appends the event authority and this
+//! program after its named accounts; the queueing instruction passes
+//! `callback_accounts()` in that spot.
+//!
+//! ```ignore
+//! emit_indexed!(ctx, HealthCheckCompleted { owner, position_id, timestamp });
+//! ```
+
+use anchor_lang::prelude::*;
+use arcium_client::idl::arcium::types::CallbackAccount;
+
+/// Seed of the PDA `emit_cpi!` signs its self-invocation with.
+pub const EVENT_AUTHORITY_SEED: &[u8] = b"__event_authority";
+
+/// Accounts `#[event_cpi]` adds to a callback, in order; none without the
+/// `event-cpi` feature.
+pub fn callback_accounts() -> Vec<CallbackAccount> {
+    if !cfg!(feature = "event-cpi") {
+        return Vec::new();
+    }
+    let (event_authority, _) = Pubkey::find_program_address(&[EVENT_AUTHORITY_SEED], &crate::ID);
+    vec![
+        CallbackAccount {
+            pubkey: event_authority,
+            is_writable: false,
+        },
+        CallbackAccount {
+            pubkey: crate::ID,
+            is_writable: false,
+        },
+    ]
+}
+
+/// `emit!`s the event and, with the `event-cpi` feature, `emit_cpi!`s it
+/// too. `$ctx` is the instruction's `Context`.
+#[macro_export]
+macro_rules! emit_indexed {
+    ($ctx:ident, $event:expr) => {{
+        let event = $event;
+        #[cfg(feature = "event-cpi")]
+        {
+            let ctx = &$ctx;
+            ::anchor_lang::prelude::emit_cpi!(event);
+        }
+        ::anchor_lang::prelude::emit!(event);
+    }};
+}
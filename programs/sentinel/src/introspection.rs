@@ -0,0 +1,46 @@
+//! Instruction-sysvar checks that tie a callback to the computation it
+//! completes.
+//!
+//! `#[arcium_callback]` already requires the previous instruction to be
+//! Arcium's `callback_computation`. [`verify_callback`] additionally requires
+//! that instruction to reference the computation account the callback was
+//! handed, and that computation to be the one the account is waiting on. A
+//! late callback for a computation that was force-cleared, or queued by an
+//! earlier program version and since superseded, is rejected instead of
+//! overwriting newer state.
+
+use crate::ErrorCode;
+use anchor_lang::{prelude::*, solana_program::sysvar::instructions};
+use arcium_anchor::prelude::*;
+
+/// Checks that the preceding Arcium instruction completes `computation_account`,
+/// and that it is the computation `pending` (queued on the same MXE) refers to.
+pub fn verify_callback(
+    instructions_sysvar: &AccountInfo,
+    computation_account: &Pubkey,
+    mxe_account: &MXEAccount,
+    pending: Option<u64>,
+) -> Result<()> {
+    let current = instructions::load_current_index_checked(instructions_sysvar)? as usize;
+    let invoker = current
+        .checked_sub(1)
+        .map(|index| instructions::load_instruction_at_checked(index, instructions_sysvar))
+        .transpose()?
+        .ok_or(ErrorCode::CallbackComputationMismatch)?;
+    require!(
+        invoker.program_id == ARCIUM_PROG_ID
+            && invoker
+                .accounts
+                .iter()
+                .any(|meta| meta.pubkey == *computation_account),
+        ErrorCode::CallbackComputationMismatch
+    );
+
+    let offset = pending.ok_or(ErrorCode::StaleCallback)?;
+    require_keys_eq!(
+        derive_comp_pda!(offset, mxe_account, ErrorCode::ClusterNotSet),
+        *computation_account,
+        ErrorCode::StaleCallback
+    );
+    Ok(())
+}
@@ -0,0 +1,48 @@
+//! Position state invariants, checked at the end of every callback that
+//! writes to a `PositionAccount`.
+//!
+//! With the `strict-invariants` feature (devnet builds) a violation fails the
+//! callback; otherwise it is only logged so a mainnet callback is never lost
+//! to a bookkeeping bug.
+
+use crate::PositionAccount;
+use anchor_lang::prelude::*;
+
+/// The fields of a position that must only move forward.
+pub struct PositionSnapshot {
+    nonce: u128,
+    last_check: i64,
+}
+
+impl PositionSnapshot {
+    pub fn of(position: &PositionAccount) -> Self {
+        Self {
+            nonce: position.nonce,
+            last_check: position.last_check,
+        }
+    }
+}
+
+/// Checks `after` against the state captured in `before`.
+pub fn check_position(before: &PositionSnapshot, after: &PositionAccount) -> Result<()> {
+    invariant(after.nonce > before.nonce, "nonce_monotonic")?;
+    invariant(
+        after.last_check >= before.last_check,
+        "last_check_monotonic",
+    )?;
+    invariant(
+        !after.is_active || after.risk_state.iter().any(|c| *c != [0; 32]),
+        "active_has_ciphertexts",
+    )?;
+    Ok(())
+}
+
+fn invariant(holds: bool, name: &str) -> Result<()> {
+    debug_assert!(holds, "invariant violated: {name}");
+    if !holds {
+        log_error!("invariant_violated", name = name);
+        #[cfg(feature = "strict-invariants")]
+        return err!(crate::ErrorCode::InvariantViolation);
+    }
+    Ok(())
+}
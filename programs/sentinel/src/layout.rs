@@ -0,0 +1,44 @@
+//! Compile-time checks of the program's instruction arguments against the
+//! circuit interfaces `arcis build` writes to `build/<circuit>.idarc`, so a
+//! field added to a circuit input fails the program build instead of every
+//! computation.
+
+/// Ciphertexts in the first encrypted input of the circuit `idarc`
+/// describes: those between its x25519 public key and the end of the array
+/// that follows it.
+pub const fn first_encrypted_input_len(idarc: &str) -> usize {
+    let bytes = idarc.as_bytes();
+    let mut i = match find(bytes, b"\"arcis_x25519_pubkey\"", 0) {
+        Some(at) => at,
+        None => return 0,
+    };
+    let end = match find(bytes, b"],\"type\":\"array\"", i) {
+        Some(at) => at,
+        None => return 0,
+    };
+    let mut count = 0;
+    while let Some(at) = find(bytes, b"\"ciphertext\"", i) {
+        if at >= end {
+            break;
+        }
+        count += 1;
+        i = at + 1;
+    }
+    count
+}
+
+/// Offset of the first `needle` in `haystack` at or after `from`.
+const fn find(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    let mut at = from;
+    while at + needle.len() <= haystack.len() {
+        let mut j = 0;
+        while j < needle.len() && haystack[at + j] == needle[j] {
+            j += 1;
+        }
+        if j == needle.len() {
+            return Some(at);
+        }
+        at += 1;
+    }
+    None
+}
@@ -1,10 +1,160 @@
 use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::{get_associated_token_address_with_program_id, AssociatedToken},
+    token_2022::{spl_token_2022, Token2022},
+    token_interface::{self, Mint, TokenAccount},
+};
 use arcium_anchor::prelude::*;
 use arcium_client::idl::arcium::types::CallbackAccount;
+use solana_sha256_hasher::hash;
+
+#[macro_use]
+pub mod logging;
+#[macro_use]
+pub mod indexing;
+pub mod introspection;
+pub mod invariants;
+pub mod layout;
+pub mod oracle;
+pub mod protocols;
+pub mod rules;
+pub mod validation;
+pub mod wormhole;
 
 const COMP_DEF_OFFSET_INIT_RISK_STATE: u32 = comp_def_offset("init_risk_state");
 const COMP_DEF_OFFSET_CHECK_HEALTH: u32 = comp_def_offset("check_position_health");
 const COMP_DEF_OFFSET_REVEAL_RISK: u32 = comp_def_offset("reveal_risk");
+const COMP_DEF_OFFSET_REVEAL_RISK_TO_OWNER: u32 = comp_def_offset("reveal_risk_to_owner");
+const COMP_DEF_OFFSET_AGGREGATE_PORTFOLIO_RISK: u32 = comp_def_offset("aggregate_portfolio_risk");
+const COMP_DEF_OFFSET_CHECK_HEALTH_WITH_PRICE: u32 =
+    comp_def_offset("check_position_health_with_price");
+const COMP_DEF_OFFSET_CHECK_HEALTH_CROSS_CHAIN: u32 =
+    comp_def_offset("check_position_health_cross_chain");
+const COMP_DEF_OFFSET_SET_ALERT_CONFIG: u32 = comp_def_offset("set_alert_config");
+const COMP_DEF_OFFSET_CHECK_HEALTH_WITH_RULES: u32 =
+    comp_def_offset("check_position_health_with_rules");
+const COMP_DEF_OFFSET_REVEAL_HISTORY_SUMMARY: u32 = comp_def_offset("reveal_history_summary");
+const COMP_DEF_OFFSET_CHECK_HEALTH_FROM_PROTOCOL: u32 =
+    comp_def_offset("check_position_health_from_protocol");
+const COMP_DEF_OFFSET_CHECK_HEALTH_BATCH: u32 = comp_def_offset("check_position_health_batch");
+const COMP_DEF_OFFSET_COMPUTE_HEALTH_FACTOR: u32 = comp_def_offset("compute_health_factor");
+const COMP_DEF_OFFSET_REVEAL_HEALTH_FACTOR_TO_OWNER: u32 =
+    comp_def_offset("reveal_health_factor_to_owner");
+const COMP_DEF_OFFSET_REVEAL_RISK_TO_VIEWER: u32 = comp_def_offset("reveal_risk_to_viewer");
+const COMP_DEF_OFFSET_STRESS_TEST_POSITION: u32 = comp_def_offset("stress_test_position");
+const COMP_DEF_OFFSET_ATTEST_HEALTH: u32 = comp_def_offset("attest_health");
+const COMP_DEF_OFFSET_REVEAL_SIZE_BUCKET: u32 = comp_def_offset("reveal_size_bucket");
+const COMP_DEF_OFFSET_ROTATE_ENCRYPTION: u32 = comp_def_offset("rotate_encryption");
+const COMP_DEF_OFFSET_EVALUATE_STOP_LOSS: u32 = comp_def_offset("evaluate_stop_loss");
+const COMP_DEF_OFFSET_CHECK_PERP_HEALTH: u32 = comp_def_offset("check_perp_health");
+const COMP_DEF_OFFSET_COMPUTE_RISK_SCORE: u32 = comp_def_offset("compute_risk_score");
+const COMP_DEF_OFFSET_ACCUMULATE_RISK_HEATMAP: u32 = comp_def_offset("accumulate_risk_heatmap");
+const COMP_DEF_OFFSET_REVEAL_RISK_HEATMAP: u32 = comp_def_offset("reveal_risk_heatmap");
+
+/// Positions per portfolio, fixed by the arity of `aggregate_portfolio_risk`.
+pub const MAX_PORTFOLIO_POSITIONS: usize = 8;
+
+/// Position ids one `OwnerIndex` page lists. A wallet with more open
+/// positions registers the others into its next pages.
+pub const MAX_INDEXED_POSITIONS: usize = 64;
+
+/// Ciphertexts of an encrypted `PositionData`, the position input of
+/// `check_health` and the circuits sharing its encoding. Checked below
+/// against the built circuits; the client's `EncryptedPosition` is sized by
+/// it.
+pub const POSITION_FIELD_COUNT: usize = 3;
+
+const _: () = {
+    use layout::first_encrypted_input_len as len;
+    macro_rules! idarc {
+        ($circuit:literal) => {
+            include_str!(concat!("../../../build/", $circuit, ".idarc"))
+        };
+    }
+    assert!(len(idarc!("check_position_health")) == POSITION_FIELD_COUNT);
+    assert!(len(idarc!("check_position_health_with_rules")) == POSITION_FIELD_COUNT);
+    assert!(len(idarc!("check_position_health_batch")) == POSITION_FIELD_COUNT);
+    assert!(len(idarc!("reveal_size_bucket")) == POSITION_FIELD_COUNT);
+};
+
+/// Positions per `check_health_batch`, fixed by the arity of
+/// `check_position_health_batch`.
+pub const MAX_BATCH_POSITIONS: usize = 4;
+
+/// Collateral mints the risk heatmap counts separately, fixed by
+/// `HeatmapCounts`; positions on any other mint share one more bucket.
+pub const HEATMAP_CLASSES: usize = 4;
+pub const HEATMAP_BUCKETS: usize = HEATMAP_CLASSES + 1;
+
+/// Positions per `accumulate_risk_heatmap`, fixed by its circuit's arity.
+pub const MAX_HEATMAP_BATCH: usize = 8;
+
+/// Positions an epoch has to sample before its heatmap can be published,
+/// and positions a bucket needs for its count to be revealed. Smaller
+/// buckets would give away the positions in them.
+pub const MIN_HEATMAP_POSITIONS: u32 = 20;
+pub const MIN_HEATMAP_BUCKET_POSITIONS: u32 = 5;
+
+/// Health checks kept per position, fixed by the arity of
+/// `reveal_history_summary`.
+pub const RISK_HISTORY_LEN: usize = 8;
+
+/// Administrative changes kept in the `AdminAuditLog`.
+pub const ADMIN_AUDIT_LOG_LEN: usize = 64;
+
+/// Current `EncryptedEnvelope::version`.
+pub const ENVELOPE_VERSION: u8 = 1;
+
+/// Most ciphertexts an `EncryptedEnvelope` stored in an account can hold.
+pub const MAX_ENVELOPE_CIPHERTEXTS: usize = 4;
+
+/// Most ciphertexts an `ActionMemo` may hold.
+pub const MAX_MEMO_CIPHERTEXTS: usize = 16;
+
+/// Current `PositionAccount::version`.
+pub const POSITION_VERSION: u8 = 1;
+
+/// Zeroed bytes at the end of a `PositionAccount` that later fields take
+/// their space from.
+pub const POSITION_RESERVED_BYTES: usize = 44;
+
+/// Most accounts an `AutoActionConfig` instruction may reference.
+pub const MAX_AUTO_ACTION_ACCOUNTS: usize = 8;
+
+/// Most instruction data an `AutoActionConfig` may hold.
+pub const MAX_AUTO_ACTION_DATA: usize = 256;
+
+/// Instruction discriminator of the order a stop-loss executor receives,
+/// Anchor's for `execute_stop_loss`: the first 8 bytes of
+/// `sha256("global:execute_stop_loss")`.
+pub const STOP_LOSS_EXECUTE_DISCRIMINATOR: [u8; 8] = [38, 122, 90, 90, 182, 241, 7, 4];
+
+/// Longest a keeper lease may run without renewal.
+pub const MAX_LEASE_SECS: i64 = 3_600;
+
+/// Least bond a `KeeperAccount` holds to check positions restricted to
+/// registered keepers.
+pub const MIN_KEEPER_BOND_LAMPORTS: u64 = 1_000_000_000;
+
+/// Seconds between `unbond_keeper` and the bond becoming withdrawable, in
+/// which disputes over the keeper's last checks can still slash it.
+pub const KEEPER_UNBONDING_SECS: i64 = 7 * 86_400;
+
+/// Oldest risk state, in seconds since its check, that `reveal_risk` reveals
+/// until the upgrade authority sets a default in `GlobalConfig`.
+pub const DEFAULT_MAX_STALENESS_SECS: i64 = 86_400;
+
+/// TVL drop between a protocol's last two snapshots, in basis points, above
+/// which `check_health` flags an exodus unless the owner sets another.
+pub const DEFAULT_TVL_DROP_BPS: u16 = 2_000;
+
+/// Delay between proposing and applying a `GlobalConfig` change until the
+/// upgrade authority sets another one.
+pub const DEFAULT_CONFIG_UPDATE_DELAY_SECS: i64 = 86_400;
+
+/// Seconds after which `force_clear_pending` may drop a computation whose
+/// callback has not arrived.
+pub const PENDING_TIMEOUT_SECS: i64 = 600;
 
 declare_id!("ABDZr3DvUSnugBNrAj8vaAhKt3tHafA82MDja812QbJC");
 
@@ -13,6 +163,9 @@ pub mod sentinel {
     use super::*;
 
     // ─── Computation Definition Initializers ───
+    //
+    // The first caller of `init_comp_def` becomes the circuit's upload authority,
+    // so these are restricted to the program's upgrade authority.
 
     pub fn init_risk_state_comp_def(ctx: Context<InitRiskStateCompDef>) -> Result<()> {
         init_comp_def(ctx.accounts, None, None)?;
@@ -29,25 +182,260 @@ pub mod sentinel {
         Ok(())
     }
 
+    pub fn init_reveal_risk_to_owner_comp_def(
+        ctx: Context<InitRevealRiskToOwnerCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    pub fn init_aggregate_portfolio_risk_comp_def(
+        ctx: Context<InitAggregatePortfolioRiskCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    pub fn init_accumulate_risk_heatmap_comp_def(
+        ctx: Context<InitAccumulateRiskHeatmapCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    pub fn init_reveal_risk_heatmap_comp_def(
+        ctx: Context<InitRevealRiskHeatmapCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    pub fn init_check_health_with_price_comp_def(
+        ctx: Context<InitCheckHealthWithPriceCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    pub fn init_check_health_cross_chain_comp_def(
+        ctx: Context<InitCheckHealthCrossChainCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    pub fn init_set_alert_config_comp_def(ctx: Context<InitSetAlertConfigCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    pub fn init_check_health_with_rules_comp_def(
+        ctx: Context<InitCheckHealthWithRulesCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    pub fn init_reveal_history_summary_comp_def(
+        ctx: Context<InitRevealHistorySummaryCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    pub fn init_check_health_from_protocol_comp_def(
+        ctx: Context<InitCheckHealthFromProtocolCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    pub fn init_check_health_batch_comp_def(
+        ctx: Context<InitCheckHealthBatchCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    pub fn init_compute_health_factor_comp_def(
+        ctx: Context<InitComputeHealthFactorCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    pub fn init_reveal_health_factor_to_owner_comp_def(
+        ctx: Context<InitRevealHealthFactorToOwnerCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    pub fn init_reveal_risk_to_viewer_comp_def(
+        ctx: Context<InitRevealRiskToViewerCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    pub fn init_stress_test_position_comp_def(
+        ctx: Context<InitStressTestPositionCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    pub fn init_attest_health_comp_def(ctx: Context<InitAttestHealthCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    pub fn init_reveal_size_bucket_comp_def(
+        ctx: Context<InitRevealSizeBucketCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    pub fn init_rotate_encryption_comp_def(
+        ctx: Context<InitRotateEncryptionCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    pub fn init_evaluate_stop_loss_comp_def(
+        ctx: Context<InitEvaluateStopLossCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    pub fn init_check_perp_health_comp_def(ctx: Context<InitCheckPerpHealthCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    pub fn init_compute_risk_score_comp_def(
+        ctx: Context<InitComputeRiskScoreCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
     // ─── Register Position ───
 
     /// Registers a new position for monitoring. Creates the position account
-    /// and initializes encrypted risk state via MPC.
+    /// and initializes encrypted risk state via MPC. An optional `referrer` is
+    /// recorded on the position and counted in the referrer's `ReferralStats`.
+    /// `metadata` says which protocol account the position mirrors and is
+    /// public; `label_hash` is as for `set_label`, zero for no label. The
+    /// position id is added to page `index_page` of the payer's `OwnerIndex`,
+    /// which is created if needed; a page after the first is only created
+    /// once the one before it exists.
+    #[allow(clippy::too_many_arguments)]
     pub fn register_position(
         ctx: Context<RegisterPosition>,
         computation_offset: u64,
         position_id: u32,
         nonce: u128,
+        referrer: Option<Pubkey>,
+        metadata: PositionMetadata,
+        label_hash: [u8; 32],
+        index_page: u32,
     ) -> Result<()> {
-        msg!("Registering position for monitoring");
+        let circuit_version = GlobalConfig::require_active(&ctx.accounts.global_config)?;
+        log_info!(
+            "register_position",
+            position_id = position_id,
+            owner = ctx.accounts.payer.key()
+        );
+
+        require!(
+            referrer.is_some() == ctx.accounts.referral_stats.is_some(),
+            ErrorCode::ReferralStatsMismatch
+        );
+        require!(
+            referrer != Some(ctx.accounts.payer.key()),
+            ErrorCode::SelfReferral
+        );
 
         ctx.accounts.position_acc.bump = ctx.bumps.position_acc;
         ctx.accounts.position_acc.position_id = position_id;
         ctx.accounts.position_acc.owner = ctx.accounts.payer.key();
+        ctx.accounts.position_acc.registrant = ctx.accounts.payer.key();
+        ctx.accounts.position_acc.pending_owner = None;
         ctx.accounts.position_acc.nonce = nonce;
-        ctx.accounts.position_acc.risk_state = [[0; 32]; 2];
+        ctx.accounts.position_acc.risk_state = [[0; 32]; 3];
         ctx.accounts.position_acc.last_check = 0;
         ctx.accounts.position_acc.is_active = true;
+        ctx.accounts.position_acc.referrer = referrer;
+        ctx.accounts.position_acc.pending_computation = Some(computation_offset);
+        ctx.accounts.position_acc.pending_since = Clock::get()?.unix_timestamp;
+        ctx.accounts.position_acc.pending_circuit_version = circuit_version;
+        ctx.accounts.position_acc.risk_state_version = 0;
+        ctx.accounts.position_acc.price_feed = None;
+        ctx.accounts.position_acc.keeper = None;
+        ctx.accounts.position_acc.keeper_expiry_slot = None;
+        ctx.accounts.position_acc.cross_chain_emitter = None;
+        ctx.accounts.position_acc.alert_config = [[0; 32]; 3];
+        ctx.accounts.position_acc.alert_nonce = 0;
+        ctx.accounts.position_acc.alert_config_set = false;
+        ctx.accounts.position_acc.min_action_severity = 1;
+        ctx.accounts.position_acc.max_staleness_secs = None;
+        ctx.accounts.position_acc.health_factor = [0; 32];
+        ctx.accounts.position_acc.label_hash = label_hash;
+        ctx.accounts.position_acc.metadata = metadata;
+        ctx.accounts.position_acc.deferred_until = 0;
+        ctx.accounts.position_acc.heatmap_epoch = 0;
+        ctx.accounts.position_acc.min_check_interval_secs = 0;
+        ctx.accounts.position_acc.size_disclosure_allowed = false;
+        ctx.accounts.position_acc.size_bucket = None;
+        ctx.accounts.position_acc.version = POSITION_VERSION;
+        ctx.accounts.position_acc.registered_keepers_only = false;
+        ctx.accounts.position_acc.heartbeat_deadline_secs = 0;
+        ctx.accounts.position_acc.monitoring_lapsed = false;
+        ctx.accounts.position_acc.last_seen = 0;
+        ctx.accounts.position_acc.receipt_minted = false;
+        ctx.accounts.position_acc.indexed = true;
+        ctx.accounts.position_acc.index_page = index_page;
+        ctx.accounts.position_acc._reserved = [0; POSITION_RESERVED_BYTES];
+        ctx.accounts.position_acc.health_factor_nonce = 0;
+        ctx.accounts.position_acc.health_factor_at = 0;
+        ctx.accounts.position_acc.tvl_protocol = None;
+        ctx.accounts.position_acc.tvl_drop_bps = DEFAULT_TVL_DROP_BPS;
+        let (config_version, risk_model) = GlobalConfig::risk_model(&ctx.accounts.global_config)?;
+        ctx.accounts.position_acc.config_version = config_version;
+        ctx.accounts.position_acc.risk_model = risk_model;
+
+        let owner_index = &mut ctx.accounts.owner_index;
+        if owner_index.registrant == Pubkey::default() {
+            // Pages are created in order, so clients read them until one is
+            // missing
+            require!(
+                index_page == 0 || ctx.accounts.previous_owner_index.is_some(),
+                ErrorCode::OwnerIndexPageGap
+            );
+            owner_index.bump = ctx.bumps.owner_index;
+            owner_index.registrant = ctx.accounts.payer.key();
+            owner_index.page = index_page;
+        }
+        owner_index.insert(position_id)?;
+
+        if let (Some(referrer), Some(stats)) = (referrer, ctx.accounts.referral_stats.as_mut()) {
+            let now = Clock::get()?.unix_timestamp;
+            stats.bump = ctx.bumps.referral_stats.unwrap_or_default();
+            stats.referrer = referrer;
+            stats.referred_positions = stats.referred_positions.saturating_add(1);
+            stats.last_referral = now;
+
+            emit!(ReferralRecorded {
+                referrer,
+                owner: ctx.accounts.payer.key(),
+                position_id,
+                timestamp: now,
+            });
+        }
 
         let args = ArgBuilder::new().plaintext_u128(nonce).build();
 
@@ -60,10 +448,14 @@ pub mod sentinel {
             vec![InitRiskStateCallback::callback_ix(
                 computation_offset,
                 &ctx.accounts.mxe_account,
-                &[CallbackAccount {
-                    pubkey: ctx.accounts.position_acc.key(),
-                    is_writable: true,
-                }],
+                &[
+                    vec![CallbackAccount {
+                        pubkey: ctx.accounts.position_acc.key(),
+                        is_writable: true,
+                    }],
+                    indexing::callback_accounts(),
+                ]
+                .concat(),
             )?],
             1,
             0,
@@ -77,22 +469,45 @@ pub mod sentinel {
         ctx: Context<InitRiskStateCallback>,
         output: SignedComputationOutputs<InitRiskStateOutput>,
     ) -> Result<()> {
+        introspection::verify_callback(
+            &ctx.accounts.instructions_sysvar,
+            ctx.accounts.computation_account.key,
+            &ctx.accounts.mxe_account,
+            ctx.accounts.position_acc.pending_computation,
+        )?;
         let o = match output.verify_output(
             &ctx.accounts.cluster_account,
             &ctx.accounts.computation_account,
         ) {
             Ok(InitRiskStateOutput { field_0 }) => field_0,
-            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+            Err(_) => {
+                log_error!("computation_aborted", circuit = "init_risk_state");
+                let now = Clock::get()?.unix_timestamp;
+                emit!(ctx
+                    .accounts
+                    .position_acc
+                    .fail_computation(ComputationKind::InitRiskState, now));
+                return Ok(());
+            }
         };
 
+        let before = invariants::PositionSnapshot::of(&ctx.accounts.position_acc);
         ctx.accounts.position_acc.risk_state = o.ciphertexts;
+        ctx.accounts.position_acc.risk_state_version =
+            ctx.accounts.position_acc.pending_circuit_version;
         ctx.accounts.position_acc.nonce = o.nonce;
+        ctx.accounts.position_acc.pending_computation = None;
+        invariants::check_position(&before, &ctx.accounts.position_acc)?;
 
-        emit!(PositionRegistered {
-            owner: ctx.accounts.position_acc.owner,
-            position_id: ctx.accounts.position_acc.position_id,
-            timestamp: Clock::get()?.unix_timestamp,
-        });
+        emit_indexed!(
+            ctx,
+            PositionRegistered {
+                owner: ctx.accounts.position_acc.owner,
+                position_id: ctx.accounts.position_acc.position_id,
+                metadata: ctx.accounts.position_acc.metadata,
+                timestamp: Clock::get()?.unix_timestamp,
+            }
+        );
 
         Ok(())
     }
@@ -101,16 +516,99 @@ pub mod sentinel {
 
     /// Submits encrypted position data for a privacy-preserving health check.
     /// The MPC nodes compute risk without seeing actual position values.
+    /// A delegated keeper is paid the plan's `fee_per_check` from the
+    /// position's subscription, which has to cover it. The fee is held in
+    /// the subscription until the check lands, less the plan's referral
+    /// share, which goes to the position's referrer; a check that aborts or
+    /// is cancelled costs nothing. With the position's
+    /// `check_bounty`, a keeper is also recorded to be paid its bounty when
+    /// the check lands.
+    ///
+    /// With `price_update`, the position's Pyth feed, the circuit also flags
+    /// a price drop of more than 10% since the last priced check. With
+    /// `tvl_snapshot`, that of the protocol the position follows, it flags a
+    /// TVL drop beyond the position's `tvl_drop_bps`.
     pub fn check_health(
         ctx: Context<CheckHealth>,
         computation_offset: u64,
         _position_id: u32,
-        encrypted_position: [[u8; 32]; 3], // 3 fields: value, collateral_ratio, threshold
+        encrypted_position: [[u8; 32]; POSITION_FIELD_COUNT], // value, collateral_ratio, threshold
         encryption_pubkey: [u8; 32],
         encryption_nonce: u128,
     ) -> Result<()> {
-        require!(ctx.accounts.position_acc.is_active, ErrorCode::PositionInactive);
+        let circuit_version = GlobalConfig::require_active(&ctx.accounts.global_config)?;
+        require!(
+            ctx.accounts.position_acc.is_active,
+            ErrorCode::PositionInactive
+        );
+        require!(
+            ctx.accounts.position_acc.may_submit(
+                &ctx.accounts.payer.key(),
+                Clock::get()?.slot,
+                ctx.accounts.keeper_account.as_deref()
+            )?,
+            ErrorCode::UnauthorizedKeeper
+        );
+        ctx.accounts
+            .position_acc
+            .require_check_interval(Clock::get()?.unix_timestamp)?;
+        let keeper_check = ctx.accounts.payer.key() != ctx.accounts.owner.key();
+        if keeper_check {
+            let referrer = ctx.accounts.position_acc.referrer;
+            match (
+                &ctx.accounts.monitoring_plan,
+                &mut ctx.accounts.subscription,
+            ) {
+                (Some(plan), Some(subscription)) => {
+                    Subscription::hold_fee(subscription, plan, ctx.accounts.payer.key(), referrer)?
+                }
+                _ => return err!(ErrorCode::SubscriptionRequired),
+            }
+        }
+        if let Some(bounty) = &mut ctx.accounts.check_bounty {
+            let payer = ctx.accounts.payer.key();
+            bounty.pending_payee = (payer != ctx.accounts.owner.key()
+                && CheckBounty::payable(bounty)?.is_some())
+            .then_some(payer);
+        }
+        let price = match &ctx.accounts.price_update {
+            Some(price_update) => {
+                let price_feed = ctx
+                    .accounts
+                    .position_acc
+                    .price_feed
+                    .ok_or(ErrorCode::PriceFeedNotSet)?;
+                require_keys_eq!(price_update.key(), price_feed, ErrorCode::PriceFeedMismatch);
+                oracle::read_price(price_update, Clock::get()?.unix_timestamp)?.price
+            }
+            None => 0,
+        };
+        let (tvl, previous_tvl) = match &ctx.accounts.tvl_snapshot {
+            Some(snapshot) => {
+                let protocol = ctx
+                    .accounts
+                    .position_acc
+                    .tvl_protocol
+                    .ok_or(ErrorCode::TvlProtocolNotSet)?;
+                require_keys_eq!(
+                    snapshot.protocol_program,
+                    protocol,
+                    ErrorCode::TvlProtocolMismatch
+                );
+                (snapshot.tvl, snapshot.previous_tvl)
+            }
+            None => (0, 0),
+        };
+        log_debug!(
+            "check_health",
+            position_id = ctx.accounts.position_acc.position_id,
+            computation_offset = computation_offset,
+            price = price,
+            tvl = tvl,
+            previous_tvl = previous_tvl
+        );
 
+        let model = ctx.accounts.position_acc.risk_model;
         let args = ArgBuilder::new()
             .x25519_pubkey(encryption_pubkey)
             .plaintext_u128(encryption_nonce)
@@ -122,11 +620,37 @@ pub mod sentinel {
                 ctx.accounts.position_acc.key(),
                 // 8 (discriminator) + 1 (bump)
                 8 + 1,
-                32 * 2, // risk_state: 2 x 32-byte ciphertexts
+                32 * 3, // risk_state: 3 x 32-byte ciphertexts
+            )
+            .plaintext_u128(ctx.accounts.position_acc.alert_nonce)
+            .account(
+                ctx.accounts.position_acc.key(),
+                // 8 (discriminator) + 1 (bump) + 96 (risk_state)
+                8 + 1 + 32 * 3,
+                32 * 3, // alert_config: 3 x 32-byte ciphertexts
             )
+            .plaintext_u8(ctx.accounts.position_acc.alert_config_set as u8)
+            .plaintext_u64(model.critical_buffer_bps)
+            .plaintext_u64(model.warning_buffer_bps)
+            .plaintext_u64(model.min_value_cents)
+            .plaintext_u64(price)
+            .plaintext_u64(tvl)
+            .plaintext_u64(previous_tvl)
+            .plaintext_u64(ctx.accounts.position_acc.tvl_drop_bps as u64)
             .build();
 
+        let referral_held = keeper_check
+            && ctx
+                .accounts
+                .subscription
+                .as_ref()
+                .is_some_and(|s| s.pending_referral_fee > 0);
         ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        ctx.accounts.position_acc.begin_computation(
+            computation_offset,
+            Clock::get()?.unix_timestamp,
+            circuit_version,
+        )?;
 
         queue_computation(
             ctx.accounts,
@@ -135,15 +659,86 @@ pub mod sentinel {
             vec![CheckPositionHealthCallback::callback_ix(
                 computation_offset,
                 &ctx.accounts.mxe_account,
-                &[CallbackAccount {
-                    pubkey: ctx.accounts.position_acc.key(),
-                    is_writable: true,
-                }],
+                &[
+                    vec![
+                        CallbackAccount {
+                            pubkey: ctx.accounts.position_acc.key(),
+                            is_writable: true,
+                        },
+                        // The program id stands in for an absent optional account
+                        match &ctx.accounts.risk_history {
+                            Some(history) => CallbackAccount {
+                                pubkey: history.key(),
+                                is_writable: true,
+                            },
+                            None => CallbackAccount {
+                                pubkey: crate::ID,
+                                is_writable: false,
+                            },
+                        },
+                        match &ctx.accounts.check_bounty {
+                            Some(bounty) => CallbackAccount {
+                                pubkey: bounty.key(),
+                                is_writable: true,
+                            },
+                            None => CallbackAccount {
+                                pubkey: crate::ID,
+                                is_writable: false,
+                            },
+                        },
+                        match ctx.accounts.subscription.as_ref().filter(|_| keeper_check) {
+                            Some(subscription) => CallbackAccount {
+                                pubkey: subscription.key(),
+                                is_writable: true,
+                            },
+                            None => CallbackAccount {
+                                pubkey: crate::ID,
+                                is_writable: false,
+                            },
+                        },
+                        match keeper_check {
+                            true => CallbackAccount {
+                                pubkey: ctx.accounts.payer.key(),
+                                is_writable: true,
+                            },
+                            false => CallbackAccount {
+                                pubkey: crate::ID,
+                                is_writable: false,
+                            },
+                        },
+                        match ctx.accounts.position_acc.referrer.filter(|_| referral_held) {
+                            Some(referrer) => CallbackAccount {
+                                pubkey: referrer,
+                                is_writable: true,
+                            },
+                            None => CallbackAccount {
+                                pubkey: crate::ID,
+                                is_writable: false,
+                            },
+                        },
+                    ],
+                    indexing::callback_accounts(),
+                ]
+                .concat(),
             )?],
             1,
             0,
         )?;
 
+        if let Some(subscription) = ctx.accounts.subscription.as_ref().filter(|_| keeper_check) {
+            let fee = subscription.pending_fee;
+            let balance = Subscription::available(subscription)?;
+            if balance < fee {
+                emit!(SubscriptionDepleted {
+                    owner: subscription.owner,
+                    position_id: ctx.accounts.position_acc.position_id,
+                    balance,
+                    fee_per_check: fee,
+                    timestamp: Clock::get()?.unix_timestamp,
+                });
+            }
+        }
+
         Ok(())
     }
 
@@ -152,137 +747,9674 @@ pub mod sentinel {
         ctx: Context<CheckPositionHealthCallback>,
         output: SignedComputationOutputs<CheckPositionHealthOutput>,
     ) -> Result<()> {
+        introspection::verify_callback(
+            &ctx.accounts.instructions_sysvar,
+            ctx.accounts.computation_account.key,
+            &ctx.accounts.mxe_account,
+            ctx.accounts.position_acc.pending_computation,
+        )?;
         let o = match output.verify_output(
             &ctx.accounts.cluster_account,
             &ctx.accounts.computation_account,
         ) {
             Ok(CheckPositionHealthOutput { field_0 }) => field_0,
-            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+            Err(_) => {
+                log_error!("computation_aborted", circuit = "check_position_health");
+                let now = Clock::get()?.unix_timestamp;
+                emit!(ctx
+                    .accounts
+                    .position_acc
+                    .fail_computation(ComputationKind::CheckHealth, now));
+                if let Some(bounty) = &mut ctx.accounts.check_bounty {
+                    bounty.pending_payee = None;
+                }
+                if let Some(subscription) = &mut ctx.accounts.subscription {
+                    subscription.release_fee();
+                }
+                return Ok(());
+            }
         };
 
+        let before = invariants::PositionSnapshot::of(&ctx.accounts.position_acc);
         ctx.accounts.position_acc.risk_state = o.ciphertexts;
+        ctx.accounts.position_acc.risk_state_version =
+            ctx.accounts.position_acc.pending_circuit_version;
         ctx.accounts.position_acc.nonce = o.nonce;
-        ctx.accounts.position_acc.last_check = Clock::get()?.unix_timestamp;
+        ctx.accounts
+            .position_acc
+            .record_check(Clock::get()?.unix_timestamp);
+        ctx.accounts.position_acc.pending_computation = None;
+        invariants::check_position(&before, &ctx.accounts.position_acc)?;
 
-        emit!(HealthCheckCompleted {
-            owner: ctx.accounts.position_acc.owner,
-            position_id: ctx.accounts.position_acc.position_id,
-            timestamp: Clock::get()?.unix_timestamp,
-        });
+        if let Some(history) = &ctx.accounts.risk_history {
+            history
+                .load_mut()?
+                .push(o.ciphertexts, o.nonce, Clock::get()?.unix_timestamp);
+        }
+
+        if let Some(bounty) = &mut ctx.accounts.check_bounty {
+            let payee = bounty.pending_payee.take();
+            let payee_info = ctx
+                .accounts
+                .keeper
+                .as_ref()
+                .filter(|info| Some(info.key()) == payee);
+            if let (Some(info), Some(lamports)) = (payee_info, CheckBounty::payable(bounty)?) {
+                bounty.sub_lamports(lamports)?;
+                info.add_lamports(lamports)?;
+                bounty.bounties_paid = bounty.bounties_paid.saturating_add(lamports);
+                emit!(CheckBountyPaid {
+                    owner: ctx.accounts.position_acc.owner,
+                    position_id: ctx.accounts.position_acc.position_id,
+                    keeper: info.key(),
+                    lamports,
+                    balance: Subscription::balance(&bounty.to_account_info())?,
+                    timestamp: Clock::get()?.unix_timestamp,
+                });
+            }
+        }
+        if let Some(subscription) = &mut ctx.accounts.subscription {
+            let paid = Subscription::pay_fee(
+                subscription,
+                ctx.accounts.keeper.as_ref(),
+                ctx.accounts.referrer.as_ref(),
+                &ctx.accounts.position_acc,
+            )?;
+            if let Some(event) = paid {
+                emit!(event);
+            }
+        }
+
+        emit_indexed!(
+            ctx,
+            HealthCheckCompleted {
+                owner: ctx.accounts.position_acc.owner,
+                position_id: ctx.accounts.position_acc.position_id,
+                timestamp: Clock::get()?.unix_timestamp,
+            }
+        );
 
         Ok(())
     }
 
-    // ─── Reveal Risk ───
+    // ─── Batch Health Check ───
 
-    /// Reveals whether the position is at risk. Only the position owner can call this.
-    pub fn reveal_risk(
-        ctx: Context<RevealRisk>,
+    /// Health checks for up to `MAX_BATCH_POSITIONS` of the payer's positions
+    /// in one computation. The position accounts are passed as writable
+    /// remaining accounts in the order of `positions`, and each is graded
+    /// like `check_health` against its own alert config. Every position in
+    /// the batch must be idle, and the callback updates all of them or none.
+    ///
+    /// Only owners batch: keepers check one position at a time so each check
+    /// is paid from that position's subscription.
+    pub fn check_health_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CheckHealthBatch<'info>>,
         computation_offset: u64,
-        position_id: u32,
+        encryption_pubkey: [u8; 32],
+        positions: Vec<EncryptedPositionInput>,
     ) -> Result<()> {
+        let circuit_version = GlobalConfig::require_active(&ctx.accounts.global_config)?;
         require!(
-            ctx.accounts.payer.key() == ctx.accounts.position_acc.owner,
-            ErrorCode::InvalidAuthority
+            !positions.is_empty()
+                && positions.len() <= MAX_BATCH_POSITIONS
+                && ctx.remaining_accounts.len() == positions.len(),
+            ErrorCode::InvalidBatch
         );
 
-        msg!("Revealing risk status for position {}", position_id);
+        let now = Clock::get()?.unix_timestamp;
+        let mut accounts = Vec::with_capacity(positions.len());
+        for (i, info) in ctx.remaining_accounts.iter().enumerate() {
+            require!(
+                info.is_writable
+                    && ctx.remaining_accounts[..i]
+                        .iter()
+                        .all(|a| a.key != info.key),
+                ErrorCode::InvalidBatch
+            );
+            let mut position = Account::<PositionAccount>::try_from(info)?;
+            require_keys_eq!(
+                position.owner,
+                ctx.accounts.payer.key(),
+                ErrorCode::InvalidAuthority
+            );
+            require!(position.is_active, ErrorCode::PositionInactive);
+            position.require_check_interval(now)?;
+            position.begin_computation(computation_offset, now, circuit_version)?;
+            accounts.push(position);
+        }
 
-        let args = ArgBuilder::new()
-            .plaintext_u128(ctx.accounts.position_acc.nonce)
-            .account(
-                ctx.accounts.position_acc.key(),
-                8 + 1,
-                32 * 2,
-            )
-            .build();
+        log_debug!(
+            "check_health_batch",
+            positions = accounts.len(),
+            computation_offset = computation_offset
+        );
+
+        // Unused slots repeat the first position; the callback ignores them.
+        let mut args = ArgBuilder::new();
+        for slot in 0..MAX_BATCH_POSITIONS {
+            let i = if slot < accounts.len() { slot } else { 0 };
+            let (input, position) = (&positions[i], &accounts[i]);
+            args = args
+                .x25519_pubkey(encryption_pubkey)
+                .plaintext_u128(input.nonce)
+                .encrypted_u64(input.ciphertexts[0])
+                .encrypted_u64(input.ciphertexts[1])
+                .encrypted_u64(input.ciphertexts[2])
+                .plaintext_u128(position.nonce)
+                .account(position.key(), 8 + 1, 32 * 3)
+                .plaintext_u128(position.alert_nonce)
+                .account(position.key(), 8 + 1 + 32 * 3, 32 * 3)
+                .plaintext_u8(position.alert_config_set as u8)
+                .plaintext_u64(position.risk_model.critical_buffer_bps)
+                .plaintext_u64(position.risk_model.warning_buffer_bps)
+                .plaintext_u64(position.risk_model.min_value_cents);
+        }
 
         ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
 
+        // The positions follow the callback's named accounts
+        let mut callback_accounts = indexing::callback_accounts();
+        callback_accounts.extend(accounts.iter().map(|position| CallbackAccount {
+            pubkey: position.key(),
+            is_writable: true,
+        }));
         queue_computation(
             ctx.accounts,
             computation_offset,
-            args,
-            vec![RevealRiskCallback::callback_ix(
+            args.build(),
+            vec![CheckPositionHealthBatchCallback::callback_ix(
                 computation_offset,
                 &ctx.accounts.mxe_account,
-                &[],
+                &callback_accounts,
             )?],
             1,
             0,
         )?;
 
+        for position in &accounts {
+            position.exit(&crate::ID)?;
+        }
         Ok(())
     }
 
-    #[arcium_callback(encrypted_ix = "reveal_risk")]
-    pub fn reveal_risk_callback(
-        ctx: Context<RevealRiskCallback>,
-        output: SignedComputationOutputs<RevealRiskOutput>,
+    /// Writes every result of the batch back, in the order the positions
+    /// were queued. The positions arrive as remaining accounts.
+    #[arcium_callback(encrypted_ix = "check_position_health_batch")]
+    pub fn check_position_health_batch_callback(
+        ctx: Context<CheckPositionHealthBatchCallback>,
+        output: SignedComputationOutputs<CheckPositionHealthBatchOutput>,
     ) -> Result<()> {
+        let infos = ctx.remaining_accounts;
+        require!(
+            !infos.is_empty() && infos.len() <= MAX_BATCH_POSITIONS,
+            ErrorCode::InvalidBatch
+        );
+        let mut positions = Vec::with_capacity(infos.len());
+        for info in infos {
+            require_keys_eq!(*info.owner, crate::ID, ErrorCode::InvalidBatch);
+            positions.push(PositionAccount::try_deserialize(
+                &mut &info.try_borrow_data()?[..],
+            )?);
+        }
+        let pending = positions[0].pending_computation;
+        introspection::verify_callback(
+            &ctx.accounts.instructions_sysvar,
+            ctx.accounts.computation_account.key,
+            &ctx.accounts.mxe_account,
+            pending,
+        )?;
+        // All or nothing: a position force-cleared since is not overwritten
+        require!(
+            positions.iter().all(|p| p.pending_computation == pending),
+            ErrorCode::StaleCallback
+        );
         let o = match output.verify_output(
             &ctx.accounts.cluster_account,
             &ctx.accounts.computation_account,
         ) {
-            Ok(RevealRiskOutput { field_0 }) => field_0,
-            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+            Ok(CheckPositionHealthBatchOutput { field_0 }) => field_0,
+            Err(_) => {
+                log_error!(
+                    "computation_aborted",
+                    circuit = "check_position_health_batch"
+                );
+                let now = Clock::get()?.unix_timestamp;
+                for (info, position) in infos.iter().zip(&mut positions) {
+                    emit!(position.fail_computation(ComputationKind::CheckHealthBatch, now));
+                    position.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+                }
+                return Ok(());
+            }
         };
 
-        emit!(RiskRevealed {
-            is_at_risk: o,
-            timestamp: Clock::get()?.unix_timestamp,
-        });
+        let now = Clock::get()?.unix_timestamp;
+        let results = [o.field_0, o.field_1, o.field_2, o.field_3];
+        for ((info, position), result) in infos.iter().zip(&mut positions).zip(results) {
+            let before = invariants::PositionSnapshot::of(position);
+            position.risk_state = result.ciphertexts;
+            position.risk_state_version = position.pending_circuit_version;
+            position.nonce = result.nonce;
+            position.record_check(now);
+            position.pending_computation = None;
+            invariants::check_position(&before, position)?;
+            position.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
 
-        if o {
-            emit!(ActionRequired {
-                action_type: "emergency_withdraw".to_string(),
-                timestamp: Clock::get()?.unix_timestamp,
-            });
+            emit_indexed!(
+                ctx,
+                HealthCheckCompleted {
+                    owner: position.owner,
+                    position_id: position.position_id,
+                    timestamp: now,
+                }
+            );
         }
 
         Ok(())
     }
-}
 
-// ─── Account Structs ───
+    // ─── Alert Config ───
 
-#[queue_computation_accounts("init_risk_state", payer)]
-#[derive(Accounts)]
-#[instruction(computation_offset: u64, position_id: u32)]
-pub struct RegisterPosition<'info> {
-    #[account(mut)]
-    pub payer: Signer<'info>,
-    #[account(
-        init_if_needed,
-        space = 9,
-        payer = payer,
-        seeds = [&SIGN_PDA_SEED],
-        bump,
-        address = derive_sign_pda!(),
-    )]
-    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
-    #[account(address = derive_mxe_pda!())]
-    pub mxe_account: Account<'info, MXEAccount>,
-    #[account(
-        mut,
-        address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet)
-    )]
-    /// CHECK: mempool_account
-    pub mempool_account: UncheckedAccount<'info>,
-    #[account(
-        mut,
-        address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet)
-    )]
-    /// CHECK: executing_pool
-    pub executing_pool: UncheckedAccount<'info>,
-    #[account(
-        mut,
-        address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet)
-    )]
-    /// CHECK: computation_account
-    pub computation_account: UncheckedAccount<'info>,
-    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_INIT_RISK_STATE))]
-    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    /// Stores the owner's encrypted alert thresholds
+    /// (`[critical_buffer_bps, warning_buffer_bps, min_value_cents]`) on the
+    /// position. `check_health` uses them in place of the default 5% / 10% /
+    /// $1 once the callback lands.
+    pub fn set_alert_config(
+        ctx: Context<SetAlertConfig>,
+        computation_offset: u64,
+        position_id: u32,
+        encrypted_config: [[u8; 32]; 3],
+        encryption_pubkey: [u8; 32],
+        encryption_nonce: u128,
+        nonce: u128,
+    ) -> Result<()> {
+        let circuit_version = GlobalConfig::require_active(&ctx.accounts.global_config)?;
+        log_info!("set_alert_config", position_id = position_id);
+
+        let args = ArgBuilder::new()
+            .plaintext_u128(nonce)
+            .x25519_pubkey(encryption_pubkey)
+            .plaintext_u128(encryption_nonce)
+            .encrypted_u64(encrypted_config[0])
+            .encrypted_u64(encrypted_config[1])
+            .encrypted_u64(encrypted_config[2])
+            .build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        ctx.accounts.position_acc.begin_computation(
+            computation_offset,
+            Clock::get()?.unix_timestamp,
+            circuit_version,
+        )?;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![SetAlertConfigCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[CallbackAccount {
+                    pubkey: ctx.accounts.position_acc.key(),
+                    is_writable: true,
+                }],
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "set_alert_config")]
+    pub fn set_alert_config_callback(
+        ctx: Context<SetAlertConfigCallback>,
+        output: SignedComputationOutputs<SetAlertConfigOutput>,
+    ) -> Result<()> {
+        introspection::verify_callback(
+            &ctx.accounts.instructions_sysvar,
+            ctx.accounts.computation_account.key,
+            &ctx.accounts.mxe_account,
+            ctx.accounts.position_acc.pending_computation,
+        )?;
+        let o = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(SetAlertConfigOutput { field_0 }) => field_0,
+            Err(_) => {
+                log_error!("computation_aborted", circuit = "set_alert_config");
+                let now = Clock::get()?.unix_timestamp;
+                emit!(ctx
+                    .accounts
+                    .position_acc
+                    .fail_computation(ComputationKind::SetAlertConfig, now));
+                return Ok(());
+            }
+        };
+
+        let before = invariants::PositionSnapshot::of(&ctx.accounts.position_acc);
+        ctx.accounts.position_acc.alert_config = o.ciphertexts;
+        ctx.accounts.position_acc.alert_nonce = o.nonce;
+        ctx.accounts.position_acc.alert_config_set = true;
+        ctx.accounts.position_acc.pending_computation = None;
+        invariants::check_position(&before, &ctx.accounts.position_acc)?;
+
+        emit!(AlertConfigUpdated {
+            owner: ctx.accounts.position_acc.owner,
+            position_id: ctx.accounts.position_acc.position_id,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Sets the lowest revealed severity, 1 (low) to 3 (critical), that
+    /// `reveal_risk` answers with `ActionRequired`. Lower severities are
+    /// still revealed, just without the event.
+    pub fn configure_alerts(
+        ctx: Context<ConfigureAlerts>,
+        position_id: u32,
+        min_action_severity: u8,
+    ) -> Result<()> {
+        require!(
+            (1..=3).contains(&min_action_severity),
+            ErrorCode::InvalidActionSeverity
+        );
+        ctx.accounts.position_acc.min_action_severity = min_action_severity;
+        log_info!(
+            "configure_alerts",
+            position_id = position_id,
+            min_action_severity = min_action_severity
+        );
+        Ok(())
+    }
+
+    /// Overrides how old, in seconds since its last check, the position's risk
+    /// state may be for `reveal_risk`. `None` follows the global default.
+    pub fn set_max_staleness(
+        ctx: Context<SetMaxStaleness>,
+        position_id: u32,
+        max_staleness_secs: Option<i64>,
+    ) -> Result<()> {
+        require!(
+            max_staleness_secs.is_none_or(|secs| secs > 0),
+            ErrorCode::InvalidStaleness
+        );
+        ctx.accounts.position_acc.max_staleness_secs = max_staleness_secs;
+        log_info!("set_max_staleness", position_id = position_id);
+        Ok(())
+    }
+
+    /// Sets the least time between two health checks of the position, which
+    /// caps what a keeper can charge its subscription. 0 removes the limit.
+    pub fn set_min_check_interval(
+        ctx: Context<SetMinCheckInterval>,
+        position_id: u32,
+        min_check_interval_secs: u32,
+    ) -> Result<()> {
+        ctx.accounts.position_acc.min_check_interval_secs = min_check_interval_secs;
+        log_info!(
+            "set_min_check_interval",
+            position_id = position_id,
+            min_check_interval_secs = min_check_interval_secs
+        );
+        Ok(())
+    }
+
+    // ─── Oracle Health Check ───
+
+    /// Sets the Pyth `PriceUpdateV2` account `check_health_with_oracle` prices
+    /// the position's collateral with. `None` disables oracle-backed checks.
+    pub fn set_price_feed(
+        ctx: Context<SetPriceFeed>,
+        position_id: u32,
+        price_feed: Option<Pubkey>,
+    ) -> Result<()> {
+        ctx.accounts.position_acc.price_feed = price_feed;
+        log_info!("set_price_feed", position_id = position_id);
+        Ok(())
+    }
+
+    /// Flags `price_feed` as deprecated once it has stopped publishing; see
+    /// `oracle::is_deprecated`. Anyone may flag a feed, typically a keeper
+    /// whose checks started failing on it, since the program checks the
+    /// feed itself.
+    pub fn flag_price_feed(ctx: Context<FlagPriceFeed>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let price_feed = ctx.accounts.price_feed.key();
+        require!(
+            oracle::is_deprecated(&ctx.accounts.price_feed.try_borrow_data()?, now),
+            ErrorCode::PriceFeedNotDeprecated
+        );
+        let migration = &mut ctx.accounts.feed_migration;
+        migration.bump = ctx.bumps.feed_migration;
+        migration.old_feed = price_feed;
+        migration.flagged_by = ctx.accounts.flagger.key();
+        migration.flagged_at = now;
+
+        log_info!("flag_price_feed", price_feed = price_feed);
+        emit!(PriceFeedFlagged {
+            price_feed,
+            flagged_by: migration.flagged_by,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Names `replacement` as the successor of a flagged feed, which must be
+    /// publishing. Positions on the old feed move over with
+    /// `repoint_price_feeds`. Only the program's upgrade authority may call
+    /// this.
+    pub fn migrate_price_feed(
+        ctx: Context<MigratePriceFeed>,
+        old_feed: Pubkey,
+        replacement: Pubkey,
+    ) -> Result<()> {
+        require_keys_neq!(
+            old_feed,
+            replacement,
+            ErrorCode::InvalidPriceFeedReplacement
+        );
+        let now = Clock::get()?.unix_timestamp;
+        oracle::read_price(&ctx.accounts.replacement_feed, now)?;
+
+        let migration = &mut ctx.accounts.feed_migration;
+        ctx.accounts.admin_audit_log.load_mut()?.record(
+            ctx.accounts.authority.key(),
+            AdminField::PriceFeedReplacement,
+            &(old_feed, migration.replacement).try_to_vec()?,
+            &(old_feed, Some(replacement)).try_to_vec()?,
+            Clock::get()?.slot,
+        );
+        migration.replacement = Some(replacement);
+        migration.migrated_at = now;
+
+        log_info!(
+            "migrate_price_feed",
+            old_feed = old_feed,
+            replacement = replacement
+        );
+        emit!(PriceFeedMigrated {
+            old_feed,
+            new_feed: replacement,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Crank: re-points the positions passed as writable remaining accounts
+    /// from a migrated feed to its replacement, one `PriceFeedRepointed`
+    /// each. Anyone may call it; every position must be on the old feed.
+    pub fn repoint_price_feeds<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RepointPriceFeeds<'info>>,
+    ) -> Result<()> {
+        let migration = &mut ctx.accounts.feed_migration;
+        let new_feed = migration
+            .replacement
+            .ok_or(ErrorCode::PriceFeedNotMigrated)?;
+        require!(!ctx.remaining_accounts.is_empty(), ErrorCode::InvalidBatch);
+        let now = Clock::get()?.unix_timestamp;
+
+        for info in ctx.remaining_accounts {
+            require!(info.is_writable, ErrorCode::InvalidBatch);
+            let mut position = Account::<PositionAccount>::try_from(info)?;
+            require!(
+                position.price_feed == Some(migration.old_feed),
+                ErrorCode::PriceFeedMismatch
+            );
+            position.price_feed = Some(new_feed);
+            position.exit(&crate::ID)?;
+
+            emit!(PriceFeedRepointed {
+                owner: position.owner,
+                position_id: position.position_id,
+                old_feed: migration.old_feed,
+                new_feed,
+                timestamp: now,
+            });
+        }
+        migration.repointed += ctx.remaining_accounts.len() as u32;
+
+        log_info!(
+            "repoint_price_feeds",
+            old_feed = migration.old_feed,
+            positions = ctx.remaining_accounts.len()
+        );
+        Ok(())
+    }
+
+    /// Like `check_health`, but the collateral is valued inside MPC at the
+    /// on-chain Pyth price rather than at a client-supplied ratio, so the
+    /// circuit cannot be fed a stale one. The encrypted position holds
+    /// collateral amount, debt value and liquidation threshold. Only the
+    /// owner submits it: keepers are paid per check through `check_health`.
+    pub fn check_health_with_oracle(
+        ctx: Context<CheckHealthWithOracle>,
+        computation_offset: u64,
+        _position_id: u32,
+        encrypted_position: [[u8; 32]; 3], // 3 fields: collateral_amount, debt_value, threshold
+        encryption_pubkey: [u8; 32],
+        encryption_nonce: u128,
+    ) -> Result<()> {
+        let circuit_version = GlobalConfig::require_active(&ctx.accounts.global_config)?;
+        require!(
+            ctx.accounts.position_acc.is_active,
+            ErrorCode::PositionInactive
+        );
+        require_keys_eq!(
+            ctx.accounts.payer.key(),
+            ctx.accounts.position_acc.owner,
+            ErrorCode::UnauthorizedKeeper
+        );
+        ctx.accounts
+            .position_acc
+            .require_check_interval(Clock::get()?.unix_timestamp)?;
+        let price_feed = ctx
+            .accounts
+            .position_acc
+            .price_feed
+            .ok_or(ErrorCode::PriceFeedNotSet)?;
+        require_keys_eq!(
+            ctx.accounts.price_update.key(),
+            price_feed,
+            ErrorCode::PriceFeedMismatch
+        );
+        let price = oracle::read_price(&ctx.accounts.price_update, Clock::get()?.unix_timestamp)?;
+        log_debug!(
+            "check_health_with_oracle",
+            position_id = ctx.accounts.position_acc.position_id,
+            computation_offset = computation_offset,
+            price = price.price,
+            conf = price.conf,
+            publish_time = price.publish_time
+        );
+
+        let args = ArgBuilder::new()
+            .x25519_pubkey(encryption_pubkey)
+            .plaintext_u128(encryption_nonce)
+            .encrypted_u64(encrypted_position[0])
+            .encrypted_u64(encrypted_position[1])
+            .encrypted_u64(encrypted_position[2])
+            .plaintext_u128(ctx.accounts.position_acc.nonce)
+            .account(ctx.accounts.position_acc.key(), 8 + 1, 32 * 3)
+            .plaintext_u64(price.price)
+            .plaintext_u64(price.conf)
+            .build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        ctx.accounts.position_acc.begin_computation(
+            computation_offset,
+            Clock::get()?.unix_timestamp,
+            circuit_version,
+        )?;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![CheckPositionHealthWithPriceCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[
+                    vec![CallbackAccount {
+                        pubkey: ctx.accounts.position_acc.key(),
+                        is_writable: true,
+                    }],
+                    indexing::callback_accounts(),
+                ]
+                .concat(),
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "check_position_health_with_price")]
+    pub fn check_position_health_with_price_callback(
+        ctx: Context<CheckPositionHealthWithPriceCallback>,
+        output: SignedComputationOutputs<CheckPositionHealthWithPriceOutput>,
+    ) -> Result<()> {
+        introspection::verify_callback(
+            &ctx.accounts.instructions_sysvar,
+            ctx.accounts.computation_account.key,
+            &ctx.accounts.mxe_account,
+            ctx.accounts.position_acc.pending_computation,
+        )?;
+        let o = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(CheckPositionHealthWithPriceOutput { field_0 }) => field_0,
+            Err(_) => {
+                log_error!(
+                    "computation_aborted",
+                    circuit = "check_position_health_with_price"
+                );
+                let now = Clock::get()?.unix_timestamp;
+                emit!(ctx
+                    .accounts
+                    .position_acc
+                    .fail_computation(ComputationKind::CheckHealthWithPrice, now));
+                return Ok(());
+            }
+        };
+
+        let before = invariants::PositionSnapshot::of(&ctx.accounts.position_acc);
+        ctx.accounts.position_acc.risk_state = o.ciphertexts;
+        ctx.accounts.position_acc.risk_state_version =
+            ctx.accounts.position_acc.pending_circuit_version;
+        ctx.accounts.position_acc.nonce = o.nonce;
+        ctx.accounts
+            .position_acc
+            .record_check(Clock::get()?.unix_timestamp);
+        ctx.accounts.position_acc.pending_computation = None;
+        invariants::check_position(&before, &ctx.accounts.position_acc)?;
+
+        emit_indexed!(
+            ctx,
+            HealthCheckCompleted {
+                owner: ctx.accounts.position_acc.owner,
+                position_id: ctx.accounts.position_acc.position_id,
+                timestamp: Clock::get()?.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    // ─── Perp Health Check ───
+
+    /// Health check of a perpetual futures position, graded by how close its
+    /// equity is to the maintenance margin at the mark price of the
+    /// position's Pyth feed (see `set_price_feed`). The encrypted position
+    /// holds side, entry price, size, leverage, maintenance margin and
+    /// funding owed; the result replaces the risk state like any check.
+    /// Only the owner submits it: keepers are paid per check through
+    /// `check_health`.
+    pub fn check_perp_health(
+        ctx: Context<CheckPerpHealth>,
+        computation_offset: u64,
+        _position_id: u32,
+        encrypted_position: [[u8; 32]; 6], // is_long, entry, size, leverage, mm, funding
+        encryption_pubkey: [u8; 32],
+        encryption_nonce: u128,
+    ) -> Result<()> {
+        let circuit_version = GlobalConfig::require_active(&ctx.accounts.global_config)?;
+        require!(
+            ctx.accounts.position_acc.is_active,
+            ErrorCode::PositionInactive
+        );
+        require_keys_eq!(
+            ctx.accounts.payer.key(),
+            ctx.accounts.position_acc.owner,
+            ErrorCode::UnauthorizedKeeper
+        );
+        ctx.accounts
+            .position_acc
+            .require_check_interval(Clock::get()?.unix_timestamp)?;
+        let price_feed = ctx
+            .accounts
+            .position_acc
+            .price_feed
+            .ok_or(ErrorCode::PriceFeedNotSet)?;
+        require_keys_eq!(
+            ctx.accounts.price_update.key(),
+            price_feed,
+            ErrorCode::PriceFeedMismatch
+        );
+        let price = oracle::read_price(&ctx.accounts.price_update, Clock::get()?.unix_timestamp)?;
+        log_debug!(
+            "check_perp_health",
+            position_id = ctx.accounts.position_acc.position_id,
+            computation_offset = computation_offset,
+            price = price.price,
+            conf = price.conf,
+            publish_time = price.publish_time
+        );
+
+        let mut args = ArgBuilder::new()
+            .x25519_pubkey(encryption_pubkey)
+            .plaintext_u128(encryption_nonce);
+        for ciphertext in encrypted_position {
+            args = args.encrypted_u64(ciphertext);
+        }
+        let args = args
+            .plaintext_u128(ctx.accounts.position_acc.nonce)
+            .account(ctx.accounts.position_acc.key(), 8 + 1, 32 * 3)
+            .plaintext_u64(price.price)
+            .plaintext_u64(price.conf)
+            .build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        ctx.accounts.position_acc.begin_computation(
+            computation_offset,
+            Clock::get()?.unix_timestamp,
+            circuit_version,
+        )?;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![CheckPerpHealthCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[
+                    vec![CallbackAccount {
+                        pubkey: ctx.accounts.position_acc.key(),
+                        is_writable: true,
+                    }],
+                    indexing::callback_accounts(),
+                ]
+                .concat(),
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "check_perp_health")]
+    pub fn check_perp_health_callback(
+        ctx: Context<CheckPerpHealthCallback>,
+        output: SignedComputationOutputs<CheckPerpHealthOutput>,
+    ) -> Result<()> {
+        introspection::verify_callback(
+            &ctx.accounts.instructions_sysvar,
+            ctx.accounts.computation_account.key,
+            &ctx.accounts.mxe_account,
+            ctx.accounts.position_acc.pending_computation,
+        )?;
+        let o = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(CheckPerpHealthOutput { field_0 }) => field_0,
+            Err(_) => {
+                log_error!("computation_aborted", circuit = "check_perp_health");
+                let now = Clock::get()?.unix_timestamp;
+                emit!(ctx
+                    .accounts
+                    .position_acc
+                    .fail_computation(ComputationKind::CheckPerpHealth, now));
+                return Ok(());
+            }
+        };
+
+        let before = invariants::PositionSnapshot::of(&ctx.accounts.position_acc);
+        ctx.accounts.position_acc.risk_state = o.ciphertexts;
+        ctx.accounts.position_acc.risk_state_version =
+            ctx.accounts.position_acc.pending_circuit_version;
+        ctx.accounts.position_acc.nonce = o.nonce;
+        ctx.accounts
+            .position_acc
+            .record_check(Clock::get()?.unix_timestamp);
+        ctx.accounts.position_acc.pending_computation = None;
+        invariants::check_position(&before, &ctx.accounts.position_acc)?;
+
+        emit_indexed!(
+            ctx,
+            HealthCheckCompleted {
+                owner: ctx.accounts.position_acc.owner,
+                position_id: ctx.accounts.position_acc.position_id,
+                timestamp: Clock::get()?.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    // ─── Composite Risk Score ───
+
+    /// Sets the weights and severity bands `compute_risk_score` grades by.
+    /// Weights sum to 100; bands are the lowest scores of severity 1, 2 and
+    /// 3, strictly ascending from at least 1. Takes effect from the next
+    /// score, with no circuit redeploy.
+    pub fn set_risk_model_config(
+        ctx: Context<SetRiskModelConfig>,
+        weights: RiskWeights,
+        severity_bands: [u8; 3],
+    ) -> Result<()> {
+        require!(weights.is_valid(), ErrorCode::InvalidRiskWeights);
+        require!(
+            0 < severity_bands[0]
+                && severity_bands[0] < severity_bands[1]
+                && severity_bands[1] < severity_bands[2]
+                && severity_bands[2] <= 100,
+            ErrorCode::InvalidSeverityBands
+        );
+        let config = &mut ctx.accounts.risk_model_config;
+        ctx.accounts.admin_audit_log.load_mut()?.record(
+            ctx.accounts.admin.key(),
+            AdminField::RiskModelConfig,
+            &(config.weights, config.severity_bands).try_to_vec()?,
+            &(weights, severity_bands).try_to_vec()?,
+            Clock::get()?.slot,
+        );
+        config.bump = ctx.bumps.risk_model_config;
+        config.weights = weights;
+        config.severity_bands = severity_bands;
+        config.updated_at = Clock::get()?.unix_timestamp;
+
+        log_info!("set_risk_model_config");
+        emit!(RiskModelConfigUpdated {
+            weights,
+            severity_bands,
+            timestamp: config.updated_at,
+        });
+
+        Ok(())
+    }
+
+    /// Health check graded by a composite 0-100 score of liquidation
+    /// proximity, the price move since the last priced check, the position's
+    /// share of the owner's holdings and the protocol's TVL trend, weighted
+    /// by `RiskModelConfig`. The score's severity band replaces the risk
+    /// state like any check, and the score itself is kept encrypted in the
+    /// position's `RiskScoreAccount` under `nonce`. The encrypted position
+    /// holds collateral ratio, liquidation threshold and concentration.
+    /// `price_update` and `tvl_snapshot` are as for `check_health`, and so is
+    /// the fee a keeper is paid from the position's `Subscription`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn compute_risk_score(
+        ctx: Context<ComputeRiskScore>,
+        computation_offset: u64,
+        _position_id: u32,
+        encrypted_position: [[u8; 32]; 3], // collateral_ratio, threshold, concentration
+        encryption_pubkey: [u8; 32],
+        encryption_nonce: u128,
+        nonce: u128,
+    ) -> Result<()> {
+        let circuit_version = GlobalConfig::require_active(&ctx.accounts.global_config)?;
+        require!(
+            ctx.accounts.position_acc.is_active,
+            ErrorCode::PositionInactive
+        );
+        require!(
+            ctx.accounts.position_acc.may_submit(
+                &ctx.accounts.payer.key(),
+                Clock::get()?.slot,
+                None
+            )?,
+            ErrorCode::UnauthorizedKeeper
+        );
+        ctx.accounts
+            .position_acc
+            .require_check_interval(Clock::get()?.unix_timestamp)?;
+        let keeper_check = ctx.accounts.payer.key() != ctx.accounts.owner.key();
+        if keeper_check {
+            let referrer = ctx.accounts.position_acc.referrer;
+            match (
+                &ctx.accounts.monitoring_plan,
+                &mut ctx.accounts.subscription,
+            ) {
+                (Some(plan), Some(subscription)) => {
+                    Subscription::hold_fee(subscription, plan, ctx.accounts.payer.key(), referrer)?
+                }
+                _ => return err!(ErrorCode::SubscriptionRequired),
+            }
+        }
+        let price = match &ctx.accounts.price_update {
+            Some(price_update) => {
+                let price_feed = ctx
+                    .accounts
+                    .position_acc
+                    .price_feed
+                    .ok_or(ErrorCode::PriceFeedNotSet)?;
+                require_keys_eq!(price_update.key(), price_feed, ErrorCode::PriceFeedMismatch);
+                oracle::read_price(price_update, Clock::get()?.unix_timestamp)?.price
+            }
+            None => 0,
+        };
+        let (tvl, previous_tvl) = match &ctx.accounts.tvl_snapshot {
+            Some(snapshot) => {
+                let protocol = ctx
+                    .accounts
+                    .position_acc
+                    .tvl_protocol
+                    .ok_or(ErrorCode::TvlProtocolNotSet)?;
+                require_keys_eq!(
+                    snapshot.protocol_program,
+                    protocol,
+                    ErrorCode::TvlProtocolMismatch
+                );
+                (snapshot.tvl, snapshot.previous_tvl)
+            }
+            None => (0, 0),
+        };
+        log_debug!(
+            "compute_risk_score",
+            position_id = ctx.accounts.position_acc.position_id,
+            computation_offset = computation_offset,
+            price = price,
+            tvl = tvl,
+            previous_tvl = previous_tvl
+        );
+
+        let config = &ctx.accounts.risk_model_config;
+        let mut args = ArgBuilder::new()
+            .plaintext_u128(nonce)
+            .x25519_pubkey(encryption_pubkey)
+            .plaintext_u128(encryption_nonce);
+        for ciphertext in encrypted_position {
+            args = args.encrypted_u64(ciphertext);
+        }
+        args = args
+            .plaintext_u128(ctx.accounts.position_acc.nonce)
+            .account(ctx.accounts.position_acc.key(), 8 + 1, 32 * 3);
+        for weight in config.weights.as_array() {
+            args = args.plaintext_u64(weight as u64);
+        }
+        for band in config.severity_bands {
+            args = args.plaintext_u64(band as u64);
+        }
+        let args = args
+            .plaintext_u64(price)
+            .plaintext_u64(tvl)
+            .plaintext_u64(previous_tvl)
+            .build();
+
+        let risk_score = &mut ctx.accounts.risk_score;
+        risk_score.bump = ctx.bumps.risk_score;
+        risk_score.position = ctx.accounts.position_acc.key();
+        let referral_held = keeper_check
+            && ctx
+                .accounts
+                .subscription
+                .as_ref()
+                .is_some_and(|s| s.pending_referral_fee > 0);
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        ctx.accounts.position_acc.begin_computation(
+            computation_offset,
+            Clock::get()?.unix_timestamp,
+            circuit_version,
+        )?;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![ComputeRiskScoreCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[
+                    vec![
+                        CallbackAccount {
+                            pubkey: ctx.accounts.position_acc.key(),
+                            is_writable: true,
+                        },
+                        CallbackAccount {
+                            pubkey: ctx.accounts.risk_score.key(),
+                            is_writable: true,
+                        },
+                        // The program id stands in for an absent optional account
+                        match ctx.accounts.subscription.as_ref().filter(|_| keeper_check) {
+                            Some(subscription) => CallbackAccount {
+                                pubkey: subscription.key(),
+                                is_writable: true,
+                            },
+                            None => CallbackAccount {
+                                pubkey: crate::ID,
+                                is_writable: false,
+                            },
+                        },
+                        match keeper_check {
+                            true => CallbackAccount {
+                                pubkey: ctx.accounts.payer.key(),
+                                is_writable: true,
+                            },
+                            false => CallbackAccount {
+                                pubkey: crate::ID,
+                                is_writable: false,
+                            },
+                        },
+                        match ctx.accounts.position_acc.referrer.filter(|_| referral_held) {
+                            Some(referrer) => CallbackAccount {
+                                pubkey: referrer,
+                                is_writable: true,
+                            },
+                            None => CallbackAccount {
+                                pubkey: crate::ID,
+                                is_writable: false,
+                            },
+                        },
+                    ],
+                    indexing::callback_accounts(),
+                ]
+                .concat(),
+            )?],
+            1,
+            0,
+        )?;
+
+        if let Some(subscription) = ctx.accounts.subscription.as_ref().filter(|_| keeper_check) {
+            let fee = subscription.pending_fee;
+            let balance = Subscription::available(subscription)?;
+            if balance < fee {
+                emit!(SubscriptionDepleted {
+                    owner: subscription.owner,
+                    position_id: ctx.accounts.position_acc.position_id,
+                    balance,
+                    fee_per_check: fee,
+                    timestamp: Clock::get()?.unix_timestamp,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "compute_risk_score")]
+    pub fn compute_risk_score_callback(
+        ctx: Context<ComputeRiskScoreCallback>,
+        output: SignedComputationOutputs<ComputeRiskScoreOutput>,
+    ) -> Result<()> {
+        introspection::verify_callback(
+            &ctx.accounts.instructions_sysvar,
+            ctx.accounts.computation_account.key,
+            &ctx.accounts.mxe_account,
+            ctx.accounts.position_acc.pending_computation,
+        )?;
+        require_keys_eq!(
+            ctx.accounts.risk_score.position,
+            ctx.accounts.position_acc.key(),
+            ErrorCode::RiskScorePositionMismatch
+        );
+        let o = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(ComputeRiskScoreOutput { field_0 }) => field_0,
+            Err(_) => {
+                log_error!("computation_aborted", circuit = "compute_risk_score");
+                let now = Clock::get()?.unix_timestamp;
+                emit!(ctx
+                    .accounts
+                    .position_acc
+                    .fail_computation(ComputationKind::ComputeRiskScore, now));
+                if let Some(subscription) = &mut ctx.accounts.subscription {
+                    subscription.release_fee();
+                }
+                return Ok(());
+            }
+        };
+        let (state, score) = (o.field_0, o.field_1);
+        let now = Clock::get()?.unix_timestamp;
+
+        let before = invariants::PositionSnapshot::of(&ctx.accounts.position_acc);
+        ctx.accounts.position_acc.risk_state = state.ciphertexts;
+        ctx.accounts.position_acc.risk_state_version =
+            ctx.accounts.position_acc.pending_circuit_version;
+        ctx.accounts.position_acc.nonce = state.nonce;
+        ctx.accounts.position_acc.record_check(now);
+        ctx.accounts.position_acc.pending_computation = None;
+        invariants::check_position(&before, &ctx.accounts.position_acc)?;
+
+        let risk_score = &mut ctx.accounts.risk_score;
+        risk_score.score = score.ciphertexts[0];
+        risk_score.nonce = score.nonce;
+        risk_score.computed_at = now;
+
+        if let Some(subscription) = &mut ctx.accounts.subscription {
+            let paid = Subscription::pay_fee(
+                subscription,
+                ctx.accounts.keeper.as_ref(),
+                ctx.accounts.referrer.as_ref(),
+                &ctx.accounts.position_acc,
+            )?;
+            if let Some(event) = paid {
+                emit!(event);
+            }
+        }
+
+        emit_indexed!(
+            ctx,
+            RiskScoreComputed {
+                owner: ctx.accounts.position_acc.owner,
+                position_id: ctx.accounts.position_acc.position_id,
+                risk_score: ctx.accounts.risk_score.key(),
+                timestamp: now,
+            }
+        );
+        emit_indexed!(
+            ctx,
+            HealthCheckCompleted {
+                owner: ctx.accounts.position_acc.owner,
+                position_id: ctx.accounts.position_acc.position_id,
+                timestamp: now,
+            }
+        );
+
+        Ok(())
+    }
+
+    // ─── Cross-Chain Health Check ───
+
+    /// Pins the Wormhole emitter whose attested summaries
+    /// `check_health_cross_chain` accepts for this position. `None` disables
+    /// cross-chain checks.
+    pub fn set_cross_chain_emitter(
+        ctx: Context<SetCrossChainEmitter>,
+        position_id: u32,
+        emitter: Option<wormhole::Emitter>,
+    ) -> Result<()> {
+        ctx.accounts.position_acc.cross_chain_emitter = emitter;
+        log_info!("set_cross_chain_emitter", position_id = position_id);
+        Ok(())
+    }
+
+    /// Health check over collateral that partly lives on another chain. The
+    /// remote collateral and debt come from a posted Wormhole VAA and enter
+    /// the circuit as plaintext next to the encrypted Solana-side values
+    /// (collateral value, debt value, threshold). Only the owner submits
+    /// it: keepers are paid per check through `check_health`.
+    pub fn check_health_cross_chain(
+        ctx: Context<CheckHealthCrossChain>,
+        computation_offset: u64,
+        _position_id: u32,
+        encrypted_position: [[u8; 32]; 3], // 3 fields: collateral_value, debt_value, threshold
+        encryption_pubkey: [u8; 32],
+        encryption_nonce: u128,
+    ) -> Result<()> {
+        let circuit_version = GlobalConfig::require_active(&ctx.accounts.global_config)?;
+        require!(
+            ctx.accounts.position_acc.is_active,
+            ErrorCode::PositionInactive
+        );
+        require_keys_eq!(
+            ctx.accounts.payer.key(),
+            ctx.accounts.position_acc.owner,
+            ErrorCode::UnauthorizedKeeper
+        );
+        ctx.accounts
+            .position_acc
+            .require_check_interval(Clock::get()?.unix_timestamp)?;
+        let summary = wormhole::read_summary(
+            &ctx.accounts.posted_vaa,
+            &ctx.accounts.position_acc,
+            Clock::get()?.unix_timestamp,
+        )?;
+        log_debug!(
+            "check_health_cross_chain",
+            position_id = ctx.accounts.position_acc.position_id,
+            computation_offset = computation_offset,
+            observed_at = summary.observed_at
+        );
+
+        let args = ArgBuilder::new()
+            .x25519_pubkey(encryption_pubkey)
+            .plaintext_u128(encryption_nonce)
+            .encrypted_u64(encrypted_position[0])
+            .encrypted_u64(encrypted_position[1])
+            .encrypted_u64(encrypted_position[2])
+            .plaintext_u128(ctx.accounts.position_acc.nonce)
+            .account(ctx.accounts.position_acc.key(), 8 + 1, 32 * 3)
+            .plaintext_u64(summary.collateral_value)
+            .plaintext_u64(summary.debt_value)
+            .build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        ctx.accounts.position_acc.begin_computation(
+            computation_offset,
+            Clock::get()?.unix_timestamp,
+            circuit_version,
+        )?;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![CheckPositionHealthCrossChainCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[
+                    vec![CallbackAccount {
+                        pubkey: ctx.accounts.position_acc.key(),
+                        is_writable: true,
+                    }],
+                    indexing::callback_accounts(),
+                ]
+                .concat(),
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "check_position_health_cross_chain")]
+    pub fn check_position_health_cross_chain_callback(
+        ctx: Context<CheckPositionHealthCrossChainCallback>,
+        output: SignedComputationOutputs<CheckPositionHealthCrossChainOutput>,
+    ) -> Result<()> {
+        introspection::verify_callback(
+            &ctx.accounts.instructions_sysvar,
+            ctx.accounts.computation_account.key,
+            &ctx.accounts.mxe_account,
+            ctx.accounts.position_acc.pending_computation,
+        )?;
+        let o = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(CheckPositionHealthCrossChainOutput { field_0 }) => field_0,
+            Err(_) => {
+                log_error!(
+                    "computation_aborted",
+                    circuit = "check_position_health_cross_chain"
+                );
+                let now = Clock::get()?.unix_timestamp;
+                emit!(ctx
+                    .accounts
+                    .position_acc
+                    .fail_computation(ComputationKind::CheckHealthCrossChain, now));
+                return Ok(());
+            }
+        };
+
+        let before = invariants::PositionSnapshot::of(&ctx.accounts.position_acc);
+        ctx.accounts.position_acc.risk_state = o.ciphertexts;
+        ctx.accounts.position_acc.risk_state_version =
+            ctx.accounts.position_acc.pending_circuit_version;
+        ctx.accounts.position_acc.nonce = o.nonce;
+        ctx.accounts
+            .position_acc
+            .record_check(Clock::get()?.unix_timestamp);
+        ctx.accounts.position_acc.pending_computation = None;
+        invariants::check_position(&before, &ctx.accounts.position_acc)?;
+
+        emit_indexed!(
+            ctx,
+            HealthCheckCompleted {
+                owner: ctx.accounts.position_acc.owner,
+                position_id: ctx.accounts.position_acc.position_id,
+                timestamp: Clock::get()?.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    // ─── Rule-Based Health Check ───
+
+    /// Creates or replaces the owner's rule set, a parameter vector compiled
+    /// by `cargo xtask compile-rules` (layout in `rules`). Applies to every
+    /// position of the owner checked with `check_health_with_rules`.
+    pub fn set_rule_set(
+        ctx: Context<SetRuleSet>,
+        params: [u64; rules::RULE_PARAMS_LEN],
+    ) -> Result<()> {
+        require!(rules::is_valid(&params), ErrorCode::InvalidRuleSet);
+
+        let rule_set = &mut ctx.accounts.rule_set;
+        rule_set.bump = ctx.bumps.rule_set;
+        rule_set.owner = ctx.accounts.owner.key();
+        rule_set.params = params;
+
+        log_info!("set_rule_set", owner = rule_set.owner);
+        Ok(())
+    }
+
+    /// Like `check_health`, but the position is scored by the owner's rule
+    /// set rather than the fixed thresholds, so tuning the scoring is a
+    /// `set_rule_set` away instead of a circuit redeployment. Only the owner
+    /// submits it: keepers are paid per check through `check_health`.
+    pub fn check_health_with_rules(
+        ctx: Context<CheckHealthWithRules>,
+        computation_offset: u64,
+        _position_id: u32,
+        encrypted_position: [[u8; 32]; POSITION_FIELD_COUNT], // value, collateral_ratio, threshold
+        encryption_pubkey: [u8; 32],
+        encryption_nonce: u128,
+    ) -> Result<()> {
+        let circuit_version = GlobalConfig::require_active(&ctx.accounts.global_config)?;
+        require!(
+            ctx.accounts.position_acc.is_active,
+            ErrorCode::PositionInactive
+        );
+        require_keys_eq!(
+            ctx.accounts.payer.key(),
+            ctx.accounts.position_acc.owner,
+            ErrorCode::UnauthorizedKeeper
+        );
+        ctx.accounts
+            .position_acc
+            .require_check_interval(Clock::get()?.unix_timestamp)?;
+        log_debug!(
+            "check_health_with_rules",
+            position_id = ctx.accounts.position_acc.position_id,
+            computation_offset = computation_offset
+        );
+
+        let mut args = ArgBuilder::new()
+            .x25519_pubkey(encryption_pubkey)
+            .plaintext_u128(encryption_nonce)
+            .encrypted_u64(encrypted_position[0])
+            .encrypted_u64(encrypted_position[1])
+            .encrypted_u64(encrypted_position[2])
+            .plaintext_u128(ctx.accounts.position_acc.nonce)
+            .account(ctx.accounts.position_acc.key(), 8 + 1, 32 * 3);
+        for param in ctx.accounts.rule_set.params {
+            args = args.plaintext_u64(param);
+        }
+        let args = args.build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        ctx.accounts.position_acc.begin_computation(
+            computation_offset,
+            Clock::get()?.unix_timestamp,
+            circuit_version,
+        )?;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![CheckPositionHealthWithRulesCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[
+                    vec![CallbackAccount {
+                        pubkey: ctx.accounts.position_acc.key(),
+                        is_writable: true,
+                    }],
+                    indexing::callback_accounts(),
+                ]
+                .concat(),
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "check_position_health_with_rules")]
+    pub fn check_position_health_with_rules_callback(
+        ctx: Context<CheckPositionHealthWithRulesCallback>,
+        output: SignedComputationOutputs<CheckPositionHealthWithRulesOutput>,
+    ) -> Result<()> {
+        introspection::verify_callback(
+            &ctx.accounts.instructions_sysvar,
+            ctx.accounts.computation_account.key,
+            &ctx.accounts.mxe_account,
+            ctx.accounts.position_acc.pending_computation,
+        )?;
+        let o = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(CheckPositionHealthWithRulesOutput { field_0 }) => field_0,
+            Err(_) => {
+                log_error!(
+                    "computation_aborted",
+                    circuit = "check_position_health_with_rules"
+                );
+                let now = Clock::get()?.unix_timestamp;
+                emit!(ctx
+                    .accounts
+                    .position_acc
+                    .fail_computation(ComputationKind::CheckHealthWithRules, now));
+                return Ok(());
+            }
+        };
+
+        let before = invariants::PositionSnapshot::of(&ctx.accounts.position_acc);
+        ctx.accounts.position_acc.risk_state = o.ciphertexts;
+        ctx.accounts.position_acc.risk_state_version =
+            ctx.accounts.position_acc.pending_circuit_version;
+        ctx.accounts.position_acc.nonce = o.nonce;
+        ctx.accounts
+            .position_acc
+            .record_check(Clock::get()?.unix_timestamp);
+        ctx.accounts.position_acc.pending_computation = None;
+        invariants::check_position(&before, &ctx.accounts.position_acc)?;
+
+        emit_indexed!(
+            ctx,
+            HealthCheckCompleted {
+                owner: ctx.accounts.position_acc.owner,
+                position_id: ctx.accounts.position_acc.position_id,
+                timestamp: Clock::get()?.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    // ─── Protocol Health Check ───
+
+    /// Registers, or replaces, how obligations of `protocol_program` are
+    /// read. Only the program's upgrade authority may register adapters.
+    pub fn register_protocol_adapter(
+        ctx: Context<RegisterProtocolAdapter>,
+        protocol_program: Pubkey,
+        layout: protocols::ObligationLayout,
+    ) -> Result<()> {
+        require!(layout.is_valid(), ErrorCode::InvalidProtocolAdapter);
+
+        let adapter = &mut ctx.accounts.protocol_adapter;
+        ctx.accounts.admin_audit_log.load_mut()?.record(
+            ctx.accounts.authority.key(),
+            AdminField::ProtocolAdapter,
+            &(protocol_program, &adapter.layout).try_to_vec()?,
+            &(protocol_program, &layout).try_to_vec()?,
+            Clock::get()?.slot,
+        );
+        adapter.bump = ctx.bumps.protocol_adapter;
+        adapter.protocol_program = protocol_program;
+        adapter.layout = layout;
+
+        log_info!(
+            "register_protocol_adapter",
+            protocol_program = protocol_program
+        );
+        emit!(ProtocolAdapterRegistered {
+            protocol_program,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Replaces the maintenance windows of `protocol_program`. While one is
+    /// open its obligations may be mid-migration, so checks read from them
+    /// are deferred instead of queued. Only the program's upgrade authority
+    /// may set them.
+    pub fn set_protocol_blackouts(
+        ctx: Context<SetProtocolBlackouts>,
+        protocol_program: Pubkey,
+        windows: Vec<BlackoutWindow>,
+    ) -> Result<()> {
+        require!(
+            windows.len() <= MAX_BLACKOUT_WINDOWS
+                && windows.iter().all(|w| w.starts_at < w.ends_at),
+            ErrorCode::InvalidBlackoutWindow
+        );
+
+        let adapter = &mut ctx.accounts.protocol_adapter;
+        ctx.accounts.admin_audit_log.load_mut()?.record(
+            ctx.accounts.authority.key(),
+            AdminField::ProtocolBlackouts,
+            &(protocol_program, &adapter.blackouts).try_to_vec()?,
+            &(protocol_program, &windows).try_to_vec()?,
+            Clock::get()?.slot,
+        );
+        adapter.blackouts = windows;
+
+        log_info!(
+            "set_protocol_blackouts",
+            protocol_program = protocol_program,
+            windows = adapter.blackouts.len()
+        );
+        emit!(ProtocolBlackoutsSet {
+            protocol_program,
+            windows: adapter.blackouts.clone(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Like `check_health`, but the position is read from the owner's
+    /// obligation in a registered lending protocol instead of from
+    /// client-submitted ciphertexts, so the owner cannot misreport it. The
+    /// obligation is public, so its values go to the circuit in plaintext.
+    /// During one of the protocol's maintenance windows the check is
+    /// deferred: nothing is queued, and the position records until when.
+    /// Only the owner submits it: keepers are paid per check through
+    /// `check_health`.
+    pub fn check_health_from_protocol(
+        ctx: Context<CheckHealthFromProtocol>,
+        computation_offset: u64,
+        _position_id: u32,
+    ) -> Result<()> {
+        let circuit_version = GlobalConfig::require_active(&ctx.accounts.global_config)?;
+        require!(
+            ctx.accounts.position_acc.is_active,
+            ErrorCode::PositionInactive
+        );
+        require_keys_eq!(
+            ctx.accounts.payer.key(),
+            ctx.accounts.position_acc.owner,
+            ErrorCode::UnauthorizedKeeper
+        );
+        ctx.accounts
+            .position_acc
+            .require_check_interval(Clock::get()?.unix_timestamp)?;
+        let now = Clock::get()?.unix_timestamp;
+        if let Some(window) = ctx.accounts.protocol_adapter.blackout_at(now) {
+            let position = &mut ctx.accounts.position_acc;
+            position.deferred_until = window.ends_at;
+            log_info!(
+                "check_deferred",
+                position_id = position.position_id,
+                resumes_at = window.ends_at
+            );
+            emit!(CheckDeferred {
+                owner: position.owner,
+                position_id: position.position_id,
+                protocol_program: ctx.accounts.protocol_adapter.protocol_program,
+                resumes_at: window.ends_at,
+                timestamp: now,
+            });
+            return Ok(());
+        }
+        let health = protocols::read_obligation(
+            &ctx.accounts.obligation,
+            &ctx.accounts.protocol_adapter.layout,
+            &ctx.accounts.position_acc.owner,
+        )?;
+        log_debug!(
+            "check_health_from_protocol",
+            position_id = ctx.accounts.position_acc.position_id,
+            computation_offset = computation_offset,
+            collateral_ratio = health.collateral_ratio,
+            liquidation_threshold = health.liquidation_threshold
+        );
+
+        let model = ctx.accounts.position_acc.risk_model;
+        let args = ArgBuilder::new()
+            .plaintext_u128(ctx.accounts.position_acc.nonce)
+            .account(ctx.accounts.position_acc.key(), 8 + 1, 32 * 3)
+            .plaintext_u128(ctx.accounts.position_acc.alert_nonce)
+            .account(ctx.accounts.position_acc.key(), 8 + 1 + 32 * 3, 32 * 3)
+            .plaintext_u8(ctx.accounts.position_acc.alert_config_set as u8)
+            .plaintext_u64(model.critical_buffer_bps)
+            .plaintext_u64(model.warning_buffer_bps)
+            .plaintext_u64(model.min_value_cents)
+            .plaintext_u64(health.position_value)
+            .plaintext_u64(health.collateral_ratio)
+            .plaintext_u64(health.liquidation_threshold)
+            .build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        ctx.accounts.position_acc.begin_computation(
+            computation_offset,
+            Clock::get()?.unix_timestamp,
+            circuit_version,
+        )?;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![CheckPositionHealthFromProtocolCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[
+                    vec![CallbackAccount {
+                        pubkey: ctx.accounts.position_acc.key(),
+                        is_writable: true,
+                    }],
+                    indexing::callback_accounts(),
+                ]
+                .concat(),
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "check_position_health_from_protocol")]
+    pub fn check_position_health_from_protocol_callback(
+        ctx: Context<CheckPositionHealthFromProtocolCallback>,
+        output: SignedComputationOutputs<CheckPositionHealthFromProtocolOutput>,
+    ) -> Result<()> {
+        introspection::verify_callback(
+            &ctx.accounts.instructions_sysvar,
+            ctx.accounts.computation_account.key,
+            &ctx.accounts.mxe_account,
+            ctx.accounts.position_acc.pending_computation,
+        )?;
+        let o = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(CheckPositionHealthFromProtocolOutput { field_0 }) => field_0,
+            Err(_) => {
+                log_error!(
+                    "computation_aborted",
+                    circuit = "check_position_health_from_protocol"
+                );
+                let now = Clock::get()?.unix_timestamp;
+                emit!(ctx
+                    .accounts
+                    .position_acc
+                    .fail_computation(ComputationKind::CheckHealthFromProtocol, now));
+                return Ok(());
+            }
+        };
+
+        let before = invariants::PositionSnapshot::of(&ctx.accounts.position_acc);
+        ctx.accounts.position_acc.risk_state = o.ciphertexts;
+        ctx.accounts.position_acc.risk_state_version =
+            ctx.accounts.position_acc.pending_circuit_version;
+        ctx.accounts.position_acc.nonce = o.nonce;
+        ctx.accounts
+            .position_acc
+            .record_check(Clock::get()?.unix_timestamp);
+        ctx.accounts.position_acc.pending_computation = None;
+        invariants::check_position(&before, &ctx.accounts.position_acc)?;
+
+        emit_indexed!(
+            ctx,
+            HealthCheckCompleted {
+                owner: ctx.accounts.position_acc.owner,
+                position_id: ctx.accounts.position_acc.position_id,
+                timestamp: Clock::get()?.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    // ─── Protocol TVL ───
+
+    /// Names the only signer allowed to post `protocol_program`'s TVL
+    /// snapshots, creating its snapshot account on first use. Only the
+    /// program's upgrade authority may set it.
+    pub fn set_tvl_updater(
+        ctx: Context<SetTvlUpdater>,
+        protocol_program: Pubkey,
+        updater: Pubkey,
+    ) -> Result<()> {
+        let snapshot = &mut ctx.accounts.tvl_snapshot;
+        ctx.accounts.admin_audit_log.load_mut()?.record(
+            ctx.accounts.authority.key(),
+            AdminField::TvlUpdater,
+            &(protocol_program, snapshot.updater).try_to_vec()?,
+            &(protocol_program, updater).try_to_vec()?,
+            Clock::get()?.slot,
+        );
+        snapshot.bump = ctx.bumps.tvl_snapshot;
+        snapshot.protocol_program = protocol_program;
+        snapshot.updater = updater;
+
+        log_info!(
+            "set_tvl_updater",
+            protocol_program = protocol_program,
+            updater = updater
+        );
+        emit!(TvlUpdaterSet {
+            protocol_program,
+            updater,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Posts `tvl`, the protocol's total value locked in USD cents, as its
+    /// latest snapshot; the one before becomes `previous_tvl`. Updaters post
+    /// on a steady cadence so consecutive snapshots compare like for like.
+    pub fn update_tvl_snapshot(
+        ctx: Context<UpdateTvlSnapshot>,
+        protocol_program: Pubkey,
+        tvl: u64,
+    ) -> Result<()> {
+        let snapshot = &mut ctx.accounts.tvl_snapshot;
+        snapshot.previous_tvl = snapshot.tvl;
+        snapshot.tvl = tvl;
+        snapshot.updated_at = Clock::get()?.unix_timestamp;
+
+        log_debug!(
+            "update_tvl_snapshot",
+            protocol_program = protocol_program,
+            tvl = tvl
+        );
+        emit!(TvlSnapshotUpdated {
+            protocol_program,
+            tvl,
+            previous_tvl: snapshot.previous_tvl,
+            timestamp: snapshot.updated_at,
+        });
+
+        Ok(())
+    }
+
+    /// Has `check_health` follow `protocol_program`'s TVL snapshots and flag
+    /// a drop of more than `tvl_drop_bps` between the last two as low
+    /// severity. `None` stops following.
+    pub fn set_tvl_protocol(
+        ctx: Context<SetTvlProtocol>,
+        position_id: u32,
+        protocol_program: Option<Pubkey>,
+        tvl_drop_bps: u16,
+    ) -> Result<()> {
+        require!(
+            tvl_drop_bps > 0 && tvl_drop_bps <= 10_000,
+            ErrorCode::InvalidTvlDrop
+        );
+        ctx.accounts.position_acc.tvl_protocol = protocol_program;
+        ctx.accounts.position_acc.tvl_drop_bps = tvl_drop_bps;
+        log_info!(
+            "set_tvl_protocol",
+            position_id = position_id,
+            tvl_drop_bps = tvl_drop_bps
+        );
+        Ok(())
+    }
+
+    // ─── Reveal Risk ───
+
+    /// Reveals whether the position is at risk. Only the position owner can call this.
+    pub fn reveal_risk(
+        ctx: Context<RevealRisk>,
+        computation_offset: u64,
+        position_id: u32,
+    ) -> Result<()> {
+        let circuit_version = GlobalConfig::require_active(&ctx.accounts.global_config)?;
+        require!(
+            ctx.accounts.payer.key() == ctx.accounts.position_acc.owner,
+            ErrorCode::InvalidAuthority
+        );
+        let now = Clock::get()?.unix_timestamp;
+        let max_staleness = match ctx.accounts.position_acc.max_staleness_secs {
+            Some(secs) => secs,
+            None => GlobalConfig::max_staleness(&ctx.accounts.global_config)?,
+        };
+        require!(
+            now - ctx.accounts.position_acc.last_check <= max_staleness,
+            ErrorCode::StaleRiskState
+        );
+
+        log_info!("reveal_risk", position_id = position_id);
+
+        let args = ArgBuilder::new()
+            .plaintext_u128(ctx.accounts.position_acc.nonce)
+            .account(ctx.accounts.position_acc.key(), 8 + 1, 32 * 3)
+            .build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        ctx.accounts
+            .position_acc
+            .begin_computation(computation_offset, now, circuit_version)?;
+
+        let callback_accounts = [
+            vec![
+                CallbackAccount {
+                    pubkey: ctx.accounts.position_acc.key(),
+                    is_writable: true,
+                },
+                // The program id stands in for an absent optional account
+                match &ctx.accounts.auto_action {
+                    Some(action) => CallbackAccount {
+                        pubkey: action.key(),
+                        is_writable: true,
+                    },
+                    None => CallbackAccount {
+                        pubkey: crate::ID,
+                        is_writable: false,
+                    },
+                },
+            ],
+            indexing::callback_accounts(),
+        ]
+        .concat();
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![RevealRiskCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &callback_accounts,
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "reveal_risk")]
+    pub fn reveal_risk_callback(
+        ctx: Context<RevealRiskCallback>,
+        output: SignedComputationOutputs<RevealRiskOutput>,
+    ) -> Result<()> {
+        introspection::verify_callback(
+            &ctx.accounts.instructions_sysvar,
+            ctx.accounts.computation_account.key,
+            &ctx.accounts.mxe_account,
+            ctx.accounts.position_acc.pending_computation,
+        )?;
+        let o = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(RevealRiskOutput { field_0 }) => field_0,
+            Err(_) => {
+                log_error!("computation_aborted", circuit = "reveal_risk");
+                let now = Clock::get()?.unix_timestamp;
+                emit!(ctx
+                    .accounts
+                    .position_acc
+                    .fail_computation(ComputationKind::RevealRisk, now));
+                return Ok(());
+            }
+        };
+
+        ctx.accounts.position_acc.pending_computation = None;
+        let is_at_risk = o > 0;
+
+        emit_indexed!(
+            ctx,
+            RiskRevealed {
+                is_at_risk,
+                timestamp: Clock::get()?.unix_timestamp,
+            }
+        );
+
+        let action_type = ActionType::for_severity(o);
+        if action_type != ActionType::None && o >= ctx.accounts.position_acc.min_action_severity {
+            emit_indexed!(
+                ctx,
+                ActionRequired {
+                    owner: ctx.accounts.position_acc.owner,
+                    position_id: ctx.accounts.position_acc.position_id,
+                    action_type,
+                    severity: o,
+                    timestamp: Clock::get()?.unix_timestamp,
+                }
+            );
+        }
+
+        if action_type == ActionType::EmergencyWithdraw {
+            if let Some(action) = &mut ctx.accounts.auto_action {
+                // Run by `execute_auto_action` in a transaction of its own: a
+                // failing target program would otherwise fail this callback,
+                // losing the reveal and leaving the position pending.
+                let position = &ctx.accounts.position_acc;
+                action.due_severity = o;
+                log_info!("auto_action_due", position_id = position.position_id);
+                emit!(AutoActionDue {
+                    owner: position.owner,
+                    position_id: position.position_id,
+                    position: position.key(),
+                    program_id: action.program_id,
+                    severity: o,
+                    timestamp: Clock::get()?.unix_timestamp,
+                });
+            }
+        }
+
+        // Lets instructions later in the callback transaction branch on the
+        // outcome without parsing logs. Set last, after any event CPI, so it
+        // is what the callback returns.
+        anchor_lang::solana_program::program::set_return_data(
+            &RevealStatus {
+                position: ctx.accounts.position_acc.key(),
+                is_at_risk,
+            }
+            .try_to_vec()?,
+        );
+
+        Ok(())
+    }
+
+    // ─── Reveal Risk To Owner ───
+
+    /// Re-encrypts the risk state to `encryption_pubkey` so only the owner can
+    /// read it. Nothing about the position's risk becomes public. The result
+    /// is delivered to the position's `RevealInbox` (created on first use) as
+    /// well as in `RiskRevealedToOwner`.
+    ///
+    /// Passing the position's `ActionMemo` attaches it to the inbox. The
+    /// program can't tell a critical result from any other, and attaching
+    /// the memo only to critical ones would say which they are, so it rides
+    /// along with every reveal and the wallet shows it when the severity it
+    /// decrypts is critical.
+    pub fn reveal_risk_to_owner(
+        ctx: Context<RevealRiskToOwner>,
+        computation_offset: u64,
+        position_id: u32,
+        encryption_pubkey: [u8; 32],
+        encryption_nonce: u128,
+    ) -> Result<()> {
+        let circuit_version = GlobalConfig::require_active(&ctx.accounts.global_config)?;
+        require!(
+            ctx.accounts.payer.key() == ctx.accounts.position_acc.owner,
+            ErrorCode::InvalidAuthority
+        );
+
+        log_info!("reveal_risk_to_owner", position_id = position_id);
+
+        let args = ArgBuilder::new()
+            .plaintext_u128(ctx.accounts.position_acc.nonce)
+            .account(ctx.accounts.position_acc.key(), 8 + 1, 32 * 3)
+            .x25519_pubkey(encryption_pubkey)
+            .plaintext_u128(encryption_nonce)
+            .build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        ctx.accounts.reveal_inbox.bump = ctx.bumps.reveal_inbox;
+        ctx.accounts.reveal_inbox.position = ctx.accounts.position_acc.key();
+        ctx.accounts.position_acc.begin_computation(
+            computation_offset,
+            Clock::get()?.unix_timestamp,
+            circuit_version,
+        )?;
+        let action_memo = match &ctx.accounts.action_memo {
+            Some(memo) => {
+                grow_account(
+                    &ctx.accounts.reveal_inbox.to_account_info(),
+                    &ctx.accounts.payer.to_account_info(),
+                    &ctx.accounts.system_program.to_account_info(),
+                    8 + RevealInbox::INIT_SPACE,
+                )?;
+                memo.key()
+            }
+            None => crate::ID,
+        };
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![RevealRiskToOwnerCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[
+                    CallbackAccount {
+                        pubkey: ctx.accounts.position_acc.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.reveal_inbox.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: action_memo,
+                        is_writable: false,
+                    },
+                ],
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "reveal_risk_to_owner")]
+    pub fn reveal_risk_to_owner_callback(
+        ctx: Context<RevealRiskToOwnerCallback>,
+        output: SignedComputationOutputs<RevealRiskToOwnerOutput>,
+    ) -> Result<()> {
+        introspection::verify_callback(
+            &ctx.accounts.instructions_sysvar,
+            ctx.accounts.computation_account.key,
+            &ctx.accounts.mxe_account,
+            ctx.accounts.position_acc.pending_computation,
+        )?;
+        let o = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(RevealRiskToOwnerOutput { field_0 }) => field_0,
+            Err(_) => {
+                log_error!("computation_aborted", circuit = "reveal_risk_to_owner");
+                let now = Clock::get()?.unix_timestamp;
+                emit!(ctx
+                    .accounts
+                    .position_acc
+                    .fail_computation(ComputationKind::RevealRiskToOwner, now));
+                return Ok(());
+            }
+        };
+
+        let now = Clock::get()?.unix_timestamp;
+        let envelope = EncryptedEnvelope::new(
+            COMP_DEF_OFFSET_REVEAL_RISK_TO_OWNER,
+            o.nonce,
+            &o.ciphertexts,
+        );
+        let inbox = &mut ctx.accounts.reveal_inbox;
+        inbox.owner = ctx.accounts.position_acc.owner;
+        inbox.encryption_key = o.encryption_key;
+        inbox.computation_offset = ctx
+            .accounts
+            .position_acc
+            .pending_computation
+            .unwrap_or_default();
+        inbox.delivered_at = now;
+        inbox.envelope = envelope.clone();
+        inbox.memo = ctx
+            .accounts
+            .action_memo
+            .as_ref()
+            .map(|memo| memo.memo.clone());
+
+        ctx.accounts.position_acc.pending_computation = None;
+
+        // No `ActionRequired` here: the severity is only readable by the
+        // owner, and emitting an action would reveal it to everyone.
+        emit!(RiskRevealedToOwner {
+            owner: ctx.accounts.position_acc.owner,
+            position_id: ctx.accounts.position_acc.position_id,
+            encryption_key: o.encryption_key,
+            envelope,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    // ─── Viewers ───
+
+    /// Lets `viewer`, e.g. an auditor, have the position's risk state
+    /// re-encrypted to `encryption_key` with `reveal_risk_to_viewer`, without
+    /// holding any of the owner's keys. Granting again replaces the key.
+    pub fn grant_viewer(
+        ctx: Context<GrantViewer>,
+        position_id: u32,
+        viewer: Pubkey,
+        encryption_key: [u8; 32],
+    ) -> Result<()> {
+        require_keys_neq!(viewer, ctx.accounts.owner.key(), ErrorCode::InvalidViewer);
+        let now = Clock::get()?.unix_timestamp;
+        let grant = &mut ctx.accounts.viewer_grant;
+        grant.bump = ctx.bumps.viewer_grant;
+        grant.position = ctx.accounts.position_acc.key();
+        grant.owner = ctx.accounts.owner.key();
+        grant.viewer = viewer;
+        grant.encryption_key = encryption_key;
+        grant.granted_at = now;
+
+        log_info!("grant_viewer", position_id = position_id, viewer = viewer);
+        emit!(ViewerGranted {
+            owner: grant.owner,
+            position_id,
+            viewer,
+            encryption_key,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Withdraws `viewer`'s access. A reveal to the viewer already queued is
+    /// dropped when it lands.
+    pub fn revoke_viewer(
+        ctx: Context<RevokeViewer>,
+        position_id: u32,
+        viewer: Pubkey,
+    ) -> Result<()> {
+        log_info!("revoke_viewer", position_id = position_id, viewer = viewer);
+        emit!(ViewerRevoked {
+            owner: ctx.accounts.owner.key(),
+            position_id,
+            viewer,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Re-encrypts the risk state to the key the owner granted the viewer.
+    /// The viewer or the owner may ask; the result is delivered to the
+    /// `ViewerGrant` and in `RiskRevealedToViewer`, and stays unreadable to
+    /// everyone else, the owner included.
+    pub fn reveal_risk_to_viewer(
+        ctx: Context<RevealRiskToViewer>,
+        computation_offset: u64,
+        position_id: u32,
+        encryption_nonce: u128,
+    ) -> Result<()> {
+        let circuit_version = GlobalConfig::require_active(&ctx.accounts.global_config)?;
+
+        log_info!(
+            "reveal_risk_to_viewer",
+            position_id = position_id,
+            viewer = ctx.accounts.viewer_grant.viewer
+        );
+
+        let args = ArgBuilder::new()
+            .plaintext_u128(ctx.accounts.position_acc.nonce)
+            .account(ctx.accounts.position_acc.key(), 8 + 1, 32 * 3)
+            .x25519_pubkey(ctx.accounts.viewer_grant.encryption_key)
+            .plaintext_u128(encryption_nonce)
+            .build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        ctx.accounts.position_acc.begin_computation(
+            computation_offset,
+            Clock::get()?.unix_timestamp,
+            circuit_version,
+        )?;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![RevealRiskToViewerCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[
+                    CallbackAccount {
+                        pubkey: ctx.accounts.position_acc.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.viewer_grant.key(),
+                        is_writable: true,
+                    },
+                ],
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "reveal_risk_to_viewer")]
+    pub fn reveal_risk_to_viewer_callback(
+        ctx: Context<RevealRiskToViewerCallback>,
+        output: SignedComputationOutputs<RevealRiskToViewerOutput>,
+    ) -> Result<()> {
+        introspection::verify_callback(
+            &ctx.accounts.instructions_sysvar,
+            ctx.accounts.computation_account.key,
+            &ctx.accounts.mxe_account,
+            ctx.accounts.position_acc.pending_computation,
+        )?;
+        let o = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(RevealRiskToViewerOutput { field_0 }) => field_0,
+            Err(_) => {
+                log_error!("computation_aborted", circuit = "reveal_risk_to_viewer");
+                let now = Clock::get()?.unix_timestamp;
+                let position = &mut ctx.accounts.position_acc;
+                emit!(position.fail_computation(ComputationKind::RevealRiskToViewer, now));
+                return Ok(());
+            }
+        };
+
+        let computation_offset = ctx.accounts.position_acc.pending_computation.take();
+        let position_key = ctx.accounts.position_acc.key();
+        let info = &ctx.accounts.viewer_grant;
+        let grant = match info.owner == &crate::ID {
+            true => ViewerGrant::try_deserialize(&mut &info.try_borrow_data()?[..]).ok(),
+            false => None,
+        };
+        // Revoked while the reveal was in flight: the output goes nowhere
+        let Some(mut grant) = grant else {
+            log_info!("reveal_risk_to_viewer_dropped", position = position_key);
+            return Ok(());
+        };
+        require_keys_eq!(grant.position, position_key, ErrorCode::InvalidAuthority);
+
+        let now = Clock::get()?.unix_timestamp;
+        let envelope = EncryptedEnvelope::new(
+            COMP_DEF_OFFSET_REVEAL_RISK_TO_VIEWER,
+            o.nonce,
+            &o.ciphertexts,
+        );
+        grant.computation_offset = computation_offset.unwrap_or_default();
+        grant.delivered_at = now;
+        grant.envelope = envelope.clone();
+        grant.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+
+        emit!(RiskRevealedToViewer {
+            owner: ctx.accounts.position_acc.owner,
+            position_id: ctx.accounts.position_acc.position_id,
+            viewer: grant.viewer,
+            encryption_key: o.encryption_key,
+            envelope,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    // ─── Health Factor ───
+
+    /// Computes the position's health factor from its encrypted collateral
+    /// value, debt value and liquidation threshold, and stores it encrypted
+    /// on the position. The owner or its keeper may call this.
+    #[allow(clippy::too_many_arguments)]
+    pub fn compute_health_factor(
+        ctx: Context<ComputeHealthFactor>,
+        computation_offset: u64,
+        position_id: u32,
+        encrypted_values: [[u8; 32]; 3], // collateral_value, debt_value, threshold
+        encryption_pubkey: [u8; 32],
+        encryption_nonce: u128,
+        nonce: u128,
+    ) -> Result<()> {
+        let circuit_version = GlobalConfig::require_active(&ctx.accounts.global_config)?;
+        require!(
+            ctx.accounts.position_acc.is_active,
+            ErrorCode::PositionInactive
+        );
+        require!(
+            ctx.accounts.position_acc.may_submit(
+                &ctx.accounts.payer.key(),
+                Clock::get()?.slot,
+                None
+            )?,
+            ErrorCode::UnauthorizedKeeper
+        );
+        log_info!("compute_health_factor", position_id = position_id);
+
+        let args = ArgBuilder::new()
+            .plaintext_u128(nonce)
+            .x25519_pubkey(encryption_pubkey)
+            .plaintext_u128(encryption_nonce)
+            .encrypted_u64(encrypted_values[0])
+            .encrypted_u64(encrypted_values[1])
+            .encrypted_u64(encrypted_values[2])
+            .build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        ctx.accounts.position_acc.begin_computation(
+            computation_offset,
+            Clock::get()?.unix_timestamp,
+            circuit_version,
+        )?;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![ComputeHealthFactorCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[CallbackAccount {
+                    pubkey: ctx.accounts.position_acc.key(),
+                    is_writable: true,
+                }],
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "compute_health_factor")]
+    pub fn compute_health_factor_callback(
+        ctx: Context<ComputeHealthFactorCallback>,
+        output: SignedComputationOutputs<ComputeHealthFactorOutput>,
+    ) -> Result<()> {
+        introspection::verify_callback(
+            &ctx.accounts.instructions_sysvar,
+            ctx.accounts.computation_account.key,
+            &ctx.accounts.mxe_account,
+            ctx.accounts.position_acc.pending_computation,
+        )?;
+        let o = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(ComputeHealthFactorOutput { field_0 }) => field_0,
+            Err(_) => {
+                log_error!("computation_aborted", circuit = "compute_health_factor");
+                let now = Clock::get()?.unix_timestamp;
+                emit!(ctx
+                    .accounts
+                    .position_acc
+                    .fail_computation(ComputationKind::ComputeHealthFactor, now));
+                return Ok(());
+            }
+        };
+
+        let now = Clock::get()?.unix_timestamp;
+        let position = &mut ctx.accounts.position_acc;
+        position.health_factor = o.ciphertexts[0];
+        position.health_factor_nonce = o.nonce;
+        position.health_factor_at = now;
+        position.pending_computation = None;
+
+        emit!(HealthFactorComputed {
+            owner: position.owner,
+            position_id: position.position_id,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Re-encrypts the stored health factor to `encryption_pubkey`, delivered
+    /// to the position's `RevealInbox` and in `HealthFactorRevealedToOwner`,
+    /// like `reveal_risk_to_owner`.
+    pub fn reveal_health_factor_to_owner(
+        ctx: Context<RevealHealthFactorToOwner>,
+        computation_offset: u64,
+        position_id: u32,
+        encryption_pubkey: [u8; 32],
+        encryption_nonce: u128,
+    ) -> Result<()> {
+        let circuit_version = GlobalConfig::require_active(&ctx.accounts.global_config)?;
+        require!(
+            ctx.accounts.position_acc.health_factor_at > 0,
+            ErrorCode::HealthFactorNotComputed
+        );
+
+        log_info!("reveal_health_factor_to_owner", position_id = position_id);
+
+        let args = ArgBuilder::new()
+            .plaintext_u128(ctx.accounts.position_acc.health_factor_nonce)
+            .account(
+                ctx.accounts.position_acc.key(),
+                PositionAccount::HEALTH_FACTOR_OFFSET,
+                32,
+            )
+            .x25519_pubkey(encryption_pubkey)
+            .plaintext_u128(encryption_nonce)
+            .build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        ctx.accounts.reveal_inbox.bump = ctx.bumps.reveal_inbox;
+        ctx.accounts.reveal_inbox.position = ctx.accounts.position_acc.key();
+        ctx.accounts.position_acc.begin_computation(
+            computation_offset,
+            Clock::get()?.unix_timestamp,
+            circuit_version,
+        )?;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![RevealHealthFactorToOwnerCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[
+                    CallbackAccount {
+                        pubkey: ctx.accounts.position_acc.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.reveal_inbox.key(),
+                        is_writable: true,
+                    },
+                ],
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "reveal_health_factor_to_owner")]
+    pub fn reveal_health_factor_to_owner_callback(
+        ctx: Context<RevealHealthFactorToOwnerCallback>,
+        output: SignedComputationOutputs<RevealHealthFactorToOwnerOutput>,
+    ) -> Result<()> {
+        introspection::verify_callback(
+            &ctx.accounts.instructions_sysvar,
+            ctx.accounts.computation_account.key,
+            &ctx.accounts.mxe_account,
+            ctx.accounts.position_acc.pending_computation,
+        )?;
+        let o = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(RevealHealthFactorToOwnerOutput { field_0 }) => field_0,
+            Err(_) => {
+                log_error!(
+                    "computation_aborted",
+                    circuit = "reveal_health_factor_to_owner"
+                );
+                let now = Clock::get()?.unix_timestamp;
+                emit!(ctx
+                    .accounts
+                    .position_acc
+                    .fail_computation(ComputationKind::RevealHealthFactorToOwner, now));
+                return Ok(());
+            }
+        };
+
+        let now = Clock::get()?.unix_timestamp;
+        let envelope = EncryptedEnvelope::new(
+            COMP_DEF_OFFSET_REVEAL_HEALTH_FACTOR_TO_OWNER,
+            o.nonce,
+            &o.ciphertexts,
+        );
+        let inbox = &mut ctx.accounts.reveal_inbox;
+        inbox.owner = ctx.accounts.position_acc.owner;
+        inbox.encryption_key = o.encryption_key;
+        inbox.computation_offset = ctx
+            .accounts
+            .position_acc
+            .pending_computation
+            .unwrap_or_default();
+        inbox.delivered_at = now;
+        inbox.envelope = envelope.clone();
+        inbox.memo = None;
+
+        ctx.accounts.position_acc.pending_computation = None;
+
+        emit!(HealthFactorRevealedToOwner {
+            owner: ctx.accounts.position_acc.owner,
+            position_id: ctx.accounts.position_acc.position_id,
+            encryption_key: o.encryption_key,
+            envelope,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    // ─── Stress Test ───
+
+    /// What-if check for deleveraging decisions: would the position breach
+    /// its liquidation threshold if its collateral lost `shock_bps` of its
+    /// value? The owner supplies the position's values encrypted to
+    /// `encryption_pubkey` and gets the answer back encrypted to the same
+    /// key, in the position's `RevealInbox` and `StressTestRevealedToOwner`.
+    /// The stored risk state is left alone.
+    #[allow(clippy::too_many_arguments)]
+    pub fn stress_test_position(
+        ctx: Context<StressTestPosition>,
+        computation_offset: u64,
+        position_id: u32,
+        encrypted_values: [[u8; 32]; 3], // collateral_value, debt_value, threshold
+        encryption_pubkey: [u8; 32],
+        encryption_nonce: u128,
+        shock_bps: u16,
+    ) -> Result<()> {
+        let circuit_version = GlobalConfig::require_active(&ctx.accounts.global_config)?;
+        require!(
+            shock_bps > 0 && shock_bps <= 10_000,
+            ErrorCode::InvalidPriceShock
+        );
+
+        log_info!(
+            "stress_test_position",
+            position_id = position_id,
+            shock_bps = shock_bps
+        );
+
+        let args = ArgBuilder::new()
+            .x25519_pubkey(encryption_pubkey)
+            .plaintext_u128(encryption_nonce)
+            .encrypted_u64(encrypted_values[0])
+            .encrypted_u64(encrypted_values[1])
+            .encrypted_u64(encrypted_values[2])
+            .plaintext_u64(shock_bps as u64)
+            .build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        ctx.accounts.reveal_inbox.bump = ctx.bumps.reveal_inbox;
+        ctx.accounts.reveal_inbox.position = ctx.accounts.position_acc.key();
+        ctx.accounts.position_acc.begin_computation(
+            computation_offset,
+            Clock::get()?.unix_timestamp,
+            circuit_version,
+        )?;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![StressTestPositionCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[
+                    CallbackAccount {
+                        pubkey: ctx.accounts.position_acc.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.reveal_inbox.key(),
+                        is_writable: true,
+                    },
+                ],
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "stress_test_position")]
+    pub fn stress_test_position_callback(
+        ctx: Context<StressTestPositionCallback>,
+        output: SignedComputationOutputs<StressTestPositionOutput>,
+    ) -> Result<()> {
+        introspection::verify_callback(
+            &ctx.accounts.instructions_sysvar,
+            ctx.accounts.computation_account.key,
+            &ctx.accounts.mxe_account,
+            ctx.accounts.position_acc.pending_computation,
+        )?;
+        let o = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(StressTestPositionOutput { field_0 }) => field_0,
+            Err(_) => {
+                log_error!("computation_aborted", circuit = "stress_test_position");
+                let now = Clock::get()?.unix_timestamp;
+                let position = &mut ctx.accounts.position_acc;
+                emit!(position.fail_computation(ComputationKind::StressTestPosition, now));
+                return Ok(());
+            }
+        };
+
+        let now = Clock::get()?.unix_timestamp;
+        let envelope = EncryptedEnvelope::new(
+            COMP_DEF_OFFSET_STRESS_TEST_POSITION,
+            o.nonce,
+            &o.ciphertexts,
+        );
+        let inbox = &mut ctx.accounts.reveal_inbox;
+        inbox.owner = ctx.accounts.position_acc.owner;
+        inbox.encryption_key = o.encryption_key;
+        inbox.computation_offset = ctx
+            .accounts
+            .position_acc
+            .pending_computation
+            .unwrap_or_default();
+        inbox.delivered_at = now;
+        inbox.envelope = envelope.clone();
+        inbox.memo = None;
+
+        ctx.accounts.position_acc.pending_computation = None;
+
+        emit!(StressTestRevealedToOwner {
+            owner: ctx.accounts.position_acc.owner,
+            position_id: ctx.accounts.position_acc.position_id,
+            encryption_key: o.encryption_key,
+            envelope,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    // ─── Attestations ───
+
+    /// Proof of solvency: reveals only whether the position's stored health
+    /// factor is at least `min_health_factor_bps` (12_000 for 1.2), and
+    /// records the cluster-signed answer in the position's
+    /// `AttestationAccount` for counterparties to check on-chain. Position
+    /// size and composition stay encrypted. Run `compute_health_factor`
+    /// first; the attestation carries the time the factor was computed.
+    pub fn attest_health(
+        ctx: Context<AttestHealth>,
+        computation_offset: u64,
+        position_id: u32,
+        min_health_factor_bps: u32,
+    ) -> Result<()> {
+        let circuit_version = GlobalConfig::require_active(&ctx.accounts.global_config)?;
+        require!(
+            min_health_factor_bps >= 10_000,
+            ErrorCode::InvalidAttestationThreshold
+        );
+        require!(
+            ctx.accounts.position_acc.health_factor_at > 0,
+            ErrorCode::HealthFactorNotComputed
+        );
+
+        log_info!(
+            "attest_health",
+            position_id = position_id,
+            min_health_factor_bps = min_health_factor_bps
+        );
+
+        let args = ArgBuilder::new()
+            .plaintext_u128(ctx.accounts.position_acc.health_factor_nonce)
+            .account(
+                ctx.accounts.position_acc.key(),
+                PositionAccount::HEALTH_FACTOR_OFFSET,
+                32,
+            )
+            .plaintext_u64(min_health_factor_bps as u64)
+            .build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        let attestation = &mut ctx.accounts.attestation;
+        attestation.bump = ctx.bumps.attestation;
+        attestation.position = ctx.accounts.position_acc.key();
+        attestation.owner = ctx.accounts.position_acc.owner;
+        attestation.requested_min_health_factor_bps = min_health_factor_bps;
+        ctx.accounts.position_acc.begin_computation(
+            computation_offset,
+            Clock::get()?.unix_timestamp,
+            circuit_version,
+        )?;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![AttestHealthCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[
+                    CallbackAccount {
+                        pubkey: ctx.accounts.position_acc.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.attestation.key(),
+                        is_writable: true,
+                    },
+                ],
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "attest_health")]
+    pub fn attest_health_callback(
+        ctx: Context<AttestHealthCallback>,
+        output: SignedComputationOutputs<AttestHealthOutput>,
+    ) -> Result<()> {
+        introspection::verify_callback(
+            &ctx.accounts.instructions_sysvar,
+            ctx.accounts.computation_account.key,
+            &ctx.accounts.mxe_account,
+            ctx.accounts.position_acc.pending_computation,
+        )?;
+        let healthy = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(AttestHealthOutput { field_0 }) => field_0,
+            Err(_) => {
+                log_error!("computation_aborted", circuit = "attest_health");
+                let now = Clock::get()?.unix_timestamp;
+                let position = &mut ctx.accounts.position_acc;
+                emit!(position.fail_computation(ComputationKind::AttestHealth, now));
+                return Ok(());
+            }
+        };
+
+        let clock = Clock::get()?;
+        let position = &mut ctx.accounts.position_acc;
+        let attestation = &mut ctx.accounts.attestation;
+        attestation.min_health_factor_bps = attestation.requested_min_health_factor_bps;
+        attestation.healthy = healthy;
+        attestation.health_factor_at = position.health_factor_at;
+        attestation.slot = clock.slot;
+        attestation.attested_at = clock.unix_timestamp;
+        position.pending_computation = None;
+
+        emit!(HealthAttested {
+            owner: position.owner,
+            position_id: position.position_id,
+            min_health_factor_bps: attestation.min_health_factor_bps,
+            healthy,
+            health_factor_at: attestation.health_factor_at,
+            slot: clock.slot,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // ─── Key Rotation ───
+
+    /// Re-encrypts the stored risk state via MPC under `nonce`, which has to
+    /// be newer than the position's. For an owner whose x25519 key leaked:
+    /// the old ciphertexts are overwritten, and reveals to the new key
+    /// continue from the rotated state. Only the owner may rotate.
+    pub fn rotate_encryption(
+        ctx: Context<RotateEncryption>,
+        computation_offset: u64,
+        position_id: u32,
+        nonce: u128,
+    ) -> Result<()> {
+        let circuit_version = GlobalConfig::require_active(&ctx.accounts.global_config)?;
+        require!(
+            nonce > ctx.accounts.position_acc.nonce,
+            ErrorCode::StaleNonce
+        );
+        log_info!("rotate_encryption", position_id = position_id);
+
+        let args = ArgBuilder::new()
+            .plaintext_u128(nonce)
+            .plaintext_u128(ctx.accounts.position_acc.nonce)
+            .account(ctx.accounts.position_acc.key(), 8 + 1, 32 * 3)
+            .build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        ctx.accounts.position_acc.begin_computation(
+            computation_offset,
+            Clock::get()?.unix_timestamp,
+            circuit_version,
+        )?;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![RotateEncryptionCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[CallbackAccount {
+                    pubkey: ctx.accounts.position_acc.key(),
+                    is_writable: true,
+                }],
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "rotate_encryption")]
+    pub fn rotate_encryption_callback(
+        ctx: Context<RotateEncryptionCallback>,
+        output: SignedComputationOutputs<RotateEncryptionOutput>,
+    ) -> Result<()> {
+        introspection::verify_callback(
+            &ctx.accounts.instructions_sysvar,
+            ctx.accounts.computation_account.key,
+            &ctx.accounts.mxe_account,
+            ctx.accounts.position_acc.pending_computation,
+        )?;
+        let o = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(RotateEncryptionOutput { field_0 }) => field_0,
+            Err(_) => {
+                log_error!("computation_aborted", circuit = "rotate_encryption");
+                let now = Clock::get()?.unix_timestamp;
+                emit!(ctx
+                    .accounts
+                    .position_acc
+                    .fail_computation(ComputationKind::RotateEncryption, now));
+                return Ok(());
+            }
+        };
+
+        let before = invariants::PositionSnapshot::of(&ctx.accounts.position_acc);
+        ctx.accounts.position_acc.risk_state = o.ciphertexts;
+        ctx.accounts.position_acc.risk_state_version =
+            ctx.accounts.position_acc.pending_circuit_version;
+        ctx.accounts.position_acc.nonce = o.nonce;
+        ctx.accounts.position_acc.pending_computation = None;
+        invariants::check_position(&before, &ctx.accounts.position_acc)?;
+
+        emit!(EncryptionRotated {
+            owner: ctx.accounts.position_acc.owner,
+            position_id: ctx.accounts.position_acc.position_id,
+            nonce: o.nonce,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // ─── Sponsored Reveals ───
+
+    /// Lets the signing sponsor pay for up to `reveals` owner reveals of
+    /// `owner`'s positions, replacing any previous allowance. `reveals = 0`
+    /// revokes.
+    pub fn set_sponsor_budget(
+        ctx: Context<SetSponsorBudget>,
+        owner: Pubkey,
+        reveals: u32,
+    ) -> Result<()> {
+        let budget = &mut ctx.accounts.sponsor_budget;
+        budget.bump = ctx.bumps.sponsor_budget;
+        budget.sponsor = ctx.accounts.sponsor.key();
+        budget.owner = owner;
+        budget.remaining = reveals;
+
+        log_info!("set_sponsor_budget", owner = owner, reveals = reveals);
+        emit!(SponsorBudgetSet {
+            sponsor: budget.sponsor,
+            owner,
+            remaining: reveals,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// `reveal_risk_to_owner` with the sponsor as fee payer. The owner still
+    /// signs, so only the owner can choose who the result is encrypted to,
+    /// but needs no SOL. Each call uses one reveal of the sponsor's budget.
+    pub fn sponsored_reveal_risk_to_owner(
+        ctx: Context<SponsoredRevealRiskToOwner>,
+        computation_offset: u64,
+        position_id: u32,
+        encryption_pubkey: [u8; 32],
+        encryption_nonce: u128,
+    ) -> Result<()> {
+        let circuit_version = GlobalConfig::require_active(&ctx.accounts.global_config)?;
+        let budget = &mut ctx.accounts.sponsor_budget;
+        require!(budget.remaining > 0, ErrorCode::SponsorBudgetExhausted);
+        budget.remaining -= 1;
+        budget.used = budget.used.saturating_add(1);
+
+        log_info!(
+            "sponsored_reveal_risk_to_owner",
+            position_id = position_id,
+            sponsor = ctx.accounts.payer.key()
+        );
+
+        let args = ArgBuilder::new()
+            .plaintext_u128(ctx.accounts.position_acc.nonce)
+            .account(ctx.accounts.position_acc.key(), 8 + 1, 32 * 3)
+            .x25519_pubkey(encryption_pubkey)
+            .plaintext_u128(encryption_nonce)
+            .build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        ctx.accounts.reveal_inbox.bump = ctx.bumps.reveal_inbox;
+        ctx.accounts.reveal_inbox.position = ctx.accounts.position_acc.key();
+        ctx.accounts.position_acc.begin_computation(
+            computation_offset,
+            Clock::get()?.unix_timestamp,
+            circuit_version,
+        )?;
+        let action_memo = match &ctx.accounts.action_memo {
+            Some(memo) => {
+                grow_account(
+                    &ctx.accounts.reveal_inbox.to_account_info(),
+                    &ctx.accounts.payer.to_account_info(),
+                    &ctx.accounts.system_program.to_account_info(),
+                    8 + RevealInbox::INIT_SPACE,
+                )?;
+                memo.key()
+            }
+            None => crate::ID,
+        };
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![RevealRiskToOwnerCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[
+                    CallbackAccount {
+                        pubkey: ctx.accounts.position_acc.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: ctx.accounts.reveal_inbox.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: action_memo,
+                        is_writable: false,
+                    },
+                ],
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    // ─── Risk History ───
+
+    /// Creates the position's risk history. From then on `check_health`
+    /// callbacks append each new encrypted risk state to it, keeping the last
+    /// `RISK_HISTORY_LEN`.
+    pub fn init_risk_history(ctx: Context<InitRiskHistory>, position_id: u32) -> Result<()> {
+        let mut history = ctx.accounts.risk_history.load_init()?;
+        history.bump = ctx.bumps.risk_history;
+        history.position = ctx.accounts.position_acc.key();
+        log_info!("init_risk_history", position_id = position_id);
+        Ok(())
+    }
+
+    /// Reveals how many of the recorded health checks were at risk, without
+    /// revealing any individual entry.
+    pub fn reveal_history_summary(
+        ctx: Context<RevealHistorySummary>,
+        computation_offset: u64,
+        position_id: u32,
+    ) -> Result<()> {
+        let circuit_version = GlobalConfig::require_active(&ctx.accounts.global_config)?;
+        let history_key = ctx.accounts.risk_history.key();
+        let history = ctx.accounts.risk_history.load()?;
+        require!(history.len > 0, ErrorCode::EmptyRiskHistory);
+
+        log_info!(
+            "reveal_history_summary",
+            position_id = position_id,
+            entries = history.len
+        );
+
+        let mut args = ArgBuilder::new().plaintext_u8(history.len as u8);
+        for (i, entry) in history.entries.iter().enumerate() {
+            args = args
+                .plaintext_u128(u128::from_le_bytes(entry.nonce))
+                .account(
+                    history_key,
+                    RiskHistoryAccount::ciphertexts_offset(i),
+                    32 * 3,
+                );
+        }
+        let args = args.build();
+        drop(history);
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        // Serializes with check_health, whose callback rewrites the entries
+        ctx.accounts.position_acc.begin_computation(
+            computation_offset,
+            Clock::get()?.unix_timestamp,
+            circuit_version,
+        )?;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![RevealHistorySummaryCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[
+                    CallbackAccount {
+                        pubkey: ctx.accounts.position_acc.key(),
+                        is_writable: true,
+                    },
+                    CallbackAccount {
+                        pubkey: history_key,
+                        is_writable: false,
+                    },
+                ],
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "reveal_history_summary")]
+    pub fn reveal_history_summary_callback(
+        ctx: Context<RevealHistorySummaryCallback>,
+        output: SignedComputationOutputs<RevealHistorySummaryOutput>,
+    ) -> Result<()> {
+        introspection::verify_callback(
+            &ctx.accounts.instructions_sysvar,
+            ctx.accounts.computation_account.key,
+            &ctx.accounts.mxe_account,
+            ctx.accounts.position_acc.pending_computation,
+        )?;
+        let o = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(RevealHistorySummaryOutput { field_0 }) => field_0,
+            Err(_) => {
+                log_error!("computation_aborted", circuit = "reveal_history_summary");
+                let now = Clock::get()?.unix_timestamp;
+                emit!(ctx
+                    .accounts
+                    .position_acc
+                    .fail_computation(ComputationKind::RevealHistorySummary, now));
+                return Ok(());
+            }
+        };
+
+        ctx.accounts.position_acc.pending_computation = None;
+
+        emit!(RiskHistorySummarized {
+            owner: ctx.accounts.position_acc.owner,
+            position_id: ctx.accounts.position_acc.position_id,
+            checks: ctx.accounts.risk_history.load()?.len,
+            at_risk_checks: o,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // ─── Portfolio ───
+
+    /// Creates the owner's portfolio. The encrypted portfolio risk stays empty
+    /// until the first `aggregate_portfolio_risk`.
+    pub fn register_portfolio(ctx: Context<RegisterPortfolio>) -> Result<()> {
+        ctx.accounts.portfolio_acc.bump = ctx.bumps.portfolio_acc;
+        ctx.accounts.portfolio_acc.owner = ctx.accounts.owner.key();
+        Ok(())
+    }
+
+    pub fn add_portfolio_position(
+        ctx: Context<AddPortfolioPosition>,
+        _position_id: u32,
+    ) -> Result<()> {
+        let position = ctx.accounts.position_acc.key();
+        let portfolio = &mut ctx.accounts.portfolio_acc;
+        require!(
+            !portfolio.positions.contains(&position),
+            ErrorCode::PositionAlreadyInPortfolio
+        );
+        require!(
+            portfolio.positions.len() < MAX_PORTFOLIO_POSITIONS,
+            ErrorCode::PortfolioFull
+        );
+        portfolio.positions.push(position);
+        Ok(())
+    }
+
+    /// Takes the position's address rather than the account so closed
+    /// positions can still be removed.
+    pub fn remove_portfolio_position(
+        ctx: Context<RemovePortfolioPosition>,
+        position: Pubkey,
+    ) -> Result<()> {
+        let portfolio = &mut ctx.accounts.portfolio_acc;
+        require!(
+            portfolio.positions.contains(&position),
+            ErrorCode::PositionNotInPortfolio
+        );
+        portfolio.positions.retain(|p| *p != position);
+        Ok(())
+    }
+
+    /// Folds the encrypted risk state of every portfolio position into one
+    /// encrypted portfolio score. The position accounts are passed as
+    /// remaining accounts, in portfolio order.
+    pub fn aggregate_portfolio_risk<'info>(
+        ctx: Context<'_, '_, 'info, 'info, AggregatePortfolioRisk<'info>>,
+        computation_offset: u64,
+        nonce: u128,
+    ) -> Result<()> {
+        GlobalConfig::require_active(&ctx.accounts.global_config)?;
+        let positions = &ctx.accounts.portfolio_acc.positions;
+        require!(!positions.is_empty(), ErrorCode::EmptyPortfolio);
+        require!(
+            ctx.remaining_accounts.len() == positions.len(),
+            ErrorCode::PortfolioPositionMismatch
+        );
+
+        let mut slots = Vec::with_capacity(MAX_PORTFOLIO_POSITIONS);
+        for (info, expected) in ctx.remaining_accounts.iter().zip(positions) {
+            require_keys_eq!(info.key(), *expected, ErrorCode::PortfolioPositionMismatch);
+            let position = Account::<PositionAccount>::try_from(info)?;
+            // A position transferred away stays listed until removed
+            require_keys_eq!(
+                position.owner,
+                ctx.accounts.portfolio_acc.owner,
+                ErrorCode::PortfolioPositionMismatch
+            );
+            slots.push((info.key(), position.nonce));
+        }
+
+        log_debug!(
+            "aggregate_portfolio_risk",
+            positions = slots.len(),
+            computation_offset = computation_offset
+        );
+
+        let mut args = ArgBuilder::new()
+            .plaintext_u128(nonce)
+            .plaintext_u8(slots.len() as u8);
+        // Unused slots repeat the first position; the circuit ignores them.
+        for i in 0..MAX_PORTFOLIO_POSITIONS {
+            let (key, position_nonce) = slots.get(i).copied().unwrap_or(slots[0]);
+            args = args
+                .plaintext_u128(position_nonce)
+                .account(key, 8 + 1, 32 * 3);
+        }
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        ctx.accounts.portfolio_acc.pending_computation = Some(computation_offset);
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args.build(),
+            vec![AggregatePortfolioRiskCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[CallbackAccount {
+                    pubkey: ctx.accounts.portfolio_acc.key(),
+                    is_writable: true,
+                }],
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "aggregate_portfolio_risk")]
+    pub fn aggregate_portfolio_risk_callback(
+        ctx: Context<AggregatePortfolioRiskCallback>,
+        output: SignedComputationOutputs<AggregatePortfolioRiskOutput>,
+    ) -> Result<()> {
+        introspection::verify_callback(
+            &ctx.accounts.instructions_sysvar,
+            ctx.accounts.computation_account.key,
+            &ctx.accounts.mxe_account,
+            ctx.accounts.portfolio_acc.pending_computation,
+        )?;
+        let o = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(AggregatePortfolioRiskOutput { field_0 }) => field_0,
+            Err(_) => {
+                log_error!("computation_aborted", circuit = "aggregate_portfolio_risk");
+                let portfolio = &mut ctx.accounts.portfolio_acc;
+                emit!(ComputationFailed {
+                    owner: portfolio.owner,
+                    position_id: None,
+                    computation_offset: portfolio.pending_computation.take().unwrap_or_default(),
+                    kind: ComputationKind::AggregatePortfolioRisk,
+                    timestamp: Clock::get()?.unix_timestamp,
+                });
+                return Ok(());
+            }
+        };
+
+        let now = Clock::get()?.unix_timestamp;
+        let portfolio = &mut ctx.accounts.portfolio_acc;
+        portfolio.risk_state = o.ciphertexts;
+        portfolio.nonce = o.nonce;
+        portfolio.last_aggregated = now;
+        portfolio.pending_computation = None;
+
+        emit!(PortfolioRiskAggregated {
+            owner: portfolio.owner,
+            positions: portfolio.positions.len() as u8,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    // ─── Risk Heatmap ───
+
+    /// Sets the collateral mints the heatmap counts separately; zero keys
+    /// leave a class unused. Only the config's admin may set them, and only
+    /// between epochs, so one heatmap never mixes two class lists.
+    pub fn configure_risk_heatmap(
+        ctx: Context<ConfigureRiskHeatmap>,
+        classes: [Pubkey; HEATMAP_CLASSES],
+    ) -> Result<()> {
+        let heatmap = &mut ctx.accounts.risk_heatmap;
+        require!(
+            heatmap.pending_computation.is_none() && heatmap.sampled == [0; HEATMAP_BUCKETS],
+            ErrorCode::HeatmapEpochInProgress
+        );
+        ctx.accounts.admin_audit_log.load_mut()?.record(
+            ctx.accounts.admin.key(),
+            AdminField::HeatmapClasses,
+            &heatmap.classes.try_to_vec()?,
+            &classes.try_to_vec()?,
+            Clock::get()?.slot,
+        );
+        heatmap.bump = ctx.bumps.risk_heatmap;
+        heatmap.classes = classes;
+        // Positions record the epoch they were sampled in, 0 for never
+        heatmap.epoch = heatmap.epoch.max(1);
+
+        log_info!("configure_risk_heatmap", epoch = heatmap.epoch);
+        emit!(RiskHeatmapConfigured {
+            classes,
+            epoch: heatmap.epoch,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Admin crank: adds up to `MAX_HEATMAP_BATCH` positions, passed as
+    /// writable remaining accounts, to the epoch's encrypted at-risk counts.
+    /// Each position is sampled at most once per epoch, so a batch can't
+    /// repeat one either.
+    pub fn accumulate_risk_heatmap<'info>(
+        ctx: Context<'_, '_, 'info, 'info, AccumulateRiskHeatmap<'info>>,
+        computation_offset: u64,
+        nonce: u128,
+    ) -> Result<()> {
+        require!(!ctx.accounts.global_config.paused, ErrorCode::ProgramPaused);
+        let infos = ctx.remaining_accounts;
+        require!(
+            !infos.is_empty() && infos.len() <= MAX_HEATMAP_BATCH,
+            ErrorCode::InvalidBatch
+        );
+        let heatmap = &ctx.accounts.risk_heatmap;
+        require!(
+            heatmap.pending_computation.is_none(),
+            ErrorCode::ComputationPending
+        );
+
+        let mut positions = Vec::with_capacity(infos.len());
+        let mut sampled = [0; HEATMAP_BUCKETS];
+        for (i, info) in infos.iter().enumerate() {
+            require!(
+                info.is_writable && infos[..i].iter().all(|a| a.key != info.key),
+                ErrorCode::InvalidBatch
+            );
+            let mut position = Account::<PositionAccount>::try_from(info)?;
+            require!(
+                position.heatmap_epoch != heatmap.epoch,
+                ErrorCode::PositionAlreadySampled
+            );
+            position.heatmap_epoch = heatmap.epoch;
+            let class = heatmap.class_of(&position.metadata.collateral_mint);
+            sampled[class] += 1;
+            positions.push((position, class));
+        }
+
+        log_debug!(
+            "accumulate_risk_heatmap",
+            positions = positions.len(),
+            computation_offset = computation_offset
+        );
+
+        let mut args = ArgBuilder::new()
+            .plaintext_u128(nonce)
+            .plaintext_u128(heatmap.nonce)
+            .account(heatmap.key(), 8 + 1, 32 * HEATMAP_BUCKETS as u32)
+            .plaintext_u8((heatmap.sampled == [0; HEATMAP_BUCKETS]) as u8)
+            .plaintext_u8(positions.len() as u8);
+        // Unused slots repeat the first position; the circuit ignores them.
+        for i in 0..MAX_HEATMAP_BATCH {
+            args = args.plaintext_u8(positions.get(i).unwrap_or(&positions[0]).1 as u8);
+        }
+        for i in 0..MAX_HEATMAP_BATCH {
+            let (position, _) = positions.get(i).unwrap_or(&positions[0]);
+            args = args
+                .plaintext_u128(position.nonce)
+                .account(position.key(), 8 + 1, 32 * 3);
+        }
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        ctx.accounts.risk_heatmap.pending_computation = Some(computation_offset);
+        ctx.accounts.risk_heatmap.pending_sampled = sampled;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args.build(),
+            vec![AccumulateRiskHeatmapCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[CallbackAccount {
+                    pubkey: ctx.accounts.risk_heatmap.key(),
+                    is_writable: true,
+                }],
+            )?],
+            1,
+            0,
+        )?;
+
+        for (position, _) in &positions {
+            position.exit(&crate::ID)?;
+        }
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "accumulate_risk_heatmap")]
+    pub fn accumulate_risk_heatmap_callback(
+        ctx: Context<AccumulateRiskHeatmapCallback>,
+        output: SignedComputationOutputs<AccumulateRiskHeatmapOutput>,
+    ) -> Result<()> {
+        introspection::verify_callback(
+            &ctx.accounts.instructions_sysvar,
+            ctx.accounts.computation_account.key,
+            &ctx.accounts.mxe_account,
+            ctx.accounts.risk_heatmap.pending_computation,
+        )?;
+        let o = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(AccumulateRiskHeatmapOutput { field_0 }) => field_0,
+            Err(_) => {
+                log_error!("computation_aborted", circuit = "accumulate_risk_heatmap");
+                // The batch's positions stay marked, so they sit this epoch out
+                let heatmap_key = ctx.accounts.risk_heatmap.key();
+                let heatmap = &mut ctx.accounts.risk_heatmap;
+                heatmap.pending_sampled = [0; HEATMAP_BUCKETS];
+                emit!(ComputationFailed {
+                    owner: heatmap_key,
+                    position_id: None,
+                    computation_offset: heatmap.pending_computation.take().unwrap_or_default(),
+                    kind: ComputationKind::AccumulateRiskHeatmap,
+                    timestamp: Clock::get()?.unix_timestamp,
+                });
+                return Ok(());
+            }
+        };
+
+        let heatmap = &mut ctx.accounts.risk_heatmap;
+        heatmap.counts = o.ciphertexts;
+        heatmap.nonce = o.nonce;
+        let added = heatmap.pending_sampled;
+        for (sampled, added) in heatmap.sampled.iter_mut().zip(added) {
+            *sampled += added;
+        }
+        heatmap.pending_sampled = [0; HEATMAP_BUCKETS];
+        heatmap.pending_computation = None;
+
+        Ok(())
+    }
+
+    /// Admin crank: reveals the epoch's at-risk counts once it sampled
+    /// `MIN_HEATMAP_POSITIONS`, withholding buckets of fewer than
+    /// `MIN_HEATMAP_BUCKET_POSITIONS`, and starts the next epoch.
+    pub fn publish_risk_heatmap(
+        ctx: Context<PublishRiskHeatmap>,
+        computation_offset: u64,
+    ) -> Result<()> {
+        require!(!ctx.accounts.global_config.paused, ErrorCode::ProgramPaused);
+        let heatmap = &ctx.accounts.risk_heatmap;
+        require!(
+            heatmap.pending_computation.is_none(),
+            ErrorCode::ComputationPending
+        );
+        require!(
+            heatmap.sampled.iter().sum::<u32>() >= MIN_HEATMAP_POSITIONS,
+            ErrorCode::HeatmapTooSparse
+        );
+
+        let mut args = ArgBuilder::new().plaintext_u128(heatmap.nonce).account(
+            heatmap.key(),
+            8 + 1,
+            32 * HEATMAP_BUCKETS as u32,
+        );
+        for sampled in heatmap.sampled {
+            args = args.plaintext_u8((sampled < MIN_HEATMAP_BUCKET_POSITIONS) as u8);
+        }
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        ctx.accounts.risk_heatmap.pending_computation = Some(computation_offset);
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args.build(),
+            vec![RevealRiskHeatmapCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[CallbackAccount {
+                    pubkey: ctx.accounts.risk_heatmap.key(),
+                    is_writable: true,
+                }],
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "reveal_risk_heatmap")]
+    pub fn reveal_risk_heatmap_callback(
+        ctx: Context<RevealRiskHeatmapCallback>,
+        output: SignedComputationOutputs<RevealRiskHeatmapOutput>,
+    ) -> Result<()> {
+        introspection::verify_callback(
+            &ctx.accounts.instructions_sysvar,
+            ctx.accounts.computation_account.key,
+            &ctx.accounts.mxe_account,
+            ctx.accounts.risk_heatmap.pending_computation,
+        )?;
+        let o = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(RevealRiskHeatmapOutput { field_0 }) => field_0,
+            Err(_) => {
+                log_error!("computation_aborted", circuit = "reveal_risk_heatmap");
+                let heatmap_key = ctx.accounts.risk_heatmap.key();
+                let heatmap = &mut ctx.accounts.risk_heatmap;
+                emit!(ComputationFailed {
+                    owner: heatmap_key,
+                    position_id: None,
+                    computation_offset: heatmap.pending_computation.take().unwrap_or_default(),
+                    kind: ComputationKind::RevealRiskHeatmap,
+                    timestamp: Clock::get()?.unix_timestamp,
+                });
+                return Ok(());
+            }
+        };
+
+        let now = Clock::get()?.unix_timestamp;
+        let heatmap = &mut ctx.accounts.risk_heatmap;
+        let sampled = heatmap.sampled;
+        for (bucket, at_risk) in heatmap.at_risk.iter_mut().enumerate() {
+            *at_risk = (sampled[bucket] >= MIN_HEATMAP_BUCKET_POSITIONS).then_some(o[bucket]);
+        }
+        heatmap.published_sampled = heatmap.sampled;
+        heatmap.published_at = now;
+        heatmap.sampled = [0; HEATMAP_BUCKETS];
+        heatmap.pending_computation = None;
+
+        emit!(RiskHeatmapPublished {
+            epoch: heatmap.epoch,
+            classes: heatmap.classes,
+            at_risk: heatmap.at_risk,
+            sampled: heatmap.published_sampled,
+            timestamp: now,
+        });
+        heatmap.epoch += 1;
+
+        Ok(())
+    }
+
+    // ─── Keeper Delegation ───
+
+    /// Authorizes `keeper` to submit health checks for the position until
+    /// `expiry_slot` (inclusive, `None` for no expiry), so an agent can run
+    /// without the owner's wallet key. Replaces any previous delegate;
+    /// `keeper = None` revokes.
+    pub fn delegate_keeper(
+        ctx: Context<DelegateKeeper>,
+        position_id: u32,
+        keeper: Option<Pubkey>,
+        expiry_slot: Option<u64>,
+    ) -> Result<()> {
+        let position = &mut ctx.accounts.position_acc;
+        position.keeper = keeper;
+        position.keeper_expiry_slot = keeper.and(expiry_slot);
+
+        log_info!("delegate_keeper", position_id = position_id);
+        emit!(KeeperDelegated {
+            owner: position.owner,
+            position_id,
+            keeper,
+            expiry_slot: position.keeper_expiry_slot,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // ─── Keeper Leases ───
+
+    /// Takes or renews the lease on `shard` of the signing keeper's positions
+    /// for `region`, until `duration_secs` from now. Keepers run in several
+    /// regions under one key renew every cycle as their heartbeat, and each
+    /// only checks positions in the shards it holds.
+    ///
+    /// The holder renews freely. Another region takes the shard once the
+    /// lease runs out, or at once if it has priority (a lower `region`), so a
+    /// recovered primary reclaims its shards from the secondary.
+    pub fn renew_keeper_lease(
+        ctx: Context<RenewKeeperLease>,
+        shard: u16,
+        region: u8,
+        duration_secs: i64,
+    ) -> Result<()> {
+        require!(
+            (1..=MAX_LEASE_SECS).contains(&duration_secs),
+            ErrorCode::InvalidLeaseDuration
+        );
+        let now = Clock::get()?.unix_timestamp;
+        let lease = &mut ctx.accounts.lease;
+        let created = lease.keeper == Pubkey::default();
+        require!(created || lease.may_take(region, now), ErrorCode::LeaseHeld);
+
+        if created || lease.region != region {
+            emit!(KeeperLeaseTaken {
+                keeper: ctx.accounts.keeper.key(),
+                shard,
+                region,
+                previous_region: (!created).then_some(lease.region),
+                timestamp: now,
+            });
+        }
+        lease.bump = ctx.bumps.lease;
+        lease.keeper = ctx.accounts.keeper.key();
+        lease.shard = shard;
+        lease.region = region;
+        lease.expires_at = now + duration_secs;
+        Ok(())
+    }
+
+    // ─── Keeper Registry ───
+
+    /// Registers the signer as a keeper, or adds to its bond, with
+    /// `lamports` more. The bond has to reach `MIN_KEEPER_BOND_LAMPORTS`;
+    /// bonding cancels an unbonding in progress.
+    pub fn register_keeper(ctx: Context<RegisterKeeper>, lamports: u64) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let keeper = &mut ctx.accounts.keeper_account;
+        if keeper.keeper == Pubkey::default() {
+            keeper.bump = ctx.bumps.keeper_account;
+            keeper.keeper = ctx.accounts.keeper.key();
+            keeper.registered_at = now;
+        }
+        keeper.unbonding_at = 0;
+
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.keeper.to_account_info(),
+                    to: keeper.to_account_info(),
+                },
+            ),
+            lamports,
+        )?;
+        let bond = Subscription::balance(&keeper.to_account_info())?;
+        require!(
+            bond >= MIN_KEEPER_BOND_LAMPORTS,
+            ErrorCode::KeeperBondTooSmall
+        );
+
+        log_info!("register_keeper", lamports = lamports, bond = bond);
+        emit!(KeeperBonded {
+            keeper: keeper.keeper,
+            lamports,
+            bond,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Starts withdrawing the signer's bond. The keeper stops counting as
+    /// registered at once, and may withdraw after `KEEPER_UNBONDING_SECS`.
+    pub fn unbond_keeper(ctx: Context<UnbondKeeper>) -> Result<()> {
+        let keeper = &mut ctx.accounts.keeper_account;
+        require!(keeper.unbonding_at == 0, ErrorCode::KeeperUnbonding);
+        let now = Clock::get()?.unix_timestamp;
+        keeper.unbonding_at = now + KEEPER_UNBONDING_SECS;
+
+        log_info!("unbond_keeper", withdrawable_at = keeper.unbonding_at);
+        emit!(KeeperUnbondingStarted {
+            keeper: keeper.keeper,
+            withdrawable_at: keeper.unbonding_at,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Closes the signer's registry account once unbonding is over,
+    /// returning the bond left after any slashing.
+    pub fn withdraw_keeper_bond(ctx: Context<WithdrawKeeperBond>) -> Result<()> {
+        let keeper = &ctx.accounts.keeper_account;
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            keeper.unbonding_at != 0 && now >= keeper.unbonding_at,
+            ErrorCode::KeeperBondLocked
+        );
+        let lamports = Subscription::balance(&keeper.to_account_info())?;
+
+        log_info!("withdraw_keeper_bond", lamports = lamports);
+        emit!(KeeperBondWithdrawn {
+            keeper: keeper.keeper,
+            lamports,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Slashes up to `lamports` of a keeper's bond to `recipient`, usually
+    /// the owner of the position the keeper misreported. Only the config's
+    /// admin may slash, on a dispute showing the keeper submitted wrong
+    /// position data, e.g. a check contradicted by a later check from the
+    /// protocol's own accounts. `evidence` is the hash of that dispute
+    /// record, kept in the event and the audit log. Unbonding keepers can
+    /// still be slashed.
+    pub fn slash_keeper(
+        ctx: Context<SlashKeeper>,
+        lamports: u64,
+        evidence: [u8; 32],
+    ) -> Result<()> {
+        let keeper = &mut ctx.accounts.keeper_account;
+        let bond = Subscription::balance(&keeper.to_account_info())?;
+        let slashed = lamports.min(bond);
+        keeper.sub_lamports(slashed)?;
+        ctx.accounts.recipient.add_lamports(slashed)?;
+        keeper.slashed_lamports = keeper.slashed_lamports.saturating_add(slashed);
+        keeper.slash_count += 1;
+        ctx.accounts.admin_audit_log.load_mut()?.record(
+            ctx.accounts.admin.key(),
+            AdminField::KeeperSlash,
+            &(keeper.keeper, bond).try_to_vec()?,
+            &(keeper.keeper, bond - slashed, evidence).try_to_vec()?,
+            Clock::get()?.slot,
+        );
+
+        log_info!("slash_keeper", keeper = keeper.keeper, slashed = slashed);
+        emit!(KeeperSlashed {
+            keeper: keeper.keeper,
+            lamports: slashed,
+            recipient: ctx.accounts.recipient.key(),
+            evidence,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Restricts who may submit data for the position to the owner and a
+    /// delegate bonded in the keeper registry, or lifts the restriction.
+    pub fn set_registered_keepers_only(
+        ctx: Context<SetRegisteredKeepersOnly>,
+        position_id: u32,
+        enabled: bool,
+    ) -> Result<()> {
+        ctx.accounts.position_acc.registered_keepers_only = enabled;
+        log_info!(
+            "set_registered_keepers_only",
+            position_id = position_id,
+            enabled = enabled
+        );
+        Ok(())
+    }
+
+    // ─── Monitoring Receipts ───
+
+    /// Creates the receipt mint and registers it: a Token-2022 mint with the
+    /// non-transferable extension, no decimals and the registry as its only
+    /// authority. Integrators read a wallet's balance of it as the number of
+    /// its positions under active monitoring. Admin only, once.
+    pub fn init_receipt_mint(ctx: Context<InitReceiptMint>) -> Result<()> {
+        let token_program = ctx.accounts.token_program.to_account_info();
+        let mint = ctx.accounts.receipt_mint.to_account_info();
+        let space = spl_token_2022::extension::ExtensionType::try_calculate_account_len::<
+            spl_token_2022::state::Mint,
+        >(&[spl_token_2022::extension::ExtensionType::NonTransferable])?;
+        let mint_seeds: &[&[u8]] = &[b"receipt_mint", &[ctx.bumps.receipt_mint]];
+        anchor_lang::system_program::create_account(
+            CpiContext::new_with_signer(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::CreateAccount {
+                    from: ctx.accounts.admin.to_account_info(),
+                    to: mint.clone(),
+                },
+                &[mint_seeds],
+            ),
+            Rent::get()?.minimum_balance(space),
+            space as u64,
+            token_program.key,
+        )?;
+        token_interface::non_transferable_mint_initialize(CpiContext::new(
+            token_program.clone(),
+            token_interface::NonTransferableMintInitialize {
+                token_program_id: token_program.clone(),
+                mint: mint.clone(),
+            },
+        ))?;
+        token_interface::initialize_mint2(
+            CpiContext::new(token_program, token_interface::InitializeMint2 { mint }),
+            0,
+            &ctx.accounts.receipt_registry.key(),
+            None,
+        )?;
+
+        let registry = &mut ctx.accounts.receipt_registry;
+        registry.bump = ctx.bumps.receipt_registry;
+        registry.mint = ctx.accounts.receipt_mint.key();
+        registry.outstanding = 0;
+        ctx.accounts.admin_audit_log.load_mut()?.record(
+            ctx.accounts.admin.key(),
+            AdminField::ReceiptMint,
+            Pubkey::default().as_ref(),
+            registry.mint.as_ref(),
+            Clock::get()?.slot,
+        );
+
+        log_info!("init_receipt_mint", mint = registry.mint);
+        emit!(ReceiptMintRegistered {
+            mint: registry.mint,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Mints the owner one monitoring receipt for an active position, into
+    /// their receipt mint token account. Opt-in: only the owner can ask for
+    /// one, and only one per position is outstanding.
+    pub fn mint_monitoring_receipt(
+        ctx: Context<MintMonitoringReceipt>,
+        position_id: u32,
+    ) -> Result<()> {
+        let position = &mut ctx.accounts.position_acc;
+        require!(position.is_active, ErrorCode::PositionInactive);
+        require!(!position.receipt_minted, ErrorCode::ReceiptAlreadyMinted);
+
+        let registry = &mut ctx.accounts.receipt_registry;
+        let registry_seeds: &[&[u8]] = &[b"receipt_registry", &[registry.bump]];
+        token_interface::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token_interface::MintTo {
+                    mint: ctx.accounts.receipt_mint.to_account_info(),
+                    to: ctx.accounts.receipt_account.to_account_info(),
+                    authority: registry.to_account_info(),
+                },
+                &[registry_seeds],
+            ),
+            1,
+        )?;
+        position.receipt_minted = true;
+        registry.outstanding += 1;
+
+        log_info!("mint_monitoring_receipt", position_id = position_id);
+        emit!(MonitoringReceiptMinted {
+            owner: position.owner,
+            position_id,
+            receipt_account: ctx.accounts.receipt_account.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Burns the position's monitoring receipt from the owner's account.
+    /// The owner can also burn a receipt with the token program directly;
+    /// once their account is empty or closed the position's receipt is
+    /// taken as burned, so it can still be deactivated, closed or handed
+    /// over.
+    pub fn burn_monitoring_receipt(
+        ctx: Context<BurnMonitoringReceipt>,
+        position_id: u32,
+    ) -> Result<()> {
+        let position = &mut ctx.accounts.position_acc;
+        require!(position.receipt_minted, ErrorCode::NoReceipt);
+
+        // Receipts share one mint, so an owner of several positions who
+        // burned one directly empties the account with the last of them
+        let receipt_info = &ctx.accounts.receipt_account;
+        let balance = if receipt_info.data_is_empty() {
+            0
+        } else {
+            require_keys_eq!(
+                *receipt_info.owner,
+                Token2022::id(),
+                anchor_lang::error::ErrorCode::AccountOwnedByWrongProgram
+            );
+            TokenAccount::try_deserialize(&mut &receipt_info.try_borrow_data()?[..])?.amount
+        };
+        if balance > 0 {
+            token_interface::burn(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    token_interface::Burn {
+                        mint: ctx.accounts.receipt_mint.to_account_info(),
+                        from: ctx.accounts.receipt_account.to_account_info(),
+                        authority: ctx.accounts.owner.to_account_info(),
+                    },
+                ),
+                1,
+            )?;
+        }
+        position.receipt_minted = false;
+        let registry = &mut ctx.accounts.receipt_registry;
+        registry.outstanding = registry.outstanding.saturating_sub(1);
+
+        log_info!("burn_monitoring_receipt", position_id = position_id);
+        emit!(MonitoringReceiptBurned {
+            owner: position.owner,
+            position_id,
+            receipt_account: ctx.accounts.receipt_account.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // ─── Ownership Transfer ───
+
+    /// Offers the position to `new_owner`, who takes it over with
+    /// `accept_position_transfer`. Until then the current owner keeps full
+    /// control, so a mistyped key can't lock the position. Replaces any
+    /// earlier offer; `None` withdraws it.
+    pub fn propose_position_transfer(
+        ctx: Context<ProposePositionTransfer>,
+        position_id: u32,
+        new_owner: Option<Pubkey>,
+    ) -> Result<()> {
+        let position = &mut ctx.accounts.position_acc;
+        // The receipt can't follow the position to its new owner
+        require!(
+            new_owner.is_none() || !position.receipt_minted,
+            ErrorCode::ReceiptOutstanding
+        );
+        position.pending_owner = new_owner;
+
+        log_info!("propose_position_transfer", position_id = position_id);
+        emit!(PositionTransferProposed {
+            owner: position.owner,
+            position_id,
+            new_owner,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Completes a transfer offered to the signer. The position keeps its
+    /// address, which is derived from `registrant`, and everything attached
+    /// to it; only the keeper delegation is revoked. Subscription funds go to
+    /// whoever owns the position when they are withdrawn. The position's
+    /// subscription and check bounty, when passed, are handed over too, so
+    /// refunds at close go to the new owner; a transfer that leaves one out
+    /// leaves it with the previous owner.
+    pub fn accept_position_transfer(
+        ctx: Context<AcceptPositionTransfer>,
+        position_id: u32,
+    ) -> Result<()> {
+        let position = &mut ctx.accounts.position_acc;
+        // A queued reveal to the previous owner's key would land after the
+        // handover.
+        require!(
+            position.pending_computation.is_none(),
+            ErrorCode::ComputationPending
+        );
+        require!(!position.receipt_minted, ErrorCode::ReceiptOutstanding);
+        let previous_owner = position.owner;
+        position.owner = ctx.accounts.new_owner.key();
+        position.pending_owner = None;
+        position.keeper = None;
+        position.keeper_expiry_slot = None;
+        if let Some(subscription) = &mut ctx.accounts.subscription {
+            subscription.owner = position.owner;
+        }
+        if let Some(bounty) = &mut ctx.accounts.check_bounty {
+            bounty.owner = position.owner;
+        }
+
+        log_info!("accept_position_transfer", position_id = position_id);
+        emit!(PositionTransferred {
+            previous_owner,
+            owner: position.owner,
+            registrant: position.registrant,
+            position_id,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // ─── Admin Audit Log ───
+
+    /// Creates the log every administrative change is recorded in. Anyone
+    /// may pay for it; the changes themselves fail until it exists.
+    pub fn init_admin_audit_log(ctx: Context<InitAdminAuditLog>) -> Result<()> {
+        ctx.accounts.admin_audit_log.load_init()?.bump = ctx.bumps.admin_audit_log;
+        log_info!("init_admin_audit_log");
+        Ok(())
+    }
+
+    // ─── Global Config ───
+
+    /// Creates the `GlobalConfig` with the default values and `admin`, who
+    /// may pause queueing and bump the circuit version. Only the program's
+    /// upgrade authority may initialize it, once.
+    pub fn initialize_config(ctx: Context<InitializeConfig>, admin: Pubkey) -> Result<()> {
+        let config = &mut ctx.accounts.global_config;
+        config.bump = ctx.bumps.global_config;
+        config.max_staleness_secs = DEFAULT_MAX_STALENESS_SECS;
+        config.update_delay_secs = DEFAULT_CONFIG_UPDATE_DELAY_SECS;
+        config.pending_update = None;
+        config.config_version = 0;
+        config.risk_model = RiskModel::BUILT_IN;
+        config.admin = admin;
+        config.paused = false;
+        config.circuit_version = 0;
+
+        log_info!("initialize_config", admin = admin);
+        emit!(ConfigInitialized {
+            authority: ctx.accounts.authority.key(),
+            admin,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Stops (or resumes) queueing computations. Callbacks of computations
+    /// already queued still land. Only the config's admin may pause.
+    pub fn set_paused(ctx: Context<AdminConfig>, paused: bool) -> Result<()> {
+        let config = &mut ctx.accounts.global_config;
+        ctx.accounts.admin_audit_log.load_mut()?.record(
+            ctx.accounts.admin.key(),
+            AdminField::Paused,
+            &[config.paused as u8],
+            &[paused as u8],
+            Clock::get()?.slot,
+        );
+        config.paused = paused;
+
+        log_info!("set_paused", paused = paused);
+        emit!(PauseSet {
+            admin: ctx.accounts.admin.key(),
+            paused,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Records that the deployed circuits are now `circuit_version`, after
+    /// their computation definitions were upgraded. Risk states computed
+    /// from then on carry it, so older ones can be found and migrated. Only
+    /// the config's admin may set it, and only upwards.
+    pub fn set_circuit_version(ctx: Context<AdminConfig>, circuit_version: u32) -> Result<()> {
+        let config = &mut ctx.accounts.global_config;
+        require!(
+            circuit_version > config.circuit_version,
+            ErrorCode::InvalidCircuitVersion
+        );
+        ctx.accounts.admin_audit_log.load_mut()?.record(
+            ctx.accounts.admin.key(),
+            AdminField::CircuitVersion,
+            &config.circuit_version.to_le_bytes(),
+            &circuit_version.to_le_bytes(),
+            Clock::get()?.slot,
+        );
+        config.circuit_version = circuit_version;
+
+        log_info!("set_circuit_version", circuit_version = circuit_version);
+        emit!(CircuitVersionSet {
+            admin: ctx.accounts.admin.key(),
+            circuit_version,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Announces new `GlobalConfig` values: how old, in seconds since its
+    /// last check, a risk state may be for `reveal_risk` on positions without
+    /// their own limit, and the delay of later changes. They can be applied
+    /// once the current delay has passed, so keepers and users see a change
+    /// coming. A new proposal replaces a pending one. Only the program's
+    /// upgrade authority may propose.
+    pub fn propose_config_update(
+        ctx: Context<ProposeConfigUpdate>,
+        max_staleness_secs: i64,
+        update_delay_secs: i64,
+    ) -> Result<()> {
+        require!(max_staleness_secs > 0, ErrorCode::InvalidStaleness);
+        require!(update_delay_secs > 0, ErrorCode::InvalidUpdateDelay);
+        let config = &mut ctx.accounts.global_config;
+        let now = Clock::get()?.unix_timestamp;
+        let apply_after = now.saturating_add(config.update_delay_secs);
+        config.pending_update = Some(ConfigUpdate {
+            max_staleness_secs,
+            update_delay_secs,
+            apply_after,
+        });
+
+        log_info!(
+            "propose_config_update",
+            max_staleness_secs = max_staleness_secs,
+            update_delay_secs = update_delay_secs,
+            apply_after = apply_after
+        );
+        emit!(ConfigUpdateProposed {
+            authority: ctx.accounts.authority.key(),
+            max_staleness_secs,
+            update_delay_secs,
+            apply_after,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Applies the pending `GlobalConfig` change once its delay has passed.
+    /// Only the program's upgrade authority may apply it.
+    pub fn apply_config_update(ctx: Context<ApplyConfigUpdate>) -> Result<()> {
+        let config = &mut ctx.accounts.global_config;
+        let update = config
+            .pending_update
+            .ok_or(ErrorCode::NoPendingConfigUpdate)?;
+        let now = Clock::get()?.unix_timestamp;
+        require!(now >= update.apply_after, ErrorCode::ConfigUpdateNotReady);
+
+        let slot = Clock::get()?.slot;
+        let mut log = ctx.accounts.admin_audit_log.load_mut()?;
+        let authority = ctx.accounts.authority.key();
+        if update.max_staleness_secs != config.max_staleness_secs {
+            log.record(
+                authority,
+                AdminField::DefaultMaxStaleness,
+                &config.max_staleness_secs.to_le_bytes(),
+                &update.max_staleness_secs.to_le_bytes(),
+                slot,
+            );
+        }
+        if update.update_delay_secs != config.update_delay_secs {
+            log.record(
+                authority,
+                AdminField::ConfigUpdateDelay,
+                &config.update_delay_secs.to_le_bytes(),
+                &update.update_delay_secs.to_le_bytes(),
+                slot,
+            );
+        }
+        config.max_staleness_secs = update.max_staleness_secs;
+        config.update_delay_secs = update.update_delay_secs;
+        config.pending_update = None;
+
+        log_info!(
+            "apply_config_update",
+            max_staleness_secs = update.max_staleness_secs,
+            update_delay_secs = update.update_delay_secs
+        );
+        emit!(GlobalConfigUpdated {
+            max_staleness_secs: update.max_staleness_secs,
+            update_delay_secs: update.update_delay_secs,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Publishes the next version of the risk model positions without an
+    /// alert config are graded by. New registrations are pinned to it;
+    /// existing positions keep the version they are pinned to until their
+    /// owner accepts the new one, so it applies without a timelock. Only the
+    /// program's upgrade authority may publish.
+    pub fn publish_risk_model(ctx: Context<PublishRiskModel>, risk_model: RiskModel) -> Result<()> {
+        require!(risk_model.is_valid(), ErrorCode::InvalidRiskModel);
+        let config = &mut ctx.accounts.global_config;
+        let config_version = config.config_version + 1;
+        ctx.accounts.admin_audit_log.load_mut()?.record(
+            ctx.accounts.authority.key(),
+            AdminField::RiskModel,
+            &(config.config_version, config.risk_model).try_to_vec()?,
+            &(config_version, risk_model).try_to_vec()?,
+            Clock::get()?.slot,
+        );
+        config.config_version = config_version;
+        config.risk_model = risk_model;
+
+        log_info!("publish_risk_model", config_version = config_version);
+        emit!(RiskModelPublished {
+            config_version,
+            risk_model,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Pins the position to risk model `config_version`, which has to be the
+    /// latest published; the owner names it so a model published meanwhile
+    /// isn't accepted unseen.
+    pub fn accept_config_version(
+        ctx: Context<AcceptConfigVersion>,
+        position_id: u32,
+        config_version: u32,
+    ) -> Result<()> {
+        let config = &ctx.accounts.global_config;
+        require!(
+            config_version == config.config_version,
+            ErrorCode::ConfigVersionMismatch
+        );
+        let position = &mut ctx.accounts.position_acc;
+        position.config_version = config_version;
+        position.risk_model = config.risk_model;
+
+        log_info!(
+            "accept_config_version",
+            position_id = position_id,
+            config_version = config_version
+        );
+        emit!(ConfigVersionAccepted {
+            owner: position.owner,
+            position_id,
+            config_version,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // ─── Subscriptions ───
+
+    /// Sets the fee a delegated keeper earns per `check_health`, in lamports,
+    /// and the share of it in basis points that goes to the referrer of a
+    /// referred position. Only the program's upgrade authority may change
+    /// them.
+    pub fn set_monitoring_plan(
+        ctx: Context<SetMonitoringPlan>,
+        fee_per_check: u64,
+        referral_share_bps: u16,
+    ) -> Result<()> {
+        require!(
+            referral_share_bps <= 10_000,
+            ErrorCode::InvalidReferralShare
+        );
+        let plan = &mut ctx.accounts.monitoring_plan;
+        let mut log = ctx.accounts.admin_audit_log.load_mut()?;
+        let slot = Clock::get()?.slot;
+        let authority = ctx.accounts.authority.key();
+        log.record(
+            authority,
+            AdminField::MonitoringPlanFee,
+            &plan.fee_per_check.to_le_bytes(),
+            &fee_per_check.to_le_bytes(),
+            slot,
+        );
+        log.record(
+            authority,
+            AdminField::MonitoringPlanReferralShare,
+            &plan.referral_share_bps.to_le_bytes(),
+            &referral_share_bps.to_le_bytes(),
+            slot,
+        );
+        plan.bump = ctx.bumps.monitoring_plan;
+        plan.fee_per_check = fee_per_check;
+        plan.referral_share_bps = referral_share_bps;
+
+        log_info!(
+            "set_monitoring_plan",
+            fee_per_check = fee_per_check,
+            referral_share_bps = referral_share_bps
+        );
+        emit!(MonitoringPlanUpdated {
+            fee_per_check,
+            referral_share_bps,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Escrows `lamports` for the position's keeper fees. Anyone may fund a
+    /// position; only its owner can withdraw.
+    pub fn fund_subscription(ctx: Context<FundSubscription>, lamports: u64) -> Result<()> {
+        let position = &ctx.accounts.position_acc;
+        let subscription = &mut ctx.accounts.subscription;
+        subscription.bump = ctx.bumps.subscription;
+        subscription.position = position.key();
+        subscription.owner = position.owner;
+
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.funder.to_account_info(),
+                    to: subscription.to_account_info(),
+                },
+            ),
+            lamports,
+        )?;
+
+        log_info!(
+            "fund_subscription",
+            position_id = position.position_id,
+            lamports = lamports
+        );
+        emit!(SubscriptionFunded {
+            owner: position.owner,
+            position_id: position.position_id,
+            funder: ctx.accounts.funder.key(),
+            lamports,
+            balance: Subscription::balance(&subscription.to_account_info())?,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Returns `lamports` of the escrowed balance to the owner. The account
+    /// itself stays rent-exempt, and trial credits stay with it.
+    pub fn withdraw_subscription(
+        ctx: Context<WithdrawSubscription>,
+        position_id: u32,
+        lamports: u64,
+    ) -> Result<()> {
+        let subscription = &mut ctx.accounts.subscription;
+        if ctx.accounts.position_acc.pending_computation.is_none() {
+            // Held for a check that was cancelled without its subscription
+            subscription.release_fee();
+        }
+        require!(
+            Subscription::owner_balance(subscription)? >= lamports,
+            ErrorCode::InsufficientSubscription
+        );
+        subscription.sub_lamports(lamports)?;
+        ctx.accounts.owner.add_lamports(lamports)?;
+
+        log_info!(
+            "withdraw_subscription",
+            position_id = position_id,
+            lamports = lamports
+        );
+        emit!(SubscriptionWithdrawn {
+            owner: ctx.accounts.owner.key(),
+            position_id,
+            lamports,
+            balance: Subscription::balance(&subscription.to_account_info())?,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Escrows `lamports` of trial credits for the position, usable for keeper
+    /// fees for `duration_secs`. The owner can't withdraw them; once they
+    /// expire the operator takes back what's left with
+    /// `sweep_expired_trial_credits`. Only the program's upgrade authority
+    /// may grant them.
+    pub fn grant_trial_credits(
+        ctx: Context<GrantTrialCredits>,
+        lamports: u64,
+        duration_secs: i64,
+    ) -> Result<()> {
+        require!(duration_secs > 0, ErrorCode::InvalidTrialDuration);
+        let position = &ctx.accounts.position_acc;
+        let subscription = &mut ctx.accounts.subscription;
+        subscription.bump = ctx.bumps.subscription;
+        subscription.position = position.key();
+        subscription.owner = position.owner;
+
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.authority.to_account_info(),
+                    to: subscription.to_account_info(),
+                },
+            ),
+            lamports,
+        )?;
+        let now = Clock::get()?.unix_timestamp;
+        subscription.trial_lamports = subscription.trial_lamports.saturating_add(lamports);
+        subscription.trial_expires_at = now.saturating_add(duration_secs);
+
+        log_info!(
+            "grant_trial_credits",
+            position_id = position.position_id,
+            lamports = lamports
+        );
+        emit!(TrialCreditsGranted {
+            owner: position.owner,
+            position_id: position.position_id,
+            lamports,
+            expires_at: subscription.trial_expires_at,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Operator crank returning a subscription's expired trial credits to the
+    /// upgrade authority. When the position has been closed, the owner's
+    /// remaining credits and the account's rent go back to the owner and
+    /// the subscription is closed.
+    pub fn sweep_expired_trial_credits(ctx: Context<SweepExpiredTrialCredits>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let subscription = &mut ctx.accounts.subscription;
+        require!(
+            subscription.trial_expires_at <= now,
+            ErrorCode::TrialNotExpired
+        );
+
+        let balance = Subscription::available(subscription)?;
+        let swept = subscription.trial_lamports.min(balance);
+        subscription.sub_lamports(swept)?;
+        ctx.accounts.authority.add_lamports(swept)?;
+        subscription.trial_lamports = 0;
+
+        let position_closed = ctx.accounts.position_acc.data_is_empty();
+        let refunded = if position_closed {
+            let refunded = Subscription::owner_balance(subscription)?;
+            subscription.close(ctx.accounts.owner.to_account_info())?;
+            refunded
+        } else {
+            0
+        };
+
+        log_info!(
+            "sweep_expired_trial_credits",
+            swept = swept,
+            refunded = refunded
+        );
+        emit!(TrialCreditsSwept {
+            owner: subscription.owner,
+            position: subscription.position,
+            swept,
+            refunded,
+            position_closed,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    // ─── Check Bounties ───
+
+    /// Sets the bounty a keeper earns for each check of the position that
+    /// lands and escrows `lamports` more for it. The bounty comes on top of
+    /// the plan's fee, from its own escrow: `check_health` records the payer
+    /// and `check_position_health_callback` pays it, as long as the escrow
+    /// covers the bounty. Checks the owner submits earn nothing, and who may
+    /// check is still up to `delegate_keeper`; `CheckBountyFunded` is how
+    /// keepers find positions worth offering to.
+    pub fn fund_check_bounty(
+        ctx: Context<FundCheckBounty>,
+        position_id: u32,
+        check_bounty_lamports: u64,
+        lamports: u64,
+    ) -> Result<()> {
+        require!(check_bounty_lamports > 0, ErrorCode::InvalidCheckBounty);
+        let position = &ctx.accounts.position_acc;
+        let bounty = &mut ctx.accounts.check_bounty;
+        bounty.bump = ctx.bumps.check_bounty;
+        bounty.position = position.key();
+        bounty.owner = position.owner;
+        bounty.check_bounty_lamports = check_bounty_lamports;
+
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.owner.to_account_info(),
+                    to: bounty.to_account_info(),
+                },
+            ),
+            lamports,
+        )?;
+
+        log_info!(
+            "fund_check_bounty",
+            position_id = position_id,
+            check_bounty_lamports = check_bounty_lamports,
+            lamports = lamports
+        );
+        emit!(CheckBountyFunded {
+            owner: position.owner,
+            position_id,
+            check_bounty_lamports,
+            lamports,
+            balance: Subscription::balance(&bounty.to_account_info())?,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Closes the bounty escrow and returns what's left to the owner. Not
+    /// while a check is pending, whose callback may pay from it.
+    pub fn close_check_bounty(ctx: Context<CloseCheckBounty>, position_id: u32) -> Result<()> {
+        require!(
+            ctx.accounts.position_acc.pending_computation.is_none(),
+            ErrorCode::ComputationPending
+        );
+        let refunded = Subscription::balance(&ctx.accounts.check_bounty.to_account_info())?;
+
+        log_info!(
+            "close_check_bounty",
+            position_id = position_id,
+            refunded = refunded
+        );
+        emit!(CheckBountyClosed {
+            owner: ctx.accounts.owner.key(),
+            position_id,
+            refunded,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // ─── Heartbeat Deadline ───
+
+    /// Sets how long the position may go without a check before anyone may
+    /// flag its monitoring as lapsed with `flag_stale_position`. 0 removes
+    /// the deadline and any lapse already flagged.
+    pub fn set_heartbeat_deadline(
+        ctx: Context<SetHeartbeatDeadline>,
+        position_id: u32,
+        heartbeat_deadline_secs: u32,
+    ) -> Result<()> {
+        let position = &mut ctx.accounts.position_acc;
+        position.heartbeat_deadline_secs = heartbeat_deadline_secs;
+        if heartbeat_deadline_secs == 0 {
+            position.monitoring_lapsed = false;
+        }
+        log_info!(
+            "set_heartbeat_deadline",
+            position_id = position_id,
+            heartbeat_deadline_secs = heartbeat_deadline_secs
+        );
+        Ok(())
+    }
+
+    /// Records that the owner or its keeper is monitoring the position
+    /// without checking it, e.g. because it was checked too recently or its
+    /// protocol is in a blackout. Costs no computation. Updates `last_seen`
+    /// but not `last_check`, so "nothing to do" reads differently from "the
+    /// keeper is down", and holds off `flag_stale_position`.
+    pub fn heartbeat(ctx: Context<Heartbeat>, position_id: u32) -> Result<()> {
+        let clock = Clock::get()?;
+        let position = &mut ctx.accounts.position_acc;
+        require!(position.is_active, ErrorCode::PositionInactive);
+        require!(
+            position.may_check(&ctx.accounts.signer.key(), clock.slot),
+            ErrorCode::UnauthorizedKeeper
+        );
+        position.record_seen(clock.unix_timestamp);
+        log_debug!("heartbeat", position_id = position_id);
+        Ok(())
+    }
+
+    /// Flags the position's monitoring as lapsed once its heartbeat deadline
+    /// has passed without a check or heartbeat: a dead man's switch for the
+    /// keeper that anyone may pull. With the position's check bounty escrow, the caller
+    /// earns one bounty for it if the escrow covers it. A lapse is flagged
+    /// once; the next check or heartbeat clears it.
+    pub fn flag_stale_position(ctx: Context<FlagStalePosition>, position_id: u32) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let position = &mut ctx.accounts.position_acc;
+        require!(position.is_active, ErrorCode::PositionInactive);
+        require!(
+            !position.monitoring_lapsed,
+            ErrorCode::MonitoringLapseFlagged
+        );
+        require!(
+            position.missed_heartbeat(now),
+            ErrorCode::HeartbeatDeadlineNotMissed
+        );
+        position.monitoring_lapsed = true;
+
+        let mut bounty = 0;
+        if let Some(escrow) = &ctx.accounts.check_bounty {
+            if let Some(lamports) = CheckBounty::payable(escrow)? {
+                escrow.sub_lamports(lamports)?;
+                ctx.accounts.caller.add_lamports(lamports)?;
+                bounty = lamports;
+            }
+        }
+
+        log_info!(
+            "flag_stale_position",
+            position_id = position_id,
+            bounty = bounty
+        );
+        emit!(MonitoringLapsed {
+            owner: position.owner,
+            position_id,
+            last_check: position.last_check,
+            last_seen: position.last_seen,
+            heartbeat_deadline_secs: position.heartbeat_deadline_secs,
+            flagged_by: ctx.accounts.caller.key(),
+            bounty,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    // ─── Coverage Queries ───
+
+    /// Answers whether `owner`'s position is under active monitoring with a
+    /// check at most `max_check_age_secs` old, as Anchor return data, for
+    /// programs that gate a counterparty on it through CPI. Changes nothing
+    /// and fails only for an account that isn't the position; programs that
+    /// read the account directly can use [`PositionAccount::is_monitored`].
+    pub fn is_monitored(
+        ctx: Context<IsMonitored>,
+        _position_id: u32,
+        owner: Pubkey,
+        max_check_age_secs: u32,
+    ) -> Result<bool> {
+        let now = Clock::get()?.unix_timestamp;
+        Ok(ctx
+            .accounts
+            .position_acc
+            .is_monitored(&owner, now, max_check_age_secs))
+    }
+
+    // ─── Auto Actions ───
+
+    /// Pre-authorizes an instruction that runs when a reveal comes back
+    /// critical, e.g. a deleverage or withdraw: `reveal_risk_callback` makes
+    /// it due and `execute_auto_action` runs it. It is signed by the config
+    /// account itself, so the target protocol must accept
+    /// [`AutoActionConfig`]'s address as the position's delegate. That PDA is
+    /// per position, unlike the Arcium sign PDA every computation shares.
+    /// Replaces any previous template.
+    pub fn set_auto_action(
+        ctx: Context<SetAutoAction>,
+        position_id: u32,
+        program_id: Pubkey,
+        accounts: Vec<AutoActionAccount>,
+        data: Vec<u8>,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.auto_action;
+        require!(
+            AutoActionConfig::is_valid_template(&config.key(), &program_id, &accounts, &data),
+            ErrorCode::InvalidAutoAction
+        );
+        config.bump = ctx.bumps.auto_action;
+        config.position = ctx.accounts.position_acc.key();
+        config.owner = ctx.accounts.owner.key();
+        config.program_id = program_id;
+        config.accounts = accounts;
+        config.data = data;
+
+        log_info!("set_auto_action", position_id = position_id);
+        emit!(AutoActionUpdated {
+            owner: config.owner,
+            position_id,
+            program_id: Some(program_id),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Removes the position's auto action; critical reveals only emit
+    /// `ActionRequired` again.
+    pub fn clear_auto_action(ctx: Context<ClearAutoAction>, position_id: u32) -> Result<()> {
+        log_info!("clear_auto_action", position_id = position_id);
+        emit!(AutoActionUpdated {
+            owner: ctx.accounts.owner.key(),
+            position_id,
+            program_id: None,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Runs the position's auto action once a critical reveal made it due
+    /// (`AutoActionDue`). Anyone may send it; the keeper does. The target
+    /// program and the template's accounts follow as remaining accounts. If
+    /// the target program fails, only this transaction fails and the action
+    /// stays due.
+    pub fn execute_auto_action(ctx: Context<ExecuteAutoAction>, position_id: u32) -> Result<()> {
+        let action = &mut ctx.accounts.auto_action;
+        let severity = action.due_severity;
+        require!(severity > 0, ErrorCode::AutoActionNotDue);
+        // remaining_accounts: target program, then the template's accounts
+        let infos = ctx.remaining_accounts;
+        let matches_template = infos.len() == action.accounts.len() + 1
+            && infos[0].key() == action.program_id
+            && infos[1..]
+                .iter()
+                .zip(&action.accounts)
+                .all(|(info, account)| info.key() == account.pubkey);
+        require!(matches_template, ErrorCode::InvalidAutoAction);
+        action.due_severity = 0;
+        action.exit(&crate::ID)?;
+
+        let position_key = ctx.accounts.position_acc.key();
+        anchor_lang::solana_program::program::invoke_signed(
+            &action.instruction(),
+            infos,
+            &[&[b"auto_action", position_key.as_ref(), &[action.bump]]],
+        )?;
+
+        log_info!("auto_action", position_id = position_id);
+        emit!(AutoActionExecuted {
+            owner: ctx.accounts.position_acc.owner,
+            position_id,
+            program_id: action.program_id,
+            severity,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Records an estimate of the liquidation penalty a protective step
+    /// saved the owner, for ROI reporting: `penalty_bps` of the lower bound
+    /// of the position's `size_bucket`. The keeper works both out off-chain,
+    /// from the protocol's liquidation parameters and the position data it
+    /// already reads; only the coarse bucket ends up on-chain. The owner or
+    /// its keeper may call this, and the position is left untouched.
+    pub fn record_value_protected(
+        ctx: Context<RecordValueProtected>,
+        position_id: u32,
+        trigger: ProtectionTrigger,
+        size_bucket: SizeBucket,
+        penalty_bps: u16,
+    ) -> Result<()> {
+        require!(
+            penalty_bps > 0 && penalty_bps <= 10_000,
+            ErrorCode::InvalidLiquidationPenalty
+        );
+        let clock = Clock::get()?;
+        let position = &ctx.accounts.position_acc;
+        require!(
+            position.may_check(&ctx.accounts.signer.key(), clock.slot),
+            ErrorCode::UnauthorizedKeeper
+        );
+        require!(
+            position
+                .size_bucket
+                .is_none_or(|revealed| revealed == size_bucket),
+            ErrorCode::SizeBucketMismatch
+        );
+
+        let value_protected = size_bucket.floor_usd_cents() * penalty_bps as u64 / 10_000;
+        log_info!(
+            "record_value_protected",
+            position_id = position_id,
+            value_protected = value_protected
+        );
+        emit!(ValueProtected {
+            owner: position.owner,
+            position_id,
+            trigger,
+            size_bucket,
+            penalty_bps,
+            value_protected,
+            recorded_by: ctx.accounts.signer.key(),
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // ─── Stop Loss ───
+
+    /// Stores the owner's encrypted stop-loss order
+    /// (`[trigger_health_factor_bps, action, max_slippage_bps]`, encrypted
+    /// to the MXE under `encryption_pubkey` and `encryption_nonce`) and
+    /// re-arms it. With an `executor`, `execute_stop_loss` sends the order
+    /// to that program over `executor_accounts` once it fires, signed by
+    /// the `StopLossAccount` like an auto action; without one only
+    /// `StopLossTriggered` is emitted, for the owner's bot to act on.
+    #[allow(clippy::too_many_arguments)]
+    pub fn set_stop_loss(
+        ctx: Context<SetStopLoss>,
+        position_id: u32,
+        encrypted_config: [[u8; 32]; 3],
+        encryption_pubkey: [u8; 32],
+        encryption_nonce: u128,
+        executor: Option<Pubkey>,
+        executor_accounts: Vec<AutoActionAccount>,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.position_acc.pending_computation.is_none(),
+            ErrorCode::ComputationPending
+        );
+        let stop_loss = &mut ctx.accounts.stop_loss;
+        let valid_executor = match &executor {
+            Some(program_id) => AutoActionConfig::is_valid_template(
+                &stop_loss.key(),
+                program_id,
+                &executor_accounts,
+                &[],
+            ),
+            None => executor_accounts.is_empty(),
+        };
+        require!(valid_executor, ErrorCode::InvalidStopLossExecutor);
+        stop_loss.bump = ctx.bumps.stop_loss;
+        stop_loss.position = ctx.accounts.position_acc.key();
+        stop_loss.owner = ctx.accounts.owner.key();
+        stop_loss.config = encrypted_config;
+        stop_loss.encryption_pubkey = encryption_pubkey;
+        stop_loss.nonce = encryption_nonce;
+        stop_loss.executor = executor;
+        stop_loss.executor_accounts = executor_accounts;
+        stop_loss.triggered_at = 0;
+        stop_loss.action = 0;
+        stop_loss.max_slippage_bps = 0;
+        stop_loss.executed_at = 0;
+
+        log_info!("set_stop_loss", position_id = position_id);
+        emit!(StopLossUpdated {
+            owner: stop_loss.owner,
+            position_id,
+            active: true,
+            executor,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Removes the position's stop-loss order, refunding its rent.
+    pub fn clear_stop_loss(ctx: Context<ClearStopLoss>, position_id: u32) -> Result<()> {
+        require!(
+            ctx.accounts.position_acc.pending_computation.is_none(),
+            ErrorCode::ComputationPending
+        );
+        log_info!("clear_stop_loss", position_id = position_id);
+        emit!(StopLossUpdated {
+            owner: ctx.accounts.owner.key(),
+            position_id,
+            active: false,
+            executor: None,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Compares the position's stored health factor to its stop-loss
+    /// trigger via MPC. Nothing is revealed unless the order fires, in
+    /// which case the callback emits `StopLossTriggered` and keeps the
+    /// revealed order for `execute_stop_loss`, once: the order stays
+    /// triggered until `set_stop_loss` re-arms it. The owner or its keeper may evaluate; run
+    /// `compute_health_factor` first.
+    pub fn evaluate_stop_loss(
+        ctx: Context<EvaluateStopLoss>,
+        computation_offset: u64,
+        position_id: u32,
+    ) -> Result<()> {
+        let circuit_version = GlobalConfig::require_active(&ctx.accounts.global_config)?;
+        let clock = Clock::get()?;
+        let position = &ctx.accounts.position_acc;
+        require!(
+            position.may_check(&ctx.accounts.payer.key(), clock.slot),
+            ErrorCode::UnauthorizedKeeper
+        );
+        require!(
+            position.health_factor_at > 0,
+            ErrorCode::HealthFactorNotComputed
+        );
+        let stop_loss = &ctx.accounts.stop_loss;
+        require!(
+            stop_loss.triggered_at == 0,
+            ErrorCode::StopLossAlreadyTriggered
+        );
+
+        log_info!("evaluate_stop_loss", position_id = position_id);
+
+        let args = ArgBuilder::new()
+            .x25519_pubkey(stop_loss.encryption_pubkey)
+            .plaintext_u128(stop_loss.nonce)
+            .encrypted_u64(stop_loss.config[0])
+            .encrypted_u64(stop_loss.config[1])
+            .encrypted_u64(stop_loss.config[2])
+            .plaintext_u128(position.health_factor_nonce)
+            .account(position.key(), PositionAccount::HEALTH_FACTOR_OFFSET, 32)
+            .build();
+
+        let callback_accounts = [
+            CallbackAccount {
+                pubkey: position.key(),
+                is_writable: true,
+            },
+            CallbackAccount {
+                pubkey: stop_loss.key(),
+                is_writable: true,
+            },
+        ];
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        ctx.accounts.position_acc.begin_computation(
+            computation_offset,
+            clock.unix_timestamp,
+            circuit_version,
+        )?;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![EvaluateStopLossCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &callback_accounts,
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "evaluate_stop_loss")]
+    pub fn evaluate_stop_loss_callback(
+        ctx: Context<EvaluateStopLossCallback>,
+        output: SignedComputationOutputs<EvaluateStopLossOutput>,
+    ) -> Result<()> {
+        introspection::verify_callback(
+            &ctx.accounts.instructions_sysvar,
+            ctx.accounts.computation_account.key,
+            &ctx.accounts.mxe_account,
+            ctx.accounts.position_acc.pending_computation,
+        )?;
+        let [fired, action, max_slippage_bps] = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(EvaluateStopLossOutput { field_0 }) => field_0,
+            Err(_) => {
+                log_error!("computation_aborted", circuit = "evaluate_stop_loss");
+                let now = Clock::get()?.unix_timestamp;
+                let position = &mut ctx.accounts.position_acc;
+                emit!(position.fail_computation(ComputationKind::EvaluateStopLoss, now));
+                return Ok(());
+            }
+        };
+
+        let now = Clock::get()?.unix_timestamp;
+        ctx.accounts.position_acc.pending_computation = None;
+        ctx.accounts.stop_loss.last_evaluated_at = now;
+        if fired == 0 {
+            return Ok(());
+        }
+
+        // The executor runs through `execute_stop_loss` in a transaction of
+        // its own: a failing executor would otherwise fail this callback,
+        // losing the order and leaving the position pending.
+        let stop_loss = &mut ctx.accounts.stop_loss;
+        stop_loss.triggered_at = now;
+        stop_loss.action = action;
+        stop_loss.max_slippage_bps = max_slippage_bps;
+        let position = &ctx.accounts.position_acc;
+        log_info!("stop_loss_triggered", position_id = position.position_id);
+        emit!(StopLossTriggered {
+            owner: position.owner,
+            position_id: position.position_id,
+            position: position.key(),
+            action,
+            max_slippage_bps,
+            health_factor_at: position.health_factor_at,
+            executor: stop_loss.executor,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Sends the position's fired stop-loss order to its executor, once.
+    /// Anyone may send it; the keeper does. The executor and its accounts
+    /// follow as remaining accounts. If the executor fails, only this
+    /// transaction fails and the order stays due.
+    pub fn execute_stop_loss(ctx: Context<ExecuteStopLoss>, position_id: u32) -> Result<()> {
+        let stop_loss = &mut ctx.accounts.stop_loss;
+        let executor = stop_loss.executor.filter(|_| stop_loss.is_due());
+        let executor = executor.ok_or(ErrorCode::StopLossNotDue)?;
+        // remaining_accounts: executor, then its accounts
+        let infos = ctx.remaining_accounts;
+        let matches_template = infos.len() == stop_loss.executor_accounts.len() + 1
+            && infos[0].key() == executor
+            && infos[1..]
+                .iter()
+                .zip(&stop_loss.executor_accounts)
+                .all(|(info, account)| info.key() == account.pubkey);
+        require!(matches_template, ErrorCode::InvalidStopLossExecutor);
+        let now = Clock::get()?.unix_timestamp;
+        stop_loss.executed_at = now;
+        stop_loss.exit(&crate::ID)?;
+
+        let position_key = ctx.accounts.position_acc.key();
+        anchor_lang::solana_program::program::invoke_signed(
+            &stop_loss.instruction(stop_loss.action, stop_loss.max_slippage_bps),
+            infos,
+            &[&[b"stop_loss", position_key.as_ref(), &[stop_loss.bump]]],
+        )?;
+
+        log_info!("stop_loss_executed", position_id = position_id);
+        emit!(StopLossExecuted {
+            owner: ctx.accounts.position_acc.owner,
+            position_id,
+            executor,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    // ─── Action Memo ───
+
+    /// Stores the owner's action memo, a plan the owner wrote for when the
+    /// position turns critical, encrypted to the owner's own key so only
+    /// the owner can read it. Risk reveals asked to attach it copy it into
+    /// the `RevealInbox` next to the result, for the wallet to show both.
+    pub fn set_action_memo(
+        ctx: Context<SetActionMemo>,
+        position_id: u32,
+        memo: EncryptedMemo,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.position_acc.pending_computation.is_none(),
+            ErrorCode::ComputationPending
+        );
+        require!(
+            !memo.ciphertexts.is_empty() && memo.ciphertexts.len() <= MAX_MEMO_CIPHERTEXTS,
+            ErrorCode::InvalidActionMemo
+        );
+        let now = Clock::get()?.unix_timestamp;
+        let action_memo = &mut ctx.accounts.action_memo;
+        action_memo.bump = ctx.bumps.action_memo;
+        action_memo.position = ctx.accounts.position_acc.key();
+        action_memo.owner = ctx.accounts.owner.key();
+        action_memo.memo = memo;
+        action_memo.updated_at = now;
+
+        log_info!("set_action_memo", position_id = position_id);
+        emit!(ActionMemoUpdated {
+            owner: action_memo.owner,
+            position_id,
+            active: true,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Removes the position's action memo, refunding its rent.
+    pub fn clear_action_memo(ctx: Context<ClearActionMemo>, position_id: u32) -> Result<()> {
+        require!(
+            ctx.accounts.position_acc.pending_computation.is_none(),
+            ErrorCode::ComputationPending
+        );
+        log_info!("clear_action_memo", position_id = position_id);
+        emit!(ActionMemoUpdated {
+            owner: ctx.accounts.owner.key(),
+            position_id,
+            active: false,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // ─── Size Buckets ───
+
+    /// Allows or forbids revealing the position's size bucket for
+    /// value-protected reporting. Forbidding it also forgets the bucket
+    /// revealed so far.
+    pub fn set_size_disclosure(
+        ctx: Context<SetSizeDisclosure>,
+        position_id: u32,
+        allowed: bool,
+    ) -> Result<()> {
+        let position = &mut ctx.accounts.position_acc;
+        position.size_disclosure_allowed = allowed;
+        if !allowed {
+            position.size_bucket = None;
+        }
+        log_info!(
+            "set_size_disclosure",
+            position_id = position_id,
+            allowed = allowed
+        );
+        Ok(())
+    }
+
+    /// Reveals which `SizeBucket` the position's value falls in and stores
+    /// it on the position, where `record_value_protected` holds estimates
+    /// to it. Only the bucket is revealed. Needs the owner's consent through
+    /// `set_size_disclosure`; the owner or its keeper supplies the position
+    /// data, encrypted like for `check_health`.
+    pub fn reveal_size_bucket(
+        ctx: Context<RevealSizeBucket>,
+        computation_offset: u64,
+        position_id: u32,
+        encrypted_position: [[u8; 32]; POSITION_FIELD_COUNT], // value, collateral_ratio, threshold
+        encryption_pubkey: [u8; 32],
+        encryption_nonce: u128,
+    ) -> Result<()> {
+        let circuit_version = GlobalConfig::require_active(&ctx.accounts.global_config)?;
+        let position = &ctx.accounts.position_acc;
+        require!(
+            position.size_disclosure_allowed,
+            ErrorCode::SizeDisclosureNotAllowed
+        );
+        require!(
+            position.may_submit(&ctx.accounts.payer.key(), Clock::get()?.slot, None)?,
+            ErrorCode::UnauthorizedKeeper
+        );
+        log_info!("reveal_size_bucket", position_id = position_id);
+
+        let args = ArgBuilder::new()
+            .x25519_pubkey(encryption_pubkey)
+            .plaintext_u128(encryption_nonce)
+            .encrypted_u64(encrypted_position[0])
+            .encrypted_u64(encrypted_position[1])
+            .encrypted_u64(encrypted_position[2])
+            .build();
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        ctx.accounts.position_acc.begin_computation(
+            computation_offset,
+            Clock::get()?.unix_timestamp,
+            circuit_version,
+        )?;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![RevealSizeBucketCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &[CallbackAccount {
+                    pubkey: ctx.accounts.position_acc.key(),
+                    is_writable: true,
+                }],
+            )?],
+            1,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "reveal_size_bucket")]
+    pub fn reveal_size_bucket_callback(
+        ctx: Context<RevealSizeBucketCallback>,
+        output: SignedComputationOutputs<RevealSizeBucketOutput>,
+    ) -> Result<()> {
+        introspection::verify_callback(
+            &ctx.accounts.instructions_sysvar,
+            ctx.accounts.computation_account.key,
+            &ctx.accounts.mxe_account,
+            ctx.accounts.position_acc.pending_computation,
+        )?;
+        let o = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(RevealSizeBucketOutput { field_0 }) => field_0,
+            Err(_) => {
+                log_error!("computation_aborted", circuit = "reveal_size_bucket");
+                let now = Clock::get()?.unix_timestamp;
+                let position = &mut ctx.accounts.position_acc;
+                emit!(position.fail_computation(ComputationKind::RevealSizeBucket, now));
+                return Ok(());
+            }
+        };
+
+        let position = &mut ctx.accounts.position_acc;
+        position.pending_computation = None;
+        // Consent withdrawn while the reveal was in flight
+        if !position.size_disclosure_allowed {
+            log_info!(
+                "reveal_size_bucket_dropped",
+                position_id = position.position_id
+            );
+            return Ok(());
+        }
+        let size_bucket = SizeBucket::from_index(o);
+        position.size_bucket = Some(size_bucket);
+
+        emit!(SizeBucketRevealed {
+            owner: position.owner,
+            position_id: position.position_id,
+            size_bucket,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // ─── Pending Computations ───
+
+    /// Clears a pending computation whose callback never arrived, e.g. after
+    /// the cluster dropped it. Only allowed once `PENDING_TIMEOUT_SECS` have
+    /// passed since it was queued, so a live callback is never orphaned. If
+    /// the callback does arrive later, `introspection` rejects it.
+    pub fn force_clear_pending(ctx: Context<ForceClearPending>, position_id: u32) -> Result<()> {
+        let position = &mut ctx.accounts.position_acc;
+        let computation_offset = position
+            .pending_computation
+            .ok_or(ErrorCode::NoPendingComputation)?;
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            now.saturating_sub(position.pending_since) >= PENDING_TIMEOUT_SECS,
+            ErrorCode::PendingNotExpired
+        );
+        position.pending_computation = None;
+
+        log_info!(
+            "force_clear_pending",
+            position_id = position_id,
+            computation_offset = computation_offset
+        );
+        emit!(PendingComputationCleared {
+            owner: position.owner,
+            position_id,
+            computation_offset,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Cancels a pending computation the cluster never executed, so the
+    /// position can be checked again. Besides `PENDING_TIMEOUT_SECS`, the
+    /// computation account has to show it expired: still queued, or gone
+    /// after Arcium reclaimed its fee. Unlike `force_clear_pending`, the
+    /// owner's keeper may cancel too; a finalized computation whose callback
+    /// failed is left to the owner's `force_clear_pending`.
+    pub fn cancel_computation(ctx: Context<CancelComputation>, position_id: u32) -> Result<()> {
+        let clock = Clock::get()?;
+        let position = &mut ctx.accounts.position_acc;
+        require!(
+            position.may_check(&ctx.accounts.signer.key(), clock.slot),
+            ErrorCode::UnauthorizedKeeper
+        );
+        let computation_offset = position
+            .pending_computation
+            .ok_or(ErrorCode::NoPendingComputation)?;
+        require!(
+            clock.unix_timestamp.saturating_sub(position.pending_since) >= PENDING_TIMEOUT_SECS,
+            ErrorCode::PendingNotExpired
+        );
+        let mxe = &ctx.accounts.mxe_account;
+        require_keys_eq!(
+            ctx.accounts.computation_account.key(),
+            derive_comp_pda!(computation_offset, mxe, ErrorCode::ClusterNotSet),
+            ErrorCode::InvalidComputationAccount
+        );
+        require!(
+            validation::is_unfinished_computation(&ctx.accounts.computation_account)?,
+            ErrorCode::ComputationFinalized
+        );
+        position.pending_computation = None;
+        if let Some(subscription) = &mut ctx.accounts.subscription {
+            subscription.release_fee();
+        }
+
+        log_info!(
+            "cancel_computation",
+            position_id = position_id,
+            computation_offset = computation_offset
+        );
+        emit!(ComputationCancelled {
+            owner: position.owner,
+            position_id,
+            computation_offset,
+            cancelled_by: ctx.accounts.signer.key(),
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // ─── Labels ───
+
+    /// Tags the position with `label_hash`, a salted hash of a label the
+    /// owner picked (see `sentinel_client::labels`), so indexers can find
+    /// it by label without the label being on-chain. Zero clears it.
+    pub fn set_label(ctx: Context<SetLabel>, position_id: u32, label_hash: [u8; 32]) -> Result<()> {
+        ctx.accounts.position_acc.label_hash = label_hash;
+
+        log_info!("set_label", position_id = position_id);
+        emit!(PositionLabelSet {
+            owner: ctx.accounts.owner.key(),
+            position_id,
+            label_hash,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // ─── Migrations ───
+
+    /// Upgrades a position written under an older `PositionAccount` layout
+    /// in place. The account grows to the current size, `payer` covering the
+    /// extra rent, so fields added since read as zero, then
+    /// `PositionAccount::migrate` sets those whose default isn't zero.
+    /// A baseline position is decoded with its own layout first; an account
+    /// of any size no layout had is rejected. Anyone may migrate a position:
+    /// only its layout changes.
+    pub fn migrate_position(ctx: Context<MigratePosition>) -> Result<()> {
+        let info = ctx.accounts.position_acc.to_account_info();
+        require!(
+            info.try_borrow_data()?
+                .starts_with(PositionAccount::DISCRIMINATOR),
+            ErrorCode::InvalidPositionAccount
+        );
+
+        // Layouts before `version` existed are told apart by their size
+        let baseline = match info.data_len() {
+            PositionAccount::BASELINE_LEN => Some(BaselinePosition::deserialize(
+                &mut &info.try_borrow_data()?[8..],
+            )?),
+            PositionAccount::UNVERSIONED_LEN => None,
+            len if len == 8 + PositionAccount::INIT_SPACE => None,
+            _ => return err!(ErrorCode::InvalidPositionAccount),
+        };
+
+        grow_account(
+            &info,
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            8 + PositionAccount::INIT_SPACE,
+        )?;
+
+        let mut position = match baseline {
+            Some(baseline) => {
+                // The new fields are written over the old ones, so no stale
+                // byte may be left past the end of the serialized position
+                info.try_borrow_mut_data()?[8..].fill(0);
+                PositionAccount::from_baseline(baseline)?
+            }
+            None => PositionAccount::try_deserialize(&mut &info.try_borrow_data()?[..])?,
+        };
+        let from_version = position.version;
+        require!(from_version < POSITION_VERSION, ErrorCode::PositionUpToDate);
+        position.migrate();
+        position.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+
+        log_info!(
+            "migrate_position",
+            position_id = position.position_id,
+            from_version = from_version
+        );
+        emit!(PositionMigrated {
+            owner: position.owner,
+            position_id: position.position_id,
+            from_version,
+            to_version: position.version,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // ─── Deactivate / Close Position ───
+
+    /// Stops monitoring a position. The account and its encrypted state are
+    /// kept; `check_health` rejects inactive positions. A monitoring receipt
+    /// has to be burned first.
+    pub fn deactivate_position(ctx: Context<DeactivatePosition>, position_id: u32) -> Result<()> {
+        require!(
+            !ctx.accounts.position_acc.receipt_minted,
+            ErrorCode::ReceiptOutstanding
+        );
+        ctx.accounts.position_acc.is_active = false;
+
+        log_info!("deactivate_position", position_id = position_id);
+        emit!(PositionDeactivated {
+            owner: ctx.accounts.owner.key(),
+            position_id,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Closes the position account and refunds its rent to the owner, along
+    /// with the credits left in its subscription and the balance of its
+    /// check bounty escrow if they are passed. Fails while a queued
+    /// computation still has to call back into the account. Unexpired trial
+    /// credits keep the subscription open until they are swept. A
+    /// monitoring receipt has to be burned first. The position id leaves
+    /// its registrant's `OwnerIndex`, which must be passed if it lists it.
+    pub fn close_position(ctx: Context<ClosePosition>, position_id: u32) -> Result<()> {
+        require!(
+            ctx.accounts.position_acc.pending_computation.is_none(),
+            ErrorCode::ComputationPending
+        );
+        require!(
+            !ctx.accounts.position_acc.receipt_minted,
+            ErrorCode::ReceiptOutstanding
+        );
+        OwnerIndex::unlist(
+            &ctx.accounts.position_acc,
+            ctx.accounts.owner_index.as_mut(),
+        )?;
+
+        if let Some(subscription) = &mut ctx.accounts.subscription {
+            let lamports = Subscription::refund_owner(subscription, &ctx.accounts.owner)?;
+            emit!(SubscriptionWithdrawn {
+                owner: ctx.accounts.owner.key(),
+                position_id,
+                lamports,
+                balance: subscription.trial_lamports,
+                timestamp: Clock::get()?.unix_timestamp,
+            });
+        }
+
+        log_info!("close_position", position_id = position_id);
+        emit!(PositionClosed {
+            owner: ctx.accounts.owner.key(),
+            position_id,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Closes a deactivated position and its risk history like
+    /// `close_position`, keeping an `ArchiveRecord` in their place: the
+    /// position's final stats and SHA-256 hashes of both accounts' last
+    /// data, so anyone holding a copy can prove it with
+    /// `ArchiveRecord::verify`. The record's rent is a fraction of theirs.
+    /// A position id is archived once; a position re-registered under an
+    /// archived id is closed with `close_position`. The `OwnerIndex` is
+    /// updated as by `close_position`.
+    pub fn archive_position(ctx: Context<ArchivePosition>, position_id: u32) -> Result<()> {
+        require!(
+            !ctx.accounts.position_acc.is_active,
+            ErrorCode::PositionStillActive
+        );
+        require!(
+            ctx.accounts.position_acc.pending_computation.is_none(),
+            ErrorCode::ComputationPending
+        );
+        OwnerIndex::unlist(
+            &ctx.accounts.position_acc,
+            ctx.accounts.owner_index.as_mut(),
+        )?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let (history_len, history_hash) = match &ctx.accounts.risk_history {
+            Some(history) => (
+                history.load()?.len,
+                hash(&history.to_account_info().try_borrow_data()?).to_bytes(),
+            ),
+            None => (0, [0; 32]),
+        };
+        let position = &ctx.accounts.position_acc;
+        let position_hash = hash(&position.to_account_info().try_borrow_data()?).to_bytes();
+
+        ctx.accounts.archive.set_inner(ArchiveRecord {
+            bump: ctx.bumps.archive,
+            owner: position.owner,
+            registrant: position.registrant,
+            position_id,
+            metadata: position.metadata,
+            last_check: position.last_check,
+            history_len,
+            fees_paid: ctx
+                .accounts
+                .subscription
+                .as_ref()
+                .map_or(0, |s| s.fees_paid),
+            bounties_paid: ctx
+                .accounts
+                .check_bounty
+                .as_ref()
+                .map_or(0, |b| b.bounties_paid),
+            position_hash,
+            history_hash,
+            archived_at: now,
+        });
+
+        if let Some(subscription) = &mut ctx.accounts.subscription {
+            let lamports = Subscription::refund_owner(subscription, &ctx.accounts.owner)?;
+            emit!(SubscriptionWithdrawn {
+                owner: ctx.accounts.owner.key(),
+                position_id,
+                lamports,
+                balance: subscription.trial_lamports,
+                timestamp: now,
+            });
+        }
+
+        log_info!("archive_position", position_id = position_id);
+        emit!(PositionArchived {
+            owner: ctx.accounts.owner.key(),
+            position_id,
+            archive: ctx.accounts.archive.key(),
+            position_hash,
+            history_hash,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+/// Grows `info` to `space` bytes if smaller, topping its rent up from
+/// `payer`, so accounts created before a field was appended can hold it.
+fn grow_account<'info>(
+    info: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    space: usize,
+) -> Result<()> {
+    if info.data_len() >= space {
+        return Ok(());
+    }
+    let rent = Rent::get()?
+        .minimum_balance(space)
+        .saturating_sub(info.lamports());
+    if rent > 0 {
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                system_program.clone(),
+                anchor_lang::system_program::Transfer {
+                    from: payer.clone(),
+                    to: info.clone(),
+                },
+            ),
+            rent,
+        )?;
+    }
+    info.resize(space)?;
+    Ok(())
+}
+
+// ─── Account Structs ───
+
+#[queue_computation_accounts("init_risk_state", payer)]
+#[derive(Accounts)]
+#[instruction(
+    computation_offset: u64,
+    position_id: u32,
+    nonce: u128,
+    referrer: Option<Pubkey>,
+    metadata: PositionMetadata,
+    label_hash: [u8; 32],
+    index_page: u32,
+)]
+pub struct RegisterPosition<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_mempool(&mempool_account) @ ErrorCode::InvalidMempoolAccount,
+    )]
+    /// CHECK: mempool_account, owner and discriminator checked in `validation`
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_execpool(&executing_pool) @ ErrorCode::InvalidExecpoolAccount,
+    )]
+    /// CHECK: executing_pool, owner and discriminator checked in `validation`
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_unallocated(&computation_account)
+            @ ErrorCode::InvalidComputationAccount,
+    )]
+    /// CHECK: computation_account, must still be unallocated (created by Arcium)
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_INIT_RISK_STATE))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(
+        mut,
+        address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = !validation::is_degraded(&cluster_account) @ ErrorCode::ClusterDegraded,
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + PositionAccount::INIT_SPACE,
+        seeds = [b"position", payer.key().as_ref(), position_id.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub position_acc: Account<'info, PositionAccount>,
+    #[account(seeds = [b"global_config"], bump)]
+    /// CHECK: may not exist yet, read by `GlobalConfig::risk_model` and
+    /// `GlobalConfig::require_active`
+    pub global_config: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + ReferralStats::INIT_SPACE,
+        seeds = [b"referral", referrer.unwrap_or_default().as_ref()],
+        bump,
+    )]
+    pub referral_stats: Option<Account<'info, ReferralStats>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + OwnerIndex::INIT_SPACE,
+        seeds = [b"owner_index", payer.key().as_ref(), index_page.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub owner_index: Account<'info, OwnerIndex>,
+    /// The page before `owner_index`; required to create a page after the
+    /// first
+    #[account(
+        seeds = [
+            b"owner_index",
+            payer.key().as_ref(),
+            index_page.wrapping_sub(1).to_le_bytes().as_ref(),
+        ],
+        bump = previous_owner_index.bump,
+    )]
+    pub previous_owner_index: Option<Account<'info, OwnerIndex>>,
+}
+
+#[callback_accounts("init_risk_state")]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct InitRiskStateCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_INIT_RISK_STATE))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        constraint = validation::is_computation(&computation_account)
+            @ ErrorCode::InvalidComputationAccount,
+    )]
+    /// CHECK: computation_account, owner and discriminator checked in `validation`
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub position_acc: Account<'info, PositionAccount>,
+}
+
+#[init_computation_definition_accounts("init_risk_state", payer)]
+#[derive(Accounts)]
+pub struct InitRiskStateCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [crate::ID.as_ref()],
+        bump,
+        seeds::program = anchor_lang::solana_program::bpf_loader_upgradeable::ID,
+        constraint = program_data.upgrade_authority_address == Some(payer.key())
+            @ ErrorCode::InvalidAuthority,
+    )]
+    pub program_data: Account<'info, ProgramData>,
+}
+
+#[queue_computation_accounts("check_position_health", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, _position_id: u32)]
+pub struct CheckHealth<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_mempool(&mempool_account) @ ErrorCode::InvalidMempoolAccount,
+    )]
+    /// CHECK: mempool_account, owner and discriminator checked in `validation`
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_execpool(&executing_pool) @ ErrorCode::InvalidExecpoolAccount,
+    )]
+    /// CHECK: executing_pool, owner and discriminator checked in `validation`
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_unallocated(&computation_account)
+            @ ErrorCode::InvalidComputationAccount,
+    )]
+    /// CHECK: computation_account, must still be unallocated (created by Arcium)
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_CHECK_HEALTH))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(
+        mut,
+        address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = !validation::is_degraded(&cluster_account) @ ErrorCode::ClusterDegraded,
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    /// CHECK: Position owner
+    #[account(address = position_acc.owner @ ErrorCode::PositionOwnerMismatch)]
+    pub owner: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [
+            b"position",
+            position_acc.registrant.as_ref(),
+            _position_id.to_le_bytes().as_ref(),
+        ],
+        bump = position_acc.bump,
+        has_one = owner @ ErrorCode::PositionOwnerMismatch
+    )]
+    pub position_acc: Account<'info, PositionAccount>,
+    #[account(seeds = [b"global_config"], bump)]
+    /// CHECK: may not exist yet, read by `GlobalConfig::require_active`
+    pub global_config: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"risk_history", position_acc.key().as_ref()],
+        bump = risk_history.load()?.bump,
+    )]
+    pub risk_history: Option<AccountLoader<'info, RiskHistoryAccount>>,
+    /// Required when a keeper submits the check
+    #[account(seeds = [b"monitoring_plan"], bump = monitoring_plan.bump)]
+    pub monitoring_plan: Option<Account<'info, MonitoringPlan>>,
+    /// Required when a keeper submits the check; pays its fee
+    #[account(
+        mut,
+        seeds = [b"subscription", position_acc.key().as_ref()],
+        bump = subscription.bump,
+    )]
+    pub subscription: Option<Account<'info, Subscription>>,
+    /// CHECK: Pyth `PriceUpdateV2`, matched against `position_acc.price_feed`.
+    /// Without it the check carries no price and skips drop detection.
+    pub price_update: Option<UncheckedAccount<'info>>,
+    /// Snapshot of `position_acc.tvl_protocol`; without it the check skips
+    /// TVL exodus detection
+    pub tvl_snapshot: Option<Account<'info, ProtocolTvlSnapshot>>,
+    /// The position's bounty escrow; without it the check earns no bounty
+    #[account(
+        mut,
+        seeds = [b"check_bounty", position_acc.key().as_ref()],
+        bump = check_bounty.bump,
+    )]
+    pub check_bounty: Option<Box<Account<'info, CheckBounty>>>,
+    /// The payer's registry account; required when a delegate checks a
+    /// position restricted to registered keepers
+    #[account(seeds = [b"keeper", payer.key().as_ref()], bump = keeper_account.bump)]
+    pub keeper_account: Option<Box<Account<'info, KeeperAccount>>>,
+}
+
+#[callback_accounts("check_position_health")]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct CheckPositionHealthCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_CHECK_HEALTH))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        constraint = validation::is_computation(&computation_account)
+            @ ErrorCode::InvalidComputationAccount,
+    )]
+    /// CHECK: computation_account, owner and discriminator checked in `validation`
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub position_acc: Account<'info, PositionAccount>,
+    #[account(
+        mut,
+        seeds = [b"risk_history", position_acc.key().as_ref()],
+        bump = risk_history.load()?.bump,
+    )]
+    pub risk_history: Option<AccountLoader<'info, RiskHistoryAccount>>,
+    #[account(
+        mut,
+        seeds = [b"check_bounty", position_acc.key().as_ref()],
+        bump = check_bounty.bump,
+    )]
+    pub check_bounty: Option<Account<'info, CheckBounty>>,
+    /// Holds the keeper's fee until the check lands
+    #[account(
+        mut,
+        seeds = [b"subscription", position_acc.key().as_ref()],
+        bump = subscription.bump,
+    )]
+    pub subscription: Option<Account<'info, Subscription>>,
+    /// CHECK: the keeper that queued the check, receives the fee and bounty;
+    /// matched against `subscription.pending_keeper` and
+    /// `check_bounty.pending_payee`
+    #[account(mut)]
+    pub keeper: Option<UncheckedAccount<'info>>,
+    /// CHECK: receives the referral share of the fee, matched against
+    /// `position_acc.referrer`
+    #[account(mut)]
+    pub referrer: Option<UncheckedAccount<'info>>,
+}
+
+#[init_computation_definition_accounts("check_position_health", payer)]
+#[derive(Accounts)]
+pub struct InitCheckHealthCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [crate::ID.as_ref()],
+        bump,
+        seeds::program = anchor_lang::solana_program::bpf_loader_upgradeable::ID,
+        constraint = program_data.upgrade_authority_address == Some(payer.key())
+            @ ErrorCode::InvalidAuthority,
+    )]
+    pub program_data: Account<'info, ProgramData>,
+}
+
+#[queue_computation_accounts("check_position_health_batch", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct CheckHealthBatch<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_mempool(&mempool_account) @ ErrorCode::InvalidMempoolAccount,
+    )]
+    /// CHECK: mempool_account, owner and discriminator checked in `validation`
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_execpool(&executing_pool) @ ErrorCode::InvalidExecpoolAccount,
+    )]
+    /// CHECK: executing_pool, owner and discriminator checked in `validation`
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_unallocated(&computation_account)
+            @ ErrorCode::InvalidComputationAccount,
+    )]
+    /// CHECK: computation_account, must still be unallocated (created by Arcium)
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_CHECK_HEALTH_BATCH))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(
+        mut,
+        address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = !validation::is_degraded(&cluster_account) @ ErrorCode::ClusterDegraded,
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(seeds = [b"global_config"], bump)]
+    /// CHECK: may not exist yet, read by `GlobalConfig::require_active`
+    pub global_config: UncheckedAccount<'info>,
+}
+
+#[callback_accounts("check_position_health_batch")]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct CheckPositionHealthBatchCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_CHECK_HEALTH_BATCH))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        constraint = validation::is_computation(&computation_account)
+            @ ErrorCode::InvalidComputationAccount,
+    )]
+    /// CHECK: computation_account, owner and discriminator checked in `validation`
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[init_computation_definition_accounts("check_position_health_batch", payer)]
+#[derive(Accounts)]
+pub struct InitCheckHealthBatchCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [crate::ID.as_ref()],
+        bump,
+        seeds::program = anchor_lang::solana_program::bpf_loader_upgradeable::ID,
+        constraint = program_data.upgrade_authority_address == Some(payer.key())
+            @ ErrorCode::InvalidAuthority,
+    )]
+    pub program_data: Account<'info, ProgramData>,
+}
+
+#[queue_computation_accounts("set_alert_config", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, position_id: u32)]
+pub struct SetAlertConfig<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_mempool(&mempool_account) @ ErrorCode::InvalidMempoolAccount,
+    )]
+    /// CHECK: mempool_account, owner and discriminator checked in `validation`
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_execpool(&executing_pool) @ ErrorCode::InvalidExecpoolAccount,
+    )]
+    /// CHECK: executing_pool, owner and discriminator checked in `validation`
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_unallocated(&computation_account)
+            @ ErrorCode::InvalidComputationAccount,
+    )]
+    /// CHECK: computation_account, must still be unallocated (created by Arcium)
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_SET_ALERT_CONFIG))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(
+        mut,
+        address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = !validation::is_degraded(&cluster_account) @ ErrorCode::ClusterDegraded,
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        mut,
+        seeds = [b"position", position_acc.registrant.as_ref(), position_id.to_le_bytes().as_ref()],
+        bump = position_acc.bump,
+        constraint = position_acc.owner == payer.key() @ ErrorCode::InvalidAuthority,
+    )]
+    pub position_acc: Account<'info, PositionAccount>,
+    #[account(seeds = [b"global_config"], bump)]
+    /// CHECK: may not exist yet, read by `GlobalConfig::require_active`
+    pub global_config: UncheckedAccount<'info>,
+}
+
+#[callback_accounts("set_alert_config")]
+#[derive(Accounts)]
+pub struct SetAlertConfigCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_SET_ALERT_CONFIG))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        constraint = validation::is_computation(&computation_account)
+            @ ErrorCode::InvalidComputationAccount,
+    )]
+    /// CHECK: computation_account, owner and discriminator checked in `validation`
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub position_acc: Account<'info, PositionAccount>,
+}
+
+#[init_computation_definition_accounts("set_alert_config", payer)]
+#[derive(Accounts)]
+pub struct InitSetAlertConfigCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [crate::ID.as_ref()],
+        bump,
+        seeds::program = anchor_lang::solana_program::bpf_loader_upgradeable::ID,
+        constraint = program_data.upgrade_authority_address == Some(payer.key())
+            @ ErrorCode::InvalidAuthority,
+    )]
+    pub program_data: Account<'info, ProgramData>,
+}
+
+#[derive(Accounts)]
+#[instruction(position_id: u32)]
+pub struct ConfigureAlerts<'info> {
+    pub owner: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"position", position_acc.registrant.as_ref(), position_id.to_le_bytes().as_ref()],
+        bump = position_acc.bump,
+        has_one = owner
+    )]
+    pub position_acc: Account<'info, PositionAccount>,
+}
+
+#[derive(Accounts)]
+#[instruction(position_id: u32)]
+pub struct SetMaxStaleness<'info> {
+    pub owner: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"position", position_acc.registrant.as_ref(), position_id.to_le_bytes().as_ref()],
+        bump = position_acc.bump,
+        has_one = owner
+    )]
+    pub position_acc: Account<'info, PositionAccount>,
+}
+
+#[derive(Accounts)]
+#[instruction(position_id: u32)]
+pub struct SetMinCheckInterval<'info> {
+    pub owner: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"position", position_acc.registrant.as_ref(), position_id.to_le_bytes().as_ref()],
+        bump = position_acc.bump,
+        has_one = owner
+    )]
+    pub position_acc: Account<'info, PositionAccount>,
+}
+
+#[derive(Accounts)]
+#[instruction(position_id: u32)]
+pub struct SetPriceFeed<'info> {
+    pub owner: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"position", position_acc.registrant.as_ref(), position_id.to_le_bytes().as_ref()],
+        bump = position_acc.bump,
+        has_one = owner
+    )]
+    pub position_acc: Account<'info, PositionAccount>,
+}
+
+#[derive(Accounts)]
+pub struct FlagPriceFeed<'info> {
+    #[account(mut)]
+    pub flagger: Signer<'info>,
+    /// CHECK: any account; `flag_price_feed` checks it has stopped publishing
+    pub price_feed: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = flagger,
+        space = 8 + FeedMigration::INIT_SPACE,
+        seeds = [b"feed_migration", price_feed.key().as_ref()],
+        bump,
+    )]
+    pub feed_migration: Account<'info, FeedMigration>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(old_feed: Pubkey, replacement: Pubkey)]
+pub struct MigratePriceFeed<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"feed_migration", old_feed.as_ref()],
+        bump = feed_migration.bump,
+    )]
+    pub feed_migration: Account<'info, FeedMigration>,
+    /// CHECK: Pyth `PriceUpdateV2`, read by `oracle::read_price`
+    #[account(address = replacement @ ErrorCode::PriceFeedMismatch)]
+    pub replacement_feed: UncheckedAccount<'info>,
+    #[account(
+        seeds = [crate::ID.as_ref()],
+        bump,
+        seeds::program = anchor_lang::solana_program::bpf_loader_upgradeable::ID,
+        constraint = program_data.upgrade_authority_address == Some(authority.key())
+            @ ErrorCode::InvalidAuthority,
+    )]
+    pub program_data: Account<'info, ProgramData>,
+    #[account(mut, seeds = [b"admin_audit_log"], bump = admin_audit_log.load()?.bump)]
+    pub admin_audit_log: AccountLoader<'info, AdminAuditLog>,
+}
+
+#[derive(Accounts)]
+pub struct RepointPriceFeeds<'info> {
+    #[account(
+        mut,
+        seeds = [b"feed_migration", feed_migration.old_feed.as_ref()],
+        bump = feed_migration.bump,
+    )]
+    pub feed_migration: Account<'info, FeedMigration>,
+}
+
+#[queue_computation_accounts("check_position_health_with_price", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, _position_id: u32)]
+pub struct CheckHealthWithOracle<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_mempool(&mempool_account) @ ErrorCode::InvalidMempoolAccount,
+    )]
+    /// CHECK: mempool_account, owner and discriminator checked in `validation`
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_execpool(&executing_pool) @ ErrorCode::InvalidExecpoolAccount,
+    )]
+    /// CHECK: executing_pool, owner and discriminator checked in `validation`
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_unallocated(&computation_account)
+            @ ErrorCode::InvalidComputationAccount,
+    )]
+    /// CHECK: computation_account, must still be unallocated (created by Arcium)
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_CHECK_HEALTH_WITH_PRICE))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(
+        mut,
+        address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = !validation::is_degraded(&cluster_account) @ ErrorCode::ClusterDegraded,
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    /// CHECK: Position owner
+    #[account(address = position_acc.owner @ ErrorCode::PositionOwnerMismatch)]
+    pub owner: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [
+            b"position",
+            position_acc.registrant.as_ref(),
+            _position_id.to_le_bytes().as_ref(),
+        ],
+        bump = position_acc.bump,
+        has_one = owner @ ErrorCode::PositionOwnerMismatch
+    )]
+    pub position_acc: Account<'info, PositionAccount>,
+    #[account(seeds = [b"global_config"], bump)]
+    /// CHECK: may not exist yet, read by `GlobalConfig::require_active`
+    pub global_config: UncheckedAccount<'info>,
+    /// CHECK: Pyth `PriceUpdateV2`, matched against `position_acc.price_feed`
+    /// in the handler and parsed in `oracle`
+    pub price_update: UncheckedAccount<'info>,
+}
+
+#[callback_accounts("check_position_health_with_price")]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct CheckPositionHealthWithPriceCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_CHECK_HEALTH_WITH_PRICE))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        constraint = validation::is_computation(&computation_account)
+            @ ErrorCode::InvalidComputationAccount,
+    )]
+    /// CHECK: computation_account, owner and discriminator checked in `validation`
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub position_acc: Account<'info, PositionAccount>,
+}
+
+#[init_computation_definition_accounts("check_position_health_with_price", payer)]
+#[derive(Accounts)]
+pub struct InitCheckHealthWithPriceCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [crate::ID.as_ref()],
+        bump,
+        seeds::program = anchor_lang::solana_program::bpf_loader_upgradeable::ID,
+        constraint = program_data.upgrade_authority_address == Some(payer.key())
+            @ ErrorCode::InvalidAuthority,
+    )]
+    pub program_data: Account<'info, ProgramData>,
+}
+
+#[queue_computation_accounts("check_perp_health", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, _position_id: u32)]
+pub struct CheckPerpHealth<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_mempool(&mempool_account) @ ErrorCode::InvalidMempoolAccount,
+    )]
+    /// CHECK: mempool_account, owner and discriminator checked in `validation`
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_execpool(&executing_pool) @ ErrorCode::InvalidExecpoolAccount,
+    )]
+    /// CHECK: executing_pool, owner and discriminator checked in `validation`
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_unallocated(&computation_account)
+            @ ErrorCode::InvalidComputationAccount,
+    )]
+    /// CHECK: computation_account, must still be unallocated (created by Arcium)
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_CHECK_PERP_HEALTH))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(
+        mut,
+        address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = !validation::is_degraded(&cluster_account) @ ErrorCode::ClusterDegraded,
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    /// CHECK: Position owner
+    #[account(address = position_acc.owner @ ErrorCode::PositionOwnerMismatch)]
+    pub owner: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [
+            b"position",
+            position_acc.registrant.as_ref(),
+            _position_id.to_le_bytes().as_ref(),
+        ],
+        bump = position_acc.bump,
+        has_one = owner @ ErrorCode::PositionOwnerMismatch
+    )]
+    pub position_acc: Account<'info, PositionAccount>,
+    #[account(seeds = [b"global_config"], bump)]
+    /// CHECK: may not exist yet, read by `GlobalConfig::require_active`
+    pub global_config: UncheckedAccount<'info>,
+    /// CHECK: Pyth `PriceUpdateV2`, matched against `position_acc.price_feed`
+    /// in the handler and parsed in `oracle`
+    pub price_update: UncheckedAccount<'info>,
+}
+
+#[callback_accounts("check_perp_health")]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct CheckPerpHealthCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_CHECK_PERP_HEALTH))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        constraint = validation::is_computation(&computation_account)
+            @ ErrorCode::InvalidComputationAccount,
+    )]
+    /// CHECK: computation_account, owner and discriminator checked in `validation`
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub position_acc: Account<'info, PositionAccount>,
+}
+
+#[init_computation_definition_accounts("check_perp_health", payer)]
+#[derive(Accounts)]
+pub struct InitCheckPerpHealthCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [crate::ID.as_ref()],
+        bump,
+        seeds::program = anchor_lang::solana_program::bpf_loader_upgradeable::ID,
+        constraint = program_data.upgrade_authority_address == Some(payer.key())
+            @ ErrorCode::InvalidAuthority,
+    )]
+    pub program_data: Account<'info, ProgramData>,
+}
+
+#[queue_computation_accounts("compute_risk_score", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, _position_id: u32)]
+pub struct ComputeRiskScore<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_mempool(&mempool_account) @ ErrorCode::InvalidMempoolAccount,
+    )]
+    /// CHECK: mempool_account, owner and discriminator checked in `validation`
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_execpool(&executing_pool) @ ErrorCode::InvalidExecpoolAccount,
+    )]
+    /// CHECK: executing_pool, owner and discriminator checked in `validation`
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_unallocated(&computation_account)
+            @ ErrorCode::InvalidComputationAccount,
+    )]
+    /// CHECK: computation_account, must still be unallocated (created by Arcium)
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_COMPUTE_RISK_SCORE))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(
+        mut,
+        address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = !validation::is_degraded(&cluster_account) @ ErrorCode::ClusterDegraded,
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    /// CHECK: Position owner
+    #[account(address = position_acc.owner @ ErrorCode::PositionOwnerMismatch)]
+    pub owner: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [
+            b"position",
+            position_acc.registrant.as_ref(),
+            _position_id.to_le_bytes().as_ref(),
+        ],
+        bump = position_acc.bump,
+        has_one = owner @ ErrorCode::PositionOwnerMismatch
+    )]
+    pub position_acc: Account<'info, PositionAccount>,
+    #[account(seeds = [b"global_config"], bump)]
+    /// CHECK: may not exist yet, read by `GlobalConfig::require_active`
+    pub global_config: UncheckedAccount<'info>,
+    #[account(seeds = [b"risk_model_config"], bump = risk_model_config.bump)]
+    pub risk_model_config: Account<'info, RiskModelConfig>,
+    /// CHECK: Pyth `PriceUpdateV2`, matched against `position_acc.price_feed`.
+    /// Without it the volatility signal scores 0.
+    pub price_update: Option<UncheckedAccount<'info>>,
+    /// Snapshot of `position_acc.tvl_protocol`; without it the TVL trend
+    /// signal scores 0
+    pub tvl_snapshot: Option<Account<'info, ProtocolTvlSnapshot>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + RiskScoreAccount::INIT_SPACE,
+        seeds = [b"risk_score", position_acc.key().as_ref()],
+        bump,
+    )]
+    pub risk_score: Account<'info, RiskScoreAccount>,
+    /// Required when a keeper submits the score
+    #[account(seeds = [b"monitoring_plan"], bump = monitoring_plan.bump)]
+    pub monitoring_plan: Option<Account<'info, MonitoringPlan>>,
+    /// Required when a keeper submits the score; pays its fee
+    #[account(
+        mut,
+        seeds = [b"subscription", position_acc.key().as_ref()],
+        bump = subscription.bump,
+    )]
+    pub subscription: Option<Account<'info, Subscription>>,
+}
+
+#[callback_accounts("compute_risk_score")]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct ComputeRiskScoreCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_COMPUTE_RISK_SCORE))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        constraint = validation::is_computation(&computation_account)
+            @ ErrorCode::InvalidComputationAccount,
+    )]
+    /// CHECK: computation_account, owner and discriminator checked in `validation`
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub position_acc: Account<'info, PositionAccount>,
+    #[account(mut)]
+    pub risk_score: Account<'info, RiskScoreAccount>,
+    /// Holds the keeper's fee until the score lands
+    #[account(
+        mut,
+        seeds = [b"subscription", position_acc.key().as_ref()],
+        bump = subscription.bump,
+    )]
+    pub subscription: Option<Account<'info, Subscription>>,
+    /// CHECK: the keeper that queued the score, receives the fee; matched
+    /// against `subscription.pending_keeper`
+    #[account(mut)]
+    pub keeper: Option<UncheckedAccount<'info>>,
+    /// CHECK: receives the referral share of the fee, matched against
+    /// `position_acc.referrer`
+    #[account(mut)]
+    pub referrer: Option<UncheckedAccount<'info>>,
+}
+
+#[init_computation_definition_accounts("compute_risk_score", payer)]
+#[derive(Accounts)]
+pub struct InitComputeRiskScoreCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [crate::ID.as_ref()],
+        bump,
+        seeds::program = anchor_lang::solana_program::bpf_loader_upgradeable::ID,
+        constraint = program_data.upgrade_authority_address == Some(payer.key())
+            @ ErrorCode::InvalidAuthority,
+    )]
+    pub program_data: Account<'info, ProgramData>,
+}
+
+#[derive(Accounts)]
+pub struct SetRiskModelConfig<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(
+        seeds = [b"global_config"],
+        bump = global_config.bump,
+        has_one = admin @ ErrorCode::InvalidAuthority,
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + RiskModelConfig::INIT_SPACE,
+        seeds = [b"risk_model_config"],
+        bump,
+    )]
+    pub risk_model_config: Account<'info, RiskModelConfig>,
+    #[account(mut, seeds = [b"admin_audit_log"], bump = admin_audit_log.load()?.bump)]
+    pub admin_audit_log: AccountLoader<'info, AdminAuditLog>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(position_id: u32)]
+pub struct SetCrossChainEmitter<'info> {
+    pub owner: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"position", position_acc.registrant.as_ref(), position_id.to_le_bytes().as_ref()],
+        bump = position_acc.bump,
+        has_one = owner
+    )]
+    pub position_acc: Account<'info, PositionAccount>,
+}
+
+#[queue_computation_accounts("check_position_health_cross_chain", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, _position_id: u32)]
+pub struct CheckHealthCrossChain<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_mempool(&mempool_account) @ ErrorCode::InvalidMempoolAccount,
+    )]
+    /// CHECK: mempool_account, owner and discriminator checked in `validation`
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_execpool(&executing_pool) @ ErrorCode::InvalidExecpoolAccount,
+    )]
+    /// CHECK: executing_pool, owner and discriminator checked in `validation`
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_unallocated(&computation_account)
+            @ ErrorCode::InvalidComputationAccount,
+    )]
+    /// CHECK: computation_account, must still be unallocated (created by Arcium)
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_CHECK_HEALTH_CROSS_CHAIN))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(
+        mut,
+        address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = !validation::is_degraded(&cluster_account) @ ErrorCode::ClusterDegraded,
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    /// CHECK: Position owner
+    #[account(address = position_acc.owner @ ErrorCode::PositionOwnerMismatch)]
+    pub owner: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [
+            b"position",
+            position_acc.registrant.as_ref(),
+            _position_id.to_le_bytes().as_ref(),
+        ],
+        bump = position_acc.bump,
+        has_one = owner @ ErrorCode::PositionOwnerMismatch
+    )]
+    pub position_acc: Account<'info, PositionAccount>,
+    #[account(seeds = [b"global_config"], bump)]
+    /// CHECK: may not exist yet, read by `GlobalConfig::require_active`
+    pub global_config: UncheckedAccount<'info>,
+    /// CHECK: Wormhole `PostedVAAV1`, owner, emitter and payload checked in `wormhole`
+    pub posted_vaa: UncheckedAccount<'info>,
+}
+
+#[callback_accounts("check_position_health_cross_chain")]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct CheckPositionHealthCrossChainCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_CHECK_HEALTH_CROSS_CHAIN))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        constraint = validation::is_computation(&computation_account)
+            @ ErrorCode::InvalidComputationAccount,
+    )]
+    /// CHECK: computation_account, owner and discriminator checked in `validation`
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub position_acc: Account<'info, PositionAccount>,
+}
+
+#[init_computation_definition_accounts("check_position_health_cross_chain", payer)]
+#[derive(Accounts)]
+pub struct InitCheckHealthCrossChainCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [crate::ID.as_ref()],
+        bump,
+        seeds::program = anchor_lang::solana_program::bpf_loader_upgradeable::ID,
+        constraint = program_data.upgrade_authority_address == Some(payer.key())
+            @ ErrorCode::InvalidAuthority,
+    )]
+    pub program_data: Account<'info, ProgramData>,
+}
+
+#[derive(Accounts)]
+pub struct SetRuleSet<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + RuleSetAccount::INIT_SPACE,
+        seeds = [b"rule_set", owner.key().as_ref()],
+        bump,
+    )]
+    pub rule_set: Account<'info, RuleSetAccount>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("check_position_health_with_rules", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, _position_id: u32)]
+pub struct CheckHealthWithRules<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_mempool(&mempool_account) @ ErrorCode::InvalidMempoolAccount,
+    )]
+    /// CHECK: mempool_account, owner and discriminator checked in `validation`
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_execpool(&executing_pool) @ ErrorCode::InvalidExecpoolAccount,
+    )]
+    /// CHECK: executing_pool, owner and discriminator checked in `validation`
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_unallocated(&computation_account)
+            @ ErrorCode::InvalidComputationAccount,
+    )]
+    /// CHECK: computation_account, must still be unallocated (created by Arcium)
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_CHECK_HEALTH_WITH_RULES))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(
+        mut,
+        address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = !validation::is_degraded(&cluster_account) @ ErrorCode::ClusterDegraded,
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    /// CHECK: Position owner
+    #[account(address = position_acc.owner @ ErrorCode::PositionOwnerMismatch)]
+    pub owner: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [
+            b"position",
+            position_acc.registrant.as_ref(),
+            _position_id.to_le_bytes().as_ref(),
+        ],
+        bump = position_acc.bump,
+        has_one = owner @ ErrorCode::PositionOwnerMismatch
+    )]
+    pub position_acc: Account<'info, PositionAccount>,
+    #[account(seeds = [b"global_config"], bump)]
+    /// CHECK: may not exist yet, read by `GlobalConfig::require_active`
+    pub global_config: UncheckedAccount<'info>,
+    #[account(seeds = [b"rule_set", owner.key().as_ref()], bump = rule_set.bump)]
+    pub rule_set: Account<'info, RuleSetAccount>,
+}
+
+#[callback_accounts("check_position_health_with_rules")]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct CheckPositionHealthWithRulesCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_CHECK_HEALTH_WITH_RULES))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        constraint = validation::is_computation(&computation_account)
+            @ ErrorCode::InvalidComputationAccount,
+    )]
+    /// CHECK: computation_account, owner and discriminator checked in `validation`
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub position_acc: Account<'info, PositionAccount>,
+}
+
+#[init_computation_definition_accounts("check_position_health_with_rules", payer)]
+#[derive(Accounts)]
+pub struct InitCheckHealthWithRulesCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [crate::ID.as_ref()],
+        bump,
+        seeds::program = anchor_lang::solana_program::bpf_loader_upgradeable::ID,
+        constraint = program_data.upgrade_authority_address == Some(payer.key())
+            @ ErrorCode::InvalidAuthority,
+    )]
+    pub program_data: Account<'info, ProgramData>,
+}
+
+#[derive(Accounts)]
+#[instruction(protocol_program: Pubkey)]
+pub struct RegisterProtocolAdapter<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + ProtocolAdapter::INIT_SPACE,
+        seeds = [b"protocol_adapter", protocol_program.as_ref()],
+        bump,
+    )]
+    pub protocol_adapter: Account<'info, ProtocolAdapter>,
+    #[account(
+        seeds = [crate::ID.as_ref()],
+        bump,
+        seeds::program = anchor_lang::solana_program::bpf_loader_upgradeable::ID,
+        constraint = program_data.upgrade_authority_address == Some(authority.key())
+            @ ErrorCode::InvalidAuthority,
+    )]
+    pub program_data: Account<'info, ProgramData>,
+    #[account(mut, seeds = [b"admin_audit_log"], bump = admin_audit_log.load()?.bump)]
+    pub admin_audit_log: AccountLoader<'info, AdminAuditLog>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(protocol_program: Pubkey)]
+pub struct SetProtocolBlackouts<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"protocol_adapter", protocol_program.as_ref()],
+        bump = protocol_adapter.bump,
+    )]
+    pub protocol_adapter: Account<'info, ProtocolAdapter>,
+    #[account(
+        seeds = [crate::ID.as_ref()],
+        bump,
+        seeds::program = anchor_lang::solana_program::bpf_loader_upgradeable::ID,
+        constraint = program_data.upgrade_authority_address == Some(authority.key())
+            @ ErrorCode::InvalidAuthority,
+    )]
+    pub program_data: Account<'info, ProgramData>,
+    #[account(mut, seeds = [b"admin_audit_log"], bump = admin_audit_log.load()?.bump)]
+    pub admin_audit_log: AccountLoader<'info, AdminAuditLog>,
+}
+
+#[queue_computation_accounts("check_position_health_from_protocol", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, _position_id: u32)]
+pub struct CheckHealthFromProtocol<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_mempool(&mempool_account) @ ErrorCode::InvalidMempoolAccount,
+    )]
+    /// CHECK: mempool_account, owner and discriminator checked in `validation`
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_execpool(&executing_pool) @ ErrorCode::InvalidExecpoolAccount,
+    )]
+    /// CHECK: executing_pool, owner and discriminator checked in `validation`
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_unallocated(&computation_account)
+            @ ErrorCode::InvalidComputationAccount,
+    )]
+    /// CHECK: computation_account, must still be unallocated (created by Arcium)
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_CHECK_HEALTH_FROM_PROTOCOL))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(
+        mut,
+        address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = !validation::is_degraded(&cluster_account) @ ErrorCode::ClusterDegraded,
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    /// CHECK: Position owner
+    #[account(address = position_acc.owner @ ErrorCode::PositionOwnerMismatch)]
+    pub owner: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [
+            b"position",
+            position_acc.registrant.as_ref(),
+            _position_id.to_le_bytes().as_ref(),
+        ],
+        bump = position_acc.bump,
+        has_one = owner @ ErrorCode::PositionOwnerMismatch
+    )]
+    pub position_acc: Account<'info, PositionAccount>,
+    #[account(seeds = [b"global_config"], bump)]
+    /// CHECK: may not exist yet, read by `GlobalConfig::require_active`
+    pub global_config: UncheckedAccount<'info>,
+    /// CHECK: obligation, parsed in `protocols` with the adapter of the
+    /// program that owns it
+    pub obligation: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"protocol_adapter", obligation.owner.as_ref()],
+        bump = protocol_adapter.bump,
+    )]
+    pub protocol_adapter: Account<'info, ProtocolAdapter>,
+}
+
+#[callback_accounts("check_position_health_from_protocol")]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct CheckPositionHealthFromProtocolCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_CHECK_HEALTH_FROM_PROTOCOL))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        constraint = validation::is_computation(&computation_account)
+            @ ErrorCode::InvalidComputationAccount,
+    )]
+    /// CHECK: computation_account, owner and discriminator checked in `validation`
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub position_acc: Account<'info, PositionAccount>,
+}
+
+#[init_computation_definition_accounts("check_position_health_from_protocol", payer)]
+#[derive(Accounts)]
+pub struct InitCheckHealthFromProtocolCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [crate::ID.as_ref()],
+        bump,
+        seeds::program = anchor_lang::solana_program::bpf_loader_upgradeable::ID,
+        constraint = program_data.upgrade_authority_address == Some(payer.key())
+            @ ErrorCode::InvalidAuthority,
+    )]
+    pub program_data: Account<'info, ProgramData>,
+}
+
+#[derive(Accounts)]
+#[instruction(protocol_program: Pubkey)]
+pub struct SetTvlUpdater<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + ProtocolTvlSnapshot::INIT_SPACE,
+        seeds = [b"tvl_snapshot", protocol_program.as_ref()],
+        bump,
+    )]
+    pub tvl_snapshot: Account<'info, ProtocolTvlSnapshot>,
+    #[account(
+        seeds = [crate::ID.as_ref()],
+        bump,
+        seeds::program = anchor_lang::solana_program::bpf_loader_upgradeable::ID,
+        constraint = program_data.upgrade_authority_address == Some(authority.key())
+            @ ErrorCode::InvalidAuthority,
+    )]
+    pub program_data: Account<'info, ProgramData>,
+    #[account(mut, seeds = [b"admin_audit_log"], bump = admin_audit_log.load()?.bump)]
+    pub admin_audit_log: AccountLoader<'info, AdminAuditLog>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(protocol_program: Pubkey)]
+pub struct UpdateTvlSnapshot<'info> {
+    pub updater: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"tvl_snapshot", protocol_program.as_ref()],
+        bump = tvl_snapshot.bump,
+        has_one = updater @ ErrorCode::InvalidAuthority,
+    )]
+    pub tvl_snapshot: Account<'info, ProtocolTvlSnapshot>,
+}
+
+#[derive(Accounts)]
+#[instruction(position_id: u32)]
+pub struct SetTvlProtocol<'info> {
+    pub owner: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"position", position_acc.registrant.as_ref(), position_id.to_le_bytes().as_ref()],
+        bump = position_acc.bump,
+        has_one = owner
+    )]
+    pub position_acc: Account<'info, PositionAccount>,
+}
+
+#[derive(Accounts)]
+#[instruction(position_id: u32)]
+pub struct SetLabel<'info> {
+    pub owner: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"position", position_acc.registrant.as_ref(), position_id.to_le_bytes().as_ref()],
+        bump = position_acc.bump,
+        has_one = owner
+    )]
+    pub position_acc: Account<'info, PositionAccount>,
+}
+
+#[queue_computation_accounts("reveal_risk", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, position_id: u32)]
+pub struct RevealRisk<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_mempool(&mempool_account) @ ErrorCode::InvalidMempoolAccount,
+    )]
+    /// CHECK: mempool_account, owner and discriminator checked in `validation`
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_execpool(&executing_pool) @ ErrorCode::InvalidExecpoolAccount,
+    )]
+    /// CHECK: executing_pool, owner and discriminator checked in `validation`
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_unallocated(&computation_account)
+            @ ErrorCode::InvalidComputationAccount,
+    )]
+    /// CHECK: computation_account, must still be unallocated (created by Arcium)
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_REVEAL_RISK))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(
+        mut,
+        address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        mut,
+        seeds = [b"position", position_acc.registrant.as_ref(), position_id.to_le_bytes().as_ref()],
+        bump = position_acc.bump,
+        constraint = position_acc.owner == payer.key() @ ErrorCode::InvalidAuthority,
+    )]
+    pub position_acc: Account<'info, PositionAccount>,
+    #[account(seeds = [b"auto_action", position_acc.key().as_ref()], bump = auto_action.bump)]
+    pub auto_action: Option<Account<'info, AutoActionConfig>>,
+    #[account(seeds = [b"global_config"], bump)]
+    /// CHECK: may not exist yet, read by `GlobalConfig::max_staleness` and
+    /// `GlobalConfig::require_active`
+    pub global_config: UncheckedAccount<'info>,
+}
+
+#[callback_accounts("reveal_risk")]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct RevealRiskCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_REVEAL_RISK))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        constraint = validation::is_computation(&computation_account)
+            @ ErrorCode::InvalidComputationAccount,
+    )]
+    /// CHECK: computation_account, owner and discriminator checked in `validation`
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub position_acc: Account<'info, PositionAccount>,
+    /// Made due when the reveal is critical
+    #[account(
+        mut,
+        seeds = [b"auto_action", position_acc.key().as_ref()],
+        bump = auto_action.bump,
+    )]
+    pub auto_action: Option<Account<'info, AutoActionConfig>>,
+}
+
+#[init_computation_definition_accounts("reveal_risk", payer)]
+#[derive(Accounts)]
+pub struct InitRevealRiskCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [crate::ID.as_ref()],
+        bump,
+        seeds::program = anchor_lang::solana_program::bpf_loader_upgradeable::ID,
+        constraint = program_data.upgrade_authority_address == Some(payer.key())
+            @ ErrorCode::InvalidAuthority,
+    )]
+    pub program_data: Account<'info, ProgramData>,
+}
+
+#[derive(Accounts)]
+#[instruction(position_id: u32)]
+pub struct InitRiskHistory<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(
+        seeds = [b"position", position_acc.registrant.as_ref(), position_id.to_le_bytes().as_ref()],
+        bump = position_acc.bump,
+        has_one = owner
+    )]
+    pub position_acc: Account<'info, PositionAccount>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + std::mem::size_of::<RiskHistoryAccount>(),
+        seeds = [b"risk_history", position_acc.key().as_ref()],
+        bump,
+    )]
+    pub risk_history: AccountLoader<'info, RiskHistoryAccount>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("reveal_history_summary", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, position_id: u32)]
+pub struct RevealHistorySummary<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_mempool(&mempool_account) @ ErrorCode::InvalidMempoolAccount,
+    )]
+    /// CHECK: mempool_account, owner and discriminator checked in `validation`
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_execpool(&executing_pool) @ ErrorCode::InvalidExecpoolAccount,
+    )]
+    /// CHECK: executing_pool, owner and discriminator checked in `validation`
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_unallocated(&computation_account)
+            @ ErrorCode::InvalidComputationAccount,
+    )]
+    /// CHECK: computation_account, must still be unallocated (created by Arcium)
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_REVEAL_HISTORY_SUMMARY))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(
+        mut,
+        address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        mut,
+        seeds = [b"position", position_acc.registrant.as_ref(), position_id.to_le_bytes().as_ref()],
+        bump = position_acc.bump,
+        constraint = position_acc.owner == payer.key() @ ErrorCode::InvalidAuthority,
+    )]
+    pub position_acc: Account<'info, PositionAccount>,
+    #[account(seeds = [b"global_config"], bump)]
+    /// CHECK: may not exist yet, read by `GlobalConfig::require_active`
+    pub global_config: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"risk_history", position_acc.key().as_ref()],
+        bump = risk_history.load()?.bump,
+    )]
+    pub risk_history: AccountLoader<'info, RiskHistoryAccount>,
+}
+
+#[callback_accounts("reveal_history_summary")]
+#[derive(Accounts)]
+pub struct RevealHistorySummaryCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_REVEAL_HISTORY_SUMMARY))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        constraint = validation::is_computation(&computation_account)
+            @ ErrorCode::InvalidComputationAccount,
+    )]
+    /// CHECK: computation_account, owner and discriminator checked in `validation`
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub position_acc: Account<'info, PositionAccount>,
+    #[account(seeds = [b"risk_history", position_acc.key().as_ref()], bump = risk_history.load()?.bump)]
+    pub risk_history: AccountLoader<'info, RiskHistoryAccount>,
+}
+
+#[init_computation_definition_accounts("reveal_history_summary", payer)]
+#[derive(Accounts)]
+pub struct InitRevealHistorySummaryCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [crate::ID.as_ref()],
+        bump,
+        seeds::program = anchor_lang::solana_program::bpf_loader_upgradeable::ID,
+        constraint = program_data.upgrade_authority_address == Some(payer.key())
+            @ ErrorCode::InvalidAuthority,
+    )]
+    pub program_data: Account<'info, ProgramData>,
+}
+
+#[queue_computation_accounts("reveal_risk_to_owner", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, position_id: u32)]
+pub struct RevealRiskToOwner<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_mempool(&mempool_account) @ ErrorCode::InvalidMempoolAccount,
+    )]
+    /// CHECK: mempool_account, owner and discriminator checked in `validation`
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_execpool(&executing_pool) @ ErrorCode::InvalidExecpoolAccount,
+    )]
+    /// CHECK: executing_pool, owner and discriminator checked in `validation`
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_unallocated(&computation_account)
+            @ ErrorCode::InvalidComputationAccount,
+    )]
+    /// CHECK: computation_account, must still be unallocated (created by Arcium)
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_REVEAL_RISK_TO_OWNER))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(
+        mut,
+        address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        mut,
+        seeds = [b"position", position_acc.registrant.as_ref(), position_id.to_le_bytes().as_ref()],
+        bump = position_acc.bump,
+        constraint = position_acc.owner == payer.key() @ ErrorCode::InvalidAuthority,
+    )]
+    pub position_acc: Account<'info, PositionAccount>,
+    #[account(seeds = [b"global_config"], bump)]
+    /// CHECK: may not exist yet, read by `GlobalConfig::require_active`
+    pub global_config: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + RevealInbox::INIT_SPACE,
+        seeds = [b"reveal_inbox", position_acc.key().as_ref()],
+        bump,
+    )]
+    pub reveal_inbox: Account<'info, RevealInbox>,
+    #[account(
+        seeds = [b"action_memo", position_acc.key().as_ref()],
+        bump = action_memo.bump,
+    )]
+    pub action_memo: Option<Account<'info, ActionMemo>>,
+}
+
+#[callback_accounts("reveal_risk_to_owner")]
+#[derive(Accounts)]
+pub struct RevealRiskToOwnerCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_REVEAL_RISK_TO_OWNER))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        constraint = validation::is_computation(&computation_account)
+            @ ErrorCode::InvalidComputationAccount,
+    )]
+    /// CHECK: computation_account, owner and discriminator checked in `validation`
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub position_acc: Account<'info, PositionAccount>,
+    #[account(
+        mut,
+        seeds = [b"reveal_inbox", position_acc.key().as_ref()],
+        bump = reveal_inbox.bump,
+    )]
+    pub reveal_inbox: Account<'info, RevealInbox>,
+    #[account(
+        seeds = [b"action_memo", position_acc.key().as_ref()],
+        bump = action_memo.bump,
+    )]
+    pub action_memo: Option<Account<'info, ActionMemo>>,
+}
+
+#[derive(Accounts)]
+#[instruction(position_id: u32, viewer: Pubkey)]
+pub struct GrantViewer<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(
+        seeds = [b"position", position_acc.registrant.as_ref(), position_id.to_le_bytes().as_ref()],
+        bump = position_acc.bump,
+        has_one = owner,
+    )]
+    pub position_acc: Account<'info, PositionAccount>,
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + ViewerGrant::INIT_SPACE,
+        seeds = [b"viewer_grant", position_acc.key().as_ref(), viewer.as_ref()],
+        bump,
+    )]
+    pub viewer_grant: Account<'info, ViewerGrant>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(position_id: u32, viewer: Pubkey)]
+pub struct RevokeViewer<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(
+        seeds = [b"position", position_acc.registrant.as_ref(), position_id.to_le_bytes().as_ref()],
+        bump = position_acc.bump,
+        has_one = owner,
+    )]
+    pub position_acc: Account<'info, PositionAccount>,
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"viewer_grant", position_acc.key().as_ref(), viewer.as_ref()],
+        bump = viewer_grant.bump,
+    )]
+    pub viewer_grant: Account<'info, ViewerGrant>,
+}
+
+#[queue_computation_accounts("reveal_risk_to_viewer", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, position_id: u32)]
+pub struct RevealRiskToViewer<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_mempool(&mempool_account) @ ErrorCode::InvalidMempoolAccount,
+    )]
+    /// CHECK: mempool_account, owner and discriminator checked in `validation`
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_execpool(&executing_pool) @ ErrorCode::InvalidExecpoolAccount,
+    )]
+    /// CHECK: executing_pool, owner and discriminator checked in `validation`
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_unallocated(&computation_account)
+            @ ErrorCode::InvalidComputationAccount,
+    )]
+    /// CHECK: computation_account, must still be unallocated (created by Arcium)
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_REVEAL_RISK_TO_VIEWER))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(
+        mut,
+        address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        mut,
+        seeds = [b"position", position_acc.registrant.as_ref(), position_id.to_le_bytes().as_ref()],
+        bump = position_acc.bump,
+    )]
+    pub position_acc: Account<'info, PositionAccount>,
+    #[account(seeds = [b"global_config"], bump)]
+    /// CHECK: may not exist yet, read by `GlobalConfig::require_active`
+    pub global_config: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"viewer_grant", position_acc.key().as_ref(), viewer_grant.viewer.as_ref()],
+        bump = viewer_grant.bump,
+        constraint = payer.key() == viewer_grant.viewer || payer.key() == position_acc.owner
+            @ ErrorCode::InvalidAuthority,
+    )]
+    pub viewer_grant: Account<'info, ViewerGrant>,
+}
+
+#[callback_accounts("reveal_risk_to_viewer")]
+#[derive(Accounts)]
+pub struct RevealRiskToViewerCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_REVEAL_RISK_TO_VIEWER))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        constraint = validation::is_computation(&computation_account)
+            @ ErrorCode::InvalidComputationAccount,
+    )]
+    /// CHECK: computation_account, owner and discriminator checked in `validation`
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub position_acc: Account<'info, PositionAccount>,
+    #[account(mut)]
+    /// CHECK: the position's `ViewerGrant`, closed if revoked since the
+    /// reveal was queued; read in `reveal_risk_to_viewer_callback`
+    pub viewer_grant: UncheckedAccount<'info>,
+}
+
+#[init_computation_definition_accounts("reveal_risk_to_owner", payer)]
+#[derive(Accounts)]
+pub struct InitRevealRiskToOwnerCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [crate::ID.as_ref()],
+        bump,
+        seeds::program = anchor_lang::solana_program::bpf_loader_upgradeable::ID,
+        constraint = program_data.upgrade_authority_address == Some(payer.key())
+            @ ErrorCode::InvalidAuthority,
+    )]
+    pub program_data: Account<'info, ProgramData>,
+}
+
+#[init_computation_definition_accounts("reveal_risk_to_viewer", payer)]
+#[derive(Accounts)]
+pub struct InitRevealRiskToViewerCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [crate::ID.as_ref()],
+        bump,
+        seeds::program = anchor_lang::solana_program::bpf_loader_upgradeable::ID,
+        constraint = program_data.upgrade_authority_address == Some(payer.key())
+            @ ErrorCode::InvalidAuthority,
+    )]
+    pub program_data: Account<'info, ProgramData>,
+}
+
+#[queue_computation_accounts("compute_health_factor", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, position_id: u32)]
+pub struct ComputeHealthFactor<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_mempool(&mempool_account) @ ErrorCode::InvalidMempoolAccount,
+    )]
+    /// CHECK: mempool_account, owner and discriminator checked in `validation`
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_execpool(&executing_pool) @ ErrorCode::InvalidExecpoolAccount,
+    )]
+    /// CHECK: executing_pool, owner and discriminator checked in `validation`
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_unallocated(&computation_account)
+            @ ErrorCode::InvalidComputationAccount,
+    )]
+    /// CHECK: computation_account, must still be unallocated (created by Arcium)
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_COMPUTE_HEALTH_FACTOR))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(
+        mut,
+        address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = !validation::is_degraded(&cluster_account) @ ErrorCode::ClusterDegraded,
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        mut,
+        seeds = [b"position", position_acc.registrant.as_ref(), position_id.to_le_bytes().as_ref()],
+        bump = position_acc.bump,
+    )]
+    pub position_acc: Account<'info, PositionAccount>,
+    #[account(seeds = [b"global_config"], bump)]
+    /// CHECK: may not exist yet, read by `GlobalConfig::require_active`
+    pub global_config: UncheckedAccount<'info>,
+}
+
+#[callback_accounts("compute_health_factor")]
+#[derive(Accounts)]
+pub struct ComputeHealthFactorCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_COMPUTE_HEALTH_FACTOR))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        constraint = validation::is_computation(&computation_account)
+            @ ErrorCode::InvalidComputationAccount,
+    )]
+    /// CHECK: computation_account, owner and discriminator checked in `validation`
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub position_acc: Account<'info, PositionAccount>,
+}
+
+#[init_computation_definition_accounts("compute_health_factor", payer)]
+#[derive(Accounts)]
+pub struct InitComputeHealthFactorCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [crate::ID.as_ref()],
+        bump,
+        seeds::program = anchor_lang::solana_program::bpf_loader_upgradeable::ID,
+        constraint = program_data.upgrade_authority_address == Some(payer.key())
+            @ ErrorCode::InvalidAuthority,
+    )]
+    pub program_data: Account<'info, ProgramData>,
+}
+
+#[queue_computation_accounts("reveal_health_factor_to_owner", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, position_id: u32)]
+pub struct RevealHealthFactorToOwner<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_mempool(&mempool_account) @ ErrorCode::InvalidMempoolAccount,
+    )]
+    /// CHECK: mempool_account, owner and discriminator checked in `validation`
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_execpool(&executing_pool) @ ErrorCode::InvalidExecpoolAccount,
+    )]
+    /// CHECK: executing_pool, owner and discriminator checked in `validation`
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_unallocated(&computation_account)
+            @ ErrorCode::InvalidComputationAccount,
+    )]
+    /// CHECK: computation_account, must still be unallocated (created by Arcium)
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_REVEAL_HEALTH_FACTOR_TO_OWNER))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(
+        mut,
+        address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        mut,
+        seeds = [b"position", position_acc.registrant.as_ref(), position_id.to_le_bytes().as_ref()],
+        bump = position_acc.bump,
+        constraint = position_acc.owner == payer.key() @ ErrorCode::InvalidAuthority,
+    )]
+    pub position_acc: Account<'info, PositionAccount>,
+    #[account(seeds = [b"global_config"], bump)]
+    /// CHECK: may not exist yet, read by `GlobalConfig::require_active`
+    pub global_config: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + RevealInbox::INIT_SPACE,
+        seeds = [b"reveal_inbox", position_acc.key().as_ref()],
+        bump,
+    )]
+    pub reveal_inbox: Account<'info, RevealInbox>,
+}
+
+#[callback_accounts("reveal_health_factor_to_owner")]
+#[derive(Accounts)]
+pub struct RevealHealthFactorToOwnerCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_REVEAL_HEALTH_FACTOR_TO_OWNER))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        constraint = validation::is_computation(&computation_account)
+            @ ErrorCode::InvalidComputationAccount,
+    )]
+    /// CHECK: computation_account, owner and discriminator checked in `validation`
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub position_acc: Account<'info, PositionAccount>,
+    #[account(
+        mut,
+        seeds = [b"reveal_inbox", position_acc.key().as_ref()],
+        bump = reveal_inbox.bump,
+    )]
+    pub reveal_inbox: Account<'info, RevealInbox>,
+}
+
+#[queue_computation_accounts("stress_test_position", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, position_id: u32)]
+pub struct StressTestPosition<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_mempool(&mempool_account) @ ErrorCode::InvalidMempoolAccount,
+    )]
+    /// CHECK: mempool_account, owner and discriminator checked in `validation`
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_execpool(&executing_pool) @ ErrorCode::InvalidExecpoolAccount,
+    )]
+    /// CHECK: executing_pool, owner and discriminator checked in `validation`
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_unallocated(&computation_account)
+            @ ErrorCode::InvalidComputationAccount,
+    )]
+    /// CHECK: computation_account, must still be unallocated (created by Arcium)
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_STRESS_TEST_POSITION))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(
+        mut,
+        address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        mut,
+        seeds = [b"position", position_acc.registrant.as_ref(), position_id.to_le_bytes().as_ref()],
+        bump = position_acc.bump,
+        constraint = position_acc.owner == payer.key() @ ErrorCode::InvalidAuthority,
+    )]
+    pub position_acc: Account<'info, PositionAccount>,
+    #[account(seeds = [b"global_config"], bump)]
+    /// CHECK: may not exist yet, read by `GlobalConfig::require_active`
+    pub global_config: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + RevealInbox::INIT_SPACE,
+        seeds = [b"reveal_inbox", position_acc.key().as_ref()],
+        bump,
+    )]
+    pub reveal_inbox: Account<'info, RevealInbox>,
+}
+
+#[callback_accounts("stress_test_position")]
+#[derive(Accounts)]
+pub struct StressTestPositionCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_STRESS_TEST_POSITION))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        constraint = validation::is_computation(&computation_account)
+            @ ErrorCode::InvalidComputationAccount,
+    )]
+    /// CHECK: computation_account, owner and discriminator checked in `validation`
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub position_acc: Account<'info, PositionAccount>,
+    #[account(
+        mut,
+        seeds = [b"reveal_inbox", position_acc.key().as_ref()],
+        bump = reveal_inbox.bump,
+    )]
+    pub reveal_inbox: Account<'info, RevealInbox>,
+}
+
+#[init_computation_definition_accounts("reveal_health_factor_to_owner", payer)]
+#[derive(Accounts)]
+pub struct InitRevealHealthFactorToOwnerCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [crate::ID.as_ref()],
+        bump,
+        seeds::program = anchor_lang::solana_program::bpf_loader_upgradeable::ID,
+        constraint = program_data.upgrade_authority_address == Some(payer.key())
+            @ ErrorCode::InvalidAuthority,
+    )]
+    pub program_data: Account<'info, ProgramData>,
+}
+
+#[init_computation_definition_accounts("stress_test_position", payer)]
+#[derive(Accounts)]
+pub struct InitStressTestPositionCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [crate::ID.as_ref()],
+        bump,
+        seeds::program = anchor_lang::solana_program::bpf_loader_upgradeable::ID,
+        constraint = program_data.upgrade_authority_address == Some(payer.key())
+            @ ErrorCode::InvalidAuthority,
+    )]
+    pub program_data: Account<'info, ProgramData>,
+}
+
+#[queue_computation_accounts("attest_health", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, position_id: u32)]
+pub struct AttestHealth<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_mempool(&mempool_account) @ ErrorCode::InvalidMempoolAccount,
+    )]
+    /// CHECK: mempool_account, owner and discriminator checked in `validation`
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_execpool(&executing_pool) @ ErrorCode::InvalidExecpoolAccount,
+    )]
+    /// CHECK: executing_pool, owner and discriminator checked in `validation`
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_unallocated(&computation_account)
+            @ ErrorCode::InvalidComputationAccount,
+    )]
+    /// CHECK: computation_account, must still be unallocated (created by Arcium)
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_ATTEST_HEALTH))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(
+        mut,
+        address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        mut,
+        seeds = [b"position", position_acc.registrant.as_ref(), position_id.to_le_bytes().as_ref()],
+        bump = position_acc.bump,
+        constraint = position_acc.owner == payer.key() @ ErrorCode::InvalidAuthority,
+    )]
+    pub position_acc: Account<'info, PositionAccount>,
+    #[account(seeds = [b"global_config"], bump)]
+    /// CHECK: may not exist yet, read by `GlobalConfig::require_active`
+    pub global_config: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + AttestationAccount::INIT_SPACE,
+        seeds = [b"attestation", position_acc.key().as_ref()],
+        bump,
+    )]
+    pub attestation: Account<'info, AttestationAccount>,
+}
+
+#[callback_accounts("attest_health")]
+#[derive(Accounts)]
+pub struct AttestHealthCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_ATTEST_HEALTH))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        constraint = validation::is_computation(&computation_account)
+            @ ErrorCode::InvalidComputationAccount,
+    )]
+    /// CHECK: computation_account, owner and discriminator checked in `validation`
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub position_acc: Account<'info, PositionAccount>,
+    #[account(
+        mut,
+        seeds = [b"attestation", position_acc.key().as_ref()],
+        bump = attestation.bump,
+    )]
+    pub attestation: Account<'info, AttestationAccount>,
+}
+
+#[init_computation_definition_accounts("attest_health", payer)]
+#[derive(Accounts)]
+pub struct InitAttestHealthCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [crate::ID.as_ref()],
+        bump,
+        seeds::program = anchor_lang::solana_program::bpf_loader_upgradeable::ID,
+        constraint = program_data.upgrade_authority_address == Some(payer.key())
+            @ ErrorCode::InvalidAuthority,
+    )]
+    pub program_data: Account<'info, ProgramData>,
+}
+
+#[queue_computation_accounts("reveal_size_bucket", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, position_id: u32)]
+pub struct RevealSizeBucket<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_mempool(&mempool_account) @ ErrorCode::InvalidMempoolAccount,
+    )]
+    /// CHECK: mempool_account, owner and discriminator checked in `validation`
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_execpool(&executing_pool) @ ErrorCode::InvalidExecpoolAccount,
+    )]
+    /// CHECK: executing_pool, owner and discriminator checked in `validation`
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_unallocated(&computation_account)
+            @ ErrorCode::InvalidComputationAccount,
+    )]
+    /// CHECK: computation_account, must still be unallocated (created by Arcium)
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_REVEAL_SIZE_BUCKET))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(
+        mut,
+        address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = !validation::is_degraded(&cluster_account) @ ErrorCode::ClusterDegraded,
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        mut,
+        seeds = [b"position", position_acc.registrant.as_ref(), position_id.to_le_bytes().as_ref()],
+        bump = position_acc.bump,
+    )]
+    pub position_acc: Account<'info, PositionAccount>,
+    #[account(seeds = [b"global_config"], bump)]
+    /// CHECK: may not exist yet, read by `GlobalConfig::require_active`
+    pub global_config: UncheckedAccount<'info>,
+}
+
+#[callback_accounts("reveal_size_bucket")]
+#[derive(Accounts)]
+pub struct RevealSizeBucketCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_REVEAL_SIZE_BUCKET))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        constraint = validation::is_computation(&computation_account)
+            @ ErrorCode::InvalidComputationAccount,
+    )]
+    /// CHECK: computation_account, owner and discriminator checked in `validation`
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub position_acc: Account<'info, PositionAccount>,
+}
+
+#[init_computation_definition_accounts("reveal_size_bucket", payer)]
+#[derive(Accounts)]
+pub struct InitRevealSizeBucketCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [crate::ID.as_ref()],
+        bump,
+        seeds::program = anchor_lang::solana_program::bpf_loader_upgradeable::ID,
+        constraint = program_data.upgrade_authority_address == Some(payer.key())
+            @ ErrorCode::InvalidAuthority,
+    )]
+    pub program_data: Account<'info, ProgramData>,
+}
+
+#[queue_computation_accounts("rotate_encryption", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, position_id: u32)]
+pub struct RotateEncryption<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_mempool(&mempool_account) @ ErrorCode::InvalidMempoolAccount,
+    )]
+    /// CHECK: mempool_account, owner and discriminator checked in `validation`
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_execpool(&executing_pool) @ ErrorCode::InvalidExecpoolAccount,
+    )]
+    /// CHECK: executing_pool, owner and discriminator checked in `validation`
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_unallocated(&computation_account)
+            @ ErrorCode::InvalidComputationAccount,
+    )]
+    /// CHECK: computation_account, must still be unallocated (created by Arcium)
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_ROTATE_ENCRYPTION))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(
+        mut,
+        address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = !validation::is_degraded(&cluster_account) @ ErrorCode::ClusterDegraded,
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        mut,
+        seeds = [b"position", position_acc.registrant.as_ref(), position_id.to_le_bytes().as_ref()],
+        bump = position_acc.bump,
+        constraint = position_acc.owner == payer.key() @ ErrorCode::InvalidAuthority,
+    )]
+    pub position_acc: Account<'info, PositionAccount>,
+    #[account(seeds = [b"global_config"], bump)]
+    /// CHECK: may not exist yet, read by `GlobalConfig::require_active`
+    pub global_config: UncheckedAccount<'info>,
+}
+
+#[callback_accounts("rotate_encryption")]
+#[derive(Accounts)]
+pub struct RotateEncryptionCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_ROTATE_ENCRYPTION))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        constraint = validation::is_computation(&computation_account)
+            @ ErrorCode::InvalidComputationAccount,
+    )]
+    /// CHECK: computation_account, owner and discriminator checked in `validation`
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub position_acc: Account<'info, PositionAccount>,
+}
+
+#[init_computation_definition_accounts("rotate_encryption", payer)]
+#[derive(Accounts)]
+pub struct InitRotateEncryptionCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [crate::ID.as_ref()],
+        bump,
+        seeds::program = anchor_lang::solana_program::bpf_loader_upgradeable::ID,
+        constraint = program_data.upgrade_authority_address == Some(payer.key())
+            @ ErrorCode::InvalidAuthority,
+    )]
+    pub program_data: Account<'info, ProgramData>,
+}
+
+#[derive(Accounts)]
+#[instruction(owner: Pubkey)]
+pub struct SetSponsorBudget<'info> {
+    #[account(mut)]
+    pub sponsor: Signer<'info>,
+    #[account(
+        init_if_needed,
+        payer = sponsor,
+        space = 8 + SponsorBudget::INIT_SPACE,
+        seeds = [b"sponsor_budget", sponsor.key().as_ref(), owner.as_ref()],
+        bump,
+    )]
+    pub sponsor_budget: Account<'info, SponsorBudget>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("reveal_risk_to_owner", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, position_id: u32)]
+pub struct SponsoredRevealRiskToOwner<'info> {
+    /// The sponsor, paying fees and rent
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub owner: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_mempool(&mempool_account) @ ErrorCode::InvalidMempoolAccount,
+    )]
+    /// CHECK: mempool_account, owner and discriminator checked in `validation`
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_execpool(&executing_pool) @ ErrorCode::InvalidExecpoolAccount,
+    )]
+    /// CHECK: executing_pool, owner and discriminator checked in `validation`
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_unallocated(&computation_account)
+            @ ErrorCode::InvalidComputationAccount,
+    )]
+    /// CHECK: computation_account, must still be unallocated (created by Arcium)
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_REVEAL_RISK_TO_OWNER))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(
+        mut,
+        address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet)
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        mut,
+        seeds = [b"position", position_acc.registrant.as_ref(), position_id.to_le_bytes().as_ref()],
+        bump = position_acc.bump,
+        has_one = owner
+    )]
+    pub position_acc: Account<'info, PositionAccount>,
+    #[account(seeds = [b"global_config"], bump)]
+    /// CHECK: may not exist yet, read by `GlobalConfig::require_active`
+    pub global_config: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + RevealInbox::INIT_SPACE,
+        seeds = [b"reveal_inbox", position_acc.key().as_ref()],
+        bump,
+    )]
+    pub reveal_inbox: Account<'info, RevealInbox>,
+    #[account(
+        mut,
+        seeds = [b"sponsor_budget", payer.key().as_ref(), owner.key().as_ref()],
+        bump = sponsor_budget.bump,
+    )]
+    pub sponsor_budget: Account<'info, SponsorBudget>,
+    #[account(
+        seeds = [b"action_memo", position_acc.key().as_ref()],
+        bump = action_memo.bump,
+    )]
+    pub action_memo: Option<Account<'info, ActionMemo>>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterPortfolio<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + PortfolioAccount::INIT_SPACE,
+        seeds = [b"portfolio", owner.key().as_ref()],
+        bump,
+    )]
+    pub portfolio_acc: Account<'info, PortfolioAccount>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(_position_id: u32)]
+pub struct AddPortfolioPosition<'info> {
+    pub owner: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"portfolio", owner.key().as_ref()],
+        bump = portfolio_acc.bump,
+        has_one = owner
+    )]
+    pub portfolio_acc: Account<'info, PortfolioAccount>,
+    #[account(
+        seeds = [
+            b"position",
+            position_acc.registrant.as_ref(),
+            _position_id.to_le_bytes().as_ref(),
+        ],
+        bump = position_acc.bump,
+        has_one = owner
+    )]
+    pub position_acc: Account<'info, PositionAccount>,
+}
+
+#[derive(Accounts)]
+pub struct RemovePortfolioPosition<'info> {
+    pub owner: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"portfolio", owner.key().as_ref()],
+        bump = portfolio_acc.bump,
+        has_one = owner
+    )]
+    pub portfolio_acc: Account<'info, PortfolioAccount>,
+}
+
+#[queue_computation_accounts("aggregate_portfolio_risk", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct AggregatePortfolioRisk<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_mempool(&mempool_account) @ ErrorCode::InvalidMempoolAccount,
+    )]
+    /// CHECK: mempool_account, owner and discriminator checked in `validation`
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_execpool(&executing_pool) @ ErrorCode::InvalidExecpoolAccount,
+    )]
+    /// CHECK: executing_pool, owner and discriminator checked in `validation`
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_unallocated(&computation_account)
+            @ ErrorCode::InvalidComputationAccount,
+    )]
+    /// CHECK: computation_account, must still be unallocated (created by Arcium)
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_AGGREGATE_PORTFOLIO_RISK))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(
+        mut,
+        address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = !validation::is_degraded(&cluster_account) @ ErrorCode::ClusterDegraded,
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        mut,
+        seeds = [b"portfolio", payer.key().as_ref()],
+        bump = portfolio_acc.bump
+    )]
+    pub portfolio_acc: Account<'info, PortfolioAccount>,
+    #[account(seeds = [b"global_config"], bump)]
+    /// CHECK: may not exist yet, read by `GlobalConfig::require_active`
+    pub global_config: UncheckedAccount<'info>,
+}
+
+#[callback_accounts("aggregate_portfolio_risk")]
+#[derive(Accounts)]
+pub struct AggregatePortfolioRiskCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_AGGREGATE_PORTFOLIO_RISK))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        constraint = validation::is_computation(&computation_account)
+            @ ErrorCode::InvalidComputationAccount,
+    )]
+    /// CHECK: computation_account, owner and discriminator checked in `validation`
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub portfolio_acc: Account<'info, PortfolioAccount>,
+}
+
+#[derive(Accounts)]
+pub struct ConfigureRiskHeatmap<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(
+        seeds = [b"global_config"],
+        bump = global_config.bump,
+        has_one = admin @ ErrorCode::InvalidAuthority,
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + RiskHeatmap::INIT_SPACE,
+        seeds = [b"risk_heatmap"],
+        bump,
+    )]
+    pub risk_heatmap: Account<'info, RiskHeatmap>,
+    #[account(mut, seeds = [b"admin_audit_log"], bump = admin_audit_log.load()?.bump)]
+    pub admin_audit_log: AccountLoader<'info, AdminAuditLog>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("accumulate_risk_heatmap", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct AccumulateRiskHeatmap<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_mempool(&mempool_account) @ ErrorCode::InvalidMempoolAccount,
+    )]
+    /// CHECK: mempool_account, owner and discriminator checked in `validation`
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_execpool(&executing_pool) @ ErrorCode::InvalidExecpoolAccount,
+    )]
+    /// CHECK: executing_pool, owner and discriminator checked in `validation`
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_unallocated(&computation_account)
+            @ ErrorCode::InvalidComputationAccount,
+    )]
+    /// CHECK: computation_account, must still be unallocated (created by Arcium)
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_ACCUMULATE_RISK_HEATMAP))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(
+        mut,
+        address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = !validation::is_degraded(&cluster_account) @ ErrorCode::ClusterDegraded,
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(mut, seeds = [b"risk_heatmap"], bump = risk_heatmap.bump)]
+    pub risk_heatmap: Account<'info, RiskHeatmap>,
+    #[account(
+        seeds = [b"global_config"],
+        bump = global_config.bump,
+        constraint = global_config.admin == payer.key() @ ErrorCode::InvalidAuthority,
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+}
+
+#[callback_accounts("accumulate_risk_heatmap")]
+#[derive(Accounts)]
+pub struct AccumulateRiskHeatmapCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_ACCUMULATE_RISK_HEATMAP))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        constraint = validation::is_computation(&computation_account)
+            @ ErrorCode::InvalidComputationAccount,
+    )]
+    /// CHECK: computation_account, owner and discriminator checked in `validation`
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut, seeds = [b"risk_heatmap"], bump = risk_heatmap.bump)]
+    pub risk_heatmap: Account<'info, RiskHeatmap>,
+}
+
+#[queue_computation_accounts("reveal_risk_heatmap", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct PublishRiskHeatmap<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_mempool(&mempool_account) @ ErrorCode::InvalidMempoolAccount,
+    )]
+    /// CHECK: mempool_account, owner and discriminator checked in `validation`
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_execpool(&executing_pool) @ ErrorCode::InvalidExecpoolAccount,
+    )]
+    /// CHECK: executing_pool, owner and discriminator checked in `validation`
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_unallocated(&computation_account)
+            @ ErrorCode::InvalidComputationAccount,
+    )]
+    /// CHECK: computation_account, must still be unallocated (created by Arcium)
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_REVEAL_RISK_HEATMAP))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(
+        mut,
+        address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = !validation::is_degraded(&cluster_account) @ ErrorCode::ClusterDegraded,
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(mut, seeds = [b"risk_heatmap"], bump = risk_heatmap.bump)]
+    pub risk_heatmap: Account<'info, RiskHeatmap>,
+    #[account(
+        seeds = [b"global_config"],
+        bump = global_config.bump,
+        constraint = global_config.admin == payer.key() @ ErrorCode::InvalidAuthority,
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+}
+
+#[callback_accounts("reveal_risk_heatmap")]
+#[derive(Accounts)]
+pub struct RevealRiskHeatmapCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_REVEAL_RISK_HEATMAP))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        constraint = validation::is_computation(&computation_account)
+            @ ErrorCode::InvalidComputationAccount,
+    )]
+    /// CHECK: computation_account, owner and discriminator checked in `validation`
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut, seeds = [b"risk_heatmap"], bump = risk_heatmap.bump)]
+    pub risk_heatmap: Account<'info, RiskHeatmap>,
+}
+
+#[init_computation_definition_accounts("aggregate_portfolio_risk", payer)]
+#[derive(Accounts)]
+pub struct InitAggregatePortfolioRiskCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [crate::ID.as_ref()],
+        bump,
+        seeds::program = anchor_lang::solana_program::bpf_loader_upgradeable::ID,
+        constraint = program_data.upgrade_authority_address == Some(payer.key())
+            @ ErrorCode::InvalidAuthority,
+    )]
+    pub program_data: Account<'info, ProgramData>,
+}
+
+#[init_computation_definition_accounts("accumulate_risk_heatmap", payer)]
+#[derive(Accounts)]
+pub struct InitAccumulateRiskHeatmapCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [crate::ID.as_ref()],
+        bump,
+        seeds::program = anchor_lang::solana_program::bpf_loader_upgradeable::ID,
+        constraint = program_data.upgrade_authority_address == Some(payer.key())
+            @ ErrorCode::InvalidAuthority,
+    )]
+    pub program_data: Account<'info, ProgramData>,
+}
+
+#[init_computation_definition_accounts("reveal_risk_heatmap", payer)]
+#[derive(Accounts)]
+pub struct InitRevealRiskHeatmapCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [crate::ID.as_ref()],
+        bump,
+        seeds::program = anchor_lang::solana_program::bpf_loader_upgradeable::ID,
+        constraint = program_data.upgrade_authority_address == Some(payer.key())
+            @ ErrorCode::InvalidAuthority,
+    )]
+    pub program_data: Account<'info, ProgramData>,
+}
+
+#[derive(Accounts)]
+#[instruction(position_id: u32)]
+pub struct DelegateKeeper<'info> {
+    pub owner: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"position", position_acc.registrant.as_ref(), position_id.to_le_bytes().as_ref()],
+        bump = position_acc.bump,
+        has_one = owner
+    )]
+    pub position_acc: Account<'info, PositionAccount>,
+}
+
+#[derive(Accounts)]
+#[instruction(shard: u16)]
+pub struct RenewKeeperLease<'info> {
+    #[account(mut)]
+    pub keeper: Signer<'info>,
+    #[account(
+        init_if_needed,
+        payer = keeper,
+        space = 8 + KeeperLease::INIT_SPACE,
+        seeds = [b"keeper_lease", keeper.key().as_ref(), shard.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub lease: Account<'info, KeeperLease>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterKeeper<'info> {
+    #[account(mut)]
+    pub keeper: Signer<'info>,
+    #[account(
+        init_if_needed,
+        payer = keeper,
+        space = 8 + KeeperAccount::INIT_SPACE,
+        seeds = [b"keeper", keeper.key().as_ref()],
+        bump,
+    )]
+    pub keeper_account: Account<'info, KeeperAccount>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UnbondKeeper<'info> {
+    pub keeper: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"keeper", keeper.key().as_ref()],
+        bump = keeper_account.bump,
+    )]
+    pub keeper_account: Account<'info, KeeperAccount>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawKeeperBond<'info> {
+    #[account(mut)]
+    pub keeper: Signer<'info>,
+    #[account(
+        mut,
+        close = keeper,
+        seeds = [b"keeper", keeper.key().as_ref()],
+        bump = keeper_account.bump,
+    )]
+    pub keeper_account: Account<'info, KeeperAccount>,
+}
+
+#[derive(Accounts)]
+pub struct SlashKeeper<'info> {
+    pub admin: Signer<'info>,
+    #[account(
+        seeds = [b"global_config"],
+        bump = global_config.bump,
+        has_one = admin @ ErrorCode::InvalidAuthority,
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+    #[account(
+        mut,
+        seeds = [b"keeper", keeper_account.keeper.as_ref()],
+        bump = keeper_account.bump,
+    )]
+    pub keeper_account: Account<'info, KeeperAccount>,
+    /// CHECK: any account the slashed lamports compensate
+    #[account(mut)]
+    pub recipient: UncheckedAccount<'info>,
+    #[account(mut, seeds = [b"admin_audit_log"], bump = admin_audit_log.load()?.bump)]
+    pub admin_audit_log: AccountLoader<'info, AdminAuditLog>,
+}
+
+#[derive(Accounts)]
+#[instruction(position_id: u32)]
+pub struct SetRegisteredKeepersOnly<'info> {
+    pub owner: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"position", position_acc.registrant.as_ref(), position_id.to_le_bytes().as_ref()],
+        bump = position_acc.bump,
+        has_one = owner
+    )]
+    pub position_acc: Account<'info, PositionAccount>,
+}
+
+#[derive(Accounts)]
+pub struct InitReceiptMint<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(
+        seeds = [b"global_config"],
+        bump = global_config.bump,
+        has_one = admin @ ErrorCode::InvalidAuthority,
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + ReceiptRegistry::INIT_SPACE,
+        seeds = [b"receipt_registry"],
+        bump,
+    )]
+    pub receipt_registry: Account<'info, ReceiptRegistry>,
+    /// CHECK: created and initialized as a Token-2022 mint in
+    /// `init_receipt_mint`
+    #[account(mut, seeds = [b"receipt_mint"], bump)]
+    pub receipt_mint: UncheckedAccount<'info>,
+    pub token_program: Program<'info, Token2022>,
+    pub system_program: Program<'info, System>,
+    #[account(mut, seeds = [b"admin_audit_log"], bump = admin_audit_log.load()?.bump)]
+    pub admin_audit_log: AccountLoader<'info, AdminAuditLog>,
+}
+
+#[derive(Accounts)]
+#[instruction(position_id: u32)]
+pub struct MintMonitoringReceipt<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"position", position_acc.registrant.as_ref(), position_id.to_le_bytes().as_ref()],
+        bump = position_acc.bump,
+        has_one = owner
+    )]
+    pub position_acc: Account<'info, PositionAccount>,
+    #[account(mut, seeds = [b"receipt_registry"], bump = receipt_registry.bump)]
+    pub receipt_registry: Account<'info, ReceiptRegistry>,
+    #[account(mut, address = receipt_registry.mint)]
+    pub receipt_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        init_if_needed,
+        payer = owner,
+        associated_token::mint = receipt_mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program,
+    )]
+    pub receipt_account: InterfaceAccount<'info, TokenAccount>,
+    pub token_program: Program<'info, Token2022>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(position_id: u32)]
+pub struct BurnMonitoringReceipt<'info> {
+    pub owner: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"position", position_acc.registrant.as_ref(), position_id.to_le_bytes().as_ref()],
+        bump = position_acc.bump,
+        has_one = owner
+    )]
+    pub position_acc: Account<'info, PositionAccount>,
+    #[account(mut, seeds = [b"receipt_registry"], bump = receipt_registry.bump)]
+    pub receipt_registry: Account<'info, ReceiptRegistry>,
+    #[account(mut, address = receipt_registry.mint)]
+    pub receipt_mint: InterfaceAccount<'info, Mint>,
+    /// CHECK: the owner's associated receipt account, which they may have
+    /// emptied and closed; read in `burn_monitoring_receipt`
+    #[account(
+        mut,
+        address = get_associated_token_address_with_program_id(
+            owner.key,
+            &receipt_mint.key(),
+            token_program.key,
+        ),
+    )]
+    pub receipt_account: UncheckedAccount<'info>,
+    pub token_program: Program<'info, Token2022>,
+}
+
+#[derive(Accounts)]
+#[instruction(position_id: u32)]
+pub struct ProposePositionTransfer<'info> {
+    pub owner: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"position", position_acc.registrant.as_ref(), position_id.to_le_bytes().as_ref()],
+        bump = position_acc.bump,
+        has_one = owner
+    )]
+    pub position_acc: Account<'info, PositionAccount>,
+}
+
+#[derive(Accounts)]
+#[instruction(position_id: u32)]
+pub struct AcceptPositionTransfer<'info> {
+    pub new_owner: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"position", position_acc.registrant.as_ref(), position_id.to_le_bytes().as_ref()],
+        bump = position_acc.bump,
+        constraint = position_acc.pending_owner == Some(new_owner.key())
+            @ ErrorCode::NotPendingOwner,
+    )]
+    pub position_acc: Account<'info, PositionAccount>,
+    /// The position's subscription, handed over with it
+    #[account(
+        mut,
+        seeds = [b"subscription", position_acc.key().as_ref()],
+        bump = subscription.bump,
+    )]
+    pub subscription: Option<Account<'info, Subscription>>,
+    /// The position's bounty escrow, handed over with it
+    #[account(
+        mut,
+        seeds = [b"check_bounty", position_acc.key().as_ref()],
+        bump = check_bounty.bump,
+    )]
+    pub check_bounty: Option<Account<'info, CheckBounty>>,
+}
+
+#[derive(Accounts)]
+pub struct InitAdminAuditLog<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + std::mem::size_of::<AdminAuditLog>(),
+        seeds = [b"admin_audit_log"],
+        bump,
+    )]
+    pub admin_audit_log: AccountLoader<'info, AdminAuditLog>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetMonitoringPlan<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + MonitoringPlan::INIT_SPACE,
+        seeds = [b"monitoring_plan"],
+        bump,
+    )]
+    pub monitoring_plan: Account<'info, MonitoringPlan>,
+    #[account(
+        seeds = [crate::ID.as_ref()],
+        bump,
+        seeds::program = anchor_lang::solana_program::bpf_loader_upgradeable::ID,
+        constraint = program_data.upgrade_authority_address == Some(authority.key())
+            @ ErrorCode::InvalidAuthority,
+    )]
+    pub program_data: Account<'info, ProgramData>,
+    #[account(mut, seeds = [b"admin_audit_log"], bump = admin_audit_log.load()?.bump)]
+    pub admin_audit_log: AccountLoader<'info, AdminAuditLog>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        seeds = [crate::ID.as_ref()],
+        bump,
+        seeds::program = anchor_lang::solana_program::bpf_loader_upgradeable::ID,
+        constraint = program_data.upgrade_authority_address == Some(authority.key())
+            @ ErrorCode::InvalidAuthority,
+    )]
+    pub program_data: Account<'info, ProgramData>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + GlobalConfig::INIT_SPACE,
+        seeds = [b"global_config"],
+        bump,
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts of the admin's `set_paused` and `set_circuit_version`.
+#[derive(Accounts)]
+pub struct AdminConfig<'info> {
+    pub admin: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"global_config"],
+        bump = global_config.bump,
+        has_one = admin @ ErrorCode::InvalidAuthority,
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+    #[account(mut, seeds = [b"admin_audit_log"], bump = admin_audit_log.load()?.bump)]
+    pub admin_audit_log: AccountLoader<'info, AdminAuditLog>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeConfigUpdate<'info> {
+    pub authority: Signer<'info>,
+    #[account(mut, seeds = [b"global_config"], bump = global_config.bump)]
+    pub global_config: Account<'info, GlobalConfig>,
+    #[account(
+        seeds = [crate::ID.as_ref()],
+        bump,
+        seeds::program = anchor_lang::solana_program::bpf_loader_upgradeable::ID,
+        constraint = program_data.upgrade_authority_address == Some(authority.key())
+            @ ErrorCode::InvalidAuthority,
+    )]
+    pub program_data: Account<'info, ProgramData>,
+}
+
+#[derive(Accounts)]
+pub struct PublishRiskModel<'info> {
+    pub authority: Signer<'info>,
+    #[account(mut, seeds = [b"global_config"], bump = global_config.bump)]
+    pub global_config: Account<'info, GlobalConfig>,
+    #[account(
+        seeds = [crate::ID.as_ref()],
+        bump,
+        seeds::program = anchor_lang::solana_program::bpf_loader_upgradeable::ID,
+        constraint = program_data.upgrade_authority_address == Some(authority.key())
+            @ ErrorCode::InvalidAuthority,
+    )]
+    pub program_data: Account<'info, ProgramData>,
+    #[account(mut, seeds = [b"admin_audit_log"], bump = admin_audit_log.load()?.bump)]
+    pub admin_audit_log: AccountLoader<'info, AdminAuditLog>,
+}
+
+#[derive(Accounts)]
+#[instruction(position_id: u32)]
+pub struct AcceptConfigVersion<'info> {
+    pub owner: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"position", position_acc.registrant.as_ref(), position_id.to_le_bytes().as_ref()],
+        bump = position_acc.bump,
+        has_one = owner
+    )]
+    pub position_acc: Account<'info, PositionAccount>,
+    #[account(seeds = [b"global_config"], bump = global_config.bump)]
+    pub global_config: Account<'info, GlobalConfig>,
+}
+
+#[derive(Accounts)]
+pub struct ApplyConfigUpdate<'info> {
+    pub authority: Signer<'info>,
+    #[account(mut, seeds = [b"global_config"], bump = global_config.bump)]
+    pub global_config: Account<'info, GlobalConfig>,
+    #[account(
+        seeds = [crate::ID.as_ref()],
+        bump,
+        seeds::program = anchor_lang::solana_program::bpf_loader_upgradeable::ID,
+        constraint = program_data.upgrade_authority_address == Some(authority.key())
+            @ ErrorCode::InvalidAuthority,
+    )]
+    pub program_data: Account<'info, ProgramData>,
+    #[account(mut, seeds = [b"admin_audit_log"], bump = admin_audit_log.load()?.bump)]
+    pub admin_audit_log: AccountLoader<'info, AdminAuditLog>,
+}
+
+#[derive(Accounts)]
+pub struct FundSubscription<'info> {
+    #[account(mut)]
+    pub funder: Signer<'info>,
+    pub position_acc: Account<'info, PositionAccount>,
+    #[account(
+        init_if_needed,
+        payer = funder,
+        space = 8 + Subscription::INIT_SPACE,
+        seeds = [b"subscription", position_acc.key().as_ref()],
+        bump,
+    )]
+    pub subscription: Account<'info, Subscription>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(position_id: u32)]
+pub struct WithdrawSubscription<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(
+        seeds = [b"position", position_acc.registrant.as_ref(), position_id.to_le_bytes().as_ref()],
+        bump = position_acc.bump,
+        has_one = owner
+    )]
+    pub position_acc: Account<'info, PositionAccount>,
+    #[account(
+        mut,
+        seeds = [b"subscription", position_acc.key().as_ref()],
+        bump = subscription.bump,
+    )]
+    pub subscription: Account<'info, Subscription>,
+}
+
+#[derive(Accounts)]
+#[instruction(position_id: u32)]
+pub struct FundCheckBounty<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(
+        seeds = [b"position", position_acc.registrant.as_ref(), position_id.to_le_bytes().as_ref()],
+        bump = position_acc.bump,
+        has_one = owner
+    )]
+    pub position_acc: Account<'info, PositionAccount>,
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + CheckBounty::INIT_SPACE,
+        seeds = [b"check_bounty", position_acc.key().as_ref()],
+        bump,
+    )]
+    pub check_bounty: Account<'info, CheckBounty>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(position_id: u32)]
+pub struct CloseCheckBounty<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(
+        seeds = [b"position", position_acc.registrant.as_ref(), position_id.to_le_bytes().as_ref()],
+        bump = position_acc.bump,
+        has_one = owner
+    )]
+    pub position_acc: Account<'info, PositionAccount>,
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"check_bounty", position_acc.key().as_ref()],
+        bump = check_bounty.bump,
+    )]
+    pub check_bounty: Account<'info, CheckBounty>,
+}
+
+#[derive(Accounts)]
+#[instruction(position_id: u32)]
+pub struct SetHeartbeatDeadline<'info> {
+    pub owner: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"position", position_acc.registrant.as_ref(), position_id.to_le_bytes().as_ref()],
+        bump = position_acc.bump,
+        has_one = owner
+    )]
+    pub position_acc: Account<'info, PositionAccount>,
+}
+
+#[derive(Accounts)]
+#[instruction(position_id: u32)]
+pub struct Heartbeat<'info> {
+    /// The owner or its keeper
+    pub signer: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"position", position_acc.registrant.as_ref(), position_id.to_le_bytes().as_ref()],
+        bump = position_acc.bump,
+    )]
+    pub position_acc: Account<'info, PositionAccount>,
+}
+
+#[derive(Accounts)]
+#[instruction(position_id: u32)]
+pub struct FlagStalePosition<'info> {
+    #[account(mut)]
+    pub caller: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"position", position_acc.registrant.as_ref(), position_id.to_le_bytes().as_ref()],
+        bump = position_acc.bump,
+    )]
+    pub position_acc: Account<'info, PositionAccount>,
+    /// The position's bounty escrow; without it flagging earns nothing
+    #[account(
+        mut,
+        seeds = [b"check_bounty", position_acc.key().as_ref()],
+        bump = check_bounty.bump,
+    )]
+    pub check_bounty: Option<Account<'info, CheckBounty>>,
+}
+
+#[derive(Accounts)]
+#[instruction(position_id: u32)]
+pub struct IsMonitored<'info> {
+    #[account(
+        seeds = [b"position", position_acc.registrant.as_ref(), position_id.to_le_bytes().as_ref()],
+        bump = position_acc.bump,
+    )]
+    pub position_acc: Account<'info, PositionAccount>,
+}
+
+#[derive(Accounts)]
+pub struct GrantTrialCredits<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        seeds = [crate::ID.as_ref()],
+        bump,
+        seeds::program = anchor_lang::solana_program::bpf_loader_upgradeable::ID,
+        constraint = program_data.upgrade_authority_address == Some(authority.key())
+            @ ErrorCode::InvalidAuthority,
+    )]
+    pub program_data: Account<'info, ProgramData>,
+    pub position_acc: Account<'info, PositionAccount>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + Subscription::INIT_SPACE,
+        seeds = [b"subscription", position_acc.key().as_ref()],
+        bump,
+    )]
+    pub subscription: Account<'info, Subscription>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SweepExpiredTrialCredits<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        seeds = [crate::ID.as_ref()],
+        bump,
+        seeds::program = anchor_lang::solana_program::bpf_loader_upgradeable::ID,
+        constraint = program_data.upgrade_authority_address == Some(authority.key())
+            @ ErrorCode::InvalidAuthority,
+    )]
+    pub program_data: Account<'info, ProgramData>,
+    #[account(
+        mut,
+        seeds = [b"subscription", subscription.position.as_ref()],
+        bump = subscription.bump,
+    )]
+    pub subscription: Account<'info, Subscription>,
+    /// CHECK: only checked for being closed
+    #[account(address = subscription.position)]
+    pub position_acc: UncheckedAccount<'info>,
+    /// CHECK: receives the refund if the position is closed
+    #[account(mut, address = subscription.owner)]
+    pub owner: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(position_id: u32)]
+pub struct SetAutoAction<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(
+        seeds = [b"position", position_acc.registrant.as_ref(), position_id.to_le_bytes().as_ref()],
+        bump = position_acc.bump,
+        has_one = owner
+    )]
+    pub position_acc: Account<'info, PositionAccount>,
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + AutoActionConfig::INIT_SPACE,
+        seeds = [b"auto_action", position_acc.key().as_ref()],
+        bump,
+    )]
+    pub auto_action: Account<'info, AutoActionConfig>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(position_id: u32)]
+pub struct ClearAutoAction<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(
+        seeds = [b"position", position_acc.registrant.as_ref(), position_id.to_le_bytes().as_ref()],
+        bump = position_acc.bump,
+        has_one = owner
+    )]
+    pub position_acc: Account<'info, PositionAccount>,
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"auto_action", position_acc.key().as_ref()],
+        bump = auto_action.bump,
+    )]
+    pub auto_action: Account<'info, AutoActionConfig>,
+}
+
+#[derive(Accounts)]
+#[instruction(position_id: u32)]
+pub struct ExecuteAutoAction<'info> {
+    #[account(
+        seeds = [b"position", position_acc.registrant.as_ref(), position_id.to_le_bytes().as_ref()],
+        bump = position_acc.bump,
+    )]
+    pub position_acc: Account<'info, PositionAccount>,
+    #[account(
+        mut,
+        seeds = [b"auto_action", position_acc.key().as_ref()],
+        bump = auto_action.bump,
+    )]
+    pub auto_action: Account<'info, AutoActionConfig>,
+}
+
+#[derive(Accounts)]
+#[instruction(position_id: u32)]
+pub struct SetStopLoss<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(
+        seeds = [b"position", position_acc.registrant.as_ref(), position_id.to_le_bytes().as_ref()],
+        bump = position_acc.bump,
+        has_one = owner
+    )]
+    pub position_acc: Account<'info, PositionAccount>,
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + StopLossAccount::INIT_SPACE,
+        seeds = [b"stop_loss", position_acc.key().as_ref()],
+        bump,
+    )]
+    pub stop_loss: Account<'info, StopLossAccount>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(position_id: u32)]
+pub struct ClearStopLoss<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(
+        seeds = [b"position", position_acc.registrant.as_ref(), position_id.to_le_bytes().as_ref()],
+        bump = position_acc.bump,
+        has_one = owner
+    )]
+    pub position_acc: Account<'info, PositionAccount>,
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"stop_loss", position_acc.key().as_ref()],
+        bump = stop_loss.bump,
+    )]
+    pub stop_loss: Account<'info, StopLossAccount>,
+}
+
+#[derive(Accounts)]
+#[instruction(position_id: u32)]
+pub struct ExecuteStopLoss<'info> {
+    #[account(
+        seeds = [b"position", position_acc.registrant.as_ref(), position_id.to_le_bytes().as_ref()],
+        bump = position_acc.bump,
+    )]
+    pub position_acc: Account<'info, PositionAccount>,
+    #[account(
+        mut,
+        seeds = [b"stop_loss", position_acc.key().as_ref()],
+        bump = stop_loss.bump,
+    )]
+    pub stop_loss: Account<'info, StopLossAccount>,
+}
+
+#[derive(Accounts)]
+#[instruction(position_id: u32)]
+pub struct SetActionMemo<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(
+        seeds = [b"position", position_acc.registrant.as_ref(), position_id.to_le_bytes().as_ref()],
+        bump = position_acc.bump,
+        has_one = owner
+    )]
+    pub position_acc: Account<'info, PositionAccount>,
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + ActionMemo::INIT_SPACE,
+        seeds = [b"action_memo", position_acc.key().as_ref()],
+        bump,
+    )]
+    pub action_memo: Account<'info, ActionMemo>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(position_id: u32)]
+pub struct ClearActionMemo<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(
+        seeds = [b"position", position_acc.registrant.as_ref(), position_id.to_le_bytes().as_ref()],
+        bump = position_acc.bump,
+        has_one = owner
+    )]
+    pub position_acc: Account<'info, PositionAccount>,
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"action_memo", position_acc.key().as_ref()],
+        bump = action_memo.bump,
+    )]
+    pub action_memo: Account<'info, ActionMemo>,
+}
+
+#[queue_computation_accounts("evaluate_stop_loss", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, position_id: u32)]
+pub struct EvaluateStopLoss<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_mempool(&mempool_account) @ ErrorCode::InvalidMempoolAccount,
+    )]
+    /// CHECK: mempool_account, owner and discriminator checked in `validation`
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_execpool(&executing_pool) @ ErrorCode::InvalidExecpoolAccount,
+    )]
+    /// CHECK: executing_pool, owner and discriminator checked in `validation`
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet),
+        constraint = validation::is_unallocated(&computation_account)
+            @ ErrorCode::InvalidComputationAccount,
+    )]
+    /// CHECK: computation_account, must still be unallocated (created by Arcium)
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_EVALUATE_STOP_LOSS))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
     #[account(
         mut,
         address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet)
@@ -294,305 +10426,2663 @@ pub struct RegisterPosition<'info> {
     pub clock_account: Account<'info, ClockAccount>,
     pub system_program: Program<'info, System>,
     pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        mut,
+        seeds = [b"position", position_acc.registrant.as_ref(), position_id.to_le_bytes().as_ref()],
+        bump = position_acc.bump,
+    )]
+    pub position_acc: Account<'info, PositionAccount>,
+    #[account(seeds = [b"global_config"], bump)]
+    /// CHECK: may not exist yet, read by `GlobalConfig::require_active`
+    pub global_config: UncheckedAccount<'info>,
+    #[account(seeds = [b"stop_loss", position_acc.key().as_ref()], bump = stop_loss.bump)]
+    pub stop_loss: Account<'info, StopLossAccount>,
+}
+
+#[callback_accounts("evaluate_stop_loss")]
+#[derive(Accounts)]
+pub struct EvaluateStopLossCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_EVALUATE_STOP_LOSS))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        constraint = validation::is_computation(&computation_account)
+            @ ErrorCode::InvalidComputationAccount,
+    )]
+    /// CHECK: computation_account, owner and discriminator checked in `validation`
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar
+    pub instructions_sysvar: AccountInfo<'info>,
+    #[account(mut)]
+    pub position_acc: Account<'info, PositionAccount>,
+    #[account(
+        mut,
+        seeds = [b"stop_loss", position_acc.key().as_ref()],
+        bump = stop_loss.bump,
+    )]
+    pub stop_loss: Account<'info, StopLossAccount>,
+}
+
+#[init_computation_definition_accounts("evaluate_stop_loss", payer)]
+#[derive(Accounts)]
+pub struct InitEvaluateStopLossCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [crate::ID.as_ref()],
+        bump,
+        seeds::program = anchor_lang::solana_program::bpf_loader_upgradeable::ID,
+        constraint = program_data.upgrade_authority_address == Some(payer.key())
+            @ ErrorCode::InvalidAuthority,
+    )]
+    pub program_data: Account<'info, ProgramData>,
+}
+
+#[derive(Accounts)]
+#[instruction(position_id: u32)]
+pub struct SetSizeDisclosure<'info> {
+    pub owner: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"position", position_acc.registrant.as_ref(), position_id.to_le_bytes().as_ref()],
+        bump = position_acc.bump,
+        has_one = owner
+    )]
+    pub position_acc: Account<'info, PositionAccount>,
+}
+
+#[derive(Accounts)]
+#[instruction(position_id: u32)]
+pub struct RecordValueProtected<'info> {
+    pub signer: Signer<'info>,
+    #[account(
+        seeds = [b"position", position_acc.registrant.as_ref(), position_id.to_le_bytes().as_ref()],
+        bump = position_acc.bump,
+    )]
+    pub position_acc: Account<'info, PositionAccount>,
+}
+
+#[derive(Accounts)]
+#[instruction(position_id: u32)]
+pub struct ForceClearPending<'info> {
+    pub owner: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"position", position_acc.registrant.as_ref(), position_id.to_le_bytes().as_ref()],
+        bump = position_acc.bump,
+        has_one = owner
+    )]
+    pub position_acc: Account<'info, PositionAccount>,
+}
+
+#[derive(Accounts)]
+#[instruction(position_id: u32)]
+pub struct CancelComputation<'info> {
+    pub signer: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"position", position_acc.registrant.as_ref(), position_id.to_le_bytes().as_ref()],
+        bump = position_acc.bump,
+    )]
+    pub position_acc: Account<'info, PositionAccount>,
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    /// CHECK: the pending computation's account, address and status checked
+    /// in `cancel_computation`
+    pub computation_account: UncheckedAccount<'info>,
+    /// The position's subscription, to give back a keeper fee held for the
+    /// cancelled check
+    #[account(
+        mut,
+        seeds = [b"subscription", position_acc.key().as_ref()],
+        bump = subscription.bump,
+    )]
+    pub subscription: Option<Account<'info, Subscription>>,
+}
+
+#[derive(Accounts)]
+#[instruction(position_id: u32)]
+pub struct DeactivatePosition<'info> {
+    pub owner: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"position", position_acc.registrant.as_ref(), position_id.to_le_bytes().as_ref()],
+        bump = position_acc.bump,
+        has_one = owner
+    )]
+    pub position_acc: Account<'info, PositionAccount>,
+}
+
+#[derive(Accounts)]
+pub struct MigratePosition<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// CHECK: a position of any layout, which `Account` may fail to read;
+    /// its discriminator is checked in `migrate_position`
+    #[account(mut, owner = crate::ID)]
+    pub position_acc: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(position_id: u32)]
+pub struct ClosePosition<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"position", position_acc.registrant.as_ref(), position_id.to_le_bytes().as_ref()],
+        bump = position_acc.bump,
+        has_one = owner
+    )]
+    pub position_acc: Account<'info, PositionAccount>,
+    #[account(
+        mut,
+        seeds = [b"subscription", position_acc.key().as_ref()],
+        bump = subscription.bump,
+    )]
+    pub subscription: Option<Account<'info, Subscription>>,
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"check_bounty", position_acc.key().as_ref()],
+        bump = check_bounty.bump,
+    )]
+    pub check_bounty: Option<Account<'info, CheckBounty>>,
+    /// The registrant's index of open positions; required if it lists
+    /// this one
+    #[account(
+        mut,
+        seeds = [
+            b"owner_index",
+            position_acc.registrant.as_ref(),
+            position_acc.index_page.to_le_bytes().as_ref(),
+        ],
+        bump = owner_index.bump,
+    )]
+    pub owner_index: Option<Account<'info, OwnerIndex>>,
+}
+
+#[derive(Accounts)]
+#[instruction(position_id: u32)]
+pub struct ArchivePosition<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"position", position_acc.registrant.as_ref(), position_id.to_le_bytes().as_ref()],
+        bump = position_acc.bump,
+        has_one = owner
+    )]
+    pub position_acc: Account<'info, PositionAccount>,
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"risk_history", position_acc.key().as_ref()],
+        bump = risk_history.load()?.bump,
+    )]
+    pub risk_history: Option<AccountLoader<'info, RiskHistoryAccount>>,
+    #[account(
+        mut,
+        seeds = [b"subscription", position_acc.key().as_ref()],
+        bump = subscription.bump,
+    )]
+    pub subscription: Option<Account<'info, Subscription>>,
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"check_bounty", position_acc.key().as_ref()],
+        bump = check_bounty.bump,
+    )]
+    pub check_bounty: Option<Account<'info, CheckBounty>>,
     #[account(
         init,
-        payer = payer,
-        space = 8 + PositionAccount::INIT_SPACE,
-        seeds = [b"position", payer.key().as_ref(), position_id.to_le_bytes().as_ref()],
+        payer = owner,
+        space = 8 + ArchiveRecord::INIT_SPACE,
+        seeds = [b"archive", position_acc.registrant.as_ref(), position_id.to_le_bytes().as_ref()],
         bump,
     )]
-    pub position_acc: Account<'info, PositionAccount>,
+    pub archive: Account<'info, ArchiveRecord>,
+    /// The registrant's index of open positions; required if it lists
+    /// this one
+    #[account(
+        mut,
+        seeds = [
+            b"owner_index",
+            position_acc.registrant.as_ref(),
+            position_acc.index_page.to_le_bytes().as_ref(),
+        ],
+        bump = owner_index.bump,
+    )]
+    pub owner_index: Option<Account<'info, OwnerIndex>>,
+    pub system_program: Program<'info, System>,
+}
+
+// ─── State ───
+
+/// Represents a monitored DeFi position with encrypted risk state.
+#[account]
+#[derive(InitSpace)]
+pub struct PositionAccount {
+    /// PDA bump seed
+    pub bump: u8,
+    /// Encrypted risk state: [is_at_risk, severity, last_price] as 32-byte
+    /// ciphertexts
+    pub risk_state: [[u8; 32]; 3],
+    /// Encrypted alert thresholds: [critical_buffer_bps, warning_buffer_bps,
+    /// min_value_cents]. Read by `check_health` at a fixed offset, so it stays
+    /// ahead of every variable-size field.
+    pub alert_config: [[u8; 32]; 3],
+    /// Encrypted health factor in basis points, from `compute_health_factor`.
+    /// Read at a fixed offset like `alert_config`.
+    pub health_factor: [u8; 32],
+    /// Salted hash of the owner's label for the position, zero if unset.
+    /// At a fixed offset so indexers can filter on it.
+    pub label_hash: [u8; 32],
+    /// Unique position identifier
+    pub position_id: u32,
+    /// Owner's public key
+    pub owner: Pubkey,
+    /// Cryptographic nonce for re-encryption
+    pub nonce: u128,
+    /// Unix timestamp of last health check
+    pub last_check: i64,
+    /// Whether the position is actively monitored
+    pub is_active: bool,
+    /// Referrer recorded at registration, if any
+    pub referrer: Option<Pubkey>,
+    /// Offset of the queued computation whose callback writes this account
+    pub pending_computation: Option<u64>,
+    /// Pyth price update account used by `check_health_with_oracle`, if configured
+    pub price_feed: Option<Pubkey>,
+    /// Delegate allowed to submit health checks besides the owner
+    pub keeper: Option<Pubkey>,
+    /// Last slot the delegate is valid for; `None` means no expiry
+    pub keeper_expiry_slot: Option<u64>,
+    /// Wormhole emitter trusted for cross-chain summaries, if configured
+    pub cross_chain_emitter: Option<wormhole::Emitter>,
+    /// Unix timestamp `pending_computation` was queued at
+    pub pending_since: i64,
+    /// Nonce of `alert_config`
+    pub alert_nonce: u128,
+    /// Whether `alert_config` has been set; until then the defaults apply
+    pub alert_config_set: bool,
+    /// Wallet that registered the position. The position's address is
+    /// derived from it, so it never changes, even when `owner` does.
+    pub registrant: Pubkey,
+    /// Owner a transfer has been offered to, until they accept
+    pub pending_owner: Option<Pubkey>,
+    /// Lowest revealed severity that emits `ActionRequired` (1 to 3)
+    pub min_action_severity: u8,
+    /// Oldest risk state `reveal_risk` reveals, in seconds since
+    /// `last_check`; `None` follows `GlobalConfig`
+    pub max_staleness_secs: Option<i64>,
+    /// Nonce of `health_factor`
+    pub health_factor_nonce: u128,
+    /// Unix timestamp `health_factor` was computed at, 0 before the first
+    pub health_factor_at: i64,
+    /// Protocol whose `ProtocolTvlSnapshot` `check_health` compares
+    pub tvl_protocol: Option<Pubkey>,
+    /// TVL drop in basis points above which `check_health` flags an exodus
+    pub tvl_drop_bps: u16,
+    /// Version of the operator's risk model the position is pinned to
+    pub config_version: u32,
+    /// Copy of that version, graded by when no alert config is set
+    pub risk_model: RiskModel,
+    /// `GlobalConfig::circuit_version` of the circuit that produced
+    /// `risk_state`
+    pub risk_state_version: u32,
+    /// Circuit version of the computation in flight
+    pub pending_circuit_version: u32,
+    /// Most recent computation that came back without a verifiable output
+    pub last_error: Option<ComputationFailure>,
+    /// What the position mirrors, set at registration
+    pub metadata: PositionMetadata,
+    /// End of the maintenance window the last protocol check was deferred
+    /// for; 0 if none was
+    pub deferred_until: i64,
+    /// Risk heatmap epoch the position was last sampled in; 0 if never
+    pub heatmap_epoch: u32,
+    /// Least seconds between two health checks of the position, so a keeper
+    /// can't drain its subscription with checks; 0 for no limit
+    pub min_check_interval_secs: u32,
+    /// Whether the owner allows `reveal_size_bucket`
+    pub size_disclosure_allowed: bool,
+    /// Size bucket last revealed with the owner's consent
+    pub size_bucket: Option<SizeBucket>,
+    /// Layout the account was written with: 0 for positions registered
+    /// before layouts were versioned, `POSITION_VERSION` once migrated
+    pub version: u8,
+    /// Whether a delegated keeper needs a bond in the keeper registry to
+    /// submit position data
+    pub registered_keepers_only: bool,
+    /// Longest the position may go without a check before anyone may flag
+    /// its monitoring as lapsed; 0 for no deadline
+    pub heartbeat_deadline_secs: u32,
+    /// Whether `flag_stale_position` found the deadline missed; cleared by
+    /// the next check or heartbeat
+    pub monitoring_lapsed: bool,
+    /// Unix timestamp the owner or keeper last showed it was monitoring the
+    /// position, by a check or a `heartbeat`; at least `last_check`
+    pub last_seen: i64,
+    /// Whether the owner holds the position's monitoring receipt, which has
+    /// to be burned before the position is deactivated, closed or handed over
+    pub receipt_minted: bool,
+    /// Whether the position is listed in its registrant's `OwnerIndex`;
+    /// false for positions registered before the index existed
+    pub indexed: bool,
+    /// `OwnerIndex` page the position is listed in
+    pub index_page: u32,
+    /// Room for later fields: a new field goes right before it and shrinks
+    /// it by its size, so the account keeps its size and positions not yet
+    /// migrated read the field as zero
+    pub _reserved: [u8; POSITION_RESERVED_BYTES],
+}
+
+impl PositionAccount {
+    /// Account data offset of `health_factor`, for `ArgBuilder::account`.
+    pub const HEALTH_FACTOR_OFFSET: u32 = 8 + 1 + 32 * 3 + 32 * 3;
+    /// Account data offset of `label_hash`, for memcmp filters.
+    pub const LABEL_HASH_OFFSET: usize = Self::HEALTH_FACTOR_OFFSET as usize + 32;
+    /// Account data offsets of `position_id` and `owner`, for memcmp filters.
+    pub const POSITION_ID_OFFSET: usize = Self::LABEL_HASH_OFFSET + 32;
+    pub const OWNER_OFFSET: usize = Self::POSITION_ID_OFFSET + 4;
+    /// Account size of the first deployed layout, with two risk state
+    /// ciphertexts and none of the fields added since.
+    pub const BASELINE_LEN: usize = 8 + BaselinePosition::LEN;
+    /// Account size of positions registered after the baseline but before
+    /// layouts were versioned: short of `version` and the 64 bytes
+    /// `_reserved` started with.
+    pub const UNVERSIONED_LEN: usize = 8 + Self::INIT_SPACE - 1 - 64;
+
+    /// Marks `computation_offset` as the one computation allowed in flight;
+    /// a second queue would race the first callback for the nonce.
+    /// `circuit_version` is the one `GlobalConfig` names for the circuit.
+    pub fn begin_computation(
+        &mut self,
+        computation_offset: u64,
+        now: i64,
+        circuit_version: u32,
+    ) -> Result<()> {
+        require!(
+            self.pending_computation.is_none(),
+            ErrorCode::ComputationPending
+        );
+        self.pending_computation = Some(computation_offset);
+        self.pending_since = now;
+        self.pending_circuit_version = circuit_version;
+        Ok(())
+    }
+
+    /// Brings a position read from an older layout to `POSITION_VERSION`.
+    /// Fields added since read as zero; a version whose fields need another
+    /// default adds a step here for positions older than it.
+    pub fn migrate(&mut self) {
+        // Version 1 added `version` and `_reserved`, both zero
+        self.version = POSITION_VERSION;
+    }
+
+    /// Reads a position written with the baseline layout into the
+    /// unversioned one, with the defaults registration gives the fields
+    /// the baseline lacked. The baseline encrypted no last price, so the
+    /// third risk state ciphertext is left zero: until the first check
+    /// that carries a price replaces it, the circuits read an arbitrary
+    /// last price, which at worst flags one price drop.
+    pub fn from_baseline(baseline: BaselinePosition) -> Result<Self> {
+        let mut position = Self::deserialize(&mut &vec![0; Self::INIT_SPACE][..])?;
+        position.bump = baseline.bump;
+        position.risk_state[..2].copy_from_slice(&baseline.risk_state);
+        position.position_id = baseline.position_id;
+        position.owner = baseline.owner;
+        // Baseline positions were derived from their owner
+        position.registrant = baseline.owner;
+        position.nonce = baseline.nonce;
+        position.last_check = baseline.last_check;
+        position.last_seen = baseline.last_check;
+        position.is_active = baseline.is_active;
+        position.min_action_severity = 1;
+        position.tvl_drop_bps = DEFAULT_TVL_DROP_BPS;
+        position.risk_model = RiskModel::BUILT_IN;
+        position.metadata = PositionMetadata::UNSPECIFIED;
+        Ok(position)
+    }
+
+    /// Records a check that landed at `now`, which ends a lapse in
+    /// monitoring.
+    pub fn record_check(&mut self, now: i64) {
+        self.last_check = now;
+        self.record_seen(now);
+    }
+
+    /// Records that the owner or keeper was monitoring the position at
+    /// `now`, checked or not.
+    pub fn record_seen(&mut self, now: i64) {
+        self.last_seen = now;
+        self.monitoring_lapsed = false;
+    }
+
+    /// Whether the position has gone past its heartbeat deadline at `now`
+    /// without a check or heartbeat.
+    pub fn missed_heartbeat(&self, now: i64) -> bool {
+        let seen = self.last_seen.max(self.last_check);
+        self.heartbeat_deadline_secs > 0
+            && now.saturating_sub(seen) > self.heartbeat_deadline_secs as i64
+    }
+
+    /// Whether the position belongs to `owner`, is active, and was checked
+    /// at most `max_check_age_secs` before `now` without its monitoring
+    /// having lapsed. For account constraints of programs that depend on a
+    /// counterparty being monitored, e.g.
+    /// `constraint = position.is_monitored(&borrower.key(), now, 3_600)`.
+    pub fn is_monitored(&self, owner: &Pubkey, now: i64, max_check_age_secs: u32) -> bool {
+        self.owner == *owner
+            && self.is_active
+            && !self.monitoring_lapsed
+            && !self.missed_heartbeat(now)
+            && now.saturating_sub(self.last_check) <= max_check_age_secs as i64
+    }
+
+    /// Fails if the position was checked, or had a computation queued, less
+    /// than `min_check_interval_secs` before `now`. Counting queued
+    /// computations keeps checks whose callback never arrives from being
+    /// retried back to back.
+    pub fn require_check_interval(&self, now: i64) -> Result<()> {
+        let last = self.last_check.max(self.pending_since);
+        require!(
+            now.saturating_sub(last) >= self.min_check_interval_secs as i64,
+            ErrorCode::CheckTooSoon
+        );
+        Ok(())
+    }
+
+    /// Records that the computation in flight came back without a verifiable
+    /// output and frees the position for a retry. Returns the event
+    /// announcing it.
+    pub fn fail_computation(&mut self, kind: ComputationKind, now: i64) -> ComputationFailed {
+        let computation_offset = self.pending_computation.take().unwrap_or_default();
+        self.last_error = Some(ComputationFailure {
+            kind,
+            computation_offset,
+            failed_at: now,
+        });
+        ComputationFailed {
+            owner: self.owner,
+            position_id: Some(self.position_id),
+            computation_offset,
+            kind,
+            timestamp: now,
+        }
+    }
+
+    /// Whether `signer` may submit a health check at `slot`: the owner always,
+    /// the delegated keeper until its expiry.
+    pub fn may_check(&self, signer: &Pubkey, slot: u64) -> bool {
+        *signer == self.owner
+            || (self.keeper == Some(*signer)
+                && self.keeper_expiry_slot.is_none_or(|expiry| slot <= expiry))
+    }
+
+    /// Whether `signer` may submit position data for the position: as for
+    /// `may_check`, and on positions restricted to registered keepers, a
+    /// delegate also needs a bond in `keeper`, its registry account.
+    /// Instructions without one take such a position's data from the owner
+    /// only.
+    pub fn may_submit(
+        &self,
+        signer: &Pubkey,
+        slot: u64,
+        keeper: Option<&Account<KeeperAccount>>,
+    ) -> Result<bool> {
+        if !self.may_check(signer, slot) {
+            return Ok(false);
+        }
+        if *signer == self.owner || !self.registered_keepers_only {
+            return Ok(true);
+        }
+        match keeper {
+            Some(keeper) => KeeperAccount::is_bonded(keeper),
+            None => Ok(false),
+        }
+    }
+}
+
+/// `PositionAccount` as first deployed, read by `migrate_position`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct BaselinePosition {
+    pub bump: u8,
+    /// Encrypted [is_at_risk, severity]
+    pub risk_state: [[u8; 32]; 2],
+    pub position_id: u32,
+    pub owner: Pubkey,
+    pub nonce: u128,
+    pub last_check: i64,
+    pub is_active: bool,
+}
+
+impl BaselinePosition {
+    /// Serialized size, without the discriminator.
+    pub const LEN: usize = 1 + 32 * 2 + 4 + 32 + 16 + 8 + 1;
+}
+
+/// Public identifiers of the protocol account a position mirrors, so keepers
+/// and dashboards can find it without an external database. Zero keys where
+/// they don't apply.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub struct PositionMetadata {
+    pub protocol: protocols::Protocol,
+    /// Lending market (Solend, Kamino) or group (MarginFi) of the position
+    pub market: Pubkey,
+    pub collateral_mint: Pubkey,
+    pub debt_mint: Pubkey,
+}
+
+impl PositionMetadata {
+    pub const UNSPECIFIED: Self = Self {
+        protocol: protocols::Protocol::Unspecified,
+        market: Pubkey::new_from_array([0; 32]),
+        collateral_mint: Pubkey::new_from_array([0; 32]),
+        debt_mint: Pubkey::new_from_array([0; 32]),
+    };
+}
+
+/// Circuit a computation ran.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub enum ComputationKind {
+    InitRiskState,
+    CheckHealth,
+    CheckHealthBatch,
+    SetAlertConfig,
+    CheckHealthWithPrice,
+    CheckHealthCrossChain,
+    CheckHealthWithRules,
+    CheckHealthFromProtocol,
+    RevealRisk,
+    RevealRiskToOwner,
+    RevealRiskToViewer,
+    ComputeHealthFactor,
+    RevealHealthFactorToOwner,
+    StressTestPosition,
+    AttestHealth,
+    RevealSizeBucket,
+    RotateEncryption,
+    RevealHistorySummary,
+    AggregatePortfolioRisk,
+    AccumulateRiskHeatmap,
+    RevealRiskHeatmap,
+    EvaluateStopLoss,
+    CheckPerpHealth,
+    ComputeRiskScore,
+}
+
+/// A computation whose output failed verification, e.g. because the
+/// cluster aborted it.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub struct ComputationFailure {
+    pub kind: ComputationKind,
+    pub computation_offset: u64,
+    /// Unix timestamp the failed callback landed at
+    pub failed_at: i64,
+}
+
+/// The operator's risk heatmap: how many positions are at risk per
+/// collateral mint, without which ones. An epoch's counts accumulate
+/// encrypted and are only revealed, bucket by bucket, once enough positions
+/// were sampled that no count points at a single owner.
+#[account]
+#[derive(InitSpace)]
+pub struct RiskHeatmap {
+    /// PDA bump seed
+    pub bump: u8,
+    /// Encrypted at-risk counts of the epoch being sampled, one ciphertext
+    /// per bucket
+    pub counts: [[u8; 32]; HEATMAP_BUCKETS],
+    pub nonce: u128,
+    /// Collateral mints counted separately; the last bucket counts the rest
+    pub classes: [Pubkey; HEATMAP_CLASSES],
+    /// Epoch being sampled, from 1
+    pub epoch: u32,
+    /// Positions sampled this epoch, per bucket
+    pub sampled: [u32; HEATMAP_BUCKETS],
+    /// Positions per bucket of the batch in flight, added when it lands
+    pub pending_sampled: [u32; HEATMAP_BUCKETS],
+    pub pending_computation: Option<u64>,
+    /// Last published at-risk counts, `None` for withheld buckets
+    pub at_risk: [Option<u64>; HEATMAP_BUCKETS],
+    /// Positions sampled per bucket for the last published counts
+    pub published_sampled: [u32; HEATMAP_BUCKETS],
+    pub published_at: i64,
+}
+
+impl RiskHeatmap {
+    /// Bucket of positions on collateral `mint`.
+    pub fn class_of(&self, mint: &Pubkey) -> usize {
+        self.classes
+            .iter()
+            .position(|class| *class == *mint && *class != Pubkey::default())
+            .unwrap_or(HEATMAP_CLASSES)
+    }
+}
+
+/// Groups an owner's positions under one encrypted aggregate risk score.
+#[account]
+#[derive(InitSpace)]
+pub struct PortfolioAccount {
+    /// PDA bump seed
+    pub bump: u8,
+    /// Encrypted portfolio risk: [at_risk_positions, max_severity] as 32-byte ciphertexts
+    pub risk_state: [[u8; 32]; 2],
+    /// Owner's public key
+    pub owner: Pubkey,
+    /// Cryptographic nonce for re-encryption
+    pub nonce: u128,
+    /// Unix timestamp of the last aggregation
+    pub last_aggregated: i64,
+    /// Member position PDAs, in the order they are passed to the circuit
+    #[max_len(MAX_PORTFOLIO_POSITIONS)]
+    pub positions: Vec<Pubkey>,
+    /// Offset of the queued aggregation whose callback writes this account
+    pub pending_computation: Option<u64>,
+}
+
+/// Ids of the open positions a wallet registered, so clients and keepers can
+/// enumerate them without scanning program accounts. Positions are addressed
+/// by their registrant, so one stays listed here when transferred; each id
+/// is added by `register_position` and removed when the position is closed
+/// or archived. The ids are split over pages of `MAX_INDEXED_POSITIONS`,
+/// numbered from 0 with no gaps; a page stays once emptied, and the next
+/// registration may reuse its room.
+#[account]
+#[derive(InitSpace)]
+pub struct OwnerIndex {
+    /// PDA bump seed
+    pub bump: u8,
+    pub registrant: Pubkey,
+    pub page: u32,
+    #[max_len(MAX_INDEXED_POSITIONS)]
+    pub position_ids: Vec<u32>,
+}
+
+impl OwnerIndex {
+    pub fn insert(&mut self, position_id: u32) -> Result<()> {
+        require!(
+            self.position_ids.len() < MAX_INDEXED_POSITIONS,
+            ErrorCode::OwnerIndexFull
+        );
+        self.position_ids.push(position_id);
+        Ok(())
+    }
+
+    /// Removes a position being closed from `index`, which has to be passed
+    /// if the position is listed in it.
+    pub fn unlist(position: &PositionAccount, index: Option<&mut Account<Self>>) -> Result<()> {
+        match index {
+            Some(index) => index.position_ids.retain(|id| *id != position.position_id),
+            None => require!(!position.indexed, ErrorCode::OwnerIndexMissing),
+        }
+        Ok(())
+    }
+}
+
+/// The last `RISK_HISTORY_LEN` encrypted risk states of a position, appended
+/// by `check_position_health_callback`. Entries fill from index 0 and then
+/// wrap around at `head`.
+#[account(zero_copy)]
+pub struct RiskHistoryAccount {
+    /// PDA bump seed
+    pub bump: u8,
+    pub _padding: [u8; 7],
+    /// Position the history belongs to
+    pub position: Pubkey,
+    /// Index the next entry is written to
+    pub head: u32,
+    /// Number of filled entries, up to `RISK_HISTORY_LEN`
+    pub len: u32,
+    pub entries: [RiskHistoryEntry; RISK_HISTORY_LEN],
+}
+
+#[zero_copy]
+pub struct RiskHistoryEntry {
+    /// Encrypted risk state: [is_at_risk, severity, last_price] as 32-byte
+    /// ciphertexts
+    pub ciphertexts: [[u8; 32]; 3],
+    /// Nonce of `ciphertexts`, little-endian (a `u128` would add padding)
+    pub nonce: [u8; 16],
+    /// Unix timestamp of the health check
+    pub timestamp: i64,
+}
+
+impl RiskHistoryAccount {
+    /// Overwrites the oldest entry once the buffer is full.
+    pub fn push(&mut self, ciphertexts: [[u8; 32]; 3], nonce: u128, timestamp: i64) {
+        self.entries[self.head as usize] = RiskHistoryEntry {
+            ciphertexts,
+            nonce: nonce.to_le_bytes(),
+            timestamp,
+        };
+        self.head = (self.head + 1) % RISK_HISTORY_LEN as u32;
+        self.len = (self.len + 1).min(RISK_HISTORY_LEN as u32);
+    }
+
+    /// Account data offset of entry `i`'s ciphertexts, for `ArgBuilder::account`.
+    pub fn ciphertexts_offset(i: usize) -> u32 {
+        (8 + std::mem::offset_of!(RiskHistoryAccount, entries)
+            + i * std::mem::size_of::<RiskHistoryEntry>()) as u32
+    }
+}
+
+/// The mint of monitoring receipts and its authority. Its address is fixed,
+/// so integrators can find the mint without trusting a passed account.
+#[account]
+#[derive(InitSpace)]
+pub struct ReceiptRegistry {
+    /// PDA bump seed
+    pub bump: u8,
+    /// Non-transferable Token-2022 mint, at the `receipt_mint` PDA
+    pub mint: Pubkey,
+    /// Receipts minted and not yet burned
+    pub outstanding: u64,
+}
+
+/// What `archive_position` keeps of a closed position: its final stats and
+/// hashes committing to the last data of the accounts it closed.
+#[account]
+#[derive(InitSpace)]
+pub struct ArchiveRecord {
+    /// PDA bump seed
+    pub bump: u8,
+    pub owner: Pubkey,
+    /// The archived position's address is derived from it and `position_id`
+    pub registrant: Pubkey,
+    pub position_id: u32,
+    pub metadata: PositionMetadata,
+    /// Unix timestamp of the position's last health check
+    pub last_check: i64,
+    /// Entries the risk history held, 0 without one
+    pub history_len: u32,
+    /// Check fees the position's subscription paid keepers
+    pub fees_paid: u64,
+    /// Bounties the position's check bounty escrow paid keepers
+    pub bounties_paid: u64,
+    /// SHA-256 of the position account's data, discriminator included
+    pub position_hash: [u8; 32],
+    /// SHA-256 of the risk history account's data; zero without one
+    pub history_hash: [u8; 32],
+    pub archived_at: i64,
+}
+
+impl ArchiveRecord {
+    /// Whether `position` and `history` are the data of the accounts the
+    /// record was archived from, as fetched before the archiving slot.
+    pub fn verify(&self, position: &[u8], history: Option<&[u8]>) -> bool {
+        let history_hash = history.map_or([0; 32], |data| hash(data).to_bytes());
+        hash(position).to_bytes() == self.position_hash && history_hash == self.history_hash
+    }
+}
+
+/// The last `ADMIN_AUDIT_LOG_LEN` administrative changes, so users can verify
+/// what changed the parameters their risk is scored with, and when. Entries
+/// fill from index 0 and then wrap around at `head`.
+#[account(zero_copy)]
+pub struct AdminAuditLog {
+    /// PDA bump seed
+    pub bump: u8,
+    pub _padding: [u8; 3],
+    /// Index the next entry is written to
+    pub head: u32,
+    /// Changes recorded since the log was created, including overwritten ones
+    pub total: u64,
+    pub entries: [AdminAuditEntry; ADMIN_AUDIT_LOG_LEN],
+}
+
+#[zero_copy]
+pub struct AdminAuditEntry {
+    /// Signer of the change
+    pub authority: Pubkey,
+    /// SHA-256 of the value before the change (its zeroed default when the
+    /// account was created by it)
+    pub old_hash: [u8; 32],
+    /// SHA-256 of the value after the change
+    pub new_hash: [u8; 32],
+    pub slot: u64,
+    /// An `AdminField`
+    pub field: u8,
+    pub _padding: [u8; 7],
+}
+
+/// What an `AdminAuditEntry` changed, and the value its hashes are of.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum AdminField {
+    /// `MonitoringPlan::fee_per_check`, little-endian
+    MonitoringPlanFee = 0,
+    /// `GlobalConfig::max_staleness_secs`, little-endian
+    DefaultMaxStaleness = 1,
+    /// A `ProtocolAdapter`'s `(protocol_program, layout)`, Borsh-serialized
+    ProtocolAdapter = 2,
+    /// `GlobalConfig::update_delay_secs`, little-endian
+    ConfigUpdateDelay = 3,
+    /// A `ProtocolTvlSnapshot`'s `(protocol_program, updater)`, Borsh-serialized
+    TvlUpdater = 4,
+    /// `GlobalConfig`'s `(config_version, risk_model)`, Borsh-serialized
+    RiskModel = 5,
+    /// `GlobalConfig::paused`, as one byte
+    Paused = 6,
+    /// `GlobalConfig::circuit_version`, little-endian
+    CircuitVersion = 7,
+    /// A `ProtocolAdapter`'s `(protocol_program, blackouts)`, Borsh-serialized
+    ProtocolBlackouts = 8,
+    /// `RiskHeatmap::classes`, Borsh-serialized
+    HeatmapClasses = 9,
+    /// A `FeedMigration`'s `(old_feed, replacement)`, Borsh-serialized
+    PriceFeedReplacement = 10,
+    /// A `KeeperAccount`'s `(keeper, bond)`, then `(keeper, bond, evidence)`
+    /// after slashing, Borsh-serialized
+    KeeperSlash = 11,
+    /// `ReceiptRegistry::mint`, from the default key when registered
+    ReceiptMint = 12,
+    /// `RiskModelConfig`'s `(weights, severity_bands)`, Borsh-serialized
+    RiskModelConfig = 13,
+    /// `MonitoringPlan::referral_share_bps`, little-endian
+    MonitoringPlanReferralShare = 14,
+}
+
+impl AdminAuditLog {
+    /// Overwrites the oldest entry once the buffer is full.
+    pub fn record(
+        &mut self,
+        authority: Pubkey,
+        field: AdminField,
+        old: &[u8],
+        new: &[u8],
+        slot: u64,
+    ) {
+        self.entries[self.head as usize] = AdminAuditEntry {
+            authority,
+            old_hash: hash(old).to_bytes(),
+            new_hash: hash(new).to_bytes(),
+            slot,
+            field: field as u8,
+            _padding: [0; 7],
+        };
+        self.head = (self.head + 1) % ADMIN_AUDIT_LOG_LEN as u32;
+        self.total += 1;
+    }
+}
+
+/// An owner's compiled rule set, read by `check_health_with_rules`.
+#[account]
+#[derive(InitSpace)]
+pub struct RuleSetAccount {
+    /// PDA bump seed
+    pub bump: u8,
+    /// Owner's public key
+    pub owner: Pubkey,
+    /// Parameter vector, laid out as described in `rules`
+    pub params: [u64; rules::RULE_PARAMS_LEN],
+}
+
+/// How to read obligations of one lending protocol, for
+/// `check_health_from_protocol`.
+#[account]
+#[derive(InitSpace)]
+pub struct ProtocolAdapter {
+    /// PDA bump seed
+    pub bump: u8,
+    /// Program that owns the protocol's obligation accounts
+    pub protocol_program: Pubkey,
+    pub layout: protocols::ObligationLayout,
+    /// Maintenance windows, during which checks are deferred
+    #[max_len(MAX_BLACKOUT_WINDOWS)]
+    pub blackouts: Vec<BlackoutWindow>,
+}
+
+/// Most maintenance windows a protocol can have scheduled at once.
+pub const MAX_BLACKOUT_WINDOWS: usize = 4;
+
+/// A protocol's maintenance window, `[starts_at, ends_at)` in Unix seconds.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub struct BlackoutWindow {
+    pub starts_at: i64,
+    pub ends_at: i64,
+}
+
+impl ProtocolAdapter {
+    /// The window open at `now`, if any.
+    pub fn blackout_at(&self, now: i64) -> Option<BlackoutWindow> {
+        self.blackouts
+            .iter()
+            .copied()
+            .find(|w| w.starts_at <= now && now < w.ends_at)
+    }
+}
+
+/// A lending protocol's public TVL as posted by its updater, for TVL exodus
+/// detection in `check_health`.
+#[account]
+#[derive(InitSpace)]
+pub struct ProtocolTvlSnapshot {
+    /// PDA bump seed
+    pub bump: u8,
+    pub protocol_program: Pubkey,
+    /// Only signer allowed to post snapshots
+    pub updater: Pubkey,
+    /// Latest TVL in USD cents
+    pub tvl: u64,
+    /// TVL of the snapshot before, 0 until there have been two
+    pub previous_tvl: u64,
+    /// Unix timestamp of the latest snapshot
+    pub updated_at: i64,
+}
+
+/// A Pyth feed flagged as no longer publishing and, once the upgrade
+/// authority names one, its replacement. Positions on the old feed are
+/// moved over by `repoint_price_feeds`.
+#[account]
+#[derive(InitSpace)]
+pub struct FeedMigration {
+    /// PDA bump seed
+    pub bump: u8,
+    pub old_feed: Pubkey,
+    /// Signer of `flag_price_feed`
+    pub flagged_by: Pubkey,
+    /// Unix timestamp the feed was flagged at
+    pub flagged_at: i64,
+    /// Feed positions move to, `None` until `migrate_price_feed`
+    pub replacement: Option<Pubkey>,
+    /// Unix timestamp of the latest `migrate_price_feed`, 0 before
+    pub migrated_at: i64,
+    /// Positions re-pointed so far
+    pub repointed: u32,
+}
+
+/// Aggregated registrations attributed to a referrer.
+#[account]
+#[derive(InitSpace)]
+pub struct ReferralStats {
+    /// PDA bump seed
+    pub bump: u8,
+    /// Referrer's public key
+    pub referrer: Pubkey,
+    /// Number of positions registered with this referrer
+    pub referred_positions: u64,
+    /// Unix timestamp of the most recent referred registration
+    pub last_referral: i64,
+}
+
+/// The latest owner reveal of a position, with everything needed to decrypt
+/// it, so a wallet can read the result without searching transaction logs.
+#[account]
+#[derive(InitSpace)]
+pub struct RevealInbox {
+    /// PDA bump seed
+    pub bump: u8,
+    pub position: Pubkey,
+    pub owner: Pubkey,
+    /// x25519 public key the envelope is encrypted to; zero until the first
+    /// reveal is delivered
+    pub encryption_key: [u8; 32],
+    pub computation_offset: u64,
+    /// Unix timestamp of delivery, 0 before the first
+    pub delivered_at: i64,
+    pub envelope: EncryptedEnvelope,
+    /// The owner's `ActionMemo` as it was when a risk reveal was delivered,
+    /// if the reveal attached it; `None` after other reveals. Inboxes
+    /// created before this field read as `None` and are grown by the first
+    /// reveal that attaches a memo.
+    pub memo: Option<EncryptedMemo>,
+}
+
+/// The owner's plan for when the position turns critical, attached to risk
+/// reveals in the `RevealInbox`. The program never reads it.
+#[account]
+#[derive(InitSpace)]
+pub struct ActionMemo {
+    /// PDA bump seed
+    pub bump: u8,
+    pub position: Pubkey,
+    pub owner: Pubkey,
+    pub memo: EncryptedMemo,
+    /// Unix timestamp of the last `set_action_memo`
+    pub updated_at: i64,
+}
+
+/// Text the owner encrypted to itself: Rescue ciphertexts under the shared
+/// secret of `encryption_key` with itself, so no one else, the MXE
+/// included, can decrypt them. The layout of the plaintext is up to the
+/// client.
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Debug, Default, PartialEq, Eq)]
+pub struct EncryptedMemo {
+    /// x25519 public key of the owner's key the memo is encrypted with
+    pub encryption_key: [u8; 32],
+    pub nonce: u128,
+    #[max_len(MAX_MEMO_CIPHERTEXTS)]
+    pub ciphertexts: Vec<[u8; 32]>,
+}
+
+/// The latest solvency attestation of a position: whether its health factor
+/// was at least `min_health_factor_bps`, as signed by the MPC cluster, and
+/// nothing else about it. Counterparties read it directly.
+#[account]
+#[derive(InitSpace)]
+pub struct AttestationAccount {
+    /// PDA bump seed
+    pub bump: u8,
+    pub position: Pubkey,
+    pub owner: Pubkey,
+    /// Threshold attested against, in basis points (12_000 = 1.2)
+    pub min_health_factor_bps: u32,
+    /// Whether the health factor was at least `min_health_factor_bps`
+    pub healthy: bool,
+    /// Unix timestamp the attested health factor was computed at
+    pub health_factor_at: i64,
+    /// Slot the attestation was recorded at, 0 before the first
+    pub slot: u64,
+    pub attested_at: i64,
+    /// Threshold of the attestation in flight
+    pub requested_min_health_factor_bps: u32,
+}
+
+impl AttestationAccount {
+    /// Whether this attests a health factor of at least
+    /// `min_health_factor_bps`, computed no earlier than `computed_after`.
+    pub fn attests(&self, min_health_factor_bps: u32, computed_after: i64) -> bool {
+        self.healthy
+            && self.min_health_factor_bps >= min_health_factor_bps
+            && self.health_factor_at >= computed_after
+    }
+}
+
+/// Read access the owner granted `viewer`, e.g. an auditor, to the
+/// position's risk state. The latest reveal to the viewer is kept here the
+/// way `RevealInbox` keeps the owner's. Revoking closes the account.
+#[account]
+#[derive(InitSpace)]
+pub struct ViewerGrant {
+    /// PDA bump seed
+    pub bump: u8,
+    pub position: Pubkey,
+    /// Owner who granted access
+    pub owner: Pubkey,
+    /// Wallet allowed to request reveals
+    pub viewer: Pubkey,
+    /// x25519 public key reveals are encrypted to, chosen by the owner
+    pub encryption_key: [u8; 32],
+    /// Unix timestamp of the grant
+    pub granted_at: i64,
+    pub computation_offset: u64,
+    /// Unix timestamp of the latest delivery, 0 before the first
+    pub delivered_at: i64,
+    pub envelope: EncryptedEnvelope,
+}
+
+/// Owner-encrypted output as stored and emitted. `circuit_id` is the comp def
+/// offset of the circuit that produced it, which fixes what each ciphertext
+/// holds, so a client can tell output layouts apart as circuits change
+/// instead of misreading them.
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Debug, Default, PartialEq, Eq)]
+pub struct EncryptedEnvelope {
+    /// `ENVELOPE_VERSION` when written
+    pub version: u8,
+    pub circuit_id: u32,
+    pub nonce: u128,
+    #[max_len(MAX_ENVELOPE_CIPHERTEXTS)]
+    pub ciphertexts: Vec<[u8; 32]>,
+}
+
+impl EncryptedEnvelope {
+    pub fn new(circuit_id: u32, nonce: u128, ciphertexts: &[[u8; 32]]) -> Self {
+        Self {
+            version: ENVELOPE_VERSION,
+            circuit_id,
+            nonce,
+            ciphertexts: ciphertexts.to_vec(),
+        }
+    }
+}
+
+/// Owner reveals a sponsor has agreed to pay for.
+#[account]
+#[derive(InitSpace)]
+pub struct SponsorBudget {
+    /// PDA bump seed
+    pub bump: u8,
+    /// Sponsor's public key
+    pub sponsor: Pubkey,
+    /// Owner whose reveals are sponsored
+    pub owner: Pubkey,
+    /// Reveals left before the sponsor has to top up
+    pub remaining: u32,
+    /// Reveals sponsored so far
+    pub used: u64,
+}
+
+/// Keeper fee schedule, set by the upgrade authority.
+#[account]
+#[derive(InitSpace)]
+pub struct MonitoringPlan {
+    /// PDA bump seed
+    pub bump: u8,
+    /// Lamports a delegated keeper earns per `check_health`
+    pub fee_per_check: u64,
+    /// Share of the fee, in basis points, paid to the referrer of a position
+    /// registered with one instead of the keeper
+    pub referral_share_bps: u16,
+}
+
+/// Program-wide settings, created by `initialize_config`. Defaults are set
+/// by the upgrade authority through `propose_config_update`,
+/// `apply_config_update` and `publish_risk_model`; the pause switch and
+/// circuit version by `admin`.
+#[account]
+#[derive(InitSpace)]
+pub struct GlobalConfig {
+    /// PDA bump seed
+    pub bump: u8,
+    /// Oldest risk state `reveal_risk` reveals, in seconds since its check
+    pub max_staleness_secs: i64,
+    /// Seconds a proposed change waits before it can be applied
+    pub update_delay_secs: i64,
+    /// Change announced by `propose_config_update`, not applied yet
+    pub pending_update: Option<ConfigUpdate>,
+    /// Latest risk model version, 0 for `RiskModel::BUILT_IN`
+    pub config_version: u32,
+    /// Risk model `config_version`
+    pub risk_model: RiskModel,
+    /// Signer of `set_paused` and `set_circuit_version`
+    pub admin: Pubkey,
+    /// Whether queueing computations is stopped
+    pub paused: bool,
+    /// Version of the deployed circuits, stamped on the risk states they
+    /// produce
+    pub circuit_version: u32,
+}
+
+/// Default alert buffers for positions without an alert config of their
+/// own, published by the operator in numbered versions.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub struct RiskModel {
+    /// Buffer above the liquidation threshold for severity 3, in basis points
+    pub critical_buffer_bps: u64,
+    /// Buffer above the liquidation threshold for severity 2, in basis points
+    pub warning_buffer_bps: u64,
+    /// Position value in USD cents below which severity 1 fires
+    pub min_value_cents: u64,
+}
+
+impl RiskModel {
+    /// Version 0, in force until the operator publishes another.
+    pub const BUILT_IN: RiskModel = RiskModel {
+        critical_buffer_bps: 500,
+        warning_buffer_bps: 1_000,
+        min_value_cents: 100,
+    };
+
+    pub fn is_valid(&self) -> bool {
+        self.critical_buffer_bps <= self.warning_buffer_bps
+    }
+}
+
+/// Weights, summing to 100, of the signals `compute_risk_score` combines.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub struct RiskWeights {
+    pub liquidation_proximity: u8,
+    pub volatility: u8,
+    pub concentration: u8,
+    pub tvl_trend: u8,
+}
+
+impl RiskWeights {
+    /// In the order the circuit takes them.
+    pub fn as_array(&self) -> [u8; 4] {
+        [
+            self.liquidation_proximity,
+            self.volatility,
+            self.concentration,
+            self.tvl_trend,
+        ]
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.as_array().iter().map(|w| *w as u32).sum::<u32>() == 100
+    }
+}
+
+/// How `compute_risk_score` weighs its signals and bands the score into
+/// severities, public and set by the config's admin.
+#[account]
+#[derive(InitSpace)]
+pub struct RiskModelConfig {
+    /// PDA bump seed
+    pub bump: u8,
+    pub weights: RiskWeights,
+    /// Lowest scores of severity 1, 2 and 3
+    pub severity_bands: [u8; 3],
+    pub updated_at: i64,
+}
+
+/// A position's latest composite risk score, encrypted to the MXE like its
+/// risk state.
+#[account]
+#[derive(InitSpace)]
+pub struct RiskScoreAccount {
+    /// PDA bump seed
+    pub bump: u8,
+    pub position: Pubkey,
+    /// Score from 0 to 100, as a 32-byte ciphertext
+    pub score: [u8; 32],
+    /// Nonce of `score`
+    pub nonce: u128,
+    /// Unix timestamp of the score, 0 before the first
+    pub computed_at: i64,
+}
+
+/// A proposed `GlobalConfig` change.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub struct ConfigUpdate {
+    pub max_staleness_secs: i64,
+    pub update_delay_secs: i64,
+    /// Unix timestamp from which `apply_config_update` accepts it
+    pub apply_after: i64,
+}
+
+impl GlobalConfig {
+    /// Fails while the config at `account` is paused; otherwise returns its
+    /// circuit version, 0 while it doesn't exist.
+    pub fn require_active(account: &AccountInfo) -> Result<u32> {
+        if account.data_is_empty() {
+            return Ok(0);
+        }
+        let config = GlobalConfig::try_deserialize(&mut &account.data.borrow()[..])?;
+        require!(!config.paused, ErrorCode::ProgramPaused);
+        Ok(config.circuit_version)
+    }
+
+    /// Latest risk model version and the model of the config at `account`,
+    /// or version 0 with `RiskModel::BUILT_IN` while it doesn't exist.
+    pub fn risk_model(account: &AccountInfo) -> Result<(u32, RiskModel)> {
+        if account.data_is_empty() {
+            return Ok((0, RiskModel::BUILT_IN));
+        }
+        let config = GlobalConfig::try_deserialize(&mut &account.data.borrow()[..])?;
+        Ok((config.config_version, config.risk_model))
+    }
+
+    /// `max_staleness_secs` of the config at `account`, or
+    /// `DEFAULT_MAX_STALENESS_SECS` while it doesn't exist.
+    pub fn max_staleness(account: &AccountInfo) -> Result<i64> {
+        if account.data_is_empty() {
+            return Ok(DEFAULT_MAX_STALENESS_SECS);
+        }
+        // Only this program can allocate data at its PDA
+        let config = GlobalConfig::try_deserialize(&mut &account.data.borrow()[..])?;
+        Ok(config.max_staleness_secs)
+    }
+}
+
+/// A position's escrow for keeper fees. Its balance is the lamports above
+/// the rent-exempt minimum.
+#[account]
+#[derive(InitSpace)]
+pub struct Subscription {
+    /// PDA bump seed
+    pub bump: u8,
+    pub position: Pubkey,
+    pub owner: Pubkey,
+    /// Lamports paid out to keepers so far
+    pub fees_paid: u64,
+    /// Part of the balance granted as trial credits by the operator
+    pub trial_lamports: u64,
+    /// When unspent trial credits become sweepable
+    pub trial_expires_at: i64,
+    /// Fee held for the pending keeper check, paid out when it lands
+    pub pending_fee: u64,
+    /// Part of `pending_fee` that goes to the position's referrer
+    pub pending_referral_fee: u64,
+    /// Keeper that queued the pending check
+    pub pending_keeper: Option<Pubkey>,
+}
+
+impl Subscription {
+    /// Lamports of `account` available for fees and withdrawals.
+    pub fn balance(account: &AccountInfo) -> Result<u64> {
+        let rent = Rent::get()?.minimum_balance(account.data_len());
+        Ok(account.lamports().saturating_sub(rent))
+    }
+
+    /// Part of `subscription`'s balance its owner funded and can take back.
+    /// A held fee is spent from trial credits first.
+    pub fn owner_balance(subscription: &Account<Subscription>) -> Result<u64> {
+        let balance = Subscription::balance(&subscription.to_account_info())?;
+        Ok(balance.saturating_sub(subscription.trial_lamports.max(subscription.pending_fee)))
+    }
+
+    /// Part of `subscription`'s balance not held for a pending check.
+    pub fn available(subscription: &Account<Subscription>) -> Result<u64> {
+        let balance = Subscription::balance(&subscription.to_account_info())?;
+        Ok(balance.saturating_sub(subscription.pending_fee))
+    }
+
+    /// Holds `plan`'s fee for the check `keeper` is queueing, with the
+    /// plan's referral share of it set aside if the position has a
+    /// `referrer`. The position is idle when a check is queued, so a fee
+    /// still held for an earlier one is given back first.
+    pub fn hold_fee(
+        subscription: &mut Account<Subscription>,
+        plan: &MonitoringPlan,
+        keeper: Pubkey,
+        referrer: Option<Pubkey>,
+    ) -> Result<()> {
+        subscription.release_fee();
+        let fee = plan.fee_per_check;
+        require!(
+            Subscription::balance(&subscription.to_account_info())? >= fee,
+            ErrorCode::InsufficientSubscription
+        );
+        subscription.pending_fee = fee;
+        subscription.pending_referral_fee = match referrer {
+            Some(_) => (fee as u128 * plan.referral_share_bps as u128 / 10_000) as u64,
+            None => 0,
+        };
+        subscription.pending_keeper = Some(keeper);
+        Ok(())
+    }
+
+    /// Gives back the fee held for a check that produced no result.
+    pub fn release_fee(&mut self) {
+        self.pending_fee = 0;
+        self.pending_referral_fee = 0;
+        self.pending_keeper = None;
+    }
+
+    /// Pays out the fee held for `position`'s check, which just landed: the
+    /// referral share to `referrer` and the rest to `keeper`, each only if
+    /// it is the account the fee was held for. Returns the event for a
+    /// referral share paid.
+    pub fn pay_fee<'info>(
+        subscription: &mut Account<'info, Subscription>,
+        keeper: Option<&UncheckedAccount<'info>>,
+        referrer: Option<&UncheckedAccount<'info>>,
+        position: &PositionAccount,
+    ) -> Result<Option<ReferralFeePaid>> {
+        let pending_keeper = subscription.pending_keeper;
+        let referral_fee = subscription.pending_referral_fee;
+        let keeper_fee = subscription.pending_fee.saturating_sub(referral_fee);
+        subscription.release_fee();
+
+        let mut paid = 0;
+        if let Some(info) = keeper.filter(|info| Some(info.key()) == pending_keeper) {
+            subscription.sub_lamports(keeper_fee)?;
+            info.add_lamports(keeper_fee)?;
+            paid += keeper_fee;
+        }
+        let referrer =
+            referrer.filter(|info| Some(info.key()) == position.referrer && referral_fee > 0);
+        if let Some(info) = referrer {
+            subscription.sub_lamports(referral_fee)?;
+            info.add_lamports(referral_fee)?;
+            paid += referral_fee;
+        }
+        subscription.fees_paid = subscription.fees_paid.saturating_add(paid);
+        // Trial credits are spent first
+        subscription.trial_lamports = subscription.trial_lamports.saturating_sub(paid);
+
+        let now = Clock::get()?.unix_timestamp;
+        Ok(referrer.map(|info| ReferralFeePaid {
+            referrer: info.key(),
+            owner: position.owner,
+            position_id: position.position_id,
+            lamports: referral_fee,
+            timestamp: now,
+        }))
+    }
+
+    /// Refunds `subscription`'s owner balance to `owner`, closing it unless
+    /// unexpired trial credits keep it open until they are swept. Returns
+    /// the balance refunded.
+    pub fn refund_owner<'info>(
+        subscription: &mut Account<'info, Subscription>,
+        owner: &Signer<'info>,
+    ) -> Result<u64> {
+        subscription.release_fee();
+        let lamports = Subscription::owner_balance(subscription)?;
+        if subscription.trial_lamports == 0 {
+            subscription.close(owner.to_account_info())?;
+        } else {
+            subscription.sub_lamports(lamports)?;
+            owner.add_lamports(lamports)?;
+        }
+        Ok(lamports)
+    }
+}
+
+/// A position's escrow for keeper bounties, funded by its owner. Its
+/// balance is the lamports above the rent-exempt minimum.
+#[account]
+#[derive(InitSpace)]
+pub struct CheckBounty {
+    /// PDA bump seed
+    pub bump: u8,
+    pub position: Pubkey,
+    pub owner: Pubkey,
+    /// Lamports paid for each check that lands
+    pub check_bounty_lamports: u64,
+    /// Payer of the pending check, paid the bounty when it lands
+    pub pending_payee: Option<Pubkey>,
+    /// Lamports paid out to keepers so far
+    pub bounties_paid: u64,
+}
+
+impl CheckBounty {
+    /// The bounty, if `bounty`'s balance covers it.
+    pub fn payable(bounty: &Account<CheckBounty>) -> Result<Option<u64>> {
+        let balance = Subscription::balance(&bounty.to_account_info())?;
+        let lamports = bounty.check_bounty_lamports;
+        Ok((balance >= lamports).then_some(lamports))
+    }
+}
+
+/// Which region's keeper checks one shard of a keeper key's positions.
+#[account]
+#[derive(InitSpace)]
+pub struct KeeperLease {
+    /// PDA bump seed
+    pub bump: u8,
+    /// Key the keepers of every region sign with
+    pub keeper: Pubkey,
+    pub shard: u16,
+    /// Region holding the lease; lower regions have priority
+    pub region: u8,
+    /// Unix timestamp the lease runs out at unless renewed
+    pub expires_at: i64,
+}
+
+impl KeeperLease {
+    /// Whether `region` may take or renew the lease at `now`: as its holder,
+    /// after it ran out, or by priority.
+    pub fn may_take(&self, region: u8, now: i64) -> bool {
+        region <= self.region || now >= self.expires_at
+    }
+}
+
+/// A keeper in the public registry. Its bond is the account's lamports
+/// above rent, which the admin can slash for provably wrong position data.
+#[account]
+#[derive(InitSpace)]
+pub struct KeeperAccount {
+    /// PDA bump seed
+    pub bump: u8,
+    pub keeper: Pubkey,
+    /// Unix timestamp of the first bond
+    pub registered_at: i64,
+    /// Unix timestamp the bond becomes withdrawable at; 0 unless unbonding
+    pub unbonding_at: i64,
+    /// Lamports slashed over the keeper's lifetime
+    pub slashed_lamports: u64,
+    pub slash_count: u32,
+}
+
+impl KeeperAccount {
+    /// Whether the keeper counts as registered: not unbonding, and bonded
+    /// with at least `MIN_KEEPER_BOND_LAMPORTS`, which slashing can undo.
+    pub fn is_bonded(account: &Account<Self>) -> Result<bool> {
+        Ok(account.unbonding_at == 0
+            && Subscription::balance(&account.to_account_info())? >= MIN_KEEPER_BOND_LAMPORTS)
+    }
+}
+
+/// An instruction run for the position when its risk is revealed as
+/// critical, by `execute_auto_action` once `reveal_risk_callback` made it
+/// due. The account signs the instruction.
+#[account]
+#[derive(InitSpace)]
+pub struct AutoActionConfig {
+    /// PDA bump seed
+    pub bump: u8,
+    pub position: Pubkey,
+    pub owner: Pubkey,
+    /// Program the instruction is sent to
+    pub program_id: Pubkey,
+    #[max_len(MAX_AUTO_ACTION_ACCOUNTS)]
+    pub accounts: Vec<AutoActionAccount>,
+    #[max_len(MAX_AUTO_ACTION_DATA)]
+    pub data: Vec<u8>,
+    /// Severity of the critical reveal the instruction is due for, 0 when
+    /// it isn't due
+    pub due_severity: u8,
+}
+
+/// One account of an `AutoActionConfig` instruction.
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AutoActionAccount {
+    pub pubkey: Pubkey,
+    /// Only the config account itself can sign
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+impl AutoActionConfig {
+    /// Whether the template fits the account and can be signed by `config`.
+    /// Calls back into this program are refused.
+    pub fn is_valid_template(
+        config: &Pubkey,
+        program_id: &Pubkey,
+        accounts: &[AutoActionAccount],
+        data: &[u8],
+    ) -> bool {
+        *program_id != crate::ID
+            && accounts.len() <= MAX_AUTO_ACTION_ACCOUNTS
+            && data.len() <= MAX_AUTO_ACTION_DATA
+            && accounts
+                .iter()
+                .all(|account| !account.is_signer || account.pubkey == *config)
+    }
+
+    pub fn instruction(&self) -> anchor_lang::solana_program::instruction::Instruction {
+        anchor_lang::solana_program::instruction::Instruction {
+            program_id: self.program_id,
+            accounts: self
+                .accounts
+                .iter()
+                .map(|account| AccountMeta {
+                    pubkey: account.pubkey,
+                    is_signer: account.is_signer,
+                    is_writable: account.is_writable,
+                })
+                .collect(),
+            data: self.data.clone(),
+        }
+    }
+}
+
+/// An encrypted stop-loss order of a position, evaluated against its
+/// health factor by `evaluate_stop_loss`. The account signs the order sent
+/// to its executor.
+#[account]
+#[derive(InitSpace)]
+pub struct StopLossAccount {
+    /// PDA bump seed
+    pub bump: u8,
+    /// trigger_health_factor_bps, action, max_slippage_bps, encrypted to the
+    /// MXE under `encryption_pubkey`
+    pub config: [[u8; 32]; 3],
+    pub encryption_pubkey: [u8; 32],
+    pub nonce: u128,
+    pub position: Pubkey,
+    pub owner: Pubkey,
+    /// Program the order is sent to when it fires, if any
+    pub executor: Option<Pubkey>,
+    /// Accounts of the order. Only this account can sign, so executors that
+    /// check who placed the order list it as a signer.
+    #[max_len(MAX_AUTO_ACTION_ACCOUNTS)]
+    pub executor_accounts: Vec<AutoActionAccount>,
+    /// Unix timestamp of the last evaluation, 0 before the first
+    pub last_evaluated_at: i64,
+    /// Unix timestamp the order fired at, 0 while armed
+    pub triggered_at: i64,
+    /// Revealed action of the fired order
+    pub action: u64,
+    /// Revealed maximum slippage of the fired order, basis points
+    pub max_slippage_bps: u64,
+    /// Unix timestamp `execute_stop_loss` sent the fired order at, 0 before
+    pub executed_at: i64,
+}
+
+impl StopLossAccount {
+    /// Whether the order fired and `execute_stop_loss` hasn't sent it yet.
+    pub fn is_due(&self) -> bool {
+        self.triggered_at != 0 && self.executed_at == 0
+    }
+
+    /// The order for the executor: `STOP_LOSS_EXECUTE_DISCRIMINATOR` and the
+    /// revealed `action` and `max_slippage_bps`, over `executor_accounts`.
+    pub fn instruction(
+        &self,
+        action: u64,
+        max_slippage_bps: u64,
+    ) -> anchor_lang::solana_program::instruction::Instruction {
+        let mut data = STOP_LOSS_EXECUTE_DISCRIMINATOR.to_vec();
+        data.extend_from_slice(&action.to_le_bytes());
+        data.extend_from_slice(&max_slippage_bps.to_le_bytes());
+        anchor_lang::solana_program::instruction::Instruction {
+            program_id: self.executor.unwrap_or_default(),
+            accounts: self
+                .executor_accounts
+                .iter()
+                .map(|account| AccountMeta {
+                    pubkey: account.pubkey,
+                    is_signer: account.is_signer,
+                    is_writable: account.is_writable,
+                })
+                .collect(),
+            data,
+        }
+    }
+}
+
+/// One position of a `check_health_batch`, encrypted like the
+/// `encrypted_position` of `check_health`. Every position needs its own nonce.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EncryptedPositionInput {
+    /// value, collateral_ratio, threshold
+    pub ciphertexts: [[u8; 32]; POSITION_FIELD_COUNT],
+    pub nonce: u128,
+}
+
+/// Protective action matching a revealed severity.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ActionType {
+    EmergencyWithdraw,
+    Deleverage,
+    AddCollateral,
+    None,
+}
+
+impl ActionType {
+    pub fn for_severity(severity: u8) -> Self {
+        match severity {
+            0 => ActionType::None,
+            1 => ActionType::AddCollateral,
+            2 => ActionType::Deleverage,
+            _ => ActionType::EmergencyWithdraw,
+        }
+    }
+}
+
+/// What a `ValueProtected` estimate credits.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProtectionTrigger {
+    /// The position's auto action ran on a critical reveal
+    AutoAction,
+    /// The owner acted on an `ActionRequired` alert
+    AlertAcknowledged,
+}
+
+/// Coarse position size, so value-protected figures can be reported without
+/// exact sizes.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub enum SizeBucket {
+    /// Under $1k
+    Under1k,
+    /// $1k to $10k
+    From1kTo10k,
+    /// $10k to $100k
+    From10kTo100k,
+    /// $100k and up
+    Over100k,
+}
+
+impl SizeBucket {
+    /// Bucket numbered `index` by `reveal_size_bucket`.
+    pub fn from_index(index: u8) -> Self {
+        match index {
+            0 => Self::Under1k,
+            1 => Self::From1kTo10k,
+            2 => Self::From10kTo100k,
+            _ => Self::Over100k,
+        }
+    }
+
+    /// Bucket of a position worth `value` USD cents.
+    pub fn of(value: u64) -> Self {
+        match value {
+            0..100_000 => Self::Under1k,
+            100_000..1_000_000 => Self::From1kTo10k,
+            1_000_000..10_000_000 => Self::From10kTo100k,
+            _ => Self::Over100k,
+        }
+    }
+
+    /// Least value in the bucket, in USD cents; estimates start from it so
+    /// they never overstate.
+    pub fn floor_usd_cents(self) -> u64 {
+        match self {
+            Self::Under1k => 0,
+            Self::From1kTo10k => 100_000,
+            Self::From10kTo100k => 1_000_000,
+            Self::Over100k => 10_000_000,
+        }
+    }
+}
+
+/// Return data set by `reveal_risk_callback`, Borsh-encoded.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RevealStatus {
+    /// Position the risk was revealed for
+    pub position: Pubkey,
+    pub is_at_risk: bool,
+}
+
+// ─── Errors ───
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Invalid authority")]
+    InvalidAuthority,
+    #[msg("The computation was aborted")]
+    AbortedComputation,
+    #[msg("Cluster not set")]
+    ClusterNotSet,
+    #[msg("Position is not active")]
+    PositionInactive,
+    #[msg("Referral stats account must be provided exactly when a referrer is")]
+    ReferralStatsMismatch,
+    #[msg("Owners cannot refer themselves")]
+    SelfReferral,
+    #[msg("Mempool account is not an Arcium mempool")]
+    InvalidMempoolAccount,
+    #[msg("Executing pool account is not an Arcium executing pool")]
+    InvalidExecpoolAccount,
+    #[msg("Computation account is not in the expected state")]
+    InvalidComputationAccount,
+    #[msg("Position state invariant violated")]
+    InvariantViolation,
+    #[msg("A computation on this position is still pending")]
+    ComputationPending,
+    #[msg("Portfolio already holds the maximum number of positions")]
+    PortfolioFull,
+    #[msg("Position is already in the portfolio")]
+    PositionAlreadyInPortfolio,
+    #[msg("Position is not in the portfolio")]
+    PositionNotInPortfolio,
+    #[msg("Portfolio has no positions")]
+    EmptyPortfolio,
+    #[msg("Remaining accounts must be the portfolio positions, in order")]
+    PortfolioPositionMismatch,
+    #[msg("Position has no price feed configured")]
+    PriceFeedNotSet,
+    #[msg("Price account is not a verified Pyth price update")]
+    InvalidPriceAccount,
+    #[msg("Price account is not the position's price feed")]
+    PriceFeedMismatch,
+    #[msg("Price update is too old")]
+    StalePrice,
+    #[msg("Oracle price is not positive or out of range")]
+    InvalidOraclePrice,
+    #[msg("Signer is neither the owner nor an active keeper for this position")]
+    UnauthorizedKeeper,
+    #[msg("Position has no cross-chain emitter configured")]
+    CrossChainEmitterNotSet,
+    #[msg("Account is not a posted Wormhole VAA with a position summary")]
+    InvalidPostedVaa,
+    #[msg("VAA was not emitted by the position's cross-chain emitter")]
+    CrossChainEmitterMismatch,
+    #[msg("Cross-chain summary is for a different position")]
+    CrossChainPositionMismatch,
+    #[msg("Cross-chain summary is too old")]
+    StaleCrossChainSummary,
+    #[msg("No computation is pending on this position")]
+    NoPendingComputation,
+    #[msg("Pending computation has not timed out yet")]
+    PendingNotExpired,
+    #[msg("Rule set parameters are malformed")]
+    InvalidRuleSet,
+    #[msg("Risk history has no entries yet")]
+    EmptyRiskHistory,
+    #[msg("Arcium cluster is degraded; new computations are paused")]
+    ClusterDegraded,
+    #[msg("Callback is not for the computation Arcium completed")]
+    CallbackComputationMismatch,
+    #[msg("Callback is for a computation the account is no longer waiting on")]
+    StaleCallback,
+    #[msg("Sponsor budget exhausted")]
+    SponsorBudgetExhausted,
+    #[msg("Keeper checks need the monitoring plan and the position's subscription")]
+    SubscriptionRequired,
+    #[msg("Subscription balance is too low")]
+    InsufficientSubscription,
+    #[msg("Obligation layout is malformed")]
+    InvalidProtocolAdapter,
+    #[msg("Account is not an obligation of the adapter's protocol")]
+    InvalidObligation,
+    #[msg("Obligation belongs to a different owner than the position")]
+    ObligationOwnerMismatch,
+    #[msg("Obligation is stale; refresh it first")]
+    StaleObligation,
+    #[msg("Auto action is too large, calls this program or needs another signer")]
+    InvalidAutoAction,
+    #[msg("Owner account is not the position's owner")]
+    PositionOwnerMismatch,
+    #[msg("Position transfer was not offered to this signer")]
+    NotPendingOwner,
+    #[msg("Batch needs 1 to 4 distinct, writable positions, one per input")]
+    InvalidBatch,
+    #[msg("Action severity must be between 1 (low) and 3 (critical)")]
+    InvalidActionSeverity,
+    #[msg("Lease is held by another region")]
+    LeaseHeld,
+    #[msg("Lease duration must be between 1 second and MAX_LEASE_SECS")]
+    InvalidLeaseDuration,
+    #[msg("Risk state is older than the position's staleness limit; check it first")]
+    StaleRiskState,
+    #[msg("Staleness limit must be positive")]
+    InvalidStaleness,
+    #[msg("Health factor has not been computed yet")]
+    HealthFactorNotComputed,
+    #[msg("Config update delay must be positive")]
+    InvalidUpdateDelay,
+    #[msg("No config update has been proposed")]
+    NoPendingConfigUpdate,
+    #[msg("Config update delay has not passed yet")]
+    ConfigUpdateNotReady,
+    #[msg("TVL drop must be between 1 and 10000 basis points")]
+    InvalidTvlDrop,
+    #[msg("Position does not follow a protocol's TVL")]
+    TvlProtocolNotSet,
+    #[msg("TVL snapshot is not of the position's protocol")]
+    TvlProtocolMismatch,
+    #[msg("Critical buffer must not exceed the warning buffer")]
+    InvalidRiskModel,
+    #[msg("Only the latest risk model version can be accepted")]
+    ConfigVersionMismatch,
+    #[msg("Trial duration must be positive")]
+    InvalidTrialDuration,
+    #[msg("Trial credits have not expired yet")]
+    TrialNotExpired,
+    #[msg("Nonce must be newer than the position's")]
+    StaleNonce,
+    #[msg("Queueing computations is paused")]
+    ProgramPaused,
+    #[msg("Circuit version must increase")]
+    InvalidCircuitVersion,
+    #[msg("Blackout windows must end after they start, at most MAX_BLACKOUT_WINDOWS")]
+    InvalidBlackoutWindow,
+    #[msg("The risk heatmap is sampling an epoch")]
+    HeatmapEpochInProgress,
+    #[msg("Position was already sampled this heatmap epoch")]
+    PositionAlreadySampled,
+    #[msg("Too few positions sampled to publish the heatmap")]
+    HeatmapTooSparse,
+    #[msg("Price shock must be between 1 and 10000 basis points")]
+    InvalidPriceShock,
+    #[msg("Price feed is still publishing")]
+    PriceFeedNotDeprecated,
+    #[msg("Price feed has no replacement yet")]
+    PriceFeedNotMigrated,
+    #[msg("A price feed cannot replace itself")]
+    InvalidPriceFeedReplacement,
+    #[msg("Owners read their positions through reveal_risk_to_owner")]
+    InvalidViewer,
+    #[msg("Attestation threshold must be a health factor of at least 1.0")]
+    InvalidAttestationThreshold,
+    #[msg("Liquidation penalty must be between 1 and 10000 basis points")]
+    InvalidLiquidationPenalty,
+    #[msg("Position was checked too recently")]
+    CheckTooSoon,
+    #[msg("Owner has not allowed revealing the position's size bucket")]
+    SizeDisclosureNotAllowed,
+    #[msg("Size bucket differs from the one revealed for the position")]
+    SizeBucketMismatch,
+    #[msg("Account is not a position")]
+    InvalidPositionAccount,
+    #[msg("Position already has the current layout")]
+    PositionUpToDate,
+    #[msg("Stop-loss executor accounts are invalid")]
+    InvalidStopLossExecutor,
+    #[msg("Stop-loss has already fired; set it again to re-arm")]
+    StopLossAlreadyTriggered,
+    #[msg("Action memo must hold 1 to MAX_MEMO_CIPHERTEXTS ciphertexts")]
+    InvalidActionMemo,
+    #[msg("Check bounty must be greater than zero")]
+    InvalidCheckBounty,
+    #[msg("Keeper bond must reach MIN_KEEPER_BOND_LAMPORTS")]
+    KeeperBondTooSmall,
+    #[msg("Keeper is already unbonding")]
+    KeeperUnbonding,
+    #[msg("Keeper bond can only be withdrawn after unbonding")]
+    KeeperBondLocked,
+    #[msg("Position has no heartbeat deadline or was checked within it")]
+    HeartbeatDeadlineNotMissed,
+    #[msg("Position's monitoring lapse is already flagged")]
+    MonitoringLapseFlagged,
+    #[msg("Position must be deactivated first")]
+    PositionStillActive,
+    #[msg("Computation was finalized; its callback failed rather than expired")]
+    ComputationFinalized,
+    #[msg("Position already has a monitoring receipt")]
+    ReceiptAlreadyMinted,
+    #[msg("Position has no monitoring receipt")]
+    NoReceipt,
+    #[msg("Burn the position's monitoring receipt first")]
+    ReceiptOutstanding,
+    #[msg("This page of the wallet's position index is full, register into the next one")]
+    OwnerIndexFull,
+    #[msg("The position is listed in its registrant's index, which must be passed")]
+    OwnerIndexMissing,
+    #[msg("Risk weights must sum to 100")]
+    InvalidRiskWeights,
+    #[msg("Severity bands must ascend strictly from at least 1 to at most 100")]
+    InvalidSeverityBands,
+    #[msg("Risk score account belongs to another position")]
+    RiskScorePositionMismatch,
+    #[msg("Referral share must be at most 10000 basis points")]
+    InvalidReferralShare,
+    #[msg("A position index page can only be created after the page before it")]
+    OwnerIndexPageGap,
+    #[msg("No critical reveal made this auto action due")]
+    AutoActionNotDue,
+    #[msg("The stop-loss has no fired order left to send to an executor")]
+    StopLossNotDue,
+}
+
+// ─── Events ───
+
+#[event]
+pub struct PositionRegistered {
+    pub owner: Pubkey,
+    pub position_id: u32,
+    pub metadata: PositionMetadata,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReferralRecorded {
+    pub referrer: Pubkey,
+    pub owner: Pubkey,
+    pub position_id: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct HealthCheckCompleted {
+    pub owner: Pubkey,
+    pub position_id: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RiskHistorySummarized {
+    pub owner: Pubkey,
+    pub position_id: u32,
+    /// Health checks in the history
+    pub checks: u32,
+    /// How many of them were at risk
+    pub at_risk_checks: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProtocolAdapterRegistered {
+    pub protocol_program: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProtocolBlackoutsSet {
+    pub protocol_program: Pubkey,
+    pub windows: Vec<BlackoutWindow>,
+    pub timestamp: i64,
+}
+
+/// A protocol check was skipped because the protocol is in maintenance.
+#[event]
+pub struct CheckDeferred {
+    pub owner: Pubkey,
+    pub position_id: u32,
+    pub protocol_program: Pubkey,
+    pub resumes_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TvlUpdaterSet {
+    pub protocol_program: Pubkey,
+    pub updater: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TvlSnapshotUpdated {
+    pub protocol_program: Pubkey,
+    pub tvl: u64,
+    pub previous_tvl: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AlertConfigUpdated {
+    pub owner: Pubkey,
+    pub position_id: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RiskRevealed {
+    pub is_at_risk: bool,
+    pub timestamp: i64,
+}
+
+/// Risk state encrypted to the owner's key. Decrypt off-chain with a
+/// `RescueCipher` over the shared secret of that key and the MXE key.
+#[event]
+pub struct HealthFactorComputed {
+    pub owner: Pubkey,
+    pub position_id: u32,
+    pub timestamp: i64,
+}
+
+/// Health factor encrypted to the owner's key, decrypted like
+/// `RiskRevealedToOwner`.
+#[event]
+pub struct HealthFactorRevealedToOwner {
+    pub owner: Pubkey,
+    pub position_id: u32,
+    /// x25519 public key the envelope is encrypted to
+    pub encryption_key: [u8; 32],
+    /// `reveal_health_factor_to_owner` output: [health_factor_bps]
+    pub envelope: EncryptedEnvelope,
+    pub timestamp: i64,
+}
+
+/// Outcome of a what-if price shock encrypted to the owner's key, decrypted
+/// like `RiskRevealedToOwner`.
+#[event]
+pub struct StressTestRevealedToOwner {
+    pub owner: Pubkey,
+    pub position_id: u32,
+    /// x25519 public key the envelope is encrypted to
+    pub encryption_key: [u8; 32],
+    /// `stress_test_position` output: [would_liquidate, health_factor_bps]
+    pub envelope: EncryptedEnvelope,
+    pub timestamp: i64,
+}
+
+/// A position's solvency was attested; see `AttestationAccount`.
+#[event]
+pub struct HealthAttested {
+    pub owner: Pubkey,
+    pub position_id: u32,
+    pub min_health_factor_bps: u32,
+    pub healthy: bool,
+    /// Unix timestamp the attested health factor was computed at
+    pub health_factor_at: i64,
+    pub slot: u64,
+    pub timestamp: i64,
 }
 
-#[callback_accounts("init_risk_state")]
-#[derive(Accounts)]
-pub struct InitRiskStateCallback<'info> {
-    pub arcium_program: Program<'info, Arcium>,
-    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_INIT_RISK_STATE))]
-    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
-    #[account(address = derive_mxe_pda!())]
-    pub mxe_account: Account<'info, MXEAccount>,
-    /// CHECK: computation_account
-    pub computation_account: UncheckedAccount<'info>,
-    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
-    pub cluster_account: Account<'info, Cluster>,
-    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
-    /// CHECK: instructions_sysvar
-    pub instructions_sysvar: AccountInfo<'info>,
-    #[account(mut)]
-    pub position_acc: Account<'info, PositionAccount>,
+/// A price feed stopped publishing; see `flag_price_feed`.
+#[event]
+pub struct PriceFeedFlagged {
+    pub price_feed: Pubkey,
+    pub flagged_by: Pubkey,
+    pub timestamp: i64,
 }
 
-#[init_computation_definition_accounts("init_risk_state", payer)]
-#[derive(Accounts)]
-pub struct InitRiskStateCompDef<'info> {
-    #[account(mut)]
-    pub payer: Signer<'info>,
-    #[account(mut, address = derive_mxe_pda!())]
-    pub mxe_account: Box<Account<'info, MXEAccount>>,
-    #[account(mut)]
-    /// CHECK: comp_def_account
-    pub comp_def_account: UncheckedAccount<'info>,
-    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
-    /// CHECK: address_lookup_table
-    pub address_lookup_table: UncheckedAccount<'info>,
-    #[account(address = LUT_PROGRAM_ID)]
-    /// CHECK: lut_program
-    pub lut_program: UncheckedAccount<'info>,
-    pub arcium_program: Program<'info, Arcium>,
-    pub system_program: Program<'info, System>,
+#[event]
+pub struct PriceFeedMigrated {
+    pub old_feed: Pubkey,
+    pub new_feed: Pubkey,
+    pub timestamp: i64,
+}
+
+/// A position moved from a migrated feed to its replacement.
+#[event]
+pub struct PriceFeedRepointed {
+    pub owner: Pubkey,
+    pub position_id: u32,
+    pub old_feed: Pubkey,
+    pub new_feed: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ViewerGranted {
+    pub owner: Pubkey,
+    pub position_id: u32,
+    pub viewer: Pubkey,
+    /// x25519 public key reveals to the viewer are encrypted to
+    pub encryption_key: [u8; 32],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ViewerRevoked {
+    pub owner: Pubkey,
+    pub position_id: u32,
+    pub viewer: Pubkey,
+    pub timestamp: i64,
+}
+
+/// Risk state encrypted to a granted viewer's key, decrypted like
+/// `RiskRevealedToOwner`.
+#[event]
+pub struct RiskRevealedToViewer {
+    pub owner: Pubkey,
+    pub position_id: u32,
+    pub viewer: Pubkey,
+    /// x25519 public key the envelope is encrypted to
+    pub encryption_key: [u8; 32],
+    /// `reveal_risk_to_viewer` output: [is_at_risk, severity, last_price]
+    pub envelope: EncryptedEnvelope,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RiskRevealedToOwner {
+    pub owner: Pubkey,
+    pub position_id: u32,
+    /// x25519 public key the envelope is encrypted to
+    pub encryption_key: [u8; 32],
+    /// `reveal_risk_to_owner` output: [is_at_risk, severity]
+    pub envelope: EncryptedEnvelope,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PortfolioRiskAggregated {
+    pub owner: Pubkey,
+    pub positions: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct KeeperDelegated {
+    pub owner: Pubkey,
+    pub position_id: u32,
+    /// `None` when the delegation was revoked
+    pub keeper: Option<Pubkey>,
+    pub expiry_slot: Option<u64>,
+    pub timestamp: i64,
+}
+
+/// A region took over a shard. `previous_region` is `None` for a new lease.
+#[event]
+pub struct KeeperLeaseTaken {
+    pub keeper: Pubkey,
+    pub shard: u16,
+    pub region: u8,
+    pub previous_region: Option<u8>,
+    pub timestamp: i64,
+}
+
+/// `bond` is the keeper's whole bond after adding `lamports`.
+#[event]
+pub struct KeeperBonded {
+    pub keeper: Pubkey,
+    pub lamports: u64,
+    pub bond: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct KeeperUnbondingStarted {
+    pub keeper: Pubkey,
+    pub withdrawable_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct KeeperBondWithdrawn {
+    pub keeper: Pubkey,
+    pub lamports: u64,
+    pub timestamp: i64,
+}
+
+/// `evidence` is the hash of the dispute record the slash was decided on.
+#[event]
+pub struct KeeperSlashed {
+    pub keeper: Pubkey,
+    pub lamports: u64,
+    pub recipient: Pubkey,
+    pub evidence: [u8; 32],
+    pub timestamp: i64,
+}
+
+/// `new_owner` is `None` when the offer was withdrawn.
+#[event]
+pub struct PositionTransferProposed {
+    pub owner: Pubkey,
+    pub position_id: u32,
+    pub new_owner: Option<Pubkey>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PositionTransferred {
+    pub previous_owner: Pubkey,
+    pub owner: Pubkey,
+    /// Wallet the position's address is derived from
+    pub registrant: Pubkey,
+    pub position_id: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SponsorBudgetSet {
+    pub sponsor: Pubkey,
+    pub owner: Pubkey,
+    pub remaining: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MonitoringPlanUpdated {
+    pub fee_per_check: u64,
+    pub referral_share_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ConfigUpdateProposed {
+    pub authority: Pubkey,
+    pub max_staleness_secs: i64,
+    pub update_delay_secs: i64,
+    pub apply_after: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RiskModelPublished {
+    pub config_version: u32,
+    pub risk_model: RiskModel,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ConfigVersionAccepted {
+    pub owner: Pubkey,
+    pub position_id: u32,
+    pub config_version: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ConfigInitialized {
+    pub authority: Pubkey,
+    pub admin: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PauseSet {
+    pub admin: Pubkey,
+    pub paused: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CircuitVersionSet {
+    pub admin: Pubkey,
+    pub circuit_version: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct GlobalConfigUpdated {
+    pub max_staleness_secs: i64,
+    pub update_delay_secs: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SubscriptionFunded {
+    pub owner: Pubkey,
+    pub position_id: u32,
+    pub funder: Pubkey,
+    pub lamports: u64,
+    /// Balance after funding
+    pub balance: u64,
+    pub timestamp: i64,
+}
+
+/// A callback's output failed verification. The position (or portfolio) is
+/// no longer waiting on the computation, so it can be queued again.
+#[event]
+pub struct ComputationFailed {
+    /// The position's or portfolio's owner, or the `RiskHeatmap` address
+    pub owner: Pubkey,
+    /// `None` for the owner's portfolio and the heatmap
+    pub position_id: Option<u32>,
+    pub computation_offset: u64,
+    pub kind: ComputationKind,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RiskHeatmapConfigured {
+    pub classes: [Pubkey; HEATMAP_CLASSES],
+    pub epoch: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RiskHeatmapPublished {
+    pub epoch: u32,
+    pub classes: [Pubkey; HEATMAP_CLASSES],
+    /// At-risk positions per bucket, `None` where too few were sampled
+    pub at_risk: [Option<u64>; HEATMAP_BUCKETS],
+    pub sampled: [u32; HEATMAP_BUCKETS],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PositionLabelSet {
+    pub owner: Pubkey,
+    pub position_id: u32,
+    pub label_hash: [u8; 32],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EncryptionRotated {
+    pub owner: Pubkey,
+    pub position_id: u32,
+    /// Nonce the risk state is encrypted under now
+    pub nonce: u128,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TrialCreditsGranted {
+    pub owner: Pubkey,
+    pub position_id: u32,
+    pub lamports: u64,
+    pub expires_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TrialCreditsSwept {
+    pub owner: Pubkey,
+    pub position: Pubkey,
+    /// Expired trial credits returned to the operator
+    pub swept: u64,
+    /// Credits refunded to the owner of a closed position
+    pub refunded: u64,
+    pub position_closed: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SubscriptionWithdrawn {
+    pub owner: Pubkey,
+    pub position_id: u32,
+    pub lamports: u64,
+    /// Balance after the withdrawal
+    pub balance: u64,
+    pub timestamp: i64,
+}
+
+/// The subscription can no longer pay for a keeper check; keepers stop
+/// monitoring the position until it is funded again.
+#[event]
+pub struct SubscriptionDepleted {
+    pub owner: Pubkey,
+    pub position_id: u32,
+    pub balance: u64,
+    pub fee_per_check: u64,
+    pub timestamp: i64,
+}
+
+/// The position went `heartbeat_deadline_secs` without a check or
+/// heartbeat: its keeper may be down. `flagged_by` earned `bounty`
+/// lamports from the position's check bounty escrow for noticing.
+#[event]
+pub struct MonitoringLapsed {
+    pub owner: Pubkey,
+    pub position_id: u32,
+    pub last_check: i64,
+    pub last_seen: i64,
+    pub heartbeat_deadline_secs: u32,
+    pub flagged_by: Pubkey,
+    pub bounty: u64,
+    pub timestamp: i64,
+}
+
+/// The position pays `check_bounty_lamports` for each keeper check that
+/// lands, while `balance` lasts.
+#[event]
+pub struct CheckBountyFunded {
+    pub owner: Pubkey,
+    pub position_id: u32,
+    pub check_bounty_lamports: u64,
+    pub lamports: u64,
+    /// Balance after funding
+    pub balance: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CheckBountyPaid {
+    pub owner: Pubkey,
+    pub position_id: u32,
+    pub keeper: Pubkey,
+    pub lamports: u64,
+    /// Balance after the payment
+    pub balance: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CheckBountyClosed {
+    pub owner: Pubkey,
+    pub position_id: u32,
+    pub refunded: u64,
+    pub timestamp: i64,
+}
+
+/// `program_id` is `None` once the auto action is cleared.
+#[event]
+pub struct AutoActionUpdated {
+    pub owner: Pubkey,
+    pub position_id: u32,
+    pub program_id: Option<Pubkey>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AutoActionExecuted {
+    pub owner: Pubkey,
+    pub position_id: u32,
+    pub program_id: Pubkey,
+    pub severity: u8,
+    pub timestamp: i64,
+}
+
+/// `active` is false once the stop-loss is cleared.
+#[event]
+pub struct StopLossUpdated {
+    pub owner: Pubkey,
+    pub position_id: u32,
+    pub active: bool,
+    pub executor: Option<Pubkey>,
+    pub timestamp: i64,
+}
+
+/// A stop-loss order fired. `executor` is the program `execute_stop_loss`
+/// sends it to; when `None`, the order is for whoever watches the owner's
+/// events to carry out.
+#[event]
+pub struct StopLossTriggered {
+    pub owner: Pubkey,
+    pub position_id: u32,
+    pub position: Pubkey,
+    pub action: u64,
+    /// Basis points
+    pub max_slippage_bps: u64,
+    /// Unix timestamp the health factor compared was computed at
+    pub health_factor_at: i64,
+    pub executor: Option<Pubkey>,
+    pub timestamp: i64,
+}
+
+/// `active` is false once the action memo is cleared.
+#[event]
+pub struct ActionMemoUpdated {
+    pub owner: Pubkey,
+    pub position_id: u32,
+    pub active: bool,
+    pub timestamp: i64,
 }
 
-#[queue_computation_accounts("check_position_health", payer)]
-#[derive(Accounts)]
-#[instruction(computation_offset: u64, _position_id: u32)]
-pub struct CheckHealth<'info> {
-    #[account(mut)]
-    pub payer: Signer<'info>,
-    #[account(
-        init_if_needed,
-        space = 9,
-        payer = payer,
-        seeds = [&SIGN_PDA_SEED],
-        bump,
-        address = derive_sign_pda!(),
-    )]
-    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
-    #[account(address = derive_mxe_pda!())]
-    pub mxe_account: Account<'info, MXEAccount>,
-    #[account(
-        mut,
-        address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet)
-    )]
-    /// CHECK: mempool_account
-    pub mempool_account: UncheckedAccount<'info>,
-    #[account(
-        mut,
-        address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet)
-    )]
-    /// CHECK: executing_pool
-    pub executing_pool: UncheckedAccount<'info>,
-    #[account(
-        mut,
-        address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet)
-    )]
-    /// CHECK: computation_account
-    pub computation_account: UncheckedAccount<'info>,
-    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_CHECK_HEALTH))]
-    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
-    #[account(
-        mut,
-        address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet)
-    )]
-    pub cluster_account: Account<'info, Cluster>,
-    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
-    pub pool_account: Account<'info, FeePool>,
-    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
-    pub clock_account: Account<'info, ClockAccount>,
-    pub system_program: Program<'info, System>,
-    pub arcium_program: Program<'info, Arcium>,
-    /// CHECK: Position owner
-    #[account(address = position_acc.owner)]
-    pub owner: UncheckedAccount<'info>,
-    #[account(
-        seeds = [b"position", owner.key().as_ref(), _position_id.to_le_bytes().as_ref()],
-        bump = position_acc.bump,
-        has_one = owner
-    )]
-    pub position_acc: Account<'info, PositionAccount>,
+#[event]
+pub struct SizeBucketRevealed {
+    pub owner: Pubkey,
+    pub position_id: u32,
+    pub size_bucket: SizeBucket,
+    pub timestamp: i64,
 }
 
-#[callback_accounts("check_position_health")]
-#[derive(Accounts)]
-pub struct CheckPositionHealthCallback<'info> {
-    pub arcium_program: Program<'info, Arcium>,
-    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_CHECK_HEALTH))]
-    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
-    #[account(address = derive_mxe_pda!())]
-    pub mxe_account: Account<'info, MXEAccount>,
-    /// CHECK: computation_account
-    pub computation_account: UncheckedAccount<'info>,
-    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
-    pub cluster_account: Account<'info, Cluster>,
-    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
-    /// CHECK: instructions_sysvar
-    pub instructions_sysvar: AccountInfo<'info>,
-    #[account(mut)]
-    pub position_acc: Account<'info, PositionAccount>,
+/// Estimated liquidation penalty a protective step saved the owner; see
+/// `record_value_protected`.
+#[event]
+pub struct ValueProtected {
+    pub owner: Pubkey,
+    pub position_id: u32,
+    pub trigger: ProtectionTrigger,
+    pub size_bucket: SizeBucket,
+    /// Liquidation penalty the estimate assumes, in basis points
+    pub penalty_bps: u16,
+    /// USD cents
+    pub value_protected: u64,
+    /// Owner or keeper who recorded the estimate
+    pub recorded_by: Pubkey,
+    pub timestamp: i64,
 }
 
-#[init_computation_definition_accounts("check_position_health", payer)]
-#[derive(Accounts)]
-pub struct InitCheckHealthCompDef<'info> {
-    #[account(mut)]
-    pub payer: Signer<'info>,
-    #[account(mut, address = derive_mxe_pda!())]
-    pub mxe_account: Box<Account<'info, MXEAccount>>,
-    #[account(mut)]
-    /// CHECK: comp_def_account
-    pub comp_def_account: UncheckedAccount<'info>,
-    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
-    /// CHECK: address_lookup_table
-    pub address_lookup_table: UncheckedAccount<'info>,
-    #[account(address = LUT_PROGRAM_ID)]
-    /// CHECK: lut_program
-    pub lut_program: UncheckedAccount<'info>,
-    pub arcium_program: Program<'info, Arcium>,
-    pub system_program: Program<'info, System>,
+#[event]
+pub struct PendingComputationCleared {
+    pub owner: Pubkey,
+    pub position_id: u32,
+    pub computation_offset: u64,
+    pub timestamp: i64,
 }
 
-#[queue_computation_accounts("reveal_risk", payer)]
-#[derive(Accounts)]
-#[instruction(computation_offset: u64, position_id: u32)]
-pub struct RevealRisk<'info> {
-    #[account(mut)]
-    pub payer: Signer<'info>,
-    #[account(
-        init_if_needed,
-        space = 9,
-        payer = payer,
-        seeds = [&SIGN_PDA_SEED],
-        bump,
-        address = derive_sign_pda!(),
-    )]
-    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
-    #[account(address = derive_mxe_pda!())]
-    pub mxe_account: Account<'info, MXEAccount>,
-    #[account(
-        mut,
-        address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet)
-    )]
-    /// CHECK: mempool_account
-    pub mempool_account: UncheckedAccount<'info>,
-    #[account(
-        mut,
-        address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet)
-    )]
-    /// CHECK: executing_pool
-    pub executing_pool: UncheckedAccount<'info>,
-    #[account(
-        mut,
-        address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet)
-    )]
-    /// CHECK: computation_account
-    pub computation_account: UncheckedAccount<'info>,
-    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_REVEAL_RISK))]
-    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
-    #[account(
-        mut,
-        address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet)
-    )]
-    pub cluster_account: Account<'info, Cluster>,
-    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
-    pub pool_account: Account<'info, FeePool>,
-    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
-    pub clock_account: Account<'info, ClockAccount>,
-    pub system_program: Program<'info, System>,
-    pub arcium_program: Program<'info, Arcium>,
-    #[account(
-        seeds = [b"position", payer.key().as_ref(), position_id.to_le_bytes().as_ref()],
-        bump = position_acc.bump
-    )]
-    pub position_acc: Account<'info, PositionAccount>,
+#[event]
+pub struct ComputationCancelled {
+    pub owner: Pubkey,
+    pub position_id: u32,
+    pub computation_offset: u64,
+    pub cancelled_by: Pubkey,
+    pub timestamp: i64,
 }
 
-#[callback_accounts("reveal_risk")]
-#[derive(Accounts)]
-pub struct RevealRiskCallback<'info> {
-    pub arcium_program: Program<'info, Arcium>,
-    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_REVEAL_RISK))]
-    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
-    #[account(address = derive_mxe_pda!())]
-    pub mxe_account: Account<'info, MXEAccount>,
-    /// CHECK: computation_account
-    pub computation_account: UncheckedAccount<'info>,
-    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
-    pub cluster_account: Account<'info, Cluster>,
-    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
-    /// CHECK: instructions_sysvar
-    pub instructions_sysvar: AccountInfo<'info>,
+#[event]
+pub struct PositionDeactivated {
+    pub owner: Pubkey,
+    pub position_id: u32,
+    pub timestamp: i64,
 }
 
-#[init_computation_definition_accounts("reveal_risk", payer)]
-#[derive(Accounts)]
-pub struct InitRevealRiskCompDef<'info> {
-    #[account(mut)]
-    pub payer: Signer<'info>,
-    #[account(mut, address = derive_mxe_pda!())]
-    pub mxe_account: Box<Account<'info, MXEAccount>>,
-    #[account(mut)]
-    /// CHECK: comp_def_account
-    pub comp_def_account: UncheckedAccount<'info>,
-    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
-    /// CHECK: address_lookup_table
-    pub address_lookup_table: UncheckedAccount<'info>,
-    #[account(address = LUT_PROGRAM_ID)]
-    /// CHECK: lut_program
-    pub lut_program: UncheckedAccount<'info>,
-    pub arcium_program: Program<'info, Arcium>,
-    pub system_program: Program<'info, System>,
+#[event]
+pub struct PositionMigrated {
+    pub owner: Pubkey,
+    pub position_id: u32,
+    pub from_version: u8,
+    pub to_version: u8,
+    pub timestamp: i64,
 }
 
-// ─── State ───
+#[event]
+pub struct PositionClosed {
+    pub owner: Pubkey,
+    pub position_id: u32,
+    pub timestamp: i64,
+}
 
-/// Represents a monitored DeFi position with encrypted risk state.
-#[account]
-#[derive(InitSpace)]
-pub struct PositionAccount {
-    /// PDA bump seed
-    pub bump: u8,
-    /// Encrypted risk state: [is_at_risk, severity] as 32-byte ciphertexts
-    pub risk_state: [[u8; 32]; 2],
-    /// Unique position identifier
+#[event]
+pub struct PositionArchived {
+    pub owner: Pubkey,
     pub position_id: u32,
-    /// Owner's public key
+    pub archive: Pubkey,
+    pub position_hash: [u8; 32],
+    pub history_hash: [u8; 32],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ActionRequired {
     pub owner: Pubkey,
-    /// Cryptographic nonce for re-encryption
-    pub nonce: u128,
-    /// Unix timestamp of last health check
-    pub last_check: i64,
-    /// Whether the position is actively monitored
-    pub is_active: bool,
+    pub position_id: u32,
+    pub action_type: ActionType,
+    /// Revealed severity: 1=low .. 3=critical
+    pub severity: u8,
+    pub timestamp: i64,
 }
 
-// ─── Errors ───
+#[event]
+pub struct ReceiptMintRegistered {
+    pub mint: Pubkey,
+    pub timestamp: i64,
+}
 
-#[error_code]
-pub enum ErrorCode {
-    #[msg("Invalid authority")]
-    InvalidAuthority,
-    #[msg("The computation was aborted")]
-    AbortedComputation,
-    #[msg("Cluster not set")]
-    ClusterNotSet,
-    #[msg("Position is not active")]
-    PositionInactive,
+#[event]
+pub struct MonitoringReceiptMinted {
+    pub owner: Pubkey,
+    pub position_id: u32,
+    pub receipt_account: Pubkey,
+    pub timestamp: i64,
 }
 
-// ─── Events ───
+#[event]
+pub struct MonitoringReceiptBurned {
+    pub owner: Pubkey,
+    pub position_id: u32,
+    pub receipt_account: Pubkey,
+    pub timestamp: i64,
+}
 
 #[event]
-pub struct PositionRegistered {
+pub struct RiskModelConfigUpdated {
+    pub weights: RiskWeights,
+    pub severity_bands: [u8; 3],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RiskScoreComputed {
     pub owner: Pubkey,
     pub position_id: u32,
+    pub risk_score: Pubkey,
     pub timestamp: i64,
 }
 
+/// A referrer's share of a keeper fee, paid when the check landed.
 #[event]
-pub struct HealthCheckCompleted {
+pub struct ReferralFeePaid {
+    pub referrer: Pubkey,
     pub owner: Pubkey,
     pub position_id: u32,
+    pub lamports: u64,
     pub timestamp: i64,
 }
 
+/// A critical reveal made the position's auto action due; it runs once
+/// someone sends `execute_auto_action`.
 #[event]
-pub struct RiskRevealed {
-    pub is_at_risk: bool,
+pub struct AutoActionDue {
+    pub owner: Pubkey,
+    pub position_id: u32,
+    pub position: Pubkey,
+    pub program_id: Pubkey,
+    pub severity: u8,
     pub timestamp: i64,
 }
 
+/// A fired stop-loss order was sent to its executor.
 #[event]
-pub struct ActionRequired {
-    pub action_type: String,
+pub struct StopLossExecuted {
+    pub owner: Pubkey,
+    pub position_id: u32,
+    pub executor: Pubkey,
     pub timestamp: i64,
 }
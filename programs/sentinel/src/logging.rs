@@ -0,0 +1,56 @@
+//! Leveled `msg!` wrappers with `key=value` formatting.
+//!
+//! Every line has the shape `level=<level> event=<event> key=value ...` so
+//! off-chain parsers can split program logs without regexes. Verbosity is a
+//! compile-time choice, so disabled levels cost no compute units:
+//!
+//! - default: `error` and `info`
+//! - `verbose-logs`: also `debug`
+//! - `quiet-logs`: `error` only
+//!
+//! ```ignore
+//! log_info!("register_position", position_id = position_id, owner = owner);
+//! // level=info event=register_position position_id=1 owner=4Nd1...
+//! ```
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __log {
+    ($level:literal, $event:literal $(, $key:ident = $value:expr)* $(,)?) => {
+        ::anchor_lang::prelude::msg!(
+            concat!("level=", $level, " event=", $event $(, " ", stringify!($key), "={}")*)
+            $(, $value)*
+        )
+    };
+}
+
+#[macro_export]
+macro_rules! log_error {
+    ($($args:tt)*) => {
+        $crate::__log!("error", $($args)*)
+    };
+}
+
+#[macro_export]
+macro_rules! log_info {
+    ($event:literal $(, $key:ident = $value:expr)* $(,)?) => {{
+        #[cfg(not(feature = "quiet-logs"))]
+        $crate::__log!("info", $event $(, $key = $value)*);
+        #[cfg(feature = "quiet-logs")]
+        {
+            $(let _ = &$value;)*
+        }
+    }};
+}
+
+#[macro_export]
+macro_rules! log_debug {
+    ($event:literal $(, $key:ident = $value:expr)* $(,)?) => {{
+        #[cfg(all(feature = "verbose-logs", not(feature = "quiet-logs")))]
+        $crate::__log!("debug", $event $(, $key = $value)*);
+        #[cfg(not(all(feature = "verbose-logs", not(feature = "quiet-logs"))))]
+        {
+            $(let _ = &$value;)*
+        }
+    }};
+}
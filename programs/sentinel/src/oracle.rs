@@ -0,0 +1,122 @@
+//! Reader for Pyth pull-oracle `PriceUpdateV2` accounts.
+//!
+//! The account is parsed by hand rather than through `pyth-solana-receiver-sdk`,
+//! which pins its own Anchor version. Which account to read is pinned per
+//! position by its owner (`PositionAccount::price_feed`), so callers cannot
+//! substitute their own. Only fully verified updates are accepted, and prices
+//! are rescaled to a fixed [`PRICE_DECIMALS`] so the circuit never has to deal
+//! with Pyth's per-feed exponent.
+//!
+//! Built with the `localnet` feature, accounts of the workspace's
+//! `mock-oracle` program are read as well, so price-driven paths can be
+//! exercised on a local validator with prices the test sets.
+
+use crate::ErrorCode;
+use anchor_lang::prelude::*;
+
+/// `sha256("account:PriceUpdateV2")[..8]`
+const PRICE_UPDATE_V2_DISCRIMINATOR: [u8; 8] = [34, 241, 35, 99, 157, 126, 244, 205];
+
+/// Pyth's receiver program, which owns `PriceUpdateV2` accounts.
+pub const PYTH_RECEIVER_ID: Pubkey = pubkey!("rec5EKMGg6MxZYaMdyBfgwp4d5rB9T1VQH5pJv5LtFJ");
+/// `mock-oracle`, whose feeds share the `PriceUpdateV2` layout.
+pub const MOCK_ORACLE_ID: Pubkey = pubkey!("CJTZNnnoLgCWAUyGhLGUwvMUBvCy8mP5VULbHT58xMaQ");
+
+/// Programs whose price update accounts are read.
+#[cfg(not(feature = "localnet"))]
+pub const PRICE_FEED_OWNERS: &[Pubkey] = &[PYTH_RECEIVER_ID];
+#[cfg(feature = "localnet")]
+pub const PRICE_FEED_OWNERS: &[Pubkey] = &[PYTH_RECEIVER_ID, MOCK_ORACLE_ID];
+
+/// Oldest price, in seconds, a health check will accept.
+pub const MAX_PRICE_AGE_SECS: i64 = 60;
+
+/// Prices handed to the circuit are in units of 10^-PRICE_DECIMALS USD.
+pub const PRICE_DECIMALS: i32 = 6;
+
+/// How long a feed may go without a fully verified update before it counts
+/// as abandoned, e.g. because Pyth moved the asset to another account.
+pub const FEED_DEPRECATED_AFTER_SECS: i64 = 86_400;
+
+/// A verified price rescaled to [`PRICE_DECIMALS`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Price {
+    pub price: u64,
+    pub conf: u64,
+    pub publish_time: i64,
+}
+
+/// Reads the price from a `PriceUpdateV2` account, rejecting accounts not
+/// owned by one of [`PRICE_FEED_OWNERS`] and partially verified,
+/// non-positive and stale updates.
+pub fn read_price(info: &AccountInfo, now: i64) -> Result<Price> {
+    require!(
+        PRICE_FEED_OWNERS.contains(info.owner),
+        ErrorCode::InvalidPriceAccount
+    );
+    let data = info.try_borrow_data()?;
+    let message = parse(&data).ok_or(ErrorCode::InvalidPriceAccount)?;
+
+    require!(
+        now.saturating_sub(message.publish_time) <= MAX_PRICE_AGE_SECS,
+        ErrorCode::StalePrice
+    );
+    require!(message.price > 0, ErrorCode::InvalidOraclePrice);
+
+    Ok(Price {
+        price: rescale(message.price as u64, message.exponent)?,
+        conf: rescale(message.conf, message.exponent)?,
+        publish_time: message.publish_time,
+    })
+}
+
+/// Whether the feed with account `data` has stopped publishing as of `now`:
+/// the account no longer holds a fully verified `PriceUpdateV2`, closed
+/// accounts included, or its last update is older than
+/// [`FEED_DEPRECATED_AFTER_SECS`].
+pub fn is_deprecated(data: &[u8], now: i64) -> bool {
+    parse(data)
+        .is_none_or(|message| now.saturating_sub(message.publish_time) > FEED_DEPRECATED_AFTER_SECS)
+}
+
+struct PriceMessage {
+    price: i64,
+    conf: u64,
+    exponent: i32,
+    publish_time: i64,
+}
+
+/// Layout: discriminator, write_authority, verification_level (Borsh enum:
+/// `0, num_signatures` for Partial, `1` for Full), then the price message.
+fn parse(data: &[u8]) -> Option<PriceMessage> {
+    let rest = data.strip_prefix(&PRICE_UPDATE_V2_DISCRIMINATOR)?;
+    let rest = rest.get(32..)?;
+    // Partial updates carry fewer guardian signatures than Full ones
+    let rest = match rest.first()? {
+        1 => &rest[1..],
+        _ => return None,
+    };
+
+    // Skip the 32-byte feed id; the account itself is pinned per position
+    Some(PriceMessage {
+        price: i64::from_le_bytes(rest.get(32..40)?.try_into().ok()?),
+        conf: u64::from_le_bytes(rest.get(40..48)?.try_into().ok()?),
+        exponent: i32::from_le_bytes(rest.get(48..52)?.try_into().ok()?),
+        publish_time: i64::from_le_bytes(rest.get(52..60)?.try_into().ok()?),
+    })
+}
+
+/// Converts `value * 10^exponent` to units of 10^-PRICE_DECIMALS.
+fn rescale(value: u64, exponent: i32) -> Result<u64> {
+    let shift = exponent + PRICE_DECIMALS;
+    let factor = 10u64
+        .checked_pow(shift.unsigned_abs())
+        .ok_or(ErrorCode::InvalidOraclePrice)?;
+    if shift >= 0 {
+        Ok(value
+            .checked_mul(factor)
+            .ok_or(ErrorCode::InvalidOraclePrice)?)
+    } else {
+        Ok(value / factor)
+    }
+}
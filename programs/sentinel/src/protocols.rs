@@ -0,0 +1,198 @@
+//! Reader for lending-protocol obligation accounts.
+//!
+//! Each supported protocol is registered as a `ProtocolAdapter` holding the
+//! program that owns its obligations and an [`ObligationLayout`]: the prefix
+//! that marks an obligation and where its owner, staleness flag and USD
+//! values sit. Layouts for Solend and Kamino are in [`SOLEND`] and
+//! [`KAMINO`]. MarginFi accounts hold bank shares rather than USD values, so
+//! they can't be read without every bank and oracle and have no layout here.
+//!
+//! An obligation is only accepted for a position of the same owner, and only
+//! while it isn't marked stale, so refresh it earlier in the same transaction.
+
+use crate::ErrorCode;
+use anchor_lang::prelude::*;
+
+/// How an obligation encodes USD values.
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ValueEncoding {
+    /// `Decimal`: u128 scaled by 10^18
+    Wad,
+    /// `*_sf`: u128 fixed point with 60 fractional bits
+    Fraction60,
+}
+
+/// Where the values a health check needs sit in an obligation account.
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ObligationLayout {
+    /// The first `discriminator_len` bytes of every obligation
+    pub discriminator: [u8; 8],
+    pub discriminator_len: u8,
+    /// Obligation owner's pubkey
+    pub owner_offset: u16,
+    /// `last_update.stale`, nonzero until the obligation is refreshed
+    pub stale_offset: u16,
+    /// USD value of the deposited collateral
+    pub deposited_value_offset: u16,
+    /// USD value of the debt, as compared against `unhealthy_borrow_value`
+    pub borrowed_value_offset: u16,
+    /// Debt value at which the obligation can be liquidated
+    pub unhealthy_borrow_value_offset: u16,
+    pub encoding: ValueEncoding,
+}
+
+/// `solend-program` `Obligation`: version byte 1, `last_update`, lending
+/// market, owner, then the `Decimal` values.
+pub const SOLEND: ObligationLayout = ObligationLayout {
+    discriminator: [1, 0, 0, 0, 0, 0, 0, 0],
+    discriminator_len: 1,
+    owner_offset: 42,
+    stale_offset: 9,
+    deposited_value_offset: 74,
+    borrowed_value_offset: 90,
+    unhealthy_borrow_value_offset: 122,
+    encoding: ValueEncoding::Wad,
+};
+
+/// Kamino Lend `Obligation` (zero-copy). The debt is
+/// `borrow_factor_adjusted_debt_value_sf`, which klend checks against
+/// `unhealthy_borrow_value_sf`.
+pub const KAMINO: ObligationLayout = ObligationLayout {
+    // sha256("account:Obligation")[..8]
+    discriminator: [168, 206, 141, 106, 88, 76, 172, 167],
+    discriminator_len: 8,
+    owner_offset: 64,
+    stale_offset: 24,
+    deposited_value_offset: 1192,
+    borrowed_value_offset: 2208,
+    unhealthy_borrow_value_offset: 2256,
+    encoding: ValueEncoding::Fraction60,
+};
+
+/// Lending protocol a position is on, as recorded in its `PositionMetadata`.
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Protocol {
+    Unspecified,
+    Solend,
+    Kamino,
+    MarginFi,
+    /// Any other protocol, told apart by the metadata's `market`
+    Other,
+}
+
+/// Ratios handed to the circuit are capped here, so adding an alert buffer
+/// to them can't overflow a `u64`.
+pub const MAX_RATIO_BPS: u64 = u32::MAX as u64;
+
+impl ObligationLayout {
+    /// Whether the discriminator fits and no field overlaps it.
+    pub fn is_valid(&self) -> bool {
+        let len = self.discriminator_len as u16;
+        len as usize <= self.discriminator.len()
+            && [
+                self.owner_offset,
+                self.stale_offset,
+                self.deposited_value_offset,
+                self.borrowed_value_offset,
+                self.unhealthy_borrow_value_offset,
+            ]
+            .iter()
+            .all(|&offset| offset >= len)
+    }
+}
+
+/// An obligation's values as the circuit's plaintext position.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ObligationHealth {
+    /// Deposited collateral in USD cents
+    pub position_value: u64,
+    /// Collateral over debt in basis points, at most [`MAX_RATIO_BPS`]
+    pub collateral_ratio: u64,
+    /// Collateral ratio, in basis points, at which the obligation becomes
+    /// liquidatable; 0 without debt
+    pub liquidation_threshold: u64,
+}
+
+/// Reads the obligation owned by `owner`, rejecting other accounts and
+/// stale obligations.
+pub fn read_obligation(
+    info: &AccountInfo,
+    layout: &ObligationLayout,
+    owner: &Pubkey,
+) -> Result<ObligationHealth> {
+    let data = info.try_borrow_data()?;
+    let obligation = parse(&data, layout).ok_or(ErrorCode::InvalidObligation)?;
+    require_keys_eq!(obligation.owner, *owner, ErrorCode::ObligationOwnerMismatch);
+    require!(!obligation.stale, ErrorCode::StaleObligation);
+
+    let position_value = to_cents(obligation.deposited_value, layout.encoding);
+    if obligation.borrowed_value == 0 {
+        return Ok(ObligationHealth {
+            position_value,
+            collateral_ratio: MAX_RATIO_BPS,
+            liquidation_threshold: 0,
+        });
+    }
+    Ok(ObligationHealth {
+        position_value,
+        collateral_ratio: ratio_bps(obligation.deposited_value, obligation.borrowed_value),
+        liquidation_threshold: ratio_bps(
+            obligation.deposited_value,
+            obligation.unhealthy_borrow_value,
+        ),
+    })
+}
+
+struct Obligation {
+    owner: Pubkey,
+    stale: bool,
+    deposited_value: u128,
+    borrowed_value: u128,
+    unhealthy_borrow_value: u128,
+}
+
+fn parse(data: &[u8], layout: &ObligationLayout) -> Option<Obligation> {
+    let prefix = &layout.discriminator[..layout.discriminator_len as usize];
+    if !data.starts_with(prefix) {
+        return None;
+    }
+    let u128_at = |offset: u16| -> Option<u128> {
+        let offset = offset as usize;
+        Some(u128::from_le_bytes(
+            data.get(offset..offset + 16)?.try_into().ok()?,
+        ))
+    };
+    let owner_offset = layout.owner_offset as usize;
+
+    Some(Obligation {
+        owner: Pubkey::new_from_array(data.get(owner_offset..owner_offset + 32)?.try_into().ok()?),
+        stale: *data.get(layout.stale_offset as usize)? != 0,
+        deposited_value: u128_at(layout.deposited_value_offset)?,
+        borrowed_value: u128_at(layout.borrowed_value_offset)?,
+        unhealthy_borrow_value: u128_at(layout.unhealthy_borrow_value_offset)?,
+    })
+}
+
+fn to_cents(value: u128, encoding: ValueEncoding) -> u64 {
+    let cents = match encoding {
+        ValueEncoding::Wad => value / 10u128.pow(16),
+        ValueEncoding::Fraction60 => {
+            let fraction = value & ((1 << 60) - 1);
+            (value >> 60).saturating_mul(100) + ((fraction * 100) >> 60)
+        }
+    };
+    u64::try_from(cents).unwrap_or(u64::MAX)
+}
+
+/// `numerator / denominator` in basis points, capped at [`MAX_RATIO_BPS`].
+/// Both values share an encoding, so it cancels out.
+fn ratio_bps(numerator: u128, denominator: u128) -> u64 {
+    if denominator == 0 {
+        return MAX_RATIO_BPS;
+    }
+    let ratio = match numerator.checked_mul(10_000) {
+        Some(scaled) => scaled / denominator,
+        None => (numerator / denominator).saturating_mul(10_000),
+    };
+    ratio.min(MAX_RATIO_BPS as u128) as u64
+}
@@ -0,0 +1,57 @@
+//! Layout of the plaintext parameter vector for rule-based risk scoring.
+//!
+//! Rule sets are written as TOML/JSON and compiled by `cargo xtask
+//! compile-rules` into [`RULE_PARAMS_LEN`] `u64`s: [`MAX_RULES`] rule slots of
+//! [`PARAMS_PER_RULE`] words each, followed by the three tier boundaries.
+//!
+//! A rule slot is `[enabled, field, op, relative, value, weight]`. The rule
+//! matches when `position[field] op rhs`, where `rhs` is `value`, or
+//! `liquidation_threshold + value` if `relative` is set. The score is the sum
+//! of the weights of matching rules, and the severity is the highest tier
+//! whose boundary the score reaches (0 if none).
+
+/// Rule slots in a parameter vector; unused slots have `enabled = 0`.
+pub const MAX_RULES: usize = 4;
+pub const PARAMS_PER_RULE: usize = 6;
+/// Tier boundaries for severity 1 (low), 2 (medium) and 3 (critical).
+pub const TIERS: usize = 3;
+pub const RULE_PARAMS_LEN: usize = MAX_RULES * PARAMS_PER_RULE + TIERS;
+
+/// Largest rule weight; keeps the score far from overflow.
+pub const MAX_WEIGHT: u64 = 1_000;
+
+/// Position fields a rule can compare, by their index in `PositionData`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u64)]
+pub enum Field {
+    PositionValue = 0,
+    CollateralRatio = 1,
+    LiquidationThreshold = 2,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u64)]
+pub enum Op {
+    Lt = 0,
+    Ge = 1,
+}
+
+/// Whether `params` is a well-formed parameter vector: flags are 0 or 1,
+/// enabled slots name a known field and op with a weight in `1..=MAX_WEIGHT`,
+/// and the tiers satisfy `0 < low < medium < critical`. Mirrors the checks
+/// `cargo xtask compile-rules` makes on the source rule set.
+pub fn is_valid(params: &[u64; RULE_PARAMS_LEN]) -> bool {
+    let (slots, tiers) = params.split_at(MAX_RULES * PARAMS_PER_RULE);
+    let rules_ok = slots.chunks(PARAMS_PER_RULE).all(|slot| match slot {
+        [0, ..] => true,
+        &[1, field, op, relative, _, weight] => {
+            field <= Field::LiquidationThreshold as u64
+                && op <= Op::Ge as u64
+                && relative <= 1
+                && !(relative == 1 && field == Field::LiquidationThreshold as u64)
+                && (1..=MAX_WEIGHT).contains(&weight)
+        }
+        _ => false,
+    });
+    rules_ok && 0 < tiers[0] && tiers[0] < tiers[1] && tiers[1] < tiers[2]
+}
@@ -0,0 +1,89 @@
+//! Owner and discriminator checks for the Arcium accounts that queue and
+//! callback contexts take as `UncheckedAccount`.
+//!
+//! The address constraints already pin these accounts to their PDAs; the
+//! checks here additionally reject anything at those addresses that is not
+//! the Arcium account type we expect.
+//!
+//! [`is_degraded`] gates instructions that start new computations: while it
+//! holds, queuing would only burn fees on computations the cluster cannot
+//! finish. Reveals of already computed state and administrative
+//! instructions are not gated.
+
+use anchor_lang::{prelude::*, Discriminator};
+use arcium_anchor::prelude::ARCIUM_PROG_ID;
+use arcium_client::idl::arcium::{
+    accounts::{
+        Cluster, ComputationAccount, LargeExecPool, LargeMempool, MediumExecPool, MediumMempool,
+        SmallExecPool, SmallMempool, TinyExecPool, TinyMempool,
+    },
+    types::{ComputationStatus, SetUnset},
+};
+
+const MEMPOOL_DISCRIMINATORS: [&[u8]; 4] = [
+    TinyMempool::DISCRIMINATOR,
+    SmallMempool::DISCRIMINATOR,
+    MediumMempool::DISCRIMINATOR,
+    LargeMempool::DISCRIMINATOR,
+];
+
+const EXECPOOL_DISCRIMINATORS: [&[u8]; 4] = [
+    TinyExecPool::DISCRIMINATOR,
+    SmallExecPool::DISCRIMINATOR,
+    MediumExecPool::DISCRIMINATOR,
+    LargeExecPool::DISCRIMINATOR,
+];
+
+/// Arcium-owned mempool of any size.
+pub fn is_mempool(info: &AccountInfo) -> bool {
+    is_arcium_account(info, &MEMPOOL_DISCRIMINATORS)
+}
+
+/// Arcium-owned executing pool of any size.
+pub fn is_execpool(info: &AccountInfo) -> bool {
+    is_arcium_account(info, &EXECPOOL_DISCRIMINATORS)
+}
+
+/// Arcium-owned computation account, as seen by callbacks.
+pub fn is_computation(info: &AccountInfo) -> bool {
+    is_arcium_account(info, &[ComputationAccount::DISCRIMINATOR])
+}
+
+/// Whether the computation at `info` was never finished by the cluster: it is
+/// still queued, or gone because Arcium reclaimed its fee as expired.
+/// Anything else at the address is not a computation account.
+pub fn is_unfinished_computation(info: &AccountInfo) -> Result<bool> {
+    if is_unallocated(info) {
+        return Ok(true);
+    }
+    require!(
+        is_computation(info),
+        crate::ErrorCode::InvalidComputationAccount
+    );
+    let computation = ComputationAccount::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+    Ok(matches!(computation.status, ComputationStatus::Queued))
+}
+
+/// Computation accounts are created by Arcium inside `queue_computation`, so
+/// at queue time the address must not hold any data yet.
+pub fn is_unallocated(info: &AccountInfo) -> bool {
+    *info.owner == System::id() && info.data_is_empty()
+}
+
+/// Whether the cluster cannot be expected to finish computations: some of its
+/// nodes are missing, or they have not agreed on the BLS key callbacks are
+/// verified against.
+pub fn is_degraded(cluster: &Cluster) -> bool {
+    cluster.nodes.len() < cluster.cluster_size as usize
+        || matches!(cluster.bls_public_key, SetUnset::Unset(..))
+}
+
+fn is_arcium_account(info: &AccountInfo, discriminators: &[&[u8]]) -> bool {
+    if *info.owner != ARCIUM_PROG_ID {
+        return false;
+    }
+    let Ok(data) = info.try_borrow_data() else {
+        return false;
+    };
+    discriminators.iter().any(|d| data.starts_with(d))
+}
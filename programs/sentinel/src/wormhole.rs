@@ -0,0 +1,103 @@
+//! Reader for cross-chain position summaries delivered as Wormhole VAAs.
+//!
+//! An attester on the remote chain publishes a [`CrossChainSummary`] through
+//! Wormhole; once the VAA is posted on Solana the core bridge owns a
+//! `PostedVAAV1` account holding the guardian-verified message. We only trust
+//! accounts owned by the core bridge whose emitter is the one the position
+//! owner pinned (`PositionAccount::cross_chain_emitter`).
+
+use crate::{ErrorCode, PositionAccount};
+use anchor_lang::prelude::*;
+
+#[cfg(not(feature = "wormhole-devnet"))]
+pub const CORE_BRIDGE_ID: Pubkey = pubkey!("worm2ZoG2kUd4vFXhvjh93UUH596ayRfgQ2MgjNMTth");
+#[cfg(feature = "wormhole-devnet")]
+pub const CORE_BRIDGE_ID: Pubkey = pubkey!("3u8hJUVTA4jH1wYAyUur7FFZVQ8H635K3tSHHF4ssjQ5");
+
+/// Oldest remote observation, in seconds, a health check will accept.
+pub const MAX_SUMMARY_AGE_SECS: i64 = 300;
+
+/// First payload byte of a [`CrossChainSummary`].
+pub const SUMMARY_PAYLOAD_ID: u8 = 1;
+
+const POSTED_VAA_MAGIC: &[u8; 3] = b"vaa";
+
+/// Wormhole chain id and 32-byte (left-padded) address of an emitter.
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Emitter {
+    pub chain: u16,
+    pub address: [u8; 32],
+}
+
+/// Remote collateral and debt attributed to one Solana position. The payload
+/// is packed big-endian, as emitted by an EVM contract:
+/// `payload_id u8 | owner [32] | position_id u32 | collateral_value u64 |
+/// debt_value u64 | observed_at u64`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CrossChainSummary {
+    /// Remote collateral in USD cents
+    pub collateral_value: u64,
+    /// Remote debt in USD cents
+    pub debt_value: u64,
+    /// Unix timestamp the attester observed the remote position at
+    pub observed_at: i64,
+}
+
+/// Reads the summary for `position` from a posted VAA, rejecting accounts not
+/// owned by the core bridge, other emitters, summaries for other positions
+/// and stale observations.
+pub fn read_summary(
+    info: &AccountInfo,
+    position: &PositionAccount,
+    now: i64,
+) -> Result<CrossChainSummary> {
+    let emitter = position
+        .cross_chain_emitter
+        .ok_or(ErrorCode::CrossChainEmitterNotSet)?;
+    require_keys_eq!(*info.owner, CORE_BRIDGE_ID, ErrorCode::InvalidPostedVaa);
+    let data = info.try_borrow_data()?;
+    let (vaa_emitter, payload) = parse_posted_vaa(&data).ok_or(ErrorCode::InvalidPostedVaa)?;
+    require!(vaa_emitter == emitter, ErrorCode::CrossChainEmitterMismatch);
+
+    let (owner, position_id, summary) =
+        parse_summary(payload).ok_or(ErrorCode::InvalidPostedVaa)?;
+    require!(
+        owner == position.owner && position_id == position.position_id,
+        ErrorCode::CrossChainPositionMismatch
+    );
+    require!(
+        now.saturating_sub(summary.observed_at) <= MAX_SUMMARY_AGE_SECS,
+        ErrorCode::StaleCrossChainSummary
+    );
+
+    Ok(summary)
+}
+
+/// Layout: `"vaa"`, vaa_version u8, consistency_level u8, vaa_time u32,
+/// vaa_signature_account [32], submission_time u32, nonce u32, sequence u64,
+/// emitter_chain u16, emitter_address [32], payload (u32 length + bytes), all
+/// little-endian.
+fn parse_posted_vaa(data: &[u8]) -> Option<(Emitter, &[u8])> {
+    let rest = data.strip_prefix(POSTED_VAA_MAGIC)?;
+    let rest = rest.get(1 + 1 + 4 + 32 + 4 + 4 + 8..)?;
+    let chain = u16::from_le_bytes(rest.get(0..2)?.try_into().ok()?);
+    let address = rest.get(2..34)?.try_into().ok()?;
+    let len = u32::from_le_bytes(rest.get(34..38)?.try_into().ok()?) as usize;
+    let payload = rest.get(38..38usize.checked_add(len)?)?;
+    Some((Emitter { chain, address }, payload))
+}
+
+fn parse_summary(payload: &[u8]) -> Option<(Pubkey, u32, CrossChainSummary)> {
+    if *payload.first()? != SUMMARY_PAYLOAD_ID {
+        return None;
+    }
+    let owner = Pubkey::new_from_array(payload.get(1..33)?.try_into().ok()?);
+    let position_id = u32::from_be_bytes(payload.get(33..37)?.try_into().ok()?);
+    let summary = CrossChainSummary {
+        collateral_value: u64::from_be_bytes(payload.get(37..45)?.try_into().ok()?),
+        debt_value: u64::from_be_bytes(payload.get(45..53)?.try_into().ok()?),
+        observed_at: i64::try_from(u64::from_be_bytes(payload.get(53..61)?.try_into().ok()?))
+            .ok()?,
+    };
+    Some((owner, position_id, summary))
+}
@@ -0,0 +1,177 @@
+//! Declarative account fixtures and snapshots.
+//!
+//! A [`Fixture`] is a set of accounts to install at genesis. Scenarios are
+//! built from deterministic keys ([`keypair`]) so addresses are stable across
+//! runs, and can be dumped to / reloaded from JSON in the same shape as
+//! `solana account --output json`, which also lets state captured from
+//! devnet be replayed in tests:
+//!
+//! ```ignore
+//! let owner = fixtures::keypair("owner");
+//! let fixture = Fixture::new().positions(owner.pubkey(), 10);
+//! let mut h = Harness::with_accounts(|pt| fixture.apply(pt)).await;
+//! ```
+
+use super::position_pda;
+use anchor_lang::{prelude::*, AccountSerialize, Discriminator};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use sentinel::PositionAccount;
+use serde::{Deserialize, Serialize};
+use solana_program_test::ProgramTest;
+use solana_sdk::{account::Account, hash::hash, signature::Keypair};
+use std::{collections::BTreeMap, path::Path};
+
+/// Keypair derived from `label`, identical on every run.
+pub fn keypair(label: &str) -> Keypair {
+    Keypair::new_from_array(hash(label.as_bytes()).to_bytes())
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Fixture {
+    accounts: BTreeMap<Pubkey, Account>,
+}
+
+impl Fixture {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an Anchor account owned by the sentinel program, sized to its
+    /// `INIT_SPACE`.
+    pub fn account<T: AccountSerialize + Discriminator + Space>(
+        mut self,
+        address: Pubkey,
+        value: &T,
+    ) -> Self {
+        let mut data = Vec::with_capacity(T::DISCRIMINATOR.len() + T::INIT_SPACE);
+        value.try_serialize(&mut data).unwrap();
+        data.resize(T::DISCRIMINATOR.len() + T::INIT_SPACE, 0);
+        self.accounts.insert(
+            address,
+            Account {
+                lamports: Rent::default().minimum_balance(data.len()),
+                data,
+                owner: sentinel::ID,
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+        self
+    }
+
+    /// Adds `position` at its PDA.
+    pub fn position(self, position: PositionAccount) -> Self {
+        let address = position_pda(&position.owner, position.position_id);
+        self.account(address, &position)
+    }
+
+    /// Adds `count` active positions for `owner` with ids `1..=count`.
+    pub fn positions(self, owner: Pubkey, count: u32) -> Self {
+        (1..=count).fold(self, |fixture, id| {
+            fixture.position(super::position(owner, id))
+        })
+    }
+
+    /// Adds a raw account, e.g. one captured with [`super::Harness::snapshot`].
+    pub fn raw(mut self, address: Pubkey, account: Account) -> Self {
+        self.accounts.insert(address, account);
+        self
+    }
+
+    pub fn get(&self, address: &Pubkey) -> Option<&Account> {
+        self.accounts.get(address)
+    }
+
+    /// Installs every account at genesis.
+    pub fn apply(&self, pt: &mut ProgramTest) {
+        for (address, account) in &self.accounts {
+            pt.add_account(*address, account.clone());
+        }
+    }
+
+    /// Writes the fixture as a JSON array of `solana account --output json`
+    /// entries.
+    pub fn save(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let entries: Vec<_> = self
+            .accounts
+            .iter()
+            .map(|(address, account)| AccountEntry::new(address, account))
+            .collect();
+        std::fs::write(path, serde_json::to_string_pretty(&entries)?)
+    }
+
+    /// Reads a file written by [`Fixture::save`]. A single CLI account dump
+    /// (one object instead of an array) is accepted as well.
+    pub fn load(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        let entries: Vec<AccountEntry> = serde_json::from_str(&text)
+            .or_else(|_| serde_json::from_str(&text).map(|entry| vec![entry]))?;
+        let mut fixture = Self::new();
+        for entry in entries {
+            let (address, account) = entry.into_account()?;
+            fixture = fixture.raw(address, account);
+        }
+        Ok(fixture)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct AccountEntry {
+    pubkey: String,
+    account: UiAccount,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UiAccount {
+    lamports: u64,
+    /// `[base64, "base64"]`
+    data: (String, String),
+    owner: String,
+    executable: bool,
+    rent_epoch: u64,
+}
+
+impl AccountEntry {
+    fn new(address: &Pubkey, account: &Account) -> Self {
+        Self {
+            pubkey: address.to_string(),
+            account: UiAccount {
+                lamports: account.lamports,
+                data: (STANDARD.encode(&account.data), "base64".into()),
+                owner: account.owner.to_string(),
+                executable: account.executable,
+                rent_epoch: account.rent_epoch,
+            },
+        }
+    }
+
+    fn into_account(self) -> std::io::Result<(Pubkey, Account)> {
+        let invalid = |e: String| std::io::Error::new(std::io::ErrorKind::InvalidData, e);
+        let address = self.pubkey.parse().map_err(|e| invalid(format!("{e}")))?;
+        let owner = self
+            .account
+            .owner
+            .parse()
+            .map_err(|e| invalid(format!("{e}")))?;
+        if self.account.data.1 != "base64" {
+            return Err(invalid(format!(
+                "unsupported encoding {}",
+                self.account.data.1
+            )));
+        }
+        let data = STANDARD
+            .decode(&self.account.data.0)
+            .map_err(|e| invalid(e.to_string()))?;
+        Ok((
+            address,
+            Account {
+                lamports: self.account.lamports,
+                data,
+                owner,
+                executable: self.account.executable,
+                rent_epoch: self.account.rent_epoch,
+            },
+        ))
+    }
+}
@@ -0,0 +1,3728 @@
+//! In-process test harness for the sentinel program.
+//!
+//! By default the program runs natively under `solana-program-test`, so no SBF
+//! build or Arcium localnet is needed. The Arcium program is replaced by a
+//! stub that accepts every CPI, and the Arcium accounts the queue contexts
+//! read are stubbed with the right owner and discriminator followed by zeroed
+//! data, which Borsh decodes as `None`/`0`/empty for every field we don't set.
+//!
+//! Anchor's CPI helpers only work on-chain, so tests that reach a CPI (account
+//! `init`, `queue_computation`) are `#[ignore]`d and run against the SBF build:
+//!
+//! ```text
+//! arcium build && SBF_OUT_DIR=$PWD/target/deploy cargo test -p sentinel -- --include-ignored
+//! ```
+
+#![allow(dead_code)]
+
+pub mod fixtures;
+
+use anchor_lang::{
+    prelude::*, solana_program::bpf_loader_upgradeable, solana_program::entrypoint::ProgramResult,
+    solana_program::instruction::Instruction, AccountSerialize, Discriminator, InstructionData,
+    ToAccountMetas,
+};
+use arcium_anchor::prelude::*;
+use arcium_client::{
+    idl::arcium::accounts::{
+        ClockAccount, Cluster, ComputationAccount, ComputationDefinitionAccount, FeePool,
+        MXEAccount, SmallExecPool, SmallMempool,
+    },
+    pda,
+};
+use sentinel::{
+    protocols::ObligationLayout, rules::RULE_PARAMS_LEN, ActionMemo, AdminAuditLog,
+    AttestationAccount, AutoActionAccount, AutoActionConfig, CheckBounty, EncryptedMemo,
+    FeedMigration, GlobalConfig, KeeperAccount, KeeperLease, MonitoringPlan, OwnerIndex,
+    PortfolioAccount, PositionAccount, ProtocolAdapter, ProtocolTvlSnapshot, ReceiptRegistry,
+    RevealInbox, RiskHeatmap, RiskHistoryAccount, RiskModel, RiskModelConfig, RiskScoreAccount,
+    RiskWeights, RuleSetAccount, SponsorBudget, StopLossAccount, Subscription, ViewerGrant,
+};
+use solana_program_test::{processor, BanksClientError, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account::Account,
+    instruction::InstructionError,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+
+pub const CLUSTER_OFFSET: u32 = 1;
+
+/// `rules/default.toml`, compiled.
+pub const DEFAULT_RULES: [u64; RULE_PARAMS_LEN] = [
+    1, 1, 0, 1, 500, 3, 1, 1, 0, 1, 1000, 2, 1, 0, 0, 0, 100, 1, 0, 0, 0, 0, 0, 0, 1, 2, 5,
+];
+
+/// Arcium's `callback_computation` discriminator, which `#[arcium_callback]`
+/// expects on the instruction preceding a callback.
+const CALLBACK_COMPUTATION_DISCRIMINATOR: [u8; 8] = [11, 224, 42, 236, 0, 154, 74, 163];
+
+/// Zeroed bytes appended to stubbed Arcium accounts; large enough for every
+/// account type the queue contexts deserialize.
+const STUB_LEN: usize = 16 * 1024;
+
+pub struct Harness {
+    pub ctx: ProgramTestContext,
+}
+
+impl Harness {
+    pub async fn new() -> Self {
+        Self::with_accounts(|_| {}).await
+    }
+
+    /// Starts the harness after letting `setup` add or replace genesis accounts.
+    pub async fn with_accounts(setup: impl FnOnce(&mut ProgramTest)) -> Self {
+        let mut pt = ProgramTest::new("sentinel", sentinel::ID, processor!(process_instruction));
+        pt.add_program("arcium", ARCIUM_PROG_ID, processor!(arcium_stub));
+        pt.add_program(
+            "mock_oracle",
+            mock_oracle::ID,
+            processor!(mock_oracle_instruction),
+        );
+
+        // Pre-created so `init_if_needed` never has to CPI into the system program
+        let mut sign_pda_data = sentinel::ArciumSignerAccount::DISCRIMINATOR.to_vec();
+        sign_pda_data.push(sign_pda_bump());
+        pt.add_account(
+            sign_pda(),
+            Account {
+                lamports: 1_000_000_000,
+                data: sign_pda_data,
+                owner: sentinel::ID,
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+
+        let mut mxe = MXEAccount::DISCRIMINATOR.to_vec();
+        mxe.push(1);
+        mxe.extend_from_slice(&CLUSTER_OFFSET.to_le_bytes());
+        pt.add_account(pda::mxe_acc(&sentinel::ID), arcium_account(mxe));
+
+        pt.add_account(
+            pda::mempool_acc(CLUSTER_OFFSET),
+            arcium_stub_account(SmallMempool::DISCRIMINATOR),
+        );
+        pt.add_account(
+            pda::execpool_acc(CLUSTER_OFFSET),
+            arcium_stub_account(SmallExecPool::DISCRIMINATOR),
+        );
+        pt.add_account(
+            pda::cluster_acc(CLUSTER_OFFSET),
+            arcium_stub_account(Cluster::DISCRIMINATOR),
+        );
+        pt.add_account(
+            ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+            arcium_stub_account(FeePool::DISCRIMINATOR),
+        );
+        pt.add_account(
+            ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+            arcium_stub_account(ClockAccount::DISCRIMINATOR),
+        );
+        for circuit in [
+            "init_risk_state",
+            "check_position_health",
+            "reveal_risk",
+            "reveal_risk_to_owner",
+            "aggregate_portfolio_risk",
+            "check_position_health_with_price",
+            "check_position_health_cross_chain",
+            "set_alert_config",
+            "check_position_health_with_rules",
+            "reveal_history_summary",
+            "check_position_health_from_protocol",
+            "check_position_health_batch",
+            "compute_health_factor",
+            "reveal_health_factor_to_owner",
+            "reveal_risk_to_viewer",
+            "stress_test_position",
+            "attest_health",
+            "reveal_size_bucket",
+            "rotate_encryption",
+            "evaluate_stop_loss",
+            "accumulate_risk_heatmap",
+            "reveal_risk_heatmap",
+            "check_perp_health",
+            "compute_risk_score",
+        ] {
+            pt.add_account(
+                comp_def_address(circuit),
+                arcium_stub_account(ComputationDefinitionAccount::DISCRIMINATOR),
+            );
+        }
+
+        setup(&mut pt);
+        Self {
+            ctx: pt.start_with_context().await,
+        }
+    }
+
+    pub fn payer(&self) -> Keypair {
+        self.ctx.payer.insecure_clone()
+    }
+
+    /// Creates a funded keypair.
+    pub async fn funded_keypair(&mut self) -> Keypair {
+        let kp = Keypair::new();
+        let ix = anchor_lang::solana_program::system_instruction::transfer(
+            &self.ctx.payer.pubkey(),
+            &kp.pubkey(),
+            10_000_000_000,
+        );
+        self.send(ix, &[]).await.unwrap();
+        kp
+    }
+
+    /// Sends `ix` paid for by the context payer, signed by `signers` as well.
+    pub async fn send(
+        &mut self,
+        ix: Instruction,
+        signers: &[&Keypair],
+    ) -> std::result::Result<(), BanksClientError> {
+        self.send_all(&[ix], signers).await
+    }
+
+    /// Sends `ixs` in one transaction, like [`Harness::send`].
+    pub async fn send_all(
+        &mut self,
+        ixs: &[Instruction],
+        signers: &[&Keypair],
+    ) -> std::result::Result<(), BanksClientError> {
+        let blockhash = self.ctx.get_new_latest_blockhash().await?;
+        let mut all: Vec<&Keypair> = vec![&self.ctx.payer];
+        all.extend_from_slice(signers);
+        let tx = Transaction::new_signed_with_payer(
+            ixs,
+            Some(&self.ctx.payer.pubkey()),
+            &all,
+            blockhash,
+        );
+        self.ctx.banks_client.process_transaction(tx).await
+    }
+
+    /// Simulates `ix` paid for by the context payer and returns the data it
+    /// set with `set_return_data`, which Anchor fills from a handler's
+    /// return value.
+    pub async fn return_data(&mut self, ix: Instruction) -> Option<Vec<u8>> {
+        let blockhash = self.ctx.get_new_latest_blockhash().await.unwrap();
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&self.ctx.payer.pubkey()),
+            &[&self.ctx.payer],
+            blockhash,
+        );
+        let simulation = self
+            .ctx
+            .banks_client
+            .simulate_transaction(tx)
+            .await
+            .unwrap();
+        simulation.result.unwrap().unwrap();
+        simulation
+            .simulation_details
+            .and_then(|details| details.return_data)
+            .map(|return_data| return_data.data)
+    }
+
+    /// Sends `callback` the way Arcium delivers it: right after a
+    /// `callback_computation` instruction that completes `completed`.
+    pub async fn send_callback(
+        &mut self,
+        completed: Pubkey,
+        callback: Instruction,
+    ) -> std::result::Result<(), BanksClientError> {
+        let mut data = CALLBACK_COMPUTATION_DISCRIMINATOR.to_vec();
+        data.resize(64, 0);
+        let callback_computation = Instruction::new_with_bytes(
+            ARCIUM_PROG_ID,
+            &data,
+            vec![AccountMeta::new_readonly(completed, false)],
+        );
+        self.send_all(&[callback_computation, callback], &[]).await
+    }
+
+    /// Adds a stubbed computation account at `computation_offset`, as Arcium
+    /// leaves it for the callback.
+    pub fn add_computation(&mut self, computation_offset: u64) {
+        self.ctx.set_account(
+            &pda::computation_acc(CLUSTER_OFFSET, computation_offset),
+            &arcium_stub_account(ComputationAccount::DISCRIMINATOR).into(),
+        );
+    }
+
+    /// Adds a stubbed computation account at `computation_offset` that
+    /// Arcium has marked finalized.
+    pub fn add_finalized_computation(&mut self, computation_offset: u64) {
+        // Discriminator, payer, mxe_program_id, computation_definition_offset,
+        // execution_fee, slot and slot_counter precede the status
+        const STATUS: usize = 8 + 32 + 32 + 4 + 24 + 8 + 2;
+        let mut account = arcium_stub_account(ComputationAccount::DISCRIMINATOR);
+        account.data[STATUS] = 1;
+        self.ctx.set_account(
+            &pda::computation_acc(CLUSTER_OFFSET, computation_offset),
+            &account.into(),
+        );
+    }
+
+    pub async fn account(&mut self, address: Pubkey) -> Option<Account> {
+        self.ctx.banks_client.get_account(address).await.unwrap()
+    }
+
+    pub async fn position(&mut self, owner: &Pubkey, position_id: u32) -> Option<PositionAccount> {
+        let account = self.account(position_pda(owner, position_id)).await?;
+        Some(PositionAccount::try_deserialize(&mut account.data.as_slice()).unwrap())
+    }
+
+    pub async fn portfolio(&mut self, owner: &Pubkey) -> Option<PortfolioAccount> {
+        let account = self.account(portfolio_pda(owner)).await?;
+        Some(PortfolioAccount::try_deserialize(&mut account.data.as_slice()).unwrap())
+    }
+
+    pub async fn rule_set(&mut self, owner: &Pubkey) -> Option<RuleSetAccount> {
+        let account = self.account(rule_set_pda(owner)).await?;
+        Some(RuleSetAccount::try_deserialize(&mut account.data.as_slice()).unwrap())
+    }
+
+    pub async fn sponsor_budget(
+        &mut self,
+        sponsor: &Pubkey,
+        owner: &Pubkey,
+    ) -> Option<SponsorBudget> {
+        let account = self.account(sponsor_budget_pda(sponsor, owner)).await?;
+        Some(SponsorBudget::try_deserialize(&mut account.data.as_slice()).unwrap())
+    }
+
+    pub async fn subscription(&mut self, position: &Pubkey) -> Option<Subscription> {
+        let account = self.account(subscription_pda(position)).await?;
+        Some(Subscription::try_deserialize(&mut account.data.as_slice()).unwrap())
+    }
+
+    pub async fn check_bounty(&mut self, position: &Pubkey) -> Option<CheckBounty> {
+        let account = self.account(check_bounty_pda(position)).await?;
+        Some(CheckBounty::try_deserialize(&mut account.data.as_slice()).unwrap())
+    }
+
+    pub async fn auto_action(&mut self, position: &Pubkey) -> Option<AutoActionConfig> {
+        let account = self.account(auto_action_pda(position)).await?;
+        Some(AutoActionConfig::try_deserialize(&mut account.data.as_slice()).unwrap())
+    }
+
+    pub async fn stop_loss(&mut self, position: &Pubkey) -> Option<StopLossAccount> {
+        let account = self.account(stop_loss_pda(position)).await?;
+        Some(StopLossAccount::try_deserialize(&mut account.data.as_slice()).unwrap())
+    }
+
+    pub async fn action_memo(&mut self, position: &Pubkey) -> Option<ActionMemo> {
+        let account = self.account(action_memo_pda(position)).await?;
+        Some(ActionMemo::try_deserialize(&mut account.data.as_slice()).unwrap())
+    }
+
+    pub async fn keeper_account(&mut self, keeper: &Pubkey) -> Option<KeeperAccount> {
+        let account = self.account(keeper_account_pda(keeper)).await?;
+        Some(KeeperAccount::try_deserialize(&mut account.data.as_slice()).unwrap())
+    }
+
+    pub async fn keeper_lease(&mut self, keeper: &Pubkey, shard: u16) -> Option<KeeperLease> {
+        let account = self.account(keeper_lease_pda(keeper, shard)).await?;
+        Some(KeeperLease::try_deserialize(&mut account.data.as_slice()).unwrap())
+    }
+
+    pub async fn protocol_adapter(&mut self, protocol_program: &Pubkey) -> Option<ProtocolAdapter> {
+        let account = self.account(protocol_adapter_pda(protocol_program)).await?;
+        Some(ProtocolAdapter::try_deserialize(&mut account.data.as_slice()).unwrap())
+    }
+
+    pub async fn risk_heatmap(&mut self) -> Option<RiskHeatmap> {
+        let account = self.account(risk_heatmap_pda()).await?;
+        Some(RiskHeatmap::try_deserialize(&mut account.data.as_slice()).unwrap())
+    }
+
+    pub async fn viewer_grant(
+        &mut self,
+        position: &Pubkey,
+        viewer: &Pubkey,
+    ) -> Option<ViewerGrant> {
+        let account = self.account(viewer_grant_pda(position, viewer)).await?;
+        Some(ViewerGrant::try_deserialize(&mut account.data.as_slice()).unwrap())
+    }
+
+    pub async fn feed_migration(&mut self, price_feed: &Pubkey) -> Option<FeedMigration> {
+        let account = self.account(feed_migration_pda(price_feed)).await?;
+        Some(FeedMigration::try_deserialize(&mut account.data.as_slice()).unwrap())
+    }
+
+    pub async fn tvl_snapshot(&mut self, protocol_program: &Pubkey) -> Option<ProtocolTvlSnapshot> {
+        let account = self.account(tvl_snapshot_pda(protocol_program)).await?;
+        Some(ProtocolTvlSnapshot::try_deserialize(&mut account.data.as_slice()).unwrap())
+    }
+
+    pub async fn reveal_inbox(&mut self, position: &Pubkey) -> Option<RevealInbox> {
+        let account = self.account(reveal_inbox_pda(position)).await?;
+        Some(RevealInbox::try_deserialize(&mut account.data.as_slice()).unwrap())
+    }
+
+    pub async fn monitoring_plan(&mut self) -> Option<MonitoringPlan> {
+        let account = self.account(monitoring_plan_pda()).await?;
+        Some(MonitoringPlan::try_deserialize(&mut account.data.as_slice()).unwrap())
+    }
+
+    pub async fn receipt_registry(&mut self) -> Option<ReceiptRegistry> {
+        let account = self.account(receipt_registry_pda()).await?;
+        Some(ReceiptRegistry::try_deserialize(&mut account.data.as_slice()).unwrap())
+    }
+
+    pub async fn global_config(&mut self) -> Option<GlobalConfig> {
+        let account = self.account(global_config_pda()).await?;
+        Some(GlobalConfig::try_deserialize(&mut account.data.as_slice()).unwrap())
+    }
+
+    pub async fn owner_index(&mut self, registrant: &Pubkey, page: u32) -> Option<OwnerIndex> {
+        let account = self.account(owner_index_pda(registrant, page)).await?;
+        Some(OwnerIndex::try_deserialize(&mut account.data.as_slice()).unwrap())
+    }
+
+    pub async fn risk_model_config(&mut self) -> Option<RiskModelConfig> {
+        let account = self.account(risk_model_config_pda()).await?;
+        Some(RiskModelConfig::try_deserialize(&mut account.data.as_slice()).unwrap())
+    }
+
+    pub async fn admin_audit_log(&mut self) -> Option<AdminAuditLog> {
+        let account = self.account(admin_audit_log_pda()).await?;
+        Some(bytemuck::pod_read_unaligned(&account.data[8..]))
+    }
+
+    pub async fn risk_history(&mut self, position: &Pubkey) -> Option<RiskHistoryAccount> {
+        let account = self.account(risk_history_pda(position)).await?;
+        Some(bytemuck::pod_read_unaligned(&account.data[8..]))
+    }
+
+    /// Captures the current state of `addresses`; missing accounts are skipped.
+    pub async fn snapshot(&mut self, addresses: &[Pubkey]) -> fixtures::Fixture {
+        let mut fixture = fixtures::Fixture::new();
+        for address in addresses {
+            if let Some(account) = self.account(*address).await {
+                fixture = fixture.raw(*address, account);
+            }
+        }
+        fixture
+    }
+
+    /// Current `Clock::unix_timestamp`.
+    pub async fn now(&mut self) -> i64 {
+        self.ctx
+            .banks_client
+            .get_sysvar::<Clock>()
+            .await
+            .unwrap()
+            .unix_timestamp
+    }
+
+    /// Writes a position account directly, bypassing `register_position`.
+    pub fn set_position(&mut self, position: &PositionAccount) {
+        self.set_program_account(
+            &position_pda(&position.registrant, position.position_id),
+            position,
+        );
+    }
+
+    /// Writes a portfolio account directly, bypassing `register_portfolio`.
+    pub fn set_portfolio(&mut self, portfolio: &PortfolioAccount) {
+        self.set_program_account(&portfolio_pda(&portfolio.owner), portfolio);
+    }
+
+    /// Writes a rule set account directly, bypassing `set_rule_set`.
+    pub fn set_rule_set(&mut self, rule_set: &RuleSetAccount) {
+        self.set_program_account(&rule_set_pda(&rule_set.owner), rule_set);
+    }
+
+    /// Registers `layout` for `protocol_program`, bypassing
+    /// `register_protocol_adapter`.
+    pub fn set_protocol_adapter(&mut self, protocol_program: Pubkey, layout: ObligationLayout) {
+        let adapter = ProtocolAdapter {
+            bump: Pubkey::find_program_address(
+                &[b"protocol_adapter", protocol_program.as_ref()],
+                &sentinel::ID,
+            )
+            .1,
+            protocol_program,
+            layout,
+            blackouts: Vec::new(),
+        };
+        self.set_program_account(&protocol_adapter_pda(&protocol_program), &adapter);
+    }
+
+    /// Writes page `page` of `registrant`'s position index directly, listing
+    /// `position_ids`.
+    pub fn set_owner_index(&mut self, registrant: Pubkey, page: u32, position_ids: &[u32]) {
+        let index = OwnerIndex {
+            bump: Pubkey::find_program_address(
+                &[b"owner_index", registrant.as_ref(), &page.to_le_bytes()],
+                &sentinel::ID,
+            )
+            .1,
+            registrant,
+            page,
+            position_ids: position_ids.to_vec(),
+        };
+        self.set_program_account(&owner_index_pda(&registrant, page), &index);
+    }
+
+    pub fn set_reveal_inbox(&mut self, inbox: &RevealInbox) {
+        self.set_program_account(&reveal_inbox_pda(&inbox.position), inbox);
+    }
+
+    pub fn set_auto_action(&mut self, config: &AutoActionConfig) {
+        self.set_program_account(&auto_action_pda(&config.position), config);
+    }
+
+    pub fn set_stop_loss(&mut self, stop_loss: &StopLossAccount) {
+        self.set_program_account(&stop_loss_pda(&stop_loss.position), stop_loss);
+    }
+
+    pub fn set_action_memo(&mut self, memo: &ActionMemo) {
+        self.set_program_account(&action_memo_pda(&memo.position), memo);
+    }
+
+    pub fn set_tvl_snapshot(&mut self, snapshot: &ProtocolTvlSnapshot) {
+        self.set_program_account(&tvl_snapshot_pda(&snapshot.protocol_program), snapshot);
+    }
+
+    pub fn set_keeper_lease(&mut self, lease: &KeeperLease) {
+        self.set_program_account(&keeper_lease_pda(&lease.keeper, lease.shard), lease);
+    }
+
+    /// Writes a registry account bonding `bond` lamports above rent.
+    pub fn set_keeper_account(&mut self, keeper: &KeeperAccount, bond: u64) {
+        let address = keeper_account_pda(&keeper.keeper);
+        let fixture = fixtures::Fixture::new().account(address, keeper);
+        let mut account = fixture.get(&address).unwrap().clone();
+        account.lamports += bond;
+        self.ctx.set_account(&address, &account.into());
+    }
+
+    pub fn set_sponsor_budget(&mut self, budget: &SponsorBudget) {
+        self.set_program_account(&sponsor_budget_pda(&budget.sponsor, &budget.owner), budget);
+    }
+
+    pub fn set_monitoring_plan(&mut self, fee_per_check: u64) {
+        let plan = MonitoringPlan {
+            bump: Pubkey::find_program_address(&[b"monitoring_plan"], &sentinel::ID).1,
+            fee_per_check,
+            referral_share_bps: 0,
+        };
+        self.set_program_account(&monitoring_plan_pda(), &plan);
+    }
+
+    pub fn set_risk_model_config(&mut self, weights: RiskWeights, severity_bands: [u8; 3]) {
+        let config = RiskModelConfig {
+            bump: Pubkey::find_program_address(&[b"risk_model_config"], &sentinel::ID).1,
+            weights,
+            severity_bands,
+            updated_at: 0,
+        };
+        self.set_program_account(&risk_model_config_pda(), &config);
+    }
+
+    /// Writes the position's `RiskScoreAccount` as a first
+    /// `compute_risk_score` would, before its callback.
+    pub fn set_risk_score(&mut self, position: &Pubkey) {
+        let score = RiskScoreAccount {
+            bump: Pubkey::find_program_address(&[b"risk_score", position.as_ref()], &sentinel::ID)
+                .1,
+            position: *position,
+            score: [0; 32],
+            nonce: 0,
+            computed_at: 0,
+        };
+        self.set_program_account(&risk_score_pda(position), &score);
+    }
+
+    pub fn set_global_config(&mut self, config: &GlobalConfig) {
+        self.set_program_account(&global_config_pda(), config);
+    }
+
+    pub fn set_default_max_staleness(&mut self, max_staleness_secs: i64) {
+        self.set_global_config(&global_config(max_staleness_secs));
+    }
+
+    /// Writes an admin audit log as `init_admin_audit_log` would, with
+    /// `total` changes recorded before.
+    pub fn set_admin_audit_log(&mut self, total: u64) {
+        let log = AdminAuditLog {
+            bump: Pubkey::find_program_address(&[b"admin_audit_log"], &sentinel::ID).1,
+            head: (total % sentinel::ADMIN_AUDIT_LOG_LEN as u64) as u32,
+            total,
+            ..bytemuck::Zeroable::zeroed()
+        };
+        let mut data = AdminAuditLog::DISCRIMINATOR.to_vec();
+        data.extend_from_slice(bytemuck::bytes_of(&log));
+        let account = Account {
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            owner: sentinel::ID,
+            executable: false,
+            rent_epoch: 0,
+        };
+        self.ctx
+            .set_account(&admin_audit_log_pda(), &account.into());
+    }
+
+    /// Writes the program's `ProgramData` with `authority` as upgrade
+    /// authority, for the instructions only it may call.
+    pub fn set_upgrade_authority(&mut self, authority: &Pubkey) {
+        // Bincode of `UpgradeableLoaderState::ProgramData` without the ELF
+        let mut data = 3u32.to_le_bytes().to_vec();
+        data.extend_from_slice(&0u64.to_le_bytes());
+        data.push(1);
+        data.extend_from_slice(authority.as_ref());
+        let account = Account {
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            owner: bpf_loader_upgradeable::ID,
+            executable: false,
+            rent_epoch: 0,
+        };
+        self.ctx.set_account(&program_data_pda(), &account.into());
+    }
+
+    /// Writes the receipt registry and its non-transferable mint as
+    /// `init_receipt_mint` would, with `outstanding` receipts minted.
+    pub fn set_receipt_mint(&mut self, outstanding: u64) {
+        use anchor_spl::token_2022::spl_token_2022::{
+            extension::{
+                non_transferable::NonTransferable, BaseStateWithExtensionsMut, ExtensionType,
+                StateWithExtensionsMut,
+            },
+            state::Mint,
+        };
+        let registry = ReceiptRegistry {
+            bump: Pubkey::find_program_address(&[b"receipt_registry"], &sentinel::ID).1,
+            mint: receipt_mint_pda(),
+            outstanding,
+        };
+        self.set_program_account(&receipt_registry_pda(), &registry);
+
+        let space =
+            ExtensionType::try_calculate_account_len::<Mint>(&[ExtensionType::NonTransferable])
+                .unwrap();
+        let mut data = vec![0; space];
+        let mut mint = StateWithExtensionsMut::<Mint>::unpack_uninitialized(&mut data).unwrap();
+        mint.init_extension::<NonTransferable>(true).unwrap();
+        mint.base = Mint {
+            mint_authority: Some(receipt_registry_pda()).into(),
+            supply: outstanding,
+            decimals: 0,
+            is_initialized: true,
+            freeze_authority: None.into(),
+        };
+        mint.pack_base();
+        mint.init_account_type().unwrap();
+        self.set_token_2022_account(&receipt_mint_pda(), data);
+    }
+
+    /// Writes `owner`'s associated receipt account holding `amount`
+    /// receipts.
+    pub fn set_receipt_account(&mut self, owner: &Pubkey, amount: u64) {
+        use anchor_spl::token_2022::spl_token_2022::{
+            extension::{
+                immutable_owner::ImmutableOwner, non_transferable::NonTransferableAccount,
+                BaseStateWithExtensionsMut, ExtensionType, StateWithExtensionsMut,
+            },
+            state::{Account as TokenAccount, AccountState},
+        };
+        let space = ExtensionType::try_calculate_account_len::<TokenAccount>(&[
+            ExtensionType::ImmutableOwner,
+            ExtensionType::NonTransferableAccount,
+        ])
+        .unwrap();
+        let mut data = vec![0; space];
+        let mut account =
+            StateWithExtensionsMut::<TokenAccount>::unpack_uninitialized(&mut data).unwrap();
+        account.init_extension::<ImmutableOwner>(true).unwrap();
+        account
+            .init_extension::<NonTransferableAccount>(true)
+            .unwrap();
+        account.base = TokenAccount {
+            mint: receipt_mint_pda(),
+            owner: *owner,
+            amount,
+            state: AccountState::Initialized,
+            ..Default::default()
+        };
+        account.pack_base();
+        account.init_account_type().unwrap();
+        self.set_token_2022_account(&receipt_account(owner), data);
+    }
+
+    fn set_token_2022_account(&mut self, address: &Pubkey, data: Vec<u8>) {
+        let account = Account {
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            owner: anchor_spl::token_2022::ID,
+            executable: false,
+            rent_epoch: 0,
+        };
+        self.ctx.set_account(address, &account.into());
+    }
+
+    /// Writes a subscription holding `balance` lamports above rent.
+    pub fn set_subscription(&mut self, subscription: &Subscription, balance: u64) {
+        let address = subscription_pda(&subscription.position);
+        let fixture = fixtures::Fixture::new().account(address, subscription);
+        let mut account = fixture.get(&address).unwrap().clone();
+        account.lamports += balance;
+        self.ctx.set_account(&address, &account.into());
+    }
+
+    pub fn set_check_bounty(&mut self, bounty: &CheckBounty, balance: u64) {
+        let address = check_bounty_pda(&bounty.position);
+        let fixture = fixtures::Fixture::new().account(address, bounty);
+        let mut account = fixture.get(&address).unwrap().clone();
+        account.lamports += balance;
+        self.ctx.set_account(&address, &account.into());
+    }
+
+    /// Replaces the cluster with one of 3 nodes of which none have joined, so
+    /// `validation::is_degraded` holds.
+    pub fn degrade_cluster(&mut self) {
+        let mut cluster = Cluster::DISCRIMINATOR.to_vec();
+        cluster.extend_from_slice(&[0, 0]); // td_info, authority: None
+        cluster.extend_from_slice(&3u16.to_le_bytes()); // cluster_size
+        self.ctx.set_account(
+            &pda::cluster_acc(CLUSTER_OFFSET),
+            &arcium_account(cluster).into(),
+        );
+    }
+
+    /// Writes a zero-copy risk history account directly.
+    pub fn set_risk_history(&mut self, history: &RiskHistoryAccount) {
+        let mut data = RiskHistoryAccount::DISCRIMINATOR.to_vec();
+        data.extend_from_slice(bytemuck::bytes_of(history));
+        let account = Account {
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            owner: sentinel::ID,
+            executable: false,
+            rent_epoch: 0,
+        };
+        self.ctx
+            .set_account(&risk_history_pda(&history.position), &account.into());
+    }
+
+    /// Writes an Anchor account owned by the sentinel program.
+    pub fn set_program_account<T: AccountSerialize + Discriminator + Space>(
+        &mut self,
+        address: &Pubkey,
+        value: &T,
+    ) {
+        let fixture = fixtures::Fixture::new().account(*address, value);
+        let account = fixture.get(address).unwrap().clone();
+        self.ctx.set_account(address, &account.into());
+    }
+}
+
+/// A registered, active position with no computation in flight.
+pub fn position(owner: Pubkey, position_id: u32) -> PositionAccount {
+    PositionAccount {
+        bump: Pubkey::find_program_address(
+            &[b"position", owner.as_ref(), &position_id.to_le_bytes()],
+            &sentinel::ID,
+        )
+        .1,
+        risk_state: [[1; 32]; 3],
+        alert_config: [[0; 32]; 3],
+        position_id,
+        owner,
+        nonce: 1,
+        last_check: 0,
+        is_active: true,
+        referrer: None,
+        pending_computation: None,
+        price_feed: None,
+        keeper: None,
+        keeper_expiry_slot: None,
+        cross_chain_emitter: None,
+        pending_since: 0,
+        alert_nonce: 0,
+        alert_config_set: false,
+        registrant: owner,
+        pending_owner: None,
+        min_action_severity: 1,
+        max_staleness_secs: None,
+        health_factor: [0; 32],
+        label_hash: [0; 32],
+        health_factor_nonce: 0,
+        health_factor_at: 0,
+        tvl_protocol: None,
+        tvl_drop_bps: sentinel::DEFAULT_TVL_DROP_BPS,
+        config_version: 0,
+        risk_model: RiskModel::BUILT_IN,
+        risk_state_version: 0,
+        pending_circuit_version: 0,
+        last_error: None,
+        metadata: sentinel::PositionMetadata::UNSPECIFIED,
+        deferred_until: 0,
+        heatmap_epoch: 0,
+        min_check_interval_secs: 0,
+        size_disclosure_allowed: false,
+        size_bucket: None,
+        version: sentinel::POSITION_VERSION,
+        registered_keepers_only: false,
+        heartbeat_deadline_secs: 0,
+        monitoring_lapsed: false,
+        last_seen: 0,
+        receipt_minted: false,
+        indexed: false,
+        index_page: 0,
+        _reserved: [0; sentinel::POSITION_RESERVED_BYTES],
+    }
+}
+
+/// An empty portfolio.
+pub fn portfolio(owner: Pubkey) -> PortfolioAccount {
+    PortfolioAccount {
+        bump: Pubkey::find_program_address(&[b"portfolio", owner.as_ref()], &sentinel::ID).1,
+        risk_state: [[0; 32]; 2],
+        owner,
+        nonce: 0,
+        last_aggregated: 0,
+        positions: vec![],
+        pending_computation: None,
+    }
+}
+
+/// An unpaused global config with `max_staleness_secs`, the default update
+/// delay, no change pending, the built-in risk model and no admin.
+pub fn global_config(max_staleness_secs: i64) -> GlobalConfig {
+    GlobalConfig {
+        bump: Pubkey::find_program_address(&[b"global_config"], &sentinel::ID).1,
+        max_staleness_secs,
+        update_delay_secs: sentinel::DEFAULT_CONFIG_UPDATE_DELAY_SECS,
+        pending_update: None,
+        config_version: 0,
+        risk_model: RiskModel::BUILT_IN,
+        admin: Pubkey::default(),
+        paused: false,
+        circuit_version: 0,
+    }
+}
+
+/// `old_feed` flagged as deprecated, replaced by `replacement` if given.
+pub fn feed_migration(old_feed: Pubkey, replacement: Option<Pubkey>) -> FeedMigration {
+    FeedMigration {
+        bump: Pubkey::find_program_address(&[b"feed_migration", old_feed.as_ref()], &sentinel::ID)
+            .1,
+        old_feed,
+        flagged_by: Pubkey::new_unique(),
+        flagged_at: 1,
+        replacement,
+        migrated_at: replacement.map_or(0, |_| 1),
+        repointed: 0,
+    }
+}
+
+/// An unconfigured risk heatmap sampling `epoch`.
+pub fn risk_heatmap(epoch: u32) -> RiskHeatmap {
+    RiskHeatmap {
+        bump: Pubkey::find_program_address(&[b"risk_heatmap"], &sentinel::ID).1,
+        counts: [[0; 32]; sentinel::HEATMAP_BUCKETS],
+        nonce: 0,
+        classes: [Pubkey::default(); sentinel::HEATMAP_CLASSES],
+        epoch,
+        sampled: [0; sentinel::HEATMAP_BUCKETS],
+        pending_sampled: [0; sentinel::HEATMAP_BUCKETS],
+        pending_computation: None,
+        at_risk: [None; sentinel::HEATMAP_BUCKETS],
+        published_sampled: [0; sentinel::HEATMAP_BUCKETS],
+        published_at: 0,
+    }
+}
+
+/// `owner`'s rule set holding `params`.
+pub fn rule_set(owner: Pubkey, params: [u64; RULE_PARAMS_LEN]) -> RuleSetAccount {
+    RuleSetAccount {
+        bump: Pubkey::find_program_address(&[b"rule_set", owner.as_ref()], &sentinel::ID).1,
+        owner,
+        params,
+    }
+}
+
+/// An empty risk history for the position at `position`.
+pub fn risk_history(position: Pubkey) -> RiskHistoryAccount {
+    RiskHistoryAccount {
+        bump: Pubkey::find_program_address(&[b"risk_history", position.as_ref()], &sentinel::ID).1,
+        position,
+        ..bytemuck::Zeroable::zeroed()
+    }
+}
+
+pub fn sponsor_budget(sponsor: Pubkey, owner: Pubkey, remaining: u32) -> SponsorBudget {
+    SponsorBudget {
+        bump: Pubkey::find_program_address(
+            &[b"sponsor_budget", sponsor.as_ref(), owner.as_ref()],
+            &sentinel::ID,
+        )
+        .1,
+        sponsor,
+        owner,
+        remaining,
+        used: 0,
+    }
+}
+
+pub fn sponsor_budget_pda(sponsor: &Pubkey, owner: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"sponsor_budget", sponsor.as_ref(), owner.as_ref()],
+        &sentinel::ID,
+    )
+    .0
+}
+
+/// An empty inbox, so `init_if_needed` finds it and needs no CPI.
+pub fn reveal_inbox(position: Pubkey) -> RevealInbox {
+    RevealInbox {
+        bump: Pubkey::find_program_address(&[b"reveal_inbox", position.as_ref()], &sentinel::ID).1,
+        position,
+        owner: Pubkey::default(),
+        encryption_key: [0; 32],
+        computation_offset: 0,
+        delivered_at: 0,
+        envelope: Default::default(),
+        memo: None,
+    }
+}
+
+/// An empty attestation, so `init_if_needed` finds it and needs no CPI.
+pub fn attestation(position: Pubkey) -> AttestationAccount {
+    AttestationAccount {
+        bump: Pubkey::find_program_address(&[b"attestation", position.as_ref()], &sentinel::ID).1,
+        position,
+        owner: Pubkey::default(),
+        min_health_factor_bps: 0,
+        healthy: false,
+        health_factor_at: 0,
+        slot: 0,
+        attested_at: 0,
+        requested_min_health_factor_bps: 0,
+    }
+}
+
+/// `owner`'s grant to `viewer` on their position `position_id`, encrypting
+/// to `encryption_key`.
+pub fn viewer_grant(
+    owner: Pubkey,
+    position_id: u32,
+    viewer: Pubkey,
+    encryption_key: [u8; 32],
+) -> ViewerGrant {
+    let position = position_pda(&owner, position_id);
+    ViewerGrant {
+        bump: Pubkey::find_program_address(
+            &[b"viewer_grant", position.as_ref(), viewer.as_ref()],
+            &sentinel::ID,
+        )
+        .1,
+        position,
+        owner,
+        viewer,
+        encryption_key,
+        granted_at: 0,
+        computation_offset: 0,
+        delivered_at: 0,
+        envelope: Default::default(),
+    }
+}
+
+/// An auto action for `owner`'s position that calls `program_id` with no
+/// accounts or data.
+pub fn auto_action(owner: Pubkey, position_id: u32, program_id: Pubkey) -> AutoActionConfig {
+    let position = position_pda(&owner, position_id);
+    AutoActionConfig {
+        bump: Pubkey::find_program_address(&[b"auto_action", position.as_ref()], &sentinel::ID).1,
+        position,
+        owner,
+        program_id,
+        accounts: vec![],
+        data: vec![],
+        due_severity: 0,
+    }
+}
+
+/// An armed stop-loss for `owner`'s position with no executor.
+pub fn stop_loss(owner: Pubkey, position_id: u32) -> StopLossAccount {
+    let position = position_pda(&owner, position_id);
+    StopLossAccount {
+        bump: Pubkey::find_program_address(&[b"stop_loss", position.as_ref()], &sentinel::ID).1,
+        config: [[4; 32]; 3],
+        encryption_pubkey: [5; 32],
+        nonce: 1,
+        position,
+        owner,
+        executor: None,
+        executor_accounts: vec![],
+        last_evaluated_at: 0,
+        triggered_at: 0,
+        action: 0,
+        max_slippage_bps: 0,
+        executed_at: 0,
+    }
+}
+
+/// An action memo of two ciphertexts for `owner`'s position.
+pub fn action_memo(owner: Pubkey, position_id: u32) -> ActionMemo {
+    let position = position_pda(&owner, position_id);
+    ActionMemo {
+        bump: Pubkey::find_program_address(&[b"action_memo", position.as_ref()], &sentinel::ID).1,
+        position,
+        owner,
+        memo: EncryptedMemo {
+            encryption_key: [5; 32],
+            nonce: 1,
+            ciphertexts: vec![[6; 32]; 2],
+        },
+        updated_at: 0,
+    }
+}
+
+/// `keeper`'s lease on `shard`, held by `region` until `expires_at`.
+pub fn keeper_lease(keeper: Pubkey, shard: u16, region: u8, expires_at: i64) -> KeeperLease {
+    KeeperLease {
+        bump: Pubkey::find_program_address(
+            &[b"keeper_lease", keeper.as_ref(), &shard.to_le_bytes()],
+            &sentinel::ID,
+        )
+        .1,
+        keeper,
+        shard,
+        region,
+        expires_at,
+    }
+}
+
+/// `keeper` registered at time 0 and not unbonding.
+pub fn keeper_account(keeper: Pubkey) -> KeeperAccount {
+    KeeperAccount {
+        bump: Pubkey::find_program_address(&[b"keeper", keeper.as_ref()], &sentinel::ID).1,
+        keeper,
+        registered_at: 0,
+        unbonding_at: 0,
+        slashed_lamports: 0,
+        slash_count: 0,
+    }
+}
+
+/// An empty subscription for `owner`'s position.
+pub fn subscription(owner: Pubkey, position_id: u32) -> Subscription {
+    let position = position_pda(&owner, position_id);
+    Subscription {
+        bump: Pubkey::find_program_address(&[b"subscription", position.as_ref()], &sentinel::ID).1,
+        position,
+        owner,
+        fees_paid: 0,
+        trial_lamports: 0,
+        trial_expires_at: 0,
+        pending_fee: 0,
+        pending_referral_fee: 0,
+        pending_keeper: None,
+    }
+}
+
+/// A bounty of `check_bounty_lamports` per check on `owner`'s position, with
+/// no check pending.
+pub fn check_bounty(owner: Pubkey, position_id: u32, check_bounty_lamports: u64) -> CheckBounty {
+    let position = position_pda(&owner, position_id);
+    CheckBounty {
+        bump: Pubkey::find_program_address(&[b"check_bounty", position.as_ref()], &sentinel::ID).1,
+        position,
+        owner,
+        check_bounty_lamports,
+        pending_payee: None,
+        bounties_paid: 0,
+    }
+}
+
+pub fn keeper_lease_pda(keeper: &Pubkey, shard: u16) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"keeper_lease", keeper.as_ref(), &shard.to_le_bytes()],
+        &sentinel::ID,
+    )
+    .0
+}
+
+pub fn keeper_account_pda(keeper: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"keeper", keeper.as_ref()], &sentinel::ID).0
+}
+
+pub fn auto_action_pda(position: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"auto_action", position.as_ref()], &sentinel::ID).0
+}
+
+pub fn stop_loss_pda(position: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"stop_loss", position.as_ref()], &sentinel::ID).0
+}
+
+pub fn action_memo_pda(position: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"action_memo", position.as_ref()], &sentinel::ID).0
+}
+
+pub fn subscription_pda(position: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"subscription", position.as_ref()], &sentinel::ID).0
+}
+
+pub fn check_bounty_pda(position: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"check_bounty", position.as_ref()], &sentinel::ID).0
+}
+
+pub fn owner_index_pda(registrant: &Pubkey, page: u32) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"owner_index", registrant.as_ref(), &page.to_le_bytes()],
+        &sentinel::ID,
+    )
+    .0
+}
+
+pub fn archive_pda(owner: &Pubkey, position_id: u32) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"archive", owner.as_ref(), &position_id.to_le_bytes()],
+        &sentinel::ID,
+    )
+    .0
+}
+
+pub fn protocol_adapter_pda(protocol_program: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"protocol_adapter", protocol_program.as_ref()],
+        &sentinel::ID,
+    )
+    .0
+}
+
+pub fn monitoring_plan_pda() -> Pubkey {
+    Pubkey::find_program_address(&[b"monitoring_plan"], &sentinel::ID).0
+}
+
+pub fn admin_audit_log_pda() -> Pubkey {
+    Pubkey::find_program_address(&[b"admin_audit_log"], &sentinel::ID).0
+}
+
+pub fn program_data_pda() -> Pubkey {
+    Pubkey::find_program_address(&[sentinel::ID.as_ref()], &bpf_loader_upgradeable::ID).0
+}
+
+pub fn tvl_snapshot_pda(protocol_program: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"tvl_snapshot", protocol_program.as_ref()], &sentinel::ID).0
+}
+
+pub fn viewer_grant_pda(position: &Pubkey, viewer: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"viewer_grant", position.as_ref(), viewer.as_ref()],
+        &sentinel::ID,
+    )
+    .0
+}
+
+pub fn feed_migration_pda(price_feed: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"feed_migration", price_feed.as_ref()], &sentinel::ID).0
+}
+
+pub fn risk_heatmap_pda() -> Pubkey {
+    Pubkey::find_program_address(&[b"risk_heatmap"], &sentinel::ID).0
+}
+
+pub fn risk_model_config_pda() -> Pubkey {
+    Pubkey::find_program_address(&[b"risk_model_config"], &sentinel::ID).0
+}
+
+pub fn risk_score_pda(position: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"risk_score", position.as_ref()], &sentinel::ID).0
+}
+
+pub fn global_config_pda() -> Pubkey {
+    Pubkey::find_program_address(&[b"global_config"], &sentinel::ID).0
+}
+
+pub fn reveal_inbox_pda(position: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"reveal_inbox", position.as_ref()], &sentinel::ID).0
+}
+
+pub fn attestation_pda(position: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"attestation", position.as_ref()], &sentinel::ID).0
+}
+
+pub fn risk_history_pda(position: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"risk_history", position.as_ref()], &sentinel::ID).0
+}
+
+pub fn rule_set_pda(owner: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"rule_set", owner.as_ref()], &sentinel::ID).0
+}
+
+pub fn portfolio_pda(owner: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"portfolio", owner.as_ref()], &sentinel::ID).0
+}
+
+pub fn position_pda(owner: &Pubkey, position_id: u32) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"position", owner.as_ref(), &position_id.to_le_bytes()],
+        &sentinel::ID,
+    )
+    .0
+}
+
+pub fn comp_def_address(circuit: &str) -> Pubkey {
+    pda::computation_definition_acc(&sentinel::ID, comp_def_offset(circuit))
+}
+
+/// Accounts of `sentinel::accounts::CheckHealth` for `owner`'s position.
+/// Keeper checks include the monitoring plan and subscription.
+pub fn check_health_ix(
+    payer: &Pubkey,
+    owner: &Pubkey,
+    position_id: u32,
+    computation_offset: u64,
+) -> Instruction {
+    check_health_with_market_ix(payer, owner, position_id, computation_offset, None, None)
+}
+
+/// `check_health_ix` with the optional price update and TVL snapshot.
+pub fn check_health_with_market_ix(
+    payer: &Pubkey,
+    owner: &Pubkey,
+    position_id: u32,
+    computation_offset: u64,
+    price_update: Option<Pubkey>,
+    tvl_protocol: Option<Pubkey>,
+) -> Instruction {
+    let keeper_check = payer != owner;
+    let accounts = sentinel::accounts::CheckHealth {
+        payer: *payer,
+        sign_pda_account: sign_pda(),
+        mxe_account: pda::mxe_acc(&sentinel::ID),
+        mempool_account: pda::mempool_acc(CLUSTER_OFFSET),
+        executing_pool: pda::execpool_acc(CLUSTER_OFFSET),
+        computation_account: pda::computation_acc(CLUSTER_OFFSET, computation_offset),
+        comp_def_account: comp_def_address("check_position_health"),
+        cluster_account: pda::cluster_acc(CLUSTER_OFFSET),
+        pool_account: ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+        clock_account: ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+        system_program: System::id(),
+        arcium_program: ARCIUM_PROG_ID,
+        owner: *owner,
+        position_acc: position_pda(owner, position_id),
+        global_config: global_config_pda(),
+        risk_history: None,
+        monitoring_plan: keeper_check.then(monitoring_plan_pda),
+        subscription: keeper_check.then(|| subscription_pda(&position_pda(owner, position_id))),
+        price_update,
+        tvl_snapshot: tvl_protocol.as_ref().map(tvl_snapshot_pda),
+        check_bounty: None,
+        keeper_account: None,
+    };
+    let data = sentinel::instruction::CheckHealth {
+        computation_offset,
+        _position_id: position_id,
+        encrypted_position: [[0; 32]; 3],
+        encryption_pubkey: [0; 32],
+        encryption_nonce: 0,
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Accounts of `sentinel::accounts::CheckHealthWithOracle` for `owner`'s
+/// position, priced from `price_update`.
+pub fn check_health_with_oracle_ix(
+    owner: &Pubkey,
+    position_id: u32,
+    computation_offset: u64,
+    price_update: Pubkey,
+) -> Instruction {
+    let accounts = sentinel::accounts::CheckHealthWithOracle {
+        payer: *owner,
+        sign_pda_account: sign_pda(),
+        mxe_account: pda::mxe_acc(&sentinel::ID),
+        mempool_account: pda::mempool_acc(CLUSTER_OFFSET),
+        executing_pool: pda::execpool_acc(CLUSTER_OFFSET),
+        computation_account: pda::computation_acc(CLUSTER_OFFSET, computation_offset),
+        comp_def_account: comp_def_address("check_position_health_with_price"),
+        cluster_account: pda::cluster_acc(CLUSTER_OFFSET),
+        pool_account: ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+        clock_account: ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+        system_program: System::id(),
+        arcium_program: ARCIUM_PROG_ID,
+        owner: *owner,
+        position_acc: position_pda(owner, position_id),
+        global_config: global_config_pda(),
+        price_update,
+    };
+    let data = sentinel::instruction::CheckHealthWithOracle {
+        computation_offset,
+        _position_id: position_id,
+        encrypted_position: [[0; 32]; 3],
+        encryption_pubkey: [0; 32],
+        encryption_nonce: 0,
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+pub fn check_perp_health_ix(
+    payer: &Pubkey,
+    owner: &Pubkey,
+    position_id: u32,
+    computation_offset: u64,
+    price_update: Pubkey,
+) -> Instruction {
+    let accounts = sentinel::accounts::CheckPerpHealth {
+        payer: *payer,
+        sign_pda_account: sign_pda(),
+        mxe_account: pda::mxe_acc(&sentinel::ID),
+        mempool_account: pda::mempool_acc(CLUSTER_OFFSET),
+        executing_pool: pda::execpool_acc(CLUSTER_OFFSET),
+        computation_account: pda::computation_acc(CLUSTER_OFFSET, computation_offset),
+        comp_def_account: comp_def_address("check_perp_health"),
+        cluster_account: pda::cluster_acc(CLUSTER_OFFSET),
+        pool_account: ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+        clock_account: ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+        system_program: System::id(),
+        arcium_program: ARCIUM_PROG_ID,
+        owner: *owner,
+        position_acc: position_pda(owner, position_id),
+        global_config: global_config_pda(),
+        price_update,
+    };
+    let data = sentinel::instruction::CheckPerpHealth {
+        computation_offset,
+        _position_id: position_id,
+        encrypted_position: [[0; 32]; 6],
+        encryption_pubkey: [0; 32],
+        encryption_nonce: 0,
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+pub fn compute_risk_score_ix(
+    payer: &Pubkey,
+    owner: &Pubkey,
+    position_id: u32,
+    computation_offset: u64,
+    price_update: Option<Pubkey>,
+) -> Instruction {
+    let position_acc = position_pda(owner, position_id);
+    let accounts = sentinel::accounts::ComputeRiskScore {
+        payer: *payer,
+        sign_pda_account: sign_pda(),
+        mxe_account: pda::mxe_acc(&sentinel::ID),
+        mempool_account: pda::mempool_acc(CLUSTER_OFFSET),
+        executing_pool: pda::execpool_acc(CLUSTER_OFFSET),
+        computation_account: pda::computation_acc(CLUSTER_OFFSET, computation_offset),
+        comp_def_account: comp_def_address("compute_risk_score"),
+        cluster_account: pda::cluster_acc(CLUSTER_OFFSET),
+        pool_account: ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+        clock_account: ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+        system_program: System::id(),
+        arcium_program: ARCIUM_PROG_ID,
+        owner: *owner,
+        position_acc,
+        global_config: global_config_pda(),
+        risk_model_config: risk_model_config_pda(),
+        price_update,
+        tvl_snapshot: None,
+        risk_score: risk_score_pda(&position_acc),
+        monitoring_plan: (payer != owner).then(monitoring_plan_pda),
+        subscription: (payer != owner).then(|| subscription_pda(&position_acc)),
+    };
+    let data = sentinel::instruction::ComputeRiskScore {
+        computation_offset,
+        _position_id: position_id,
+        encrypted_position: [[0; 32]; 3],
+        encryption_pubkey: [0; 32],
+        encryption_nonce: 0,
+        nonce: 0,
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+pub fn configure_alerts_ix(
+    owner: &Pubkey,
+    position_id: u32,
+    min_action_severity: u8,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::ConfigureAlerts {
+            position_id,
+            min_action_severity,
+        }
+        .data(),
+        sentinel::accounts::ConfigureAlerts {
+            owner: *owner,
+            position_acc: position_pda(owner, position_id),
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn set_heartbeat_deadline_ix(
+    owner: &Pubkey,
+    position_id: u32,
+    heartbeat_deadline_secs: u32,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::SetHeartbeatDeadline {
+            position_id,
+            heartbeat_deadline_secs,
+        }
+        .data(),
+        sentinel::accounts::SetHeartbeatDeadline {
+            owner: *owner,
+            position_acc: position_pda(owner, position_id),
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn heartbeat_ix(signer: &Pubkey, owner: &Pubkey, position_id: u32) -> Instruction {
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::Heartbeat { position_id }.data(),
+        sentinel::accounts::Heartbeat {
+            signer: *signer,
+            position_acc: position_pda(owner, position_id),
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn is_monitored_ix(owner: &Pubkey, position_id: u32, max_check_age_secs: u32) -> Instruction {
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::IsMonitored {
+            _position_id: position_id,
+            owner: *owner,
+            max_check_age_secs,
+        }
+        .data(),
+        sentinel::accounts::IsMonitored {
+            position_acc: position_pda(owner, position_id),
+        }
+        .to_account_metas(None),
+    )
+}
+
+/// Accounts of `sentinel::accounts::FlagStalePosition` for `owner`'s
+/// position, with its check bounty escrow if `check_bounty`.
+pub fn flag_stale_position_ix(
+    caller: &Pubkey,
+    owner: &Pubkey,
+    position_id: u32,
+    check_bounty: bool,
+) -> Instruction {
+    let position_acc = position_pda(owner, position_id);
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::FlagStalePosition { position_id }.data(),
+        sentinel::accounts::FlagStalePosition {
+            caller: *caller,
+            position_acc,
+            check_bounty: check_bounty.then(|| check_bounty_pda(&position_acc)),
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn set_min_check_interval_ix(
+    owner: &Pubkey,
+    position_id: u32,
+    min_check_interval_secs: u32,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::SetMinCheckInterval {
+            position_id,
+            min_check_interval_secs,
+        }
+        .data(),
+        sentinel::accounts::SetMinCheckInterval {
+            owner: *owner,
+            position_acc: position_pda(owner, position_id),
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn set_max_staleness_ix(
+    owner: &Pubkey,
+    position_id: u32,
+    max_staleness_secs: Option<i64>,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::SetMaxStaleness {
+            position_id,
+            max_staleness_secs,
+        }
+        .data(),
+        sentinel::accounts::SetMaxStaleness {
+            owner: *owner,
+            position_acc: position_pda(owner, position_id),
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn set_tvl_updater_ix(
+    authority: &Pubkey,
+    protocol_program: Pubkey,
+    updater: Pubkey,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::SetTvlUpdater {
+            protocol_program,
+            updater,
+        }
+        .data(),
+        sentinel::accounts::SetTvlUpdater {
+            authority: *authority,
+            tvl_snapshot: tvl_snapshot_pda(&protocol_program),
+            program_data: program_data_pda(),
+            admin_audit_log: admin_audit_log_pda(),
+            system_program: System::id(),
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn set_protocol_blackouts_ix(
+    authority: &Pubkey,
+    protocol_program: Pubkey,
+    windows: Vec<sentinel::BlackoutWindow>,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::SetProtocolBlackouts {
+            protocol_program,
+            windows,
+        }
+        .data(),
+        sentinel::accounts::SetProtocolBlackouts {
+            authority: *authority,
+            protocol_adapter: protocol_adapter_pda(&protocol_program),
+            program_data: program_data_pda(),
+            admin_audit_log: admin_audit_log_pda(),
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn update_tvl_snapshot_ix(updater: &Pubkey, protocol_program: Pubkey, tvl: u64) -> Instruction {
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::UpdateTvlSnapshot {
+            protocol_program,
+            tvl,
+        }
+        .data(),
+        sentinel::accounts::UpdateTvlSnapshot {
+            updater: *updater,
+            tvl_snapshot: tvl_snapshot_pda(&protocol_program),
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn set_tvl_protocol_ix(
+    owner: &Pubkey,
+    position_id: u32,
+    protocol_program: Option<Pubkey>,
+    tvl_drop_bps: u16,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::SetTvlProtocol {
+            position_id,
+            protocol_program,
+            tvl_drop_bps,
+        }
+        .data(),
+        sentinel::accounts::SetTvlProtocol {
+            owner: *owner,
+            position_acc: position_pda(owner, position_id),
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn set_price_feed_ix(
+    owner: &Pubkey,
+    position_id: u32,
+    price_feed: Option<Pubkey>,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::SetPriceFeed {
+            position_id,
+            price_feed,
+        }
+        .data(),
+        sentinel::accounts::SetPriceFeed {
+            owner: *owner,
+            position_acc: position_pda(owner, position_id),
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn flag_price_feed_ix(flagger: &Pubkey, price_feed: Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::FlagPriceFeed {}.data(),
+        sentinel::accounts::FlagPriceFeed {
+            flagger: *flagger,
+            price_feed,
+            feed_migration: feed_migration_pda(&price_feed),
+            system_program: System::id(),
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn migrate_price_feed_ix(
+    authority: &Pubkey,
+    old_feed: Pubkey,
+    replacement: Pubkey,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::MigratePriceFeed {
+            old_feed,
+            replacement,
+        }
+        .data(),
+        sentinel::accounts::MigratePriceFeed {
+            authority: *authority,
+            feed_migration: feed_migration_pda(&old_feed),
+            replacement_feed: replacement,
+            program_data: program_data_pda(),
+            admin_audit_log: admin_audit_log_pda(),
+        }
+        .to_account_metas(None),
+    )
+}
+
+/// Accounts of `sentinel::accounts::RepointPriceFeeds`, followed by
+/// `positions` as writable remaining accounts.
+pub fn repoint_price_feeds_ix(old_feed: &Pubkey, positions: &[Pubkey]) -> Instruction {
+    let mut accounts = sentinel::accounts::RepointPriceFeeds {
+        feed_migration: feed_migration_pda(old_feed),
+    }
+    .to_account_metas(None);
+    accounts.extend(positions.iter().map(|p| AccountMeta::new(*p, false)));
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::RepointPriceFeeds {}.data(),
+        accounts,
+    )
+}
+
+/// A fully verified Pyth `PriceUpdateV2` account with a 1% confidence interval.
+pub fn pyth_price_update(price: i64, exponent: i32, publish_time: i64) -> Account {
+    let mut data = vec![34, 241, 35, 99, 157, 126, 244, 205];
+    data.extend_from_slice(&[0; 32]); // write_authority
+    data.push(1); // VerificationLevel::Full
+    data.extend_from_slice(&[0; 32]); // feed_id
+    data.extend_from_slice(&price.to_le_bytes());
+    data.extend_from_slice(&(price as u64 / 100).to_le_bytes()); // conf
+    data.extend_from_slice(&exponent.to_le_bytes());
+    data.extend_from_slice(&publish_time.to_le_bytes());
+    data.extend_from_slice(&publish_time.to_le_bytes()); // prev_publish_time
+    data.extend_from_slice(&price.to_le_bytes()); // ema_price
+    data.extend_from_slice(&(price as u64 / 100).to_le_bytes()); // ema_conf
+    data.extend_from_slice(&0u64.to_le_bytes()); // posted_slot
+    Account {
+        lamports: 1_000_000_000,
+        data,
+        owner: sentinel::oracle::PYTH_RECEIVER_ID,
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+/// A `mock-oracle` feed of `authority` publishing `price` with a 1%
+/// confidence interval at `publish_time`.
+pub fn mock_price_feed(
+    authority: &Pubkey,
+    price: i64,
+    exponent: i32,
+    publish_time: i64,
+) -> Account {
+    let feed = mock_oracle::PriceUpdateV2 {
+        write_authority: *authority,
+        verification_level: mock_oracle::VerificationLevel::Full,
+        price_message: mock_oracle::PriceFeedMessage {
+            price,
+            conf: price as u64 / 100,
+            exponent,
+            publish_time,
+            ..Default::default()
+        },
+        posted_slot: 0,
+    };
+    let mut data = Vec::new();
+    feed.try_serialize(&mut data).unwrap();
+    Account {
+        lamports: 1_000_000_000,
+        data,
+        owner: mock_oracle::ID,
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+pub fn set_mock_price_ix(
+    authority: &Pubkey,
+    feed: Pubkey,
+    price: i64,
+    publish_time: Option<i64>,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        mock_oracle::ID,
+        &mock_oracle::instruction::SetPrice {
+            price,
+            conf: price as u64 / 100,
+            publish_time,
+        }
+        .data(),
+        mock_oracle::accounts::SetPrice {
+            write_authority: *authority,
+            feed,
+        }
+        .to_account_metas(None),
+    )
+}
+
+/// Accounts of `sentinel::accounts::CheckHealthCrossChain` for `owner`'s
+/// position, with remote values from `posted_vaa`.
+pub fn check_health_cross_chain_ix(
+    owner: &Pubkey,
+    position_id: u32,
+    computation_offset: u64,
+    posted_vaa: Pubkey,
+) -> Instruction {
+    let accounts = sentinel::accounts::CheckHealthCrossChain {
+        payer: *owner,
+        sign_pda_account: sign_pda(),
+        mxe_account: pda::mxe_acc(&sentinel::ID),
+        mempool_account: pda::mempool_acc(CLUSTER_OFFSET),
+        executing_pool: pda::execpool_acc(CLUSTER_OFFSET),
+        computation_account: pda::computation_acc(CLUSTER_OFFSET, computation_offset),
+        comp_def_account: comp_def_address("check_position_health_cross_chain"),
+        cluster_account: pda::cluster_acc(CLUSTER_OFFSET),
+        pool_account: ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+        clock_account: ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+        system_program: System::id(),
+        arcium_program: ARCIUM_PROG_ID,
+        owner: *owner,
+        position_acc: position_pda(owner, position_id),
+        global_config: global_config_pda(),
+        posted_vaa,
+    };
+    let data = sentinel::instruction::CheckHealthCrossChain {
+        computation_offset,
+        _position_id: position_id,
+        encrypted_position: [[0; 32]; 3],
+        encryption_pubkey: [0; 32],
+        encryption_nonce: 0,
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Accounts of `sentinel::accounts::CheckHealthFromProtocol` for `owner`'s
+/// position, read from `obligation`.
+pub fn check_health_from_protocol_ix(
+    owner: &Pubkey,
+    position_id: u32,
+    computation_offset: u64,
+    obligation: Pubkey,
+    protocol_program: &Pubkey,
+) -> Instruction {
+    let accounts = sentinel::accounts::CheckHealthFromProtocol {
+        payer: *owner,
+        sign_pda_account: sign_pda(),
+        mxe_account: pda::mxe_acc(&sentinel::ID),
+        mempool_account: pda::mempool_acc(CLUSTER_OFFSET),
+        executing_pool: pda::execpool_acc(CLUSTER_OFFSET),
+        computation_account: pda::computation_acc(CLUSTER_OFFSET, computation_offset),
+        comp_def_account: comp_def_address("check_position_health_from_protocol"),
+        cluster_account: pda::cluster_acc(CLUSTER_OFFSET),
+        pool_account: ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+        clock_account: ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+        system_program: System::id(),
+        arcium_program: ARCIUM_PROG_ID,
+        owner: *owner,
+        position_acc: position_pda(owner, position_id),
+        global_config: global_config_pda(),
+        obligation,
+        protocol_adapter: protocol_adapter_pda(protocol_program),
+    };
+    let data = sentinel::instruction::CheckHealthFromProtocol {
+        computation_offset,
+        _position_id: position_id,
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// A Solend obligation of `owner` owned by `protocol_program`, with USD
+/// values as `Decimal`s.
+pub fn solend_obligation(
+    protocol_program: Pubkey,
+    owner: &Pubkey,
+    deposited_usd: u64,
+    borrowed_usd: u64,
+    unhealthy_borrow_usd: u64,
+    stale: bool,
+) -> Account {
+    let wad = |usd: u64| (usd as u128 * 10u128.pow(18)).to_le_bytes();
+    let mut data = vec![1]; // version
+    data.extend_from_slice(&0u64.to_le_bytes()); // last_update.slot
+    data.push(stale as u8);
+    data.extend_from_slice(&[0; 32]); // lending_market
+    data.extend_from_slice(owner.as_ref());
+    data.extend_from_slice(&wad(deposited_usd));
+    data.extend_from_slice(&wad(borrowed_usd));
+    data.extend_from_slice(&wad(borrowed_usd)); // allowed_borrow_value
+    data.extend_from_slice(&wad(unhealthy_borrow_usd));
+    data.resize(1300, 0);
+    Account {
+        lamports: 1_000_000_000,
+        data,
+        owner: protocol_program,
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+pub fn set_rule_set_ix(owner: &Pubkey, params: [u64; RULE_PARAMS_LEN]) -> Instruction {
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::SetRuleSet { params }.data(),
+        sentinel::accounts::SetRuleSet {
+            owner: *owner,
+            rule_set: rule_set_pda(owner),
+            system_program: System::id(),
+        }
+        .to_account_metas(None),
+    )
+}
+
+/// Accounts of `sentinel::accounts::CheckHealthWithRules` for `owner`'s
+/// position, scored by `owner`'s rule set.
+pub fn check_health_with_rules_ix(
+    owner: &Pubkey,
+    position_id: u32,
+    computation_offset: u64,
+) -> Instruction {
+    let accounts = sentinel::accounts::CheckHealthWithRules {
+        payer: *owner,
+        sign_pda_account: sign_pda(),
+        mxe_account: pda::mxe_acc(&sentinel::ID),
+        mempool_account: pda::mempool_acc(CLUSTER_OFFSET),
+        executing_pool: pda::execpool_acc(CLUSTER_OFFSET),
+        computation_account: pda::computation_acc(CLUSTER_OFFSET, computation_offset),
+        comp_def_account: comp_def_address("check_position_health_with_rules"),
+        cluster_account: pda::cluster_acc(CLUSTER_OFFSET),
+        pool_account: ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+        clock_account: ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+        system_program: System::id(),
+        arcium_program: ARCIUM_PROG_ID,
+        owner: *owner,
+        position_acc: position_pda(owner, position_id),
+        global_config: global_config_pda(),
+        rule_set: rule_set_pda(owner),
+    };
+    let data = sentinel::instruction::CheckHealthWithRules {
+        computation_offset,
+        _position_id: position_id,
+        encrypted_position: [[0; 32]; 3],
+        encryption_pubkey: [0; 32],
+        encryption_nonce: 0,
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// A `PostedVAAV1` account owned by the core bridge carrying a position
+/// summary from `emitter`.
+pub fn posted_summary_vaa(
+    emitter: sentinel::wormhole::Emitter,
+    owner: &Pubkey,
+    position_id: u32,
+    observed_at: i64,
+) -> Account {
+    let mut payload = vec![sentinel::wormhole::SUMMARY_PAYLOAD_ID];
+    payload.extend_from_slice(owner.as_ref());
+    payload.extend_from_slice(&position_id.to_be_bytes());
+    payload.extend_from_slice(&500_000u64.to_be_bytes()); // collateral_value
+    payload.extend_from_slice(&100_000u64.to_be_bytes()); // debt_value
+    payload.extend_from_slice(&(observed_at as u64).to_be_bytes());
+
+    let mut data = b"vaa".to_vec();
+    data.extend_from_slice(&[1, 1]); // vaa_version, consistency_level
+    data.extend_from_slice(&[0; 4 + 32 + 4 + 4 + 8]); // time, signatures, submission, nonce, sequence
+    data.extend_from_slice(&emitter.chain.to_le_bytes());
+    data.extend_from_slice(&emitter.address);
+    data.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    data.extend_from_slice(&payload);
+    Account {
+        lamports: 1_000_000_000,
+        data,
+        owner: sentinel::wormhole::CORE_BRIDGE_ID,
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+/// Accounts of `sentinel::accounts::RevealRisk`, with the position derived
+/// from `payer` and no auto action.
+pub fn reveal_risk_ix(payer: &Pubkey, position_id: u32, computation_offset: u64) -> Instruction {
+    let accounts = sentinel::accounts::RevealRisk {
+        payer: *payer,
+        sign_pda_account: sign_pda(),
+        mxe_account: pda::mxe_acc(&sentinel::ID),
+        mempool_account: pda::mempool_acc(CLUSTER_OFFSET),
+        executing_pool: pda::execpool_acc(CLUSTER_OFFSET),
+        computation_account: pda::computation_acc(CLUSTER_OFFSET, computation_offset),
+        comp_def_account: comp_def_address("reveal_risk"),
+        cluster_account: pda::cluster_acc(CLUSTER_OFFSET),
+        pool_account: ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+        clock_account: ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+        system_program: System::id(),
+        arcium_program: ARCIUM_PROG_ID,
+        position_acc: position_pda(payer, position_id),
+        auto_action: None,
+        global_config: global_config_pda(),
+    };
+    let data = sentinel::instruction::RevealRisk {
+        computation_offset,
+        position_id,
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Accounts of `sentinel::accounts::RevealRiskToOwner`, with the position
+/// derived from `payer`.
+pub fn reveal_risk_to_owner_ix(
+    payer: &Pubkey,
+    position_id: u32,
+    computation_offset: u64,
+) -> Instruction {
+    let accounts = sentinel::accounts::RevealRiskToOwner {
+        payer: *payer,
+        sign_pda_account: sign_pda(),
+        mxe_account: pda::mxe_acc(&sentinel::ID),
+        mempool_account: pda::mempool_acc(CLUSTER_OFFSET),
+        executing_pool: pda::execpool_acc(CLUSTER_OFFSET),
+        computation_account: pda::computation_acc(CLUSTER_OFFSET, computation_offset),
+        comp_def_account: comp_def_address("reveal_risk_to_owner"),
+        cluster_account: pda::cluster_acc(CLUSTER_OFFSET),
+        pool_account: ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+        clock_account: ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+        system_program: System::id(),
+        arcium_program: ARCIUM_PROG_ID,
+        position_acc: position_pda(payer, position_id),
+        global_config: global_config_pda(),
+        reveal_inbox: reveal_inbox_pda(&position_pda(payer, position_id)),
+        action_memo: None,
+    };
+    let data = sentinel::instruction::RevealRiskToOwner {
+        computation_offset,
+        position_id,
+        encryption_pubkey: [0; 32],
+        encryption_nonce: 0,
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+pub fn grant_viewer_ix(
+    owner: &Pubkey,
+    position_id: u32,
+    viewer: Pubkey,
+    encryption_key: [u8; 32],
+) -> Instruction {
+    let position_acc = position_pda(owner, position_id);
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::GrantViewer {
+            position_id,
+            viewer,
+            encryption_key,
+        }
+        .data(),
+        sentinel::accounts::GrantViewer {
+            owner: *owner,
+            position_acc,
+            viewer_grant: viewer_grant_pda(&position_acc, &viewer),
+            system_program: System::id(),
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn revoke_viewer_ix(owner: &Pubkey, position_id: u32, viewer: Pubkey) -> Instruction {
+    let position_acc = position_pda(owner, position_id);
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::RevokeViewer {
+            position_id,
+            viewer,
+        }
+        .data(),
+        sentinel::accounts::RevokeViewer {
+            owner: *owner,
+            position_acc,
+            viewer_grant: viewer_grant_pda(&position_acc, &viewer),
+        }
+        .to_account_metas(None),
+    )
+}
+
+/// Accounts of `sentinel::accounts::RevealRiskToViewer` for `viewer`'s grant
+/// on `owner`'s position, paid by `payer`.
+pub fn reveal_risk_to_viewer_ix(
+    payer: &Pubkey,
+    owner: &Pubkey,
+    position_id: u32,
+    viewer: &Pubkey,
+    computation_offset: u64,
+) -> Instruction {
+    let position_acc = position_pda(owner, position_id);
+    let accounts = sentinel::accounts::RevealRiskToViewer {
+        payer: *payer,
+        sign_pda_account: sign_pda(),
+        mxe_account: pda::mxe_acc(&sentinel::ID),
+        mempool_account: pda::mempool_acc(CLUSTER_OFFSET),
+        executing_pool: pda::execpool_acc(CLUSTER_OFFSET),
+        computation_account: pda::computation_acc(CLUSTER_OFFSET, computation_offset),
+        comp_def_account: comp_def_address("reveal_risk_to_viewer"),
+        cluster_account: pda::cluster_acc(CLUSTER_OFFSET),
+        pool_account: ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+        clock_account: ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+        system_program: System::id(),
+        arcium_program: ARCIUM_PROG_ID,
+        position_acc,
+        global_config: global_config_pda(),
+        viewer_grant: viewer_grant_pda(&position_acc, viewer),
+    };
+    let data = sentinel::instruction::RevealRiskToViewer {
+        computation_offset,
+        position_id,
+        encryption_nonce: 0,
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+pub fn set_sponsor_budget_ix(sponsor: &Pubkey, owner: Pubkey, reveals: u32) -> Instruction {
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::SetSponsorBudget { owner, reveals }.data(),
+        sentinel::accounts::SetSponsorBudget {
+            sponsor: *sponsor,
+            sponsor_budget: sponsor_budget_pda(sponsor, &owner),
+            system_program: System::id(),
+        }
+        .to_account_metas(None),
+    )
+}
+
+/// Accounts of `sentinel::accounts::SponsoredRevealRiskToOwner`, with
+/// `sponsor` paying for `owner`'s position.
+pub fn sponsored_reveal_risk_to_owner_ix(
+    sponsor: &Pubkey,
+    owner: &Pubkey,
+    position_id: u32,
+    computation_offset: u64,
+) -> Instruction {
+    let accounts = sentinel::accounts::SponsoredRevealRiskToOwner {
+        payer: *sponsor,
+        owner: *owner,
+        sign_pda_account: sign_pda(),
+        mxe_account: pda::mxe_acc(&sentinel::ID),
+        mempool_account: pda::mempool_acc(CLUSTER_OFFSET),
+        executing_pool: pda::execpool_acc(CLUSTER_OFFSET),
+        computation_account: pda::computation_acc(CLUSTER_OFFSET, computation_offset),
+        comp_def_account: comp_def_address("reveal_risk_to_owner"),
+        cluster_account: pda::cluster_acc(CLUSTER_OFFSET),
+        pool_account: ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+        clock_account: ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+        system_program: System::id(),
+        arcium_program: ARCIUM_PROG_ID,
+        position_acc: position_pda(owner, position_id),
+        global_config: global_config_pda(),
+        reveal_inbox: reveal_inbox_pda(&position_pda(owner, position_id)),
+        sponsor_budget: sponsor_budget_pda(sponsor, owner),
+        action_memo: None,
+    };
+    let data = sentinel::instruction::SponsoredRevealRiskToOwner {
+        computation_offset,
+        position_id,
+        encryption_pubkey: [0; 32],
+        encryption_nonce: 0,
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Accounts of `sentinel::accounts::SetAlertConfig` for `payer`'s position.
+pub fn set_alert_config_ix(
+    payer: &Pubkey,
+    position_id: u32,
+    computation_offset: u64,
+) -> Instruction {
+    let accounts = sentinel::accounts::SetAlertConfig {
+        payer: *payer,
+        sign_pda_account: sign_pda(),
+        mxe_account: pda::mxe_acc(&sentinel::ID),
+        mempool_account: pda::mempool_acc(CLUSTER_OFFSET),
+        executing_pool: pda::execpool_acc(CLUSTER_OFFSET),
+        computation_account: pda::computation_acc(CLUSTER_OFFSET, computation_offset),
+        comp_def_account: comp_def_address("set_alert_config"),
+        cluster_account: pda::cluster_acc(CLUSTER_OFFSET),
+        pool_account: ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+        clock_account: ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+        system_program: System::id(),
+        arcium_program: ARCIUM_PROG_ID,
+        position_acc: position_pda(payer, position_id),
+        global_config: global_config_pda(),
+    };
+    let data = sentinel::instruction::SetAlertConfig {
+        computation_offset,
+        position_id,
+        encrypted_config: [[0; 32]; 3],
+        encryption_pubkey: [0; 32],
+        encryption_nonce: 0,
+        nonce: 0,
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Accounts of `sentinel::accounts::ComputeHealthFactor` for `owner`'s
+/// position, signed by `payer`.
+pub fn compute_health_factor_ix(
+    payer: &Pubkey,
+    owner: &Pubkey,
+    position_id: u32,
+    computation_offset: u64,
+) -> Instruction {
+    let accounts = sentinel::accounts::ComputeHealthFactor {
+        payer: *payer,
+        sign_pda_account: sign_pda(),
+        mxe_account: pda::mxe_acc(&sentinel::ID),
+        mempool_account: pda::mempool_acc(CLUSTER_OFFSET),
+        executing_pool: pda::execpool_acc(CLUSTER_OFFSET),
+        computation_account: pda::computation_acc(CLUSTER_OFFSET, computation_offset),
+        comp_def_account: comp_def_address("compute_health_factor"),
+        cluster_account: pda::cluster_acc(CLUSTER_OFFSET),
+        pool_account: ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+        clock_account: ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+        system_program: System::id(),
+        arcium_program: ARCIUM_PROG_ID,
+        position_acc: position_pda(owner, position_id),
+        global_config: global_config_pda(),
+    };
+    let data = sentinel::instruction::ComputeHealthFactor {
+        computation_offset,
+        position_id,
+        encrypted_values: [[0; 32]; 3],
+        encryption_pubkey: [0; 32],
+        encryption_nonce: 0,
+        nonce: 0,
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+pub fn set_size_disclosure_ix(owner: &Pubkey, position_id: u32, allowed: bool) -> Instruction {
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::SetSizeDisclosure {
+            position_id,
+            allowed,
+        }
+        .data(),
+        sentinel::accounts::SetSizeDisclosure {
+            owner: *owner,
+            position_acc: position_pda(owner, position_id),
+        }
+        .to_account_metas(None),
+    )
+}
+
+/// Accounts of `sentinel::accounts::RevealSizeBucket` for `owner`'s position,
+/// signed by `payer`.
+pub fn reveal_size_bucket_ix(
+    payer: &Pubkey,
+    owner: &Pubkey,
+    position_id: u32,
+    computation_offset: u64,
+) -> Instruction {
+    let accounts = sentinel::accounts::RevealSizeBucket {
+        payer: *payer,
+        sign_pda_account: sign_pda(),
+        mxe_account: pda::mxe_acc(&sentinel::ID),
+        mempool_account: pda::mempool_acc(CLUSTER_OFFSET),
+        executing_pool: pda::execpool_acc(CLUSTER_OFFSET),
+        computation_account: pda::computation_acc(CLUSTER_OFFSET, computation_offset),
+        comp_def_account: comp_def_address("reveal_size_bucket"),
+        cluster_account: pda::cluster_acc(CLUSTER_OFFSET),
+        pool_account: ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+        clock_account: ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+        system_program: System::id(),
+        arcium_program: ARCIUM_PROG_ID,
+        position_acc: position_pda(owner, position_id),
+        global_config: global_config_pda(),
+    };
+    let data = sentinel::instruction::RevealSizeBucket {
+        computation_offset,
+        position_id,
+        encrypted_position: [[0; 32]; 3],
+        encryption_pubkey: [0; 32],
+        encryption_nonce: 0,
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Accounts of `sentinel::accounts::RotateEncryption` for `owner`'s position,
+/// signed by `payer`.
+pub fn rotate_encryption_ix(
+    payer: &Pubkey,
+    owner: &Pubkey,
+    position_id: u32,
+    computation_offset: u64,
+    nonce: u128,
+) -> Instruction {
+    let accounts = sentinel::accounts::RotateEncryption {
+        payer: *payer,
+        sign_pda_account: sign_pda(),
+        mxe_account: pda::mxe_acc(&sentinel::ID),
+        mempool_account: pda::mempool_acc(CLUSTER_OFFSET),
+        executing_pool: pda::execpool_acc(CLUSTER_OFFSET),
+        computation_account: pda::computation_acc(CLUSTER_OFFSET, computation_offset),
+        comp_def_account: comp_def_address("rotate_encryption"),
+        cluster_account: pda::cluster_acc(CLUSTER_OFFSET),
+        pool_account: ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+        clock_account: ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+        system_program: System::id(),
+        arcium_program: ARCIUM_PROG_ID,
+        position_acc: position_pda(owner, position_id),
+        global_config: global_config_pda(),
+    };
+    let data = sentinel::instruction::RotateEncryption {
+        computation_offset,
+        position_id,
+        nonce,
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Accounts of `sentinel::accounts::RevealHealthFactorToOwner` for `payer`'s
+/// position.
+pub fn reveal_health_factor_to_owner_ix(
+    payer: &Pubkey,
+    position_id: u32,
+    computation_offset: u64,
+) -> Instruction {
+    let accounts = sentinel::accounts::RevealHealthFactorToOwner {
+        payer: *payer,
+        sign_pda_account: sign_pda(),
+        mxe_account: pda::mxe_acc(&sentinel::ID),
+        mempool_account: pda::mempool_acc(CLUSTER_OFFSET),
+        executing_pool: pda::execpool_acc(CLUSTER_OFFSET),
+        computation_account: pda::computation_acc(CLUSTER_OFFSET, computation_offset),
+        comp_def_account: comp_def_address("reveal_health_factor_to_owner"),
+        cluster_account: pda::cluster_acc(CLUSTER_OFFSET),
+        pool_account: ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+        clock_account: ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+        system_program: System::id(),
+        arcium_program: ARCIUM_PROG_ID,
+        position_acc: position_pda(payer, position_id),
+        global_config: global_config_pda(),
+        reveal_inbox: reveal_inbox_pda(&position_pda(payer, position_id)),
+    };
+    let data = sentinel::instruction::RevealHealthFactorToOwner {
+        computation_offset,
+        position_id,
+        encryption_pubkey: [0; 32],
+        encryption_nonce: 0,
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Accounts of `sentinel::accounts::StressTestPosition` for `payer`'s
+/// position.
+pub fn stress_test_position_ix(
+    payer: &Pubkey,
+    position_id: u32,
+    computation_offset: u64,
+    shock_bps: u16,
+) -> Instruction {
+    let accounts = sentinel::accounts::StressTestPosition {
+        payer: *payer,
+        sign_pda_account: sign_pda(),
+        mxe_account: pda::mxe_acc(&sentinel::ID),
+        mempool_account: pda::mempool_acc(CLUSTER_OFFSET),
+        executing_pool: pda::execpool_acc(CLUSTER_OFFSET),
+        computation_account: pda::computation_acc(CLUSTER_OFFSET, computation_offset),
+        comp_def_account: comp_def_address("stress_test_position"),
+        cluster_account: pda::cluster_acc(CLUSTER_OFFSET),
+        pool_account: ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+        clock_account: ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+        system_program: System::id(),
+        arcium_program: ARCIUM_PROG_ID,
+        position_acc: position_pda(payer, position_id),
+        global_config: global_config_pda(),
+        reveal_inbox: reveal_inbox_pda(&position_pda(payer, position_id)),
+    };
+    let data = sentinel::instruction::StressTestPosition {
+        computation_offset,
+        position_id,
+        encrypted_values: [[0; 32]; 3],
+        encryption_pubkey: [0; 32],
+        encryption_nonce: 0,
+        shock_bps,
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Accounts of `sentinel::accounts::AttestHealth` for `payer`'s position.
+pub fn attest_health_ix(
+    payer: &Pubkey,
+    position_id: u32,
+    computation_offset: u64,
+    min_health_factor_bps: u32,
+) -> Instruction {
+    let accounts = sentinel::accounts::AttestHealth {
+        payer: *payer,
+        sign_pda_account: sign_pda(),
+        mxe_account: pda::mxe_acc(&sentinel::ID),
+        mempool_account: pda::mempool_acc(CLUSTER_OFFSET),
+        executing_pool: pda::execpool_acc(CLUSTER_OFFSET),
+        computation_account: pda::computation_acc(CLUSTER_OFFSET, computation_offset),
+        comp_def_account: comp_def_address("attest_health"),
+        cluster_account: pda::cluster_acc(CLUSTER_OFFSET),
+        pool_account: ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+        clock_account: ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+        system_program: System::id(),
+        arcium_program: ARCIUM_PROG_ID,
+        position_acc: position_pda(payer, position_id),
+        global_config: global_config_pda(),
+        attestation: attestation_pda(&position_pda(payer, position_id)),
+    };
+    let data = sentinel::instruction::AttestHealth {
+        computation_offset,
+        position_id,
+        min_health_factor_bps,
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Accounts of `sentinel::accounts::RevealHistorySummary` for `payer`'s position.
+pub fn reveal_history_summary_ix(
+    payer: &Pubkey,
+    position_id: u32,
+    computation_offset: u64,
+) -> Instruction {
+    let position_acc = position_pda(payer, position_id);
+    let accounts = sentinel::accounts::RevealHistorySummary {
+        payer: *payer,
+        sign_pda_account: sign_pda(),
+        mxe_account: pda::mxe_acc(&sentinel::ID),
+        mempool_account: pda::mempool_acc(CLUSTER_OFFSET),
+        executing_pool: pda::execpool_acc(CLUSTER_OFFSET),
+        computation_account: pda::computation_acc(CLUSTER_OFFSET, computation_offset),
+        comp_def_account: comp_def_address("reveal_history_summary"),
+        cluster_account: pda::cluster_acc(CLUSTER_OFFSET),
+        pool_account: ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+        clock_account: ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+        system_program: System::id(),
+        arcium_program: ARCIUM_PROG_ID,
+        position_acc,
+        global_config: global_config_pda(),
+        risk_history: risk_history_pda(&position_acc),
+    };
+    let data = sentinel::instruction::RevealHistorySummary {
+        computation_offset,
+        position_id,
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+pub fn init_risk_history_ix(owner: &Pubkey, position_id: u32) -> Instruction {
+    let position_acc = position_pda(owner, position_id);
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::InitRiskHistory { position_id }.data(),
+        sentinel::accounts::InitRiskHistory {
+            owner: *owner,
+            position_acc,
+            risk_history: risk_history_pda(&position_acc),
+            system_program: System::id(),
+        }
+        .to_account_metas(None),
+    )
+}
+
+/// `check_position_health_callback` for `owner`'s position, delivering a
+/// failed output for the computation at `computation_offset`.
+pub fn check_position_health_callback_ix(
+    owner: &Pubkey,
+    position_id: u32,
+    computation_offset: u64,
+) -> Instruction {
+    let accounts = sentinel::accounts::CheckPositionHealthCallback {
+        arcium_program: ARCIUM_PROG_ID,
+        comp_def_account: comp_def_address("check_position_health"),
+        mxe_account: pda::mxe_acc(&sentinel::ID),
+        computation_account: pda::computation_acc(CLUSTER_OFFSET, computation_offset),
+        cluster_account: pda::cluster_acc(CLUSTER_OFFSET),
+        instructions_sysvar: anchor_lang::solana_program::sysvar::instructions::ID,
+        position_acc: position_pda(owner, position_id),
+        risk_history: None,
+        check_bounty: None,
+        subscription: None,
+        keeper: None,
+        referrer: None,
+    };
+    let data = sentinel::instruction::CheckPositionHealthCallback {
+        output: SignedComputationOutputs::Failure,
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// `check_position_health_callback_ix` with the position's check bounty and
+/// `payee`.
+pub fn check_position_health_callback_with_bounty_ix(
+    owner: &Pubkey,
+    position_id: u32,
+    computation_offset: u64,
+    payee: &Pubkey,
+) -> Instruction {
+    let mut ix = check_position_health_callback_ix(owner, position_id, computation_offset);
+    let bounty = check_bounty_pda(&position_pda(owner, position_id));
+    let len = ix.accounts.len();
+    ix.accounts[len - 4] = AccountMeta::new(bounty, false);
+    ix.accounts[len - 2] = AccountMeta::new(*payee, false);
+    ix
+}
+
+/// `check_position_health_callback_ix` with the position's subscription and
+/// `keeper`, the payer of the check.
+pub fn check_position_health_callback_with_fee_ix(
+    owner: &Pubkey,
+    position_id: u32,
+    computation_offset: u64,
+    keeper: &Pubkey,
+) -> Instruction {
+    let mut ix = check_position_health_callback_ix(owner, position_id, computation_offset);
+    let subscription = subscription_pda(&position_pda(owner, position_id));
+    let len = ix.accounts.len();
+    ix.accounts[len - 3] = AccountMeta::new(subscription, false);
+    ix.accounts[len - 2] = AccountMeta::new(*keeper, false);
+    ix
+}
+
+pub fn compute_risk_score_callback_ix(
+    owner: &Pubkey,
+    position_id: u32,
+    computation_offset: u64,
+) -> Instruction {
+    let position_acc = position_pda(owner, position_id);
+    let accounts = sentinel::accounts::ComputeRiskScoreCallback {
+        arcium_program: ARCIUM_PROG_ID,
+        comp_def_account: comp_def_address("compute_risk_score"),
+        mxe_account: pda::mxe_acc(&sentinel::ID),
+        computation_account: pda::computation_acc(CLUSTER_OFFSET, computation_offset),
+        cluster_account: pda::cluster_acc(CLUSTER_OFFSET),
+        instructions_sysvar: anchor_lang::solana_program::sysvar::instructions::ID,
+        position_acc,
+        risk_score: risk_score_pda(&position_acc),
+        subscription: None,
+        keeper: None,
+        referrer: None,
+    };
+    let data = sentinel::instruction::ComputeRiskScoreCallback {
+        output: SignedComputationOutputs::Failure,
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// `compute_risk_score_callback_ix` with the position's subscription and
+/// `keeper`.
+pub fn compute_risk_score_callback_with_fee_ix(
+    owner: &Pubkey,
+    position_id: u32,
+    computation_offset: u64,
+    keeper: &Pubkey,
+) -> Instruction {
+    let mut ix = compute_risk_score_callback_ix(owner, position_id, computation_offset);
+    let subscription = subscription_pda(&position_pda(owner, position_id));
+    let len = ix.accounts.len();
+    ix.accounts[len - 3] = AccountMeta::new(subscription, false);
+    ix.accounts[len - 2] = AccountMeta::new(*keeper, false);
+    ix
+}
+
+/// Accounts of `sentinel::accounts::CheckHealthBatch`, followed by
+/// `positions` as writable remaining accounts, one zeroed input each.
+pub fn check_health_batch_ix(
+    payer: &Pubkey,
+    computation_offset: u64,
+    positions: &[Pubkey],
+) -> Instruction {
+    let mut accounts = sentinel::accounts::CheckHealthBatch {
+        payer: *payer,
+        sign_pda_account: sign_pda(),
+        mxe_account: pda::mxe_acc(&sentinel::ID),
+        mempool_account: pda::mempool_acc(CLUSTER_OFFSET),
+        executing_pool: pda::execpool_acc(CLUSTER_OFFSET),
+        computation_account: pda::computation_acc(CLUSTER_OFFSET, computation_offset),
+        comp_def_account: comp_def_address("check_position_health_batch"),
+        cluster_account: pda::cluster_acc(CLUSTER_OFFSET),
+        pool_account: ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+        clock_account: ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+        system_program: System::id(),
+        arcium_program: ARCIUM_PROG_ID,
+        global_config: global_config_pda(),
+    }
+    .to_account_metas(None);
+    accounts.extend(positions.iter().map(|p| AccountMeta::new(*p, false)));
+    let data = sentinel::instruction::CheckHealthBatch {
+        computation_offset,
+        encryption_pubkey: [0; 32],
+        positions: (0..positions.len() as u128)
+            .map(|nonce| sentinel::EncryptedPositionInput {
+                ciphertexts: [[0; 32]; 3],
+                nonce,
+            })
+            .collect(),
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts)
+}
+
+/// A failed `check_position_health_batch` callback for `positions`.
+pub fn check_position_health_batch_callback_ix(
+    positions: &[Pubkey],
+    computation_offset: u64,
+) -> Instruction {
+    let mut accounts = sentinel::accounts::CheckPositionHealthBatchCallback {
+        arcium_program: ARCIUM_PROG_ID,
+        comp_def_account: comp_def_address("check_position_health_batch"),
+        mxe_account: pda::mxe_acc(&sentinel::ID),
+        computation_account: pda::computation_acc(CLUSTER_OFFSET, computation_offset),
+        cluster_account: pda::cluster_acc(CLUSTER_OFFSET),
+        instructions_sysvar: anchor_lang::solana_program::sysvar::instructions::ID,
+    }
+    .to_account_metas(None);
+    accounts.extend(positions.iter().map(|p| AccountMeta::new(*p, false)));
+    let data = sentinel::instruction::CheckPositionHealthBatchCallback {
+        output: SignedComputationOutputs::Failure,
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts)
+}
+
+/// Accounts of `sentinel::accounts::AggregatePortfolioRisk`, followed by
+/// `positions` as remaining accounts.
+pub fn aggregate_portfolio_risk_ix(
+    payer: &Pubkey,
+    computation_offset: u64,
+    positions: &[Pubkey],
+) -> Instruction {
+    let mut accounts = sentinel::accounts::AggregatePortfolioRisk {
+        payer: *payer,
+        sign_pda_account: sign_pda(),
+        mxe_account: pda::mxe_acc(&sentinel::ID),
+        mempool_account: pda::mempool_acc(CLUSTER_OFFSET),
+        executing_pool: pda::execpool_acc(CLUSTER_OFFSET),
+        computation_account: pda::computation_acc(CLUSTER_OFFSET, computation_offset),
+        comp_def_account: comp_def_address("aggregate_portfolio_risk"),
+        cluster_account: pda::cluster_acc(CLUSTER_OFFSET),
+        pool_account: ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+        clock_account: ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+        system_program: System::id(),
+        arcium_program: ARCIUM_PROG_ID,
+        portfolio_acc: portfolio_pda(payer),
+        global_config: global_config_pda(),
+    }
+    .to_account_metas(None);
+    accounts.extend(
+        positions
+            .iter()
+            .map(|p| AccountMeta::new_readonly(*p, false)),
+    );
+    let data = sentinel::instruction::AggregatePortfolioRisk {
+        computation_offset,
+        nonce: 0,
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts)
+}
+
+pub fn configure_risk_heatmap_ix(
+    admin: &Pubkey,
+    classes: [Pubkey; sentinel::HEATMAP_CLASSES],
+) -> Instruction {
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::ConfigureRiskHeatmap { classes }.data(),
+        sentinel::accounts::ConfigureRiskHeatmap {
+            admin: *admin,
+            global_config: global_config_pda(),
+            risk_heatmap: risk_heatmap_pda(),
+            admin_audit_log: admin_audit_log_pda(),
+            system_program: System::id(),
+        }
+        .to_account_metas(None),
+    )
+}
+
+/// Accounts of `sentinel::accounts::AccumulateRiskHeatmap`, followed by
+/// `positions` as writable remaining accounts.
+pub fn accumulate_risk_heatmap_ix(
+    payer: &Pubkey,
+    computation_offset: u64,
+    positions: &[Pubkey],
+) -> Instruction {
+    let mut accounts = sentinel::accounts::AccumulateRiskHeatmap {
+        payer: *payer,
+        sign_pda_account: sign_pda(),
+        mxe_account: pda::mxe_acc(&sentinel::ID),
+        mempool_account: pda::mempool_acc(CLUSTER_OFFSET),
+        executing_pool: pda::execpool_acc(CLUSTER_OFFSET),
+        computation_account: pda::computation_acc(CLUSTER_OFFSET, computation_offset),
+        comp_def_account: comp_def_address("accumulate_risk_heatmap"),
+        cluster_account: pda::cluster_acc(CLUSTER_OFFSET),
+        pool_account: ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+        clock_account: ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+        system_program: System::id(),
+        arcium_program: ARCIUM_PROG_ID,
+        risk_heatmap: risk_heatmap_pda(),
+        global_config: global_config_pda(),
+    }
+    .to_account_metas(None);
+    accounts.extend(positions.iter().map(|p| AccountMeta::new(*p, false)));
+    let data = sentinel::instruction::AccumulateRiskHeatmap {
+        computation_offset,
+        nonce: 0,
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts)
+}
+
+pub fn publish_risk_heatmap_ix(payer: &Pubkey, computation_offset: u64) -> Instruction {
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::PublishRiskHeatmap { computation_offset }.data(),
+        sentinel::accounts::PublishRiskHeatmap {
+            payer: *payer,
+            sign_pda_account: sign_pda(),
+            mxe_account: pda::mxe_acc(&sentinel::ID),
+            mempool_account: pda::mempool_acc(CLUSTER_OFFSET),
+            executing_pool: pda::execpool_acc(CLUSTER_OFFSET),
+            computation_account: pda::computation_acc(CLUSTER_OFFSET, computation_offset),
+            comp_def_account: comp_def_address("reveal_risk_heatmap"),
+            cluster_account: pda::cluster_acc(CLUSTER_OFFSET),
+            pool_account: ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+            clock_account: ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+            system_program: System::id(),
+            arcium_program: ARCIUM_PROG_ID,
+            risk_heatmap: risk_heatmap_pda(),
+            global_config: global_config_pda(),
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn add_portfolio_position_ix(owner: &Pubkey, position_id: u32) -> Instruction {
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::AddPortfolioPosition {
+            _position_id: position_id,
+        }
+        .data(),
+        sentinel::accounts::AddPortfolioPosition {
+            owner: *owner,
+            portfolio_acc: portfolio_pda(owner),
+            position_acc: position_pda(owner, position_id),
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn remove_portfolio_position_ix(owner: &Pubkey, position: Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::RemovePortfolioPosition { position }.data(),
+        sentinel::accounts::RemovePortfolioPosition {
+            owner: *owner,
+            portfolio_acc: portfolio_pda(owner),
+        }
+        .to_account_metas(None),
+    )
+}
+
+/// `check_health_ix` with `payer`'s registry account.
+pub fn check_health_with_keeper_account_ix(
+    payer: &Pubkey,
+    owner: &Pubkey,
+    position_id: u32,
+    computation_offset: u64,
+) -> Instruction {
+    let mut ix = check_health_ix(payer, owner, position_id, computation_offset);
+    ix.accounts.last_mut().unwrap().pubkey = keeper_account_pda(payer);
+    ix
+}
+
+pub fn register_keeper_ix(keeper: &Pubkey, lamports: u64) -> Instruction {
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::RegisterKeeper { lamports }.data(),
+        sentinel::accounts::RegisterKeeper {
+            keeper: *keeper,
+            keeper_account: keeper_account_pda(keeper),
+            system_program: System::id(),
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn unbond_keeper_ix(keeper: &Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::UnbondKeeper {}.data(),
+        sentinel::accounts::UnbondKeeper {
+            keeper: *keeper,
+            keeper_account: keeper_account_pda(keeper),
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn withdraw_keeper_bond_ix(keeper: &Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::WithdrawKeeperBond {}.data(),
+        sentinel::accounts::WithdrawKeeperBond {
+            keeper: *keeper,
+            keeper_account: keeper_account_pda(keeper),
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn slash_keeper_ix(
+    admin: &Pubkey,
+    keeper: &Pubkey,
+    recipient: Pubkey,
+    lamports: u64,
+    evidence: [u8; 32],
+) -> Instruction {
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::SlashKeeper { lamports, evidence }.data(),
+        sentinel::accounts::SlashKeeper {
+            admin: *admin,
+            global_config: global_config_pda(),
+            keeper_account: keeper_account_pda(keeper),
+            recipient,
+            admin_audit_log: admin_audit_log_pda(),
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn receipt_registry_pda() -> Pubkey {
+    Pubkey::find_program_address(&[b"receipt_registry"], &sentinel::ID).0
+}
+
+pub fn receipt_mint_pda() -> Pubkey {
+    Pubkey::find_program_address(&[b"receipt_mint"], &sentinel::ID).0
+}
+
+pub fn receipt_account(owner: &Pubkey) -> Pubkey {
+    anchor_spl::associated_token::get_associated_token_address_with_program_id(
+        owner,
+        &receipt_mint_pda(),
+        &anchor_spl::token_2022::ID,
+    )
+}
+
+pub fn init_receipt_mint_ix(admin: &Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::InitReceiptMint {}.data(),
+        sentinel::accounts::InitReceiptMint {
+            admin: *admin,
+            global_config: global_config_pda(),
+            receipt_registry: receipt_registry_pda(),
+            receipt_mint: receipt_mint_pda(),
+            token_program: anchor_spl::token_2022::ID,
+            system_program: solana_sdk::system_program::ID,
+            admin_audit_log: admin_audit_log_pda(),
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn mint_monitoring_receipt_ix(owner: &Pubkey, position_id: u32) -> Instruction {
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::MintMonitoringReceipt { position_id }.data(),
+        sentinel::accounts::MintMonitoringReceipt {
+            owner: *owner,
+            position_acc: position_pda(owner, position_id),
+            receipt_registry: receipt_registry_pda(),
+            receipt_mint: receipt_mint_pda(),
+            receipt_account: receipt_account(owner),
+            token_program: anchor_spl::token_2022::ID,
+            associated_token_program: anchor_spl::associated_token::ID,
+            system_program: solana_sdk::system_program::ID,
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn burn_monitoring_receipt_ix(owner: &Pubkey, position_id: u32) -> Instruction {
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::BurnMonitoringReceipt { position_id }.data(),
+        sentinel::accounts::BurnMonitoringReceipt {
+            owner: *owner,
+            position_acc: position_pda(owner, position_id),
+            receipt_registry: receipt_registry_pda(),
+            receipt_mint: receipt_mint_pda(),
+            receipt_account: receipt_account(owner),
+            token_program: anchor_spl::token_2022::ID,
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn set_registered_keepers_only_ix(
+    owner: &Pubkey,
+    position_id: u32,
+    enabled: bool,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::SetRegisteredKeepersOnly {
+            position_id,
+            enabled,
+        }
+        .data(),
+        sentinel::accounts::SetRegisteredKeepersOnly {
+            owner: *owner,
+            position_acc: position_pda(owner, position_id),
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn delegate_keeper_ix(
+    owner: &Pubkey,
+    position_id: u32,
+    keeper: Option<Pubkey>,
+    expiry_slot: Option<u64>,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::DelegateKeeper {
+            position_id,
+            keeper,
+            expiry_slot,
+        }
+        .data(),
+        sentinel::accounts::DelegateKeeper {
+            owner: *owner,
+            position_acc: position_pda(owner, position_id),
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn renew_keeper_lease_ix(
+    keeper: &Pubkey,
+    shard: u16,
+    region: u8,
+    duration_secs: i64,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::RenewKeeperLease {
+            shard,
+            region,
+            duration_secs,
+        }
+        .data(),
+        sentinel::accounts::RenewKeeperLease {
+            keeper: *keeper,
+            lease: keeper_lease_pda(keeper, shard),
+            system_program: System::id(),
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn init_admin_audit_log_ix(payer: &Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::InitAdminAuditLog {}.data(),
+        sentinel::accounts::InitAdminAuditLog {
+            payer: *payer,
+            admin_audit_log: admin_audit_log_pda(),
+            system_program: System::id(),
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn set_monitoring_plan_ix(
+    authority: &Pubkey,
+    fee_per_check: u64,
+    referral_share_bps: u16,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::SetMonitoringPlan {
+            fee_per_check,
+            referral_share_bps,
+        }
+        .data(),
+        sentinel::accounts::SetMonitoringPlan {
+            authority: *authority,
+            monitoring_plan: monitoring_plan_pda(),
+            program_data: program_data_pda(),
+            admin_audit_log: admin_audit_log_pda(),
+            system_program: System::id(),
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn initialize_config_ix(authority: &Pubkey, admin: Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::InitializeConfig { admin }.data(),
+        sentinel::accounts::InitializeConfig {
+            authority: *authority,
+            global_config: global_config_pda(),
+            program_data: program_data_pda(),
+            system_program: System::id(),
+        }
+        .to_account_metas(None),
+    )
+}
+
+fn admin_config_accounts(admin: &Pubkey) -> Vec<AccountMeta> {
+    sentinel::accounts::AdminConfig {
+        admin: *admin,
+        global_config: global_config_pda(),
+        admin_audit_log: admin_audit_log_pda(),
+    }
+    .to_account_metas(None)
+}
+
+pub fn set_paused_ix(admin: &Pubkey, paused: bool) -> Instruction {
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::SetPaused { paused }.data(),
+        admin_config_accounts(admin),
+    )
+}
+
+pub fn set_risk_model_config_ix(
+    admin: &Pubkey,
+    weights: sentinel::RiskWeights,
+    severity_bands: [u8; 3],
+) -> Instruction {
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::SetRiskModelConfig {
+            weights,
+            severity_bands,
+        }
+        .data(),
+        sentinel::accounts::SetRiskModelConfig {
+            admin: *admin,
+            global_config: global_config_pda(),
+            risk_model_config: risk_model_config_pda(),
+            admin_audit_log: admin_audit_log_pda(),
+            system_program: System::id(),
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn set_circuit_version_ix(admin: &Pubkey, circuit_version: u32) -> Instruction {
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::SetCircuitVersion { circuit_version }.data(),
+        admin_config_accounts(admin),
+    )
+}
+
+pub fn propose_config_update_ix(
+    authority: &Pubkey,
+    max_staleness_secs: i64,
+    update_delay_secs: i64,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::ProposeConfigUpdate {
+            max_staleness_secs,
+            update_delay_secs,
+        }
+        .data(),
+        sentinel::accounts::ProposeConfigUpdate {
+            authority: *authority,
+            global_config: global_config_pda(),
+            program_data: program_data_pda(),
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn apply_config_update_ix(authority: &Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::ApplyConfigUpdate {}.data(),
+        sentinel::accounts::ApplyConfigUpdate {
+            authority: *authority,
+            global_config: global_config_pda(),
+            program_data: program_data_pda(),
+            admin_audit_log: admin_audit_log_pda(),
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn publish_risk_model_ix(authority: &Pubkey, risk_model: RiskModel) -> Instruction {
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::PublishRiskModel { risk_model }.data(),
+        sentinel::accounts::PublishRiskModel {
+            authority: *authority,
+            global_config: global_config_pda(),
+            program_data: program_data_pda(),
+            admin_audit_log: admin_audit_log_pda(),
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn accept_config_version_ix(
+    owner: &Pubkey,
+    registrant: &Pubkey,
+    position_id: u32,
+    config_version: u32,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::AcceptConfigVersion {
+            position_id,
+            config_version,
+        }
+        .data(),
+        sentinel::accounts::AcceptConfigVersion {
+            owner: *owner,
+            position_acc: position_pda(registrant, position_id),
+            global_config: global_config_pda(),
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn fund_subscription_ix(funder: &Pubkey, position: Pubkey, lamports: u64) -> Instruction {
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::FundSubscription { lamports }.data(),
+        sentinel::accounts::FundSubscription {
+            funder: *funder,
+            position_acc: position,
+            subscription: subscription_pda(&position),
+            system_program: System::id(),
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn fund_check_bounty_ix(
+    owner: &Pubkey,
+    position_id: u32,
+    check_bounty_lamports: u64,
+    lamports: u64,
+) -> Instruction {
+    let position_acc = position_pda(owner, position_id);
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::FundCheckBounty {
+            position_id,
+            check_bounty_lamports,
+            lamports,
+        }
+        .data(),
+        sentinel::accounts::FundCheckBounty {
+            owner: *owner,
+            position_acc,
+            check_bounty: check_bounty_pda(&position_acc),
+            system_program: System::id(),
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn close_check_bounty_ix(owner: &Pubkey, position_id: u32) -> Instruction {
+    let position_acc = position_pda(owner, position_id);
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::CloseCheckBounty { position_id }.data(),
+        sentinel::accounts::CloseCheckBounty {
+            owner: *owner,
+            position_acc,
+            check_bounty: check_bounty_pda(&position_acc),
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn grant_trial_credits_ix(
+    authority: &Pubkey,
+    position: Pubkey,
+    lamports: u64,
+    duration_secs: i64,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::GrantTrialCredits {
+            lamports,
+            duration_secs,
+        }
+        .data(),
+        sentinel::accounts::GrantTrialCredits {
+            authority: *authority,
+            program_data: program_data_pda(),
+            position_acc: position,
+            subscription: subscription_pda(&position),
+            system_program: System::id(),
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn sweep_expired_trial_credits_ix(
+    authority: &Pubkey,
+    owner: &Pubkey,
+    position: Pubkey,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::SweepExpiredTrialCredits {}.data(),
+        sentinel::accounts::SweepExpiredTrialCredits {
+            authority: *authority,
+            program_data: program_data_pda(),
+            subscription: subscription_pda(&position),
+            position_acc: position,
+            owner: *owner,
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn withdraw_subscription_ix(owner: &Pubkey, position_id: u32, lamports: u64) -> Instruction {
+    let position_acc = position_pda(owner, position_id);
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::WithdrawSubscription {
+            position_id,
+            lamports,
+        }
+        .data(),
+        sentinel::accounts::WithdrawSubscription {
+            owner: *owner,
+            position_acc,
+            subscription: subscription_pda(&position_acc),
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn propose_position_transfer_ix(
+    owner: &Pubkey,
+    registrant: &Pubkey,
+    position_id: u32,
+    new_owner: Option<Pubkey>,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::ProposePositionTransfer {
+            position_id,
+            new_owner,
+        }
+        .data(),
+        sentinel::accounts::ProposePositionTransfer {
+            owner: *owner,
+            position_acc: position_pda(registrant, position_id),
+        }
+        .to_account_metas(None),
+    )
+}
+
+/// With `attached`, the position's subscription and check bounty go along.
+pub fn accept_position_transfer_ix(
+    new_owner: &Pubkey,
+    registrant: &Pubkey,
+    position_id: u32,
+    attached: bool,
+) -> Instruction {
+    let position_acc = position_pda(registrant, position_id);
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::AcceptPositionTransfer { position_id }.data(),
+        sentinel::accounts::AcceptPositionTransfer {
+            new_owner: *new_owner,
+            position_acc,
+            subscription: attached.then(|| subscription_pda(&position_acc)),
+            check_bounty: attached.then(|| check_bounty_pda(&position_acc)),
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn set_auto_action_ix(
+    owner: &Pubkey,
+    position_id: u32,
+    program_id: Pubkey,
+    accounts: Vec<AutoActionAccount>,
+    data: Vec<u8>,
+) -> Instruction {
+    let position_acc = position_pda(owner, position_id);
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::SetAutoAction {
+            position_id,
+            program_id,
+            accounts,
+            data,
+        }
+        .data(),
+        sentinel::accounts::SetAutoAction {
+            owner: *owner,
+            position_acc,
+            auto_action: auto_action_pda(&position_acc),
+            system_program: System::id(),
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn clear_auto_action_ix(owner: &Pubkey, position_id: u32) -> Instruction {
+    let position_acc = position_pda(owner, position_id);
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::ClearAutoAction { position_id }.data(),
+        sentinel::accounts::ClearAutoAction {
+            owner: *owner,
+            position_acc,
+            auto_action: auto_action_pda(&position_acc),
+        }
+        .to_account_metas(None),
+    )
+}
+
+/// `execute_auto_action` for `action`, the auto action of `owner`'s position.
+pub fn execute_auto_action_ix(
+    owner: &Pubkey,
+    position_id: u32,
+    action: &AutoActionConfig,
+) -> Instruction {
+    let position_acc = position_pda(owner, position_id);
+    let mut accounts = sentinel::accounts::ExecuteAutoAction {
+        position_acc,
+        auto_action: auto_action_pda(&position_acc),
+    }
+    .to_account_metas(None);
+    accounts.push(AccountMeta::new_readonly(action.program_id, false));
+    accounts.extend(action.accounts.iter().map(|a| match a.is_writable {
+        true => AccountMeta::new(a.pubkey, false),
+        false => AccountMeta::new_readonly(a.pubkey, false),
+    }));
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::ExecuteAutoAction { position_id }.data(),
+        accounts,
+    )
+}
+
+/// `execute_stop_loss` for `stop_loss`, the stop-loss of `owner`'s position.
+pub fn execute_stop_loss_ix(
+    owner: &Pubkey,
+    position_id: u32,
+    stop_loss: &StopLossAccount,
+) -> Instruction {
+    let position_acc = position_pda(owner, position_id);
+    let mut accounts = sentinel::accounts::ExecuteStopLoss {
+        position_acc,
+        stop_loss: stop_loss_pda(&position_acc),
+    }
+    .to_account_metas(None);
+    accounts.push(AccountMeta::new_readonly(
+        stop_loss.executor.unwrap_or_default(),
+        false,
+    ));
+    accounts.extend(
+        stop_loss
+            .executor_accounts
+            .iter()
+            .map(|a| match a.is_writable {
+                true => AccountMeta::new(a.pubkey, false),
+                false => AccountMeta::new_readonly(a.pubkey, false),
+            }),
+    );
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::ExecuteStopLoss { position_id }.data(),
+        accounts,
+    )
+}
+
+pub fn set_stop_loss_ix(
+    owner: &Pubkey,
+    position_id: u32,
+    encrypted_config: [[u8; 32]; 3],
+    executor: Option<Pubkey>,
+    executor_accounts: Vec<AutoActionAccount>,
+) -> Instruction {
+    let position_acc = position_pda(owner, position_id);
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::SetStopLoss {
+            position_id,
+            encrypted_config,
+            encryption_pubkey: [7; 32],
+            encryption_nonce: 2,
+            executor,
+            executor_accounts,
+        }
+        .data(),
+        sentinel::accounts::SetStopLoss {
+            owner: *owner,
+            position_acc,
+            stop_loss: stop_loss_pda(&position_acc),
+            system_program: System::id(),
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn clear_stop_loss_ix(owner: &Pubkey, position_id: u32) -> Instruction {
+    let position_acc = position_pda(owner, position_id);
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::ClearStopLoss { position_id }.data(),
+        sentinel::accounts::ClearStopLoss {
+            owner: *owner,
+            position_acc,
+            stop_loss: stop_loss_pda(&position_acc),
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn set_action_memo_ix(owner: &Pubkey, position_id: u32, memo: EncryptedMemo) -> Instruction {
+    let position_acc = position_pda(owner, position_id);
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::SetActionMemo { position_id, memo }.data(),
+        sentinel::accounts::SetActionMemo {
+            owner: *owner,
+            position_acc,
+            action_memo: action_memo_pda(&position_acc),
+            system_program: System::id(),
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn clear_action_memo_ix(owner: &Pubkey, position_id: u32) -> Instruction {
+    let position_acc = position_pda(owner, position_id);
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::ClearActionMemo { position_id }.data(),
+        sentinel::accounts::ClearActionMemo {
+            owner: *owner,
+            position_acc,
+            action_memo: action_memo_pda(&position_acc),
+        }
+        .to_account_metas(None),
+    )
+}
+
+/// Accounts of `sentinel::accounts::EvaluateStopLoss` for `owner`'s position,
+/// paid by `payer`.
+pub fn evaluate_stop_loss_ix(
+    payer: &Pubkey,
+    owner: &Pubkey,
+    position_id: u32,
+    computation_offset: u64,
+) -> Instruction {
+    let position_acc = position_pda(owner, position_id);
+    let accounts = sentinel::accounts::EvaluateStopLoss {
+        payer: *payer,
+        sign_pda_account: sign_pda(),
+        mxe_account: pda::mxe_acc(&sentinel::ID),
+        mempool_account: pda::mempool_acc(CLUSTER_OFFSET),
+        executing_pool: pda::execpool_acc(CLUSTER_OFFSET),
+        computation_account: pda::computation_acc(CLUSTER_OFFSET, computation_offset),
+        comp_def_account: comp_def_address("evaluate_stop_loss"),
+        cluster_account: pda::cluster_acc(CLUSTER_OFFSET),
+        pool_account: ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+        clock_account: ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+        system_program: System::id(),
+        arcium_program: ARCIUM_PROG_ID,
+        position_acc,
+        global_config: global_config_pda(),
+        stop_loss: stop_loss_pda(&position_acc),
+    };
+    let data = sentinel::instruction::EvaluateStopLoss {
+        computation_offset,
+        position_id,
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// `record_value_protected` for `owner`'s position, signed by `signer`.
+pub fn record_value_protected_ix(
+    signer: &Pubkey,
+    owner: &Pubkey,
+    position_id: u32,
+    penalty_bps: u16,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::RecordValueProtected {
+            position_id,
+            trigger: sentinel::ProtectionTrigger::AutoAction,
+            size_bucket: sentinel::SizeBucket::From10kTo100k,
+            penalty_bps,
+        }
+        .data(),
+        sentinel::accounts::RecordValueProtected {
+            signer: *signer,
+            position_acc: position_pda(owner, position_id),
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn force_clear_pending_ix(owner: &Pubkey, position_id: u32) -> Instruction {
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::ForceClearPending { position_id }.data(),
+        sentinel::accounts::ForceClearPending {
+            owner: *owner,
+            position_acc: position_pda(owner, position_id),
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn cancel_computation_ix(
+    signer: &Pubkey,
+    owner: &Pubkey,
+    position_id: u32,
+    computation_offset: u64,
+    with_subscription: bool,
+) -> Instruction {
+    let position_acc = position_pda(owner, position_id);
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::CancelComputation { position_id }.data(),
+        sentinel::accounts::CancelComputation {
+            signer: *signer,
+            position_acc,
+            mxe_account: pda::mxe_acc(&sentinel::ID),
+            computation_account: pda::computation_acc(CLUSTER_OFFSET, computation_offset),
+            subscription: with_subscription.then(|| subscription_pda(&position_acc)),
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn deactivate_ix(owner: &Pubkey, position_id: u32) -> Instruction {
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::DeactivatePosition { position_id }.data(),
+        sentinel::accounts::DeactivatePosition {
+            owner: *owner,
+            position_acc: position_pda(owner, position_id),
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn set_label_ix(owner: &Pubkey, position_id: u32, label_hash: [u8; 32]) -> Instruction {
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::SetLabel {
+            position_id,
+            label_hash,
+        }
+        .data(),
+        sentinel::accounts::SetLabel {
+            owner: *owner,
+            position_acc: position_pda(owner, position_id),
+        }
+        .to_account_metas(None),
+    )
+}
+
+/// Closes `owner`'s position, refunding its subscription too with
+/// `with_subscription` and removing it from `owner`'s index with
+/// `with_index`.
+pub fn close_ix(
+    owner: &Pubkey,
+    position_id: u32,
+    with_subscription: bool,
+    index_page: Option<u32>,
+) -> Instruction {
+    let position_acc = position_pda(owner, position_id);
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::ClosePosition { position_id }.data(),
+        sentinel::accounts::ClosePosition {
+            owner: *owner,
+            position_acc,
+            subscription: with_subscription.then(|| subscription_pda(&position_acc)),
+            check_bounty: None,
+            owner_index: index_page.map(|page| owner_index_pda(owner, page)),
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn archive_position_ix(
+    owner: &Pubkey,
+    position_id: u32,
+    with_history: bool,
+    with_subscription: bool,
+) -> Instruction {
+    let position_acc = position_pda(owner, position_id);
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::ArchivePosition { position_id }.data(),
+        sentinel::accounts::ArchivePosition {
+            owner: *owner,
+            position_acc,
+            risk_history: with_history.then(|| risk_history_pda(&position_acc)),
+            subscription: with_subscription.then(|| subscription_pda(&position_acc)),
+            check_bounty: None,
+            archive: archive_pda(owner, position_id),
+            owner_index: None,
+            system_program: solana_sdk::system_program::ID,
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn migrate_position_ix(payer: &Pubkey, position: Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        sentinel::ID,
+        &sentinel::instruction::MigratePosition {}.data(),
+        sentinel::accounts::MigratePosition {
+            payer: *payer,
+            position_acc: position,
+            system_program: solana_sdk::system_program::ID,
+        }
+        .to_account_metas(None),
+    )
+}
+
+/// `position` as written before layouts were versioned: version 0, and the
+/// account short of `version` and the 64 bytes `_reserved` started with.
+pub fn legacy_position(position: &PositionAccount) -> Account {
+    let legacy = PositionAccount {
+        version: 0,
+        ..position.clone()
+    };
+    let mut data = Vec::new();
+    legacy.try_serialize(&mut data).unwrap();
+    data.resize(8 + PositionAccount::INIT_SPACE - 1 - 64, 0);
+    Account {
+        lamports: 1_000_000_000,
+        data,
+        owner: sentinel::ID,
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+/// `position` as first deployed: a discriminator followed by the baseline
+/// fields, with its first two risk state ciphertexts.
+pub fn baseline_position(position: &PositionAccount) -> Account {
+    let baseline = sentinel::BaselinePosition {
+        bump: position.bump,
+        risk_state: [position.risk_state[0], position.risk_state[1]],
+        position_id: position.position_id,
+        owner: position.owner,
+        nonce: position.nonce,
+        last_check: position.last_check,
+        is_active: position.is_active,
+    };
+    let mut data = PositionAccount::DISCRIMINATOR.to_vec();
+    baseline.serialize(&mut data).unwrap();
+    assert_eq!(data.len(), PositionAccount::BASELINE_LEN);
+    Account {
+        lamports: 1_000_000_000,
+        data,
+        owner: sentinel::ID,
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+/// `inbox` as written before reveals could attach an action memo: the
+/// account short of `memo`.
+pub fn legacy_reveal_inbox(inbox: &RevealInbox) -> Account {
+    let mut data = Vec::new();
+    inbox.try_serialize(&mut data).unwrap();
+    data.resize(
+        8 + RevealInbox::INIT_SPACE - 1 - EncryptedMemo::INIT_SPACE,
+        0,
+    );
+    Account {
+        lamports: 1_000_000_000,
+        data,
+        owner: sentinel::ID,
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+/// Accounts of `sentinel::accounts::RegisterPosition` for `payer`.
+pub fn register_position_ix(
+    payer: &Pubkey,
+    position_id: u32,
+    computation_offset: u64,
+    referrer: Option<Pubkey>,
+    referral_stats: Option<Pubkey>,
+) -> Instruction {
+    register_position_on_page_ix(
+        payer,
+        position_id,
+        computation_offset,
+        referrer,
+        referral_stats,
+        0,
+    )
+}
+
+/// `register_position_ix` listing the position in page `index_page` of the
+/// payer's index, with the page before it.
+pub fn register_position_on_page_ix(
+    payer: &Pubkey,
+    position_id: u32,
+    computation_offset: u64,
+    referrer: Option<Pubkey>,
+    referral_stats: Option<Pubkey>,
+    index_page: u32,
+) -> Instruction {
+    let accounts = sentinel::accounts::RegisterPosition {
+        payer: *payer,
+        sign_pda_account: sign_pda(),
+        mxe_account: pda::mxe_acc(&sentinel::ID),
+        mempool_account: pda::mempool_acc(CLUSTER_OFFSET),
+        executing_pool: pda::execpool_acc(CLUSTER_OFFSET),
+        computation_account: pda::computation_acc(CLUSTER_OFFSET, computation_offset),
+        comp_def_account: comp_def_address("init_risk_state"),
+        cluster_account: pda::cluster_acc(CLUSTER_OFFSET),
+        pool_account: ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+        clock_account: ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+        system_program: System::id(),
+        arcium_program: ARCIUM_PROG_ID,
+        position_acc: position_pda(payer, position_id),
+        global_config: global_config_pda(),
+        referral_stats,
+        owner_index: owner_index_pda(payer, index_page),
+        previous_owner_index: index_page
+            .checked_sub(1)
+            .map(|page| owner_index_pda(payer, page)),
+    };
+    let data = sentinel::instruction::RegisterPosition {
+        computation_offset,
+        position_id,
+        nonce: 7,
+        referrer,
+        metadata: sentinel::PositionMetadata::UNSPECIFIED,
+        label_hash: [0; 32],
+        index_page,
+    };
+    Instruction::new_with_bytes(sentinel::ID, &data.data(), accounts.to_account_metas(None))
+}
+
+pub fn referral_stats_pda(referrer: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"referral", referrer.as_ref()], &sentinel::ID).0
+}
+
+pub fn sign_pda() -> Pubkey {
+    Pubkey::find_program_address(&[SIGN_PDA_SEED], &sentinel::ID).0
+}
+
+fn sign_pda_bump() -> u8 {
+    Pubkey::find_program_address(&[SIGN_PDA_SEED], &sentinel::ID).1
+}
+
+/// An Arcium-owned account holding `discriminator` and zeroed data.
+pub fn arcium_stub_account(discriminator: &[u8]) -> Account {
+    arcium_account(discriminator.to_vec())
+}
+
+/// An Arcium-owned account holding `data` padded with zeroes.
+fn arcium_account(mut data: Vec<u8>) -> Account {
+    data.resize(data.len() + STUB_LEN, 0);
+    Account {
+        lamports: 1_000_000_000,
+        data,
+        owner: ARCIUM_PROG_ID,
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+/// Asserts that the transaction failed with the Anchor error `code`.
+pub fn assert_error(result: std::result::Result<(), BanksClientError>, code: impl Into<u32>) {
+    let code = code.into();
+    match result {
+        Err(BanksClientError::TransactionError(TransactionError::InstructionError(
+            _,
+            InstructionError::Custom(actual),
+        ))) => assert_eq!(actual, code, "expected error {code}, got {actual}"),
+        other => panic!("expected error {code}, got {other:?}"),
+    }
+}
+
+// Anchor's `entry` ties the account slice and the `AccountInfo`s to one
+// lifetime, which `processor!` can't express; leaking the slice is fine in tests.
+fn process_instruction<'a, 'b, 'c>(
+    program_id: &'a Pubkey,
+    accounts: &'b [AccountInfo<'c>],
+    data: &[u8],
+) -> ProgramResult {
+    let accounts: &'c [AccountInfo<'c>] = Box::leak(accounts.to_vec().into_boxed_slice());
+    sentinel::entry(program_id, accounts, data)
+}
+
+fn mock_oracle_instruction<'a, 'b, 'c>(
+    program_id: &'a Pubkey,
+    accounts: &'b [AccountInfo<'c>],
+    data: &[u8],
+) -> ProgramResult {
+    let accounts: &'c [AccountInfo<'c>] = Box::leak(accounts.to_vec().into_boxed_slice());
+    mock_oracle::entry(program_id, accounts, data)
+}
+
+fn arcium_stub(_program_id: &Pubkey, _accounts: &[AccountInfo], _data: &[u8]) -> ProgramResult {
+    Ok(())
+}
@@ -0,0 +1,43 @@
+mod common;
+
+use common::{fixtures::*, *};
+use solana_sdk::signature::Signer;
+
+#[test]
+fn keypairs_are_deterministic() {
+    assert_eq!(keypair("owner").pubkey(), keypair("owner").pubkey());
+    assert_ne!(keypair("owner").pubkey(), keypair("keeper").pubkey());
+}
+
+#[test]
+fn fixture_round_trips_through_json() {
+    let fixture = Fixture::new().positions(keypair("owner").pubkey(), 10);
+    let path = std::env::temp_dir().join(format!("sentinel-fixture-{}.json", std::process::id()));
+
+    fixture.save(&path).unwrap();
+    let loaded = Fixture::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(loaded, fixture);
+}
+
+#[tokio::test]
+async fn portfolio_fixture_loads_and_snapshots() {
+    let owner = keypair("owner");
+    let fixture = Fixture::new().positions(owner.pubkey(), 10);
+    let addresses: Vec<_> = (1..=10)
+        .map(|id| position_pda(&owner.pubkey(), id))
+        .collect();
+    let mut h = Harness::with_accounts(|pt| fixture.apply(pt)).await;
+
+    h.send(deactivate_ix(&owner.pubkey(), 4), &[&owner])
+        .await
+        .unwrap();
+    let snapshot = h.snapshot(&addresses).await;
+
+    for (id, address) in (1..=10).zip(&addresses) {
+        let changed = snapshot.get(address).unwrap() != fixture.get(address).unwrap();
+        assert_eq!(changed, id == 4, "position {id}");
+    }
+    assert!(!h.position(&owner.pubkey(), 4).await.unwrap().is_active);
+}
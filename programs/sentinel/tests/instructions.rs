@@ -0,0 +1,5692 @@
+mod common;
+
+use anchor_lang::{
+    prelude::{Pubkey, Rent},
+    AccountDeserialize, Discriminator, Space,
+};
+use arcium_client::{idl::arcium::accounts::SmallExecPool, pda};
+use common::*;
+use sentinel::ErrorCode;
+use solana_sdk::hash::hash;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::signature::{Keypair, Signer};
+
+// ─── Register Position ───
+
+#[tokio::test]
+#[ignore = "needs the SBF build, see tests/common"]
+async fn register_position_creates_position_pda() {
+    let mut h = Harness::new().await;
+    let owner = h.payer();
+
+    h.send(
+        register_position_ix(&owner.pubkey(), 3, 11, None, None),
+        &[],
+    )
+    .await
+    .unwrap();
+
+    let position = h.position(&owner.pubkey(), 3).await.unwrap();
+    assert_eq!(position.owner, owner.pubkey());
+    assert_eq!(position.position_id, 3);
+    assert_eq!(position.nonce, 7);
+    assert!(position.is_active);
+    assert_eq!(position.pending_computation, Some(11));
+    assert_eq!(position.metadata, sentinel::PositionMetadata::UNSPECIFIED);
+    assert!(position.indexed);
+    assert_eq!(
+        h.owner_index(&owner.pubkey(), 0)
+            .await
+            .unwrap()
+            .position_ids,
+        vec![3]
+    );
+}
+
+#[tokio::test]
+#[ignore = "needs the SBF build, see tests/common"]
+async fn register_position_lists_position_in_next_index_page() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    let full: Vec<u32> = (100..100 + sentinel::MAX_INDEXED_POSITIONS as u32).collect();
+    h.set_owner_index(owner, 0, &full);
+
+    let result = h
+        .send(register_position_ix(&owner, 3, 11, None, None), &[])
+        .await;
+    assert_error(result, ErrorCode::OwnerIndexFull);
+
+    h.send(
+        register_position_on_page_ix(&owner, 3, 12, None, None, 1),
+        &[],
+    )
+    .await
+    .unwrap();
+
+    let page = h.owner_index(&owner, 1).await.unwrap();
+    assert_eq!((page.page, page.position_ids), (1, vec![3]));
+    assert_eq!(h.position(&owner, 3).await.unwrap().index_page, 1);
+}
+
+#[tokio::test]
+#[ignore = "needs the SBF build, see tests/common"]
+async fn register_position_rejects_index_page_gap() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_owner_index(owner, 0, &[1]);
+
+    let mut ix = register_position_on_page_ix(&owner, 3, 11, None, None, 2);
+    // Page 1 doesn't exist, so it goes as absent
+    let previous = ix.accounts.len() - 1;
+    ix.accounts[previous].pubkey = sentinel::ID;
+    let result = h.send(ix, &[]).await;
+    assert_error(result, ErrorCode::OwnerIndexPageGap);
+}
+
+#[tokio::test]
+#[ignore = "needs the SBF build, see tests/common"]
+async fn register_position_rejects_self_referral() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    let stats = referral_stats_pda(&owner);
+
+    let result = h
+        .send(
+            register_position_ix(&owner, 1, 1, Some(owner), Some(stats)),
+            &[],
+        )
+        .await;
+    assert_error(result, ErrorCode::SelfReferral);
+}
+
+#[tokio::test]
+#[ignore = "needs the SBF build, see tests/common"]
+async fn register_position_requires_referral_stats_with_referrer() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+
+    let result = h
+        .send(
+            register_position_ix(&owner, 1, 1, Some(Pubkey::new_unique()), None),
+            &[],
+        )
+        .await;
+    assert_error(result, ErrorCode::ReferralStatsMismatch);
+}
+
+#[tokio::test]
+#[ignore = "needs the SBF build, see tests/common"]
+async fn register_position_counts_referrals() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    let referrer = Pubkey::new_unique();
+    let stats = referral_stats_pda(&referrer);
+
+    for id in 1..=2 {
+        h.send(
+            register_position_ix(&owner, id, id as u64, Some(referrer), Some(stats)),
+            &[],
+        )
+        .await
+        .unwrap();
+    }
+
+    let account = h.account(stats).await.unwrap();
+    let stats: sentinel::ReferralStats =
+        anchor_lang::AccountDeserialize::try_deserialize(&mut account.data.as_slice()).unwrap();
+    assert_eq!(stats.referrer, referrer);
+    assert_eq!(stats.referred_positions, 2);
+}
+
+// ─── Check Health ───
+
+#[tokio::test]
+#[ignore = "needs the SBF build, see tests/common"]
+async fn check_health_marks_computation_pending() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+
+    h.send(check_health_ix(&owner, &owner, 1, 42), &[])
+        .await
+        .unwrap();
+
+    let position = h.position(&owner, 1).await.unwrap();
+    assert_eq!(position.pending_computation, Some(42));
+}
+
+#[tokio::test]
+async fn check_health_rejects_inactive_position() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&sentinel::PositionAccount {
+        is_active: false,
+        ..position(owner, 1)
+    });
+
+    let result = h.send(check_health_ix(&owner, &owner, 1, 1), &[]).await;
+    assert_error(result, ErrorCode::PositionInactive);
+}
+
+#[tokio::test]
+async fn check_health_rejects_non_mempool_account() {
+    // An executing pool placed at the mempool address
+    let mut h = Harness::with_accounts(|pt| {
+        pt.add_account(
+            pda::mempool_acc(CLUSTER_OFFSET),
+            arcium_stub_account(SmallExecPool::DISCRIMINATOR),
+        );
+    })
+    .await;
+    let owner = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+
+    let result = h.send(check_health_ix(&owner, &owner, 1, 1), &[]).await;
+    assert_error(result, ErrorCode::InvalidMempoolAccount);
+}
+
+#[tokio::test]
+async fn check_health_rejects_foreign_position() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    let other = Pubkey::new_unique();
+    h.set_position(&position(other, 1));
+
+    // Owner account and position PDA disagree
+    let mut ix = check_health_ix(&owner, &owner, 1, 1);
+    let position_meta = ix
+        .accounts
+        .iter_mut()
+        .find(|meta| meta.pubkey == position_pda(&owner, 1))
+        .unwrap();
+    position_meta.pubkey = position_pda(&other, 1);
+
+    let result = h.send(ix, &[]).await;
+    assert_error(result, ErrorCode::PositionOwnerMismatch);
+}
+
+#[tokio::test]
+async fn check_health_rejects_pending_computation() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&sentinel::PositionAccount {
+        pending_computation: Some(3),
+        ..position(owner, 1)
+    });
+
+    let result = h.send(check_health_ix(&owner, &owner, 1, 4), &[]).await;
+    assert_error(result, ErrorCode::ComputationPending);
+}
+
+#[tokio::test]
+async fn check_health_rejects_check_within_min_interval() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    let now = h.now().await;
+    h.set_position(&sentinel::PositionAccount {
+        last_check: now - 30,
+        min_check_interval_secs: 60,
+        ..position(owner, 1)
+    });
+
+    let result = h.send(check_health_ix(&owner, &owner, 1, 1), &[]).await;
+    assert_error(result, ErrorCode::CheckTooSoon);
+}
+
+#[tokio::test]
+async fn check_health_interval_counts_computations_without_callback() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    let now = h.now().await;
+    // The last check queued 30s ago failed; it still counts
+    h.set_position(&sentinel::PositionAccount {
+        pending_since: now - 30,
+        min_check_interval_secs: 60,
+        ..position(owner, 1)
+    });
+
+    let result = h.send(check_health_ix(&owner, &owner, 1, 1), &[]).await;
+    assert_error(result, ErrorCode::CheckTooSoon);
+}
+
+#[tokio::test]
+async fn check_health_rejects_degraded_cluster() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+    h.degrade_cluster();
+
+    let result = h.send(check_health_ix(&owner, &owner, 1, 1), &[]).await;
+    assert_error(result, ErrorCode::ClusterDegraded);
+}
+
+// ─── Batch Health Check ───
+
+#[tokio::test]
+#[ignore = "needs the SBF build, see tests/common"]
+async fn check_health_batch_marks_every_position_pending() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+    h.set_position(&position(owner, 2));
+
+    let positions = [position_pda(&owner, 1), position_pda(&owner, 2)];
+    h.send(check_health_batch_ix(&owner, 5, &positions), &[])
+        .await
+        .unwrap();
+
+    for id in 1..=2 {
+        assert_eq!(
+            h.position(&owner, id).await.unwrap().pending_computation,
+            Some(5)
+        );
+    }
+}
+
+#[tokio::test]
+async fn check_health_batch_rejects_empty_or_oversized_batch() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    let positions: Vec<_> = (1..=5).map(|id| position_pda(&owner, id)).collect();
+    for id in 1..=5 {
+        h.set_position(&position(owner, id));
+    }
+
+    let empty = h.send(check_health_batch_ix(&owner, 1, &[]), &[]).await;
+    assert_error(empty, ErrorCode::InvalidBatch);
+
+    let oversized = h
+        .send(check_health_batch_ix(&owner, 2, &positions), &[])
+        .await;
+    assert_error(oversized, ErrorCode::InvalidBatch);
+}
+
+#[tokio::test]
+async fn check_health_batch_rejects_duplicate_position() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+
+    let positions = [position_pda(&owner, 1), position_pda(&owner, 1)];
+    let result = h
+        .send(check_health_batch_ix(&owner, 1, &positions), &[])
+        .await;
+    assert_error(result, ErrorCode::InvalidBatch);
+}
+
+#[tokio::test]
+async fn check_health_batch_rejects_other_owners_position() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    let other = Pubkey::new_unique();
+    h.set_position(&position(owner, 1));
+    h.set_position(&position(other, 1));
+
+    let positions = [position_pda(&owner, 1), position_pda(&other, 1)];
+    let result = h
+        .send(check_health_batch_ix(&owner, 1, &positions), &[])
+        .await;
+    assert_error(result, ErrorCode::InvalidAuthority);
+}
+
+#[tokio::test]
+async fn check_health_batch_rejects_pending_computation() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+    h.set_position(&sentinel::PositionAccount {
+        pending_computation: Some(3),
+        ..position(owner, 2)
+    });
+
+    let positions = [position_pda(&owner, 1), position_pda(&owner, 2)];
+    let result = h
+        .send(check_health_batch_ix(&owner, 4, &positions), &[])
+        .await;
+    assert_error(result, ErrorCode::ComputationPending);
+    assert_eq!(
+        h.position(&owner, 1).await.unwrap().pending_computation,
+        None
+    );
+}
+
+// ─── Alert Config ───
+
+#[tokio::test]
+async fn set_alert_config_rejects_other_payer() {
+    let mut h = Harness::new().await;
+    let owner = Pubkey::new_unique();
+    let intruder = Keypair::new();
+    h.set_position(&position(owner, 1));
+
+    let mut ix = set_alert_config_ix(&intruder.pubkey(), 1, 1);
+    let n = ix.accounts.len();
+    ix.accounts[n - 2].pubkey = position_pda(&owner, 1);
+
+    let result = h.send(ix, &[&intruder]).await;
+    assert_error(result, ErrorCode::InvalidAuthority);
+}
+
+#[tokio::test]
+async fn set_alert_config_rejects_pending_computation() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&sentinel::PositionAccount {
+        pending_computation: Some(3),
+        ..position(owner, 1)
+    });
+
+    let result = h.send(set_alert_config_ix(&owner, 1, 4), &[]).await;
+    assert_error(result, ErrorCode::ComputationPending);
+}
+
+#[tokio::test]
+async fn set_alert_config_rejects_degraded_cluster() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+    h.degrade_cluster();
+
+    let result = h.send(set_alert_config_ix(&owner, 1, 1), &[]).await;
+    assert_error(result, ErrorCode::ClusterDegraded);
+}
+
+#[tokio::test]
+#[ignore = "needs the SBF build, see tests/common"]
+async fn set_alert_config_marks_computation_pending() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+
+    h.send(set_alert_config_ix(&owner, 1, 8), &[])
+        .await
+        .unwrap();
+
+    let position = h.position(&owner, 1).await.unwrap();
+    assert_eq!(position.pending_computation, Some(8));
+}
+
+#[tokio::test]
+async fn configure_alerts_sets_min_action_severity() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+
+    h.send(configure_alerts_ix(&owner, 1, 3), &[])
+        .await
+        .unwrap();
+
+    assert_eq!(h.position(&owner, 1).await.unwrap().min_action_severity, 3);
+}
+
+#[tokio::test]
+async fn configure_alerts_rejects_out_of_range_severity() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+
+    for severity in [0, 4] {
+        let result = h.send(configure_alerts_ix(&owner, 1, severity), &[]).await;
+        assert_error(result, ErrorCode::InvalidActionSeverity);
+    }
+}
+
+#[tokio::test]
+async fn configure_alerts_rejects_other_signer() {
+    let mut h = Harness::new().await;
+    let owner = Pubkey::new_unique();
+    let intruder = Keypair::new();
+    h.set_position(&position(owner, 1));
+
+    let mut ix = configure_alerts_ix(&intruder.pubkey(), 1, 2);
+    ix.accounts[1].pubkey = position_pda(&owner, 1);
+
+    let result = h.send(ix, &[&intruder]).await;
+    assert_error(result, anchor_lang::error::ErrorCode::ConstraintHasOne);
+}
+
+#[tokio::test]
+async fn set_max_staleness_overrides_default() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+
+    h.send(set_max_staleness_ix(&owner, 1, Some(3_600)), &[])
+        .await
+        .unwrap();
+    assert_eq!(
+        h.position(&owner, 1).await.unwrap().max_staleness_secs,
+        Some(3_600)
+    );
+
+    h.send(set_max_staleness_ix(&owner, 1, None), &[])
+        .await
+        .unwrap();
+    assert_eq!(
+        h.position(&owner, 1).await.unwrap().max_staleness_secs,
+        None
+    );
+}
+
+#[tokio::test]
+async fn set_min_check_interval_updates_position() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+
+    h.send(set_min_check_interval_ix(&owner, 1, 300), &[])
+        .await
+        .unwrap();
+    assert_eq!(
+        h.position(&owner, 1).await.unwrap().min_check_interval_secs,
+        300
+    );
+}
+
+#[tokio::test]
+async fn set_max_staleness_rejects_non_positive_limit() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+
+    for secs in [0, -1] {
+        let result = h
+            .send(set_max_staleness_ix(&owner, 1, Some(secs)), &[])
+            .await;
+        assert_error(result, ErrorCode::InvalidStaleness);
+    }
+}
+
+// ─── Oracle Health Check ───
+
+/// A position priced from a Pyth price update published `age` seconds ago.
+/// Returns the harness, owner and price update address.
+async fn oracle_harness(age: i64, verified: bool) -> (Harness, Pubkey, Pubkey) {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    let feed = Pubkey::new_unique();
+    h.set_position(&sentinel::PositionAccount {
+        price_feed: Some(feed),
+        ..position(owner, 1)
+    });
+    let now = h.now().await;
+    let mut update = pyth_price_update(150_000_000, -8, now - age);
+    if !verified {
+        update.data[40] = 0; // VerificationLevel::Partial
+    }
+    h.ctx.set_account(&feed, &update.into());
+    (h, owner, feed)
+}
+
+#[tokio::test]
+async fn set_price_feed_updates_position() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    let feed = Pubkey::new_unique();
+    h.set_position(&position(owner, 1));
+
+    h.send(set_price_feed_ix(&owner, 1, Some(feed)), &[])
+        .await
+        .unwrap();
+
+    assert_eq!(h.position(&owner, 1).await.unwrap().price_feed, Some(feed));
+}
+
+#[tokio::test]
+async fn check_health_with_oracle_requires_price_feed() {
+    let (mut h, owner, feed) = oracle_harness(0, true).await;
+    h.set_position(&position(owner, 1));
+
+    let result = h
+        .send(check_health_with_oracle_ix(&owner, 1, 1, feed), &[])
+        .await;
+    assert_error(result, ErrorCode::PriceFeedNotSet);
+}
+
+#[tokio::test]
+async fn check_health_with_oracle_rejects_other_price_account() {
+    let (mut h, owner, _) = oracle_harness(0, true).await;
+    let other = Pubkey::new_unique();
+
+    let result = h
+        .send(check_health_with_oracle_ix(&owner, 1, 1, other), &[])
+        .await;
+    assert_error(result, ErrorCode::PriceFeedMismatch);
+}
+
+#[tokio::test]
+async fn check_health_with_oracle_rejects_unverified_price() {
+    let (mut h, owner, feed) = oracle_harness(0, false).await;
+
+    let result = h
+        .send(check_health_with_oracle_ix(&owner, 1, 1, feed), &[])
+        .await;
+    assert_error(result, ErrorCode::InvalidPriceAccount);
+}
+
+#[tokio::test]
+async fn check_health_with_oracle_rejects_stale_price() {
+    let age = sentinel::oracle::MAX_PRICE_AGE_SECS + 1;
+    let (mut h, owner, feed) = oracle_harness(age, true).await;
+
+    let result = h
+        .send(check_health_with_oracle_ix(&owner, 1, 1, feed), &[])
+        .await;
+    assert_error(result, ErrorCode::StalePrice);
+}
+
+#[tokio::test]
+async fn check_health_with_oracle_rejects_price_account_of_other_program() {
+    let (mut h, owner, feed) = oracle_harness(0, true).await;
+    let now = h.now().await;
+    let mut update = pyth_price_update(150_000_000, -8, now);
+    update.owner = Pubkey::new_unique();
+    h.ctx.set_account(&feed, &update.into());
+
+    let result = h
+        .send(check_health_with_oracle_ix(&owner, 1, 1, feed), &[])
+        .await;
+    assert_error(result, ErrorCode::InvalidPriceAccount);
+}
+
+#[tokio::test]
+async fn check_health_with_oracle_rejects_delegated_keeper() {
+    let (mut h, _, feed) = oracle_harness(0, true).await;
+    let owner = Pubkey::new_unique();
+    let keeper = h.payer().pubkey();
+    h.set_position(&sentinel::PositionAccount {
+        price_feed: Some(feed),
+        keeper: Some(keeper),
+        ..position(owner, 1)
+    });
+
+    let mut ix = check_health_with_oracle_ix(&owner, 1, 1, feed);
+    ix.accounts[0].pubkey = keeper;
+    let result = h.send(ix, &[]).await;
+    assert_error(result, ErrorCode::UnauthorizedKeeper);
+}
+
+#[tokio::test]
+async fn check_health_with_oracle_reads_mock_oracle_feed() {
+    assert_eq!(mock_oracle::ID, sentinel::oracle::MOCK_ORACLE_ID);
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    let feed = Pubkey::new_unique();
+    h.set_position(&sentinel::PositionAccount {
+        price_feed: Some(feed),
+        ..position(owner, 1)
+    });
+    let now = h.now().await;
+    h.ctx
+        .set_account(&feed, &mock_price_feed(&owner, 150_000_000, -8, now).into());
+
+    let stale = now - sentinel::oracle::MAX_PRICE_AGE_SECS - 1;
+    h.send(
+        set_mock_price_ix(&owner, feed, 150_000_000, Some(stale)),
+        &[],
+    )
+    .await
+    .unwrap();
+    let result = h
+        .send(check_health_with_oracle_ix(&owner, 1, 1, feed), &[])
+        .await;
+    assert_error(result, ErrorCode::StalePrice);
+
+    h.send(set_mock_price_ix(&owner, feed, 0, None), &[])
+        .await
+        .unwrap();
+    let result = h
+        .send(check_health_with_oracle_ix(&owner, 1, 2, feed), &[])
+        .await;
+    assert_error(result, ErrorCode::InvalidOraclePrice);
+}
+
+#[tokio::test]
+#[ignore = "needs the SBF build, see tests/common"]
+async fn check_health_with_oracle_prices_from_mock_oracle_feed() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    let feed = Pubkey::new_unique();
+    h.set_position(&sentinel::PositionAccount {
+        price_feed: Some(feed),
+        ..position(owner, 1)
+    });
+    let now = h.now().await;
+    h.ctx
+        .set_account(&feed, &mock_price_feed(&owner, 1, -8, now).into());
+    h.send(set_mock_price_ix(&owner, feed, 150_000_000, None), &[])
+        .await
+        .unwrap();
+
+    h.send(check_health_with_oracle_ix(&owner, 1, 8, feed), &[])
+        .await
+        .unwrap();
+
+    assert_eq!(
+        h.position(&owner, 1).await.unwrap().pending_computation,
+        Some(8)
+    );
+}
+
+fn priced_check_health_ix(owner: &Pubkey, price_update: Pubkey) -> Instruction {
+    check_health_with_market_ix(owner, owner, 1, 1, Some(price_update), None)
+}
+
+#[tokio::test]
+async fn check_health_price_update_requires_price_feed() {
+    let (mut h, owner, feed) = oracle_harness(0, true).await;
+    h.set_position(&position(owner, 1));
+
+    let result = h.send(priced_check_health_ix(&owner, feed), &[]).await;
+    assert_error(result, ErrorCode::PriceFeedNotSet);
+}
+
+#[tokio::test]
+async fn check_health_rejects_other_price_update() {
+    let (mut h, owner, _) = oracle_harness(0, true).await;
+
+    let result = h
+        .send(priced_check_health_ix(&owner, Pubkey::new_unique()), &[])
+        .await;
+    assert_error(result, ErrorCode::PriceFeedMismatch);
+}
+
+#[tokio::test]
+async fn check_health_rejects_stale_price_update() {
+    let age = sentinel::oracle::MAX_PRICE_AGE_SECS + 1;
+    let (mut h, owner, feed) = oracle_harness(age, true).await;
+
+    let result = h.send(priced_check_health_ix(&owner, feed), &[]).await;
+    assert_error(result, ErrorCode::StalePrice);
+}
+
+#[tokio::test]
+#[ignore = "needs the SBF build, see tests/common"]
+async fn check_health_with_oracle_marks_computation_pending() {
+    let (mut h, owner, feed) = oracle_harness(0, true).await;
+
+    h.send(check_health_with_oracle_ix(&owner, 1, 8, feed), &[])
+        .await
+        .unwrap();
+
+    assert_eq!(
+        h.position(&owner, 1).await.unwrap().pending_computation,
+        Some(8)
+    );
+}
+
+// ─── Feed Migration ───
+
+#[tokio::test]
+#[ignore = "needs the SBF build, see tests/common"]
+async fn flag_price_feed_flags_silent_feed() {
+    let age = sentinel::oracle::FEED_DEPRECATED_AFTER_SECS + 1;
+    let (mut h, owner, feed) = oracle_harness(age, true).await;
+
+    h.send(flag_price_feed_ix(&owner, feed), &[]).await.unwrap();
+
+    let migration = h.feed_migration(&feed).await.unwrap();
+    assert_eq!((migration.flagged_by, migration.replacement), (owner, None));
+}
+
+#[tokio::test]
+#[ignore = "needs the SBF build, see tests/common"]
+async fn flag_price_feed_rejects_publishing_feed() {
+    let (mut h, owner, feed) = oracle_harness(0, true).await;
+
+    let result = h.send(flag_price_feed_ix(&owner, feed), &[]).await;
+    assert_error(result, ErrorCode::PriceFeedNotDeprecated);
+}
+
+/// `oracle_harness`'s stale feed flagged, the payer as upgrade authority,
+/// and a publishing replacement. Returns the harness, owner, old and new
+/// feed.
+async fn migration_harness() -> (Harness, Pubkey, Pubkey, Pubkey) {
+    let age = sentinel::oracle::FEED_DEPRECATED_AFTER_SECS + 1;
+    let (mut h, owner, feed) = oracle_harness(age, true).await;
+    h.set_upgrade_authority(&owner);
+    h.set_admin_audit_log(0);
+    h.set_program_account(&feed_migration_pda(&feed), &feed_migration(feed, None));
+    let replacement = Pubkey::new_unique();
+    let now = h.now().await;
+    h.ctx.set_account(
+        &replacement,
+        &pyth_price_update(150_000_000, -8, now).into(),
+    );
+    (h, owner, feed, replacement)
+}
+
+#[tokio::test]
+async fn migrate_price_feed_names_replacement() {
+    let (mut h, authority, feed, replacement) = migration_harness().await;
+
+    h.send(migrate_price_feed_ix(&authority, feed, replacement), &[])
+        .await
+        .unwrap();
+
+    let migration = h.feed_migration(&feed).await.unwrap();
+    assert_eq!(migration.replacement, Some(replacement));
+    let entry = h.admin_audit_log().await.unwrap().entries[0];
+    assert_eq!(
+        entry.field,
+        sentinel::AdminField::PriceFeedReplacement as u8
+    );
+}
+
+#[tokio::test]
+async fn migrate_price_feed_rejects_silent_replacement() {
+    let (mut h, authority, feed, _) = migration_harness().await;
+    let silent = Pubkey::new_unique();
+    let age = sentinel::oracle::MAX_PRICE_AGE_SECS + 1;
+    let now = h.now().await;
+    h.ctx.set_account(
+        &silent,
+        &pyth_price_update(150_000_000, -8, now - age).into(),
+    );
+
+    let result = h
+        .send(migrate_price_feed_ix(&authority, feed, silent), &[])
+        .await;
+    assert_error(result, ErrorCode::StalePrice);
+    let result = h
+        .send(migrate_price_feed_ix(&authority, feed, feed), &[])
+        .await;
+    assert_error(result, ErrorCode::InvalidPriceFeedReplacement);
+}
+
+#[tokio::test]
+async fn repoint_price_feeds_moves_positions_to_replacement() {
+    let (mut h, owner, feed, replacement) = migration_harness().await;
+    h.set_program_account(
+        &feed_migration_pda(&feed),
+        &feed_migration(feed, Some(replacement)),
+    );
+    h.set_position(&sentinel::PositionAccount {
+        price_feed: Some(feed),
+        ..position(owner, 2)
+    });
+
+    let positions = [position_pda(&owner, 1), position_pda(&owner, 2)];
+    h.send(repoint_price_feeds_ix(&feed, &positions), &[])
+        .await
+        .unwrap();
+
+    assert_eq!(
+        h.position(&owner, 1).await.unwrap().price_feed,
+        Some(replacement)
+    );
+    assert_eq!(
+        h.position(&owner, 2).await.unwrap().price_feed,
+        Some(replacement)
+    );
+    assert_eq!(h.feed_migration(&feed).await.unwrap().repointed, 2);
+}
+
+#[tokio::test]
+async fn repoint_price_feeds_rejects_unmigrated_feed_and_other_positions() {
+    let (mut h, owner, feed, replacement) = migration_harness().await;
+    h.set_position(&position(owner, 2));
+
+    let result = h
+        .send(
+            repoint_price_feeds_ix(&feed, &[position_pda(&owner, 1)]),
+            &[],
+        )
+        .await;
+    assert_error(result, ErrorCode::PriceFeedNotMigrated);
+
+    h.set_program_account(
+        &feed_migration_pda(&feed),
+        &feed_migration(feed, Some(replacement)),
+    );
+    let result = h
+        .send(
+            repoint_price_feeds_ix(&feed, &[position_pda(&owner, 2)]),
+            &[],
+        )
+        .await;
+    assert_error(result, ErrorCode::PriceFeedMismatch);
+}
+
+// ─── Perp Health Check ───
+
+#[tokio::test]
+async fn check_perp_health_requires_price_feed() {
+    let (mut h, owner, feed) = oracle_harness(0, true).await;
+    h.set_position(&position(owner, 1));
+
+    let result = h
+        .send(check_perp_health_ix(&owner, &owner, 1, 1, feed), &[])
+        .await;
+    assert_error(result, ErrorCode::PriceFeedNotSet);
+}
+
+#[tokio::test]
+async fn check_perp_health_rejects_stale_price() {
+    let age = sentinel::oracle::MAX_PRICE_AGE_SECS + 1;
+    let (mut h, owner, feed) = oracle_harness(age, true).await;
+
+    let result = h
+        .send(check_perp_health_ix(&owner, &owner, 1, 1, feed), &[])
+        .await;
+    assert_error(result, ErrorCode::StalePrice);
+}
+
+#[tokio::test]
+async fn check_perp_health_rejects_stranger() {
+    let (mut h, _, feed) = oracle_harness(0, true).await;
+    let owner = Pubkey::new_unique();
+    let stranger = h.payer().pubkey();
+    h.set_position(&sentinel::PositionAccount {
+        price_feed: Some(feed),
+        ..position(owner, 1)
+    });
+
+    let result = h
+        .send(check_perp_health_ix(&stranger, &owner, 1, 1, feed), &[])
+        .await;
+    assert_error(result, ErrorCode::UnauthorizedKeeper);
+}
+
+#[tokio::test]
+async fn check_perp_health_rejects_delegated_keeper() {
+    let (mut h, _, feed) = oracle_harness(0, true).await;
+    let owner = Pubkey::new_unique();
+    let keeper = h.payer().pubkey();
+    h.set_position(&sentinel::PositionAccount {
+        price_feed: Some(feed),
+        keeper: Some(keeper),
+        ..position(owner, 1)
+    });
+
+    let result = h
+        .send(check_perp_health_ix(&keeper, &owner, 1, 1, feed), &[])
+        .await;
+    assert_error(result, ErrorCode::UnauthorizedKeeper);
+}
+
+#[tokio::test]
+#[ignore = "needs the SBF build, see tests/common"]
+async fn check_perp_health_marks_computation_pending() {
+    let (mut h, owner, feed) = oracle_harness(0, true).await;
+
+    h.send(check_perp_health_ix(&owner, &owner, 1, 4, feed), &[])
+        .await
+        .unwrap();
+
+    assert_eq!(
+        h.position(&owner, 1).await.unwrap().pending_computation,
+        Some(4)
+    );
+}
+
+// ─── Composite Risk Score ───
+
+const WEIGHTS: sentinel::RiskWeights = sentinel::RiskWeights {
+    liquidation_proximity: 40,
+    volatility: 20,
+    concentration: 20,
+    tvl_trend: 20,
+};
+
+async fn risk_model_harness(admin: Option<Pubkey>) -> (Harness, Pubkey) {
+    let mut h = Harness::new().await;
+    let signer = h.payer().pubkey();
+    h.set_admin_audit_log(0);
+    h.set_global_config(&sentinel::GlobalConfig {
+        admin: admin.unwrap_or(signer),
+        ..global_config(60)
+    });
+    h.set_risk_model_config(WEIGHTS, [25, 50, 75]);
+    (h, signer)
+}
+
+#[tokio::test]
+async fn set_risk_model_config_records_change() {
+    let (mut h, admin) = risk_model_harness(None).await;
+    let weights = sentinel::RiskWeights {
+        liquidation_proximity: 70,
+        volatility: 10,
+        concentration: 10,
+        tvl_trend: 10,
+    };
+
+    h.send(set_risk_model_config_ix(&admin, weights, [10, 40, 90]), &[])
+        .await
+        .unwrap();
+
+    let config = h.risk_model_config().await.unwrap();
+    assert_eq!(config.weights, weights);
+    assert_eq!(config.severity_bands, [10, 40, 90]);
+    assert!(config.updated_at > 0);
+    let log = h.admin_audit_log().await.unwrap();
+    assert_eq!(
+        log.entries[0].field,
+        sentinel::AdminField::RiskModelConfig as u8
+    );
+}
+
+#[tokio::test]
+async fn set_risk_model_config_rejects_invalid_model() {
+    let (mut h, admin) = risk_model_harness(None).await;
+    let weights = sentinel::RiskWeights {
+        tvl_trend: 21,
+        ..WEIGHTS
+    };
+
+    let result = h
+        .send(set_risk_model_config_ix(&admin, weights, [25, 50, 75]), &[])
+        .await;
+    assert_error(result, ErrorCode::InvalidRiskWeights);
+
+    for bands in [[0, 50, 75], [25, 25, 75], [25, 50, 101]] {
+        let result = h
+            .send(set_risk_model_config_ix(&admin, WEIGHTS, bands), &[])
+            .await;
+        assert_error(result, ErrorCode::InvalidSeverityBands);
+    }
+}
+
+#[tokio::test]
+async fn set_risk_model_config_rejects_non_admin() {
+    let (mut h, signer) = risk_model_harness(Some(Pubkey::new_unique())).await;
+
+    let result = h
+        .send(
+            set_risk_model_config_ix(&signer, WEIGHTS, [25, 50, 75]),
+            &[],
+        )
+        .await;
+    assert_error(result, ErrorCode::InvalidAuthority);
+}
+
+#[tokio::test]
+async fn compute_risk_score_rejects_stranger() {
+    let (mut h, stranger) = risk_model_harness(None).await;
+    let owner = Pubkey::new_unique();
+    h.set_position(&position(owner, 1));
+    h.set_risk_score(&position_pda(&owner, 1));
+    h.set_monitoring_plan(1_000);
+    h.set_subscription(&subscription(owner, 1), 5_000);
+
+    let result = h
+        .send(compute_risk_score_ix(&stranger, &owner, 1, 1, None), &[])
+        .await;
+    assert_error(result, ErrorCode::UnauthorizedKeeper);
+}
+
+#[tokio::test]
+async fn compute_risk_score_rejects_other_price_feed() {
+    let (mut h, owner, feed) = oracle_harness(0, true).await;
+    h.set_risk_model_config(WEIGHTS, [25, 50, 75]);
+    h.set_risk_score(&position_pda(&owner, 1));
+    let other = Pubkey::new_unique();
+    let update = h.account(feed).await.unwrap();
+    h.ctx.set_account(&other, &update.into());
+
+    let result = h
+        .send(
+            compute_risk_score_ix(&owner, &owner, 1, 1, Some(other)),
+            &[],
+        )
+        .await;
+    assert_error(result, ErrorCode::PriceFeedMismatch);
+}
+
+#[tokio::test]
+#[ignore = "needs the SBF build, see tests/common"]
+async fn compute_risk_score_marks_computation_pending() {
+    let (mut h, owner, feed) = oracle_harness(0, true).await;
+    h.set_risk_model_config(WEIGHTS, [25, 50, 75]);
+
+    h.send(compute_risk_score_ix(&owner, &owner, 1, 4, Some(feed)), &[])
+        .await
+        .unwrap();
+
+    let position_acc = position_pda(&owner, 1);
+    assert_eq!(
+        h.position(&owner, 1).await.unwrap().pending_computation,
+        Some(4)
+    );
+    assert!(h.account(risk_score_pda(&position_acc)).await.is_some());
+}
+
+#[tokio::test]
+async fn compute_risk_score_requires_subscription_for_keeper() {
+    let (mut h, owner) = risk_model_harness(None).await;
+    let keeper = Keypair::new();
+    h.set_position(&sentinel::PositionAccount {
+        keeper: Some(keeper.pubkey()),
+        ..position(owner, 1)
+    });
+    h.set_risk_score(&position_pda(&owner, 1));
+    h.set_monitoring_plan(1_000);
+
+    let mut ix = compute_risk_score_ix(&keeper.pubkey(), &owner, 1, 1, None);
+    let subscription = subscription_pda(&position_pda(&owner, 1));
+    ix.accounts
+        .iter_mut()
+        .find(|meta| meta.pubkey == subscription)
+        .unwrap()
+        .pubkey = sentinel::ID;
+    let result = h.send(ix, &[&keeper]).await;
+    assert_error(result, ErrorCode::SubscriptionRequired);
+}
+
+#[tokio::test]
+async fn compute_risk_score_rejects_underfunded_subscription() {
+    let (mut h, owner) = risk_model_harness(None).await;
+    let keeper = Keypair::new();
+    h.set_position(&sentinel::PositionAccount {
+        keeper: Some(keeper.pubkey()),
+        ..position(owner, 1)
+    });
+    h.set_risk_score(&position_pda(&owner, 1));
+    h.set_monitoring_plan(1_000);
+    h.set_subscription(&subscription(owner, 1), 999);
+
+    let ix = compute_risk_score_ix(&keeper.pubkey(), &owner, 1, 1, None);
+    let result = h.send(ix, &[&keeper]).await;
+    assert_error(result, ErrorCode::InsufficientSubscription);
+}
+
+#[tokio::test]
+#[ignore = "needs the SBF build, see tests/common"]
+async fn compute_risk_score_holds_keeper_fee() {
+    let (mut h, owner) = risk_model_harness(None).await;
+    let keeper = h.funded_keypair().await;
+    h.set_position(&sentinel::PositionAccount {
+        keeper: Some(keeper.pubkey()),
+        ..position(owner, 1)
+    });
+    h.set_monitoring_plan(1_000);
+    h.set_subscription(&subscription(owner, 1), 5_000);
+
+    let ix = compute_risk_score_ix(&keeper.pubkey(), &owner, 1, 1, None);
+    h.send(ix, &[&keeper]).await.unwrap();
+
+    let subscription = h.subscription(&position_pda(&owner, 1)).await.unwrap();
+    assert_eq!(subscription.pending_fee, 1_000);
+    assert_eq!(subscription.pending_keeper, Some(keeper.pubkey()));
+}
+
+#[tokio::test]
+async fn aborted_score_releases_keeper_fee() {
+    let (mut h, owner) = risk_model_harness(None).await;
+    let keeper = Pubkey::new_unique();
+    h.set_position(&sentinel::PositionAccount {
+        pending_computation: Some(1),
+        ..position(owner, 1)
+    });
+    h.set_risk_score(&position_pda(&owner, 1));
+    h.set_subscription(
+        &sentinel::Subscription {
+            pending_fee: 1_000,
+            pending_keeper: Some(keeper),
+            ..subscription(owner, 1)
+        },
+        5_000,
+    );
+    h.add_computation(1);
+    let address = subscription_pda(&position_pda(&owner, 1));
+    let escrowed = h.account(address).await.unwrap().lamports;
+
+    let computation = pda::computation_acc(CLUSTER_OFFSET, 1);
+    let ix = compute_risk_score_callback_with_fee_ix(&owner, 1, 1, &keeper);
+    h.send_callback(computation, ix).await.unwrap();
+
+    let subscription = h.subscription(&position_pda(&owner, 1)).await.unwrap();
+    assert_eq!(
+        (subscription.pending_fee, subscription.pending_keeper),
+        (0, None)
+    );
+    assert_eq!(subscription.fees_paid, 0);
+    assert_eq!(h.account(address).await.unwrap().lamports, escrowed);
+    assert!(h.account(keeper).await.is_none());
+}
+
+// ─── Cross-Chain Health Check ───
+
+const EMITTER: sentinel::wormhole::Emitter = sentinel::wormhole::Emitter {
+    chain: 2,
+    address: [9; 32],
+};
+
+/// A position trusting `EMITTER`, and a VAA at the returned address.
+async fn cross_chain_harness(
+    vaa: impl FnOnce(&Pubkey, i64) -> solana_sdk::account::Account,
+) -> (Harness, Pubkey, Pubkey) {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&sentinel::PositionAccount {
+        cross_chain_emitter: Some(EMITTER),
+        ..position(owner, 1)
+    });
+    let address = Pubkey::new_unique();
+    let account = vaa(&owner, h.now().await);
+    h.ctx.set_account(&address, &account.into());
+    (h, owner, address)
+}
+
+#[tokio::test]
+async fn check_health_cross_chain_rejects_vaa_not_owned_by_core_bridge() {
+    let (mut h, owner, vaa) = cross_chain_harness(|owner, now| {
+        let mut account = posted_summary_vaa(EMITTER, owner, 1, now);
+        account.owner = Pubkey::new_unique();
+        account
+    })
+    .await;
+
+    let result = h
+        .send(check_health_cross_chain_ix(&owner, 1, 1, vaa), &[])
+        .await;
+    assert_error(result, ErrorCode::InvalidPostedVaa);
+}
+
+#[tokio::test]
+async fn check_health_cross_chain_rejects_other_emitter() {
+    let (mut h, owner, vaa) = cross_chain_harness(|owner, now| {
+        let other = sentinel::wormhole::Emitter {
+            chain: 2,
+            address: [1; 32],
+        };
+        posted_summary_vaa(other, owner, 1, now)
+    })
+    .await;
+
+    let result = h
+        .send(check_health_cross_chain_ix(&owner, 1, 1, vaa), &[])
+        .await;
+    assert_error(result, ErrorCode::CrossChainEmitterMismatch);
+}
+
+#[tokio::test]
+async fn check_health_cross_chain_rejects_summary_for_other_position() {
+    let (mut h, owner, vaa) =
+        cross_chain_harness(|owner, now| posted_summary_vaa(EMITTER, owner, 2, now)).await;
+
+    let result = h
+        .send(check_health_cross_chain_ix(&owner, 1, 1, vaa), &[])
+        .await;
+    assert_error(result, ErrorCode::CrossChainPositionMismatch);
+}
+
+#[tokio::test]
+async fn check_health_cross_chain_rejects_stale_summary() {
+    let (mut h, owner, vaa) = cross_chain_harness(|owner, now| {
+        posted_summary_vaa(
+            EMITTER,
+            owner,
+            1,
+            now - sentinel::wormhole::MAX_SUMMARY_AGE_SECS - 1,
+        )
+    })
+    .await;
+
+    let result = h
+        .send(check_health_cross_chain_ix(&owner, 1, 1, vaa), &[])
+        .await;
+    assert_error(result, ErrorCode::StaleCrossChainSummary);
+}
+
+#[tokio::test]
+async fn check_health_cross_chain_rejects_delegated_keeper() {
+    let mut h = Harness::new().await;
+    let owner = Pubkey::new_unique();
+    let keeper = h.payer().pubkey();
+    h.set_position(&sentinel::PositionAccount {
+        cross_chain_emitter: Some(EMITTER),
+        keeper: Some(keeper),
+        ..position(owner, 1)
+    });
+    let vaa = Pubkey::new_unique();
+    let account = posted_summary_vaa(EMITTER, &owner, 1, h.now().await);
+    h.ctx.set_account(&vaa, &account.into());
+
+    let mut ix = check_health_cross_chain_ix(&owner, 1, 1, vaa);
+    ix.accounts[0].pubkey = keeper;
+    let result = h.send(ix, &[]).await;
+    assert_error(result, ErrorCode::UnauthorizedKeeper);
+}
+
+#[tokio::test]
+#[ignore = "needs the SBF build, see tests/common"]
+async fn check_health_cross_chain_marks_computation_pending() {
+    let (mut h, owner, vaa) =
+        cross_chain_harness(|owner, now| posted_summary_vaa(EMITTER, owner, 1, now)).await;
+
+    h.send(check_health_cross_chain_ix(&owner, 1, 6, vaa), &[])
+        .await
+        .unwrap();
+
+    assert_eq!(
+        h.position(&owner, 1).await.unwrap().pending_computation,
+        Some(6)
+    );
+}
+
+// ─── Rule-Based Health Check ───
+
+#[tokio::test]
+async fn set_rule_set_replaces_params() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_rule_set(&rule_set(owner, DEFAULT_RULES));
+
+    let mut params = DEFAULT_RULES;
+    params[5] = 10; // first rule's weight
+    h.send(set_rule_set_ix(&owner, params), &[]).await.unwrap();
+
+    assert_eq!(h.rule_set(&owner).await.unwrap().params, params);
+}
+
+#[tokio::test]
+async fn set_rule_set_rejects_malformed_params() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_rule_set(&rule_set(owner, DEFAULT_RULES));
+
+    let mut unordered_tiers = DEFAULT_RULES;
+    unordered_tiers[25] = 9; // medium above critical
+    let mut unknown_field = DEFAULT_RULES;
+    unknown_field[1] = 3;
+    let mut zero_weight = DEFAULT_RULES;
+    zero_weight[5] = 0;
+
+    for params in [unordered_tiers, unknown_field, zero_weight] {
+        let result = h.send(set_rule_set_ix(&owner, params), &[]).await;
+        assert_error(result, ErrorCode::InvalidRuleSet);
+    }
+}
+
+#[tokio::test]
+async fn check_health_with_rules_requires_rule_set() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+
+    let result = h.send(check_health_with_rules_ix(&owner, 1, 1), &[]).await;
+    assert_error(result, anchor_lang::error::ErrorCode::AccountNotInitialized);
+}
+
+#[tokio::test]
+async fn check_health_with_rules_rejects_delegated_keeper() {
+    let mut h = Harness::new().await;
+    let owner = Pubkey::new_unique();
+    let keeper = h.payer().pubkey();
+    h.set_position(&sentinel::PositionAccount {
+        keeper: Some(keeper),
+        ..position(owner, 1)
+    });
+    h.set_rule_set(&rule_set(owner, DEFAULT_RULES));
+
+    let mut ix = check_health_with_rules_ix(&owner, 1, 1);
+    ix.accounts[0].pubkey = keeper;
+    let result = h.send(ix, &[]).await;
+    assert_error(result, ErrorCode::UnauthorizedKeeper);
+}
+
+#[tokio::test]
+#[ignore = "needs the SBF build, see tests/common"]
+async fn check_health_with_rules_marks_computation_pending() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+    h.set_rule_set(&rule_set(owner, DEFAULT_RULES));
+
+    h.send(check_health_with_rules_ix(&owner, 1, 9), &[])
+        .await
+        .unwrap();
+
+    let position = h.position(&owner, 1).await.unwrap();
+    assert_eq!(position.pending_computation, Some(9));
+}
+
+// ─── Protocol Health Check ───
+
+/// A Solend position of the context payer, and its obligation. Returns the
+/// harness, owner, obligation address and protocol program.
+async fn protocol_harness(
+    obligation: impl FnOnce(Pubkey, &Pubkey) -> solana_sdk::account::Account,
+) -> (Harness, Pubkey, Pubkey, Pubkey) {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    let protocol = Pubkey::new_unique();
+    h.set_position(&position(owner, 1));
+    h.set_protocol_adapter(protocol, sentinel::protocols::SOLEND);
+    let address = Pubkey::new_unique();
+    h.ctx
+        .set_account(&address, &obligation(protocol, &owner).into());
+    (h, owner, address, protocol)
+}
+
+#[test]
+fn obligation_layouts_are_valid() {
+    assert!(sentinel::protocols::SOLEND.is_valid());
+    assert!(sentinel::protocols::KAMINO.is_valid());
+}
+
+#[tokio::test]
+async fn check_health_from_protocol_rejects_other_owners_obligation() {
+    let (mut h, owner, obligation, protocol) = protocol_harness(|protocol, _| {
+        solend_obligation(protocol, &Pubkey::new_unique(), 1_000, 500, 800, false)
+    })
+    .await;
+
+    let result = h
+        .send(
+            check_health_from_protocol_ix(&owner, 1, 1, obligation, &protocol),
+            &[],
+        )
+        .await;
+    assert_error(result, ErrorCode::ObligationOwnerMismatch);
+}
+
+#[tokio::test]
+async fn check_health_from_protocol_rejects_stale_obligation() {
+    let (mut h, owner, obligation, protocol) = protocol_harness(|protocol, owner| {
+        solend_obligation(protocol, owner, 1_000, 500, 800, true)
+    })
+    .await;
+
+    let result = h
+        .send(
+            check_health_from_protocol_ix(&owner, 1, 1, obligation, &protocol),
+            &[],
+        )
+        .await;
+    assert_error(result, ErrorCode::StaleObligation);
+}
+
+#[tokio::test]
+async fn check_health_from_protocol_rejects_unregistered_protocol() {
+    let (mut h, owner, _, _) = protocol_harness(|protocol, owner| {
+        solend_obligation(protocol, owner, 1_000, 500, 800, false)
+    })
+    .await;
+    let other = Pubkey::new_unique();
+    let obligation = Pubkey::new_unique();
+    h.ctx.set_account(
+        &obligation,
+        &solend_obligation(other, &owner, 1_000, 500, 800, false).into(),
+    );
+
+    let result = h
+        .send(
+            check_health_from_protocol_ix(&owner, 1, 1, obligation, &other),
+            &[],
+        )
+        .await;
+    assert_error(result, anchor_lang::error::ErrorCode::AccountNotInitialized);
+}
+
+#[tokio::test]
+async fn check_health_from_protocol_rejects_delegated_keeper() {
+    let mut h = Harness::new().await;
+    let owner = Pubkey::new_unique();
+    let keeper = h.payer().pubkey();
+    let protocol = Pubkey::new_unique();
+    h.set_position(&sentinel::PositionAccount {
+        keeper: Some(keeper),
+        ..position(owner, 1)
+    });
+    h.set_protocol_adapter(protocol, sentinel::protocols::SOLEND);
+    let obligation = Pubkey::new_unique();
+    h.ctx.set_account(
+        &obligation,
+        &solend_obligation(protocol, &owner, 1_000, 500, 800, false).into(),
+    );
+
+    let mut ix = check_health_from_protocol_ix(&owner, 1, 1, obligation, &protocol);
+    ix.accounts[0].pubkey = keeper;
+    let result = h.send(ix, &[]).await;
+    assert_error(result, ErrorCode::UnauthorizedKeeper);
+}
+
+#[tokio::test]
+#[ignore = "needs the SBF build, see tests/common"]
+async fn check_health_from_protocol_marks_computation_pending() {
+    let (mut h, owner, obligation, protocol) = protocol_harness(|protocol, owner| {
+        solend_obligation(protocol, owner, 1_000, 500, 800, false)
+    })
+    .await;
+
+    h.send(
+        check_health_from_protocol_ix(&owner, 1, 6, obligation, &protocol),
+        &[],
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(
+        h.position(&owner, 1).await.unwrap().pending_computation,
+        Some(6)
+    );
+}
+
+#[tokio::test]
+async fn set_protocol_blackouts_records_change() {
+    let (mut h, authority, _, protocol) = protocol_harness(|protocol, owner| {
+        solend_obligation(protocol, owner, 1_000, 500, 800, false)
+    })
+    .await;
+    h.set_upgrade_authority(&authority);
+    h.set_admin_audit_log(0);
+    let windows = vec![sentinel::BlackoutWindow {
+        starts_at: 100,
+        ends_at: 200,
+    }];
+
+    h.send(
+        set_protocol_blackouts_ix(&authority, protocol, windows.clone()),
+        &[],
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(
+        h.protocol_adapter(&protocol).await.unwrap().blackouts,
+        windows
+    );
+    let entry = h.admin_audit_log().await.unwrap().entries[0];
+    assert_eq!(entry.field, sentinel::AdminField::ProtocolBlackouts as u8);
+}
+
+#[tokio::test]
+async fn set_protocol_blackouts_rejects_empty_window() {
+    let (mut h, authority, _, protocol) = protocol_harness(|protocol, owner| {
+        solend_obligation(protocol, owner, 1_000, 500, 800, false)
+    })
+    .await;
+    h.set_upgrade_authority(&authority);
+    h.set_admin_audit_log(0);
+    let windows = vec![sentinel::BlackoutWindow {
+        starts_at: 200,
+        ends_at: 200,
+    }];
+
+    let result = h
+        .send(
+            set_protocol_blackouts_ix(&authority, protocol, windows),
+            &[],
+        )
+        .await;
+    assert_error(result, ErrorCode::InvalidBlackoutWindow);
+}
+
+#[tokio::test]
+async fn check_health_from_protocol_defers_during_blackout() {
+    // Mid-upgrade obligations may not parse, so the blackout wins over the
+    // obligation's own errors
+    let (mut h, owner, obligation, protocol) = protocol_harness(|protocol, owner| {
+        solend_obligation(protocol, owner, 1_000, 500, 800, true)
+    })
+    .await;
+    let now = h.now().await;
+    let mut adapter = h.protocol_adapter(&protocol).await.unwrap();
+    adapter.blackouts = vec![sentinel::BlackoutWindow {
+        starts_at: now - 60,
+        ends_at: now + 600,
+    }];
+    h.set_program_account(&protocol_adapter_pda(&protocol), &adapter);
+
+    h.send(
+        check_health_from_protocol_ix(&owner, 1, 6, obligation, &protocol),
+        &[],
+    )
+    .await
+    .unwrap();
+
+    let position = h.position(&owner, 1).await.unwrap();
+    assert_eq!(position.pending_computation, None);
+    assert_eq!(position.deferred_until, now + 600);
+}
+
+// ─── Protocol TVL ───
+
+/// `protocol`'s snapshot, posted to by `updater`.
+fn tvl_snapshot(protocol: Pubkey, updater: Pubkey, tvl: u64) -> sentinel::ProtocolTvlSnapshot {
+    sentinel::ProtocolTvlSnapshot {
+        bump: Pubkey::find_program_address(&[b"tvl_snapshot", protocol.as_ref()], &sentinel::ID).1,
+        protocol_program: protocol,
+        updater,
+        tvl,
+        previous_tvl: 0,
+        updated_at: 0,
+    }
+}
+
+#[tokio::test]
+async fn set_tvl_updater_records_change() {
+    let mut h = Harness::new().await;
+    let authority = h.payer().pubkey();
+    let protocol = Pubkey::new_unique();
+    let updater = Pubkey::new_unique();
+    h.set_upgrade_authority(&authority);
+    h.set_admin_audit_log(0);
+    h.set_tvl_snapshot(&tvl_snapshot(protocol, Pubkey::default(), 0));
+
+    h.send(set_tvl_updater_ix(&authority, protocol, updater), &[])
+        .await
+        .unwrap();
+
+    assert_eq!(h.tvl_snapshot(&protocol).await.unwrap().updater, updater);
+    let entry = h.admin_audit_log().await.unwrap().entries[0];
+    assert_eq!(entry.field, sentinel::AdminField::TvlUpdater as u8);
+}
+
+#[tokio::test]
+async fn update_tvl_snapshot_keeps_previous_tvl() {
+    let mut h = Harness::new().await;
+    let updater = h.payer().pubkey();
+    let protocol = Pubkey::new_unique();
+    h.set_tvl_snapshot(&tvl_snapshot(protocol, updater, 1_000_000));
+
+    h.send(update_tvl_snapshot_ix(&updater, protocol, 700_000), &[])
+        .await
+        .unwrap();
+
+    let snapshot = h.tvl_snapshot(&protocol).await.unwrap();
+    assert_eq!((snapshot.tvl, snapshot.previous_tvl), (700_000, 1_000_000));
+    assert!(snapshot.updated_at > 0);
+}
+
+#[tokio::test]
+async fn update_tvl_snapshot_rejects_other_signer() {
+    let mut h = Harness::new().await;
+    let signer = h.payer().pubkey();
+    let protocol = Pubkey::new_unique();
+    h.set_tvl_snapshot(&tvl_snapshot(protocol, Pubkey::new_unique(), 1_000_000));
+
+    let result = h
+        .send(update_tvl_snapshot_ix(&signer, protocol, 0), &[])
+        .await;
+    assert_error(result, ErrorCode::InvalidAuthority);
+}
+
+#[tokio::test]
+async fn set_tvl_protocol_rejects_invalid_drop() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+
+    let protocol = Some(Pubkey::new_unique());
+    for drop_bps in [0, 10_001] {
+        let result = h
+            .send(set_tvl_protocol_ix(&owner, 1, protocol, drop_bps), &[])
+            .await;
+        assert_error(result, ErrorCode::InvalidTvlDrop);
+    }
+    h.send(set_tvl_protocol_ix(&owner, 1, protocol, 3_000), &[])
+        .await
+        .unwrap();
+
+    let position = h.position(&owner, 1).await.unwrap();
+    assert_eq!(
+        (position.tvl_protocol, position.tvl_drop_bps),
+        (protocol, 3_000)
+    );
+}
+
+#[tokio::test]
+async fn check_health_rejects_tvl_snapshot_of_other_protocol() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    let protocol = Pubkey::new_unique();
+    let other = Pubkey::new_unique();
+    h.set_position(&sentinel::PositionAccount {
+        tvl_protocol: Some(protocol),
+        ..position(owner, 1)
+    });
+    h.set_tvl_snapshot(&tvl_snapshot(other, owner, 1_000_000));
+
+    let ix = check_health_with_market_ix(&owner, &owner, 1, 1, None, Some(other));
+    let result = h.send(ix, &[]).await;
+    assert_error(result, ErrorCode::TvlProtocolMismatch);
+}
+
+#[tokio::test]
+async fn check_health_tvl_snapshot_requires_tvl_protocol() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    let protocol = Pubkey::new_unique();
+    h.set_position(&position(owner, 1));
+    h.set_tvl_snapshot(&tvl_snapshot(protocol, owner, 1_000_000));
+
+    let ix = check_health_with_market_ix(&owner, &owner, 1, 1, None, Some(protocol));
+    let result = h.send(ix, &[]).await;
+    assert_error(result, ErrorCode::TvlProtocolNotSet);
+}
+
+// ─── Reveal Risk To Owner ───
+
+#[tokio::test]
+async fn reveal_risk_to_owner_rejects_other_payer() {
+    let mut h = Harness::new().await;
+    let owner = Pubkey::new_unique();
+    let intruder = Keypair::new();
+    h.set_position(&position(owner, 1));
+
+    let mut ix = reveal_risk_to_owner_ix(&intruder.pubkey(), 1, 1);
+    let position_meta = ix
+        .accounts
+        .iter_mut()
+        .find(|meta| meta.pubkey == position_pda(&intruder.pubkey(), 1))
+        .unwrap();
+    position_meta.pubkey = position_pda(&owner, 1);
+
+    let result = h.send(ix, &[&intruder]).await;
+    assert_error(result, anchor_lang::error::ErrorCode::ConstraintSeeds);
+}
+
+#[tokio::test]
+#[ignore = "needs the SBF build, see tests/common"]
+async fn reveal_risk_to_owner_marks_computation_pending() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+
+    h.send(reveal_risk_to_owner_ix(&owner, 1, 5), &[])
+        .await
+        .unwrap();
+
+    let position = h.position(&owner, 1).await.unwrap();
+    assert_eq!(position.pending_computation, Some(5));
+}
+
+#[tokio::test]
+#[ignore = "needs the SBF build, see tests/common"]
+async fn reveal_risk_to_owner_creates_inbox() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+
+    h.send(reveal_risk_to_owner_ix(&owner, 1, 5), &[])
+        .await
+        .unwrap();
+
+    let position = position_pda(&owner, 1);
+    let inbox = h.reveal_inbox(&position).await.unwrap();
+    assert_eq!(inbox.position, position);
+    // Nothing delivered until the callback
+    assert_eq!(inbox.delivered_at, 0);
+}
+
+#[tokio::test]
+#[ignore = "needs the SBF build, see tests/common"]
+async fn reveal_risk_to_owner_proceeds_on_degraded_cluster() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+    h.degrade_cluster();
+
+    h.send(reveal_risk_to_owner_ix(&owner, 1, 5), &[])
+        .await
+        .unwrap();
+}
+
+// ─── Viewers ───
+
+#[tokio::test]
+async fn grant_viewer_replaces_encryption_key() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    let auditor = Pubkey::new_unique();
+    h.set_position(&position(owner, 1));
+    let grant_pda = viewer_grant_pda(&position_pda(&owner, 1), &auditor);
+    h.set_program_account(&grant_pda, &viewer_grant(owner, 1, auditor, [1; 32]));
+
+    h.send(grant_viewer_ix(&owner, 1, auditor, [2; 32]), &[])
+        .await
+        .unwrap();
+
+    let grant = h
+        .viewer_grant(&position_pda(&owner, 1), &auditor)
+        .await
+        .unwrap();
+    assert_eq!((grant.viewer, grant.encryption_key), (auditor, [2; 32]));
+}
+
+#[tokio::test]
+async fn grant_viewer_rejects_owner() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+    let grant_pda = viewer_grant_pda(&position_pda(&owner, 1), &owner);
+    h.set_program_account(&grant_pda, &viewer_grant(owner, 1, owner, [1; 32]));
+
+    let result = h
+        .send(grant_viewer_ix(&owner, 1, owner, [2; 32]), &[])
+        .await;
+    assert_error(result, ErrorCode::InvalidViewer);
+}
+
+#[tokio::test]
+async fn revoke_viewer_closes_grant() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    let auditor = Pubkey::new_unique();
+    h.set_position(&position(owner, 1));
+    let grant_pda = viewer_grant_pda(&position_pda(&owner, 1), &auditor);
+    h.set_program_account(&grant_pda, &viewer_grant(owner, 1, auditor, [1; 32]));
+
+    h.send(revoke_viewer_ix(&owner, 1, auditor), &[])
+        .await
+        .unwrap();
+
+    assert!(h
+        .viewer_grant(&position_pda(&owner, 1), &auditor)
+        .await
+        .is_none());
+}
+
+#[tokio::test]
+async fn reveal_risk_to_viewer_rejects_stranger() {
+    let mut h = Harness::new().await;
+    let owner = Pubkey::new_unique();
+    let auditor = Pubkey::new_unique();
+    let stranger = Keypair::new();
+    h.set_position(&position(owner, 1));
+    let grant_pda = viewer_grant_pda(&position_pda(&owner, 1), &auditor);
+    h.set_program_account(&grant_pda, &viewer_grant(owner, 1, auditor, [1; 32]));
+
+    let ix = reveal_risk_to_viewer_ix(&stranger.pubkey(), &owner, 1, &auditor, 1);
+    let result = h.send(ix, &[&stranger]).await;
+    assert_error(result, ErrorCode::InvalidAuthority);
+}
+
+#[tokio::test]
+#[ignore = "needs the SBF build, see tests/common"]
+async fn reveal_risk_to_viewer_marks_computation_pending() {
+    let mut h = Harness::new().await;
+    let owner = Pubkey::new_unique();
+    let auditor = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+    let grant_pda = viewer_grant_pda(&position_pda(&owner, 1), &auditor);
+    h.set_program_account(&grant_pda, &viewer_grant(owner, 1, auditor, [1; 32]));
+
+    h.send(
+        reveal_risk_to_viewer_ix(&auditor, &owner, 1, &auditor, 5),
+        &[],
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(
+        h.position(&owner, 1).await.unwrap().pending_computation,
+        Some(5)
+    );
+}
+
+// ─── Health Factor ───
+
+#[tokio::test]
+#[ignore = "needs the SBF build, see tests/common"]
+async fn compute_health_factor_marks_computation_pending() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+
+    h.send(compute_health_factor_ix(&owner, &owner, 1, 6), &[])
+        .await
+        .unwrap();
+
+    assert_eq!(
+        h.position(&owner, 1).await.unwrap().pending_computation,
+        Some(6)
+    );
+}
+
+#[tokio::test]
+async fn compute_health_factor_rejects_unauthorized_signer() {
+    let mut h = Harness::new().await;
+    let owner = Pubkey::new_unique();
+    let payer = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+
+    let result = h
+        .send(compute_health_factor_ix(&payer, &owner, 1, 1), &[])
+        .await;
+    assert_error(result, ErrorCode::UnauthorizedKeeper);
+}
+
+#[tokio::test]
+async fn compute_health_factor_rejects_pending_computation() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&sentinel::PositionAccount {
+        pending_computation: Some(3),
+        ..position(owner, 1)
+    });
+
+    let result = h
+        .send(compute_health_factor_ix(&owner, &owner, 1, 4), &[])
+        .await;
+    assert_error(result, ErrorCode::ComputationPending);
+}
+
+#[tokio::test]
+async fn reveal_health_factor_to_owner_rejects_uncomputed_factor() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+    h.set_reveal_inbox(&reveal_inbox(position_pda(&owner, 1)));
+
+    let result = h
+        .send(reveal_health_factor_to_owner_ix(&owner, 1, 1), &[])
+        .await;
+    assert_error(result, ErrorCode::HealthFactorNotComputed);
+}
+
+#[tokio::test]
+async fn reveal_health_factor_to_owner_rejects_other_payer() {
+    let mut h = Harness::new().await;
+    let owner = Pubkey::new_unique();
+    let intruder = Keypair::new();
+    h.set_position(&position(owner, 1));
+    h.set_reveal_inbox(&reveal_inbox(position_pda(&owner, 1)));
+
+    let mut ix = reveal_health_factor_to_owner_ix(&intruder.pubkey(), 1, 1);
+    let n = ix.accounts.len();
+    ix.accounts[n - 3].pubkey = position_pda(&owner, 1);
+    ix.accounts[n - 1].pubkey = reveal_inbox_pda(&position_pda(&owner, 1));
+
+    let result = h.send(ix, &[&intruder]).await;
+    assert_error(result, ErrorCode::InvalidAuthority);
+}
+
+#[tokio::test]
+#[ignore = "needs the SBF build, see tests/common"]
+async fn reveal_health_factor_to_owner_marks_computation_pending() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    let now = h.now().await;
+    h.set_position(&sentinel::PositionAccount {
+        health_factor_at: now,
+        ..position(owner, 1)
+    });
+
+    h.send(reveal_health_factor_to_owner_ix(&owner, 1, 5), &[])
+        .await
+        .unwrap();
+
+    assert_eq!(
+        h.position(&owner, 1).await.unwrap().pending_computation,
+        Some(5)
+    );
+}
+
+// ─── Stress Test ───
+
+#[tokio::test]
+async fn stress_test_position_rejects_shock_out_of_range() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+    h.set_reveal_inbox(&reveal_inbox(position_pda(&owner, 1)));
+
+    let result = h.send(stress_test_position_ix(&owner, 1, 1, 0), &[]).await;
+    assert_error(result, ErrorCode::InvalidPriceShock);
+    let result = h
+        .send(stress_test_position_ix(&owner, 1, 1, 10_001), &[])
+        .await;
+    assert_error(result, ErrorCode::InvalidPriceShock);
+}
+
+#[tokio::test]
+async fn stress_test_position_rejects_other_payer() {
+    let mut h = Harness::new().await;
+    let owner = Pubkey::new_unique();
+    let intruder = Keypair::new();
+    h.set_position(&position(owner, 1));
+    h.set_reveal_inbox(&reveal_inbox(position_pda(&owner, 1)));
+
+    let mut ix = stress_test_position_ix(&intruder.pubkey(), 1, 1, 2_000);
+    let n = ix.accounts.len();
+    ix.accounts[n - 3].pubkey = position_pda(&owner, 1);
+    ix.accounts[n - 1].pubkey = reveal_inbox_pda(&position_pda(&owner, 1));
+
+    let result = h.send(ix, &[&intruder]).await;
+    assert_error(result, ErrorCode::InvalidAuthority);
+}
+
+#[tokio::test]
+#[ignore = "needs the SBF build, see tests/common"]
+async fn stress_test_position_marks_computation_pending() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+
+    h.send(stress_test_position_ix(&owner, 1, 5, 2_000), &[])
+        .await
+        .unwrap();
+
+    assert_eq!(
+        h.position(&owner, 1).await.unwrap().pending_computation,
+        Some(5)
+    );
+}
+
+// ─── Attestations ───
+
+#[tokio::test]
+async fn attest_health_rejects_threshold_below_one() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    let mut pos = position(owner, 1);
+    pos.health_factor_at = 1;
+    h.set_position(&pos);
+    let position_acc = position_pda(&owner, 1);
+    h.set_program_account(&attestation_pda(&position_acc), &attestation(position_acc));
+
+    let result = h.send(attest_health_ix(&owner, 1, 1, 9_999), &[]).await;
+    assert_error(result, ErrorCode::InvalidAttestationThreshold);
+}
+
+#[tokio::test]
+async fn attest_health_requires_computed_health_factor() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+    let position_acc = position_pda(&owner, 1);
+    h.set_program_account(&attestation_pda(&position_acc), &attestation(position_acc));
+
+    let result = h.send(attest_health_ix(&owner, 1, 1, 12_000), &[]).await;
+    assert_error(result, ErrorCode::HealthFactorNotComputed);
+}
+
+#[tokio::test]
+#[ignore = "needs the SBF build, see tests/common"]
+async fn attest_health_marks_computation_pending() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    let mut pos = position(owner, 1);
+    pos.health_factor_at = 1;
+    h.set_position(&pos);
+
+    h.send(attest_health_ix(&owner, 1, 5, 12_000), &[])
+        .await
+        .unwrap();
+
+    assert_eq!(
+        h.position(&owner, 1).await.unwrap().pending_computation,
+        Some(5)
+    );
+}
+
+// ─── Key Rotation ───
+
+#[tokio::test]
+#[ignore = "needs the SBF build, see tests/common"]
+async fn rotate_encryption_marks_computation_pending() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+
+    h.send(rotate_encryption_ix(&owner, &owner, 1, 8, 2), &[])
+        .await
+        .unwrap();
+
+    assert_eq!(
+        h.position(&owner, 1).await.unwrap().pending_computation,
+        Some(8)
+    );
+}
+
+#[tokio::test]
+async fn rotate_encryption_rejects_stale_nonce() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+
+    let result = h
+        .send(rotate_encryption_ix(&owner, &owner, 1, 8, 1), &[])
+        .await;
+    assert_error(result, ErrorCode::StaleNonce);
+}
+
+#[tokio::test]
+async fn rotate_encryption_rejects_keeper() {
+    let mut h = Harness::new().await;
+    let owner = Pubkey::new_unique();
+    let keeper = h.payer().pubkey();
+    h.set_position(&sentinel::PositionAccount {
+        keeper: Some(keeper),
+        ..position(owner, 1)
+    });
+
+    let result = h
+        .send(rotate_encryption_ix(&keeper, &owner, 1, 8, 2), &[])
+        .await;
+    assert_error(result, ErrorCode::InvalidAuthority);
+}
+
+#[tokio::test]
+async fn rotate_encryption_rejects_pending_computation() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&sentinel::PositionAccount {
+        pending_computation: Some(3),
+        ..position(owner, 1)
+    });
+
+    let result = h
+        .send(rotate_encryption_ix(&owner, &owner, 1, 8, 2), &[])
+        .await;
+    assert_error(result, ErrorCode::ComputationPending);
+}
+
+// ─── Sponsored Reveals ───
+
+#[tokio::test]
+async fn set_sponsor_budget_replaces_allowance() {
+    let mut h = Harness::new().await;
+    let sponsor = h.payer().pubkey();
+    let owner = Pubkey::new_unique();
+    h.set_sponsor_budget(&sponsor_budget(sponsor, owner, 5));
+
+    h.send(set_sponsor_budget_ix(&sponsor, owner, 2), &[])
+        .await
+        .unwrap();
+
+    assert_eq!(
+        h.sponsor_budget(&sponsor, &owner).await.unwrap().remaining,
+        2
+    );
+}
+
+#[tokio::test]
+async fn sponsored_reveal_rejects_exhausted_budget() {
+    let mut h = Harness::new().await;
+    let sponsor = h.payer().pubkey();
+    let owner = Keypair::new();
+    h.set_position(&position(owner.pubkey(), 1));
+    h.set_reveal_inbox(&reveal_inbox(position_pda(&owner.pubkey(), 1)));
+    h.set_sponsor_budget(&sponsor_budget(sponsor, owner.pubkey(), 0));
+
+    let ix = sponsored_reveal_risk_to_owner_ix(&sponsor, &owner.pubkey(), 1, 5);
+    let result = h.send(ix, &[&owner]).await;
+    assert_error(result, ErrorCode::SponsorBudgetExhausted);
+}
+
+#[tokio::test]
+async fn sponsored_reveal_rejects_other_sponsors_budget() {
+    let mut h = Harness::new().await;
+    let sponsor = h.payer().pubkey();
+    let owner = Keypair::new();
+    h.set_position(&position(owner.pubkey(), 1));
+    h.set_reveal_inbox(&reveal_inbox(position_pda(&owner.pubkey(), 1)));
+    let other_sponsor = Pubkey::new_unique();
+    h.set_sponsor_budget(&sponsor_budget(other_sponsor, owner.pubkey(), 3));
+
+    let mut ix = sponsored_reveal_risk_to_owner_ix(&sponsor, &owner.pubkey(), 1, 5);
+    let budget_meta = ix
+        .accounts
+        .iter_mut()
+        .find(|meta| meta.pubkey == sponsor_budget_pda(&sponsor, &owner.pubkey()))
+        .unwrap();
+    budget_meta.pubkey = sponsor_budget_pda(&other_sponsor, &owner.pubkey());
+
+    let result = h.send(ix, &[&owner]).await;
+    assert_error(result, anchor_lang::error::ErrorCode::ConstraintSeeds);
+}
+
+#[tokio::test]
+#[ignore = "needs the SBF build, see tests/common"]
+async fn sponsored_reveal_uses_budget() {
+    let mut h = Harness::new().await;
+    let sponsor = h.payer().pubkey();
+    let owner = Keypair::new();
+    h.set_position(&position(owner.pubkey(), 1));
+    h.set_sponsor_budget(&sponsor_budget(sponsor, owner.pubkey(), 2));
+
+    let ix = sponsored_reveal_risk_to_owner_ix(&sponsor, &owner.pubkey(), 1, 5);
+    h.send(ix, &[&owner]).await.unwrap();
+
+    let budget = h.sponsor_budget(&sponsor, &owner.pubkey()).await.unwrap();
+    assert_eq!((budget.remaining, budget.used), (1, 1));
+    assert_eq!(
+        h.position(&owner.pubkey(), 1)
+            .await
+            .unwrap()
+            .pending_computation,
+        Some(5)
+    );
+}
+
+// ─── Risk History ───
+
+#[test]
+fn risk_history_wraps_around() {
+    let mut history = risk_history(Pubkey::new_unique());
+    for i in 0..sentinel::RISK_HISTORY_LEN as i64 + 2 {
+        history.push([[i as u8; 32]; 3], i as u128, i);
+    }
+
+    assert_eq!(history.len as usize, sentinel::RISK_HISTORY_LEN);
+    assert_eq!(history.head, 2);
+    // The two oldest entries were overwritten
+    assert_eq!(
+        history.entries[0].timestamp,
+        sentinel::RISK_HISTORY_LEN as i64
+    );
+    assert_eq!(history.entries[2].timestamp, 2);
+}
+
+#[tokio::test]
+#[ignore = "needs the SBF build, see tests/common"]
+async fn init_risk_history_creates_empty_history() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+
+    h.send(init_risk_history_ix(&owner, 1), &[]).await.unwrap();
+
+    let history = h.risk_history(&position_pda(&owner, 1)).await.unwrap();
+    assert_eq!(history.position, position_pda(&owner, 1));
+    assert_eq!(history.len, 0);
+}
+
+#[tokio::test]
+async fn reveal_history_summary_rejects_empty_history() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+    h.set_risk_history(&risk_history(position_pda(&owner, 1)));
+
+    let result = h.send(reveal_history_summary_ix(&owner, 1, 1), &[]).await;
+    assert_error(result, ErrorCode::EmptyRiskHistory);
+}
+
+#[tokio::test]
+#[ignore = "needs the SBF build, see tests/common"]
+async fn reveal_history_summary_marks_computation_pending() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+    let mut history = risk_history(position_pda(&owner, 1));
+    history.push([[1; 32]; 3], 1, 0);
+    h.set_risk_history(&history);
+
+    h.send(reveal_history_summary_ix(&owner, 1, 6), &[])
+        .await
+        .unwrap();
+
+    let position = h.position(&owner, 1).await.unwrap();
+    assert_eq!(position.pending_computation, Some(6));
+}
+
+// ─── Portfolio ───
+
+#[tokio::test]
+async fn add_portfolio_position_appends_position() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_portfolio(&portfolio(owner));
+    h.set_position(&position(owner, 1));
+    h.set_position(&position(owner, 2));
+
+    h.send(add_portfolio_position_ix(&owner, 2), &[])
+        .await
+        .unwrap();
+    h.send(add_portfolio_position_ix(&owner, 1), &[])
+        .await
+        .unwrap();
+
+    let portfolio = h.portfolio(&owner).await.unwrap();
+    assert_eq!(
+        portfolio.positions,
+        vec![position_pda(&owner, 2), position_pda(&owner, 1)]
+    );
+}
+
+#[tokio::test]
+async fn add_portfolio_position_rejects_duplicate() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_portfolio(&sentinel::PortfolioAccount {
+        positions: vec![position_pda(&owner, 1)],
+        ..portfolio(owner)
+    });
+    h.set_position(&position(owner, 1));
+
+    let result = h.send(add_portfolio_position_ix(&owner, 1), &[]).await;
+    assert_error(result, ErrorCode::PositionAlreadyInPortfolio);
+}
+
+#[tokio::test]
+async fn add_portfolio_position_rejects_full_portfolio() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    let max = sentinel::MAX_PORTFOLIO_POSITIONS as u32;
+    h.set_portfolio(&sentinel::PortfolioAccount {
+        positions: (1..=max).map(|id| position_pda(&owner, id)).collect(),
+        ..portfolio(owner)
+    });
+    h.set_position(&position(owner, max + 1));
+
+    let result = h
+        .send(add_portfolio_position_ix(&owner, max + 1), &[])
+        .await;
+    assert_error(result, ErrorCode::PortfolioFull);
+}
+
+#[tokio::test]
+async fn remove_portfolio_position_rejects_unknown_position() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_portfolio(&portfolio(owner));
+
+    let result = h
+        .send(
+            remove_portfolio_position_ix(&owner, position_pda(&owner, 1)),
+            &[],
+        )
+        .await;
+    assert_error(result, ErrorCode::PositionNotInPortfolio);
+}
+
+#[tokio::test]
+async fn aggregate_portfolio_risk_rejects_empty_portfolio() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_portfolio(&portfolio(owner));
+
+    let result = h
+        .send(aggregate_portfolio_risk_ix(&owner, 1, &[]), &[])
+        .await;
+    assert_error(result, ErrorCode::EmptyPortfolio);
+}
+
+#[tokio::test]
+async fn aggregate_portfolio_risk_requires_positions_in_order() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    let positions = [position_pda(&owner, 1), position_pda(&owner, 2)];
+    h.set_portfolio(&sentinel::PortfolioAccount {
+        positions: positions.to_vec(),
+        ..portfolio(owner)
+    });
+    h.set_position(&position(owner, 1));
+    h.set_position(&position(owner, 2));
+
+    let missing = h
+        .send(aggregate_portfolio_risk_ix(&owner, 1, &positions[..1]), &[])
+        .await;
+    assert_error(missing, ErrorCode::PortfolioPositionMismatch);
+
+    let reversed = [positions[1], positions[0]];
+    let result = h
+        .send(aggregate_portfolio_risk_ix(&owner, 2, &reversed), &[])
+        .await;
+    assert_error(result, ErrorCode::PortfolioPositionMismatch);
+}
+
+#[tokio::test]
+#[ignore = "needs the SBF build, see tests/common"]
+async fn aggregate_portfolio_risk_marks_computation_pending() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    let positions = [position_pda(&owner, 1), position_pda(&owner, 2)];
+    h.set_portfolio(&sentinel::PortfolioAccount {
+        positions: positions.to_vec(),
+        ..portfolio(owner)
+    });
+    h.set_position(&position(owner, 1));
+    h.set_position(&position(owner, 2));
+
+    h.send(aggregate_portfolio_risk_ix(&owner, 3, &positions), &[])
+        .await
+        .unwrap();
+
+    assert_eq!(
+        h.portfolio(&owner).await.unwrap().pending_computation,
+        Some(3)
+    );
+}
+
+// ─── Risk Heatmap ───
+
+/// A harness whose global config names the payer admin, with a heatmap
+/// sampling `heatmap`'s epoch.
+async fn heatmap_harness(heatmap: &sentinel::RiskHeatmap) -> (Harness, Pubkey) {
+    let mut h = Harness::new().await;
+    let admin = h.payer().pubkey();
+    h.set_global_config(&sentinel::GlobalConfig {
+        admin,
+        ..global_config(60)
+    });
+    h.set_admin_audit_log(0);
+    h.set_program_account(&risk_heatmap_pda(), heatmap);
+    (h, admin)
+}
+
+#[tokio::test]
+async fn configure_risk_heatmap_sets_classes_and_records_change() {
+    let (mut h, admin) = heatmap_harness(&risk_heatmap(0)).await;
+    let (sol, usdc) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let classes = [sol, usdc, Pubkey::default(), Pubkey::default()];
+
+    h.send(configure_risk_heatmap_ix(&admin, classes), &[])
+        .await
+        .unwrap();
+
+    let heatmap = h.risk_heatmap().await.unwrap();
+    assert_eq!(heatmap.classes, classes);
+    assert_eq!(heatmap.epoch, 1);
+    assert_eq!(heatmap.class_of(&usdc), 1);
+    assert_eq!(
+        heatmap.class_of(&Pubkey::default()),
+        sentinel::HEATMAP_CLASSES
+    );
+    let entry = h.admin_audit_log().await.unwrap().entries[0];
+    assert_eq!(entry.field, sentinel::AdminField::HeatmapClasses as u8);
+}
+
+#[tokio::test]
+async fn configure_risk_heatmap_rejects_epoch_in_progress() {
+    let mut heatmap = risk_heatmap(3);
+    heatmap.sampled[0] = 1;
+    let (mut h, admin) = heatmap_harness(&heatmap).await;
+
+    let result = h
+        .send(
+            configure_risk_heatmap_ix(&admin, [Pubkey::default(); 4]),
+            &[],
+        )
+        .await;
+    assert_error(result, ErrorCode::HeatmapEpochInProgress);
+}
+
+#[tokio::test]
+async fn accumulate_risk_heatmap_rejects_position_sampled_this_epoch() {
+    let (mut h, admin) = heatmap_harness(&risk_heatmap(3)).await;
+    let owner = Pubkey::new_unique();
+    h.set_position(&sentinel::PositionAccount {
+        heatmap_epoch: 3,
+        ..position(owner, 1)
+    });
+
+    let positions = [position_pda(&owner, 1)];
+    let result = h
+        .send(accumulate_risk_heatmap_ix(&admin, 7, &positions), &[])
+        .await;
+    assert_error(result, ErrorCode::PositionAlreadySampled);
+}
+
+#[tokio::test]
+async fn accumulate_risk_heatmap_rejects_duplicate_position() {
+    let (mut h, admin) = heatmap_harness(&risk_heatmap(3)).await;
+    let owner = Pubkey::new_unique();
+    h.set_position(&position(owner, 1));
+
+    let positions = [position_pda(&owner, 1), position_pda(&owner, 1)];
+    let result = h
+        .send(accumulate_risk_heatmap_ix(&admin, 7, &positions), &[])
+        .await;
+    assert_error(result, ErrorCode::InvalidBatch);
+}
+
+#[tokio::test]
+async fn publish_risk_heatmap_rejects_sparse_epoch() {
+    let mut heatmap = risk_heatmap(3);
+    heatmap.sampled = [sentinel::MIN_HEATMAP_POSITIONS - 1, 0, 0, 0, 0];
+    let (mut h, admin) = heatmap_harness(&heatmap).await;
+
+    let result = h.send(publish_risk_heatmap_ix(&admin, 7), &[]).await;
+    assert_error(result, ErrorCode::HeatmapTooSparse);
+}
+
+#[tokio::test]
+async fn risk_heatmap_cranks_reject_non_admin() {
+    let (mut h, _) = heatmap_harness(&risk_heatmap(3)).await;
+    let operator = Keypair::new();
+
+    let result = h
+        .send(publish_risk_heatmap_ix(&operator.pubkey(), 7), &[&operator])
+        .await;
+    assert_error(result, ErrorCode::InvalidAuthority);
+}
+
+// ─── Keeper Delegation ───
+
+#[tokio::test]
+async fn delegate_keeper_records_keeper() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    let keeper = Pubkey::new_unique();
+    h.set_position(&position(owner, 1));
+
+    h.send(delegate_keeper_ix(&owner, 1, Some(keeper), Some(100)), &[])
+        .await
+        .unwrap();
+
+    let position = h.position(&owner, 1).await.unwrap();
+    assert_eq!(position.keeper, Some(keeper));
+    assert_eq!(position.keeper_expiry_slot, Some(100));
+}
+
+#[tokio::test]
+async fn check_health_rejects_undelegated_payer() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    let stranger = Keypair::new();
+    h.set_position(&position(owner, 1));
+    h.set_monitoring_plan(0);
+    h.set_subscription(&subscription(owner, 1), 0);
+
+    let result = h
+        .send(
+            check_health_ix(&stranger.pubkey(), &owner, 1, 1),
+            &[&stranger],
+        )
+        .await;
+    assert_error(result, ErrorCode::UnauthorizedKeeper);
+}
+
+#[tokio::test]
+async fn check_health_rejects_expired_keeper() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    let keeper = Keypair::new();
+    h.set_position(&sentinel::PositionAccount {
+        keeper: Some(keeper.pubkey()),
+        keeper_expiry_slot: Some(0),
+        ..position(owner, 1)
+    });
+    h.set_monitoring_plan(0);
+    h.set_subscription(&subscription(owner, 1), 0);
+
+    let result = h
+        .send(check_health_ix(&keeper.pubkey(), &owner, 1, 1), &[&keeper])
+        .await;
+    assert_error(result, ErrorCode::UnauthorizedKeeper);
+}
+
+#[tokio::test]
+#[ignore = "needs the SBF build, see tests/common"]
+async fn check_health_accepts_active_keeper() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    let keeper = h.funded_keypair().await;
+    h.set_position(&sentinel::PositionAccount {
+        keeper: Some(keeper.pubkey()),
+        ..position(owner, 1)
+    });
+    h.set_monitoring_plan(0);
+    h.set_subscription(&subscription(owner, 1), 0);
+
+    h.send(check_health_ix(&keeper.pubkey(), &owner, 1, 4), &[&keeper])
+        .await
+        .unwrap();
+
+    assert_eq!(
+        h.position(&owner, 1).await.unwrap().pending_computation,
+        Some(4)
+    );
+}
+
+// ─── Admin Audit Log ───
+
+#[tokio::test]
+#[ignore = "needs the SBF build, see tests/common"]
+async fn init_admin_audit_log_creates_empty_log() {
+    let mut h = Harness::new().await;
+    let payer = h.payer().pubkey();
+
+    h.send(init_admin_audit_log_ix(&payer), &[]).await.unwrap();
+
+    let log = h.admin_audit_log().await.unwrap();
+    assert_eq!((log.head, log.total), (0, 0));
+}
+
+#[tokio::test]
+async fn set_monitoring_plan_records_change() {
+    let mut h = Harness::new().await;
+    let authority = h.payer().pubkey();
+    h.set_upgrade_authority(&authority);
+    h.set_admin_audit_log(0);
+    h.set_monitoring_plan(1_000);
+
+    h.send(set_monitoring_plan_ix(&authority, 2_000, 2_500), &[])
+        .await
+        .unwrap();
+
+    let log = h.admin_audit_log().await.unwrap();
+    assert_eq!((log.head, log.total), (2, 2));
+    let entry = log.entries[0];
+    assert_eq!(entry.authority, authority);
+    assert_eq!(entry.field, sentinel::AdminField::MonitoringPlanFee as u8);
+    assert_eq!(entry.old_hash, hash(&1_000u64.to_le_bytes()).to_bytes());
+    assert_eq!(entry.new_hash, hash(&2_000u64.to_le_bytes()).to_bytes());
+    assert!(entry.slot > 0);
+    let entry = log.entries[1];
+    assert_eq!(
+        entry.field,
+        sentinel::AdminField::MonitoringPlanReferralShare as u8
+    );
+    assert_eq!(entry.new_hash, hash(&2_500u16.to_le_bytes()).to_bytes());
+    assert_eq!(h.monitoring_plan().await.unwrap().referral_share_bps, 2_500);
+}
+
+#[tokio::test]
+async fn set_monitoring_plan_rejects_invalid_referral_share() {
+    let mut h = Harness::new().await;
+    let authority = h.payer().pubkey();
+    h.set_upgrade_authority(&authority);
+    h.set_admin_audit_log(0);
+    h.set_monitoring_plan(1_000);
+
+    let result = h
+        .send(set_monitoring_plan_ix(&authority, 1_000, 10_001), &[])
+        .await;
+    assert_error(result, ErrorCode::InvalidReferralShare);
+}
+
+#[tokio::test]
+async fn admin_audit_log_overwrites_oldest_change() {
+    let mut h = Harness::new().await;
+    let authority = h.payer().pubkey();
+    let last = sentinel::ADMIN_AUDIT_LOG_LEN as u64 - 1;
+    h.set_upgrade_authority(&authority);
+    h.set_admin_audit_log(last);
+    h.set_global_config(&sentinel::GlobalConfig {
+        pending_update: Some(sentinel::ConfigUpdate {
+            max_staleness_secs: 120,
+            update_delay_secs: sentinel::DEFAULT_CONFIG_UPDATE_DELAY_SECS,
+            apply_after: 0,
+        }),
+        ..global_config(60)
+    });
+
+    h.send(apply_config_update_ix(&authority), &[])
+        .await
+        .unwrap();
+
+    let log = h.admin_audit_log().await.unwrap();
+    assert_eq!((log.head, log.total), (0, last + 1));
+    let entry = log.entries[last as usize];
+    assert_eq!(entry.field, sentinel::AdminField::DefaultMaxStaleness as u8);
+    assert_eq!(entry.new_hash, hash(&120i64.to_le_bytes()).to_bytes());
+}
+
+#[tokio::test]
+async fn set_monitoring_plan_rejects_other_authority() {
+    let mut h = Harness::new().await;
+    let authority = h.payer().pubkey();
+    h.set_upgrade_authority(&Pubkey::new_unique());
+    h.set_admin_audit_log(0);
+    h.set_monitoring_plan(1_000);
+
+    let result = h.send(set_monitoring_plan_ix(&authority, 0, 0), &[]).await;
+    assert_error(result, ErrorCode::InvalidAuthority);
+}
+
+// ─── Global Config ───
+
+/// A global config allowing 60 s of staleness, with `pending_update`.
+fn global_config_with(pending_update: Option<sentinel::ConfigUpdate>) -> sentinel::GlobalConfig {
+    sentinel::GlobalConfig {
+        update_delay_secs: 100,
+        pending_update,
+        ..global_config(60)
+    }
+}
+
+#[tokio::test]
+async fn propose_config_update_waits_for_delay() {
+    let mut h = Harness::new().await;
+    let authority = h.payer().pubkey();
+    h.set_upgrade_authority(&authority);
+    h.set_global_config(&global_config_with(None));
+    let now = h.now().await;
+
+    h.send(propose_config_update_ix(&authority, 120, 50), &[])
+        .await
+        .unwrap();
+
+    let config = h.global_config().await.unwrap();
+    assert_eq!(config.max_staleness_secs, 60);
+    let update = config.pending_update.unwrap();
+    assert_eq!(
+        (update.max_staleness_secs, update.update_delay_secs),
+        (120, 50)
+    );
+    assert!(update.apply_after >= now + 100);
+}
+
+#[tokio::test]
+async fn propose_config_update_rejects_non_positive_delay() {
+    let mut h = Harness::new().await;
+    let authority = h.payer().pubkey();
+    h.set_upgrade_authority(&authority);
+    h.set_global_config(&global_config_with(None));
+
+    let result = h
+        .send(propose_config_update_ix(&authority, 120, 0), &[])
+        .await;
+    assert_error(result, ErrorCode::InvalidUpdateDelay);
+}
+
+#[tokio::test]
+async fn propose_config_update_rejects_other_authority() {
+    let mut h = Harness::new().await;
+    let authority = h.payer().pubkey();
+    h.set_upgrade_authority(&Pubkey::new_unique());
+    h.set_global_config(&global_config_with(None));
+
+    let result = h
+        .send(propose_config_update_ix(&authority, 120, 50), &[])
+        .await;
+    assert_error(result, ErrorCode::InvalidAuthority);
+}
+
+#[tokio::test]
+async fn apply_config_update_rejects_change_before_delay() {
+    let mut h = Harness::new().await;
+    let authority = h.payer().pubkey();
+    let now = h.now().await;
+    h.set_upgrade_authority(&authority);
+    h.set_admin_audit_log(0);
+    h.set_global_config(&global_config_with(Some(sentinel::ConfigUpdate {
+        max_staleness_secs: 120,
+        update_delay_secs: 50,
+        apply_after: now + 100,
+    })));
+
+    let result = h.send(apply_config_update_ix(&authority), &[]).await;
+    assert_error(result, ErrorCode::ConfigUpdateNotReady);
+}
+
+#[tokio::test]
+async fn apply_config_update_requires_proposal() {
+    let mut h = Harness::new().await;
+    let authority = h.payer().pubkey();
+    h.set_upgrade_authority(&authority);
+    h.set_admin_audit_log(0);
+    h.set_global_config(&global_config_with(None));
+
+    let result = h.send(apply_config_update_ix(&authority), &[]).await;
+    assert_error(result, ErrorCode::NoPendingConfigUpdate);
+}
+
+#[tokio::test]
+async fn apply_config_update_applies_due_change() {
+    let mut h = Harness::new().await;
+    let authority = h.payer().pubkey();
+    let now = h.now().await;
+    h.set_upgrade_authority(&authority);
+    h.set_admin_audit_log(0);
+    h.set_global_config(&global_config_with(Some(sentinel::ConfigUpdate {
+        max_staleness_secs: 120,
+        update_delay_secs: 50,
+        apply_after: now,
+    })));
+
+    h.send(apply_config_update_ix(&authority), &[])
+        .await
+        .unwrap();
+
+    let config = h.global_config().await.unwrap();
+    assert_eq!(
+        (config.max_staleness_secs, config.update_delay_secs),
+        (120, 50)
+    );
+    assert_eq!(config.pending_update, None);
+    let log = h.admin_audit_log().await.unwrap();
+    assert_eq!(log.total, 2);
+    assert_eq!(
+        log.entries[0].field,
+        sentinel::AdminField::DefaultMaxStaleness as u8
+    );
+    assert_eq!(
+        log.entries[1].field,
+        sentinel::AdminField::ConfigUpdateDelay as u8
+    );
+}
+
+#[tokio::test]
+#[ignore = "needs the SBF build, see tests/common"]
+async fn initialize_config_sets_admin_and_defaults() {
+    let mut h = Harness::new().await;
+    let authority = h.payer().pubkey();
+    let admin = Pubkey::new_unique();
+    h.set_upgrade_authority(&authority);
+
+    h.send(initialize_config_ix(&authority, admin), &[])
+        .await
+        .unwrap();
+
+    let config = h.global_config().await.unwrap();
+    assert_eq!(config.admin, admin);
+    assert!(!config.paused);
+    assert_eq!(
+        config.max_staleness_secs,
+        sentinel::DEFAULT_MAX_STALENESS_SECS
+    );
+    assert_eq!(config.risk_model, sentinel::RiskModel::BUILT_IN);
+}
+
+#[tokio::test]
+#[ignore = "needs the SBF build, see tests/common"]
+async fn initialize_config_rejects_other_authority() {
+    let mut h = Harness::new().await;
+    let authority = h.payer().pubkey();
+    h.set_upgrade_authority(&Pubkey::new_unique());
+
+    let result = h
+        .send(initialize_config_ix(&authority, authority), &[])
+        .await;
+    assert_error(result, ErrorCode::InvalidAuthority);
+}
+
+#[tokio::test]
+async fn set_paused_stops_queueing() {
+    let mut h = Harness::new().await;
+    let admin = h.payer().pubkey();
+    h.set_admin_audit_log(0);
+    h.set_global_config(&sentinel::GlobalConfig {
+        admin,
+        ..global_config(60)
+    });
+    h.set_position(&position(admin, 1));
+
+    h.send(set_paused_ix(&admin, true), &[]).await.unwrap();
+
+    assert!(h.global_config().await.unwrap().paused);
+    let log = h.admin_audit_log().await.unwrap();
+    assert_eq!(log.entries[0].field, sentinel::AdminField::Paused as u8);
+    let result = h.send(check_health_ix(&admin, &admin, 1, 4), &[]).await;
+    assert_error(result, ErrorCode::ProgramPaused);
+    assert_eq!(
+        h.position(&admin, 1).await.unwrap().pending_computation,
+        None
+    );
+}
+
+#[tokio::test]
+async fn set_paused_rejects_non_admin() {
+    let mut h = Harness::new().await;
+    let signer = h.payer().pubkey();
+    h.set_admin_audit_log(0);
+    h.set_global_config(&sentinel::GlobalConfig {
+        admin: Pubkey::new_unique(),
+        ..global_config(60)
+    });
+
+    let result = h.send(set_paused_ix(&signer, true), &[]).await;
+    assert_error(result, ErrorCode::InvalidAuthority);
+}
+
+#[tokio::test]
+async fn set_circuit_version_only_increases() {
+    let mut h = Harness::new().await;
+    let admin = h.payer().pubkey();
+    h.set_admin_audit_log(0);
+    h.set_global_config(&sentinel::GlobalConfig {
+        admin,
+        ..global_config(60)
+    });
+
+    h.send(set_circuit_version_ix(&admin, 2), &[])
+        .await
+        .unwrap();
+
+    assert_eq!(h.global_config().await.unwrap().circuit_version, 2);
+    let result = h.send(set_circuit_version_ix(&admin, 2), &[]).await;
+    assert_error(result, ErrorCode::InvalidCircuitVersion);
+}
+
+#[tokio::test]
+#[ignore = "needs the SBF build, see tests/common"]
+async fn check_health_records_circuit_version() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_global_config(&sentinel::GlobalConfig {
+        circuit_version: 3,
+        ..global_config(60)
+    });
+    h.set_position(&position(owner, 1));
+
+    h.send(check_health_ix(&owner, &owner, 1, 4), &[])
+        .await
+        .unwrap();
+
+    assert_eq!(
+        h.position(&owner, 1).await.unwrap().pending_circuit_version,
+        3
+    );
+}
+
+const STRICT_MODEL: sentinel::RiskModel = sentinel::RiskModel {
+    critical_buffer_bps: 1_000,
+    warning_buffer_bps: 2_000,
+    min_value_cents: 500,
+};
+
+#[tokio::test]
+async fn publish_risk_model_bumps_config_version() {
+    let mut h = Harness::new().await;
+    let authority = h.payer().pubkey();
+    h.set_upgrade_authority(&authority);
+    h.set_admin_audit_log(0);
+    h.set_global_config(&global_config(60));
+
+    h.send(publish_risk_model_ix(&authority, STRICT_MODEL), &[])
+        .await
+        .unwrap();
+
+    let config = h.global_config().await.unwrap();
+    assert_eq!(
+        (config.config_version, config.risk_model),
+        (1, STRICT_MODEL)
+    );
+    let log = h.admin_audit_log().await.unwrap();
+    assert_eq!(log.total, 1);
+    assert_eq!(log.entries[0].field, sentinel::AdminField::RiskModel as u8);
+}
+
+#[tokio::test]
+async fn publish_risk_model_rejects_inverted_buffers() {
+    let mut h = Harness::new().await;
+    let authority = h.payer().pubkey();
+    h.set_upgrade_authority(&authority);
+    h.set_admin_audit_log(0);
+    h.set_global_config(&global_config(60));
+    let model = sentinel::RiskModel {
+        critical_buffer_bps: 3_000,
+        ..STRICT_MODEL
+    };
+
+    let result = h.send(publish_risk_model_ix(&authority, model), &[]).await;
+    assert_error(result, ErrorCode::InvalidRiskModel);
+}
+
+#[tokio::test]
+async fn publish_risk_model_rejects_other_authority() {
+    let mut h = Harness::new().await;
+    let authority = h.payer().pubkey();
+    h.set_upgrade_authority(&Pubkey::new_unique());
+    h.set_admin_audit_log(0);
+    h.set_global_config(&global_config(60));
+
+    let result = h
+        .send(publish_risk_model_ix(&authority, STRICT_MODEL), &[])
+        .await;
+    assert_error(result, ErrorCode::InvalidAuthority);
+}
+
+#[tokio::test]
+async fn accept_config_version_pins_latest_model() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+    h.set_global_config(&sentinel::GlobalConfig {
+        config_version: 2,
+        risk_model: STRICT_MODEL,
+        ..global_config(60)
+    });
+
+    h.send(accept_config_version_ix(&owner, &owner, 1, 2), &[])
+        .await
+        .unwrap();
+
+    let position = h.position(&owner, 1).await.unwrap();
+    assert_eq!(
+        (position.config_version, position.risk_model),
+        (2, STRICT_MODEL)
+    );
+}
+
+#[tokio::test]
+async fn accept_config_version_rejects_stale_version() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+    h.set_global_config(&sentinel::GlobalConfig {
+        config_version: 2,
+        risk_model: STRICT_MODEL,
+        ..global_config(60)
+    });
+
+    let result = h
+        .send(accept_config_version_ix(&owner, &owner, 1, 1), &[])
+        .await;
+    assert_error(result, ErrorCode::ConfigVersionMismatch);
+    let position = h.position(&owner, 1).await.unwrap();
+    assert_eq!(position.config_version, 0);
+}
+
+// ─── Keeper Leases ───
+
+#[tokio::test]
+#[ignore = "needs the SBF build, see tests/common"]
+async fn renew_keeper_lease_creates_lease() {
+    let mut h = Harness::new().await;
+    let keeper = h.payer().pubkey();
+
+    h.send(renew_keeper_lease_ix(&keeper, 3, 1, 60), &[])
+        .await
+        .unwrap();
+
+    let lease = h.keeper_lease(&keeper, 3).await.unwrap();
+    assert_eq!((lease.shard, lease.region), (3, 1));
+}
+
+#[tokio::test]
+async fn renew_keeper_lease_extends_own_lease() {
+    let mut h = Harness::new().await;
+    let keeper = h.payer().pubkey();
+    let now = h.now().await;
+    h.set_keeper_lease(&keeper_lease(keeper, 0, 1, now + 10));
+
+    h.send(renew_keeper_lease_ix(&keeper, 0, 1, 90), &[])
+        .await
+        .unwrap();
+
+    let lease = h.keeper_lease(&keeper, 0).await.unwrap();
+    assert_eq!(lease.region, 1);
+    assert!(lease.expires_at >= now + 90);
+}
+
+#[tokio::test]
+async fn renew_keeper_lease_rejects_secondary_while_primary_holds() {
+    let mut h = Harness::new().await;
+    let keeper = h.payer().pubkey();
+    let now = h.now().await;
+    h.set_keeper_lease(&keeper_lease(keeper, 0, 0, now + 60));
+
+    let result = h.send(renew_keeper_lease_ix(&keeper, 0, 1, 90), &[]).await;
+    assert_error(result, ErrorCode::LeaseHeld);
+}
+
+#[tokio::test]
+async fn renew_keeper_lease_lets_secondary_take_expired_lease() {
+    let mut h = Harness::new().await;
+    let keeper = h.payer().pubkey();
+    let now = h.now().await;
+    h.set_keeper_lease(&keeper_lease(keeper, 0, 0, now - 1));
+
+    h.send(renew_keeper_lease_ix(&keeper, 0, 1, 90), &[])
+        .await
+        .unwrap();
+
+    assert_eq!(h.keeper_lease(&keeper, 0).await.unwrap().region, 1);
+}
+
+#[tokio::test]
+async fn renew_keeper_lease_lets_primary_reclaim() {
+    let mut h = Harness::new().await;
+    let keeper = h.payer().pubkey();
+    let now = h.now().await;
+    h.set_keeper_lease(&keeper_lease(keeper, 0, 1, now + 60));
+
+    h.send(renew_keeper_lease_ix(&keeper, 0, 0, 90), &[])
+        .await
+        .unwrap();
+
+    assert_eq!(h.keeper_lease(&keeper, 0).await.unwrap().region, 0);
+}
+
+#[tokio::test]
+async fn renew_keeper_lease_rejects_invalid_duration() {
+    let mut h = Harness::new().await;
+    let keeper = h.payer().pubkey();
+    h.set_keeper_lease(&keeper_lease(keeper, 0, 0, 0));
+
+    for duration in [0, sentinel::MAX_LEASE_SECS + 1] {
+        let result = h
+            .send(renew_keeper_lease_ix(&keeper, 0, 0, duration), &[])
+            .await;
+        assert_error(result, ErrorCode::InvalidLeaseDuration);
+    }
+}
+
+// ─── Keeper Registry ───
+
+#[tokio::test]
+#[ignore = "needs the SBF build, see tests/common"]
+async fn register_keeper_requires_minimum_bond() {
+    let mut h = Harness::new().await;
+    let keeper = h.payer().pubkey();
+    let bond = sentinel::MIN_KEEPER_BOND_LAMPORTS;
+
+    let result = h.send(register_keeper_ix(&keeper, bond - 1), &[]).await;
+    assert_error(result, ErrorCode::KeeperBondTooSmall);
+    h.send(register_keeper_ix(&keeper, bond), &[])
+        .await
+        .unwrap();
+
+    let registered = h.keeper_account(&keeper).await.unwrap();
+    assert_eq!(registered.keeper, keeper);
+    assert_eq!(registered.unbonding_at, 0);
+}
+
+#[tokio::test]
+async fn unbond_keeper_starts_unbonding_once() {
+    let mut h = Harness::new().await;
+    let keeper = h.payer().pubkey();
+    h.set_keeper_account(&keeper_account(keeper), sentinel::MIN_KEEPER_BOND_LAMPORTS);
+    let now = h.now().await;
+
+    h.send(unbond_keeper_ix(&keeper), &[]).await.unwrap();
+
+    let unbonding = h.keeper_account(&keeper).await.unwrap().unbonding_at;
+    assert!(unbonding >= now + sentinel::KEEPER_UNBONDING_SECS);
+    let result = h.send(unbond_keeper_ix(&keeper), &[]).await;
+    assert_error(result, ErrorCode::KeeperUnbonding);
+}
+
+#[tokio::test]
+async fn withdraw_keeper_bond_waits_for_unbonding() {
+    let mut h = Harness::new().await;
+    let keeper = h.payer().pubkey();
+    let now = h.now().await;
+    for unbonding_at in [0, now + 60] {
+        let registered = sentinel::KeeperAccount {
+            unbonding_at,
+            ..keeper_account(keeper)
+        };
+        h.set_keeper_account(&registered, 5);
+
+        let result = h.send(withdraw_keeper_bond_ix(&keeper), &[]).await;
+        assert_error(result, ErrorCode::KeeperBondLocked);
+    }
+}
+
+#[tokio::test]
+async fn withdraw_keeper_bond_closes_account_after_unbonding() {
+    let mut h = Harness::new().await;
+    let keeper = h.payer().pubkey();
+    h.set_keeper_account(
+        &sentinel::KeeperAccount {
+            unbonding_at: 1,
+            ..keeper_account(keeper)
+        },
+        5,
+    );
+
+    h.send(withdraw_keeper_bond_ix(&keeper), &[]).await.unwrap();
+
+    assert!(h.keeper_account(&keeper).await.is_none());
+}
+
+#[tokio::test]
+async fn slash_keeper_pays_recipient_and_records_change() {
+    let mut h = Harness::new().await;
+    let admin = h.payer().pubkey();
+    let (keeper, recipient) = (Pubkey::new_unique(), Pubkey::new_unique());
+    h.set_global_config(&sentinel::GlobalConfig {
+        admin,
+        ..global_config(60)
+    });
+    h.set_admin_audit_log(0);
+    h.set_keeper_account(&keeper_account(keeper), 3_000);
+    h.set_subscription(&subscription(recipient, 1), 0);
+    let recipient = subscription_pda(&position_pda(&recipient, 1));
+    let before = h.account(recipient).await.unwrap().lamports;
+
+    h.send(
+        slash_keeper_ix(&admin, &keeper, recipient, 1_000, [7; 32]),
+        &[],
+    )
+    .await
+    .unwrap();
+    h.send(
+        slash_keeper_ix(&admin, &keeper, recipient, 5_000, [8; 32]),
+        &[],
+    )
+    .await
+    .unwrap();
+
+    let slashed = h.keeper_account(&keeper).await.unwrap();
+    assert_eq!((slashed.slashed_lamports, slashed.slash_count), (3_000, 2));
+    assert_eq!(h.account(recipient).await.unwrap().lamports, before + 3_000);
+    let entry = h.admin_audit_log().await.unwrap().entries[1];
+    assert_eq!(entry.field, sentinel::AdminField::KeeperSlash as u8);
+}
+
+#[tokio::test]
+async fn slash_keeper_requires_admin() {
+    let mut h = Harness::new().await;
+    let stranger = h.payer().pubkey();
+    let keeper = Pubkey::new_unique();
+    h.set_global_config(&global_config(60));
+    h.set_admin_audit_log(0);
+    h.set_keeper_account(&keeper_account(keeper), 3_000);
+
+    let ix = slash_keeper_ix(&stranger, &keeper, stranger, 1_000, [7; 32]);
+    let result = h.send(ix, &[]).await;
+    assert_error(result, ErrorCode::InvalidAuthority);
+}
+
+#[tokio::test]
+async fn set_registered_keepers_only_sets_flag() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+
+    h.send(set_registered_keepers_only_ix(&owner, 1, true), &[])
+        .await
+        .unwrap();
+
+    assert!(h.position(&owner, 1).await.unwrap().registered_keepers_only);
+}
+
+/// `owner`'s position 1, restricted to registered keepers and delegated to
+/// `keeper`, with a free monitoring plan.
+fn restricted_position(h: &mut Harness, owner: Pubkey, keeper: Pubkey) {
+    h.set_position(&sentinel::PositionAccount {
+        keeper: Some(keeper),
+        registered_keepers_only: true,
+        ..position(owner, 1)
+    });
+    h.set_monitoring_plan(0);
+    h.set_subscription(&subscription(owner, 1), 0);
+}
+
+#[tokio::test]
+async fn check_health_rejects_unregistered_keeper_on_restricted_position() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    let keeper = Keypair::new();
+    restricted_position(&mut h, owner, keeper.pubkey());
+
+    let result = h
+        .send(check_health_ix(&keeper.pubkey(), &owner, 1, 1), &[&keeper])
+        .await;
+    assert_error(result, ErrorCode::UnauthorizedKeeper);
+}
+
+#[tokio::test]
+async fn check_health_rejects_unbonding_or_slashed_keeper_on_restricted_position() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    let keeper = Keypair::new();
+    restricted_position(&mut h, owner, keeper.pubkey());
+    let bond = sentinel::MIN_KEEPER_BOND_LAMPORTS;
+    for (unbonding_at, bond) in [(1, bond), (0, bond - 1)] {
+        let registered = sentinel::KeeperAccount {
+            unbonding_at,
+            ..keeper_account(keeper.pubkey())
+        };
+        h.set_keeper_account(&registered, bond);
+
+        let ix = check_health_with_keeper_account_ix(&keeper.pubkey(), &owner, 1, 1);
+        let result = h.send(ix, &[&keeper]).await;
+        assert_error(result, ErrorCode::UnauthorizedKeeper);
+    }
+}
+
+#[tokio::test]
+#[ignore = "needs the SBF build, see tests/common"]
+async fn check_health_accepts_bonded_keeper_on_restricted_position() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    let keeper = h.funded_keypair().await;
+    restricted_position(&mut h, owner, keeper.pubkey());
+    h.set_keeper_account(
+        &keeper_account(keeper.pubkey()),
+        sentinel::MIN_KEEPER_BOND_LAMPORTS,
+    );
+
+    let ix = check_health_with_keeper_account_ix(&keeper.pubkey(), &owner, 1, 4);
+    h.send(ix, &[&keeper]).await.unwrap();
+
+    assert_eq!(
+        h.position(&owner, 1).await.unwrap().pending_computation,
+        Some(4)
+    );
+}
+
+#[tokio::test]
+async fn restricted_position_takes_other_checks_from_owner_only() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    let keeper = Keypair::new();
+    restricted_position(&mut h, owner, keeper.pubkey());
+    h.set_keeper_account(
+        &keeper_account(keeper.pubkey()),
+        sentinel::MIN_KEEPER_BOND_LAMPORTS,
+    );
+    h.send(set_size_disclosure_ix(&owner, 1, true), &[])
+        .await
+        .unwrap();
+
+    let ix = reveal_size_bucket_ix(&keeper.pubkey(), &owner, 1, 1);
+    let result = h.send(ix, &[&keeper]).await;
+    assert_error(result, ErrorCode::UnauthorizedKeeper);
+}
+
+// ─── Monitoring Receipts ───
+
+#[tokio::test]
+#[ignore = "needs the SBF build, see tests/common"]
+async fn monitoring_receipt_is_minted_and_burned_once() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_global_config(&sentinel::GlobalConfig {
+        admin: owner,
+        ..global_config(60)
+    });
+    h.set_admin_audit_log(0);
+    h.set_position(&position(owner, 1));
+    h.send(init_receipt_mint_ix(&owner), &[]).await.unwrap();
+
+    h.send(mint_monitoring_receipt_ix(&owner, 1), &[])
+        .await
+        .unwrap();
+    let result = h.send(mint_monitoring_receipt_ix(&owner, 1), &[]).await;
+    assert_error(result, ErrorCode::ReceiptAlreadyMinted);
+
+    let receipt = h.account(receipt_account(&owner)).await.unwrap();
+    let receipt =
+        anchor_spl::token_interface::TokenAccount::try_deserialize(&mut receipt.data.as_slice())
+            .unwrap();
+    assert_eq!(receipt.amount, 1);
+    assert!(h.position(&owner, 1).await.unwrap().receipt_minted);
+
+    h.send(burn_monitoring_receipt_ix(&owner, 1), &[])
+        .await
+        .unwrap();
+    assert!(!h.position(&owner, 1).await.unwrap().receipt_minted);
+    h.send(deactivate_ix(&owner, 1), &[]).await.unwrap();
+}
+
+#[tokio::test]
+async fn burn_monitoring_receipt_accepts_receipt_burned_directly() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&sentinel::PositionAccount {
+        receipt_minted: true,
+        ..position(owner, 1)
+    });
+    // The owner burned the receipt with the token program
+    h.set_receipt_mint(1);
+    h.set_receipt_account(&owner, 0);
+
+    h.send(burn_monitoring_receipt_ix(&owner, 1), &[])
+        .await
+        .unwrap();
+
+    assert!(!h.position(&owner, 1).await.unwrap().receipt_minted);
+    assert_eq!(h.receipt_registry().await.unwrap().outstanding, 0);
+    h.send(deactivate_ix(&owner, 1), &[]).await.unwrap();
+}
+
+#[tokio::test]
+async fn burn_monitoring_receipt_accepts_closed_receipt_account() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&sentinel::PositionAccount {
+        receipt_minted: true,
+        ..position(owner, 1)
+    });
+    h.set_receipt_mint(1);
+
+    h.send(burn_monitoring_receipt_ix(&owner, 1), &[])
+        .await
+        .unwrap();
+
+    assert!(!h.position(&owner, 1).await.unwrap().receipt_minted);
+    h.send(close_ix(&owner, 1, false, None), &[]).await.unwrap();
+}
+
+#[tokio::test]
+async fn deactivate_position_requires_burned_receipt() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&sentinel::PositionAccount {
+        receipt_minted: true,
+        ..position(owner, 1)
+    });
+
+    let result = h.send(deactivate_ix(&owner, 1), &[]).await;
+    assert_error(result, ErrorCode::ReceiptOutstanding);
+}
+
+#[tokio::test]
+async fn close_position_requires_burned_receipt() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&sentinel::PositionAccount {
+        receipt_minted: true,
+        ..position(owner, 1)
+    });
+
+    let result = h.send(close_ix(&owner, 1, false, None), &[]).await;
+    assert_error(result, ErrorCode::ReceiptOutstanding);
+}
+
+#[tokio::test]
+async fn position_transfer_requires_burned_receipt() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&sentinel::PositionAccount {
+        receipt_minted: true,
+        ..position(owner, 1)
+    });
+
+    let ix = propose_position_transfer_ix(&owner, &owner, 1, Some(Pubkey::new_unique()));
+    let result = h.send(ix, &[]).await;
+    assert_error(result, ErrorCode::ReceiptOutstanding);
+}
+
+// ─── Ownership Transfer ───
+
+#[tokio::test]
+async fn propose_position_transfer_records_pending_owner() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    let new_owner = Pubkey::new_unique();
+    h.set_position(&position(owner, 1));
+
+    h.send(
+        propose_position_transfer_ix(&owner, &owner, 1, Some(new_owner)),
+        &[],
+    )
+    .await
+    .unwrap();
+
+    let position = h.position(&owner, 1).await.unwrap();
+    assert_eq!(position.pending_owner, Some(new_owner));
+    assert_eq!(position.owner, owner);
+}
+
+#[tokio::test]
+async fn accept_position_transfer_moves_ownership() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    let new_owner = Keypair::new();
+    h.set_position(&sentinel::PositionAccount {
+        pending_owner: Some(new_owner.pubkey()),
+        keeper: Some(Pubkey::new_unique()),
+        ..position(owner, 1)
+    });
+
+    let ix = accept_position_transfer_ix(&new_owner.pubkey(), &owner, 1, false);
+    h.send(ix, &[&new_owner]).await.unwrap();
+
+    // Same address, new owner
+    let position = h.position(&owner, 1).await.unwrap();
+    assert_eq!(position.owner, new_owner.pubkey());
+    assert_eq!(position.registrant, owner);
+    assert_eq!(position.pending_owner, None);
+    assert_eq!(position.keeper, None);
+}
+
+#[tokio::test]
+async fn accept_position_transfer_hands_over_subscription_and_bounty() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    let new_owner = Keypair::new();
+    h.set_position(&sentinel::PositionAccount {
+        pending_owner: Some(new_owner.pubkey()),
+        ..position(owner, 1)
+    });
+    h.set_subscription(&subscription(owner, 1), 0);
+    h.set_check_bounty(&check_bounty(owner, 1, 1_000), 0);
+
+    let ix = accept_position_transfer_ix(&new_owner.pubkey(), &owner, 1, true);
+    h.send(ix, &[&new_owner]).await.unwrap();
+
+    let position = position_pda(&owner, 1);
+    assert_eq!(
+        h.subscription(&position).await.unwrap().owner,
+        new_owner.pubkey()
+    );
+    assert_eq!(
+        h.check_bounty(&position).await.unwrap().owner,
+        new_owner.pubkey()
+    );
+}
+
+#[tokio::test]
+async fn accept_position_transfer_rejects_other_signer() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    let intruder = Keypair::new();
+    h.set_position(&sentinel::PositionAccount {
+        pending_owner: Some(Pubkey::new_unique()),
+        ..position(owner, 1)
+    });
+
+    let ix = accept_position_transfer_ix(&intruder.pubkey(), &owner, 1, false);
+    let result = h.send(ix, &[&intruder]).await;
+    assert_error(result, ErrorCode::NotPendingOwner);
+}
+
+#[tokio::test]
+async fn accept_position_transfer_rejects_pending_computation() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    let new_owner = Keypair::new();
+    h.set_position(&sentinel::PositionAccount {
+        pending_owner: Some(new_owner.pubkey()),
+        pending_computation: Some(3),
+        ..position(owner, 1)
+    });
+
+    let ix = accept_position_transfer_ix(&new_owner.pubkey(), &owner, 1, false);
+    let result = h.send(ix, &[&new_owner]).await;
+    assert_error(result, ErrorCode::ComputationPending);
+}
+
+#[tokio::test]
+async fn transferred_position_answers_to_new_owner() {
+    let mut h = Harness::new().await;
+    let registrant = h.payer().pubkey();
+    let new_owner = Keypair::new();
+    h.set_position(&sentinel::PositionAccount {
+        owner: new_owner.pubkey(),
+        ..position(registrant, 1)
+    });
+
+    let result = h
+        .send(check_health_ix(&registrant, &registrant, 1, 1), &[])
+        .await;
+    assert_error(result, ErrorCode::PositionOwnerMismatch);
+    let result = h.send(deactivate_ix(&registrant, 1), &[]).await;
+    assert_error(result, anchor_lang::error::ErrorCode::ConstraintHasOne);
+
+    let mut ix = deactivate_ix(&new_owner.pubkey(), 1);
+    ix.accounts[1].pubkey = position_pda(&registrant, 1);
+    h.send(ix, &[&new_owner]).await.unwrap();
+    assert!(!h.position(&registrant, 1).await.unwrap().is_active);
+}
+
+// ─── Subscriptions ───
+
+#[tokio::test]
+async fn check_health_requires_subscription_for_keeper() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    let keeper = Keypair::new();
+    h.set_position(&sentinel::PositionAccount {
+        keeper: Some(keeper.pubkey()),
+        ..position(owner, 1)
+    });
+    h.set_monitoring_plan(1_000);
+
+    let mut ix = check_health_ix(&keeper.pubkey(), &owner, 1, 1);
+    let subscription = subscription_pda(&position_pda(&owner, 1));
+    ix.accounts
+        .iter_mut()
+        .find(|meta| meta.pubkey == subscription)
+        .unwrap()
+        .pubkey = sentinel::ID;
+    let result = h.send(ix, &[&keeper]).await;
+    assert_error(result, ErrorCode::SubscriptionRequired);
+}
+
+#[tokio::test]
+async fn check_health_rejects_underfunded_subscription() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    let keeper = Keypair::new();
+    h.set_position(&sentinel::PositionAccount {
+        keeper: Some(keeper.pubkey()),
+        ..position(owner, 1)
+    });
+    h.set_monitoring_plan(1_000);
+    h.set_subscription(&subscription(owner, 1), 999);
+
+    let result = h
+        .send(check_health_ix(&keeper.pubkey(), &owner, 1, 1), &[&keeper])
+        .await;
+    assert_error(result, ErrorCode::InsufficientSubscription);
+}
+
+#[tokio::test]
+#[ignore = "needs the SBF build, see tests/common"]
+async fn check_health_pays_keeper_fee() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    let keeper = h.funded_keypair().await;
+    h.set_position(&sentinel::PositionAccount {
+        keeper: Some(keeper.pubkey()),
+        ..position(owner, 1)
+    });
+    h.set_monitoring_plan(1_000);
+    h.set_subscription(&subscription(owner, 1), 1_500);
+
+    h.send(check_health_ix(&keeper.pubkey(), &owner, 1, 4), &[&keeper])
+        .await
+        .unwrap();
+
+    // Held until the callback lands
+    let subscription = h.subscription(&position_pda(&owner, 1)).await.unwrap();
+    assert_eq!(subscription.pending_fee, 1_000);
+    assert_eq!(subscription.pending_keeper, Some(keeper.pubkey()));
+    assert_eq!(subscription.fees_paid, 0);
+}
+
+#[tokio::test]
+#[ignore = "needs the SBF build, see tests/common"]
+async fn fund_subscription_escrows_lamports() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+    let position = position_pda(&owner, 1);
+
+    h.send(fund_subscription_ix(&owner, position, 5_000), &[])
+        .await
+        .unwrap();
+
+    let subscription = h.subscription(&position).await.unwrap();
+    assert_eq!(subscription.owner, owner);
+    let account = h.account(subscription_pda(&position)).await.unwrap();
+    assert_eq!(
+        account.lamports,
+        Rent::default().minimum_balance(account.data.len()) + 5_000
+    );
+}
+
+#[tokio::test]
+async fn withdraw_subscription_keeps_rent() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+    h.set_subscription(&subscription(owner, 1), 5_000);
+
+    h.send(withdraw_subscription_ix(&owner, 1, 3_000), &[])
+        .await
+        .unwrap();
+
+    let account = h
+        .account(subscription_pda(&position_pda(&owner, 1)))
+        .await
+        .unwrap();
+    assert_eq!(
+        account.lamports,
+        Rent::default().minimum_balance(account.data.len()) + 2_000
+    );
+    let result = h
+        .send(withdraw_subscription_ix(&owner, 1, 2_001), &[])
+        .await;
+    assert_error(result, ErrorCode::InsufficientSubscription);
+}
+
+#[tokio::test]
+async fn withdraw_subscription_keeps_held_fee() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&sentinel::PositionAccount {
+        pending_computation: Some(3),
+        ..position(owner, 1)
+    });
+    h.set_subscription(
+        &sentinel::Subscription {
+            pending_fee: 1_000,
+            pending_keeper: Some(Pubkey::new_unique()),
+            ..subscription(owner, 1)
+        },
+        5_000,
+    );
+
+    let result = h
+        .send(withdraw_subscription_ix(&owner, 1, 4_001), &[])
+        .await;
+    assert_error(result, ErrorCode::InsufficientSubscription);
+    h.send(withdraw_subscription_ix(&owner, 1, 4_000), &[])
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn withdraw_subscription_releases_fee_of_cancelled_check() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+    h.set_subscription(
+        &sentinel::Subscription {
+            pending_fee: 1_000,
+            pending_keeper: Some(Pubkey::new_unique()),
+            ..subscription(owner, 1)
+        },
+        5_000,
+    );
+
+    h.send(withdraw_subscription_ix(&owner, 1, 5_000), &[])
+        .await
+        .unwrap();
+
+    assert_eq!(
+        h.subscription(&position_pda(&owner, 1))
+            .await
+            .unwrap()
+            .pending_fee,
+        0
+    );
+}
+
+#[tokio::test]
+async fn withdraw_subscription_keeps_trial_credits() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+    h.set_subscription(
+        &sentinel::Subscription {
+            trial_lamports: 2_000,
+            ..subscription(owner, 1)
+        },
+        5_000,
+    );
+
+    let result = h
+        .send(withdraw_subscription_ix(&owner, 1, 3_001), &[])
+        .await;
+    assert_error(result, ErrorCode::InsufficientSubscription);
+    h.send(withdraw_subscription_ix(&owner, 1, 3_000), &[])
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+#[ignore = "needs the SBF build, see tests/common"]
+async fn grant_trial_credits_escrows_lamports() {
+    let mut h = Harness::new().await;
+    let authority = h.payer().pubkey();
+    let owner = Pubkey::new_unique();
+    h.set_upgrade_authority(&authority);
+    h.set_position(&position(owner, 1));
+    let position = position_pda(&owner, 1);
+    let now = h.now().await;
+
+    h.send(
+        grant_trial_credits_ix(&authority, position, 4_000, 3_600),
+        &[],
+    )
+    .await
+    .unwrap();
+
+    let subscription = h.subscription(&position).await.unwrap();
+    assert_eq!(subscription.owner, owner);
+    assert_eq!(subscription.trial_lamports, 4_000);
+    assert!(subscription.trial_expires_at >= now + 3_600);
+}
+
+#[tokio::test]
+async fn sweep_expired_trial_credits_rejects_active_trial() {
+    let mut h = Harness::new().await;
+    let authority = h.payer().pubkey();
+    let owner = Pubkey::new_unique();
+    let now = h.now().await;
+    h.set_upgrade_authority(&authority);
+    h.set_position(&position(owner, 1));
+    h.set_subscription(
+        &sentinel::Subscription {
+            trial_lamports: 2_000,
+            trial_expires_at: now + 3_600,
+            ..subscription(owner, 1)
+        },
+        5_000,
+    );
+
+    let result = h
+        .send(
+            sweep_expired_trial_credits_ix(&authority, &owner, position_pda(&owner, 1)),
+            &[],
+        )
+        .await;
+    assert_error(result, ErrorCode::TrialNotExpired);
+}
+
+#[tokio::test]
+async fn sweep_expired_trial_credits_returns_trial_to_operator() {
+    let mut h = Harness::new().await;
+    let authority = h.funded_keypair().await;
+    let owner = Pubkey::new_unique();
+    let now = h.now().await;
+    h.set_upgrade_authority(&authority.pubkey());
+    h.set_position(&position(owner, 1));
+    h.set_subscription(
+        &sentinel::Subscription {
+            trial_lamports: 2_000,
+            trial_expires_at: now,
+            ..subscription(owner, 1)
+        },
+        5_000,
+    );
+    let before = h.account(authority.pubkey()).await.unwrap().lamports;
+
+    let ix = sweep_expired_trial_credits_ix(&authority.pubkey(), &owner, position_pda(&owner, 1));
+    h.send(ix, &[&authority]).await.unwrap();
+
+    let after = h.account(authority.pubkey()).await.unwrap().lamports;
+    assert_eq!(after, before + 2_000);
+    let subscription = h.subscription(&position_pda(&owner, 1)).await.unwrap();
+    assert_eq!(subscription.trial_lamports, 0);
+    let account = h
+        .account(subscription_pda(&position_pda(&owner, 1)))
+        .await
+        .unwrap();
+    assert_eq!(
+        account.lamports,
+        Rent::default().minimum_balance(account.data.len()) + 3_000
+    );
+}
+
+#[tokio::test]
+async fn sweep_expired_trial_credits_closes_orphaned_subscription() {
+    let mut h = Harness::new().await;
+    let authority = h.funded_keypair().await;
+    let owner = h.funded_keypair().await;
+    let now = h.now().await;
+    h.set_upgrade_authority(&authority.pubkey());
+    h.set_subscription(
+        &sentinel::Subscription {
+            trial_lamports: 2_000,
+            trial_expires_at: now,
+            ..subscription(owner.pubkey(), 1)
+        },
+        5_000,
+    );
+    let subscription = subscription_pda(&position_pda(&owner.pubkey(), 1));
+    let rent = Rent::default().minimum_balance(h.account(subscription).await.unwrap().data.len());
+    let operator_before = h.account(authority.pubkey()).await.unwrap().lamports;
+    let owner_before = h.account(owner.pubkey()).await.unwrap().lamports;
+
+    let position = position_pda(&owner.pubkey(), 1);
+    let ix = sweep_expired_trial_credits_ix(&authority.pubkey(), &owner.pubkey(), position);
+    h.send(ix, &[&authority]).await.unwrap();
+
+    assert!(h.account(subscription).await.is_none());
+    let operator_after = h.account(authority.pubkey()).await.unwrap().lamports;
+    assert_eq!(operator_after, operator_before + 2_000);
+    let owner_after = h.account(owner.pubkey()).await.unwrap().lamports;
+    assert_eq!(owner_after, owner_before + rent + 3_000);
+}
+
+#[tokio::test]
+async fn sweep_expired_trial_credits_rejects_other_authority() {
+    let mut h = Harness::new().await;
+    let authority = h.payer().pubkey();
+    let owner = Pubkey::new_unique();
+    h.set_upgrade_authority(&Pubkey::new_unique());
+    h.set_subscription(&subscription(owner, 1), 5_000);
+
+    let result = h
+        .send(
+            sweep_expired_trial_credits_ix(&authority, &owner, position_pda(&owner, 1)),
+            &[],
+        )
+        .await;
+    assert_error(result, ErrorCode::InvalidAuthority);
+}
+
+#[tokio::test]
+async fn withdraw_subscription_rejects_other_signer() {
+    let mut h = Harness::new().await;
+    let owner = Pubkey::new_unique();
+    let stranger = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+    h.set_subscription(&subscription(owner, 1), 5_000);
+
+    let mut ix = withdraw_subscription_ix(&stranger, 1, 1_000);
+    ix.accounts[1].pubkey = position_pda(&owner, 1);
+    ix.accounts[2].pubkey = subscription_pda(&position_pda(&owner, 1));
+    let result = h.send(ix, &[]).await;
+    assert_error(result, anchor_lang::error::ErrorCode::ConstraintHasOne);
+}
+
+// ─── Check Bounties ───
+
+#[tokio::test]
+#[ignore = "needs the SBF build, see tests/common"]
+async fn fund_check_bounty_escrows_lamports() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+    let position = position_pda(&owner, 1);
+
+    h.send(fund_check_bounty_ix(&owner, 1, 1_000, 5_000), &[])
+        .await
+        .unwrap();
+    h.send(fund_check_bounty_ix(&owner, 1, 2_000, 1_000), &[])
+        .await
+        .unwrap();
+
+    let bounty = h.check_bounty(&position).await.unwrap();
+    assert_eq!(bounty.owner, owner);
+    assert_eq!(bounty.check_bounty_lamports, 2_000);
+    assert_eq!(bounty.pending_payee, None);
+    let account = h.account(check_bounty_pda(&position)).await.unwrap();
+    assert_eq!(
+        account.lamports,
+        Rent::default().minimum_balance(account.data.len()) + 6_000
+    );
+}
+
+#[tokio::test]
+async fn fund_check_bounty_rejects_zero_bounty() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+    h.set_check_bounty(&check_bounty(owner, 1, 1_000), 0);
+
+    let result = h.send(fund_check_bounty_ix(&owner, 1, 0, 5_000), &[]).await;
+    assert_error(result, ErrorCode::InvalidCheckBounty);
+}
+
+#[tokio::test]
+async fn fund_check_bounty_rejects_other_signer() {
+    let mut h = Harness::new().await;
+    let owner = Pubkey::new_unique();
+    let stranger = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+    h.set_check_bounty(&check_bounty(owner, 1, 1_000), 0);
+
+    let mut ix = fund_check_bounty_ix(&stranger, 1, 1_000, 5_000);
+    ix.accounts[1].pubkey = position_pda(&owner, 1);
+    ix.accounts[2].pubkey = check_bounty_pda(&position_pda(&owner, 1));
+    let result = h.send(ix, &[]).await;
+    assert_error(result, anchor_lang::error::ErrorCode::ConstraintHasOne);
+}
+
+#[tokio::test]
+async fn close_check_bounty_refunds_owner() {
+    let mut h = Harness::new().await;
+    let owner = h.funded_keypair().await;
+    h.set_position(&position(owner.pubkey(), 1));
+    h.set_check_bounty(&check_bounty(owner.pubkey(), 1, 1_000), 5_000);
+    let address = check_bounty_pda(&position_pda(&owner.pubkey(), 1));
+    let escrowed = h.account(address).await.unwrap().lamports;
+    let before = h.account(owner.pubkey()).await.unwrap().lamports;
+
+    h.send(close_check_bounty_ix(&owner.pubkey(), 1), &[&owner])
+        .await
+        .unwrap();
+
+    assert!(h.account(address).await.is_none());
+    assert_eq!(
+        h.account(owner.pubkey()).await.unwrap().lamports,
+        before + escrowed
+    );
+}
+
+#[tokio::test]
+async fn close_check_bounty_rejects_pending_check() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&sentinel::PositionAccount {
+        pending_computation: Some(3),
+        ..position(owner, 1)
+    });
+    h.set_check_bounty(&check_bounty(owner, 1, 1_000), 5_000);
+
+    let result = h.send(close_check_bounty_ix(&owner, 1), &[]).await;
+    assert_error(result, ErrorCode::ComputationPending);
+}
+
+#[tokio::test]
+async fn close_position_closes_check_bounty() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+    h.set_check_bounty(&check_bounty(owner, 1, 1_000), 5_000);
+    let address = check_bounty_pda(&position_pda(&owner, 1));
+
+    let mut ix = close_ix(&owner, 1, false, None);
+    // owner, position_acc, subscription, then check_bounty
+    let meta = &mut ix.accounts[3];
+    (meta.pubkey, meta.is_writable) = (address, true);
+    h.send(ix, &[]).await.unwrap();
+
+    assert!(h.account(address).await.is_none());
+}
+
+#[tokio::test]
+#[ignore = "needs the SBF build, see tests/common"]
+async fn check_health_records_keeper_as_bounty_payee() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    let keeper = h.funded_keypair().await;
+    h.set_position(&sentinel::PositionAccount {
+        keeper: Some(keeper.pubkey()),
+        ..position(owner, 1)
+    });
+    h.set_monitoring_plan(0);
+    h.set_subscription(&subscription(owner, 1), 0);
+    h.set_check_bounty(&check_bounty(owner, 1, 1_000), 5_000);
+    let position = position_pda(&owner, 1);
+
+    let mut ix = check_health_ix(&keeper.pubkey(), &owner, 1, 4);
+    let meta = ix.accounts.last_mut().unwrap();
+    (meta.pubkey, meta.is_writable) = (check_bounty_pda(&position), true);
+    h.send(ix, &[&keeper]).await.unwrap();
+
+    let bounty = h.check_bounty(&position).await.unwrap();
+    assert_eq!(bounty.pending_payee, Some(keeper.pubkey()));
+}
+
+#[tokio::test]
+async fn aborted_check_pays_no_bounty() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    let keeper = Pubkey::new_unique();
+    h.set_position(&sentinel::PositionAccount {
+        pending_computation: Some(1),
+        ..position(owner, 1)
+    });
+    h.set_check_bounty(
+        &sentinel::CheckBounty {
+            pending_payee: Some(keeper),
+            ..check_bounty(owner, 1, 1_000)
+        },
+        5_000,
+    );
+    h.add_computation(1);
+    let address = check_bounty_pda(&position_pda(&owner, 1));
+    let escrowed = h.account(address).await.unwrap().lamports;
+
+    let computation = pda::computation_acc(CLUSTER_OFFSET, 1);
+    let ix = check_position_health_callback_with_bounty_ix(&owner, 1, 1, &keeper);
+    h.send_callback(computation, ix).await.unwrap();
+
+    let bounty = h.check_bounty(&position_pda(&owner, 1)).await.unwrap();
+    assert_eq!(bounty.pending_payee, None);
+    assert_eq!(bounty.bounties_paid, 0);
+    assert_eq!(h.account(address).await.unwrap().lamports, escrowed);
+    assert!(h.account(keeper).await.is_none());
+}
+
+#[tokio::test]
+async fn aborted_check_releases_keeper_fee() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    let keeper = Pubkey::new_unique();
+    h.set_position(&sentinel::PositionAccount {
+        pending_computation: Some(1),
+        ..position(owner, 1)
+    });
+    h.set_subscription(
+        &sentinel::Subscription {
+            pending_fee: 1_000,
+            pending_keeper: Some(keeper),
+            ..subscription(owner, 1)
+        },
+        5_000,
+    );
+    h.add_computation(1);
+    let address = subscription_pda(&position_pda(&owner, 1));
+    let escrowed = h.account(address).await.unwrap().lamports;
+
+    let computation = pda::computation_acc(CLUSTER_OFFSET, 1);
+    let ix = check_position_health_callback_with_fee_ix(&owner, 1, 1, &keeper);
+    h.send_callback(computation, ix).await.unwrap();
+
+    let subscription = h.subscription(&position_pda(&owner, 1)).await.unwrap();
+    assert_eq!(
+        (subscription.pending_fee, subscription.pending_keeper),
+        (0, None)
+    );
+    assert_eq!(subscription.fees_paid, 0);
+    assert_eq!(h.account(address).await.unwrap().lamports, escrowed);
+    assert!(h.account(keeper).await.is_none());
+}
+
+// ─── Heartbeat Deadline ───
+
+#[tokio::test]
+async fn set_heartbeat_deadline_updates_position_and_clears_lapse() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&sentinel::PositionAccount {
+        monitoring_lapsed: true,
+        ..position(owner, 1)
+    });
+
+    h.send(set_heartbeat_deadline_ix(&owner, 1, 3_600), &[])
+        .await
+        .unwrap();
+    let position = h.position(&owner, 1).await.unwrap();
+    assert_eq!(position.heartbeat_deadline_secs, 3_600);
+    assert!(position.monitoring_lapsed);
+
+    h.send(set_heartbeat_deadline_ix(&owner, 1, 0), &[])
+        .await
+        .unwrap();
+    assert!(!h.position(&owner, 1).await.unwrap().monitoring_lapsed);
+}
+
+/// `owner`'s position 1 with a 60 second heartbeat deadline, last checked
+/// `age` seconds ago.
+async fn heartbeat_position(h: &mut Harness, owner: Pubkey, age: i64) {
+    let now = h.now().await;
+    h.set_position(&sentinel::PositionAccount {
+        heartbeat_deadline_secs: 60,
+        last_check: now - age,
+        ..position(owner, 1)
+    });
+}
+
+#[tokio::test]
+async fn flag_stale_position_flags_missed_deadline_once() {
+    let mut h = Harness::new().await;
+    let owner = Pubkey::new_unique();
+    let caller = h.payer().pubkey();
+    heartbeat_position(&mut h, owner, 61).await;
+
+    h.send(flag_stale_position_ix(&caller, &owner, 1, false), &[])
+        .await
+        .unwrap();
+
+    assert!(h.position(&owner, 1).await.unwrap().monitoring_lapsed);
+    let result = h
+        .send(flag_stale_position_ix(&caller, &owner, 1, false), &[])
+        .await;
+    assert_error(result, ErrorCode::MonitoringLapseFlagged);
+}
+
+#[tokio::test]
+async fn flag_stale_position_rejects_position_within_deadline() {
+    let mut h = Harness::new().await;
+    let owner = Pubkey::new_unique();
+    let caller = h.payer().pubkey();
+    heartbeat_position(&mut h, owner, 30).await;
+
+    let result = h
+        .send(flag_stale_position_ix(&caller, &owner, 1, false), &[])
+        .await;
+    assert_error(result, ErrorCode::HeartbeatDeadlineNotMissed);
+}
+
+#[tokio::test]
+async fn flag_stale_position_rejects_position_without_deadline() {
+    let mut h = Harness::new().await;
+    let owner = Pubkey::new_unique();
+    let caller = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+
+    let result = h
+        .send(flag_stale_position_ix(&caller, &owner, 1, false), &[])
+        .await;
+    assert_error(result, ErrorCode::HeartbeatDeadlineNotMissed);
+}
+
+#[tokio::test]
+async fn heartbeat_records_keeper_and_holds_off_flag() {
+    let mut h = Harness::new().await;
+    let owner = Pubkey::new_unique();
+    let keeper = h.payer().pubkey();
+    let now = h.now().await;
+    h.set_position(&sentinel::PositionAccount {
+        keeper: Some(keeper),
+        heartbeat_deadline_secs: 60,
+        last_check: now - 61,
+        monitoring_lapsed: true,
+        ..position(owner, 1)
+    });
+
+    h.send(heartbeat_ix(&keeper, &owner, 1), &[]).await.unwrap();
+
+    let position = h.position(&owner, 1).await.unwrap();
+    assert!(position.last_seen >= now);
+    assert_eq!(position.last_check, now - 61);
+    assert!(!position.monitoring_lapsed);
+    let result = h
+        .send(flag_stale_position_ix(&keeper, &owner, 1, false), &[])
+        .await;
+    assert_error(result, ErrorCode::HeartbeatDeadlineNotMissed);
+}
+
+#[tokio::test]
+async fn heartbeat_rejects_undelegated_signer() {
+    let mut h = Harness::new().await;
+    let owner = Pubkey::new_unique();
+    let stranger = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+
+    let result = h.send(heartbeat_ix(&stranger, &owner, 1), &[]).await;
+    assert_error(result, ErrorCode::UnauthorizedKeeper);
+}
+
+#[tokio::test]
+async fn flag_stale_position_pays_caller_from_check_bounty() {
+    let mut h = Harness::new().await;
+    let owner = Pubkey::new_unique();
+    let caller = h.funded_keypair().await;
+    heartbeat_position(&mut h, owner, 61).await;
+    h.set_check_bounty(&check_bounty(owner, 1, 1_000), 5_000);
+    let before = h.account(caller.pubkey()).await.unwrap().lamports;
+
+    let ix = flag_stale_position_ix(&caller.pubkey(), &owner, 1, true);
+    h.send(ix, &[&caller]).await.unwrap();
+
+    assert_eq!(
+        h.account(caller.pubkey()).await.unwrap().lamports,
+        before + 1_000
+    );
+    let escrow = h
+        .account(check_bounty_pda(&position_pda(&owner, 1)))
+        .await
+        .unwrap();
+    assert_eq!(
+        escrow.lamports,
+        Rent::default().minimum_balance(escrow.data.len()) + 4_000
+    );
+}
+
+// ─── Coverage Queries ───
+
+#[tokio::test]
+#[ignore = "needs the SBF build, see tests/common"]
+async fn is_monitored_answers_for_recently_checked_position() {
+    let mut h = Harness::new().await;
+    let owner = Pubkey::new_unique();
+    let now = h.now().await;
+    h.set_position(&sentinel::PositionAccount {
+        last_check: now - 600,
+        ..position(owner, 1)
+    });
+
+    assert_eq!(
+        h.return_data(is_monitored_ix(&owner, 1, 3_600)).await,
+        Some(vec![1])
+    );
+    assert_eq!(
+        h.return_data(is_monitored_ix(&owner, 1, 300)).await,
+        Some(vec![0])
+    );
+}
+
+#[tokio::test]
+#[ignore = "needs the SBF build, see tests/common"]
+async fn is_monitored_is_false_for_inactive_lapsed_or_other_owner() {
+    let mut h = Harness::new().await;
+    let owner = Pubkey::new_unique();
+    let now = h.now().await;
+    let checked = sentinel::PositionAccount {
+        last_check: now,
+        ..position(owner, 1)
+    };
+
+    h.set_position(&sentinel::PositionAccount {
+        is_active: false,
+        ..checked.clone()
+    });
+    assert_eq!(
+        h.return_data(is_monitored_ix(&owner, 1, 3_600)).await,
+        Some(vec![0])
+    );
+
+    h.set_position(&sentinel::PositionAccount {
+        monitoring_lapsed: true,
+        ..checked.clone()
+    });
+    assert_eq!(
+        h.return_data(is_monitored_ix(&owner, 1, 3_600)).await,
+        Some(vec![0])
+    );
+
+    h.set_position(&sentinel::PositionAccount {
+        owner: Pubkey::new_unique(),
+        ..checked
+    });
+    assert_eq!(
+        h.return_data(is_monitored_ix(&owner, 1, 3_600)).await,
+        Some(vec![0])
+    );
+}
+
+#[tokio::test]
+async fn is_monitored_rejects_account_other_than_position() {
+    let mut h = Harness::new().await;
+    let owner = Pubkey::new_unique();
+    h.set_position(&position(owner, 1));
+    h.set_position(&position(owner, 2));
+
+    let mut ix = is_monitored_ix(&owner, 1, 3_600);
+    ix.accounts[0].pubkey = position_pda(&owner, 2);
+    let result = h.send(ix, &[]).await;
+    assert_error(result, anchor_lang::error::ErrorCode::ConstraintSeeds);
+}
+
+// ─── Auto Actions ───
+
+#[tokio::test]
+#[ignore = "needs the SBF build, see tests/common"]
+async fn set_auto_action_creates_config() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+    let target = Pubkey::new_unique();
+
+    h.send(
+        set_auto_action_ix(&owner, 1, target, vec![], vec![1, 2]),
+        &[],
+    )
+    .await
+    .unwrap();
+
+    let config = h.auto_action(&position_pda(&owner, 1)).await.unwrap();
+    assert_eq!(config.program_id, target);
+    assert_eq!(config.data, vec![1, 2]);
+}
+
+#[tokio::test]
+async fn set_auto_action_replaces_template() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+    h.set_auto_action(&auto_action(owner, 1, Pubkey::new_unique()));
+    let target = Pubkey::new_unique();
+    let position = position_pda(&owner, 1);
+    let accounts = vec![
+        sentinel::AutoActionAccount {
+            pubkey: auto_action_pda(&position),
+            is_signer: true,
+            is_writable: false,
+        },
+        sentinel::AutoActionAccount {
+            pubkey: Pubkey::new_unique(),
+            is_signer: false,
+            is_writable: true,
+        },
+    ];
+
+    let ix = set_auto_action_ix(&owner, 1, target, accounts.clone(), vec![7; 64]);
+    h.send(ix, &[]).await.unwrap();
+
+    let config = h.auto_action(&position).await.unwrap();
+    assert_eq!(config.program_id, target);
+    assert_eq!(config.accounts, accounts);
+    assert_eq!(config.data, vec![7; 64]);
+}
+
+#[tokio::test]
+async fn set_auto_action_rejects_invalid_template() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+    h.set_auto_action(&auto_action(owner, 1, Pubkey::new_unique()));
+    let target = Pubkey::new_unique();
+    let other_signer = sentinel::AutoActionAccount {
+        pubkey: Pubkey::new_unique(),
+        is_signer: true,
+        is_writable: false,
+    };
+
+    for ix in [
+        set_auto_action_ix(&owner, 1, sentinel::ID, vec![], vec![]),
+        set_auto_action_ix(&owner, 1, target, vec![other_signer], vec![]),
+        set_auto_action_ix(
+            &owner,
+            1,
+            target,
+            vec![],
+            vec![0; sentinel::MAX_AUTO_ACTION_DATA + 1],
+        ),
+    ] {
+        let result = h.send(ix, &[]).await;
+        assert_error(result, ErrorCode::InvalidAutoAction);
+    }
+}
+
+#[tokio::test]
+async fn clear_auto_action_closes_config() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+    h.set_auto_action(&auto_action(owner, 1, Pubkey::new_unique()));
+
+    h.send(clear_auto_action_ix(&owner, 1), &[]).await.unwrap();
+
+    assert!(h.auto_action(&position_pda(&owner, 1)).await.is_none());
+}
+
+#[tokio::test]
+async fn clear_auto_action_rejects_other_signer() {
+    let mut h = Harness::new().await;
+    let owner = Pubkey::new_unique();
+    let stranger = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+    h.set_auto_action(&auto_action(owner, 1, Pubkey::new_unique()));
+
+    let mut ix = clear_auto_action_ix(&stranger, 1);
+    ix.accounts[1].pubkey = position_pda(&owner, 1);
+    ix.accounts[2].pubkey = auto_action_pda(&position_pda(&owner, 1));
+    let result = h.send(ix, &[]).await;
+    assert_error(result, anchor_lang::error::ErrorCode::ConstraintHasOne);
+}
+
+#[tokio::test]
+async fn execute_auto_action_rejects_action_not_due() {
+    let mut h = Harness::new().await;
+    let owner = Pubkey::new_unique();
+    let action = auto_action(owner, 1, Pubkey::new_unique());
+    h.set_position(&position(owner, 1));
+    h.set_auto_action(&action);
+
+    let result = h
+        .send(execute_auto_action_ix(&owner, 1, &action), &[])
+        .await;
+    assert_error(result, ErrorCode::AutoActionNotDue);
+}
+
+#[tokio::test]
+async fn execute_auto_action_rejects_other_program() {
+    let mut h = Harness::new().await;
+    let owner = Pubkey::new_unique();
+    let action = sentinel::AutoActionConfig {
+        due_severity: 4,
+        ..auto_action(owner, 1, Pubkey::new_unique())
+    };
+    h.set_position(&position(owner, 1));
+    h.set_auto_action(&action);
+
+    let other = sentinel::AutoActionConfig {
+        program_id: Pubkey::new_unique(),
+        ..action.clone()
+    };
+    let result = h.send(execute_auto_action_ix(&owner, 1, &other), &[]).await;
+    assert_error(result, ErrorCode::InvalidAutoAction);
+    assert_eq!(
+        h.auto_action(&position_pda(&owner, 1))
+            .await
+            .unwrap()
+            .due_severity,
+        4
+    );
+}
+
+#[tokio::test]
+#[ignore = "needs the SBF build, see tests/common"]
+async fn execute_auto_action_runs_due_action_once() {
+    let mut h = Harness::new().await;
+    let owner = Pubkey::new_unique();
+    let action = sentinel::AutoActionConfig {
+        due_severity: 4,
+        ..auto_action(owner, 1, mock_oracle::ID)
+    };
+    h.set_position(&position(owner, 1));
+    h.set_auto_action(&action);
+
+    h.send(execute_auto_action_ix(&owner, 1, &action), &[])
+        .await
+        .unwrap();
+
+    assert_eq!(
+        h.auto_action(&position_pda(&owner, 1))
+            .await
+            .unwrap()
+            .due_severity,
+        0
+    );
+    let result = h
+        .send(execute_auto_action_ix(&owner, 1, &action), &[])
+        .await;
+    assert_error(result, ErrorCode::AutoActionNotDue);
+}
+
+#[tokio::test]
+async fn record_value_protected_accepts_owner_and_keeper() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    let keeper = Keypair::new();
+    let mut pos = position(owner, 1);
+    pos.keeper = Some(keeper.pubkey());
+    h.set_position(&pos);
+
+    h.send(record_value_protected_ix(&owner, &owner, 1, 500), &[])
+        .await
+        .unwrap();
+    let ix = record_value_protected_ix(&keeper.pubkey(), &owner, 1, 500);
+    h.send(ix, &[&keeper]).await.unwrap();
+}
+
+#[tokio::test]
+async fn record_value_protected_rejects_stranger() {
+    let mut h = Harness::new().await;
+    let owner = Pubkey::new_unique();
+    let stranger = Keypair::new();
+    h.set_position(&position(owner, 1));
+
+    let ix = record_value_protected_ix(&stranger.pubkey(), &owner, 1, 500);
+    let result = h.send(ix, &[&stranger]).await;
+    assert_error(result, ErrorCode::UnauthorizedKeeper);
+}
+
+#[tokio::test]
+async fn record_value_protected_rejects_penalty_out_of_range() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+
+    let result = h
+        .send(record_value_protected_ix(&owner, &owner, 1, 0), &[])
+        .await;
+    assert_error(result, ErrorCode::InvalidLiquidationPenalty);
+    let result = h
+        .send(record_value_protected_ix(&owner, &owner, 1, 10_001), &[])
+        .await;
+    assert_error(result, ErrorCode::InvalidLiquidationPenalty);
+}
+
+// ─── Stop Loss ───
+
+#[tokio::test]
+async fn set_stop_loss_replaces_and_rearms_order() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+    h.set_stop_loss(&sentinel::StopLossAccount {
+        triggered_at: 1_000,
+        action: 2,
+        max_slippage_bps: 50,
+        executed_at: 1_001,
+        ..stop_loss(owner, 1)
+    });
+    let executor = Pubkey::new_unique();
+    let position = position_pda(&owner, 1);
+    let accounts = vec![sentinel::AutoActionAccount {
+        pubkey: stop_loss_pda(&position),
+        is_signer: true,
+        is_writable: false,
+    }];
+
+    let ix = set_stop_loss_ix(&owner, 1, [[9; 32]; 3], Some(executor), accounts.clone());
+    h.send(ix, &[]).await.unwrap();
+
+    let order = h.stop_loss(&position).await.unwrap();
+    assert_eq!(order.config, [[9; 32]; 3]);
+    assert_eq!(order.encryption_pubkey, [7; 32]);
+    assert_eq!(order.nonce, 2);
+    assert_eq!(order.executor, Some(executor));
+    assert_eq!(order.executor_accounts, accounts);
+    assert_eq!(order.triggered_at, 0);
+    assert_eq!(
+        (order.action, order.max_slippage_bps, order.executed_at),
+        (0, 0, 0)
+    );
+}
+
+#[tokio::test]
+async fn set_stop_loss_rejects_invalid_executor() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+    h.set_stop_loss(&stop_loss(owner, 1));
+    let account = sentinel::AutoActionAccount {
+        pubkey: Pubkey::new_unique(),
+        is_signer: false,
+        is_writable: true,
+    };
+    let other_signer = sentinel::AutoActionAccount {
+        is_signer: true,
+        ..account
+    };
+
+    for ix in [
+        set_stop_loss_ix(&owner, 1, [[9; 32]; 3], None, vec![account]),
+        set_stop_loss_ix(&owner, 1, [[9; 32]; 3], Some(sentinel::ID), vec![]),
+        set_stop_loss_ix(
+            &owner,
+            1,
+            [[9; 32]; 3],
+            Some(Pubkey::new_unique()),
+            vec![other_signer],
+        ),
+    ] {
+        let result = h.send(ix, &[]).await;
+        assert_error(result, ErrorCode::InvalidStopLossExecutor);
+    }
+}
+
+#[tokio::test]
+async fn clear_stop_loss_closes_order() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+    h.set_stop_loss(&stop_loss(owner, 1));
+
+    h.send(clear_stop_loss_ix(&owner, 1), &[]).await.unwrap();
+
+    assert!(h.stop_loss(&position_pda(&owner, 1)).await.is_none());
+}
+
+#[tokio::test]
+async fn evaluate_stop_loss_requires_computed_health_factor() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+    h.set_stop_loss(&stop_loss(owner, 1));
+
+    let result = h
+        .send(evaluate_stop_loss_ix(&owner, &owner, 1, 1), &[])
+        .await;
+    assert_error(result, ErrorCode::HealthFactorNotComputed);
+}
+
+#[tokio::test]
+async fn evaluate_stop_loss_rejects_fired_order() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    let mut pos = position(owner, 1);
+    pos.health_factor_at = 1;
+    h.set_position(&pos);
+    let mut fired = stop_loss(owner, 1);
+    fired.triggered_at = 1_000;
+    h.set_stop_loss(&fired);
+
+    let result = h
+        .send(evaluate_stop_loss_ix(&owner, &owner, 1, 1), &[])
+        .await;
+    assert_error(result, ErrorCode::StopLossAlreadyTriggered);
+}
+
+#[tokio::test]
+async fn evaluate_stop_loss_rejects_stranger() {
+    let mut h = Harness::new().await;
+    let owner = Pubkey::new_unique();
+    let stranger = h.payer().pubkey();
+    let mut pos = position(owner, 1);
+    pos.health_factor_at = 1;
+    h.set_position(&pos);
+    h.set_stop_loss(&stop_loss(owner, 1));
+
+    let result = h
+        .send(evaluate_stop_loss_ix(&stranger, &owner, 1, 1), &[])
+        .await;
+    assert_error(result, ErrorCode::UnauthorizedKeeper);
+}
+
+#[tokio::test]
+#[ignore = "needs the SBF build, see tests/common"]
+async fn evaluate_stop_loss_marks_computation_pending() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    let mut pos = position(owner, 1);
+    pos.health_factor_at = 1;
+    h.set_position(&pos);
+    h.set_stop_loss(&stop_loss(owner, 1));
+
+    h.send(evaluate_stop_loss_ix(&owner, &owner, 1, 6), &[])
+        .await
+        .unwrap();
+
+    assert_eq!(
+        h.position(&owner, 1).await.unwrap().pending_computation,
+        Some(6)
+    );
+}
+
+#[tokio::test]
+async fn execute_stop_loss_rejects_order_not_due() {
+    let mut h = Harness::new().await;
+    let owner = Pubkey::new_unique();
+    h.set_position(&position(owner, 1));
+    let executor = Some(Pubkey::new_unique());
+
+    for order in [
+        sentinel::StopLossAccount {
+            executor,
+            ..stop_loss(owner, 1)
+        },
+        sentinel::StopLossAccount {
+            triggered_at: 1_000,
+            ..stop_loss(owner, 1)
+        },
+        sentinel::StopLossAccount {
+            executor,
+            triggered_at: 1_000,
+            executed_at: 1_001,
+            ..stop_loss(owner, 1)
+        },
+    ] {
+        h.set_stop_loss(&order);
+        let result = h.send(execute_stop_loss_ix(&owner, 1, &order), &[]).await;
+        assert_error(result, ErrorCode::StopLossNotDue);
+    }
+}
+
+#[tokio::test]
+async fn execute_stop_loss_rejects_other_executor() {
+    let mut h = Harness::new().await;
+    let owner = Pubkey::new_unique();
+    h.set_position(&position(owner, 1));
+    let order = sentinel::StopLossAccount {
+        executor: Some(Pubkey::new_unique()),
+        triggered_at: 1_000,
+        ..stop_loss(owner, 1)
+    };
+    h.set_stop_loss(&order);
+
+    let other = sentinel::StopLossAccount {
+        executor: Some(Pubkey::new_unique()),
+        ..order.clone()
+    };
+    let result = h.send(execute_stop_loss_ix(&owner, 1, &other), &[]).await;
+    assert_error(result, ErrorCode::InvalidStopLossExecutor);
+    assert!(h
+        .stop_loss(&position_pda(&owner, 1))
+        .await
+        .unwrap()
+        .is_due());
+}
+
+#[tokio::test]
+#[ignore = "needs the SBF build, see tests/common"]
+async fn execute_stop_loss_sends_fired_order_once() {
+    let mut h = Harness::new().await;
+    let owner = Pubkey::new_unique();
+    h.set_position(&position(owner, 1));
+    let order = sentinel::StopLossAccount {
+        executor: Some(mock_oracle::ID),
+        triggered_at: 1_000,
+        action: 1,
+        max_slippage_bps: 50,
+        ..stop_loss(owner, 1)
+    };
+    h.set_stop_loss(&order);
+
+    h.send(execute_stop_loss_ix(&owner, 1, &order), &[])
+        .await
+        .unwrap();
+
+    assert!(!h
+        .stop_loss(&position_pda(&owner, 1))
+        .await
+        .unwrap()
+        .is_due());
+    let result = h.send(execute_stop_loss_ix(&owner, 1, &order), &[]).await;
+    assert_error(result, ErrorCode::StopLossNotDue);
+}
+
+// ─── Action Memo ───
+
+#[tokio::test]
+async fn set_action_memo_replaces_memo() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+    h.set_action_memo(&action_memo(owner, 1));
+    let memo = sentinel::EncryptedMemo {
+        encryption_key: [8; 32],
+        nonce: 9,
+        ciphertexts: vec![[1; 32]; 3],
+    };
+
+    h.send(set_action_memo_ix(&owner, 1, memo.clone()), &[])
+        .await
+        .unwrap();
+
+    let stored = h.action_memo(&position_pda(&owner, 1)).await.unwrap();
+    assert_eq!(stored.memo, memo);
+    assert_eq!(stored.owner, owner);
+}
+
+#[tokio::test]
+async fn set_action_memo_rejects_empty_or_oversized_memo() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+    h.set_action_memo(&action_memo(owner, 1));
+
+    for len in [0, sentinel::MAX_MEMO_CIPHERTEXTS + 1] {
+        let memo = sentinel::EncryptedMemo {
+            ciphertexts: vec![[1; 32]; len],
+            ..Default::default()
+        };
+        let result = h.send(set_action_memo_ix(&owner, 1, memo), &[]).await;
+        assert_error(result, ErrorCode::InvalidActionMemo);
+    }
+}
+
+#[tokio::test]
+async fn set_action_memo_rejects_pending_computation() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    let mut pos = position(owner, 1);
+    pos.pending_computation = Some(3);
+    h.set_position(&pos);
+    h.set_action_memo(&action_memo(owner, 1));
+
+    let result = h
+        .send(
+            set_action_memo_ix(&owner, 1, action_memo(owner, 1).memo),
+            &[],
+        )
+        .await;
+    assert_error(result, ErrorCode::ComputationPending);
+}
+
+#[tokio::test]
+async fn clear_action_memo_closes_memo() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+    h.set_action_memo(&action_memo(owner, 1));
+
+    h.send(clear_action_memo_ix(&owner, 1), &[]).await.unwrap();
+
+    assert!(h.action_memo(&position_pda(&owner, 1)).await.is_none());
+}
+
+#[tokio::test]
+#[ignore = "needs the SBF build, see tests/common"]
+async fn reveal_risk_to_owner_grows_legacy_inbox_for_memo() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    let address = position_pda(&owner, 1);
+    h.set_position(&position(owner, 1));
+    h.set_action_memo(&action_memo(owner, 1));
+    let inbox = legacy_reveal_inbox(&reveal_inbox(address));
+    h.ctx
+        .set_account(&reveal_inbox_pda(&address), &inbox.into());
+
+    let mut ix = reveal_risk_to_owner_ix(&owner, 1, 5);
+    ix.accounts.last_mut().unwrap().pubkey = action_memo_pda(&address);
+    h.send(ix, &[]).await.unwrap();
+
+    let account = h.account(reveal_inbox_pda(&address)).await.unwrap();
+    assert_eq!(account.data.len(), 8 + sentinel::RevealInbox::INIT_SPACE);
+}
+
+// ─── Size Buckets ───
+
+#[tokio::test]
+async fn set_size_disclosure_withdrawal_forgets_bucket() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&sentinel::PositionAccount {
+        size_disclosure_allowed: true,
+        size_bucket: Some(sentinel::SizeBucket::Over100k),
+        ..position(owner, 1)
+    });
+
+    h.send(set_size_disclosure_ix(&owner, 1, false), &[])
+        .await
+        .unwrap();
+
+    let position = h.position(&owner, 1).await.unwrap();
+    assert!(!position.size_disclosure_allowed);
+    assert_eq!(position.size_bucket, None);
+}
+
+#[tokio::test]
+async fn reveal_size_bucket_requires_consent() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+
+    let result = h
+        .send(reveal_size_bucket_ix(&owner, &owner, 1, 1), &[])
+        .await;
+    assert_error(result, ErrorCode::SizeDisclosureNotAllowed);
+}
+
+#[tokio::test]
+#[ignore = "needs the SBF build, see tests/common"]
+async fn reveal_size_bucket_marks_computation_pending() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+    h.send(set_size_disclosure_ix(&owner, 1, true), &[])
+        .await
+        .unwrap();
+
+    h.send(reveal_size_bucket_ix(&owner, &owner, 1, 5), &[])
+        .await
+        .unwrap();
+
+    assert_eq!(
+        h.position(&owner, 1).await.unwrap().pending_computation,
+        Some(5)
+    );
+}
+
+#[tokio::test]
+async fn record_value_protected_rejects_other_bucket_than_revealed() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&sentinel::PositionAccount {
+        size_disclosure_allowed: true,
+        size_bucket: Some(sentinel::SizeBucket::Under1k),
+        ..position(owner, 1)
+    });
+
+    let result = h
+        .send(record_value_protected_ix(&owner, &owner, 1, 500), &[])
+        .await;
+    assert_error(result, ErrorCode::SizeBucketMismatch);
+}
+
+// ─── Pending Computations ───
+
+#[tokio::test]
+async fn force_clear_pending_waits_for_timeout() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    let now = h.now().await;
+    h.set_position(&sentinel::PositionAccount {
+        pending_computation: Some(3),
+        pending_since: now,
+        ..position(owner, 1)
+    });
+
+    let result = h.send(force_clear_pending_ix(&owner, 1), &[]).await;
+    assert_error(result, ErrorCode::PendingNotExpired);
+}
+
+#[tokio::test]
+async fn force_clear_pending_clears_timed_out_computation() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    let now = h.now().await;
+    h.set_position(&sentinel::PositionAccount {
+        pending_computation: Some(3),
+        pending_since: now - sentinel::PENDING_TIMEOUT_SECS,
+        ..position(owner, 1)
+    });
+
+    h.send(force_clear_pending_ix(&owner, 1), &[])
+        .await
+        .unwrap();
+
+    assert_eq!(
+        h.position(&owner, 1).await.unwrap().pending_computation,
+        None
+    );
+}
+
+/// `owner`'s position 1 with computation 3 pending for `age` seconds,
+/// checkable by the harness payer as its keeper.
+async fn pending_position(h: &mut Harness, owner: Pubkey, age: i64) {
+    let now = h.now().await;
+    h.set_position(&sentinel::PositionAccount {
+        keeper: Some(h.payer().pubkey()),
+        pending_computation: Some(3),
+        pending_since: now - age,
+        ..position(owner, 1)
+    });
+}
+
+#[tokio::test]
+async fn cancel_computation_clears_expired_queued_computation() {
+    let mut h = Harness::new().await;
+    let owner = Pubkey::new_unique();
+    let keeper = h.payer().pubkey();
+    pending_position(&mut h, owner, sentinel::PENDING_TIMEOUT_SECS).await;
+    h.add_computation(3);
+
+    h.send(cancel_computation_ix(&keeper, &owner, 1, 3, false), &[])
+        .await
+        .unwrap();
+
+    assert_eq!(
+        h.position(&owner, 1).await.unwrap().pending_computation,
+        None
+    );
+}
+
+#[tokio::test]
+async fn cancel_computation_releases_held_fee() {
+    let mut h = Harness::new().await;
+    let owner = Pubkey::new_unique();
+    let keeper = h.payer().pubkey();
+    pending_position(&mut h, owner, sentinel::PENDING_TIMEOUT_SECS).await;
+    h.add_computation(3);
+    h.set_subscription(
+        &sentinel::Subscription {
+            pending_fee: 1_000,
+            pending_referral_fee: 250,
+            pending_keeper: Some(keeper),
+            ..subscription(owner, 1)
+        },
+        5_000,
+    );
+
+    h.send(cancel_computation_ix(&keeper, &owner, 1, 3, true), &[])
+        .await
+        .unwrap();
+
+    let subscription = h.subscription(&position_pda(&owner, 1)).await.unwrap();
+    assert_eq!(
+        (subscription.pending_fee, subscription.pending_referral_fee),
+        (0, 0)
+    );
+    assert_eq!(subscription.pending_keeper, None);
+    assert_eq!(subscription.fees_paid, 0);
+    let account = h
+        .account(subscription_pda(&position_pda(&owner, 1)))
+        .await
+        .unwrap();
+    assert_eq!(
+        account.lamports,
+        Rent::default().minimum_balance(account.data.len()) + 5_000
+    );
+}
+
+#[tokio::test]
+async fn cancel_computation_clears_reclaimed_computation() {
+    let mut h = Harness::new().await;
+    let owner = Pubkey::new_unique();
+    let keeper = h.payer().pubkey();
+    pending_position(&mut h, owner, sentinel::PENDING_TIMEOUT_SECS).await;
+
+    h.send(cancel_computation_ix(&keeper, &owner, 1, 3, false), &[])
+        .await
+        .unwrap();
+
+    assert_eq!(
+        h.position(&owner, 1).await.unwrap().pending_computation,
+        None
+    );
+}
+
+#[tokio::test]
+async fn cancel_computation_waits_for_timeout() {
+    let mut h = Harness::new().await;
+    let owner = Pubkey::new_unique();
+    let keeper = h.payer().pubkey();
+    pending_position(&mut h, owner, 0).await;
+    h.add_computation(3);
+
+    let result = h
+        .send(cancel_computation_ix(&keeper, &owner, 1, 3, false), &[])
+        .await;
+    assert_error(result, ErrorCode::PendingNotExpired);
+}
+
+#[tokio::test]
+async fn cancel_computation_rejects_finalized_computation() {
+    let mut h = Harness::new().await;
+    let owner = Pubkey::new_unique();
+    let keeper = h.payer().pubkey();
+    pending_position(&mut h, owner, sentinel::PENDING_TIMEOUT_SECS).await;
+    h.add_finalized_computation(3);
+
+    let result = h
+        .send(cancel_computation_ix(&keeper, &owner, 1, 3, false), &[])
+        .await;
+    assert_error(result, ErrorCode::ComputationFinalized);
+    assert_eq!(
+        h.position(&owner, 1).await.unwrap().pending_computation,
+        Some(3)
+    );
+}
+
+#[tokio::test]
+async fn cancel_computation_rejects_other_computation_account() {
+    let mut h = Harness::new().await;
+    let owner = Pubkey::new_unique();
+    let keeper = h.payer().pubkey();
+    pending_position(&mut h, owner, sentinel::PENDING_TIMEOUT_SECS).await;
+    h.add_computation(4);
+
+    let result = h
+        .send(cancel_computation_ix(&keeper, &owner, 1, 4, false), &[])
+        .await;
+    assert_error(result, ErrorCode::InvalidComputationAccount);
+}
+
+#[tokio::test]
+async fn cancel_computation_rejects_undelegated_signer() {
+    let mut h = Harness::new().await;
+    let owner = Pubkey::new_unique();
+    let stranger = h.funded_keypair().await;
+    pending_position(&mut h, owner, sentinel::PENDING_TIMEOUT_SECS).await;
+
+    let ix = cancel_computation_ix(&stranger.pubkey(), &owner, 1, 3, false);
+    let result = h.send(ix, &[&stranger]).await;
+    assert_error(result, ErrorCode::UnauthorizedKeeper);
+}
+
+// ─── Owner Isolation ───
+
+#[tokio::test]
+async fn positions_with_same_id_are_independent() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    let other = Pubkey::new_unique();
+    h.set_position(&position(owner, 1));
+    h.set_position(&position(other, 1));
+
+    h.send(deactivate_ix(&owner, 1), &[]).await.unwrap();
+
+    assert!(!h.position(&owner, 1).await.unwrap().is_active);
+    assert!(h.position(&other, 1).await.unwrap().is_active);
+}
+
+#[tokio::test]
+async fn check_health_rejects_other_owners_position() {
+    let mut h = Harness::new().await;
+    let owner = Pubkey::new_unique();
+    let intruder = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+    h.set_position(&position(intruder, 1));
+    h.set_monitoring_plan(0);
+    h.set_subscription(&subscription(owner, 1), 0);
+
+    // Consistent owner and position, but the payer is neither owner nor keeper
+    let result = h.send(check_health_ix(&intruder, &owner, 1, 1), &[]).await;
+    assert_error(result, ErrorCode::UnauthorizedKeeper);
+}
+
+#[tokio::test]
+async fn check_health_rejects_owner_of_same_id() {
+    let mut h = Harness::new().await;
+    let owner = Pubkey::new_unique();
+    let intruder = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+    h.set_position(&position(intruder, 1));
+
+    // The other owner's account against the payer's own position
+    let mut ix = check_health_ix(&intruder, &intruder, 1, 1);
+    let owner_meta = ix
+        .accounts
+        .iter_mut()
+        .find(|meta| meta.pubkey == intruder && !meta.is_signer)
+        .unwrap();
+    owner_meta.pubkey = owner;
+    let result = h.send(ix, &[]).await;
+    assert_error(result, ErrorCode::PositionOwnerMismatch);
+}
+
+#[tokio::test]
+async fn reveal_risk_rejects_never_checked_position() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+
+    let result = h.send(reveal_risk_ix(&owner, 1, 1), &[]).await;
+    assert_error(result, ErrorCode::StaleRiskState);
+}
+
+#[tokio::test]
+async fn reveal_risk_rejects_state_older_than_global_default() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    let now = h.now().await;
+    h.set_default_max_staleness(60);
+    h.set_position(&sentinel::PositionAccount {
+        last_check: now - 120,
+        ..position(owner, 1)
+    });
+
+    let result = h.send(reveal_risk_ix(&owner, 1, 1), &[]).await;
+    assert_error(result, ErrorCode::StaleRiskState);
+}
+
+#[tokio::test]
+async fn reveal_risk_prefers_position_staleness_override() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    let now = h.now().await;
+    h.set_position(&sentinel::PositionAccount {
+        last_check: now - 120,
+        max_staleness_secs: Some(60),
+        ..position(owner, 1)
+    });
+
+    let result = h.send(reveal_risk_ix(&owner, 1, 1), &[]).await;
+    assert_error(result, ErrorCode::StaleRiskState);
+}
+
+#[tokio::test]
+#[ignore = "needs the SBF build, see tests/common"]
+async fn reveal_risk_reveals_fresh_state() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    let now = h.now().await;
+    h.set_default_max_staleness(60);
+    h.set_position(&sentinel::PositionAccount {
+        last_check: now - 30,
+        ..position(owner, 1)
+    });
+
+    h.send(reveal_risk_ix(&owner, 1, 5), &[]).await.unwrap();
+
+    assert_eq!(
+        h.position(&owner, 1).await.unwrap().pending_computation,
+        Some(5)
+    );
+}
+
+#[tokio::test]
+async fn reveal_risk_rejects_other_owners_position() {
+    let mut h = Harness::new().await;
+    let owner = Pubkey::new_unique();
+    let intruder = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+    h.set_position(&position(intruder, 1));
+
+    let mut ix = reveal_risk_ix(&intruder, 1, 1);
+    let position_meta = ix
+        .accounts
+        .iter_mut()
+        .find(|meta| meta.pubkey == position_pda(&intruder, 1))
+        .unwrap();
+    position_meta.pubkey = position_pda(&owner, 1);
+
+    let result = h.send(ix, &[]).await;
+    assert_error(result, ErrorCode::InvalidAuthority);
+}
+
+#[tokio::test]
+async fn close_position_rejects_other_owners_position() {
+    let mut h = Harness::new().await;
+    let owner = Pubkey::new_unique();
+    let intruder = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+    h.set_position(&position(intruder, 1));
+
+    let mut ix = close_ix(&intruder, 1, false, None);
+    ix.accounts[1].pubkey = position_pda(&owner, 1);
+
+    let result = h.send(ix, &[]).await;
+    assert_error(result, anchor_lang::error::ErrorCode::ConstraintHasOne);
+    assert!(h.position(&owner, 1).await.is_some());
+}
+
+// ─── Callbacks ───
+
+#[tokio::test]
+async fn callback_rejects_computation_not_pending() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+    h.add_computation(1);
+
+    let computation = pda::computation_acc(CLUSTER_OFFSET, 1);
+    let result = h
+        .send_callback(computation, check_position_health_callback_ix(&owner, 1, 1))
+        .await;
+    assert_error(result, ErrorCode::StaleCallback);
+}
+
+#[tokio::test]
+async fn callback_rejects_superseded_computation() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&sentinel::PositionAccount {
+        pending_computation: Some(2),
+        ..position(owner, 1)
+    });
+    h.add_computation(1);
+
+    let computation = pda::computation_acc(CLUSTER_OFFSET, 1);
+    let result = h
+        .send_callback(computation, check_position_health_callback_ix(&owner, 1, 1))
+        .await;
+    assert_error(result, ErrorCode::StaleCallback);
+}
+
+#[tokio::test]
+async fn callback_rejects_other_completed_computation() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&sentinel::PositionAccount {
+        pending_computation: Some(1),
+        ..position(owner, 1)
+    });
+    h.add_computation(1);
+
+    let other = pda::computation_acc(CLUSTER_OFFSET, 7);
+    let result = h
+        .send_callback(other, check_position_health_callback_ix(&owner, 1, 1))
+        .await;
+    assert_error(result, ErrorCode::CallbackComputationMismatch);
+}
+
+#[tokio::test]
+async fn aborted_callback_frees_position_and_records_failure() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&sentinel::PositionAccount {
+        pending_computation: Some(1),
+        ..position(owner, 1)
+    });
+    h.add_computation(1);
+
+    let computation = pda::computation_acc(CLUSTER_OFFSET, 1);
+    h.send_callback(computation, check_position_health_callback_ix(&owner, 1, 1))
+        .await
+        .unwrap();
+
+    let position = h.position(&owner, 1).await.unwrap();
+    assert_eq!(position.pending_computation, None);
+    let failure = position.last_error.unwrap();
+    assert_eq!(failure.kind, sentinel::ComputationKind::CheckHealth);
+    assert_eq!(failure.computation_offset, 1);
+}
+
+#[tokio::test]
+async fn batch_callback_rejects_position_waiting_on_other_computation() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&sentinel::PositionAccount {
+        pending_computation: Some(1),
+        ..position(owner, 1)
+    });
+    h.set_position(&sentinel::PositionAccount {
+        pending_computation: Some(2),
+        ..position(owner, 2)
+    });
+    h.add_computation(1);
+
+    let computation = pda::computation_acc(CLUSTER_OFFSET, 1);
+    let positions = [position_pda(&owner, 1), position_pda(&owner, 2)];
+    let result = h
+        .send_callback(
+            computation,
+            check_position_health_batch_callback_ix(&positions, 1),
+        )
+        .await;
+    assert_error(result, ErrorCode::StaleCallback);
+}
+
+#[tokio::test]
+async fn aborted_batch_callback_frees_every_position() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    for id in 1..=2 {
+        h.set_position(&sentinel::PositionAccount {
+            pending_computation: Some(1),
+            ..position(owner, id)
+        });
+    }
+    h.add_computation(1);
+
+    let computation = pda::computation_acc(CLUSTER_OFFSET, 1);
+    let positions = [position_pda(&owner, 1), position_pda(&owner, 2)];
+    let result = h
+        .send_callback(
+            computation,
+            check_position_health_batch_callback_ix(&positions, 1),
+        )
+        .await;
+    result.unwrap();
+
+    for id in 1..=2 {
+        let position = h.position(&owner, id).await.unwrap();
+        assert_eq!(position.pending_computation, None);
+        assert_eq!(
+            position.last_error.map(|failure| failure.kind),
+            Some(sentinel::ComputationKind::CheckHealthBatch)
+        );
+    }
+}
+
+// ─── Labels ───
+
+#[tokio::test]
+async fn set_label_stores_hash_at_fixed_offset() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+
+    h.send(set_label_ix(&owner, 1, [7; 32]), &[]).await.unwrap();
+
+    let account = h.account(position_pda(&owner, 1)).await.unwrap();
+    let offset = sentinel::PositionAccount::LABEL_HASH_OFFSET;
+    assert_eq!(account.data[offset..offset + 32], [7; 32]);
+}
+
+#[tokio::test]
+async fn set_label_rejects_other_signer() {
+    let mut h = Harness::new().await;
+    let owner = Pubkey::new_unique();
+    let intruder = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+
+    let mut ix = set_label_ix(&intruder, 1, [7; 32]);
+    ix.accounts[1].pubkey = position_pda(&owner, 1);
+    let result = h.send(ix, &[]).await;
+    assert_error(result, anchor_lang::error::ErrorCode::ConstraintHasOne);
+}
+
+// ─── Migrations ───
+
+#[tokio::test]
+async fn migrate_position_upgrades_legacy_layout() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    let address = position_pda(&owner, 1);
+    h.ctx
+        .set_account(&address, &legacy_position(&position(owner, 1)).into());
+
+    h.send(migrate_position_ix(&owner, address), &[])
+        .await
+        .unwrap();
+
+    let account = h.account(address).await.unwrap();
+    assert_eq!(
+        account.data.len(),
+        8 + sentinel::PositionAccount::INIT_SPACE
+    );
+    let migrated = h.position(&owner, 1).await.unwrap();
+    assert_eq!(migrated.version, sentinel::POSITION_VERSION);
+    assert_eq!(migrated.position_id, 1);
+    assert_eq!(migrated._reserved, [0; sentinel::POSITION_RESERVED_BYTES]);
+}
+
+#[tokio::test]
+async fn migrate_position_upgrades_baseline_layout() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    let address = position_pda(&owner, 1);
+    let mut original = position(owner, 1);
+    original.risk_state = [[1; 32], [2; 32], [3; 32]];
+    original.nonce = 7;
+    original.last_check = 1_700_000_000;
+    h.ctx
+        .set_account(&address, &baseline_position(&original).into());
+
+    h.send(migrate_position_ix(&owner, address), &[])
+        .await
+        .unwrap();
+
+    let account = h.account(address).await.unwrap();
+    assert_eq!(
+        account.data.len(),
+        8 + sentinel::PositionAccount::INIT_SPACE
+    );
+    let migrated = h.position(&owner, 1).await.unwrap();
+    assert_eq!(migrated.version, sentinel::POSITION_VERSION);
+    assert_eq!(migrated.position_id, 1);
+    assert_eq!(migrated.owner, owner);
+    assert_eq!(migrated.registrant, owner);
+    assert_eq!(migrated.risk_state, [[1; 32], [2; 32], [0; 32]]);
+    assert_eq!(migrated.nonce, 7);
+    assert_eq!(migrated.last_check, 1_700_000_000);
+    assert!(migrated.is_active);
+    assert_eq!(migrated.alert_config, [[0; 32]; 3]);
+    assert_eq!(migrated.label_hash, [0; 32]);
+    assert_eq!(migrated.min_action_severity, 1);
+    assert_eq!(migrated.risk_model, sentinel::RiskModel::BUILT_IN);
+    assert_eq!(migrated.pending_computation, None);
+    assert_eq!(migrated._reserved, [0; sentinel::POSITION_RESERVED_BYTES]);
+}
+
+#[tokio::test]
+async fn migrate_position_rejects_unknown_layout() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    let address = position_pda(&owner, 1);
+    let mut account = baseline_position(&position(owner, 1));
+    account.data.push(0);
+    h.ctx.set_account(&address, &account.into());
+
+    let result = h.send(migrate_position_ix(&owner, address), &[]).await;
+    assert_error(result, ErrorCode::InvalidPositionAccount);
+}
+
+#[tokio::test]
+async fn migrate_position_rejects_current_layout() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+
+    let result = h
+        .send(migrate_position_ix(&owner, position_pda(&owner, 1)), &[])
+        .await;
+    assert_error(result, ErrorCode::PositionUpToDate);
+}
+
+#[tokio::test]
+async fn migrate_position_rejects_other_accounts() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    let position = position_pda(&owner, 1);
+    h.set_reveal_inbox(&reveal_inbox(position));
+
+    let result = h
+        .send(
+            migrate_position_ix(&owner, reveal_inbox_pda(&position)),
+            &[],
+        )
+        .await;
+    assert_error(result, ErrorCode::InvalidPositionAccount);
+}
+
+// ─── Deactivate / Close Position ───
+
+#[tokio::test]
+async fn deactivate_position_stops_monitoring() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+
+    h.send(deactivate_ix(&owner, 1), &[]).await.unwrap();
+
+    assert!(!h.position(&owner, 1).await.unwrap().is_active);
+}
+
+#[tokio::test]
+async fn deactivate_position_rejects_other_signer() {
+    let mut h = Harness::new().await;
+    let owner = Pubkey::new_unique();
+    let intruder = Keypair::new();
+    h.set_position(&position(owner, 1));
+
+    let mut ix = deactivate_ix(&intruder.pubkey(), 1);
+    ix.accounts[1].pubkey = position_pda(&owner, 1);
+
+    let result = h.send(ix, &[&intruder]).await;
+    assert_error(result, anchor_lang::error::ErrorCode::ConstraintHasOne);
+}
+
+#[tokio::test]
+async fn close_position_refunds_rent() {
+    let mut h = Harness::new().await;
+    let owner = h.funded_keypair().await;
+    h.set_position(&position(owner.pubkey(), 1));
+    let rent = h
+        .account(position_pda(&owner.pubkey(), 1))
+        .await
+        .unwrap()
+        .lamports;
+    let before = h.account(owner.pubkey()).await.unwrap().lamports;
+
+    h.send(close_ix(&owner.pubkey(), 1, false, None), &[&owner])
+        .await
+        .unwrap();
+
+    assert!(h.account(position_pda(&owner.pubkey(), 1)).await.is_none());
+    let after = h.account(owner.pubkey()).await.unwrap().lamports;
+    assert_eq!(after, before + rent);
+}
+
+#[tokio::test]
+async fn close_position_rejects_pending_computation() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&sentinel::PositionAccount {
+        pending_computation: Some(9),
+        ..position(owner, 1)
+    });
+
+    let result = h.send(close_ix(&owner, 1, false, None), &[]).await;
+    assert_error(result, ErrorCode::ComputationPending);
+    assert!(h.position(&owner, 1).await.is_some());
+}
+
+#[tokio::test]
+async fn close_position_refunds_subscription() {
+    let mut h = Harness::new().await;
+    let owner = h.funded_keypair().await;
+    h.set_position(&position(owner.pubkey(), 1));
+    h.set_subscription(&subscription(owner.pubkey(), 1), 5_000);
+    let position_rent = h
+        .account(position_pda(&owner.pubkey(), 1))
+        .await
+        .unwrap()
+        .lamports;
+    let subscription = subscription_pda(&position_pda(&owner.pubkey(), 1));
+    let subscription_lamports = h.account(subscription).await.unwrap().lamports;
+    let before = h.account(owner.pubkey()).await.unwrap().lamports;
+
+    h.send(close_ix(&owner.pubkey(), 1, true, None), &[&owner])
+        .await
+        .unwrap();
+
+    assert!(h.account(subscription).await.is_none());
+    let after = h.account(owner.pubkey()).await.unwrap().lamports;
+    assert_eq!(after, before + position_rent + subscription_lamports);
+}
+
+#[tokio::test]
+async fn close_position_leaves_trial_credits_in_subscription() {
+    let mut h = Harness::new().await;
+    let owner = h.funded_keypair().await;
+    let now = h.now().await;
+    h.set_position(&position(owner.pubkey(), 1));
+    h.set_subscription(
+        &sentinel::Subscription {
+            trial_lamports: 2_000,
+            trial_expires_at: now + 3_600,
+            ..subscription(owner.pubkey(), 1)
+        },
+        5_000,
+    );
+    let position_rent = h
+        .account(position_pda(&owner.pubkey(), 1))
+        .await
+        .unwrap()
+        .lamports;
+    let before = h.account(owner.pubkey()).await.unwrap().lamports;
+
+    h.send(close_ix(&owner.pubkey(), 1, true, None), &[&owner])
+        .await
+        .unwrap();
+
+    let after = h.account(owner.pubkey()).await.unwrap().lamports;
+    assert_eq!(after, before + position_rent + 3_000);
+    let account = h
+        .account(subscription_pda(&position_pda(&owner.pubkey(), 1)))
+        .await
+        .unwrap();
+    assert_eq!(
+        account.lamports,
+        Rent::default().minimum_balance(account.data.len()) + 2_000
+    );
+}
+
+#[tokio::test]
+async fn close_position_removes_position_from_owner_index() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&sentinel::PositionAccount {
+        indexed: true,
+        ..position(owner, 1)
+    });
+    h.set_owner_index(owner, 0, &[2, 1, 5]);
+
+    h.send(close_ix(&owner, 1, false, Some(0)), &[])
+        .await
+        .unwrap();
+
+    assert_eq!(
+        h.owner_index(&owner, 0).await.unwrap().position_ids,
+        vec![2, 5]
+    );
+}
+
+#[tokio::test]
+async fn close_position_removes_position_from_its_index_page() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&sentinel::PositionAccount {
+        indexed: true,
+        index_page: 1,
+        ..position(owner, 1)
+    });
+    h.set_owner_index(owner, 0, &[2]);
+    h.set_owner_index(owner, 1, &[1, 5]);
+
+    let result = h.send(close_ix(&owner, 1, false, Some(0)), &[]).await;
+    assert_error(result, anchor_lang::error::ErrorCode::ConstraintSeeds);
+
+    h.send(close_ix(&owner, 1, false, Some(1)), &[])
+        .await
+        .unwrap();
+
+    assert_eq!(
+        h.owner_index(&owner, 0).await.unwrap().position_ids,
+        vec![2]
+    );
+    assert_eq!(
+        h.owner_index(&owner, 1).await.unwrap().position_ids,
+        vec![5]
+    );
+}
+
+#[tokio::test]
+async fn close_position_requires_owner_index_of_indexed_position() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&sentinel::PositionAccount {
+        indexed: true,
+        ..position(owner, 1)
+    });
+    h.set_owner_index(owner, 0, &[1]);
+
+    let result = h.send(close_ix(&owner, 1, false, None), &[]).await;
+    assert_error(result, ErrorCode::OwnerIndexMissing);
+    assert!(h.position(&owner, 1).await.is_some());
+}
+
+#[tokio::test]
+#[ignore = "needs the SBF build, see tests/common"]
+async fn archive_position_rejects_active_position() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&position(owner, 1));
+
+    let result = h
+        .send(archive_position_ix(&owner, 1, false, false), &[])
+        .await;
+    assert_error(result, ErrorCode::PositionStillActive);
+    assert!(h.position(&owner, 1).await.is_some());
+}
+
+#[tokio::test]
+#[ignore = "needs the SBF build, see tests/common"]
+async fn archive_position_rejects_pending_computation() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&sentinel::PositionAccount {
+        is_active: false,
+        pending_computation: Some(9),
+        ..position(owner, 1)
+    });
+
+    let result = h
+        .send(archive_position_ix(&owner, 1, false, false), &[])
+        .await;
+    assert_error(result, ErrorCode::ComputationPending);
+}
+
+#[tokio::test]
+#[ignore = "needs the SBF build, see tests/common"]
+async fn archive_position_keeps_verifiable_record() {
+    let mut h = Harness::new().await;
+    let owner = h.payer().pubkey();
+    h.set_position(&sentinel::PositionAccount {
+        is_active: false,
+        last_check: 1_000,
+        ..position(owner, 1)
+    });
+    let address = position_pda(&owner, 1);
+    let mut history = risk_history(address);
+    history.push([[7; 32]; 3], 5, 1_000);
+    h.set_risk_history(&history);
+    let position_data = h.account(address).await.unwrap().data;
+    let history_data = h.account(risk_history_pda(&address)).await.unwrap().data;
+
+    h.send(archive_position_ix(&owner, 1, true, false), &[])
+        .await
+        .unwrap();
+
+    assert!(h.account(address).await.is_none());
+    assert!(h.account(risk_history_pda(&address)).await.is_none());
+    let account = h.account(archive_pda(&owner, 1)).await.unwrap();
+    let record = sentinel::ArchiveRecord::try_deserialize(&mut account.data.as_slice()).unwrap();
+    assert_eq!((record.owner, record.position_id), (owner, 1));
+    assert_eq!((record.last_check, record.history_len), (1_000, 1));
+    assert!(record.verify(&position_data, Some(&history_data)));
+    assert!(!record.verify(&position_data, None));
+}
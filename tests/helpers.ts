@@ -0,0 +1,180 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { PublicKey } from "@solana/web3.js";
+import { Sentinel } from "../target/types/sentinel";
+import { MockOracle } from "../target/types/mock_oracle";
+import { createHash } from "crypto";
+import nacl from "tweetnacl";
+import {
+  getCompDefAccOffset,
+  getArciumAccountBaseSeed,
+  getArciumProgramId,
+  uploadCircuit,
+  getMXEAccAddress,
+  x25519,
+  getMXEPublicKey,
+  getLookupTableAddress,
+  getArciumProgram,
+} from "@arcium-hq/client";
+import * as fs from "fs";
+
+export const ENCRYPTION_KEY_MESSAGE = "fold-defi-encryption-key-v1";
+
+export function deriveEncryptionKey(
+  wallet: anchor.web3.Keypair,
+  message: string
+): { privateKey: Uint8Array; publicKey: Uint8Array } {
+  const messageBytes = new TextEncoder().encode(message);
+  const signature = nacl.sign.detached(messageBytes, wallet.secretKey);
+  const privateKey = new Uint8Array(
+    createHash("sha256").update(signature).digest()
+  );
+  const publicKey = x25519.getPublicKey(privateKey);
+  return { privateKey, publicKey };
+}
+
+export async function initCompDef(
+  provider: anchor.AnchorProvider,
+  program: Program<Sentinel>,
+  owner: anchor.web3.Keypair,
+  circuitName: string,
+  methodName: string
+): Promise<string> {
+  const baseSeed = getArciumAccountBaseSeed("ComputationDefinitionAccount");
+  const offset = getCompDefAccOffset(circuitName);
+  const compDefPDA = PublicKey.findProgramAddressSync(
+    [baseSeed, program.programId.toBuffer(), offset],
+    getArciumProgramId()
+  )[0];
+
+  const arciumProgram = getArciumProgram(provider);
+  const mxeAccount = getMXEAccAddress(program.programId);
+  const mxeAcc = await arciumProgram.account.mxeAccount.fetch(mxeAccount);
+  const lutAddress = getLookupTableAddress(program.programId, mxeAcc.lutOffsetSlot);
+
+  // Check if comp def already exists (for devnet re-runs)
+  const compDefInfo = await provider.connection.getAccountInfo(compDefPDA);
+  if (compDefInfo) {
+    console.log(`${circuitName} comp def already initialized, skipping`);
+  } else {
+    const sig = await (program.methods as any)
+      [methodName]()
+      .accounts({
+        compDefAccount: compDefPDA,
+        payer: owner.publicKey,
+        mxeAccount,
+        addressLookupTable: lutAddress,
+      })
+      .signers([owner])
+      .rpc({ commitment: "confirmed" });
+    console.log(`Initialized ${circuitName} comp def:`, sig);
+  }
+
+  const rawCircuit = fs.readFileSync(`build/${circuitName}.arcis`);
+  await uploadCircuit(provider, circuitName, program.programId, rawCircuit, true);
+
+  return "ok";
+}
+
+/** Initializes and uploads every computation definition the program uses. */
+export async function initAllCompDefs(
+  provider: anchor.AnchorProvider,
+  program: Program<Sentinel>,
+  owner: anchor.web3.Keypair
+): Promise<void> {
+  await initCompDef(provider, program, owner, "init_risk_state", "initRiskStateCompDef");
+  await initCompDef(provider, program, owner, "check_position_health", "initCheckHealthCompDef");
+  await initCompDef(provider, program, owner, "reveal_risk", "initRevealRiskCompDef");
+  await initCompDef(provider, program, owner, "reveal_risk_to_owner", "initRevealRiskToOwnerCompDef");
+  await initCompDef(provider, program, owner, "aggregate_portfolio_risk", "initAggregatePortfolioRiskCompDef");
+  await initCompDef(provider, program, owner, "check_position_health_with_price", "initCheckHealthWithPriceCompDef");
+  await initCompDef(provider, program, owner, "check_position_health_cross_chain", "initCheckHealthCrossChainCompDef");
+  await initCompDef(provider, program, owner, "set_alert_config", "initSetAlertConfigCompDef");
+  await initCompDef(provider, program, owner, "check_position_health_with_rules", "initCheckHealthWithRulesCompDef");
+  await initCompDef(provider, program, owner, "reveal_history_summary", "initRevealHistorySummaryCompDef");
+  await initCompDef(provider, program, owner, "check_position_health_from_protocol", "initCheckHealthFromProtocolCompDef");
+  await initCompDef(provider, program, owner, "check_position_health_batch", "initCheckHealthBatchCompDef");
+  await initCompDef(provider, program, owner, "compute_health_factor", "initComputeHealthFactorCompDef");
+  await initCompDef(provider, program, owner, "reveal_health_factor_to_owner", "initRevealHealthFactorToOwnerCompDef");
+  await initCompDef(provider, program, owner, "reveal_risk_to_viewer", "initRevealRiskToViewerCompDef");
+  await initCompDef(provider, program, owner, "stress_test_position", "initStressTestPositionCompDef");
+  await initCompDef(provider, program, owner, "attest_health", "initAttestHealthCompDef");
+  await initCompDef(provider, program, owner, "reveal_size_bucket", "initRevealSizeBucketCompDef");
+  await initCompDef(provider, program, owner, "rotate_encryption", "initRotateEncryptionCompDef");
+  await initCompDef(provider, program, owner, "evaluate_stop_loss", "initEvaluateStopLossCompDef");
+  await initCompDef(provider, program, owner, "accumulate_risk_heatmap", "initAccumulateRiskHeatmapCompDef");
+  await initCompDef(provider, program, owner, "reveal_risk_heatmap", "initRevealRiskHeatmapCompDef");
+  await initCompDef(provider, program, owner, "check_perp_health", "initCheckPerpHealthCompDef");
+  await initCompDef(provider, program, owner, "compute_risk_score", "initComputeRiskScoreCompDef");
+}
+
+/**
+ * Creates a `mock-oracle` feed owned by `authority` and publishes `price`
+ * (in units of 10^exponent) now. Point a position's `price_feed` at the
+ * returned address; the program reads it when built with `--features localnet`.
+ */
+export async function createMockPriceFeed(
+  program: Program<MockOracle>,
+  authority: anchor.web3.Keypair,
+  price: number,
+  exponent: number = -8
+): Promise<PublicKey> {
+  const feedId = Array.from(nacl.randomBytes(32));
+  const [feed] = PublicKey.findProgramAddressSync(
+    [Buffer.from("feed"), authority.publicKey.toBuffer(), Buffer.from(feedId)],
+    program.programId
+  );
+  await program.methods
+    .createFeed(feedId, exponent)
+    .accounts({ authority: authority.publicKey })
+    .signers([authority])
+    .rpc({ commitment: "confirmed" });
+  await setMockPrice(program, authority, feed, price);
+  return feed;
+}
+
+/** Publishes `price` ± 1% on a `mock-oracle` feed, at `publishTime` or now. */
+export async function setMockPrice(
+  program: Program<MockOracle>,
+  authority: anchor.web3.Keypair,
+  feed: PublicKey,
+  price: number,
+  publishTime: number | null = null
+): Promise<string> {
+  return program.methods
+    .setPrice(
+      new anchor.BN(price),
+      new anchor.BN(Math.floor(price / 100)),
+      publishTime === null ? null : new anchor.BN(publishTime)
+    )
+    .accounts({ writeAuthority: authority.publicKey, feed })
+    .signers([authority])
+    .rpc({ commitment: "confirmed" });
+}
+
+export async function getMXEPublicKeyWithRetry(
+  provider: anchor.AnchorProvider,
+  programId: PublicKey,
+  maxRetries: number = 20,
+  retryDelayMs: number = 500
+): Promise<Uint8Array> {
+  for (let attempt = 1; attempt <= maxRetries; attempt++) {
+    try {
+      const key = await getMXEPublicKey(provider, programId);
+      if (key) return key;
+    } catch (error) {
+      console.log(`Attempt ${attempt} failed:`, error);
+    }
+    if (attempt < maxRetries) {
+      await new Promise((r) => setTimeout(r, retryDelayMs));
+    }
+  }
+  throw new Error(`Failed to fetch MXE public key after ${maxRetries} attempts`);
+}
+
+export function readKpJson(path: string): anchor.web3.Keypair {
+  const file = fs.readFileSync(path);
+  return anchor.web3.Keypair.fromSecretKey(
+    new Uint8Array(JSON.parse(file.toString()))
+  );
+}
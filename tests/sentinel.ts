@@ -1,16 +1,11 @@
 import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
-import { PublicKey } from "@solana/web3.js";
 import { Sentinel } from "../target/types/sentinel";
-import { randomBytes, createHash } from "crypto";
-import nacl from "tweetnacl";
+import { randomBytes } from "crypto";
 import {
   awaitComputationFinalization,
   getArciumEnv,
   getCompDefAccOffset,
-  getArciumAccountBaseSeed,
-  getArciumProgramId,
-  uploadCircuit,
   RescueCipher,
   deserializeLE,
   getMXEAccAddress,
@@ -19,29 +14,17 @@ import {
   getExecutingPoolAccAddress,
   x25519,
   getComputationAccAddress,
-  getMXEPublicKey,
   getClusterAccAddress,
-  getLookupTableAddress,
-  getArciumProgram,
 } from "@arcium-hq/client";
-import * as fs from "fs";
 import * as os from "os";
 import { expect } from "chai";
-
-const ENCRYPTION_KEY_MESSAGE = "fold-defi-encryption-key-v1";
-
-function deriveEncryptionKey(
-  wallet: anchor.web3.Keypair,
-  message: string
-): { privateKey: Uint8Array; publicKey: Uint8Array } {
-  const messageBytes = new TextEncoder().encode(message);
-  const signature = nacl.sign.detached(messageBytes, wallet.secretKey);
-  const privateKey = new Uint8Array(
-    createHash("sha256").update(signature).digest()
-  );
-  const publicKey = x25519.getPublicKey(privateKey);
-  return { privateKey, publicKey };
-}
+import {
+  ENCRYPTION_KEY_MESSAGE,
+  deriveEncryptionKey,
+  getMXEPublicKeyWithRetry,
+  initAllCompDefs,
+  readKpJson,
+} from "./helpers";
 
 describe("Fold DeFi Security Agent", () => {
   anchor.setProvider(anchor.AnchorProvider.env());
@@ -77,9 +60,7 @@ describe("Fold DeFi Security Agent", () => {
 
     // Initialize computation definitions
     console.log("Initializing computation definitions...");
-    await initCompDef(program, owner, "init_risk_state", "initRiskStateCompDef");
-    await initCompDef(program, owner, "check_position_health", "initCheckHealthCompDef");
-    await initCompDef(program, owner, "reveal_risk", "initRevealRiskCompDef");
+    await initAllCompDefs(provider as anchor.AnchorProvider, program, owner);
     console.log("All computation definitions initialized");
 
     // Derive encryption keys
@@ -87,6 +68,24 @@ describe("Fold DeFi Security Agent", () => {
     const sharedSecret = x25519.getSharedSecret(privateKey, mxePublicKey);
     const cipher = new RescueCipher(sharedSecret);
 
+    // Positions are addressed by the registering wallet, also after a transfer
+    const [positionAddress] = anchor.web3.PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("position"),
+        owner.publicKey.toBuffer(),
+        new anchor.BN(POSITION_ID).toArrayLike(Buffer, "le", 4),
+      ],
+      program.programId
+    );
+    const [ownerIndexAddress] = anchor.web3.PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("owner_index"),
+        owner.publicKey.toBuffer(),
+        new anchor.BN(0).toArrayLike(Buffer, "le", 4),
+      ],
+      program.programId
+    );
+
     // Register position for monitoring
     console.log("Registering position...");
     const registerNonce = randomBytes(16);
@@ -96,7 +95,16 @@ describe("Fold DeFi Security Agent", () => {
       .registerPosition(
         registerOffset,
         POSITION_ID,
-        new anchor.BN(deserializeLE(registerNonce).toString())
+        new anchor.BN(deserializeLE(registerNonce).toString()),
+        null,
+        {
+          protocol: { unspecified: {} },
+          market: anchor.web3.PublicKey.default,
+          collateralMint: anchor.web3.PublicKey.default,
+          debtMint: anchor.web3.PublicKey.default,
+        },
+        Array(32).fill(0),
+        0
       )
       .accountsPartial({
         computationAccount: getComputationAccAddress(
@@ -111,6 +119,8 @@ describe("Fold DeFi Security Agent", () => {
           program.programId,
           Buffer.from(getCompDefAccOffset("init_risk_state")).readUInt32LE()
         ),
+        referralStats: null,
+        previousOwnerIndex: null,
       })
       .rpc({ skipPreflight: true, commitment: "confirmed" });
 
@@ -122,9 +132,57 @@ describe("Fold DeFi Security Agent", () => {
       "confirmed"
     );
 
+    // Widen the critical buffer to 10% (warning at 20%, dust below $1)
+    console.log("Setting encrypted alert config...");
+    const alertNonce = randomBytes(16);
+    const alertCiphertext = cipher.encrypt(
+      [BigInt(1000), BigInt(2000), BigInt(100)],
+      alertNonce
+    );
+    const alertOffset = new anchor.BN(randomBytes(8), "hex");
+    const alertEventPromise = awaitEvent("alertConfigUpdated");
+
+    await program.methods
+      .setAlertConfig(
+        alertOffset,
+        POSITION_ID,
+        [
+          Array.from(alertCiphertext[0]),
+          Array.from(alertCiphertext[1]),
+          Array.from(alertCiphertext[2]),
+        ],
+        Array.from(publicKey),
+        new anchor.BN(deserializeLE(alertNonce).toString()),
+        new anchor.BN(deserializeLE(randomBytes(16)).toString())
+      )
+      .accountsPartial({
+        positionAcc: positionAddress,
+        computationAccount: getComputationAccAddress(
+          arciumEnv.arciumClusterOffset,
+          alertOffset
+        ),
+        clusterAccount,
+        mxeAccount: getMXEAccAddress(program.programId),
+        mempoolAccount: getMempoolAccAddress(arciumEnv.arciumClusterOffset),
+        executingPool: getExecutingPoolAccAddress(arciumEnv.arciumClusterOffset),
+        compDefAccount: getCompDefAccAddress(
+          program.programId,
+          Buffer.from(getCompDefAccOffset("set_alert_config")).readUInt32LE()
+        ),
+      })
+      .rpc({ skipPreflight: true, commitment: "confirmed" });
+
+    await awaitComputationFinalization(
+      provider as anchor.AnchorProvider,
+      alertOffset,
+      program.programId,
+      "confirmed"
+    );
+    await alertEventPromise;
+
     // Simulate a health check with a risky position
     // Position: $5000, 115% collateral, 110% liquidation threshold
-    // This is within the 5% danger zone -> should be critical risk
+    // This is within the configured 10% danger zone -> should be critical risk
     console.log("Submitting encrypted health check (risky position)...");
     const positionData = [
       BigInt(500000),  // $5000 in cents
@@ -151,6 +209,7 @@ describe("Fold DeFi Security Agent", () => {
         new anchor.BN(deserializeLE(checkNonce).toString())
       )
       .accountsPartial({
+        positionAcc: positionAddress,
         computationAccount: getComputationAccAddress(
           arciumEnv.arciumClusterOffset,
           checkOffset
@@ -164,6 +223,13 @@ describe("Fold DeFi Security Agent", () => {
           Buffer.from(getCompDefAccOffset("check_position_health")).readUInt32LE()
         ),
         owner: owner.publicKey,
+        riskHistory: null,
+        monitoringPlan: null,
+        subscription: null,
+        priceUpdate: null,
+        tvlSnapshot: null,
+        checkBounty: null,
+        keeperAccount: null,
       })
       .rpc({ skipPreflight: true, commitment: "confirmed" });
 
@@ -181,10 +247,12 @@ describe("Fold DeFi Security Agent", () => {
     console.log("Revealing risk status...");
     const revealOffset = new anchor.BN(randomBytes(8), "hex");
     const riskEventPromise = awaitEvent("riskRevealed");
+    const actionEventPromise = awaitEvent("actionRequired");
 
     await program.methods
       .revealRisk(revealOffset, POSITION_ID)
       .accountsPartial({
+        positionAcc: positionAddress,
         computationAccount: getComputationAccAddress(
           arciumEnv.arciumClusterOffset,
           revealOffset
@@ -197,10 +265,11 @@ describe("Fold DeFi Security Agent", () => {
           program.programId,
           Buffer.from(getCompDefAccOffset("reveal_risk")).readUInt32LE()
         ),
+        autoAction: null,
       })
       .rpc({ skipPreflight: true, commitment: "confirmed" });
 
-    await awaitComputationFinalization(
+    const revealCallbackSig = await awaitComputationFinalization(
       provider as anchor.AnchorProvider,
       revealOffset,
       program.programId,
@@ -210,80 +279,160 @@ describe("Fold DeFi Security Agent", () => {
     const riskEvent = await riskEventPromise;
     console.log("Position at risk:", riskEvent.isAtRisk);
     expect(riskEvent.isAtRisk).to.equal(true);
-  });
 
-  async function initCompDef(
-    program: Program<Sentinel>,
-    owner: anchor.web3.Keypair,
-    circuitName: string,
-    methodName: string
-  ): Promise<string> {
-    const baseSeed = getArciumAccountBaseSeed("ComputationDefinitionAccount");
-    const offset = getCompDefAccOffset(circuitName);
-    const compDefPDA = PublicKey.findProgramAddressSync(
-      [baseSeed, program.programId.toBuffer(), offset],
-      getArciumProgramId()
-    )[0];
-
-    const arciumProgram = getArciumProgram(provider as anchor.AnchorProvider);
-    const mxeAccount = getMXEAccAddress(program.programId);
-    const mxeAcc = await arciumProgram.account.mxeAccount.fetch(mxeAccount);
-    const lutAddress = getLookupTableAddress(program.programId, mxeAcc.lutOffsetSlot);
-
-    // Check if comp def already exists (for devnet re-runs)
-    const compDefInfo = await provider.connection.getAccountInfo(compDefPDA);
-    if (compDefInfo) {
-      console.log(`${circuitName} comp def already initialized, skipping`);
-    } else {
-      const sig = await (program.methods as any)
-        [methodName]()
-        .accounts({
-          compDefAccount: compDefPDA,
-          payer: owner.publicKey,
-          mxeAccount,
-          addressLookupTable: lutAddress,
-        })
-        .signers([owner])
-        .rpc({ commitment: "confirmed" });
-      console.log(`Initialized ${circuitName} comp def:`, sig);
-    }
-
-    const rawCircuit = fs.readFileSync(`build/${circuitName}.arcis`);
-    await uploadCircuit(
+    const actionEvent = await actionEventPromise;
+    expect(actionEvent.actionType).to.deep.equal({ emergencyWithdraw: {} });
+    expect(actionEvent.severity).to.equal(3);
+    expect(actionEvent.positionId).to.equal(POSITION_ID);
+
+    // The callback also sets return data: RevealStatus { position, is_at_risk }
+    const revealTx = await provider.connection.getTransaction(revealCallbackSig, {
+      commitment: "confirmed",
+      maxSupportedTransactionVersion: 0,
+    });
+    const returnData = revealTx.meta.returnData;
+    expect(returnData.programId).to.equal(program.programId.toBase58());
+    const status = Buffer.from(returnData.data[0], "base64");
+    expect(status.length).to.equal(33);
+    expect(status[32]).to.equal(1);
+
+    // Reveal privately: the result is encrypted to the owner's x25519 key
+    console.log("Revealing risk status to owner...");
+    const privateRevealOffset = new anchor.BN(randomBytes(8), "hex");
+    const privateRevealNonce = randomBytes(16);
+    const privateRiskEventPromise = awaitEvent("riskRevealedToOwner");
+
+    await program.methods
+      .revealRiskToOwner(
+        privateRevealOffset,
+        POSITION_ID,
+        Array.from(publicKey),
+        new anchor.BN(deserializeLE(privateRevealNonce).toString())
+      )
+      .accountsPartial({
+        positionAcc: positionAddress,
+        computationAccount: getComputationAccAddress(
+          arciumEnv.arciumClusterOffset,
+          privateRevealOffset
+        ),
+        clusterAccount,
+        mxeAccount: getMXEAccAddress(program.programId),
+        mempoolAccount: getMempoolAccAddress(arciumEnv.arciumClusterOffset),
+        executingPool: getExecutingPoolAccAddress(arciumEnv.arciumClusterOffset),
+        compDefAccount: getCompDefAccAddress(
+          program.programId,
+          Buffer.from(getCompDefAccOffset("reveal_risk_to_owner")).readUInt32LE()
+        ),
+        actionMemo: null,
+      })
+      .rpc({ skipPreflight: true, commitment: "confirmed" });
+
+    await awaitComputationFinalization(
       provider as anchor.AnchorProvider,
-      circuitName,
+      privateRevealOffset,
       program.programId,
-      rawCircuit,
-      true
+      "confirmed"
     );
 
-    return "ok";
-  }
-});
+    const privateRiskEvent = await privateRiskEventPromise;
+    const envelope = privateRiskEvent.envelope;
+    expect(envelope.version).to.equal(1);
+    expect(envelope.circuitId).to.equal(
+      Buffer.from(getCompDefAccOffset("reveal_risk_to_owner")).readUInt32LE()
+    );
+    const [isAtRisk, severity] = cipher.decrypt(
+      envelope.ciphertexts,
+      new Uint8Array(envelope.nonce.toArray("le", 16))
+    );
+    console.log("Decrypted risk:", { isAtRisk, severity });
+    expect(isAtRisk).to.equal(BigInt(1));
+    expect(severity).to.equal(BigInt(3));
+
+    // The same result is waiting in the position's inbox
+    const [inboxAddress] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("reveal_inbox"), positionAddress.toBuffer()],
+      program.programId
+    );
+    const inbox = await program.account.revealInbox.fetch(inboxAddress);
+    expect(inbox.envelope.circuitId).to.equal(envelope.circuitId);
+    expect(inbox.envelope.nonce.eq(envelope.nonce)).to.be.true;
+    expect(
+      cipher.decrypt(
+        inbox.envelope.ciphertexts,
+        new Uint8Array(inbox.envelope.nonce.toArray("le", 16))
+      )
+    ).to.deep.equal([isAtRisk, severity]);
+
+    // Fold the position into a portfolio-level encrypted risk score
+    console.log("Aggregating portfolio risk...");
+    await program.methods
+      .registerPortfolio()
+      .accounts({ owner: owner.publicKey })
+      .rpc({ commitment: "confirmed" });
+    await program.methods
+      .addPortfolioPosition(POSITION_ID)
+      .accountsPartial({ owner: owner.publicKey, positionAcc: positionAddress })
+      .rpc({ commitment: "confirmed" });
+
+    const aggregateOffset = new anchor.BN(randomBytes(8), "hex");
+    const portfolioEventPromise = awaitEvent("portfolioRiskAggregated");
 
-async function getMXEPublicKeyWithRetry(
-  provider: anchor.AnchorProvider,
-  programId: PublicKey,
-  maxRetries: number = 20,
-  retryDelayMs: number = 500
-): Promise<Uint8Array> {
-  for (let attempt = 1; attempt <= maxRetries; attempt++) {
-    try {
-      const key = await getMXEPublicKey(provider, programId);
-      if (key) return key;
-    } catch (error) {
-      console.log(`Attempt ${attempt} failed:`, error);
-    }
-    if (attempt < maxRetries) {
-      await new Promise((r) => setTimeout(r, retryDelayMs));
-    }
-  }
-  throw new Error(`Failed to fetch MXE public key after ${maxRetries} attempts`);
-}
-
-function readKpJson(path: string): anchor.web3.Keypair {
-  const file = fs.readFileSync(path);
-  return anchor.web3.Keypair.fromSecretKey(
-    new Uint8Array(JSON.parse(file.toString()))
-  );
-}
+    await program.methods
+      .aggregatePortfolioRisk(
+        aggregateOffset,
+        new anchor.BN(deserializeLE(randomBytes(16)).toString())
+      )
+      .accountsPartial({
+        computationAccount: getComputationAccAddress(
+          arciumEnv.arciumClusterOffset,
+          aggregateOffset
+        ),
+        clusterAccount,
+        mxeAccount: getMXEAccAddress(program.programId),
+        mempoolAccount: getMempoolAccAddress(arciumEnv.arciumClusterOffset),
+        executingPool: getExecutingPoolAccAddress(arciumEnv.arciumClusterOffset),
+        compDefAccount: getCompDefAccAddress(
+          program.programId,
+          Buffer.from(getCompDefAccOffset("aggregate_portfolio_risk")).readUInt32LE()
+        ),
+      })
+      .remainingAccounts([
+        { pubkey: positionAddress, isSigner: false, isWritable: false },
+      ])
+      .rpc({ skipPreflight: true, commitment: "confirmed" });
+
+    await awaitComputationFinalization(
+      provider as anchor.AnchorProvider,
+      aggregateOffset,
+      program.programId,
+      "confirmed"
+    );
+    const portfolioEvent = await portfolioEventPromise;
+    expect(portfolioEvent.positions).to.equal(1);
+
+    // Stop monitoring, then reclaim the position's rent
+    console.log("Deactivating and closing position...");
+    await program.methods
+      .deactivatePosition(POSITION_ID)
+      .accountsPartial({ owner: owner.publicKey, positionAcc: positionAddress })
+      .rpc({ commitment: "confirmed" });
+
+    const deactivated = await program.account.positionAccount.fetch(positionAddress);
+    expect(deactivated.isActive).to.equal(false);
+    expect(deactivated.pendingComputation).to.equal(null);
+
+    await program.methods
+      .closePosition(POSITION_ID)
+      .accountsPartial({
+        owner: owner.publicKey,
+        positionAcc: positionAddress,
+        subscription: null,
+        checkBounty: null,
+        ownerIndex: ownerIndexAddress,
+      })
+      .rpc({ commitment: "confirmed" });
+    expect(await provider.connection.getAccountInfo(positionAddress)).to.equal(null);
+    const index = await program.account.ownerIndex.fetch(ownerIndexAddress);
+    expect(index.positionIds).to.not.include(POSITION_ID);
+  });
+});
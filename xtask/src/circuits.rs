@@ -0,0 +1,132 @@
+use crate::deploy::account_exists;
+use anchor_client::{
+    solana_sdk::{signature::Keypair, signer::Signer},
+    Program,
+};
+use anyhow::{bail, Result};
+use arcium_anchor::comp_def_offset;
+use arcium_client::{
+    idl::arcium::{accounts::ComputationDefinitionAccount, types::CircuitSource},
+    instruction::{
+        embiggen_raw_circuit_acc_ix, finalize_computation_definition_ix, init_raw_circuit_acc_ix,
+        upload_circuit_ix,
+    },
+    pda,
+};
+use futures::future::try_join_all;
+use std::sync::Arc;
+
+/// Largest account the runtime allows; circuits bigger than this are split
+/// across several raw circuit accounts.
+const MAX_ACCOUNT_SIZE: usize = 10 * 1024 * 1024;
+/// Discriminator + bump stored ahead of the circuit bytes.
+const RAW_CIRCUIT_HEADER: usize = 9;
+/// Growth of a single realloc, and the size a raw circuit account starts at.
+const MAX_REALLOC_PER_IX: usize = 10_240;
+/// Resize instructions that fit in one transaction.
+const MAX_EMBIGGEN_IX_PER_TX: usize = 18;
+/// Circuit bytes carried by one `upload_circuit` instruction.
+const UPLOAD_CHUNK: usize = 814;
+
+/// Uploads `raw` into the raw circuit accounts of `circuit` and finalizes the
+/// computation definition. No-op if the definition is already finalized or
+/// does not use an on-chain circuit source.
+pub async fn upload(
+    program: &Program<Arc<Keypair>>,
+    payer: &Arc<Keypair>,
+    circuit: &str,
+    raw: &[u8],
+    concurrency: usize,
+) -> Result<()> {
+    let offset = comp_def_offset(circuit);
+    let comp_def_acc = pda::computation_definition_acc(&sentinel::ID, offset);
+    let comp_def: ComputationDefinitionAccount = program.account(comp_def_acc).await?;
+    match comp_def.circuit_source {
+        CircuitSource::OnChain(source) if source.is_completed => {
+            println!("  circuit already finalized");
+            return Ok(());
+        }
+        CircuitSource::OnChain(source) if source.upload_auth != payer.pubkey() => bail!(
+            "circuit `{circuit}` can only be uploaded by {}, not {}",
+            source.upload_auth,
+            payer.pubkey()
+        ),
+        CircuitSource::OnChain(_) => {}
+        CircuitSource::OffChain(_) | CircuitSource::Local(_) => {
+            println!("  circuit is not stored on-chain, nothing to upload");
+            return Ok(());
+        }
+    }
+
+    let part_size = MAX_ACCOUNT_SIZE - RAW_CIRCUIT_HEADER;
+    for (index, part) in raw.chunks(part_size).enumerate() {
+        let index = u8::try_from(index)?;
+        let raw_acc = pda::raw_circuit_acc(&comp_def_acc, index);
+        if account_exists(program, &raw_acc).await? {
+            println!("  raw circuit account {index} already exists, skipping");
+            continue;
+        }
+
+        program
+            .request()
+            .instruction(init_raw_circuit_acc_ix(
+                &payer.pubkey(),
+                offset,
+                &sentinel::ID,
+                index,
+            ))
+            .send()
+            .await?;
+
+        let required = part.len() + RAW_CIRCUIT_HEADER;
+        let resizes = required
+            .saturating_sub(MAX_REALLOC_PER_IX)
+            .div_ceil(MAX_REALLOC_PER_IX);
+        let resize_ix = embiggen_raw_circuit_acc_ix(&payer.pubkey(), offset, &sentinel::ID, index);
+        let mut remaining = resizes;
+        while remaining > 0 {
+            let batch = remaining.min(MAX_EMBIGGEN_IX_PER_TX);
+            let mut request = program.request();
+            for _ in 0..batch {
+                request = request.instruction(resize_ix.clone());
+            }
+            request.send().await?;
+            remaining -= batch;
+        }
+
+        let chunks: Vec<_> = part.chunks(UPLOAD_CHUNK).enumerate().collect();
+        for batch in chunks.chunks(concurrency.max(1)) {
+            try_join_all(batch.iter().map(|(i, chunk)| {
+                let mut data = [0u8; UPLOAD_CHUNK];
+                data[..chunk.len()].copy_from_slice(chunk);
+                let ix = upload_circuit_ix(
+                    &payer.pubkey(),
+                    &sentinel::ID,
+                    offset,
+                    index,
+                    data,
+                    (i * UPLOAD_CHUNK) as u32,
+                );
+                program.request().instruction(ix).send()
+            }))
+            .await?;
+        }
+        println!(
+            "  uploaded raw circuit account {index} ({} bytes, {} txs)",
+            part.len(),
+            chunks.len()
+        );
+    }
+
+    let sig = program
+        .request()
+        .instruction(finalize_computation_definition_ix(
+            &payer.pubkey(),
+            offset,
+            &sentinel::ID,
+        ))
+        .send()
+        .await?;
+    println!("  finalized: {sig}");
+    Ok(())
+}
@@ -0,0 +1,376 @@
+use crate::{circuits, DeployArgs};
+use anchor_client::{
+    solana_sdk::{
+        commitment_config::CommitmentConfig,
+        pubkey::Pubkey,
+        signature::{read_keypair_file, Keypair},
+        signer::Signer,
+    },
+    Client, Cluster, Program,
+};
+use anchor_lang::solana_program::bpf_loader_upgradeable::get_program_data_address;
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use arcium_anchor::{comp_def_offset, LUT_PROGRAM_ID};
+use arcium_client::{
+    idl::arcium::{
+        accounts::{ComputationDefinitionAccount, MXEAccount},
+        types::CircuitSource,
+        ID as ARCIUM_PROG_ID,
+    },
+    pda,
+};
+use sentinel::GlobalConfig;
+use solana_rpc_client::nonblocking::rpc_client::RpcClient;
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+    sync::Arc,
+};
+
+/// Encrypted instructions in the order their computation definitions are
+/// initialized. `init_risk_state` has to exist before any position can be
+/// registered; the others are only reachable once a position exists.
+pub const CIRCUITS: &[&str] = &[
+    "init_risk_state",
+    "check_position_health",
+    "reveal_risk",
+    "reveal_risk_to_owner",
+    "aggregate_portfolio_risk",
+    "check_position_health_with_price",
+    "check_position_health_cross_chain",
+    "set_alert_config",
+    "check_position_health_with_rules",
+    "reveal_history_summary",
+    "check_position_health_from_protocol",
+    "check_position_health_batch",
+    "compute_health_factor",
+    "reveal_health_factor_to_owner",
+    "reveal_risk_to_viewer",
+    "stress_test_position",
+    "attest_health",
+    "reveal_size_bucket",
+    "rotate_encryption",
+    "evaluate_stop_loss",
+    "accumulate_risk_heatmap",
+    "reveal_risk_heatmap",
+    "check_perp_health",
+    "compute_risk_score",
+];
+
+pub async fn run(args: DeployArgs) -> Result<()> {
+    let root = workspace_root();
+    std::env::set_current_dir(&root)?;
+
+    let cluster: Cluster = args
+        .cluster
+        .parse()
+        .map_err(|e| anyhow!("invalid cluster {:?}: {e}", args.cluster))?;
+    let cluster_offset = match args.cluster_offset {
+        Some(offset) => offset,
+        None => arcium_cluster_offset(&root, &args.cluster)?,
+    };
+    let keypair_path = args.keypair_path();
+
+    if !args.skip_build {
+        step("Building program and circuits");
+        run_cmd(Command::new("arcium").arg("build"))?;
+    }
+
+    if !args.skip_program_deploy {
+        step("Deploying program and initializing MXE");
+        run_cmd(
+            Command::new("arcium")
+                .args(["deploy", "--cluster-offset", &cluster_offset.to_string()])
+                .arg("--keypair-path")
+                .arg(&keypair_path)
+                .args(["--recovery-set-size", &args.recovery_set_size.to_string()])
+                .args(["--rpc-url", cluster.url()]),
+        )?;
+    }
+
+    let payer = Arc::new(
+        read_keypair_file(&keypair_path)
+            .map_err(|e| anyhow!("failed to read {}: {e}", keypair_path.display()))?,
+    );
+    let rpc =
+        RpcClient::new_with_commitment(cluster.url().to_string(), CommitmentConfig::confirmed());
+    let client = Client::new_with_options(cluster, payer.clone(), CommitmentConfig::confirmed());
+    let program = client.program(sentinel::ID, rpc)?;
+
+    let mxe: MXEAccount = program
+        .account(pda::mxe_acc(&sentinel::ID))
+        .await
+        .context("MXE account not found, was the program deployed with `arcium deploy`?")?;
+
+    for circuit in CIRCUITS {
+        step(&format!("Computation definition `{circuit}`"));
+        init_comp_def(&program, &payer, &mxe, circuit).await?;
+
+        let raw = std::fs::read(root.join("build").join(format!("{circuit}.arcis")))
+            .with_context(|| format!("missing build/{circuit}.arcis, run `arcium build`"))?;
+        circuits::upload(&program, &payer, circuit, &raw, args.upload_concurrency).await?;
+    }
+
+    let admin = args.admin.unwrap_or(payer.pubkey());
+    step("Admin audit log and global config");
+    init_global_config(&program, &payer, admin).await?;
+
+    step("Verifying deployment");
+    verify(&program, cluster_offset).await?;
+    verify_global_config(&program, &admin, args.circuit_version).await?;
+    println!("\nDeployment complete.");
+    Ok(())
+}
+
+/// Sends the program's `init_*_comp_def` instruction for `circuit`, unless the
+/// computation definition account already exists.
+async fn init_comp_def(
+    program: &Program<Arc<Keypair>>,
+    payer: &Arc<Keypair>,
+    mxe: &MXEAccount,
+    circuit: &str,
+) -> Result<()> {
+    let comp_def_account = pda::computation_definition_acc(&sentinel::ID, comp_def_offset(circuit));
+    if account_exists(program, &comp_def_account).await? {
+        println!("  already initialized ({comp_def_account})");
+        return Ok(());
+    }
+
+    macro_rules! comp_def_request {
+        ($name:ident) => {
+            program
+                .request()
+                .accounts(sentinel::accounts::$name {
+                    payer: payer.pubkey(),
+                    mxe_account: pda::mxe_acc(&sentinel::ID),
+                    comp_def_account,
+                    address_lookup_table: pda::mxe_lut_acc(&sentinel::ID, mxe.lut_offset_slot),
+                    lut_program: LUT_PROGRAM_ID,
+                    arcium_program: ARCIUM_PROG_ID,
+                    system_program: solana_system_interface::program::ID,
+                    program_data: get_program_data_address(&sentinel::ID),
+                })
+                .args(sentinel::instruction::$name {})
+        };
+    }
+
+    let request = match circuit {
+        "init_risk_state" => comp_def_request!(InitRiskStateCompDef),
+        "check_position_health" => comp_def_request!(InitCheckHealthCompDef),
+        "reveal_risk" => comp_def_request!(InitRevealRiskCompDef),
+        "reveal_risk_to_owner" => comp_def_request!(InitRevealRiskToOwnerCompDef),
+        "aggregate_portfolio_risk" => comp_def_request!(InitAggregatePortfolioRiskCompDef),
+        "check_position_health_with_price" => {
+            comp_def_request!(InitCheckHealthWithPriceCompDef)
+        }
+        "check_position_health_cross_chain" => {
+            comp_def_request!(InitCheckHealthCrossChainCompDef)
+        }
+        "set_alert_config" => comp_def_request!(InitSetAlertConfigCompDef),
+        "check_position_health_with_rules" => {
+            comp_def_request!(InitCheckHealthWithRulesCompDef)
+        }
+        "reveal_history_summary" => comp_def_request!(InitRevealHistorySummaryCompDef),
+        "check_position_health_from_protocol" => {
+            comp_def_request!(InitCheckHealthFromProtocolCompDef)
+        }
+        "check_position_health_batch" => comp_def_request!(InitCheckHealthBatchCompDef),
+        "compute_health_factor" => comp_def_request!(InitComputeHealthFactorCompDef),
+        "reveal_health_factor_to_owner" => {
+            comp_def_request!(InitRevealHealthFactorToOwnerCompDef)
+        }
+        "reveal_risk_to_viewer" => comp_def_request!(InitRevealRiskToViewerCompDef),
+        "stress_test_position" => comp_def_request!(InitStressTestPositionCompDef),
+        "attest_health" => comp_def_request!(InitAttestHealthCompDef),
+        "reveal_size_bucket" => comp_def_request!(InitRevealSizeBucketCompDef),
+        "rotate_encryption" => comp_def_request!(InitRotateEncryptionCompDef),
+        "evaluate_stop_loss" => comp_def_request!(InitEvaluateStopLossCompDef),
+        "accumulate_risk_heatmap" => comp_def_request!(InitAccumulateRiskHeatmapCompDef),
+        "reveal_risk_heatmap" => comp_def_request!(InitRevealRiskHeatmapCompDef),
+        "check_perp_health" => comp_def_request!(InitCheckPerpHealthCompDef),
+        "compute_risk_score" => comp_def_request!(InitComputeRiskScoreCompDef),
+        other => bail!("no comp def initializer for circuit `{other}`"),
+    };
+    let sig = request.send().await?;
+    println!("  initialized: {sig}");
+    Ok(())
+}
+
+/// Creates the admin audit log and then the `GlobalConfig` with `admin`,
+/// each unless it already exists. Administrative instructions fail until
+/// both do, and only the upgrade authority, the payer here, may create the
+/// config.
+async fn init_global_config(
+    program: &Program<Arc<Keypair>>,
+    payer: &Arc<Keypair>,
+    admin: Pubkey,
+) -> Result<()> {
+    let system_program = solana_system_interface::program::ID;
+    let admin_audit_log = sentinel_client::instructions::admin_audit_log_pda();
+    if account_exists(program, &admin_audit_log).await? {
+        println!("  audit log already initialized ({admin_audit_log})");
+    } else {
+        let sig = program
+            .request()
+            .accounts(sentinel::accounts::InitAdminAuditLog {
+                payer: payer.pubkey(),
+                admin_audit_log,
+                system_program,
+            })
+            .args(sentinel::instruction::InitAdminAuditLog {})
+            .send()
+            .await?;
+        println!("  audit log initialized: {sig}");
+    }
+
+    let global_config = sentinel_client::instructions::global_config_pda();
+    if account_exists(program, &global_config).await? {
+        println!("  config already initialized ({global_config})");
+    } else {
+        let sig = program
+            .request()
+            .accounts(sentinel::accounts::InitializeConfig {
+                authority: payer.pubkey(),
+                program_data: get_program_data_address(&sentinel::ID),
+                global_config,
+                system_program,
+            })
+            .args(sentinel::instruction::InitializeConfig { admin })
+            .send()
+            .await?;
+        println!("  config initialized with admin {admin}: {sig}");
+    }
+    Ok(())
+}
+
+/// Asserts that the audit log exists and that the `GlobalConfig` has
+/// `admin`, is not paused and is at `circuit_version`.
+async fn verify_global_config(
+    program: &Program<Arc<Keypair>>,
+    admin: &Pubkey,
+    circuit_version: u32,
+) -> Result<()> {
+    let admin_audit_log = sentinel_client::instructions::admin_audit_log_pda();
+    let account = program.internal_rpc().get_account(&admin_audit_log).await?;
+    ensure!(
+        account.owner == sentinel::ID,
+        "audit log {admin_audit_log} is owned by {}, expected the program",
+        account.owner
+    );
+    println!("  audit log ready ({admin_audit_log})");
+
+    let config: GlobalConfig = program
+        .account(sentinel_client::instructions::global_config_pda())
+        .await?;
+    ensure!(
+        config.admin == *admin,
+        "config admin is {}, expected {admin}",
+        config.admin
+    );
+    ensure!(
+        !config.paused,
+        "config is paused, queueing computations fails"
+    );
+    ensure!(
+        config.circuit_version == circuit_version,
+        "config is at circuit version {}, expected {circuit_version}",
+        config.circuit_version
+    );
+    println!("  config ready (admin {admin}, circuit version {circuit_version})");
+    Ok(())
+}
+
+/// Asserts that the program is executable, the MXE is bound to the expected
+/// cluster, and every computation definition is registered with a fully
+/// uploaded circuit.
+async fn verify(program: &Program<Arc<Keypair>>, cluster_offset: u32) -> Result<()> {
+    let rpc = program.internal_rpc();
+
+    let program_account = rpc.get_account(&sentinel::ID).await?;
+    ensure!(
+        program_account.executable,
+        "program {} is not executable",
+        sentinel::ID
+    );
+    println!("  program {} executable", sentinel::ID);
+
+    let mxe: MXEAccount = program.account(pda::mxe_acc(&sentinel::ID)).await?;
+    ensure!(
+        mxe.cluster == Some(cluster_offset),
+        "MXE is bound to cluster {:?}, expected {cluster_offset}",
+        mxe.cluster
+    );
+    println!("  MXE bound to cluster {cluster_offset}");
+
+    for circuit in CIRCUITS {
+        let offset = comp_def_offset(circuit);
+        let address = pda::computation_definition_acc(&sentinel::ID, offset);
+        let account = rpc.get_account(&address).await?;
+        ensure!(
+            account.owner == ARCIUM_PROG_ID,
+            "comp def `{circuit}` ({address}) is owned by {}, expected the Arcium program",
+            account.owner
+        );
+        ensure!(
+            mxe.computation_definitions.contains(&offset),
+            "comp def `{circuit}` is not registered with the MXE"
+        );
+
+        let comp_def: ComputationDefinitionAccount = program.account(address).await?;
+        match comp_def.circuit_source {
+            CircuitSource::OnChain(source) => ensure!(
+                source.is_completed,
+                "circuit `{circuit}` upload has not been finalized"
+            ),
+            CircuitSource::OffChain(_) | CircuitSource::Local(_) => {}
+        }
+        println!("  `{circuit}` ready ({address})");
+    }
+    Ok(())
+}
+
+pub async fn account_exists(program: &Program<Arc<Keypair>>, address: &Pubkey) -> Result<bool> {
+    Ok(program
+        .internal_rpc()
+        .get_account_with_commitment(address, CommitmentConfig::confirmed())
+        .await?
+        .value
+        .is_some())
+}
+
+/// Reads `[clusters.<name>].offset` from Arcium.toml.
+fn arcium_cluster_offset(root: &Path, cluster: &str) -> Result<u32> {
+    let manifest: toml::Table = std::fs::read_to_string(root.join("Arcium.toml"))?.parse()?;
+    manifest
+        .get("clusters")
+        .and_then(|clusters| clusters.get(cluster))
+        .and_then(|entry| entry.get("offset"))
+        .and_then(|offset| offset.as_integer())
+        .and_then(|offset| u32::try_from(offset).ok())
+        .with_context(|| {
+            format!("no [clusters.{cluster}] offset in Arcium.toml, pass --cluster-offset")
+        })
+}
+
+fn run_cmd(cmd: &mut Command) -> Result<()> {
+    let status = cmd
+        .status()
+        .with_context(|| format!("failed to spawn {:?}", cmd.get_program()))?;
+    ensure!(
+        status.success(),
+        "{:?} exited with {status}",
+        cmd.get_program()
+    );
+    Ok(())
+}
+
+fn step(title: &str) {
+    println!("\n==> {title}");
+}
+
+fn workspace_root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .expect("xtask lives inside the workspace")
+        .to_path_buf()
+}
@@ -0,0 +1,95 @@
+use anchor_client::solana_sdk::pubkey::Pubkey;
+use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+
+mod circuits;
+mod deploy;
+mod rules;
+mod schema;
+
+/// Workspace automation for the sentinel program.
+#[derive(Parser)]
+#[command(name = "xtask")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Builds and deploys the program, then initializes and uploads every
+    /// computation definition in dependency order.
+    Deploy(DeployArgs),
+    /// Validates a TOML/JSON rule set and prints the circuit parameter
+    /// vector it compiles to.
+    CompileRules(CompileRulesArgs),
+    /// Writes the versioned JSON Schemas of webhook payloads and inbox
+    /// records.
+    Schema(SchemaArgs),
+}
+
+#[derive(clap::Args)]
+pub struct DeployArgs {
+    /// Target cluster (`localnet`, `devnet`, or an RPC URL)
+    #[arg(long, default_value = "devnet")]
+    pub cluster: String,
+    /// Arcium cluster offset; defaults to `[clusters.<cluster>].offset` in Arcium.toml
+    #[arg(long)]
+    pub cluster_offset: Option<u32>,
+    /// Payer keypair
+    #[arg(long, default_value = "~/.config/solana/id.json")]
+    pub keypair: String,
+    /// Recovery set size passed to `arcium deploy`
+    #[arg(long, default_value_t = 5)]
+    pub recovery_set_size: u32,
+    /// Number of circuit upload transactions kept in flight at once
+    #[arg(long, default_value_t = 8)]
+    pub upload_concurrency: usize,
+    /// Skip `arcium build` (use existing artifacts in `build/` and `target/deploy/`)
+    #[arg(long)]
+    pub skip_build: bool,
+    /// Skip `arcium deploy` (program and MXE already on-chain)
+    #[arg(long)]
+    pub skip_program_deploy: bool,
+    /// Admin a new `GlobalConfig` is created with, and that an existing one
+    /// must have; defaults to the payer
+    #[arg(long)]
+    pub admin: Option<Pubkey>,
+    /// Circuit version the `GlobalConfig` must be at
+    #[arg(long, default_value_t = 0)]
+    pub circuit_version: u32,
+}
+
+#[derive(clap::Args)]
+pub struct CompileRulesArgs {
+    /// Rule set, e.g. `rules/default.toml`
+    pub path: PathBuf,
+    /// Write the parameter vector here instead of stdout
+    #[arg(long)]
+    pub out: Option<PathBuf>,
+}
+
+#[derive(clap::Args)]
+pub struct SchemaArgs {
+    /// Directory the `v<N>/` schema directory is written under
+    #[arg(long, default_value = "schemas")]
+    pub out: PathBuf,
+}
+
+impl DeployArgs {
+    pub fn keypair_path(&self) -> PathBuf {
+        match self.keypair.strip_prefix("~/") {
+            Some(rest) => PathBuf::from(std::env::var("HOME").unwrap_or_default()).join(rest),
+            None => PathBuf::from(&self.keypair),
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    match Cli::parse().command {
+        Command::Deploy(args) => deploy::run(args).await,
+        Command::CompileRules(args) => rules::run(args),
+        Command::Schema(args) => schema::run(args),
+    }
+}
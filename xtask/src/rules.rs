@@ -0,0 +1,175 @@
+//! Rule-set compiler: validates a TOML/JSON rule set and lowers it to the
+//! plaintext parameter vector described in `sentinel::rules`.
+
+use anyhow::{ensure, Context, Result};
+use sentinel::rules::{Field, Op, MAX_RULES, MAX_WEIGHT, PARAMS_PER_RULE, RULE_PARAMS_LEN};
+use serde::Deserialize;
+use std::path::Path;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RuleSet {
+    pub rules: Vec<Rule>,
+    pub tiers: Tiers,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rule {
+    pub field: RuleField,
+    pub op: RuleOp,
+    /// Compare against `liquidation_threshold + value` instead of `value`
+    #[serde(default)]
+    pub relative_to_threshold: bool,
+    pub value: u64,
+    pub weight: u64,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleField {
+    PositionValue,
+    CollateralRatio,
+    LiquidationThreshold,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleOp {
+    Lt,
+    Ge,
+}
+
+/// Minimum score for each severity.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Tiers {
+    pub low: u64,
+    pub medium: u64,
+    pub critical: u64,
+}
+
+impl From<RuleField> for Field {
+    fn from(field: RuleField) -> Self {
+        match field {
+            RuleField::PositionValue => Field::PositionValue,
+            RuleField::CollateralRatio => Field::CollateralRatio,
+            RuleField::LiquidationThreshold => Field::LiquidationThreshold,
+        }
+    }
+}
+
+impl From<RuleOp> for Op {
+    fn from(op: RuleOp) -> Self {
+        match op {
+            RuleOp::Lt => Op::Lt,
+            RuleOp::Ge => Op::Ge,
+        }
+    }
+}
+
+/// Parses `path` as JSON if it ends in `.json`, TOML otherwise.
+pub fn load(path: &Path) -> Result<RuleSet> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    let rule_set = if path.extension().is_some_and(|ext| ext == "json") {
+        serde_json::from_str(&text)?
+    } else {
+        toml::from_str(&text)?
+    };
+    Ok(rule_set)
+}
+
+impl RuleSet {
+    pub fn validate(&self) -> Result<()> {
+        ensure!(!self.rules.is_empty(), "rule set has no rules");
+        ensure!(
+            self.rules.len() <= MAX_RULES,
+            "rule set has {} rules, at most {MAX_RULES} are supported",
+            self.rules.len()
+        );
+        for (i, rule) in self.rules.iter().enumerate() {
+            ensure!(
+                (1..=MAX_WEIGHT).contains(&rule.weight),
+                "rule {i}: weight must be between 1 and {MAX_WEIGHT}"
+            );
+            ensure!(
+                !(rule.relative_to_threshold
+                    && matches!(rule.field, RuleField::LiquidationThreshold)),
+                "rule {i}: liquidation_threshold cannot be compared relative to itself"
+            );
+        }
+        let Tiers {
+            low,
+            medium,
+            critical,
+        } = self.tiers;
+        ensure!(
+            0 < low && low < medium && medium < critical,
+            "tiers must satisfy 0 < low < medium < critical"
+        );
+        let max_score: u64 = self.rules.iter().map(|rule| rule.weight).sum();
+        ensure!(
+            critical <= max_score,
+            "critical tier {critical} is unreachable, the rules score at most {max_score}"
+        );
+        Ok(())
+    }
+
+    /// Lowers the rule set to the circuit's parameter vector.
+    pub fn compile(&self) -> Result<Vec<u64>> {
+        self.validate()?;
+        let mut params = vec![0; RULE_PARAMS_LEN];
+        for (slot, rule) in params.chunks_mut(PARAMS_PER_RULE).zip(&self.rules) {
+            slot.copy_from_slice(&[
+                1,
+                Field::from(rule.field) as u64,
+                Op::from(rule.op) as u64,
+                rule.relative_to_threshold as u64,
+                rule.value,
+                rule.weight,
+            ]);
+        }
+        let tiers = &mut params[MAX_RULES * PARAMS_PER_RULE..];
+        tiers.copy_from_slice(&[self.tiers.low, self.tiers.medium, self.tiers.critical]);
+        Ok(params)
+    }
+}
+
+pub fn run(args: crate::CompileRulesArgs) -> Result<()> {
+    let params = load(&args.path)?.compile()?;
+    let json = serde_json::to_string(&params)?;
+    match args.out {
+        Some(out) => std::fs::write(&out, json)
+            .with_context(|| format!("failed to write {}", out.display()))?,
+        None => println!("{json}"),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_rules_compile() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("../rules/default.toml");
+        let params = load(&path).unwrap().compile().unwrap();
+        assert_eq!(params.len(), RULE_PARAMS_LEN);
+        assert_eq!(&params[..PARAMS_PER_RULE], &[1, 1, 0, 1, 500, 3]);
+        assert_eq!(&params[RULE_PARAMS_LEN - 3..], &[1, 2, 5]);
+        assert!(sentinel::rules::is_valid(&params.try_into().unwrap()));
+    }
+
+    #[test]
+    fn rejects_unreachable_tier() {
+        let rule_set: RuleSet = toml::from_str(
+            r#"
+            rules = [{ field = "position_value", op = "lt", value = 100, weight = 1 }]
+            tiers = { low = 1, medium = 2, critical = 3 }
+            "#,
+        )
+        .unwrap();
+        assert!(rule_set.compile().is_err());
+    }
+}
@@ -0,0 +1,17 @@
+//! Writes the JSON Schemas of `sentinel_client::schema` to
+//! `schemas/v<SCHEMA_VERSION>/`, where integrators pick them up.
+
+use anyhow::{Context, Result};
+use sentinel_client::schema::{render, schemas, SCHEMA_VERSION};
+
+pub fn run(args: crate::SchemaArgs) -> Result<()> {
+    let dir = args.out.join(format!("v{SCHEMA_VERSION}"));
+    std::fs::create_dir_all(&dir).with_context(|| format!("failed to create {}", dir.display()))?;
+    for (name, schema) in schemas() {
+        let path = dir.join(name);
+        std::fs::write(&path, render(&schema))
+            .with_context(|| format!("failed to write {}", path.display()))?;
+        println!("wrote {}", path.display());
+    }
+    Ok(())
+}